@@ -0,0 +1,82 @@
+//! Indexing throughput, FTS query latency and capsule assembly time over a
+//! generated repository. Run with `cargo bench -p focal-core`; set
+//! `FOCAL_BENCH_FILES` to change the repository size (default 200 files).
+
+use std::collections::HashSet;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use focal_core::context::ContextEngine;
+use focal_core::db::{Database, SearchFilter};
+use focal_core::fixture::{generate_repo, sample_queries, FixtureSpec};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn spec() -> FixtureSpec {
+    let files = std::env::var("FOCAL_BENCH_FILES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(200);
+    FixtureSpec { files, ..FixtureSpec::default() }
+}
+
+fn indexed(dir: &TempDir) -> Database {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    db
+}
+
+fn bench_index(c: &mut Criterion) {
+    let spec = spec();
+    let dir = TempDir::new().unwrap();
+    generate_repo(dir.path(), &spec).unwrap();
+    let registry = GrammarRegistry::new();
+
+    let mut group = c.benchmark_group("index");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(spec.files as u64));
+    group.bench_function(format!("{}_files", spec.files), |b| {
+        b.iter_batched(
+            || Database::open_in_memory().unwrap(),
+            |db| Indexer::new(&db, &registry).index_directory(dir.path()).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn bench_search(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    generate_repo(dir.path(), &spec()).unwrap();
+    let db = indexed(&dir);
+    let queries = sample_queries();
+    let mut i = 0;
+    c.bench_function("search_code", |b| {
+        b.iter(|| {
+            i = (i + 1) % queries.len();
+            db.search_code(&queries[i], &SearchFilter::default(), 20).unwrap()
+        })
+    });
+}
+
+fn bench_capsule(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    generate_repo(dir.path(), &spec()).unwrap();
+    let db = indexed(&dir);
+    let engine = ContextEngine::new(&db);
+    let queries = sample_queries();
+    let sent = HashSet::new();
+    let mut i = 0;
+    c.bench_function("get_capsule", |b| {
+        b.iter(|| {
+            i = (i + 1) % queries.len();
+            engine
+                .get_capsule(&format!("how does {} work", queries[i]), 8_000, None, &sent)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_index, bench_search, bench_capsule);
+criterion_main!(benches);
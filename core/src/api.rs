@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::db::{parse_signature_types, Database, Repository, Symbol};
+use crate::encoding::decode_source;
+use crate::grammar::TypeSignature;
+
+// ---------------------------------------------------------------------------
+// Report types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiSymbol {
+    #[serde(skip)]
+    pub id: i64,
+    pub name: String,
+    pub kind: String,
+    pub signature: String,
+    pub file_path: String,
+    pub line: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<TypeSignature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiModule {
+    pub module: String,
+    pub symbols: Vec<ApiSymbol>,
+}
+
+/// Exported symbols of one repository, grouped by module.
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicApi {
+    pub repo: String,
+    pub modules: Vec<ApiModule>,
+    pub total: usize,
+    /// True when `total` exceeded the requested limit and symbols were dropped.
+    pub truncated: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Report
+// ---------------------------------------------------------------------------
+
+/// Public symbols of `repo`, optionally restricted to files under
+/// `path_prefix`. Source files are read from the repo root to check export
+/// keywords and collect doc comments; files that cannot be read fall back to
+/// the indexed signature and carry no docs.
+pub fn public_api(
+    db: &Database,
+    repo: &Repository,
+    path_prefix: Option<&str>,
+    include_docs: bool,
+    max_symbols: usize,
+) -> Result<PublicApi> {
+    let prefix = path_prefix.map(crate::paths::normalize_path);
+    let mut modules: BTreeMap<String, Vec<ApiSymbol>> = BTreeMap::new();
+    let mut total = 0;
+
+    for file in db.get_files_for_repo(repo.id)? {
+        if let Some(prefix) = &prefix {
+            if file.path != *prefix && !file.path.starts_with(&format!("{prefix}/")) {
+                continue;
+            }
+        }
+        let symbols = db.get_symbols_by_file(file.id)?;
+        let by_id: HashMap<i64, &Symbol> = symbols.iter().map(|s| (s.id, s)).collect();
+        let source = std::fs::read(Path::new(&repo.root_path).join(&file.path))
+            .map(|raw| String::from_utf8_lossy(&decode_source(&raw).text).into_owned())
+            .unwrap_or_default();
+        let lines: Vec<&str> = source.lines().collect();
+
+        for sym in &symbols {
+            let parent = sym.parent_id.and_then(|p| by_id.get(&p).copied());
+            if !is_public(&file.language, sym, parent, &lines) {
+                continue;
+            }
+            total += 1;
+            let doc = if include_docs {
+                match file.language.as_str() {
+                    "py" => python_docstring(&sym.body),
+                    lang => doc_comment(&lines, sym.start_line, lang),
+                }
+            } else {
+                None
+            };
+            modules
+                .entry(module_for(&file.path, &file.language))
+                .or_default()
+                .push(ApiSymbol {
+                    id: sym.id,
+                    name: sym.name.clone(),
+                    kind: sym.kind.clone(),
+                    signature: sym.signature.clone(),
+                    file_path: file.path.clone(),
+                    line: sym.start_line,
+                    types: parse_signature_types(sym.signature_types.clone()),
+                    doc,
+                });
+        }
+    }
+
+    // Keep module order stable and cut whole symbols once the limit is hit.
+    let mut remaining = max_symbols;
+    let modules: Vec<ApiModule> = modules
+        .into_iter()
+        .filter_map(|(module, mut symbols)| {
+            symbols.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+            symbols.truncate(remaining);
+            remaining -= symbols.len();
+            (!symbols.is_empty()).then_some(ApiModule { module, symbols })
+        })
+        .collect();
+
+    Ok(PublicApi {
+        repo: repo.name.clone(),
+        modules,
+        total,
+        truncated: total > max_symbols,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Visibility heuristics
+// ---------------------------------------------------------------------------
+
+/// Whether `sym` is part of its package's public surface. Symbols carry the
+/// visibility their grammar recorded at index time; a member additionally
+/// needs a public parent. Symbols indexed before visibility was tracked fall
+/// back to these heuristics:
+/// - Go: identifier starts with an uppercase letter (and so does a method's receiver type)
+/// - Rust: declared `pub` (`pub(crate)` and friends are internal)
+/// - TS/JS: declared with `export`; class members of an exported class
+///   unless `private`/`protected`/`#`
+/// - Python: no leading underscore (dunders are public), inside a public class
+pub fn is_public(language: &str, sym: &Symbol, parent: Option<&Symbol>, lines: &[&str]) -> bool {
+    if !sym.visibility.is_empty() {
+        return sym.visibility == "public"
+            && parent.is_none_or(|p| is_public(language, p, None, lines));
+    }
+    match language {
+        "go" => {
+            let mut segments = sym.qualified_name.split('.').rev();
+            let exported = |s: Option<&str>| s.and_then(|s| s.chars().next()).is_some_and(char::is_uppercase);
+            let name_ok = exported(Some(&sym.name));
+            if sym.kind == "method" {
+                // qualified_name is pkg.Recv.Method
+                segments.next();
+                name_ok && exported(segments.next())
+            } else {
+                name_ok
+            }
+        }
+        "rs" => sym.signature.starts_with("pub ") || sym.signature.starts_with("pub\n"),
+        "ts" | "tsx" => match parent {
+            Some(class) => {
+                is_public(language, class, None, lines)
+                    && !["private", "protected", "#"]
+                        .iter()
+                        .any(|m| sym.signature.starts_with(m))
+            }
+            None => declaration_line(sym, lines).trim_start().starts_with("export "),
+        },
+        "py" => {
+            let public_name = !sym.name.starts_with('_')
+                || (sym.name.starts_with("__") && sym.name.ends_with("__"));
+            public_name && parent.is_none_or(|p| is_public(language, p, None, lines))
+        }
+        _ => false,
+    }
+}
+
+/// The source line a symbol starts on, or its signature when the file was
+/// unavailable.
+fn declaration_line<'a>(sym: &'a Symbol, lines: &[&'a str]) -> &'a str {
+    usize::try_from(sym.start_line - 1)
+        .ok()
+        .and_then(|i| lines.get(i).copied())
+        .unwrap_or(&sym.signature)
+}
+
+// ---------------------------------------------------------------------------
+// Doc comments
+// ---------------------------------------------------------------------------
+
+/// The comment block directly above line `start_line` (1-based), skipping
+/// attributes/decorators in between: `///` for Rust, `//` for Go, `/** */`
+/// or `//` for TS/JS. Comment markers are stripped, and secrets masked as
+/// in stored bodies, since callers read `lines` from disk.
+pub fn doc_comment(lines: &[&str], start_line: i64, language: &str) -> Option<String> {
+    let mut i = usize::try_from(start_line - 1).ok()?;
+    let mut collected: Vec<String> = Vec::new();
+    let mut in_block = false;
+
+    while i > 0 {
+        i -= 1;
+        let line = lines.get(i)?.trim();
+        if in_block {
+            let text = line.trim_start_matches("/**").trim_start_matches("/*");
+            collected.push(text.trim_start_matches('*').trim().to_string());
+            if line.starts_with("/*") {
+                break;
+            }
+            continue;
+        }
+        let is_attribute = match language {
+            "rs" => line.starts_with("#["),
+            "ts" | "tsx" => line.starts_with('@'),
+            _ => false,
+        };
+        if is_attribute && collected.is_empty() {
+            continue;
+        }
+        let marker = match language {
+            "rs" => "///",
+            _ => "//",
+        };
+        if let Some(text) = line.strip_prefix(marker) {
+            if language == "rs" && text.starts_with('/') {
+                break; // `////` is a plain comment
+            }
+            collected.push(text.trim().to_string());
+        } else if matches!(language, "ts" | "tsx") && line.ends_with("*/") && collected.is_empty() {
+            let text = line.trim_end_matches("*/").trim();
+            if line.starts_with("/*") {
+                collected.push(text.trim_start_matches("/**").trim_start_matches("/*").trim().to_string());
+                break;
+            }
+            collected.push(text.trim_start_matches('*').trim().to_string());
+            in_block = true;
+        } else {
+            break;
+        }
+    }
+
+    collected.reverse();
+    let doc = collected.join("\n").trim().to_string();
+    (!doc.is_empty()).then(|| crate::redact::redact(&doc).0.into_owned())
+}
+
+/// A Python function or class docstring: the string literal that opens the
+/// body, with its quotes removed.
+pub fn python_docstring(body: &str) -> Option<String> {
+    let mut lines = body.lines();
+    // Skip the (possibly multi-line) `def ...:` / `class ...:` header.
+    for line in lines.by_ref() {
+        if line.trim_end().ends_with(':') {
+            break;
+        }
+    }
+    let first = lines.by_ref().map(str::trim).find(|l| !l.is_empty())?;
+    let quote = ["\"\"\"", "'''"].into_iter().find(|q| first.starts_with(q))?;
+    let rest = &first[quote.len()..];
+    if let Some(end) = rest.find(quote) {
+        return Some(rest[..end].trim().to_string());
+    }
+    let mut doc = vec![rest.trim().to_string()];
+    for line in lines {
+        if let Some(end) = line.find(quote) {
+            doc.push(line[..end].trim().to_string());
+            break;
+        }
+        doc.push(line.trim().to_string());
+    }
+    let doc = doc.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Module a file's symbols are listed under: the directory (package) for
+/// Go, the module path for Rust, and the extension-less path for TS/Python.
+pub fn module_for(rel_path: &str, language: &str) -> String {
+    match language {
+        "go" => match rel_path.rsplit_once('/') {
+            Some((dir, _)) => dir.to_string(),
+            None => ".".to_string(),
+        },
+        "rs" => {
+            let module = crate::indexer::file_to_module(rel_path, language);
+            if module.is_empty() {
+                "crate".to_string()
+            } else {
+                module
+            }
+        }
+        _ => match rel_path.rsplit_once('.') {
+            Some((stem, _)) => stem.to_string(),
+            None => rel_path.to_string(),
+        },
+    }
+}
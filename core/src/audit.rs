@@ -0,0 +1,137 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::db::Database;
+
+// ---------------------------------------------------------------------------
+// Tool-call audit log
+// ---------------------------------------------------------------------------
+//
+// A shared `focal serve --http` host records every tool call in the
+// append-only `audit_log` table: which session and bearer token called which
+// tool with which arguments, and how it went. Tokens are stored as a
+// fingerprint, never verbatim. `GET /admin/audit` on the same port returns
+// the log to holders of `[audit] admin_token`.
+
+/// Default and maximum rows returned by one audit query.
+pub const DEFAULT_AUDIT_LIMIT: usize = 100;
+pub const MAX_AUDIT_LIMIT: usize = 1000;
+
+/// Days audit rows are kept before `focal serve` purges them at startup.
+pub const DEFAULT_RETENTION_DAYS: u64 = 90;
+
+/// One tool call, as recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub session_id: String,
+    /// `token_fingerprint` of the caller's bearer token, if it sent one.
+    pub token: Option<String>,
+    pub tool: String,
+    /// The call's arguments as JSON (`{}` when it had none).
+    pub params: String,
+    /// `ok`, or `error` when the tool failed.
+    pub outcome: String,
+    /// Approximate tokens in the response.
+    pub response_tokens: usize,
+}
+
+/// A stored row of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditRecord {
+    pub id: i64,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    pub at: String,
+    pub session_id: String,
+    pub token: Option<String>,
+    pub tool: String,
+    pub params: String,
+    pub outcome: String,
+    pub response_tokens: usize,
+}
+
+/// Which audit rows to return, newest first. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    pub session_id: Option<String>,
+    pub token: Option<String>,
+    pub tool: Option<String>,
+    /// Only calls at or after this UTC time (`YYYY-MM-DD[ HH:MM:SS]`).
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    /// Parse the query string of `GET /admin/audit`
+    /// (`session=…&token=…&tool=…&since=…&limit=…`). Unknown keys are ignored.
+    pub fn from_query(query: &str) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let value = value.into_owned();
+            match key.as_ref() {
+                "session" => filter.session_id = Some(value),
+                "token" => filter.token = Some(value),
+                "tool" => filter.tool = Some(value),
+                "since" => filter.since = Some(value),
+                "limit" => {
+                    let limit = value.parse().map_err(|_| format!("invalid limit '{value}'"))?;
+                    filter.limit = Some(limit);
+                }
+                _ => {}
+            }
+        }
+        Ok(filter)
+    }
+
+    /// `limit`, defaulted and clamped to `MAX_AUDIT_LIMIT`.
+    pub fn effective_limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_AUDIT_LIMIT).clamp(1, MAX_AUDIT_LIMIT)
+    }
+}
+
+/// Stable, non-reversible name for a bearer token: `sha256:` and the first
+/// 12 hex digits of its hash.
+pub fn token_fingerprint(token: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(token.as_bytes()));
+    format!("sha256:{}", &digest[..12])
+}
+
+/// The token of an `Authorization: Bearer <token>` header value.
+pub fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// Compare a presented token to the configured one in constant time. Both
+/// are hashed first, so neither the first differing byte nor a length
+/// mismatch shows in the timing.
+pub fn token_matches(presented: &str, expected: &str) -> bool {
+    let (a, b) = (Sha256::digest(presented.as_bytes()), Sha256::digest(expected.as_bytes()));
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Answer `GET /admin/audit`: `(status, JSON body)`. Refused (403) unless
+/// `[audit] admin_token` is set and presented as a bearer token.
+pub fn admin_response(
+    db: &Database,
+    admin_token: Option<&str>,
+    authorization: Option<&str>,
+    query: &str,
+) -> (u16, String) {
+    let error = |status: u16, message: &str| (status, serde_json::json!({ "error": message }).to_string());
+    let Some(expected) = admin_token.filter(|t| !t.is_empty()) else {
+        return error(403, "audit endpoint disabled: set [audit] admin_token");
+    };
+    let authorized = authorization.and_then(bearer_token).is_some_and(|t| token_matches(t, expected));
+    if !authorized {
+        return error(401, "missing or invalid admin token");
+    }
+    let filter = match AuditFilter::from_query(query) {
+        Ok(filter) => filter,
+        Err(e) => return error(400, &e),
+    };
+    match db.query_audit_log(&filter) {
+        Ok(records) => (200, serde_json::json!({ "entries": records }).to_string()),
+        Err(e) => error(500, &format!("query error: {e}")),
+    }
+}
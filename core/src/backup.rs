@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::db::Database;
+
+// ---------------------------------------------------------------------------
+// Index backups
+// ---------------------------------------------------------------------------
+//
+// Most of index.db can be rebuilt by re-indexing, but memories (decisions,
+// gotchas, the session trail) exist nowhere else. `focal backup` copies the
+// whole database, memories included, into a rotation directory
+// (`[backup] dir`, default `~/.focal/backups`) as
+// `index-<UTC timestamp>.db`, using SQLite's online backup API, and keeps the
+// newest `keep`. `focal serve` backs up at startup, and then hourly, whenever
+// the newest snapshot is older than `interval_hours`. A stdio server only
+// lives for one session, so a plain timer would rarely fire. `focal restore`
+// copies a snapshot back over the index. It first snapshots the current
+// database, so a restore can itself be undone. An index too damaged to open
+// or snapshot is renamed aside instead, and the snapshot copied into place.
+
+pub const DEFAULT_KEEP: usize = 7;
+pub const DEFAULT_INTERVAL_HOURS: u64 = 24;
+
+/// How often `focal serve` checks whether a backup is due.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const PREFIX: &str = "index-";
+const SUFFIX: &str = ".db";
+
+/// One backup written to the rotation directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Memories in the snapshot.
+    pub memories: i64,
+    /// Older snapshots deleted by rotation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<PathBuf>,
+}
+
+/// Outcome of `restore`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Restored {
+    /// The snapshot now in place.
+    pub from: PathBuf,
+    pub memories: i64,
+    /// The database as it was before the restore.
+    pub previous: Snapshot,
+}
+
+/// Outcome of `restore_index`.
+#[derive(Debug, Clone, Serialize)]
+pub enum IndexRestore {
+    /// The index was backed up, then replaced in place.
+    Replaced(Restored),
+    /// The index couldn't be opened or backed up: it was moved to `corrupt`
+    /// and the snapshot copied over it.
+    SetAside { from: PathBuf, memories: i64, corrupt: PathBuf },
+}
+
+/// Snapshot `db` into `dir` and delete all but the newest `keep` snapshots.
+/// `key` must be the database's key when it is encrypted.
+pub fn create(db: &Database, dir: &Path, keep: usize, key: Option<&str>) -> Result<Snapshot> {
+    let mut snapshot = snapshot(db, dir, key)?;
+    snapshot.removed = rotate(dir, keep)?;
+    Ok(snapshot)
+}
+
+/// Replace `db` with the snapshot at `src`, after checking that the
+/// snapshot is intact and backing up the current database into `dir`.
+pub fn restore(db: &mut Database, src: &Path, dir: &Path, keep: usize, key: Option<&str>) -> Result<Restored> {
+    let memories = check_snapshot(src, key)?;
+    replace(db, src, dir, keep, key, memories)
+}
+
+/// `restore` for the index file at `db_path`. When the current index can't
+/// be opened or backed up, as with a corrupt file, it is renamed aside to
+/// `<name>.corrupt-<timestamp>` along with its `-wal` and `-shm`, and the
+/// snapshot is copied into place.
+pub fn restore_index(db_path: &Path, src: &Path, dir: &Path, keep: usize, key: Option<&str>) -> Result<IndexRestore> {
+    let memories = check_snapshot(src, key)?;
+    // A missing index is created in the snapshot's format.
+    let encrypt = key.is_some() && !db_path.metadata().is_ok_and(|m| m.len() > 0);
+    let replaced = crate::encryption::open(db_path, encrypt)
+        .and_then(|mut db| replace(&mut db, src, dir, keep, key, memories));
+    let error = match replaced {
+        Ok(restored) => return Ok(IndexRestore::Replaced(restored)),
+        Err(e) => e,
+    };
+    tracing::warn!(error = %format!("{error:#}"), db = %db_path.display(), "index unusable; setting it aside");
+
+    let stamp = utc_stamp(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64);
+    let name = db_path.file_name().and_then(|n| n.to_str()).unwrap_or("index.db");
+    let corrupt = db_path.with_file_name(format!("{name}.corrupt-{stamp}"));
+    for suffix in ["", "-wal", "-shm"] {
+        let from = db_path.with_file_name(format!("{name}{suffix}"));
+        if from.exists() {
+            let to = corrupt.with_file_name(format!("{name}.corrupt-{stamp}{suffix}"));
+            std::fs::rename(&from, &to)
+                .with_context(|| format!("failed to move {} aside after: {error:#}", from.display()))?;
+        }
+    }
+    std::fs::copy(src, db_path)
+        .with_context(|| format!("failed to copy {} to {}", src.display(), db_path.display()))?;
+    Ok(IndexRestore::SetAside { from: src.to_path_buf(), memories, corrupt })
+}
+
+/// Memories in the snapshot at `src`; fails unless it is intact.
+fn check_snapshot(src: &Path, key: Option<&str>) -> Result<i64> {
+    let src_str = src.to_string_lossy();
+    let backup = match key {
+        Some(key) => Database::open_read_only_encrypted(&src_str, key)?,
+        None => Database::open_read_only(&src_str)?,
+    };
+    backup.quick_check().with_context(|| format!("{} is not a usable backup", src.display()))?;
+    backup.count_memories()
+}
+
+fn replace(db: &mut Database, src: &Path, dir: &Path, keep: usize, key: Option<&str>, memories: i64) -> Result<Restored> {
+    // Rotate only after the restore, so it can't delete `src`.
+    let mut previous = snapshot(db, dir, key)?;
+    db.restore_from(src, key)?;
+    previous.removed = rotate(dir, keep)?;
+    Ok(Restored { from: src.to_path_buf(), memories, previous })
+}
+
+/// Snapshots in `dir`, oldest first.
+pub fn list(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", dir.display())),
+    };
+    let mut snapshots: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(PREFIX) && n.ends_with(SUFFIX))
+        })
+        .collect();
+    // Timestamps are fixed-width, so names sort chronologically
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// The newest snapshot in `dir`.
+pub fn latest(dir: &Path) -> Result<Option<PathBuf>> {
+    Ok(list(dir)?.pop())
+}
+
+/// Whether the newest snapshot in `dir` is at least `interval_hours` old,
+/// or there is none. Always false when `interval_hours` is 0.
+pub fn due(dir: &Path, interval_hours: u64) -> Result<bool> {
+    if interval_hours == 0 {
+        return Ok(false);
+    }
+    let Some(newest) = latest(dir)? else {
+        return Ok(true);
+    };
+    let age = std::fs::metadata(&newest)?.modified()?.elapsed().unwrap_or_default();
+    Ok(age >= Duration::from_secs(interval_hours * 60 * 60))
+}
+
+fn snapshot(db: &Database, dir: &Path, key: Option<&str>) -> Result<Snapshot> {
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = next_path(dir);
+    db.backup_to(&path, key)?;
+    Ok(Snapshot {
+        bytes: std::fs::metadata(&path)?.len(),
+        memories: db.count_memories()?,
+        path,
+        removed: Vec::new(),
+    })
+}
+
+/// Delete all but the newest `keep` snapshots in `dir`.
+fn rotate(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let snapshots = list(dir)?;
+    let excess = snapshots.len().saturating_sub(keep.max(1));
+    let mut removed = Vec::with_capacity(excess);
+    for path in snapshots.into_iter().take(excess) {
+        std::fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// `dir/index-<now>.db`, moved a millisecond on while the name is taken.
+fn next_path(dir: &Path) -> PathBuf {
+    let mut millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    loop {
+        let path = dir.join(format!("{PREFIX}{}{SUFFIX}", utc_stamp(millis)));
+        if !path.exists() {
+            return path;
+        }
+        millis += 1;
+    }
+}
+
+/// `20261017T093005.123Z` for a Unix time in milliseconds.
+fn utc_stamp(millis: u64) -> String {
+    let secs = millis / 1000;
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}.{:03}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        millis % 1000
+    )
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::db::Symbol;
+use crate::dependencies::{aliased, quoted};
+
+// ---------------------------------------------------------------------------
+// TypeScript re-exports and barrel files
+// ---------------------------------------------------------------------------
+//
+// `import { parse } from "./utils"` often names a barrel (`utils/index.ts`)
+// that only re-exports: `export * from "./parse"`, `export { parse as
+// parseInput } from "./parse"`, or an import followed by `export { parse }`.
+// Resolving by bare name misses renamed and aliased bindings and picks an
+// arbitrary target when names collide, so calls to an imported name follow
+// the chain from the importing file to the module that declares it.
+
+/// Re-export hops followed before giving up; also breaks cycles.
+pub const MAX_REEXPORT_DEPTH: usize = 8;
+
+/// Extensions tried for an extension-less relative import.
+const EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx"];
+
+/// `export { imported as exported }`, with the module it comes from (None
+/// for a bare export list naming a local or imported binding).
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedExport {
+    pub exported: String,
+    pub imported: String,
+    pub spec: Option<String>,
+}
+
+/// What a TS/JS file imports and re-exports, read from its `imports` and
+/// `exports` references (the statement text).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModuleLinks {
+    /// Local name → (module specifier, imported name). Default imports
+    /// import `default`.
+    pub imports: HashMap<String, (String, String)>,
+    pub named: Vec<NamedExport>,
+    /// Specifiers of `export * from` statements.
+    pub star: Vec<String>,
+}
+
+impl ModuleLinks {
+    /// Parse `(kind, statement)` pairs; other kinds are ignored.
+    pub fn parse<'a>(statements: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut links = Self::default();
+        for (kind, text) in statements {
+            let text = text.trim().trim_end_matches(';').trim();
+            match kind {
+                "imports" if text.starts_with("import ") => links.add_import(text),
+                "imports" if text.starts_with("export ") => links.add_reexport(text),
+                "exports" => links.add_export_list(text, None),
+                _ => {}
+            }
+        }
+        links
+    }
+
+    fn add_import(&mut self, text: &str) {
+        let Some(spec) = quoted(text) else { return };
+        let Some((clause, _)) = text["import ".len()..].rsplit_once(" from ") else { return };
+        let clause = clause.trim().trim_start_matches("type ").trim();
+        let (default, named) = match clause.split_once('{') {
+            Some((default, named)) => (default, named.split('}').next().unwrap_or_default()),
+            None => (clause, ""),
+        };
+        let default = default.trim().trim_end_matches(',').trim();
+        if !default.is_empty() && !default.starts_with('*') {
+            self.imports.insert(default.to_string(), (spec.clone(), "default".to_string()));
+        }
+        for (name, local) in named.split(',').map(|n| n.trim().trim_start_matches("type ")).filter_map(aliased) {
+            self.imports.insert(local, (spec.clone(), name));
+        }
+    }
+
+    fn add_reexport(&mut self, text: &str) {
+        let Some(spec) = quoted(text) else { return };
+        let clause = text["export ".len()..].trim_start().trim_start_matches("type ").trim_start();
+        if clause.starts_with('{') {
+            self.add_export_list(clause, Some(spec));
+        } else if clause.starts_with('*') && !clause[1..].trim_start().starts_with("as ") {
+            // `export * as ns from` binds a namespace, not the names themselves.
+            self.star.push(spec);
+        }
+    }
+
+    fn add_export_list(&mut self, text: &str, spec: Option<String>) {
+        let Some((_, list)) = text.split_once('{') else { return };
+        let list = list.split('}').next().unwrap_or_default();
+        for (imported, exported) in list.split(',').map(|n| n.trim().trim_start_matches("type ")).filter_map(aliased) {
+            self.named.push(NamedExport { exported, imported, spec: spec.clone() });
+        }
+    }
+}
+
+/// A file as barrel resolution sees it: its links and the ids of the
+/// top-level symbols it declares (`default` for its default export).
+#[derive(Debug, Default)]
+pub struct Module {
+    pub links: ModuleLinks,
+    pub declared: HashMap<String, i64>,
+}
+
+impl Module {
+    pub fn new(links: ModuleLinks, symbols: &[Symbol]) -> Self {
+        let mut declared = HashMap::new();
+        for sym in symbols.iter().filter(|s| s.parent_id.is_none()) {
+            if sym.modifiers.split_whitespace().any(|m| m == "default") {
+                declared.entry("default".to_string()).or_insert(sym.id);
+            }
+            declared.entry(sym.name.clone()).or_insert(sym.id);
+        }
+        Self { links, declared }
+    }
+}
+
+/// Repo-relative path a relative specifier imported from `importer` names,
+/// if that file exists: `./x` tries `x.ts`, `x.tsx`, ... then `x/index.*`,
+/// and ESM-style `./x.js` also tries `x.ts`. Package imports yield None.
+pub fn resolve_module(importer: &str, spec: &str, exists: &dyn Fn(&str) -> bool) -> Option<String> {
+    if !spec.starts_with('.') {
+        return None;
+    }
+    let dir = importer.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let mut parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+    for segment in spec.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            segment => parts.push(segment),
+        }
+    }
+    let base = parts.join("/");
+    let stem = [".js", ".jsx", ".mjs"]
+        .iter()
+        .find_map(|ext| base.strip_suffix(ext))
+        .unwrap_or(&base);
+    std::iter::once(base.clone())
+        .chain(EXTENSIONS.iter().map(|ext| format!("{stem}.{ext}")))
+        .chain(EXTENSIONS.iter().map(|ext| format!("{base}/index.{ext}")))
+        .find(|candidate| exists(candidate))
+}
+
+/// Follows imports through re-export chains. Modules are loaded on demand
+/// by the caller and cached for the resolution pass.
+pub struct Barrels {
+    files: HashSet<String>,
+    cache: HashMap<String, Option<Rc<Module>>>,
+}
+
+impl Barrels {
+    /// `files` are the repo-relative paths of the repository's files.
+    pub fn new(files: impl IntoIterator<Item = String>) -> Self {
+        Self { files: files.into_iter().collect(), cache: HashMap::new() }
+    }
+
+    /// Id of the symbol `local` refers to in `importer`, whose links are
+    /// `links`, when it is imported from a file of this repository.
+    pub fn resolve_import(
+        &mut self,
+        importer: &str,
+        links: &ModuleLinks,
+        local: &str,
+        load: &mut dyn FnMut(&str) -> Option<Module>,
+    ) -> Option<i64> {
+        let (spec, imported) = links.imports.get(local)?;
+        let target = self.module_path(importer, spec)?;
+        self.resolve_export(&target, imported, 0, load)
+    }
+
+    /// Id of the symbol module `path` exports as `name`.
+    fn resolve_export(
+        &mut self,
+        path: &str,
+        name: &str,
+        depth: usize,
+        load: &mut dyn FnMut(&str) -> Option<Module>,
+    ) -> Option<i64> {
+        if depth > MAX_REEXPORT_DEPTH {
+            return None;
+        }
+        let module = self.module(path, load)?;
+        if let Some(&id) = module.declared.get(name) {
+            return Some(id);
+        }
+        for export in module.links.named.iter().filter(|e| e.exported == name) {
+            let hop = match &export.spec {
+                Some(spec) => Some((spec.clone(), export.imported.clone())),
+                // `import { parse } from "./parse"; export { parse }`
+                None => module.links.imports.get(&export.imported).cloned(),
+            };
+            let found = match hop {
+                Some((spec, imported)) => self
+                    .module_path(path, &spec)
+                    .and_then(|target| self.resolve_export(&target, &imported, depth + 1, load)),
+                // `export { run as start }` of a local declaration.
+                None => module.declared.get(&export.imported).copied(),
+            };
+            if found.is_some() {
+                return found;
+            }
+        }
+        if name == "default" {
+            return None;
+        }
+        for spec in &module.links.star {
+            let found = self
+                .module_path(path, spec)
+                .and_then(|target| self.resolve_export(&target, name, depth + 1, load));
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
+    fn module_path(&self, importer: &str, spec: &str) -> Option<String> {
+        resolve_module(importer, spec, &|p| self.files.contains(p))
+    }
+
+    fn module(&mut self, path: &str, load: &mut dyn FnMut(&str) -> Option<Module>) -> Option<Rc<Module>> {
+        self.cache
+            .entry(path.to_string())
+            .or_insert_with(|| load(path).map(Rc::new))
+            .clone()
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::context::ContextEngine;
+use crate::db::{Database, SearchFilter};
+use crate::fixture::{generate_repo, sample_queries, FixtureSpec};
+use crate::grammar::GrammarRegistry;
+use crate::indexer::Indexer;
+
+// ---------------------------------------------------------------------------
+// Report types
+// ---------------------------------------------------------------------------
+
+/// Latency distribution of repeated calls, in microseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct Latency {
+    pub samples: usize,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub max_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub files: usize,
+    pub symbols: usize,
+    pub edges: usize,
+    pub index_ms: u64,
+    pub files_per_sec: f64,
+    pub symbols_per_sec: f64,
+    pub search: Latency,
+    pub capsule: Latency,
+}
+
+// ---------------------------------------------------------------------------
+// Runner
+// ---------------------------------------------------------------------------
+
+/// Generate a synthetic repository under `root`, index it into an in-memory
+/// database, then time `iterations` FTS searches and context capsules. The
+/// numbers behind `focal bench`; the criterion benches measure the same
+/// operations with statistical rigor.
+pub fn run_bench(root: &Path, spec: &FixtureSpec, iterations: usize) -> Result<BenchReport> {
+    generate_repo(root, spec)?;
+    let db = Database::open_in_memory()?;
+    let registry = GrammarRegistry::new();
+
+    let start = Instant::now();
+    let stats = Indexer::new(&db, &registry).index_directory(root)?;
+    let index_time = start.elapsed();
+    let secs = index_time.as_secs_f64().max(f64::EPSILON);
+
+    let queries = sample_queries();
+    let mut search = Vec::with_capacity(iterations);
+    let mut capsule = Vec::with_capacity(iterations);
+    let engine = ContextEngine::new(&db);
+    for query in queries.iter().cycle().take(iterations) {
+        let start = Instant::now();
+        db.search_code(query, &SearchFilter::default(), 20)?;
+        search.push(start.elapsed());
+
+        let start = Instant::now();
+        engine.get_capsule(&format!("how does {query} work"), 8_000, None, &HashSet::new())?;
+        capsule.push(start.elapsed());
+    }
+
+    Ok(BenchReport {
+        files: stats.files_indexed,
+        symbols: stats.symbols_extracted,
+        edges: stats.edges_created,
+        index_ms: index_time.as_millis() as u64,
+        files_per_sec: (stats.files_indexed as f64 / secs).round(),
+        symbols_per_sec: (stats.symbols_extracted as f64 / secs).round(),
+        search: latency(search),
+        capsule: latency(capsule),
+    })
+}
+
+fn latency(mut samples: Vec<Duration>) -> Latency {
+    samples.sort();
+    let at = |q: f64| {
+        samples
+            .get(((samples.len() as f64 * q) as usize).min(samples.len().saturating_sub(1)))
+            .map_or(0, |d| d.as_micros() as u64)
+    };
+    Latency {
+        samples: samples.len(),
+        p50_us: at(0.5),
+        p95_us: at(0.95),
+        max_us: samples.last().map_or(0, |d| d.as_micros() as u64),
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::{HashMap, HashSet};
+
+// ---------------------------------------------------------------------------
+// Builtin call filtering
+// ---------------------------------------------------------------------------
+//
+// Calls to language builtins and ubiquitous standard-library helpers
+// (`println!`, `len`, `console.log`) are everywhere. A repository symbol of
+// the same name still gets the edge, since dropping it would lose real calls;
+// otherwise edge resolution skips `calls` references to these names, so they
+// never become external stubs, unless the filter is turned off.
+
+const RUST: &[&str] = &[
+    "println", "print", "eprintln", "eprint", "format", "write", "writeln", "vec", "panic", "assert",
+    "assert_eq", "assert_ne", "debug_assert", "debug_assert_eq", "debug_assert_ne", "unreachable",
+    "todo", "unimplemented", "dbg", "matches", "Some", "Ok", "Err", "Box", "clone", "to_string",
+    "to_owned", "into", "unwrap", "unwrap_or", "unwrap_or_default", "unwrap_or_else", "expect", "iter",
+    "into_iter", "collect", "map", "len", "is_empty", "push",
+];
+
+const GO: &[&str] = &[
+    "len", "cap", "append", "make", "new", "panic", "recover", "print", "println", "copy", "delete",
+    "close", "Println", "Printf", "Sprintf", "Sprint", "Errorf", "Fprintf", "Fprintln",
+];
+
+const PYTHON: &[&str] = &[
+    "print", "len", "str", "int", "float", "bool", "list", "dict", "set", "tuple", "range", "enumerate",
+    "zip", "isinstance", "issubclass", "getattr", "setattr", "hasattr", "super", "open", "sorted",
+    "reversed", "min", "max", "sum", "any", "all", "repr", "type", "format", "append", "extend",
+];
+
+const TYPESCRIPT: &[&str] = &[
+    "log", "error", "warn", "info", "debug", "parseInt", "parseFloat", "setTimeout", "setInterval",
+    "clearTimeout", "clearInterval", "require", "push", "map", "filter", "forEach", "reduce", "join",
+    "toString", "stringify", "then", "catch", "resolve", "reject",
+];
+
+/// The default denylist for `language` (a file extension).
+pub fn default_builtins(language: &str) -> &'static [&'static str] {
+    match language {
+        "rs" => RUST,
+        "go" => GO,
+        "py" => PYTHON,
+        "ts" | "tsx" => TYPESCRIPT,
+        _ => &[],
+    }
+}
+
+/// Decides which callee names edge resolution ignores.
+#[derive(Debug, Clone)]
+pub struct BuiltinFilter {
+    enabled: bool,
+    extra: HashMap<String, HashSet<String>>,
+}
+
+impl Default for BuiltinFilter {
+    fn default() -> Self {
+        Self { enabled: true, extra: HashMap::new() }
+    }
+}
+
+impl BuiltinFilter {
+    /// A filter that keeps every call edge.
+    pub fn disabled() -> Self {
+        Self { enabled: false, extra: HashMap::new() }
+    }
+
+    /// Also ignore `names` in files of `language`.
+    pub fn with_extra(mut self, language: &str, names: impl IntoIterator<Item = String>) -> Self {
+        self.extra.entry(language.to_string()).or_default().extend(names);
+        self
+    }
+
+    /// True when a call to `name` in a `language` file should not become an edge.
+    pub fn is_builtin(&self, language: &str, name: &str) -> bool {
+        self.enabled
+            && (default_builtins(language).contains(&name)
+                || self.extra.get(language).is_some_and(|names| names.contains(name)))
+    }
+}
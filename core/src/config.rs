@@ -1,10 +1,42 @@
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+use crate::builtins::BuiltinFilter;
 
 #[derive(Debug, Default, Deserialize)]
 pub struct FocalConfig {
     #[serde(default)]
     pub manifests: ManifestConfig,
+    #[serde(default)]
+    pub edges: EdgeConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub index: IndexConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    /// `[projects]`: repositories indexed from separate roots that form one
+    /// project, by repository name (`shop = ["backend", "frontend", "proto"]`).
+    /// Tools accept `project` wherever they accept `repo`, and references
+    /// resolve across the members.
+    #[serde(default)]
+    pub projects: BTreeMap<String, Vec<String>>,
+    /// `[repositories]`: repository names by root path, for roots whose
+    /// directory names collide (`"~/work/api" = "work-api"`). A renamed
+    /// repository keeps its old name as an alias.
+    #[serde(default)]
+    pub repositories: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -15,6 +47,236 @@ pub struct ManifestConfig {
     pub auto_import_git: Vec<String>,
 }
 
+/// `[edges]`: which call references become graph edges.
+#[derive(Debug, Default, Deserialize)]
+pub struct EdgeConfig {
+    /// Keep calls to builtins (`println!`, `len`, `console.log`) in the graph.
+    #[serde(default)]
+    pub keep_builtins: bool,
+    /// Additional callee names to ignore when no repository symbol has them,
+    /// keyed by file extension (`rs = ["log_event"]`).
+    #[serde(default)]
+    pub extra_builtins: HashMap<String, Vec<String>>,
+}
+
+/// `[session]`: per-session limits for the MCP server.
+#[derive(Debug, Default, Deserialize)]
+pub struct SessionConfig {
+    /// Total tokens a session may receive before get_context budgets start
+    /// shrinking; they scale with the unspent share of the cap.
+    #[serde(default)]
+    pub soft_cap_tokens: Option<usize>,
+}
+
+/// `[index]`: which files are indexed and how, and how changes and
+/// deletions are handled.
+#[derive(Debug, Default, Deserialize)]
+pub struct IndexConfig {
+    /// Hours a deleted file is kept as a tombstone, so it can come back with
+    /// its symbol ids and memory links (default 24).
+    #[serde(default)]
+    pub tombstone_grace_hours: Option<u64>,
+    /// Changed files the watcher takes per batch (default 64).
+    #[serde(default)]
+    pub watcher_batch_size: Option<usize>,
+    /// Mask API keys, tokens and private keys in stored bodies (default true).
+    #[serde(default)]
+    pub redact_secrets: Option<bool>,
+    /// Paths never indexed nor returned by any tool (`**/secrets/**`,
+    /// `.env*`). Files indexed before a pattern was added are purged.
+    #[serde(default)]
+    pub deny_paths: Vec<String>,
+    /// Indexable files a repository may have before a full walk refuses to
+    /// index it without `--force` (default 100,000).
+    #[serde(default)]
+    pub max_files_per_repo: Option<usize>,
+    /// Symbols a repository may have before a full walk refuses to index it
+    /// without `--force` (default 2,000,000).
+    #[serde(default)]
+    pub max_symbols_per_repo: Option<usize>,
+    /// Skip directories where more than this many files share a generated
+    /// name pattern (default 200; 0 disables).
+    #[serde(default)]
+    pub generated_dir_files: Option<usize>,
+}
+
+impl IndexConfig {
+    pub fn tombstone_grace_hours(&self) -> u64 {
+        self.tombstone_grace_hours.unwrap_or(crate::db::DEFAULT_TOMBSTONE_GRACE_HOURS)
+    }
+
+    pub fn watcher_batch_size(&self) -> usize {
+        self.watcher_batch_size.unwrap_or(crate::reindex_scheduler::BATCH_SIZE)
+    }
+
+    pub fn redact_secrets(&self) -> bool {
+        self.redact_secrets.unwrap_or(true)
+    }
+
+    pub fn deny_list(&self) -> crate::deny::DenyList {
+        crate::deny::DenyList::new(self.deny_paths.iter().cloned())
+    }
+
+    pub fn guardrails(&self) -> crate::guardrails::Guardrails {
+        use crate::guardrails::{
+            Guardrails, DEFAULT_GENERATED_DIR_FILES, DEFAULT_MAX_FILES, DEFAULT_MAX_SYMBOLS,
+        };
+        Guardrails::new(
+            self.max_files_per_repo.unwrap_or(DEFAULT_MAX_FILES),
+            self.max_symbols_per_repo.unwrap_or(DEFAULT_MAX_SYMBOLS),
+            self.generated_dir_files.unwrap_or(DEFAULT_GENERATED_DIR_FILES),
+        )
+    }
+}
+
+/// `[database]`: upkeep of the SQLite file for long-running servers.
+#[derive(Debug, Default, Deserialize)]
+pub struct DatabaseConfig {
+    /// Minutes between `wal_checkpoint(TRUNCATE)` runs (default 10; 0 disables).
+    #[serde(default)]
+    pub wal_checkpoint_minutes: Option<u64>,
+}
+
+impl DatabaseConfig {
+    pub fn wal_checkpoint_minutes(&self) -> u64 {
+        self.wal_checkpoint_minutes.unwrap_or(crate::db::DEFAULT_WAL_CHECKPOINT_MINUTES)
+    }
+}
+
+/// `[backup]`: snapshots of the index, made by `focal backup` and by
+/// `focal serve` on a schedule.
+#[derive(Debug, Default, Deserialize)]
+pub struct BackupConfig {
+    /// Rotation directory (default `<focal dir>/backups`).
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    /// Snapshots kept; older ones are deleted (default 7).
+    #[serde(default)]
+    pub keep: Option<usize>,
+    /// `focal serve` backs up when the newest snapshot is older than this
+    /// many hours (default 24; 0 disables).
+    #[serde(default)]
+    pub interval_hours: Option<u64>,
+}
+
+impl BackupConfig {
+    pub fn dir(&self) -> PathBuf {
+        self.dir.clone().unwrap_or_else(|| focal_dir().join("backups"))
+    }
+
+    pub fn keep(&self) -> usize {
+        self.keep.unwrap_or(crate::backup::DEFAULT_KEEP).max(1)
+    }
+
+    pub fn interval_hours(&self) -> u64 {
+        self.interval_hours.unwrap_or(crate::backup::DEFAULT_INTERVAL_HOURS)
+    }
+}
+
+/// `[audit]`: tool-call auditing for shared `focal serve --http` hosts.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditConfig {
+    /// Record every tool call in `audit_log` (default: on with `--http`,
+    /// off over stdio).
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    /// Bearer token required by `GET /admin/audit`; the endpoint is
+    /// disabled without one.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Days rows are kept in `audit_log` (default 90; 0 keeps them forever).
+    #[serde(default)]
+    pub retention_days: Option<u64>,
+}
+
+impl AuditConfig {
+    pub fn enabled(&self, http: bool) -> bool {
+        self.enabled.unwrap_or(http)
+    }
+
+    pub fn retention_days(&self) -> u64 {
+        self.retention_days.unwrap_or(crate::audit::DEFAULT_RETENTION_DAYS)
+    }
+}
+
+/// `[http]`: fair sharing of a `focal serve --http` host between sessions.
+#[derive(Debug, Default, Deserialize)]
+pub struct HttpConfig {
+    /// Sustained requests per minute per MCP session (default 600; 0 disables).
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// Requests a session may send in a burst before the rate applies (default 60).
+    #[serde(default)]
+    pub burst: Option<u32>,
+    /// Tool calls running at once across all sessions (default 16; 0 disables).
+    #[serde(default)]
+    pub max_concurrent_tools: Option<usize>,
+    /// Tool calls running at once within one session (default 4; 0 disables).
+    #[serde(default)]
+    pub max_concurrent_tools_per_session: Option<usize>,
+    /// Report `/readyz` as unready while the watcher reindexes (default false).
+    #[serde(default)]
+    pub unready_during_reindex: bool,
+}
+
+impl HttpConfig {
+    pub fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute.unwrap_or(crate::rate_limit::DEFAULT_REQUESTS_PER_MINUTE)
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst.unwrap_or(crate::rate_limit::DEFAULT_BURST)
+    }
+
+    pub fn max_concurrent_tools(&self) -> usize {
+        self.max_concurrent_tools.unwrap_or(crate::rate_limit::DEFAULT_MAX_CONCURRENT_TOOLS)
+    }
+
+    pub fn max_concurrent_tools_per_session(&self) -> usize {
+        self.max_concurrent_tools_per_session
+            .unwrap_or(crate::rate_limit::DEFAULT_MAX_CONCURRENT_TOOLS_PER_SESSION)
+    }
+}
+
+/// `[memory]`: budgets for the in-process caches, so a server fits a small
+/// dev container.
+#[derive(Debug, Default, Deserialize)]
+pub struct MemoryConfig {
+    /// Megabytes the graph cache may keep between traversals (default 512).
+    /// A larger graph is loaded per traversal and released after it.
+    #[serde(default)]
+    pub graph_cache_mb: Option<usize>,
+    /// Megabytes of cached tool results per session (default 64; 0 disables
+    /// the cache).
+    #[serde(default)]
+    pub query_cache_mb: Option<usize>,
+}
+
+impl MemoryConfig {
+    pub fn graph_cache_bytes(&self) -> usize {
+        self.graph_cache_mb
+            .map_or(crate::graph_cache::DEFAULT_BUDGET_BYTES, |mb| mb * 1024 * 1024)
+    }
+
+    pub fn query_cache_bytes(&self) -> usize {
+        self.query_cache_mb
+            .map_or(crate::query_cache::DEFAULT_BUDGET_BYTES, |mb| mb * 1024 * 1024)
+    }
+}
+
+impl EdgeConfig {
+    pub fn builtin_filter(&self) -> BuiltinFilter {
+        if self.keep_builtins {
+            return BuiltinFilter::disabled();
+        }
+        self.extra_builtins
+            .iter()
+            .fold(BuiltinFilter::default(), |filter, (language, names)| {
+                filter.with_extra(language, names.iter().cloned())
+            })
+    }
+}
+
 impl FocalConfig {
     pub fn load() -> Self {
         let path = Self::config_path();
@@ -28,9 +290,205 @@ impl FocalConfig {
     }
 
     fn config_path() -> PathBuf {
-        dirs::home_dir()
-            .expect("failed to determine home directory")
-            .join(".focal")
-            .join("config.toml")
+        focal_dir().join("config.toml")
+    }
+
+    /// `[repositories]` keyed by canonical root path, as the indexer records
+    /// roots. A leading `~/` is the home directory.
+    pub fn repo_names(&self) -> BTreeMap<String, String> {
+        self.repositories
+            .iter()
+            .map(|(root, name)| {
+                let root = match (root.strip_prefix("~/"), dirs::home_dir()) {
+                    (Some(rest), Some(home)) => home.join(rest),
+                    _ => PathBuf::from(root),
+                };
+                let root = crate::paths::canonicalize(&root).unwrap_or(root);
+                (root.to_string_lossy().to_string(), name.clone())
+            })
+            .collect()
     }
 }
+
+// ---------------------------------------------------------------------------
+// Per-repository settings
+// ---------------------------------------------------------------------------
+//
+// `.focal.toml` at a repository root is checked in with the code, so a team
+// can tune how context is assembled for its codebase. `ContextEngine` reads
+// it on every capsule; nothing is cached, so edits apply immediately.
+
+/// File name of the per-repository config, at the repository root.
+pub const REPO_CONFIG_FILE: &str = ".focal.toml";
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RepoConfig {
+    #[serde(default)]
+    pub context: ContextTuning,
+}
+
+/// Deepest graph expansion `[context] expansion_depth` may ask for.
+pub const MAX_EXPANSION_DEPTH: usize = 3;
+
+/// Intents a `[context.templates.<intent>]` table may name.
+const TEMPLATE_INTENTS: &[&str] = &["debug", "explore", "modify", "refactor"];
+
+/// `[context]` in `.focal.toml`: capsule assembly for this repository.
+/// Unset values keep the built-in defaults; unknown keys are an error.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextTuning {
+    /// Pivots kept after ranking (default 5).
+    #[serde(default)]
+    pub pivots: Option<usize>,
+    /// Graph hops expanded from each pivot (default 1, at most 3).
+    #[serde(default)]
+    pub expansion_depth: Option<usize>,
+    /// Weight of recently changed files when ranking debug pivots (default 0.5).
+    #[serde(default)]
+    pub recency_boost: Option<f64>,
+    /// Share of the token budget reserved for memories (default 0.1); a
+    /// request's `memory_budget` wins.
+    #[serde(default)]
+    pub memory_budget: Option<f64>,
+    /// Symbol names never added as neighbours: helpers nearly every function
+    /// calls (`must`, `log_event`), which would crowd out the real ones.
+    #[serde(default)]
+    pub builtins: Vec<String>,
+    /// `[context.templates.<intent>]`: switch parts of an intent's capsule
+    /// template on or off (`[context.templates.modify] tests = true`).
+    #[serde(default)]
+    pub templates: HashMap<String, TemplateOverrides>,
+}
+
+/// Per-intent overrides of `context::CapsuleTemplate`; unset keeps the default.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateOverrides {
+    #[serde(default)]
+    pub dependents: Option<bool>,
+    #[serde(default)]
+    pub dependencies: Option<bool>,
+    #[serde(default)]
+    pub tests: Option<bool>,
+    #[serde(default)]
+    pub public_api: Option<bool>,
+    #[serde(default)]
+    pub caller_chains: Option<bool>,
+    #[serde(default)]
+    pub recent_changes: Option<bool>,
+}
+
+impl ContextTuning {
+    /// Reject values capsule assembly can't use.
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.pivots == Some(0) {
+            anyhow::bail!("[context] pivots must be at least 1");
+        }
+        if self.expansion_depth.is_some_and(|d| d > MAX_EXPANSION_DEPTH) {
+            anyhow::bail!("[context] expansion_depth must be at most {MAX_EXPANSION_DEPTH}");
+        }
+        for (key, value) in [("recency_boost", self.recency_boost), ("memory_budget", self.memory_budget)] {
+            if value.is_some_and(|v| !(0.0..=1.0).contains(&v)) {
+                anyhow::bail!("[context] {key} must be between 0.0 and 1.0");
+            }
+        }
+        if let Some(intent) = self.templates.keys().find(|k| !TEMPLATE_INTENTS.contains(&k.as_str())) {
+            anyhow::bail!("unknown intent [context.templates.{intent}]; expected one of {}", TEMPLATE_INTENTS.join(", "));
+        }
+        Ok(())
+    }
+}
+
+/// mtime and size of a `.focal.toml` when it was parsed.
+type FileStamp = (SystemTime, u64);
+
+/// Parsed `.focal.toml` files by path.
+static LOADED: LazyLock<Mutex<HashMap<PathBuf, (FileStamp, RepoConfig)>>> = LazyLock::new(Default::default);
+
+impl RepoConfig {
+    /// `load` for hot paths such as capsule assembly: the parsed file is
+    /// kept per repository until its mtime or size changes, so a call costs
+    /// one `stat`. None when the repository has no `.focal.toml`.
+    pub fn load_cached(root: &Path) -> Option<Self> {
+        let path = root.join(REPO_CONFIG_FILE);
+        let meta = std::fs::metadata(&path).ok().filter(|m| m.is_file())?;
+        let stamp = (meta.modified().ok()?, meta.len());
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, config)) = loaded.get(&path).filter(|(at, _)| *at == stamp) {
+            return Some(config.clone());
+        }
+        let config = Self::load(root);
+        loaded.insert(path, (stamp, config.clone()));
+        Some(config)
+    }
+
+    /// `<root>/.focal.toml`, or defaults when it is missing. A file that
+    /// doesn't parse or holds invalid values is reported and ignored.
+    pub fn load(root: &Path) -> Self {
+        Self::try_load(root).unwrap_or_else(|e| {
+            tracing::warn!(root = %root.display(), error = %e, "failed to parse {REPO_CONFIG_FILE}, using defaults");
+            Self::default()
+        })
+    }
+
+    /// Like `load`, but a parse error is returned instead of ignored.
+    pub fn try_load(root: &Path) -> anyhow::Result<Self> {
+        match std::fs::read_to_string(root.join(REPO_CONFIG_FILE)) {
+            Ok(content) => {
+                let config: Self = toml::from_str(&content)?;
+                config.context.validate()?;
+                Ok(config)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Locations and environment overrides
+// ---------------------------------------------------------------------------
+//
+// Containers often run without a home directory, so every path focal writes
+// is resolved here rather than from `dirs::home_dir()` directly.
+
+/// Path of the index database, overriding `<focal dir>/index.db`.
+pub const DB_PATH_ENV: &str = "FOCAL_DB_PATH";
+/// Workspace roots to serve when none are given on the command line,
+/// separated like `PATH` (`:` on Unix, `;` on Windows).
+pub const WORKSPACES_ENV: &str = "FOCAL_WORKSPACES";
+/// HTTP port, overriding `--port`'s default.
+pub const PORT_ENV: &str = "FOCAL_PORT";
+/// HTTP bind address, overriding `--host`'s default.
+pub const HOST_ENV: &str = "FOCAL_HOST";
+
+/// Where focal keeps its config, reports and (by default) the database: the
+/// directory of `FOCAL_DB_PATH` when set, so everything focal writes follows
+/// the database; else `~/.focal`; without a home directory, `focal` under the
+/// system temp directory.
+pub fn focal_dir() -> PathBuf {
+    if let Some(parent) = env_db_path().as_deref().and_then(Path::parent).filter(|p| !p.as_os_str().is_empty()) {
+        return parent.to_path_buf();
+    }
+    if let Some(home) = dirs::home_dir() {
+        return home.join(".focal");
+    }
+    std::env::temp_dir().join("focal")
+}
+
+/// The index database: `FOCAL_DB_PATH`, else `<focal dir>/index.db`.
+pub fn db_path() -> PathBuf {
+    env_db_path().unwrap_or_else(|| focal_dir().join("index.db"))
+}
+
+fn env_db_path() -> Option<PathBuf> {
+    std::env::var_os(DB_PATH_ENV).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+/// Workspace roots from `FOCAL_WORKSPACES`, empty when unset.
+pub fn workspaces_from_env() -> Vec<PathBuf> {
+    std::env::var_os(WORKSPACES_ENV)
+        .map(|v| std::env::split_paths(&v).filter(|p| !p.as_os_str().is_empty()).collect())
+        .unwrap_or_default()
+}
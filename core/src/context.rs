@@ -1,8 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
 use serde::Serialize;
 
-use crate::db::{Database, Memory, Symbol};
+use crate::config::{ContextTuning, RepoConfig, TemplateOverrides, MAX_EXPANSION_DEPTH, REPO_CONFIG_FILE};
+use crate::db::{Database, Memory, SearchFilter, Symbol, SymbolSummary};
+use crate::proximity::Focus;
 
 // ---------------------------------------------------------------------------
 // Intent detection
@@ -60,6 +63,110 @@ impl Intent {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Expansion strategy
+// ---------------------------------------------------------------------------
+
+/// Which neighbours of the pivots fill phase 2 of a capsule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expansion {
+    /// Callers and/or callees along graph edges, chosen by intent.
+    #[default]
+    Graph,
+    /// Symbols sharing the pivot's parent (class, impl, module) or, for
+    /// top-level pivots, its file: the implementations to pattern-match
+    /// when adding a similar one.
+    Siblings,
+    /// Graph neighbours first, then siblings.
+    Both,
+}
+
+impl Expansion {
+    /// Parse an `expansion` parameter; None is `Graph`.
+    pub fn parse(expansion: Option<&str>) -> anyhow::Result<Self> {
+        match expansion.map(str::trim).unwrap_or("graph") {
+            "" | "graph" => Ok(Self::Graph),
+            "siblings" => Ok(Self::Siblings),
+            "both" => Ok(Self::Both),
+            other => anyhow::bail!("unknown expansion '{other}': expected graph, siblings or both"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Per-intent capsule templates
+// ---------------------------------------------------------------------------
+
+/// What a capsule for one intent is assembled from, beyond its pivots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CapsuleTemplate {
+    /// Callers of each pivot.
+    pub dependents: bool,
+    /// Callees of each pivot.
+    pub dependencies: bool,
+    /// Tests linked to each pivot (`test_links`).
+    pub tests: bool,
+    /// Other public symbols in each pivot's file.
+    pub public_api: bool,
+    /// Caller chains up to `CALLER_CHAIN_DEPTH` hops, as `call_chains`.
+    pub caller_chains: bool,
+    /// Last re-index time of the capsule's files, as `recent_changes`.
+    pub recent_changes: bool,
+}
+
+impl CapsuleTemplate {
+    /// Debug: who calls the pivot and how control got there, what it calls,
+    /// and which files changed lately.
+    pub const DEBUG: Self = Self {
+        dependents: true,
+        dependencies: true,
+        tests: false,
+        public_api: false,
+        caller_chains: true,
+        recent_changes: true,
+    };
+    /// Refactor: the blast radius, the tests that guard it, and the API
+    /// surface that must keep its shape.
+    pub const REFACTOR: Self = Self {
+        dependents: true,
+        dependencies: false,
+        tests: true,
+        public_api: true,
+        caller_chains: false,
+        recent_changes: false,
+    };
+    /// Modify and Explore: what the pivot builds on.
+    pub const DEPENDENCIES: Self = Self {
+        dependents: false,
+        dependencies: true,
+        tests: false,
+        public_api: false,
+        caller_chains: false,
+        recent_changes: false,
+    };
+
+    pub fn for_intent(intent: Intent) -> Self {
+        match intent {
+            Intent::Debug => Self::DEBUG,
+            Intent::Refactor => Self::REFACTOR,
+            Intent::Modify | Intent::Explore => Self::DEPENDENCIES,
+        }
+    }
+
+    /// This template with the fields a `.focal.toml` override sets.
+    pub fn with_overrides(self, overrides: &TemplateOverrides) -> Self {
+        Self {
+            dependents: overrides.dependents.unwrap_or(self.dependents),
+            dependencies: overrides.dependencies.unwrap_or(self.dependencies),
+            tests: overrides.tests.unwrap_or(self.tests),
+            public_api: overrides.public_api.unwrap_or(self.public_api),
+            caller_chains: overrides.caller_chains.unwrap_or(self.caller_chains),
+            recent_changes: overrides.recent_changes.unwrap_or(self.recent_changes),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Capsule data types
 // ---------------------------------------------------------------------------
@@ -79,21 +186,142 @@ pub struct CapsuleItem {
     /// For symbols already sent in this session, contains a placeholder note.
     pub body: String,
     pub is_pivot: bool,
+    /// Why the symbol is here: "pivot", "dependency", "dependent", "test",
+    /// "public_api" or "sibling".
+    pub role: String,
     pub token_estimate: usize,
     pub start_line: i64,
     pub end_line: i64,
 }
 
+/// Compact pointer to a relevant memory that didn't fit the memory budget.
+/// The agent can fetch the full note via `list_memories` / `search_memory`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryRef {
+    pub id: i64,
+    /// First `MEMORY_REF_PREVIEW_CHARS` characters of the content.
+    pub preview: String,
+}
+
+/// When a capsule file last changed in the index.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentChange {
+    pub file_path: String,
+    pub indexed_at: String,
+}
+
+/// Outline of a file holding a pivot (`ContextEngine::with_file_skeletons`).
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSkeleton {
+    pub file_path: String,
+    pub symbols: Vec<SymbolSummary>,
+    pub token_estimate: usize,
+}
+
 /// Token-budgeted context capsule returned by `ContextEngine::get_capsule`.
 #[derive(Debug, Clone, Serialize)]
 pub struct ContextCapsule {
     pub intent: String,
     pub items: Vec<CapsuleItem>,
     pub memories: Vec<Memory>,
+    /// Relevant memories that overflowed the memory budget, best first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub memory_refs: Vec<MemoryRef>,
+    /// Debug: stack-frame-style caller chains ending at a pivot, outermost
+    /// caller first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub call_chains: Vec<String>,
+    /// Debug: the capsule's files, most recently re-indexed first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recent_changes: Vec<RecentChange>,
+    /// Skeletons of the pivots' files, when requested and within budget.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub file_skeletons: Vec<FileSkeleton>,
+    /// Symbols left out because they were marked irrelevant this session.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub skipped_irrelevant: usize,
     pub total_tokens: usize,
     pub budget: usize,
 }
 
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn clamp_fraction(fraction: f64) -> f64 {
+    if fraction.is_finite() {
+        fraction.clamp(0.0, 1.0)
+    } else {
+        DEFAULT_MEMORY_BUDGET_FRACTION
+    }
+}
+
+fn round3(x: f64) -> f64 {
+    (x * 1000.0).round() / 1000.0
+}
+
+/// How a capsule was assembled (`ContextEngine::explain`), without bodies.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapsuleTrace {
+    pub intent: String,
+    /// The `.focal.toml` the capsule was tuned by, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<String>,
+    /// The query after intent keywords were stripped.
+    pub fts_query: String,
+    /// Weight given to recently changed files when ranking (Debug only).
+    pub recency_boost: f64,
+    pub expansion: Expansion,
+    pub template: CapsuleTemplate,
+    /// Every pivot candidate, best score first among those ranked.
+    pub candidates: Vec<CandidateTrace>,
+    /// Every neighbour considered in phase 2, in order.
+    pub expansions: Vec<ExpansionTrace>,
+    pub budget: BudgetTrace,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateTrace {
+    #[serde(skip)]
+    pub symbol_id: i64,
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    /// "fts", or "name_match" for the LIKE fallback.
+    pub source: &'static str,
+    /// Rank score plus focus/working-set boosts; absent for excluded symbols.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// "pivot", "over_budget", "below_cutoff" (past the pivot count) or
+    /// "irrelevant" (marked irrelevant this session).
+    pub decision: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpansionTrace {
+    /// The pivot it was reached from.
+    pub from: String,
+    pub name: String,
+    pub role: &'static str,
+    /// "added", "over_budget" or "irrelevant".
+    pub decision: &'static str,
+}
+
+/// Tokens spent per capsule section.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetTrace {
+    pub budget: usize,
+    /// Share reserved for memories.
+    pub memory_budget: usize,
+    pub pivots: usize,
+    pub neighbours: usize,
+    /// Caller chains and recent changes.
+    pub extras: usize,
+    pub file_skeletons: usize,
+    pub memories: usize,
+    pub total: usize,
+}
+
 // ---------------------------------------------------------------------------
 // Token estimation
 // ---------------------------------------------------------------------------
@@ -144,17 +372,174 @@ fn item_token_cost(sym: &Symbol, file_path: &str, include_body: bool) -> usize {
     chars.div_ceil(4)
 }
 
+/// Estimate tokens for a file skeleton: the path plus, per symbol, the same
+/// name/kind/signature/line overhead as a body-less capsule item.
+fn skeleton_token_cost(file_path: &str, symbols: &[SymbolSummary]) -> usize {
+    let chars: usize = symbols
+        .iter()
+        .map(|s| s.name.len() + s.kind.len() + s.signature.len() + 20)
+        .sum();
+    (file_path.len() + chars).div_ceil(4)
+}
+
+// ---------------------------------------------------------------------------
+// Memory relevance scoring
+// ---------------------------------------------------------------------------
+
+/// Default share of the capsule budget reserved for memories.
+pub const DEFAULT_MEMORY_BUDGET_FRACTION: f64 = 0.1;
+
+/// Pivot candidates fetched for re-ranking when a focus is set.
+const FOCUS_CANDIDATES: i64 = 25;
+
+/// Pivots kept after ranking, unless `.focal.toml` sets `pivots`.
+const MAX_PIVOTS: usize = 5;
+
+/// Recency weight for debug pivots, unless `.focal.toml` sets `recency_boost`.
+const DEFAULT_RECENCY_BOOST: f64 = 0.5;
+
+/// Siblings added per pivot, nearest by line first.
+const SIBLINGS_PER_PIVOT: usize = 8;
+
+/// Public symbols added per pivot by the Refactor template.
+const PUBLIC_API_PER_PIVOT: usize = 10;
+
+/// Longest caller chain (hops above the pivot) and chains kept per pivot.
+const CALLER_CHAIN_DEPTH: usize = 3;
+const CALLER_CHAINS_PER_PIVOT: usize = 3;
+
+/// Files listed in a Debug capsule's `recent_changes`.
+const MAX_RECENT_CHANGES: usize = 5;
+
+/// Weight of the session working set relative to an explicit focus.
+const WORKING_SET_WEIGHT: f64 = 0.5;
+
+/// Characters of content kept in an overflow `MemoryRef` preview.
+const MEMORY_REF_PREVIEW_CHARS: usize = 80;
+
+/// Score weight for a memory linked to one of the capsule's pivot symbols.
+const MEMORY_LINKED_WEIGHT: f64 = 3.0;
+/// Score weight for the top FTS match against the query; lower-ranked
+/// matches get a proportionally smaller share.
+const MEMORY_FTS_WEIGHT: f64 = 2.0;
+/// Pinned memories outrank everything that isn't pinned.
+const MEMORY_PINNED_WEIGHT: f64 = 10.0;
+/// Age (days) at which the recency component has decayed to half.
+const MEMORY_RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Multiplier applied to a memory's relevance by category. Curated knowledge
+/// (decisions, invariants) beats auto-generated observations, which mention
+/// symbol names constantly and would otherwise win every FTS match.
+fn category_weight(category: &str) -> f64 {
+    match category {
+        "decision" | "invariant" => 1.0,
+        "architecture" | "bug_fix" => 0.8,
+        "convention" | "pattern" => 0.6,
+        "observation" => 0.1,
+        _ => 0.4,
+    }
+}
+
+/// Truncate memory content to a one-line preview for overflow references.
+fn memory_preview(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(MEMORY_REF_PREVIEW_CHARS) {
+        Some((idx, _)) => format!("{}…", &flat[..idx]),
+        None => flat,
+    }
+}
+
+/// Recency component in [0, 1]: halves every `MEMORY_RECENCY_HALF_LIFE_DAYS`.
+fn recency_decay(age_days: f64) -> f64 {
+    0.5_f64.powf(age_days.max(0.0) / MEMORY_RECENCY_HALF_LIFE_DAYS)
+}
+
 // ---------------------------------------------------------------------------
 // ContextEngine
 // ---------------------------------------------------------------------------
 
 pub struct ContextEngine<'a> {
     db: &'a Database,
+    /// Set by the request; otherwise `.focal.toml`, then the default.
+    memory_budget_fraction: Option<f64>,
+    package: String,
+    focus: Option<Focus>,
+    working_set: Option<Focus>,
+    excluded: HashSet<i64>,
+    file_skeletons: bool,
+    expansion: Expansion,
+    include_generated: bool,
 }
 
 impl<'a> ContextEngine<'a> {
     pub fn new(db: &'a Database) -> Self {
-        Self { db }
+        Self {
+            db,
+            memory_budget_fraction: None,
+            package: String::new(),
+            focus: None,
+            working_set: None,
+            excluded: HashSet::new(),
+            file_skeletons: false,
+            expansion: Expansion::Graph,
+            include_generated: false,
+        }
+    }
+
+    /// Draw pivot symbols only from files of this monorepo package; the
+    /// graph expansion around them may still cross package boundaries.
+    pub fn with_package(mut self, package: &str) -> Self {
+        self.package = package.to_string();
+        self
+    }
+
+    /// Also draw pivots from generated files (protobuf stubs, compiled
+    /// bundles), which are skipped by default.
+    pub fn with_generated(mut self, include: bool) -> Self {
+        self.include_generated = include;
+        self
+    }
+
+    /// Prefer pivots near `focus` (graph hops or same directory). Candidates
+    /// are over-fetched and re-ranked, so a nearby but lower-scoring match
+    /// can displace a distant one.
+    pub fn with_focus(mut self, focus: Focus) -> Self {
+        self.focus = Some(focus);
+        self
+    }
+
+    /// Prefer pivots near the session's working set, at half the weight of
+    /// an explicit focus.
+    pub fn with_working_set(mut self, working_set: Focus) -> Self {
+        self.working_set = Some(working_set.with_weight(WORKING_SET_WEIGHT));
+        self
+    }
+
+    /// Never include these symbols, as pivots or as neighbours (symbols the
+    /// agent marked irrelevant).
+    pub fn with_excluded(mut self, excluded: HashSet<i64>) -> Self {
+        self.excluded = excluded;
+        self
+    }
+
+    /// Choose how pivots are expanded (default: graph edges by intent).
+    pub fn with_expansion(mut self, expansion: Expansion) -> Self {
+        self.expansion = expansion;
+        self
+    }
+
+    /// Append the skeleton of each pivot's file, once per file, with what
+    /// budget is left after symbols and the memory share.
+    pub fn with_file_skeletons(mut self, enabled: bool) -> Self {
+        self.file_skeletons = enabled;
+        self
+    }
+
+    /// Set the share of the token budget reserved for memories (clamped to
+    /// 0.0–1.0). Memories that don't fit are listed as `memory_refs`.
+    pub fn with_memory_budget_fraction(mut self, fraction: f64) -> Self {
+        self.memory_budget_fraction = Some(clamp_fraction(fraction));
+        self
     }
 
     /// Build a token-budgeted context capsule for `query`.
@@ -162,11 +547,16 @@ impl<'a> ContextEngine<'a> {
     /// Algorithm:
     /// 1. Detect intent from query text.
     /// 2. Phase 1 — FTS5 search for pivot symbols (top 5), add with full body.
-    /// 3. Phase 2 — Expand to adjacent symbols via the dependency graph,
-    ///    direction driven by intent. Adjacent symbols get skeleton only.
-    /// 4. Phase 3 — Attach memories linked to pivot symbols, capped at 10%
-    ///    of the token budget.
-    /// 5. Respect token budget at every step; stop adding when exhausted.
+    /// 3. Phase 2 — Expand to the neighbours the intent's `CapsuleTemplate`
+    ///    names (callers, callees, tests, public API) and/or to the pivots'
+    ///    siblings (see `Expansion`). Adjacent symbols get skeleton only.
+    /// 4. Template extras (Debug: caller chains, recent changes) and,
+    ///    optionally, skeletons of the pivots' files, in the budget left
+    ///    after symbols and the memory share.
+    /// 5. Phase 3 — Attach memories linked to pivots or matching the query,
+    ///    highest relevance score first, capped at the memory budget fraction
+    ///    (default 10%). Memories that don't fit become compact references.
+    /// 6. Respect token budget at every step; stop adding when exhausted.
     pub fn get_capsule(
         &self,
         query: &str,
@@ -174,6 +564,36 @@ impl<'a> ContextEngine<'a> {
         repo_id: Option<i64>,
         already_sent: &HashSet<i64>,
     ) -> anyhow::Result<ContextCapsule> {
+        Ok(self.assemble(query, max_tokens, repo_id, already_sent)?.0)
+    }
+
+    /// Run the `get_capsule` pipeline and return only its decision trace:
+    /// intent, FTS query, scored pivot candidates, expansion decisions and
+    /// token accounting. No bodies, so it is cheap to inspect.
+    pub fn explain(
+        &self,
+        query: &str,
+        max_tokens: usize,
+        repo_id: Option<i64>,
+        already_sent: &HashSet<i64>,
+    ) -> anyhow::Result<CapsuleTrace> {
+        let (_, mut trace) = self.assemble(query, max_tokens, repo_id, already_sent)?;
+        for candidate in &mut trace.candidates {
+            candidate.file_path = self
+                .db
+                .get_file_path_for_symbol(candidate.symbol_id)
+                .unwrap_or_else(|_| "<unknown>".to_string());
+        }
+        Ok(trace)
+    }
+
+    fn assemble(
+        &self,
+        query: &str,
+        max_tokens: usize,
+        repo_id: Option<i64>,
+        already_sent: &HashSet<i64>,
+    ) -> anyhow::Result<(ContextCapsule, CapsuleTrace)> {
         let intent = Intent::detect(query);
         let budget = max_tokens;
         let mut used_tokens: usize = 0;
@@ -184,25 +604,40 @@ impl<'a> ContextEngine<'a> {
         // Strip intent keywords ("fix", "refactor", etc.) so they don't pollute
         // the FTS5 match. The user is describing *what to do*, not *what to find*.
         let fts_query = strip_intent_keywords(query);
+        let (tuning, config) = self.repo_tuning(repo_id)?;
+        let max_pivots = tuning.pivots.unwrap_or(MAX_PIVOTS).max(1);
 
         // Apply recency bias for debug intent: recently-changed files are more
         // likely to contain the bug. Other intents get pure BM25 ranking.
         let recency_boost = match intent {
-            Intent::Debug => 0.5,
+            Intent::Debug => tuning.recency_boost.unwrap_or(DEFAULT_RECENCY_BOOST),
             _ => 0.0,
         };
-        let mut pivots = self
-            .db
-            .search_code_with_recency(&fts_query, "", repo_id, 5, recency_boost)?;
+        let foci: Vec<&Focus> = self.focus.iter().chain(&self.working_set).collect();
+        let candidates = if foci.is_empty() && self.excluded.is_empty() {
+            max_pivots as i64
+        } else {
+            FOCUS_CANDIDATES.max(max_pivots as i64)
+        };
+        let filter = SearchFilter {
+            package: &self.package,
+            include_generated: self.include_generated,
+            ..SearchFilter::repo(repo_id.as_ref())
+        };
+        let mut pivots = self.db.search_code_with_recency(&fts_query, &filter, candidates, recency_boost)?;
+        let fts_hits = pivots.len();
 
         // Fallback: if FTS returned < 3 results, try fuzzy name match.
         // FTS5 tokenizes on whitespace/punctuation and misses camelCase
         // symbol names or partial matches that LIKE can catch.
         if pivots.len() < 3 {
             let terms: Vec<&str> = fts_query.split_whitespace().collect();
-            if let Ok(fallback) = self.db.search_symbols_by_name_like(&terms, repo_id, 5) {
+            let fallback =
+                self.db
+                    .search_symbols_by_name_like(&terms, repo_id, &self.package, self.include_generated, 5);
+            if let Ok(fallback) = fallback {
                 for sym in fallback {
-                    if pivots.len() >= 5 {
+                    if pivots.len() >= candidates as usize {
                         break;
                     }
                     // Avoid duplicates — seen_ids isn't populated yet, check pivots directly
@@ -213,6 +648,33 @@ impl<'a> ContextEngine<'a> {
             }
         }
 
+        let mut candidate_trace: Vec<CandidateTrace> = pivots
+            .iter()
+            .enumerate()
+            .map(|(i, sym)| CandidateTrace {
+                symbol_id: sym.id,
+                name: sym.name.clone(),
+                kind: sym.kind.clone(),
+                file_path: String::new(),
+                source: if i < fts_hits { "fts" } else { "name_match" },
+                score: None,
+                decision: "irrelevant",
+            })
+            .collect();
+
+        let before = pivots.len();
+        pivots.retain(|p| !self.excluded.contains(&p.id));
+        let mut skipped_irrelevant = before - pivots.len();
+        let scored = Focus::score_all(self.db, &foci, pivots)?;
+        for (rank, (score, sym)) in scored.iter().enumerate() {
+            if let Some(c) = candidate_trace.iter_mut().find(|c| c.symbol_id == sym.id) {
+                c.score = Some(round3(*score));
+                c.decision = if rank < max_pivots { "over_budget" } else { "below_cutoff" };
+            }
+        }
+        let mut pivots: Vec<Symbol> = scored.into_iter().map(|(_, sym)| sym).collect();
+        pivots.truncate(max_pivots);
+
         for sym in &pivots {
             let file_path = self
                 .db
@@ -224,6 +686,9 @@ impl<'a> ContextEngine<'a> {
             if used_tokens + cost > budget {
                 break;
             }
+            if let Some(c) = candidate_trace.iter_mut().find(|c| c.symbol_id == sym.id) {
+                c.decision = "pivot";
+            }
 
             items.push(CapsuleItem {
                 symbol_id: sym.id,
@@ -242,6 +707,7 @@ impl<'a> ContextEngine<'a> {
                     "(full body sent earlier in session)".to_string()
                 },
                 is_pivot: true,
+                role: "pivot".to_string(),
                 token_estimate: cost,
                 start_line: sym.start_line,
                 end_line: sym.end_line,
@@ -251,8 +717,14 @@ impl<'a> ContextEngine<'a> {
         }
 
         // ----- Phase 2: Expand to adjacent symbols -----
-        // Collect adjacent symbols from graph edges, driven by intent.
-        let mut adjacent_symbols: Vec<(Symbol, String)> = Vec::new();
+        // Collect the neighbours the intent's template asks for and/or the
+        // pivots' siblings, per `self.expansion`.
+        let intent_name = format!("{:?}", intent).to_lowercase();
+        let template = match tuning.templates.get(&intent_name) {
+            Some(overrides) => CapsuleTemplate::for_intent(intent).with_overrides(overrides),
+            None => CapsuleTemplate::for_intent(intent),
+        };
+        let mut adjacent_symbols: Vec<(Symbol, String, &'static str, String)> = Vec::new();
 
         for pivot in &pivots {
             if !seen_ids.contains(&pivot.id) {
@@ -260,69 +732,53 @@ impl<'a> ContextEngine<'a> {
                 continue;
             }
 
-            match intent {
-                Intent::Debug => {
-                    // Callers (dependents) + dependencies
-                    if let Ok(dependents) = self.db.get_dependents(pivot.id) {
-                        for (_edge, sym) in dependents {
-                            if seen_ids.insert(sym.id) {
-                                let fp = self
-                                    .db
-                                    .get_file_path_for_symbol(sym.id)
-                                    .unwrap_or_else(|_| "<unknown>".to_string());
-                                adjacent_symbols.push((sym, fp));
-                            }
-                        }
-                    }
-                    if let Ok(deps) = self.db.get_dependencies(pivot.id) {
-                        for (_edge, sym) in deps {
-                            if seen_ids.insert(sym.id) {
-                                let fp = self
-                                    .db
-                                    .get_file_path_for_symbol(sym.id)
-                                    .unwrap_or_else(|_| "<unknown>".to_string());
-                                adjacent_symbols.push((sym, fp));
-                            }
-                        }
-                    }
-                }
-                Intent::Refactor => {
-                    // Blast radius: dependents only
-                    if let Ok(dependents) = self.db.get_dependents(pivot.id) {
-                        for (_edge, sym) in dependents {
-                            if seen_ids.insert(sym.id) {
-                                let fp = self
-                                    .db
-                                    .get_file_path_for_symbol(sym.id)
-                                    .unwrap_or_else(|_| "<unknown>".to_string());
-                                adjacent_symbols.push((sym, fp));
-                            }
-                        }
+            if self.expansion != Expansion::Siblings {
+                for (sym, role) in self.neighbours(&template, pivot, &tuning)? {
+                    if seen_ids.insert(sym.id) {
+                        let fp = self
+                            .db
+                            .get_file_path_for_symbol(sym.id)
+                            .unwrap_or_else(|_| "<unknown>".to_string());
+                        adjacent_symbols.push((sym, fp, role, pivot.name.clone()));
                     }
                 }
-                Intent::Modify | Intent::Explore => {
-                    // Dependencies only
-                    if let Ok(deps) = self.db.get_dependencies(pivot.id) {
-                        for (_edge, sym) in deps {
-                            if seen_ids.insert(sym.id) {
-                                let fp = self
-                                    .db
-                                    .get_file_path_for_symbol(sym.id)
-                                    .unwrap_or_else(|_| "<unknown>".to_string());
-                                adjacent_symbols.push((sym, fp));
-                            }
-                        }
+            }
+
+            if self.expansion != Expansion::Graph {
+                let file_path = items
+                    .iter()
+                    .find(|i| i.symbol_id == pivot.id)
+                    .map(|i| i.file_path.clone())
+                    .unwrap_or_default();
+                for sym in self.siblings(pivot)? {
+                    if seen_ids.insert(sym.id) {
+                        adjacent_symbols.push((sym, file_path.clone(), "sibling", pivot.name.clone()));
                     }
                 }
             }
         }
 
+        let pivot_tokens = used_tokens;
+        let mut expansion_trace: Vec<ExpansionTrace> = Vec::new();
+
         // Add adjacent symbols as skeletons (no body)
-        for (sym, file_path) in &adjacent_symbols {
+        let mut exhausted = false;
+        for (sym, file_path, role, from) in &adjacent_symbols {
+            let mut decide = |decision| {
+                expansion_trace.push(ExpansionTrace { from: from.clone(), name: sym.name.clone(), role, decision });
+            };
+            if self.excluded.contains(&sym.id) {
+                decide("irrelevant");
+                skipped_irrelevant += 1;
+                continue;
+            }
             let cost = item_token_cost(sym, file_path, false);
-            if used_tokens + cost > budget {
-                break;
+            if exhausted || used_tokens + cost > budget {
+                decide("over_budget");
+                exhausted = true;
+                continue;
             }
+            decide("added");
 
             items.push(CapsuleItem {
                 symbol_id: sym.id,
@@ -332,6 +788,7 @@ impl<'a> ContextEngine<'a> {
                 signature: sym.signature.clone(),
                 body: String::new(),
                 is_pivot: false,
+                role: role.to_string(),
                 token_estimate: cost,
                 start_line: sym.start_line,
                 end_line: sym.end_line,
@@ -339,36 +796,363 @@ impl<'a> ContextEngine<'a> {
             used_tokens += cost;
         }
 
-        // ----- Phase 3: Attach memories (up to 10% of budget) -----
-        let memory_budget = budget / 10;
-        let mut memory_tokens: usize = 0;
-        let mut memories: Vec<Memory> = Vec::new();
+        let neighbour_tokens = used_tokens - pivot_tokens;
+        let memory_fraction = self
+            .memory_budget_fraction
+            .or(tuning.memory_budget.map(clamp_fraction))
+            .unwrap_or(DEFAULT_MEMORY_BUDGET_FRACTION);
+        let memory_budget = (budget as f64 * memory_fraction) as usize;
+        let code_budget = budget.saturating_sub(memory_budget);
+        let included_pivots: Vec<&Symbol> = pivots.iter().filter(|p| items.iter().any(|i| i.symbol_id == p.id)).collect();
 
-        for pivot in &pivots {
-            if memory_tokens >= memory_budget {
-                break;
+        // ----- Template extras: caller chains and recent changes -----
+        let before_extras = used_tokens;
+        let mut call_chains: Vec<String> = Vec::new();
+        if template.caller_chains {
+            for pivot in &included_pivots {
+                for chain in self.caller_chains(pivot)? {
+                    let cost = estimate_tokens(&chain) + 2;
+                    if used_tokens + cost > code_budget {
+                        break;
+                    }
+                    used_tokens += cost;
+                    call_chains.push(chain);
+                }
             }
-            let mems = self
-                .db
-                .get_memories_for_symbol(pivot.id, false)
-                .unwrap_or_default();
-            for mem in mems {
-                let cost = estimate_tokens(&mem.content);
-                if memory_tokens + cost > memory_budget {
+        }
+        let mut recent_changes: Vec<RecentChange> = Vec::new();
+        if template.recent_changes {
+            let mut files: Vec<RecentChange> = Vec::new();
+            let mut seen_files: HashSet<&str> = HashSet::new();
+            for item in &items {
+                if !seen_files.insert(&item.file_path) {
+                    continue;
+                }
+                let Some(indexed_at) = self.db.file_indexed_at_for_symbol(item.symbol_id)? else {
+                    continue;
+                };
+                files.push(RecentChange { file_path: item.file_path.clone(), indexed_at });
+            }
+            files.sort_by(|a, b| b.indexed_at.cmp(&a.indexed_at).then_with(|| a.file_path.cmp(&b.file_path)));
+            for change in files.into_iter().take(MAX_RECENT_CHANGES) {
+                let cost = estimate_tokens(&change.file_path) + 8;
+                if used_tokens + cost > code_budget {
                     break;
                 }
-                memory_tokens += cost;
-                memories.push(mem);
+                used_tokens += cost;
+                recent_changes.push(change);
             }
         }
+
+        let extra_tokens = used_tokens - before_extras;
+
+        // ----- File skeletons of the pivots, budget permitting -----
+        let mut file_skeletons: Vec<FileSkeleton> = Vec::new();
+        if self.file_skeletons {
+            let pivot_files: Vec<(i64, String)> = included_pivots
+                .iter()
+                .filter_map(|p| items.iter().find(|i| i.symbol_id == p.id).map(|i| (p.file_id, i.file_path.clone())))
+                .collect();
+            let mut seen_files: HashSet<i64> = HashSet::new();
+            for (file_id, file_path) in pivot_files {
+                if !seen_files.insert(file_id) {
+                    continue;
+                }
+                let symbols = self.db.get_skeleton(file_id, "standard")?;
+                // Nothing to add when the capsule already has every symbol of the file.
+                if symbols.len() <= items.iter().filter(|i| i.file_path == file_path).count() {
+                    continue;
+                }
+                let cost = skeleton_token_cost(&file_path, &symbols);
+                if used_tokens + cost > code_budget {
+                    continue;
+                }
+                used_tokens += cost;
+                file_skeletons.push(FileSkeleton { file_path, symbols, token_estimate: cost });
+            }
+        }
+
+        // ----- Phase 3: Attach memories (up to the memory budget), best first -----
+        let mut memory_tokens: usize = 0;
+        let mut memories: Vec<Memory> = Vec::new();
+        let mut memory_refs: Vec<MemoryRef> = Vec::new();
+
+        for mem in self.rank_memories(&fts_query, &pivots) {
+            let cost = estimate_tokens(&mem.content);
+            if memory_tokens + cost > memory_budget {
+                // A shorter, lower-scored memory may still fit; this one is
+                // surfaced as a reference so the agent knows it exists, as
+                // long as the reference itself fits.
+                let preview = memory_preview(&mem.content);
+                let ref_cost = estimate_tokens(&preview) + 4;
+                if memory_tokens + ref_cost <= memory_budget {
+                    memory_tokens += ref_cost;
+                    memory_refs.push(MemoryRef { id: mem.id, preview });
+                }
+                continue;
+            }
+            memory_tokens += cost;
+            memories.push(mem);
+        }
         used_tokens += memory_tokens;
 
-        Ok(ContextCapsule {
-            intent: format!("{:?}", intent).to_lowercase(),
+        let trace = CapsuleTrace {
+            intent: intent_name.clone(),
+            config,
+            fts_query,
+            recency_boost,
+            expansion: self.expansion,
+            template,
+            candidates: candidate_trace,
+            expansions: expansion_trace,
+            budget: BudgetTrace {
+                budget,
+                memory_budget,
+                pivots: pivot_tokens,
+                neighbours: neighbour_tokens,
+                extras: extra_tokens,
+                file_skeletons: file_skeletons.iter().map(|f| f.token_estimate).sum(),
+                memories: memory_tokens,
+                total: used_tokens,
+            },
+        };
+        let capsule = ContextCapsule {
+            intent: intent_name,
             items,
             memories,
+            memory_refs,
+            call_chains,
+            recent_changes,
+            file_skeletons,
+            skipped_irrelevant,
             total_tokens: used_tokens,
             budget,
+        };
+        Ok((capsule, trace))
+    }
+
+    /// The neighbours `template` asks for around `pivot`, tagged with their
+    /// role, in the order they should be added.
+    fn neighbours(
+        &self,
+        template: &CapsuleTemplate,
+        pivot: &Symbol,
+        tuning: &ContextTuning,
+    ) -> anyhow::Result<Vec<(Symbol, &'static str)>> {
+        let depth = tuning.expansion_depth.unwrap_or(1).clamp(1, MAX_EXPANSION_DEPTH);
+        let mut out = Vec::new();
+        if template.dependents {
+            out.extend(self.graph_walk(pivot, depth, true, &tuning.builtins)?.into_iter().map(|s| (s, "dependent")));
+        }
+        if template.dependencies {
+            out.extend(self.graph_walk(pivot, depth, false, &tuning.builtins)?.into_iter().map(|s| (s, "dependency")));
+        }
+        if template.tests {
+            out.extend(self.db.test_symbols_for(pivot.id)?.into_iter().map(|s| (s, "test")));
+        }
+        if template.public_api {
+            let public = self
+                .db
+                .get_symbols_by_file(pivot.file_id)?
+                .into_iter()
+                .filter(|s| s.id != pivot.id && s.visibility == "public")
+                .take(PUBLIC_API_PER_PIVOT);
+            out.extend(public.map(|s| (s, "public_api")));
+        }
+        out.retain(|(s, _)| !tuning.builtins.contains(&s.name));
+        Ok(out)
+    }
+
+    /// Symbols up to `depth` hops from `pivot` along callers (`dependents`)
+    /// or callees, nearest first. Denylisted names are neither returned nor
+    /// walked through.
+    fn graph_walk(&self, pivot: &Symbol, depth: usize, dependents: bool, builtins: &[String]) -> anyhow::Result<Vec<Symbol>> {
+        let mut seen = HashSet::from([pivot.id]);
+        let mut frontier = vec![pivot.id];
+        let mut out = Vec::new();
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for id in frontier {
+                let hop = if dependents { self.db.get_dependents(id)? } else { self.db.get_dependencies(id)? };
+                for (_, sym) in hop {
+                    if !builtins.contains(&sym.name) && seen.insert(sym.id) {
+                        next.push(sym.id);
+                        out.push(sym);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        Ok(out)
+    }
+
+    /// `[context]` from the `.focal.toml` of the repository a capsule is
+    /// scoped to — or, unscoped, of the only indexed checkout — and the
+    /// file's path when there is one.
+    fn repo_tuning(&self, repo_id: Option<i64>) -> anyhow::Result<(ContextTuning, Option<String>)> {
+        let repos = self.db.list_repositories()?;
+        // Manifest and external pseudo-repositories have no root on disk.
+        let checkouts: Vec<_> = repos.iter().filter(|r| crate::paths::is_absolute(&r.root_path)).collect();
+        let repo = match repo_id {
+            Some(id) => checkouts.iter().find(|r| r.id == id),
+            None if checkouts.len() == 1 => checkouts.first(),
+            None => None,
+        };
+        let Some(root) = repo.map(|r| Path::new(&r.root_path)) else {
+            return Ok((ContextTuning::default(), None));
+        };
+        Ok(match RepoConfig::load_cached(root) {
+            Some(config) => (config.context, Some(root.join(REPO_CONFIG_FILE).display().to_string())),
+            None => (ContextTuning::default(), None),
         })
     }
+
+    /// Caller chains ending at `pivot`, like the frames of a stack trace:
+    /// `main → serve → handle_request`. Each follows callers up to
+    /// `CALLER_CHAIN_DEPTH` hops, stopping early at a symbol nothing calls.
+    fn caller_chains(&self, pivot: &Symbol) -> anyhow::Result<Vec<String>> {
+        let mut chains = Vec::new();
+        let mut stack: Vec<Vec<Symbol>> = vec![vec![pivot.clone()]];
+        while let Some(path) = stack.pop() {
+            if chains.len() >= CALLER_CHAINS_PER_PIVOT {
+                break;
+            }
+            let top = path.last().expect("chains start at the pivot");
+            let callers: Vec<Symbol> = if path.len() > CALLER_CHAIN_DEPTH {
+                Vec::new()
+            } else {
+                self.db
+                    .get_dependents(top.id)?
+                    .into_iter()
+                    .map(|(_, s)| s)
+                    .filter(|s| !path.iter().any(|p| p.id == s.id) && !self.excluded.contains(&s.id))
+                    .collect()
+            };
+            if callers.is_empty() {
+                if path.len() > 1 {
+                    let names: Vec<&str> = path.iter().rev().map(|s| s.name.as_str()).collect();
+                    chains.push(names.join(" → "));
+                }
+                continue;
+            }
+            // Reversed so the first caller is explored first.
+            for caller in callers.into_iter().rev() {
+                let mut next = path.clone();
+                next.push(caller);
+                stack.push(next);
+            }
+        }
+        Ok(chains)
+    }
+
+    /// Symbols with the same parent as `pivot` (its file's top level when it
+    /// has none), nearest by line first.
+    fn siblings(&self, pivot: &Symbol) -> anyhow::Result<Vec<Symbol>> {
+        let mut siblings: Vec<Symbol> = self
+            .db
+            .get_symbols_by_file(pivot.file_id)?
+            .into_iter()
+            .filter(|s| s.id != pivot.id && s.parent_id == pivot.parent_id)
+            .collect();
+        siblings.sort_by_key(|s| (s.start_line - pivot.start_line).abs());
+        siblings.truncate(SIBLINGS_PER_PIVOT);
+        Ok(siblings)
+    }
+
+    /// Gather candidate memories for a capsule — those linked to any pivot or
+    /// to a file/directory containing one, FTS matches against the query, and
+    /// pinned memories — and order them by relevance. Superseded memories are
+    /// replaced by the newest memory in their supersession chain.
+    ///
+    /// Score = (pivot linkage + FTS rank + recency decay) × category weight,
+    /// plus a flat bonus for pinned memories. Stale memories are excluded.
+    /// Ties keep newer memories first so the ordering is deterministic.
+    fn rank_memories(&self, fts_query: &str, pivots: &[Symbol]) -> Vec<Memory> {
+        let mut candidates: HashMap<i64, (Memory, f64)> = HashMap::new();
+
+        for pivot in pivots {
+            for mem in self
+                .db
+                .get_memories_for_symbol(pivot.id, false)
+                .unwrap_or_default()
+            {
+                candidates
+                    .entry(mem.id)
+                    .or_insert((mem, MEMORY_LINKED_WEIGHT));
+            }
+        }
+
+        let mut pivot_paths: Vec<(i64, String)> = pivots
+            .iter()
+            .filter_map(|p| self.db.get_file_by_id(p.file_id).ok().flatten())
+            .map(|f| (f.repo_id, f.path))
+            .collect();
+        pivot_paths.sort();
+        pivot_paths.dedup();
+        for (repo_id, path) in &pivot_paths {
+            for mem in self
+                .db
+                .get_memories_for_path(Some(*repo_id), path, false)
+                .unwrap_or_default()
+            {
+                candidates
+                    .entry(mem.id)
+                    .or_insert((mem, MEMORY_LINKED_WEIGHT));
+            }
+        }
+
+        let matches = self.db.search_memories(fts_query, 20).unwrap_or_default();
+        let match_count = matches.len() as f64;
+        for (rank, mem) in matches.into_iter().enumerate() {
+            if mem.stale {
+                continue;
+            }
+            let fts_score = MEMORY_FTS_WEIGHT * (1.0 - rank as f64 / match_count);
+            candidates.entry(mem.id).or_insert((mem, 0.0)).1 += fts_score;
+        }
+
+        for mem in self.db.list_pinned_memories().unwrap_or_default() {
+            candidates.entry(mem.id).or_insert((mem, 0.0));
+        }
+
+        // Swap superseded candidates for the head of their chain, keeping the
+        // stronger of the two base scores.
+        let superseded: Vec<(i64, i64)> = candidates
+            .keys()
+            .filter_map(|&id| {
+                let latest = self.db.latest_in_supersession_chain(id).ok()?;
+                (latest != id).then_some((id, latest))
+            })
+            .collect();
+        for (old_id, latest_id) in superseded {
+            let Some((_, base)) = candidates.remove(&old_id) else {
+                continue;
+            };
+            if let Some(entry) = candidates.get_mut(&latest_id) {
+                entry.1 = entry.1.max(base);
+            } else if let Ok(Some(mem)) = self.db.get_memory_by_id(latest_id) {
+                if !mem.stale {
+                    candidates.insert(latest_id, (mem, base));
+                }
+            }
+        }
+
+        let ids: Vec<i64> = candidates.keys().copied().collect();
+        let ages = self.db.get_memory_ages_days(&ids).unwrap_or_default();
+
+        let mut scored: Vec<(Memory, f64)> = candidates
+            .into_values()
+            .map(|(mem, base)| {
+                let recency = recency_decay(ages.get(&mem.id).copied().unwrap_or(0.0));
+                let mut score = (base + recency) * category_weight(&mem.category);
+                if mem.pinned {
+                    score += MEMORY_PINNED_WEIGHT;
+                }
+                (mem, score)
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.id.cmp(&a.0.id)));
+        scored.into_iter().map(|(mem, _)| mem).collect()
+    }
 }
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+
+// ---------------------------------------------------------------------------
+// Module coupling
+// ---------------------------------------------------------------------------
+//
+// Robert C. Martin's package metrics, with a directory as the module and the
+// symbol edge graph as the dependency relation:
+//
+// - afferent coupling (Ca): symbols outside the directory that depend on a
+//   symbol inside it;
+// - efferent coupling (Ce): symbols outside the directory that a symbol
+//   inside it depends on;
+// - instability I = Ce / (Ca + Ce): 0 for a directory everything leans on,
+//   1 for one that only leans on others;
+// - cohesion: the share of the directory's outgoing edges that stay inside.
+//
+// Only edges between symbols of the same repository count, so calls into
+// external stubs and project siblings don't skew the numbers. The metrics
+// are computed at the end of every full index run and stored in
+// `module_metrics`; incremental re-indexes leave them as they are.
+
+/// Metrics of the files directly in one directory (`.` for the root).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleMetrics {
+    pub module: String,
+    pub files: usize,
+    pub symbols: usize,
+    pub afferent: usize,
+    pub efferent: usize,
+    /// None when the directory has no edges in or out.
+    pub instability: Option<f64>,
+    pub internal_edges: usize,
+    /// None when the directory's symbols depend on nothing.
+    pub cohesion: Option<f64>,
+}
+
+/// Directory of a repo-relative path.
+pub fn module_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or(".", |(dir, _)| dir)
+}
+
+#[derive(Default)]
+struct Tally {
+    files: usize,
+    symbols: usize,
+    afferent: HashSet<i64>,
+    efferent: HashSet<i64>,
+    internal_edges: usize,
+    outgoing_edges: usize,
+}
+
+/// Metrics for every directory of `repo_id` holding an indexed file, by
+/// module path.
+pub fn compute(db: &Database, repo_id: i64) -> Result<Vec<ModuleMetrics>> {
+    let mut tallies: HashMap<String, Tally> = HashMap::new();
+    for (path, symbols) in db.file_symbol_counts(repo_id)? {
+        let tally = tallies.entry(module_of(&path).to_string()).or_default();
+        tally.files += 1;
+        tally.symbols += symbols;
+    }
+    for (source_id, source_path, target_id, target_path) in db.repo_internal_edges(repo_id)? {
+        let (source, target) = (module_of(&source_path), module_of(&target_path));
+        if source == target {
+            let tally = tallies.entry(source.to_string()).or_default();
+            tally.internal_edges += 1;
+            tally.outgoing_edges += 1;
+            continue;
+        }
+        let tally = tallies.entry(source.to_string()).or_default();
+        tally.efferent.insert(target_id);
+        tally.outgoing_edges += 1;
+        tallies.entry(target.to_string()).or_default().afferent.insert(source_id);
+    }
+
+    let mut metrics: Vec<ModuleMetrics> = tallies
+        .into_iter()
+        .map(|(module, t)| {
+            let (afferent, efferent) = (t.afferent.len(), t.efferent.len());
+            ModuleMetrics {
+                module,
+                files: t.files,
+                symbols: t.symbols,
+                afferent,
+                efferent,
+                instability: ratio(efferent, afferent + efferent),
+                internal_edges: t.internal_edges,
+                cohesion: ratio(t.internal_edges, t.outgoing_edges),
+            }
+        })
+        .collect();
+    metrics.sort_by(|a, b| a.module.cmp(&b.module));
+    Ok(metrics)
+}
+
+/// `part / whole` rounded to two decimals; None for an empty whole.
+fn ratio(part: usize, whole: usize) -> Option<f64> {
+    (whole > 0).then(|| (part as f64 / whole as f64 * 100.0).round() / 100.0)
+}
+
+/// Recompute and store `repo_id`'s metrics.
+pub fn refresh(db: &Database, repo_id: i64) -> Result<Vec<ModuleMetrics>> {
+    let metrics = compute(db, repo_id)?;
+    db.replace_module_metrics(repo_id, &metrics)?;
+    Ok(metrics)
+}
+
+/// Order a report: `instability`, `afferent`, `efferent` (highest first),
+/// `cohesion` (lowest first), or anything else by module path.
+pub fn sort_metrics(metrics: &mut [ModuleMetrics], by: &str) {
+    let key = |m: &ModuleMetrics| -> f64 {
+        match by {
+            "instability" => -m.instability.unwrap_or(-1.0),
+            "afferent" => -(m.afferent as f64),
+            "efferent" => -(m.efferent as f64),
+            "cohesion" => m.cohesion.unwrap_or(2.0),
+            _ => 0.0,
+        }
+    };
+    metrics.sort_by(|a, b| key(a).total_cmp(&key(b)).then_with(|| a.module.cmp(&b.module)));
+}
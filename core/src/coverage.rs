@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::db::{Database, Repository};
+
+// ---------------------------------------------------------------------------
+// Report parsing
+// ---------------------------------------------------------------------------
+
+/// Line hit counts per source file, as named in the report.
+pub type LineHits = BTreeMap<String, HashMap<usize, u64>>;
+
+/// Parse an lcov tracefile (`SF:`/`DA:` records) or a Cobertura XML report.
+/// `format` is `"lcov"`, `"cobertura"`, or empty to detect from the content.
+pub fn parse_report(text: &str, format: &str) -> Result<LineHits> {
+    let format = match format {
+        "" if text.trim_start().starts_with('<') => "cobertura",
+        "" => "lcov",
+        f => f,
+    };
+    match format {
+        "lcov" => Ok(parse_lcov(text)),
+        "cobertura" => Ok(parse_cobertura(text)),
+        other => bail!("unknown coverage format '{other}' (expected lcov or cobertura)"),
+    }
+}
+
+fn parse_lcov(text: &str) -> LineHits {
+    let mut files = LineHits::new();
+    let mut current: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(path) = line.strip_prefix("SF:") {
+            current = Some(path.to_string());
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            let mut parts = data.split(',');
+            let (Some(file), Some(Ok(line_no)), Some(Ok(hits))) = (
+                current.as_ref(),
+                parts.next().map(str::parse::<usize>),
+                parts.next().map(str::parse::<u64>),
+            ) else {
+                continue;
+            };
+            let lines = files.entry(file.clone()).or_default();
+            let entry = lines.entry(line_no).or_default();
+            *entry = (*entry).max(hits);
+        } else if line == "end_of_record" {
+            current = None;
+        }
+    }
+    files
+}
+
+/// Minimal Cobertura reader: `<class filename="...">` opens a file and each
+/// `<line number="N" hits="H"/>` inside it records a hit count. Method-level
+/// `<lines>` repeat class-level ones; the highest count wins.
+fn parse_cobertura(text: &str) -> LineHits {
+    let mut files = LineHits::new();
+    let mut current: Option<String> = None;
+    for tag in text.split('<').skip(1) {
+        let tag = tag.split('>').next().unwrap_or_default();
+        if let Some(attrs) = tag.strip_prefix("class ") {
+            current = xml_attr(attrs, "filename");
+        } else if let Some(attrs) = tag.strip_prefix("line ") {
+            let (Some(file), Some(Ok(line_no)), Some(Ok(hits))) = (
+                current.as_ref(),
+                xml_attr(attrs, "number").map(|n| n.parse::<usize>()),
+                xml_attr(attrs, "hits").map(|h| h.parse::<u64>()),
+            ) else {
+                continue;
+            };
+            let lines = files.entry(file.clone()).or_default();
+            let entry = lines.entry(line_no).or_default();
+            *entry = (*entry).max(hits);
+        } else if tag.starts_with("/class") {
+            current = None;
+        }
+    }
+    files
+}
+
+fn xml_attr(attrs: &str, name: &str) -> Option<String> {
+    let start = attrs.find(&format!("{name}=\""))? + name.len() + 2;
+    let len = attrs[start..].find('"')?;
+    Some(attrs[start..start + len].to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Import
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageImport {
+    pub repo: String,
+    pub files_matched: usize,
+    pub files_unmatched: usize,
+    pub symbols_covered: usize,
+    pub symbols_uncovered: usize,
+    /// Report paths that matched no indexed file (first 20).
+    pub unmatched_files: Vec<String>,
+}
+
+/// Map report line hits onto the symbols of `repo`, replacing any coverage
+/// imported earlier. A symbol gets coverage when at least one instrumented
+/// line falls inside its line range; coverage is dropped with the symbol when
+/// its file is re-indexed. A report matching none of the repository's files
+/// leaves its existing coverage untouched.
+pub fn import_coverage(db: &Database, repo: &Repository, hits: &LineHits) -> Result<CoverageImport> {
+    let indexed: Vec<_> = db.get_files_for_repo(repo.id)?;
+    let paths: Vec<&str> = indexed.iter().map(|f| f.path.as_str()).collect();
+
+    let mut result = CoverageImport {
+        repo: repo.name.clone(),
+        files_matched: 0,
+        files_unmatched: 0,
+        symbols_covered: 0,
+        symbols_uncovered: 0,
+        unmatched_files: Vec::new(),
+    };
+    let mut matched = Vec::new();
+    for (report_path, lines) in hits {
+        match match_indexed_path(report_path, &repo.root_path, &paths) {
+            Some(i) => matched.push((&indexed[i], lines)),
+            None => {
+                result.files_unmatched += 1;
+                if result.unmatched_files.len() < 20 {
+                    result.unmatched_files.push(report_path.clone());
+                }
+            }
+        }
+    }
+    result.files_matched = matched.len();
+    if matched.is_empty() {
+        return Ok(result);
+    }
+
+    db.with_transaction(|| {
+        db.clear_coverage_for_repo(repo.id)?;
+        for (file, lines) in &matched {
+            for sym in db.get_symbols_by_file(file.id)? {
+                let (mut total, mut covered) = (0, 0);
+                for line in sym.start_line.max(0) as usize..=sym.end_line.max(0) as usize {
+                    if let Some(&h) = lines.get(&line) {
+                        total += 1;
+                        covered += i64::from(h > 0);
+                    }
+                }
+                if total == 0 {
+                    continue;
+                }
+                db.set_symbol_coverage(sym.id, total, covered)?;
+                if covered > 0 {
+                    result.symbols_covered += 1;
+                } else {
+                    result.symbols_uncovered += 1;
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+/// Position in `indexed` of the file a report path refers to. Absolute paths
+/// under the repo root are made relative; otherwise the longest indexed path
+/// the report path ends with wins (CI checkouts live elsewhere), then an
+/// indexed path ending with the report path when exactly one does (Cobertura
+/// paths relative to a source directory).
+fn match_indexed_path(report_path: &str, root: &str, indexed: &[&str]) -> Option<usize> {
+    let normalized = report_path.replace('\\', "/");
+    let root = root.replace('\\', "/");
+    let rel = normalized
+        .strip_prefix(&format!("{}/", root.trim_end_matches('/')))
+        .unwrap_or(&normalized)
+        .trim_start_matches("./");
+
+    if let Some(exact) = indexed.iter().position(|p| *p == rel) {
+        return Some(exact);
+    }
+    let suffix = (0..indexed.len())
+        .filter(|&i| rel.ends_with(&format!("/{}", indexed[i])))
+        .max_by_key(|&i| indexed[i].len());
+    if suffix.is_some() {
+        return suffix;
+    }
+    let mut containing = (0..indexed.len()).filter(|&i| indexed[i].ends_with(&format!("/{rel}")));
+    match (containing.next(), containing.next()) {
+        (Some(only), None) => Some(only),
+        _ => None,
+    }
+}
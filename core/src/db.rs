@@ -1,6 +1,22 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection, OptionalExtension};
-use serde::Serialize;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{AuditEntry, AuditFilter, AuditRecord};
+use crate::coupling::ModuleMetrics;
+use crate::deny::DenyList;
+use crate::dependencies::{external_qualified_name, Dependency, EXTERNAL_REPO, EXTERNAL_ROOT};
+use crate::env::EnvUsage;
+use crate::grammar::{ErrorSite, ExtractedRoute, TypeSignature};
+use crate::graph_cache::{DirtyRows, GraphCache, GraphCacheStats};
+use crate::memory::StaleReason;
+use crate::module_docs::{package_docs, ModuleDoc, PackageDoc};
+use crate::packages::{package_for_path, Package};
+use crate::paths;
+use crate::sql::SqlQuery;
 
 // ---------------------------------------------------------------------------
 // Data structs
@@ -12,6 +28,10 @@ pub struct Repository {
     pub name: String,
     pub root_path: String,
     pub indexed_at: Option<String>,
+    /// Commit SHA the index was built from; None for the working tree.
+    pub revision: Option<String>,
+    /// Git branch whose namespace is live in `files`/`symbols`.
+    pub branch: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +42,20 @@ pub struct FileRecord {
     pub language: String,
     pub hash: String,
     pub indexed_at: Option<String>,
+    /// Encoding the file was stored in before transcoding to UTF-8.
+    pub encoding: String,
+}
+
+/// A client-supplied file path resolved to an indexed file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedFile {
+    #[serde(skip)]
+    pub file_id: i64,
+    pub repo: String,
+    #[serde(skip)]
+    pub repo_root: String,
+    /// Canonical repo-relative path, forward slashes.
+    pub file_path: String,
 }
 
 #[derive(Debug, Clone)]
@@ -39,6 +73,121 @@ pub struct Symbol {
     pub parent_id: Option<i64>,
     pub source: String,
     pub manifest_repo: Option<String>,
+    /// Declared visibility: "public", "internal", "protected" or "private";
+    /// empty for symbols indexed before visibility was tracked.
+    pub visibility: String,
+    /// Space-separated declaration modifiers (e.g. "async static").
+    pub modifiers: String,
+    /// `TypeSignature` JSON for functions and methods.
+    pub signature_types: Option<String>,
+    /// Newline-separated attributes, decorators and directives.
+    pub attributes: String,
+}
+
+/// What the indexer learned about a file besides its symbols, stored with
+/// its record by `upsert_parsed_file`.
+#[derive(Debug)]
+pub struct FileFacts<'a> {
+    pub line_count: i64,
+    /// Indexed without symbol bodies (oversized or minified).
+    pub partial: bool,
+    /// Produced by a code generator.
+    pub generated: bool,
+    /// Original encoding, before transcoding to UTF-8.
+    pub encoding: &'a str,
+    /// Monorepo package; None keeps the stored one.
+    pub package: Option<&'a str>,
+}
+
+/// Grammar-level details of a symbol being inserted: declared visibility,
+/// modifiers (space-separated), its `TypeSignature` as JSON, and attributes
+/// (newline-separated).
+#[derive(Debug, Default)]
+pub struct SymbolDetails<'a> {
+    pub visibility: &'a str,
+    pub modifiers: &'a str,
+    pub signature_types: Option<&'a str>,
+    pub attributes: &'a str,
+}
+
+/// Which symbols `search_code` returns. Empty strings and a None `repo_ids`
+/// match everything; an empty `repo_ids` set (an empty project) matches
+/// nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchFilter<'a> {
+    pub kind: &'a str,
+    pub repo_ids: Option<&'a [i64]>,
+    pub visibility: &'a str,
+    /// Substring of the symbol's attributes.
+    pub attribute: &'a str,
+    /// Monorepo package of the symbol's file.
+    pub package: &'a str,
+    /// Keep symbols of generated files, which are left out otherwise.
+    pub include_generated: bool,
+}
+
+impl<'a> SearchFilter<'a> {
+    /// Everything in one repository, or in all of them for None.
+    pub fn repo(repo_id: Option<&'a i64>) -> Self {
+        Self { repo_ids: repo_id.map(std::slice::from_ref), ..Self::default() }
+    }
+
+    /// Append the filter's conditions to a query over `symbols s JOIN files
+    /// f`, numbering placeholders after those already in `params`.
+    fn push_conditions(&self, sql: &mut String, params: &mut Vec<Box<dyn rusqlite::types::ToSql>>) {
+        if !self.include_generated {
+            sql.push_str(" AND f.generated = 0");
+        }
+        if !self.kind.is_empty() {
+            params.push(Box::new(self.kind.to_string()));
+            sql.push_str(&format!(" AND s.kind = ?{}", params.len()));
+        }
+        if let Some(ids) = self.repo_ids {
+            let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("?{}", params.len() + i)).collect();
+            sql.push_str(&format!(" AND f.repo_id IN ({})", placeholders.join(", ")));
+            params.extend(ids.iter().map(|&id| Box::new(id) as Box<dyn rusqlite::types::ToSql>));
+        }
+        if !self.visibility.is_empty() {
+            params.push(Box::new(self.visibility.to_string()));
+            sql.push_str(&format!(" AND s.visibility = ?{}", params.len()));
+        }
+        if !self.attribute.is_empty() {
+            params.push(Box::new(format!("%{}%", like_escape(self.attribute))));
+            sql.push_str(&format!(" AND s.attributes LIKE ?{} ESCAPE '\\'", params.len()));
+        }
+        if !self.package.is_empty() {
+            params.push(Box::new(self.package.to_string()));
+            sql.push_str(&format!(" AND f.package = ?{}", params.len()));
+        }
+    }
+}
+
+impl Symbol {
+    /// Read a symbol from `row`, whose columns from `offset` on are `id,
+    /// file_id, name, kind, signature, body, body_hash, start_line,
+    /// end_line, parent_id, qualified_name, source, manifest_repo,
+    /// visibility, modifiers, signature_types, attributes`.
+    fn from_row(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<Symbol> {
+        Ok(Symbol {
+            id: row.get(offset)?,
+            file_id: row.get(offset + 1)?,
+            name: row.get(offset + 2)?,
+            kind: row.get(offset + 3)?,
+            signature: row.get(offset + 4)?,
+            body: row.get(offset + 5)?,
+            body_hash: row.get(offset + 6)?,
+            start_line: row.get(offset + 7)?,
+            end_line: row.get(offset + 8)?,
+            parent_id: row.get(offset + 9)?,
+            qualified_name: row.get(offset + 10)?,
+            source: row.get(offset + 11)?,
+            manifest_repo: row.get(offset + 12)?,
+            visibility: row.get(offset + 13)?,
+            modifiers: row.get(offset + 14)?,
+            signature_types: row.get(offset + 15)?,
+            attributes: row.get(offset + 16)?,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -47,6 +196,10 @@ pub struct Edge {
     pub source_id: i64,
     pub target_id: i64,
     pub kind: String,
+    /// Distinct argument lists the source passes at its call sites (one
+    /// argument kind per entry, see `ExtractedReference::args`). Empty when
+    /// unknown or for non-call edges.
+    pub call_sites: Vec<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -61,8 +214,27 @@ pub struct Memory {
     /// Set when a linked symbol's body changed but its name still matches.
     /// The memory may still be valid but should be verified against the new code.
     pub needs_review: bool,
+    /// Template fields for structured memory kinds (see `memory::MemoryKind`).
+    /// `None` for free-text memories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured: Option<serde_json::Value>,
+    /// Pinned memories always rank first when capsules pick which notes to attach.
+    pub pinned: bool,
+    /// Why the memory became stale (see `memory::StaleReason`); None while fresh.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_reason: Option<String>,
+    /// When it became stale.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_at: Option<String>,
 }
 
+/// Serialized field names of `SymbolResult`, for `include` / `exclude` masks.
+pub const SYMBOL_RESULT_FIELDS: &[&str] = &[
+    "id", "name", "kind", "signature", "body", "file_path", "repo_name", "start_line", "end_line", "memories",
+    "dependency_hints", "source", "manifest_repo", "visibility", "modifiers", "types", "attributes", "owners",
+    "coverage", "partial", "package", "generated",
+];
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SymbolResult {
     pub id: i64,
@@ -80,6 +252,43 @@ pub struct SymbolResult {
     pub dependency_hints: Vec<String>,
     pub source: String,
     pub manifest_repo: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub visibility: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<String>,
+    /// Parameter and return types for functions and methods
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<TypeSignature>,
+    /// Attributes, decorators and directives (`#[derive(Debug)]`, `@app.get("/")`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+    /// CODEOWNERS owners of the symbol's file (filled at the presentation layer)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// Line coverage from the last imported report (filled at the presentation layer)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<SymbolCoverage>,
+    /// The file exceeded the size limit: name, signature and line range were
+    /// indexed but the body was not stored (filled at the presentation layer)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// Monorepo package of the symbol's file (filled at the presentation layer)
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub package: String,
+    /// The symbol's file was produced by a code generator (filled at the
+    /// presentation layer)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub generated: bool,
+}
+
+/// Test coverage of one symbol's instrumented lines.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SymbolCoverage {
+    /// At least one instrumented line was executed.
+    pub covered: bool,
+    pub percent: f64,
+    pub lines_covered: i64,
+    pub lines_total: i64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -99,7 +308,40 @@ pub struct HealthReport {
     pub edge_count: i64,
     pub memory_count: i64,
     pub repo_count: i64,
+    /// `symbols_fts` and `memories_fts` index exactly the rows of their
+    /// content tables and pass FTS5's structural integrity check (see
+    /// `fts_indexes_ok`).
     pub fts_ok: bool,
+    /// Files with a recorded indexing problem (see `get_index_errors`).
+    pub index_error_count: i64,
+    /// Secrets masked in stored symbols, over all files.
+    pub redaction_count: i64,
+    /// Size of the `-wal` file; absent for in-memory databases.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wal_size_bytes: Option<u64>,
+}
+
+/// What `check_fts_consistency` found and fixed in one FTS table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FtsRepair {
+    pub table: &'static str,
+    /// Content rows the index was missing, now indexed.
+    pub inserted: usize,
+    /// Index entries whose content row is gone, now deleted.
+    pub removed: usize,
+    /// The index was still inconsistent after the incremental repair and
+    /// was rebuilt from the content table.
+    pub rebuilt: bool,
+}
+
+/// Result of `PRAGMA wal_checkpoint(TRUNCATE)`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct WalCheckpoint {
+    /// Another connection held a lock, so the WAL could not be fully reset.
+    pub busy: bool,
+    /// WAL size before and after; both 0 for in-memory databases.
+    pub bytes_before: u64,
+    pub bytes_after: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -115,42 +357,539 @@ pub struct SessionRecoveryData {
 pub struct RepoOverview {
     pub name: String,
     pub root_path: String,
+    /// Commit SHA the index was built from; absent for the working tree.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// Checked-out branch whose namespace is live.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
     pub file_count: i64,
+    /// Source lines across indexed files.
+    pub line_count: i64,
     pub symbol_count: i64,
+    /// Mean and largest symbol length in lines.
+    pub avg_symbol_lines: f64,
+    pub max_symbol_lines: i64,
     pub memory_count: i64,
+    /// Files and symbols per language, most files first.
+    pub languages: Vec<LanguageCount>,
+    /// Symbols per kind, most numerous first.
+    pub symbol_kinds: Vec<KindCount>,
+    /// Edges leaving this repository's symbols, per kind.
+    pub edge_kinds: Vec<KindCount>,
+    /// When the repository was last indexed, and how many seconds ago.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_age_secs: Option<i64>,
+    /// Configured projects (`[projects]`) this repository belongs to.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub projects: Vec<String>,
+    /// README and doc-comment paragraph per package; filled on request
+    /// (`get_package_docs`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<PackageDoc>,
+}
+
+/// A configured project: its member repositories' overviews and totals.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectOverview {
+    pub name: String,
+    pub file_count: i64,
+    pub line_count: i64,
+    pub symbol_count: i64,
+    /// Files and symbols per language across every member.
     pub languages: Vec<LanguageCount>,
+    /// Symbols per kind across every member, most numerous first.
+    pub symbol_kinds: Vec<KindCount>,
+    /// Graph edges from a symbol in one member repository to one in another.
+    pub cross_repo_edges: i64,
+    pub repos: Vec<RepoOverview>,
+    /// Configured members that are not indexed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_repos: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSize {
+    pub path: String,
+    pub language: String,
+    pub line_count: i64,
+    pub symbol_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolSize {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: i64,
+    pub lines: i64,
+}
+
+/// A symbol many others depend on.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hotspot {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: i64,
+    /// Distinct symbols with an edge to this one.
+    pub dependents: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KindSize {
+    pub kind: String,
+    pub count: i64,
+    pub total_lines: i64,
+    pub avg_lines: f64,
+}
+
+/// Size statistics for one repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeReport {
+    pub repo: String,
+    pub file_count: i64,
+    pub line_count: i64,
+    pub symbol_count: i64,
+    pub avg_symbol_lines: f64,
+    pub max_symbol_lines: i64,
+    /// Per symbol kind, most numerous first.
+    pub kinds: Vec<KindSize>,
+    pub largest_files: Vec<FileSize>,
+    pub largest_symbols: Vec<SymbolSize>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct LanguageCount {
     pub language: String,
+    /// Files in this language.
+    pub count: i64,
+    pub symbols: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KindCount {
+    pub kind: String,
     pub count: i64,
 }
 
+/// Hours a deleted file's tombstone is kept before it is purged.
+pub const DEFAULT_TOMBSTONE_GRACE_HOURS: u64 = 24;
+
+/// Minutes between WAL checkpoints while the server runs.
+pub const DEFAULT_WAL_CHECKPOINT_MINUTES: u64 = 10;
+
+/// Extractor changes that leave already-indexed files stale, oldest first:
+/// the language whose files are re-parsed, or None for all. Append only;
+/// `PRAGMA user_version` counts the entries a database has applied.
+const REPARSE_MIGRATIONS: &[Option<&str>] = &[
+    // Dotted Python and TypeScript qualified names; calls attributed by them.
+    Some("py"),
+    Some("ts"),
+    // Go methods named after their receiver type (`Server.Start`).
+    Some("go"),
+    // Rust impl methods named after the bare implementing type
+    // (`Wrapper::get` for `impl<T> Wrapper<T>`, `Config::fmt` for a trait impl).
+    Some("rs"),
+];
+
+/// `graph_keys(id, repo_id, key)`: the graph snapshot key of every symbol
+/// in `?1`'s files and the files its edges point into. A key is
+/// `path::qualified_name`, with `@start_line` appended when the name repeats
+/// in its file (a Python property and its setter), so such symbols stay
+/// apart while the keys of everything else survive lines shifting.
+const GRAPH_KEYS: &str = "graph_keys AS (
+    SELECT id, repo_id,
+           path || '::' || q || CASE WHEN COUNT(*) OVER (PARTITION BY file_id, q) > 1
+                                     THEN '@' || start_line ELSE '' END AS key
+    FROM (SELECT s.id, s.file_id, s.start_line, f.repo_id, f.path,
+                 COALESCE(NULLIF(s.qualified_name, ''), s.name) AS q
+          FROM symbols s JOIN files f ON f.id = s.file_id
+          WHERE f.repo_id = ?1 OR f.id IN (
+              SELECT t.file_id FROM edges e
+              JOIN symbols t ON t.id = e.target_id
+              JOIN symbols src ON src.id = e.source_id
+              JOIN files sf ON sf.id = src.file_id
+              WHERE sf.repo_id = ?1)))";
+
+/// Distinct argument lists kept per call edge
+/// (`Database::insert_edge_with_call_sites`).
+pub const MAX_CALL_SITES: usize = 16;
+
+/// A `graph_snapshots` row (see `graph_snapshot::record`). `graph` is None
+/// once the snapshot is older than the ones kept in full.
+#[derive(Debug, Clone)]
+pub struct StoredGraphSnapshot {
+    pub id: i64,
+    pub taken_at: String,
+    pub symbol_count: usize,
+    pub edge_count: usize,
+    pub graph_hash: String,
+    pub graph: Option<String>,
+}
+
+/// A deleted file held for a grace period (see `Database::tombstone_file`).
+#[derive(Debug, Clone)]
+pub struct Tombstone {
+    pub hash: String,
+    pub snapshot: ParkedFile,
+    /// Symbol ids in snapshot order, reused when the file comes back.
+    pub symbol_ids: Vec<i64>,
+    pub incoming_edges: Vec<TombstoneEdge>,
+    pub deleted_at: String,
+}
+
+/// An edge another file had into a tombstoned or re-indexed one. Both ends
+/// are kept by file and qualified name rather than id: the source file may
+/// be re-indexed while the tombstone lasts, which renumbers its symbols.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TombstoneEdge {
+    pub source_repo_id: i64,
+    pub source_path: String,
+    /// Qualified name of the source symbol, or its name when it has none.
+    pub source: String,
+    /// Qualified name of the target symbol in the tombstoned file, or its name.
+    pub target: String,
+    pub kind: String,
+    #[serde(default)]
+    pub call_sites: Option<String>,
+}
+
+/// A file's symbols and outgoing edges as they were on another branch,
+/// stored so switching back restores them without re-parsing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParkedFile {
+    pub language: String,
+    pub hash: String,
+    pub symbols: Vec<ParkedSymbol>,
+    /// (source symbol name, target symbol name, kind)
+    pub edges: Vec<(String, String, String)>,
+    /// (memory id, symbol name, body hash) — as from `collect_memory_symbol_names`
+    pub memory_links: Vec<(i64, String, String)>,
+    #[serde(default)]
+    pub routes: Vec<ExtractedRoute>,
+    #[serde(default)]
+    pub env_usages: Vec<EnvUsage>,
+    #[serde(default)]
+    pub sql_queries: Vec<SqlQuery>,
+    #[serde(default)]
+    pub error_sites: Vec<ErrorSite>,
+    #[serde(default)]
+    pub line_count: i64,
+    #[serde(default)]
+    pub partial: bool,
+    #[serde(default)]
+    pub generated: bool,
+    #[serde(default)]
+    pub encoding: String,
+    #[serde(default)]
+    pub redactions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParkedSymbol {
+    pub name: String,
+    pub qualified_name: String,
+    pub kind: String,
+    pub signature: String,
+    pub body: String,
+    pub body_hash: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    #[serde(default)]
+    pub visibility: String,
+    #[serde(default)]
+    pub modifiers: String,
+    #[serde(default)]
+    pub signature_types: Option<String>,
+    #[serde(default)]
+    pub attributes: String,
+    /// Index of the parent within `ParkedFile::symbols`.
+    pub parent: Option<usize>,
+}
+
+/// An HTTP route registration joined to the symbol that handles it.
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteEntry {
+    pub method: String,
+    pub path: String,
+    pub handler: String,
+    pub repo: String,
+    pub file_path: String,
+    pub line: i64,
+    /// Where the handler symbol is defined, when it resolved in the index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handler_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handler_line: Option<i64>,
+}
+
+/// A read of an environment variable, with the innermost symbol around it.
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvUsageEntry {
+    pub name: String,
+    pub repo: String,
+    pub file_path: String,
+    pub line: i64,
+    /// Absent for reads at module level.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// An embedded SQL statement that touches a given table.
+#[derive(Debug, Clone, Serialize)]
+pub struct SqlQueryEntry {
+    pub operation: String,
+    pub tables: Vec<String>,
+    pub query: String,
+    pub repo: String,
+    pub file_path: String,
+    pub line: i64,
+    /// Absent for statements at module level.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// A discarded or panicking error, with the innermost symbol around it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorSiteEntry {
+    pub kind: String,
+    pub repo: String,
+    pub file_path: String,
+    pub line: i64,
+    pub snippet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// A symbol edge from one file into another, as lifted to file granularity
+/// by `GraphCache::file_dependencies`.
+#[derive(Debug, Clone)]
+pub struct FileEdge {
+    pub target_file_id: i64,
+    pub target_path: String,
+    pub source_symbol: String,
+    pub target_symbol: String,
+    pub kind: String,
+}
+
+/// A package with its share of the index, as reported by `list_packages`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageStats {
+    pub repo: String,
+    pub name: String,
+    pub path: String,
+    pub kind: String,
+    pub file_count: i64,
+    pub symbol_count: i64,
+    pub line_count: i64,
+    pub languages: Vec<String>,
+}
+
+/// A declared dependency and the files that import it, as reported by
+/// `list_dependencies`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyEntry {
+    pub repo: String,
+    pub name: String,
+    pub version: String,
+    pub kind: String,
+    pub manifest: String,
+    /// Repo-relative paths of files with an import matching the dependency.
+    pub imported_by: Vec<String>,
+}
+
+/// A recorded indexing problem for one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexErrorEntry {
+    pub repo: String,
+    pub path: String,
+    /// `read`, `parse`, `syntax`, `panic` or `edge_resolution`.
+    pub kind: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<i64>,
+    pub recorded_at: String,
+}
+
+/// A test linked to the symbol it exercises; `via` is "call", "name" or
+/// "call+name".
+#[derive(Debug, Clone, Serialize)]
+pub struct TestLinkEntry {
+    pub repo: String,
+    pub test: String,
+    pub test_file: String,
+    pub test_line: i64,
+    pub symbol: String,
+    pub symbol_kind: String,
+    pub symbol_file: String,
+    pub symbol_line: i64,
+    pub via: String,
+}
+
+fn round1(x: f64) -> f64 {
+    (x * 10.0).round() / 10.0
+}
+
+/// Decode a `signature_types` column; malformed JSON reads as absent.
+pub fn parse_signature_types(json: Option<String>) -> Option<TypeSignature> {
+    json.and_then(|j| serde_json::from_str(&j).ok())
+}
+
+/// Decode a `call_sites` column; NULL or malformed JSON reads as empty.
+pub fn parse_call_sites(json: Option<String>) -> Vec<Vec<String>> {
+    json.and_then(|j| serde_json::from_str(&j).ok()).unwrap_or_default()
+}
+
 // ---------------------------------------------------------------------------
 // Database
 // ---------------------------------------------------------------------------
 
+/// The synced graph cache, held for one traversal; see `Database::graph`.
+pub struct GraphRef<'a> {
+    graph: MutexGuard<'a, GraphCache>,
+    dirty: &'a Mutex<DirtyRows>,
+}
+
+impl std::ops::Deref for GraphRef<'_> {
+    type Target = GraphCache;
+
+    fn deref(&self) -> &GraphCache {
+        &self.graph
+    }
+}
+
+impl Drop for GraphRef<'_> {
+    fn drop(&mut self) {
+        self.graph.evict_if_over_budget(self.dirty);
+    }
+}
+
+/// Identifies the state of the index: changes on any write except
+/// auto-observations. Cached query results are valid only for the version
+/// they were computed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DataVersion {
+    /// SQLite's `PRAGMA data_version`: changes on commits by other connections.
+    external: i64,
+    /// Rows written through this connection.
+    local: u64,
+}
+
 pub struct Database {
     conn: Connection,
+    /// Rows written through `conn`, counted by an update hook.
+    writes: Arc<AtomicU64>,
+    /// Set while writing auto-observations, which the hook doesn't count.
+    quiet: Arc<AtomicBool>,
+    /// In-memory edge graph for traversals; see `graph_cache.rs`.
+    graph: Mutex<GraphCache>,
+    /// Rows the graph cache must reload, marked by the update hook.
+    graph_dirty: Arc<Mutex<DirtyRows>>,
 }
 
 impl Database {
+    fn from_connection(conn: Connection) -> Result<Self> {
+        let writes = Arc::new(AtomicU64::new(0));
+        let quiet = Arc::new(AtomicBool::new(false));
+        let graph_dirty = Arc::new(Mutex::new(DirtyRows::default()));
+        let (hook_writes, hook_quiet) = (Arc::clone(&writes), Arc::clone(&quiet));
+        let hook_dirty = Arc::clone(&graph_dirty);
+        conn.update_hook(Some(move |_: rusqlite::hooks::Action, _: &str, table: &str, rowid: i64| {
+            if !hook_quiet.load(Ordering::Relaxed) {
+                hook_writes.fetch_add(1, Ordering::Relaxed);
+            }
+            if let Ok(mut dirty) = hook_dirty.lock() {
+                dirty.mark(table, rowid);
+            }
+        }))?;
+        Ok(Self { conn, writes, quiet, graph: Mutex::new(GraphCache::default()), graph_dirty })
+    }
+
+    /// Cap the graph cache kept between traversals at `bytes`
+    /// (`[memory] graph_cache_mb`).
+    pub fn with_graph_cache_budget(self, bytes: usize) -> Self {
+        if let Ok(mut graph) = self.graph.lock() {
+            graph.set_budget(bytes);
+        }
+        self
+    }
+
+    /// The in-memory edge graph, brought up to date with the index: built on
+    /// first use, then patched with the rows written since the last call.
+    /// A graph over its budget is released when the returned guard drops.
+    pub fn graph(&self) -> Result<GraphRef<'_>> {
+        let data_version = self.conn.query_row("PRAGMA data_version", [], |row| row.get(0))?;
+        let mut graph = self.graph.lock().map_err(|_| anyhow::anyhow!("graph cache lock poisoned"))?;
+        graph.sync(&self.conn, data_version, &self.graph_dirty)?;
+        Ok(GraphRef { graph, dirty: &self.graph_dirty })
+    }
+
+    /// Size and budget of the graph cache, without loading it.
+    pub fn graph_cache_stats(&self) -> Result<GraphCacheStats> {
+        let graph = self.graph.lock().map_err(|_| anyhow::anyhow!("graph cache lock poisoned"))?;
+        Ok(graph.stats())
+    }
+
+    /// Current `DataVersion`.
+    pub fn data_version(&self) -> Result<DataVersion> {
+        let external = self.conn.query_row("PRAGMA data_version", [], |row| row.get(0))?;
+        Ok(DataVersion { external, local: self.writes.load(Ordering::Relaxed) })
+    }
+
     /// Open (or create) a SQLite database at `path` and run migrations.
     pub fn open(path: &str) -> Result<Self> {
         let conn = Connection::open(path)
             .with_context(|| format!("failed to open database at {path}"))?;
-        let db = Self { conn };
+        let db = Self::from_connection(conn)?;
+        db.apply_pragmas()?;
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// Open an existing database without write access or migrations, for
+    /// one-shot CLI queries that must not contend with a running server.
+    pub fn open_read_only(path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| format!("failed to open database at {path} read-only"))?;
+        conn.execute_batch("PRAGMA busy_timeout = 5000;")?;
+        Self::from_connection(conn)
+    }
+
+    /// Open (or create) a SQLCipher-encrypted database keyed with `key`.
+    /// Needs the `encryption` feature; see `encryption::open`.
+    pub fn open_encrypted(path: &str, key: &str) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open database at {path}"))?;
+        apply_key(&conn, path, key)?;
+        let db = Self::from_connection(conn)?;
         db.apply_pragmas()?;
         db.migrate()?;
         Ok(db)
     }
 
+    /// `open_read_only` for an encrypted database.
+    pub fn open_read_only_encrypted(path: &str, key: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .with_context(|| format!("failed to open database at {path} read-only"))?;
+        apply_key(&conn, path, key)?;
+        conn.execute_batch("PRAGMA busy_timeout = 5000;")?;
+        Self::from_connection(conn)
+    }
+
     /// In-memory database for tests.
     pub fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()
             .context("failed to open in-memory database")?;
-        let db = Self { conn };
+        let db = Self::from_connection(conn)?;
         db.apply_pragmas()?;
         db.migrate()?;
         Ok(db)
@@ -166,31 +905,103 @@ impl Database {
     }
 
     /// Execute `f` inside an IMMEDIATE transaction. Commits on Ok, rolls back on Err.
+    /// Inside an open transaction `f` runs in a savepoint instead, so a
+    /// failure only undoes its own writes.
     pub fn with_transaction<T, F>(&self, f: F) -> Result<T>
     where
         F: FnOnce() -> Result<T>,
     {
-        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        if !self.conn.is_autocommit() {
+            self.conn.execute_batch("SAVEPOINT nested")?;
+            return match f() {
+                Ok(val) => {
+                    self.conn.execute_batch("RELEASE nested")?;
+                    Ok(val)
+                }
+                Err(e) => {
+                    let _ = self.conn.execute_batch("ROLLBACK TO nested; RELEASE nested");
+                    Err(e)
+                }
+            };
+        }
+        self.begin_transaction()?;
         match f() {
             Ok(val) => {
-                self.conn.execute_batch("COMMIT")?;
+                self.commit_transaction()?;
                 Ok(val)
             }
             Err(e) => {
-                let _ = self.conn.execute_batch("ROLLBACK");
+                self.rollback_transaction();
                 Err(e)
             }
         }
     }
 
+    /// Open an IMMEDIATE transaction spanning several operations; pair with
+    /// `commit_transaction` or `rollback_transaction`. Prefer `with_transaction`
+    /// when the work fits in one closure.
+    pub fn begin_transaction(&self) -> Result<()> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        Ok(())
+    }
+
+    pub fn commit_transaction(&self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
+
+    /// Roll back the open transaction, if any.
+    pub fn rollback_transaction(&self) {
+        if !self.conn.is_autocommit() {
+            let _ = self.conn.execute_batch("ROLLBACK");
+        }
+    }
+
     fn migrate(&self) -> Result<()> {
+        // v0.3.0: irrelevance marks were keyed by symbol id, so re-indexing
+        // a file dropped them. They are session scratch data: drop the old
+        // table and let the schema below recreate it keyed by name.
+        let has_symbol_id_marks: bool = self
+            .conn
+            .prepare("SELECT symbol_id FROM irrelevant_symbols LIMIT 0")
+            .is_ok();
+        if has_symbol_id_marks {
+            self.conn.execute_batch("DROP TABLE irrelevant_symbols;")?;
+        }
+
         self.conn.execute_batch(
             "
             CREATE TABLE IF NOT EXISTS repositories (
                 id         INTEGER PRIMARY KEY,
                 name       TEXT NOT NULL,
                 root_path  TEXT NOT NULL UNIQUE,
-                indexed_at TEXT
+                indexed_at TEXT,
+                revision   TEXT,
+                branch     TEXT,
+                limits_waived INTEGER NOT NULL DEFAULT 0
+            );
+
+            -- Explicit names by root path, used instead of the directory
+            -- basename: `[repositories]` from config (source 'config',
+            -- replaced by sync_repo_names) or rename_repository ('rename').
+            CREATE TABLE IF NOT EXISTS repository_names (
+                root_path TEXT PRIMARY KEY,
+                name      TEXT NOT NULL,
+                source    TEXT NOT NULL DEFAULT 'config'
+            );
+
+            -- Earlier names of renamed repositories, still accepted by tools.
+            CREATE TABLE IF NOT EXISTS repository_aliases (
+                alias   TEXT PRIMARY KEY,
+                repo_id INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE
+            );
+
+            -- `[projects]` from config: repository names grouped into one
+            -- project. Replaced wholesale by sync_projects at startup.
+            CREATE TABLE IF NOT EXISTS project_repos (
+                project TEXT NOT NULL,
+                repo    TEXT NOT NULL,
+                PRIMARY KEY (project, repo)
             );
 
             CREATE TABLE IF NOT EXISTS files (
@@ -200,6 +1011,12 @@ impl Database {
                 language   TEXT NOT NULL,
                 hash       TEXT NOT NULL,
                 indexed_at TEXT,
+                line_count INTEGER NOT NULL DEFAULT 0,
+                partial    INTEGER NOT NULL DEFAULT 0,
+                encoding   TEXT NOT NULL DEFAULT 'UTF-8',
+                package    TEXT NOT NULL DEFAULT '',
+                redactions INTEGER NOT NULL DEFAULT 0,
+                generated  INTEGER NOT NULL DEFAULT 0,
                 UNIQUE(repo_id, path)
             );
 
@@ -211,6 +1028,10 @@ impl Database {
                 signature  TEXT NOT NULL DEFAULT '',
                 body       TEXT NOT NULL DEFAULT '',
                 body_hash  TEXT NOT NULL DEFAULT '',
+                visibility TEXT NOT NULL DEFAULT '',
+                modifiers  TEXT NOT NULL DEFAULT '',
+                signature_types TEXT,
+                attributes TEXT NOT NULL DEFAULT '',
                 start_line INTEGER NOT NULL,
                 end_line   INTEGER NOT NULL,
                 parent_id  INTEGER REFERENCES symbols(id) ON DELETE SET NULL
@@ -220,7 +1041,8 @@ impl Database {
                 id        INTEGER PRIMARY KEY,
                 source_id INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
                 target_id INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
-                kind      TEXT NOT NULL
+                kind      TEXT NOT NULL,
+                call_sites TEXT
             );
 
             CREATE TABLE IF NOT EXISTS memories (
@@ -231,7 +1053,11 @@ impl Database {
                 session_id    TEXT NOT NULL DEFAULT '',
                 created_at    TEXT NOT NULL DEFAULT (datetime('now')),
                 stale         INTEGER NOT NULL DEFAULT 0,
-                needs_review  INTEGER NOT NULL DEFAULT 0
+                needs_review  INTEGER NOT NULL DEFAULT 0,
+                structured    TEXT,
+                pinned        INTEGER NOT NULL DEFAULT 0,
+                stale_reason  TEXT,
+                stale_at      TEXT
             );
 
             CREATE TABLE IF NOT EXISTS memory_symbols (
@@ -240,41 +1066,269 @@ impl Database {
                 PRIMARY KEY (memory_id, symbol_id)
             );
 
-            -- Indexes
-            CREATE INDEX IF NOT EXISTS idx_files_repo_id        ON files(repo_id);
-            CREATE INDEX IF NOT EXISTS idx_symbols_file_name     ON symbols(file_id, name);
-            CREATE INDEX IF NOT EXISTS idx_symbols_kind_name     ON symbols(kind, name);
-            CREATE INDEX IF NOT EXISTS idx_edges_source          ON edges(source_id);
-            CREATE INDEX IF NOT EXISTS idx_edges_target          ON edges(target_id);
-            CREATE INDEX IF NOT EXISTS idx_memory_symbols_sym    ON memory_symbols(symbol_id);
+            -- Memories about a file or a whole directory (path prefix),
+            -- relative to the root of `repo_id` (NULL: any repository).
+            CREATE TABLE IF NOT EXISTS memory_files (
+                memory_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+                path      TEXT NOT NULL,
+                repo_id   INTEGER REFERENCES repositories(id) ON DELETE CASCADE,
+                PRIMARY KEY (memory_id, path)
+            );
 
-            -- Name-only index for find_symbol_by_name_any
-            CREATE INDEX IF NOT EXISTS idx_symbols_name ON symbols(name);
+            -- Directed relationships between memories: source supersedes /
+            -- refines / relates_to target.
+            CREATE TABLE IF NOT EXISTS memory_links (
+                source_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+                target_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+                kind      TEXT NOT NULL,
+                PRIMARY KEY (source_id, target_id, kind)
+            );
 
-            -- Prevent duplicate edges
-            CREATE UNIQUE INDEX IF NOT EXISTS idx_edges_unique
-                ON edges(source_id, target_id, kind);
-            ",
-        )?;
+            -- Files as they were on a branch other than the live one
+            -- (JSON ParkedFile snapshot), restored on switching back.
+            CREATE TABLE IF NOT EXISTS branch_files (
+                repo_id   INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                branch    TEXT NOT NULL,
+                path      TEXT NOT NULL,
+                hash      TEXT NOT NULL,
+                snapshot  TEXT NOT NULL,
+                parked_at TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (repo_id, branch, path)
+            );
 
-        // FTS5 virtual table — CREATE VIRTUAL TABLE … IF NOT EXISTS is supported
-        // since SQLite 3.37.0, which rusqlite bundles well past that.
-        self.conn.execute_batch(
-            "CREATE VIRTUAL TABLE IF NOT EXISTS symbols_fts
-             USING fts5(name, signature, body, content=symbols, content_rowid=id);",
-        )?;
+            -- Files deleted from disk, kept for a grace period so a file that
+            -- comes back unchanged keeps its symbol ids (JSON ParkedFile
+            -- snapshot, original symbol ids, incoming edges).
+            CREATE TABLE IF NOT EXISTS tombstones (
+                repo_id        INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                path           TEXT NOT NULL,
+                hash           TEXT NOT NULL,
+                snapshot       TEXT NOT NULL,
+                symbol_ids     TEXT NOT NULL,
+                incoming_edges TEXT NOT NULL,
+                deleted_at     TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (repo_id, path)
+            );
 
-        self.conn.execute_batch(
-            "CREATE VIRTUAL TABLE IF NOT EXISTS memories_fts
-             USING fts5(content, category, content=memories, content_rowid=id);",
-        )?;
+            -- Symbols and edges of a repository at the end of each index run.
+            CREATE TABLE IF NOT EXISTS graph_snapshots (
+                id           INTEGER PRIMARY KEY,
+                repo_id      INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                taken_at     TEXT NOT NULL DEFAULT (datetime('now')),
+                symbol_count INTEGER NOT NULL,
+                edge_count   INTEGER NOT NULL,
+                graph_hash   TEXT NOT NULL,
+                graph        TEXT
+            );
 
-        // Additive migrations for existing databases. Each uses a conditional
-        // check so they're idempotent — safe to run on every startup.
-        self.apply_additive_migrations()?;
+            -- Coupling metrics per directory (see coupling.rs).
+            CREATE TABLE IF NOT EXISTS module_metrics (
+                repo_id        INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                module         TEXT NOT NULL,
+                files          INTEGER NOT NULL,
+                symbols        INTEGER NOT NULL,
+                afferent       INTEGER NOT NULL,
+                efferent       INTEGER NOT NULL,
+                instability    REAL,
+                internal_edges INTEGER NOT NULL,
+                cohesion       REAL,
+                PRIMARY KEY (repo_id, module)
+            );
 
-        Ok(())
-    }
+            -- HTTP route registrations (method + path -> handler name).
+            CREATE TABLE IF NOT EXISTS routes (
+                id      INTEGER PRIMARY KEY,
+                file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                method  TEXT NOT NULL,
+                path    TEXT NOT NULL,
+                handler TEXT NOT NULL DEFAULT '',
+                line    INTEGER NOT NULL
+            );
+
+            -- Environment-variable reads; symbol_id is the innermost
+            -- symbol whose line range contains the read.
+            CREATE TABLE IF NOT EXISTS env_usages (
+                id        INTEGER PRIMARY KEY,
+                file_id   INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                symbol_id INTEGER REFERENCES symbols(id) ON DELETE SET NULL,
+                name      TEXT NOT NULL,
+                line      INTEGER NOT NULL
+            );
+
+            -- SQL statements found in string literals, and the tables each
+            -- one reads or writes.
+            CREATE TABLE IF NOT EXISTS sql_queries (
+                id        INTEGER PRIMARY KEY,
+                file_id   INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                symbol_id INTEGER REFERENCES symbols(id) ON DELETE SET NULL,
+                operation TEXT NOT NULL,
+                query     TEXT NOT NULL,
+                line      INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS sql_query_tables (
+                query_id   INTEGER NOT NULL REFERENCES sql_queries(id) ON DELETE CASCADE,
+                table_name TEXT NOT NULL,
+                position   INTEGER NOT NULL,
+                PRIMARY KEY (query_id, table_name)
+            );
+
+            -- Errors discarded or escalated to panics (unwrap, `_ = err`,
+            -- bare except, empty catch).
+            CREATE TABLE IF NOT EXISTS error_sites (
+                id        INTEGER PRIMARY KEY,
+                file_id   INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                symbol_id INTEGER REFERENCES symbols(id) ON DELETE SET NULL,
+                kind      TEXT NOT NULL,
+                line      INTEGER NOT NULL,
+                snippet   TEXT NOT NULL DEFAULT ''
+            );
+
+            -- Line coverage imported from lcov/Cobertura reports.
+            CREATE TABLE IF NOT EXISTS symbol_coverage (
+                symbol_id     INTEGER PRIMARY KEY REFERENCES symbols(id) ON DELETE CASCADE,
+                lines_total   INTEGER NOT NULL,
+                lines_covered INTEGER NOT NULL,
+                imported_at   TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            -- Test functions and the production symbols they exercise.
+            CREATE TABLE IF NOT EXISTS test_links (
+                test_id   INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
+                symbol_id INTEGER NOT NULL REFERENCES symbols(id) ON DELETE CASCADE,
+                via       TEXT NOT NULL,
+                PRIMARY KEY (test_id, symbol_id)
+            );
+
+            -- README paragraphs and package doc comments (module_docs.rs),
+            -- rebuilt on every full index.
+            CREATE TABLE IF NOT EXISTS module_docs (
+                repo_id INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                path    TEXT NOT NULL,
+                kind    TEXT NOT NULL,
+                doc     TEXT NOT NULL,
+                PRIMARY KEY (repo_id, path)
+            );
+
+            -- Directories with their own manifest (Cargo.toml, package.json,
+            -- go.mod, pyproject.toml); files.package names the innermost one.
+            CREATE TABLE IF NOT EXISTS packages (
+                repo_id INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                path    TEXT NOT NULL,
+                name    TEXT NOT NULL,
+                kind    TEXT NOT NULL,
+                PRIMARY KEY (repo_id, path)
+            );
+
+            -- Third-party packages declared in manifests, rebuilt on every
+            -- full index, and the files whose imports resolve to them.
+            CREATE TABLE IF NOT EXISTS dependencies (
+                id       INTEGER PRIMARY KEY,
+                repo_id  INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                manifest TEXT NOT NULL,
+                name     TEXT NOT NULL,
+                module   TEXT NOT NULL DEFAULT '',
+                version  TEXT NOT NULL,
+                kind     TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS dependency_imports (
+                dependency_id INTEGER NOT NULL REFERENCES dependencies(id) ON DELETE CASCADE,
+                file_id       INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                PRIMARY KEY (dependency_id, file_id)
+            );
+
+            -- Problems met while indexing a file (unreadable, unparsable,
+            -- syntax errors, extractor panics); replaced when the file is.
+            CREATE TABLE IF NOT EXISTS index_errors (
+                id          INTEGER PRIMARY KEY,
+                repo_id     INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                path        TEXT NOT NULL,
+                kind        TEXT NOT NULL,
+                message     TEXT NOT NULL,
+                line        INTEGER,
+                recorded_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            -- Symbols an agent flagged as off-topic during a session;
+            -- get_context leaves them out of that session's capsules. Keyed
+            -- by qualified name so a mark survives re-indexing the file.
+            CREATE TABLE IF NOT EXISTS irrelevant_symbols (
+                session_id     TEXT NOT NULL,
+                repo_id        INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                qualified_name TEXT NOT NULL,
+                reason         TEXT NOT NULL DEFAULT '',
+                marked_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                PRIMARY KEY (session_id, repo_id, qualified_name)
+            );
+
+            -- Tool calls on a shared server (see audit.rs). Append-only: the
+            -- triggers below reject updates and deletes, except the retention
+            -- purge (`purge_audit_log`).
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id              INTEGER PRIMARY KEY,
+                at              TEXT NOT NULL DEFAULT (datetime('now')),
+                session_id      TEXT NOT NULL,
+                token           TEXT,
+                tool            TEXT NOT NULL,
+                params          TEXT NOT NULL,
+                outcome         TEXT NOT NULL,
+                response_tokens INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+
+            -- Indexes
+            CREATE INDEX IF NOT EXISTS idx_files_repo_id        ON files(repo_id);
+            CREATE INDEX IF NOT EXISTS idx_audit_log_session     ON audit_log(session_id, at);
+            CREATE INDEX IF NOT EXISTS idx_routes_file           ON routes(file_id);
+            CREATE INDEX IF NOT EXISTS idx_env_usages_file       ON env_usages(file_id);
+            CREATE INDEX IF NOT EXISTS idx_env_usages_name       ON env_usages(name);
+            CREATE INDEX IF NOT EXISTS idx_sql_queries_file      ON sql_queries(file_id);
+            CREATE INDEX IF NOT EXISTS idx_sql_query_tables_name ON sql_query_tables(table_name);
+            CREATE INDEX IF NOT EXISTS idx_error_sites_file      ON error_sites(file_id);
+            CREATE INDEX IF NOT EXISTS idx_test_links_symbol     ON test_links(symbol_id);
+            CREATE INDEX IF NOT EXISTS idx_dependencies_repo     ON dependencies(repo_id, name);
+            CREATE INDEX IF NOT EXISTS idx_dependency_imports_file ON dependency_imports(file_id);
+            CREATE INDEX IF NOT EXISTS idx_index_errors_path     ON index_errors(repo_id, path);
+            CREATE INDEX IF NOT EXISTS idx_symbols_file_name     ON symbols(file_id, name);
+            CREATE INDEX IF NOT EXISTS idx_symbols_kind_name     ON symbols(kind, name);
+            CREATE INDEX IF NOT EXISTS idx_edges_source          ON edges(source_id);
+            CREATE INDEX IF NOT EXISTS idx_edges_target          ON edges(target_id);
+            CREATE INDEX IF NOT EXISTS idx_memory_symbols_sym    ON memory_symbols(symbol_id);
+            CREATE INDEX IF NOT EXISTS idx_memory_files_path     ON memory_files(path);
+            CREATE INDEX IF NOT EXISTS idx_memory_links_target   ON memory_links(target_id, kind);
+
+            -- Name-only index for find_symbol_by_name_any
+            CREATE INDEX IF NOT EXISTS idx_symbols_name ON symbols(name);
+
+            -- Prevent duplicate edges
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_edges_unique
+                ON edges(source_id, target_id, kind);
+            ",
+        )?;
+
+        // FTS5 virtual table — CREATE VIRTUAL TABLE … IF NOT EXISTS is supported
+        // since SQLite 3.37.0, which rusqlite bundles well past that.
+        self.conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS symbols_fts
+             USING fts5(name, signature, body, content=symbols, content_rowid=id);",
+        )?;
+
+        self.conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS memories_fts
+             USING fts5(content, category, content=memories, content_rowid=id);",
+        )?;
+
+        // Additive migrations for existing databases. Each uses a conditional
+        // check so they're idempotent — safe to run on every startup.
+        self.apply_additive_migrations()?;
+
+        Ok(())
+    }
 
     /// Column additions for existing databases. Each migration checks whether
     /// the column already exists before altering the table, so this is safe
@@ -302,6 +1356,81 @@ impl Database {
             )?;
         }
 
+        // v0.3.0: structured memory fields (JSON, validated against a template)
+        let has_structured: bool = self
+            .conn
+            .prepare("SELECT structured FROM memories LIMIT 0")
+            .is_ok();
+        if !has_structured {
+            self.conn.execute_batch(
+                "ALTER TABLE memories ADD COLUMN structured TEXT;"
+            )?;
+        }
+
+        // v0.3.0: pinned flag for memory relevance scoring
+        let has_pinned: bool = self
+            .conn
+            .prepare("SELECT pinned FROM memories LIMIT 0")
+            .is_ok();
+        if !has_pinned {
+            self.conn.execute_batch(
+                "ALTER TABLE memories ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;"
+            )?;
+        }
+
+        // v0.3.0: why and when a memory became stale
+        let has_stale_reason: bool = self
+            .conn
+            .prepare("SELECT stale_reason FROM memories LIMIT 0")
+            .is_ok();
+        if !has_stale_reason {
+            self.conn.execute_batch(
+                "ALTER TABLE memories ADD COLUMN stale_reason TEXT;
+                 ALTER TABLE memories ADD COLUMN stale_at TEXT;"
+            )?;
+        }
+
+        // v0.3.0: repository a memory's file/directory link belongs to
+        let has_memory_file_repo: bool = self
+            .conn
+            .prepare("SELECT repo_id FROM memory_files LIMIT 0")
+            .is_ok();
+        if !has_memory_file_repo {
+            self.conn.execute_batch(
+                "ALTER TABLE memory_files ADD COLUMN repo_id INTEGER REFERENCES repositories(id) ON DELETE CASCADE;"
+            )?;
+        }
+
+        // v0.3.0: git revision a repository was indexed from
+        let has_revision: bool = self
+            .conn
+            .prepare("SELECT revision FROM repositories LIMIT 0")
+            .is_ok();
+        if !has_revision {
+            self.conn.execute_batch(
+                "ALTER TABLE repositories ADD COLUMN revision TEXT;"
+            )?;
+        }
+
+        // v0.3.0: branch namespace that is live for a repository
+        let has_branch: bool = self
+            .conn
+            .prepare("SELECT branch FROM repositories LIMIT 0")
+            .is_ok();
+        if !has_branch {
+            self.conn.execute_batch(
+                "ALTER TABLE repositories ADD COLUMN branch TEXT;"
+            )?;
+        }
+
+        // v0.3.0: repository roots were stored with the Windows verbatim
+        // prefix (\\?\C:\...), which never matched plain watcher paths.
+        self.conn.execute_batch(
+            "UPDATE OR IGNORE repositories
+                SET root_path = substr(root_path, 5)
+              WHERE substr(root_path, 1, 4) = '\\\\?\\' AND substr(root_path, 6, 1) = ':';",
+        )?;
+
         // v0.3.0: manifest support columns
         let has_qualified_name: bool = self
             .conn
@@ -334,6 +1463,173 @@ impl Database {
             );"
         )?;
 
+        // v0.3.0: declared visibility and modifiers on symbols
+        let has_visibility: bool = self
+            .conn
+            .prepare("SELECT visibility FROM symbols LIMIT 0")
+            .is_ok();
+        if !has_visibility {
+            self.conn.execute_batch(
+                "ALTER TABLE symbols ADD COLUMN visibility TEXT NOT NULL DEFAULT '';
+                 ALTER TABLE symbols ADD COLUMN modifiers TEXT NOT NULL DEFAULT '';"
+            )?;
+            self.force_reparse(None)?;
+        }
+
+        // v0.3.0: structured parameter/return types (TypeSignature JSON)
+        let has_signature_types: bool = self
+            .conn
+            .prepare("SELECT signature_types FROM symbols LIMIT 0")
+            .is_ok();
+        if !has_signature_types {
+            self.conn.execute_batch(
+                "ALTER TABLE symbols ADD COLUMN signature_types TEXT;"
+            )?;
+            self.force_reparse(None)?;
+        }
+
+        // v0.3.0: attributes/decorators/directives on symbols
+        let has_attributes: bool = self
+            .conn
+            .prepare("SELECT attributes FROM symbols LIMIT 0")
+            .is_ok();
+        if !has_attributes {
+            self.conn.execute_batch(
+                "ALTER TABLE symbols ADD COLUMN attributes TEXT NOT NULL DEFAULT '';"
+            )?;
+            self.force_reparse(None)?;
+        }
+
+        // v0.3.0: source line count per file, for size statistics
+        let has_line_count: bool = self
+            .conn
+            .prepare("SELECT line_count FROM files LIMIT 0")
+            .is_ok();
+        if !has_line_count {
+            self.conn.execute_batch(
+                "ALTER TABLE files ADD COLUMN line_count INTEGER NOT NULL DEFAULT 0;"
+            )?;
+            self.force_reparse(None)?;
+        }
+
+        // v0.3.0: oversized files indexed without symbol bodies
+        let has_partial: bool = self
+            .conn
+            .prepare("SELECT partial FROM files LIMIT 0")
+            .is_ok();
+        if !has_partial {
+            self.conn.execute_batch(
+                "ALTER TABLE files ADD COLUMN partial INTEGER NOT NULL DEFAULT 0;"
+            )?;
+        }
+
+        // v0.3.0: secrets masked per file. Bodies stored before masking
+        // existed are re-parsed: clearing the hashes defeats the skip check.
+        let has_redactions: bool = self
+            .conn
+            .prepare("SELECT redactions FROM files LIMIT 0")
+            .is_ok();
+        if !has_redactions {
+            self.conn.execute_batch(
+                "ALTER TABLE files ADD COLUMN redactions INTEGER NOT NULL DEFAULT 0;
+                 UPDATE files SET hash = '';"
+            )?;
+        }
+
+        // v0.3.0: original encoding of files transcoded to UTF-8
+        let has_encoding: bool = self
+            .conn
+            .prepare("SELECT encoding FROM files LIMIT 0")
+            .is_ok();
+        if !has_encoding {
+            self.conn.execute_batch(
+                "ALTER TABLE files ADD COLUMN encoding TEXT NOT NULL DEFAULT 'UTF-8';"
+            )?;
+            // Bodies stored before transcoding may be mangled.
+            self.force_reparse(None)?;
+        }
+
+        // v0.3.0: monorepo package of each file
+        let has_package: bool = self
+            .conn
+            .prepare("SELECT package FROM files LIMIT 0")
+            .is_ok();
+        if !has_package {
+            self.conn.execute_batch(
+                "ALTER TABLE files ADD COLUMN package TEXT NOT NULL DEFAULT '';"
+            )?;
+        }
+
+        // v0.3.0: argument shapes of call edges
+        let has_call_sites: bool = self
+            .conn
+            .prepare("SELECT call_sites FROM edges LIMIT 0")
+            .is_ok();
+        if !has_call_sites {
+            self.conn.execute_batch("ALTER TABLE edges ADD COLUMN call_sites TEXT;")?;
+            self.force_reparse(None)?;
+        }
+
+        // v0.3.0: files produced by code generators. Files indexed before
+        // detection existed are re-parsed so they get flagged.
+        let has_generated: bool = self
+            .conn
+            .prepare("SELECT generated FROM files LIMIT 0")
+            .is_ok();
+        if !has_generated {
+            self.conn.execute_batch(
+                "ALTER TABLE files ADD COLUMN generated INTEGER NOT NULL DEFAULT 0;
+                 UPDATE files SET hash = '' WHERE hash != 'imported';"
+            )?;
+        }
+
+        // v0.3.0: repositories indexed past the size guardrails on purpose
+        let has_limits_waived: bool = self
+            .conn
+            .prepare("SELECT limits_waived FROM repositories LIMIT 0")
+            .is_ok();
+        if !has_limits_waived {
+            self.conn.execute_batch(
+                "ALTER TABLE repositories ADD COLUMN limits_waived INTEGER NOT NULL DEFAULT 0;"
+            )?;
+        }
+
+        // v0.3.0: the name a dependency is imported by, when it differs
+        // from the declared name. Rows from before import by their name
+        // until the next full index rebuilds them.
+        let has_dependency_module: bool = self
+            .conn
+            .prepare("SELECT module FROM dependencies LIMIT 0")
+            .is_ok();
+        if !has_dependency_module {
+            self.conn.execute_batch(
+                "ALTER TABLE dependencies ADD COLUMN module TEXT NOT NULL DEFAULT '';"
+            )?;
+        }
+
+        // Extractor changes that need no column still leave stored symbols
+        // stale: re-parse the files of each one not yet applied.
+        let applied = self.conn.query_row("PRAGMA user_version", [], |r| r.get::<_, i64>(0))? as usize;
+        if applied < REPARSE_MIGRATIONS.len() {
+            for language in &REPARSE_MIGRATIONS[applied..] {
+                self.force_reparse(*language)?;
+            }
+            self.conn
+                .execute_batch(&format!("PRAGMA user_version = {};", REPARSE_MIGRATIONS.len()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Clear the content hashes of parsed files (of one `language`, or all)
+    /// so the next index run re-parses them: the skip check compares against
+    /// the stored hash. Imported and synthetic files keep theirs.
+    fn force_reparse(&self, language: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE files SET hash = ''
+              WHERE hash NOT IN ('imported', 'manifest', 'external') AND (?1 IS NULL OR language = ?1)",
+            params![language],
+        )?;
         Ok(())
     }
 
@@ -341,30 +1637,58 @@ impl Database {
     // Repository CRUD
     // -----------------------------------------------------------------------
 
+    /// Register `root_path` (or refresh its `indexed_at`) and return its id.
+    /// A name recorded for the root in `repository_names` (`[repositories]`
+    /// or `rename_repository`) wins over `name`. Renaming an existing
+    /// repository keeps its old name as an alias. `root_path` is stored
+    /// normalized (`paths::normalize_root`), so one tree gets one row.
     pub fn upsert_repository(&self, name: &str, root_path: &str) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO repositories (name, root_path, indexed_at)
-             VALUES (?1, ?2, datetime('now'))
-             ON CONFLICT(root_path) DO UPDATE SET name = excluded.name,
-                                                  indexed_at = excluded.indexed_at",
-            params![name, root_path],
-        )?;
-        // ON CONFLICT UPDATE leaves last_insert_rowid stale (reflects the last
-        // real INSERT on this connection, not this statement). Always SELECT to
-        // get the authoritative id.
-        let id: i64 = self.conn.query_row(
-            "SELECT id FROM repositories WHERE root_path = ?1",
-            params![root_path],
-            |r| r.get(0),
-        )?;
-        Ok(id)
+        let root_path = &paths::normalize_root(root_path);
+        let recorded: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT name FROM repository_names WHERE root_path = ?1",
+                params![root_path],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let name = recorded.as_deref().unwrap_or(name);
+        let existing: Option<(i64, String)> = self
+            .conn
+            .query_row(
+                "SELECT id, name FROM repositories WHERE root_path = ?1",
+                params![root_path],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        match existing {
+            Some((id, current)) => {
+                self.conn.execute(
+                    "UPDATE repositories SET indexed_at = datetime('now') WHERE id = ?1",
+                    params![id],
+                )?;
+                if current != name {
+                    self.rename_repository_id(id, &current, name)?;
+                }
+                Ok(id)
+            }
+            None => {
+                self.conn.execute(
+                    "INSERT INTO repositories (name, root_path, indexed_at) VALUES (?1, ?2, datetime('now'))",
+                    params![name, root_path],
+                )?;
+                Ok(self.conn.last_insert_rowid())
+            }
+        }
     }
 
     pub fn get_repository_by_path(&self, root_path: &str) -> Result<Option<Repository>> {
+        let root_path = paths::normalize_root(root_path);
         let r = self
             .conn
             .query_row(
-                "SELECT id, name, root_path, indexed_at FROM repositories WHERE root_path = ?1",
+                "SELECT id, name, root_path, indexed_at, revision, branch
+                 FROM repositories WHERE root_path = ?1",
                 params![root_path],
                 |row| {
                     Ok(Repository {
@@ -372,6 +1696,8 @@ impl Database {
                         name: row.get(1)?,
                         root_path: row.get(2)?,
                         indexed_at: row.get(3)?,
+                        revision: row.get(4)?,
+                        branch: row.get(5)?,
                     })
                 },
             )
@@ -379,538 +1705,3045 @@ impl Database {
         Ok(r)
     }
 
+    /// Record the commit a repository's index was built from (None = working tree).
+    pub fn set_repository_revision(&self, repo_id: i64, revision: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE repositories SET revision = ?2 WHERE id = ?1",
+            params![repo_id, revision],
+        )?;
+        Ok(())
+    }
+
+    /// Whether the repository was indexed past the guardrail caps with
+    /// `--force` (see `guardrails`).
+    pub fn limits_waived(&self, repo_id: i64) -> Result<bool> {
+        let waived = self
+            .conn
+            .query_row("SELECT limits_waived FROM repositories WHERE id = ?1", params![repo_id], |row| row.get(0))
+            .optional()?;
+        Ok(waived.unwrap_or(false))
+    }
+
+    pub fn set_limits_waived(&self, repo_id: i64, waived: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE repositories SET limits_waived = ?2 WHERE id = ?1",
+            params![repo_id, waived],
+        )?;
+        Ok(())
+    }
+
+    /// Live symbols in a repository's files.
+    pub fn count_repo_symbols(&self, repo_id: i64) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM symbols s JOIN files f ON s.file_id = f.id WHERE f.repo_id = ?1",
+            params![repo_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Repository id by current name, else by an earlier name it was
+    /// renamed from.
     pub fn get_repo_id_by_name(&self, name: &str) -> Result<Option<i64>> {
-        let r = self
+        let r = self.conn.query_row(
+            "SELECT COALESCE((SELECT id FROM repositories WHERE name = ?1 ORDER BY id LIMIT 1),
+                             (SELECT repo_id FROM repository_aliases WHERE alias = ?1))",
+            params![name],
+            |row| row.get(0),
+        )?;
+        Ok(r)
+    }
+
+    /// The current name of the repository once called `name`; `name` itself
+    /// when it is current or unknown.
+    pub fn current_repo_name(&self, name: &str) -> Result<String> {
+        let current: Option<String> = self
             .conn
             .query_row(
-                "SELECT id FROM repositories WHERE name = ?1",
+                "SELECT r.name FROM repository_aliases a
+                 JOIN repositories r ON r.id = a.repo_id
+                 WHERE a.alias = ?1
+                   AND NOT EXISTS (SELECT 1 FROM repositories WHERE name = ?1)",
                 params![name],
                 |row| row.get(0),
             )
             .optional()?;
-        Ok(r)
+        Ok(current.unwrap_or_else(|| name.to_string()))
     }
 
-    // -----------------------------------------------------------------------
-    // File CRUD
-    // -----------------------------------------------------------------------
+    /// Rename a repository. Its id is kept, and with it every file, symbol,
+    /// edge and memory link; the old name stays an alias tools accept. The
+    /// name sticks across re-indexes unless `[repositories]` sets another.
+    pub fn rename_repository(&self, old: &str, new: &str) -> Result<()> {
+        let (id, root_path): (i64, String) = self
+            .conn
+            .query_row(
+                "SELECT id, root_path FROM repositories WHERE name = ?1 ORDER BY id LIMIT 1",
+                params![old],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .with_context(|| format!("repository '{old}' not found"))?;
+        let new = new.trim();
+        self.ensure_repo_name_free(id, new)?;
+        self.with_transaction(|| {
+            self.rename_repository_id(id, old, new)?;
+            self.conn.execute(
+                "INSERT OR REPLACE INTO repository_names (root_path, name, source) VALUES (?1, ?2, 'rename')",
+                params![root_path, new],
+            )?;
+            Ok(())
+        })
+    }
 
-    pub fn upsert_file(
-        &self,
-        repo_id: i64,
-        path: &str,
-        language: &str,
-        hash: &str,
-    ) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO files (repo_id, path, language, hash, indexed_at)
-             VALUES (?1, ?2, ?3, ?4, datetime('now'))
-             ON CONFLICT(repo_id, path) DO UPDATE SET language   = excluded.language,
-                                                      hash       = excluded.hash,
-                                                      indexed_at = excluded.indexed_at",
-            params![repo_id, path, language, hash],
-        )?;
-        // Always SELECT — last_insert_rowid is unreliable on the UPDATE path
-        // of an ON CONFLICT upsert.
-        let id: i64 = self.conn.query_row(
-            "SELECT id FROM files WHERE repo_id = ?1 AND path = ?2",
-            params![repo_id, path],
-            |r| r.get(0),
+    /// Add a repository at `root_path` named `name`, or `name-2`, `name-3`…
+    /// when another repository has it; a suffixed name is recorded like a
+    /// rename so indexing the root later keeps it. An explicitly named root
+    /// (`[repositories]`, `rename_repository`) keeps its recorded name.
+    pub fn create_repository_unique(&self, name: &str, root_path: &str) -> Result<i64> {
+        let normalized = paths::normalize_root(root_path);
+        let recorded: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM repository_names WHERE root_path = ?1)",
+            params![normalized],
+            |row| row.get(0),
         )?;
-        Ok(id)
+        if recorded {
+            return self.upsert_repository(name, root_path);
+        }
+        let taken = |candidate: &str| -> Result<bool> {
+            Ok(self.conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM repositories WHERE name = ?1)",
+                params![candidate],
+                |row| row.get(0),
+            )?)
+        };
+        let mut unique = name.to_string();
+        let mut n = 1;
+        while taken(&unique)? {
+            n += 1;
+            unique = format!("{name}-{n}");
+        }
+        self.with_transaction(|| {
+            if unique != name {
+                self.conn.execute(
+                    "INSERT INTO repository_names (root_path, name, source) VALUES (?1, ?2, 'rename')",
+                    params![normalized, unique],
+                )?;
+            }
+            self.upsert_repository(&unique, root_path)
+        })
     }
 
-    pub fn get_file_by_path(&self, repo_id: i64, path: &str) -> Result<Option<FileRecord>> {
-        let r = self
-            .conn
-            .query_row(
-                "SELECT id, repo_id, path, language, hash, indexed_at
-                 FROM files WHERE repo_id = ?1 AND path = ?2",
-                params![repo_id, path],
-                |row| {
-                    Ok(FileRecord {
-                        id: row.get(0)?,
-                        repo_id: row.get(1)?,
-                        path: row.get(2)?,
-                        language: row.get(3)?,
-                        hash: row.get(4)?,
-                        indexed_at: row.get(5)?,
-                    })
-                },
-            )
-            .optional()?;
-        Ok(r)
+    /// Fail unless `name` is non-empty and no repository other than `id`
+    /// has it.
+    fn ensure_repo_name_free(&self, id: i64, name: &str) -> Result<()> {
+        if name.is_empty() {
+            anyhow::bail!("repository name must not be empty");
+        }
+        let taken: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM repositories WHERE name = ?1 AND id != ?2)",
+            params![name, id],
+            |row| row.get(0),
+        )?;
+        if taken {
+            anyhow::bail!("repository name '{name}' is already in use");
+        }
+        Ok(())
     }
 
-    /// Remove a file and all its symbols/edges from the index.
-    /// Returns true if a file record was actually deleted.
-    pub fn remove_file(&self, repo_id: i64, rel_path: &str) -> Result<bool> {
-        let file_id: Option<i64> = self
-            .conn
-            .query_row(
-                "SELECT id FROM files WHERE repo_id = ?1 AND path = ?2",
-                params![repo_id, rel_path],
-                |row| row.get(0),
-            )
-            .optional()?;
-        let file_id = match file_id {
-            Some(id) => id,
-            None => return Ok(false),
-        };
-        self.delete_edges_by_file(file_id)?;
-        self.delete_symbols_by_file(file_id)?;
-        self.conn
-            .execute("DELETE FROM files WHERE id = ?1", params![file_id])?;
-        Ok(true)
+    /// Rename repository `id` from `old` to `new`, recording `old` as an
+    /// alias and moving its project memberships.
+    fn rename_repository_id(&self, id: i64, old: &str, new: &str) -> Result<()> {
+        if old == new {
+            return Ok(());
+        }
+        self.with_transaction(|| {
+            self.set_repo_name(id, old, new)?;
+            self.move_project_members(old, new)
+        })
     }
 
-    pub fn get_file_hash(&self, repo_id: i64, path: &str) -> Result<Option<String>> {
-        let r = self
-            .conn
-            .query_row(
-                "SELECT hash FROM files WHERE repo_id = ?1 AND path = ?2",
-                params![repo_id, path],
-                |row| row.get(0),
-            )
-            .optional()?;
-        Ok(r)
+    /// Rename repository `id` from `old` to `new`, recording `old` as an
+    /// alias. Project memberships are left alone.
+    fn set_repo_name(&self, id: i64, old: &str, new: &str) -> Result<()> {
+        self.conn.execute("UPDATE repositories SET name = ?2 WHERE id = ?1", params![id, new])?;
+        self.conn.execute("DELETE FROM repository_aliases WHERE alias = ?1", params![new])?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO repository_aliases (alias, repo_id) VALUES (?1, ?2)",
+            params![old, id],
+        )?;
+        Ok(())
     }
 
-    pub fn get_files_for_repo(&self, repo_id: i64) -> Result<Vec<FileRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, repo_id, path, language, hash, indexed_at
-             FROM files WHERE repo_id = ?1 ORDER BY path",
+    /// Move project memberships of repository name `from` to `to`.
+    fn move_project_members(&self, from: &str, to: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE OR IGNORE project_repos SET repo = ?2 WHERE repo = ?1",
+            params![from, to],
         )?;
-        let rows = stmt.query_map(params![repo_id], |row| {
-            Ok(FileRecord {
-                id: row.get(0)?,
-                repo_id: row.get(1)?,
-                path: row.get(2)?,
-                language: row.get(3)?,
-                hash: row.get(4)?,
-                indexed_at: row.get(5)?,
-            })
-        })?;
-        let mut out = Vec::new();
-        for r in rows {
-            out.push(r?);
-        }
-        Ok(out)
+        self.conn.execute("DELETE FROM project_repos WHERE repo = ?1", params![from])?;
+        Ok(())
     }
 
-    pub fn get_file_path_for_symbol(&self, symbol_id: i64) -> Result<String> {
-        let path: String = self.conn.query_row(
-            "SELECT f.path FROM files f
-             JOIN symbols s ON s.file_id = f.id
-             WHERE s.id = ?1",
-            params![symbol_id],
-            |row| row.get(0),
-        )?;
-        Ok(path)
+    /// Repository rows whose roots normalize to the same tree (symlinked
+    /// homes, trailing slashes, `/private` on macOS), one group per tree with
+    /// the row to keep first: the one already stored under the normalized
+    /// root, else the most recently indexed.
+    pub fn duplicate_repositories(&self) -> Result<Vec<(String, Vec<Repository>)>> {
+        let mut groups: std::collections::BTreeMap<String, Vec<Repository>> = std::collections::BTreeMap::new();
+        for repo in self.list_repositories()? {
+            groups.entry(paths::normalize_root(&repo.root_path)).or_default().push(repo);
+        }
+        Ok(groups
+            .into_iter()
+            .filter(|(_, repos)| repos.len() > 1)
+            .map(|(root, mut repos)| {
+                repos.sort_by(|a, b| {
+                    (b.root_path == root)
+                        .cmp(&(a.root_path == root))
+                        .then_with(|| b.indexed_at.cmp(&a.indexed_at))
+                        .then_with(|| a.id.cmp(&b.id))
+                });
+                (root, repos)
+            })
+            .collect())
     }
 
-    // -----------------------------------------------------------------------
-    // Symbol CRUD
-    // -----------------------------------------------------------------------
+    /// Merge every group of `duplicate_repositories` into its first row,
+    /// which takes the normalized root. Returns the number of rows merged
+    /// away.
+    pub fn merge_duplicate_repositories(&self) -> Result<usize> {
+        let mut merged = 0;
+        for (root, repos) in self.duplicate_repositories()? {
+            let (keep, duplicates) = repos.split_first().expect("groups have two or more rows");
+            self.with_transaction(|| {
+                for duplicate in duplicates {
+                    self.merge_repository_into(keep, duplicate)?;
+                }
+                self.conn.execute(
+                    "UPDATE repositories SET root_path = ?2 WHERE id = ?1",
+                    params![keep.id, root],
+                )?;
+                self.conn.execute(
+                    "UPDATE OR IGNORE repository_names SET root_path = ?2 WHERE root_path = ?1",
+                    params![keep.root_path, root],
+                )?;
+                Ok(())
+            })?;
+            merged += duplicates.len();
+        }
+        Ok(merged)
+    }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn insert_symbol(
-        &self,
-        file_id: i64,
-        name: &str,
-        qualified_name: &str,
-        kind: &str,
-        signature: &str,
-        body: &str,
-        body_hash: &str,
-        start_line: i64,
-        end_line: i64,
-        parent_id: Option<i64>,
-    ) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO symbols (file_id, name, qualified_name, kind, signature, body, body_hash, start_line, end_line, parent_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![file_id, name, qualified_name, kind, signature, body, body_hash, start_line, end_line, parent_id],
+    /// Reparent `duplicate`'s files (with their symbols and edges) and other
+    /// per-repository rows onto `keep`, then delete `duplicate`. A path both
+    /// rows have keeps `keep`'s file; memory links and incoming edges of the
+    /// dropped file's symbols move to the same-named symbols of the kept one.
+    fn merge_repository_into(&self, keep: &Repository, duplicate: &Repository) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, k.id, d.path FROM files d
+             JOIN files k ON k.repo_id = ?1 AND k.path = d.path
+             WHERE d.repo_id = ?2",
         )?;
-        let id = self.conn.last_insert_rowid();
-        // Maintain FTS index incrementally
+        let clashes: Vec<(i64, i64, String)> = stmt
+            .query_map(params![keep.id, duplicate.id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (dropped, kept, path) in clashes {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO memory_symbols (memory_id, symbol_id)
+                 SELECT ms.memory_id, k.id FROM memory_symbols ms
+                 JOIN symbols d ON d.id = ms.symbol_id
+                 JOIN symbols k ON k.file_id = ?2 AND k.name = d.name AND k.kind = d.kind
+                 WHERE d.file_id = ?1",
+                params![dropped, kept],
+            )?;
+            self.conn.execute(
+                "INSERT OR IGNORE INTO edges (source_id, target_id, kind)
+                 SELECT e.source_id, k.id, e.kind FROM edges e
+                 JOIN symbols d ON d.id = e.target_id
+                 JOIN symbols k ON k.file_id = ?2 AND k.name = d.name AND k.kind = d.kind
+                 WHERE d.file_id = ?1 AND e.source_id NOT IN (SELECT id FROM symbols WHERE file_id = ?1)",
+                params![dropped, kept],
+            )?;
+            self.remove_file(duplicate.id, &path)?;
+        }
+
+        for table in [
+            "files",
+            "branch_files",
+            "tombstones",
+            "packages",
+            "dependencies",
+            "index_errors",
+            "module_docs",
+            "module_metrics",
+            "graph_snapshots",
+        ] {
+            self.conn.execute(
+                &format!("UPDATE OR IGNORE {table} SET repo_id = ?1 WHERE repo_id = ?2"),
+                params![keep.id, duplicate.id],
+            )?;
+        }
         self.conn.execute(
-            "INSERT INTO symbols_fts(rowid, name, signature, body) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, signature, body],
+            "UPDATE OR IGNORE repository_aliases SET repo_id = ?1 WHERE repo_id = ?2",
+            params![keep.id, duplicate.id],
         )?;
-        Ok(id)
+        if duplicate.name != keep.name {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO repository_aliases (alias, repo_id) VALUES (?1, ?2)",
+                params![duplicate.name, keep.id],
+            )?;
+            self.conn.execute(
+                "UPDATE OR IGNORE project_repos SET repo = ?2 WHERE repo = ?1",
+                params![duplicate.name, keep.name],
+            )?;
+        }
+        self.conn.execute("DELETE FROM repository_names WHERE root_path = ?1", params![duplicate.root_path])?;
+        self.conn.execute("DELETE FROM repositories WHERE id = ?1", params![duplicate.id])?;
+        Ok(())
     }
 
-    pub fn get_symbols_by_file(&self, file_id: i64) -> Result<Vec<Symbol>> {
+    /// Replace the configured repository names (`[repositories]`: canonical
+    /// root path to name) and rename indexed repositories whose configured
+    /// name differs. Configured names override earlier `rename_repository`s.
+    /// Repositories being renamed first move to a temporary name, so a
+    /// config that swaps two names applies cleanly.
+    pub fn sync_repo_names(&self, names: &std::collections::BTreeMap<String, String>) -> Result<()> {
+        self.with_transaction(|| {
+            self.conn.execute("DELETE FROM repository_names WHERE source = 'config'", [])?;
+            let mut renames = Vec::new();
+            for (root_path, name) in names {
+                let name = name.trim();
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO repository_names (root_path, name, source) VALUES (?1, ?2, 'config')",
+                    params![root_path, name],
+                )?;
+                let current: Option<(i64, String)> = self
+                    .conn
+                    .query_row(
+                        "SELECT id, name FROM repositories WHERE root_path = ?1",
+                        params![root_path],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                if let Some((id, old)) = current.filter(|(_, old)| old != name) {
+                    renames.push((id, old, name));
+                }
+            }
+
+            // Free every old name before taking any new one.
+            let temporary = |id: &i64| format!("\u{1}renaming-{id}");
+            for (id, old, _) in &renames {
+                self.conn.execute("UPDATE repositories SET name = ?2 WHERE id = ?1", params![id, temporary(id)])?;
+                self.move_project_members(old, &temporary(id))?;
+            }
+            for (id, old, name) in &renames {
+                self.ensure_repo_name_free(*id, name)?;
+                self.set_repo_name(*id, old, name)?;
+                self.move_project_members(&temporary(id), name)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Replace the configured project groupings (`[projects]`): project
+    /// name to member repository names. Members need not be indexed yet;
+    /// names a repository was renamed from map to its current name.
+    pub fn sync_projects(&self, projects: &std::collections::BTreeMap<String, Vec<String>>) -> Result<()> {
+        self.with_transaction(|| {
+            self.conn.execute("DELETE FROM project_repos", [])?;
+            let mut stmt = self
+                .conn
+                .prepare("INSERT OR IGNORE INTO project_repos (project, repo) VALUES (?1, ?2)")?;
+            for (project, repos) in projects {
+                for repo in repos {
+                    stmt.execute(params![project, self.current_repo_name(repo.trim())?])?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Indexed member repositories of `project`, by name; None when no such
+    /// project is configured.
+    pub fn project_repositories(&self, project: &str) -> Result<Option<Vec<Repository>>> {
+        let configured: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM project_repos WHERE project = ?1)",
+            params![project],
+            |row| row.get(0),
+        )?;
+        if !configured {
+            return Ok(None);
+        }
         let mut stmt = self.conn.prepare(
-            "SELECT id, file_id, name, kind, signature, body, body_hash,
-                    start_line, end_line, parent_id, qualified_name, source, manifest_repo
-             FROM symbols WHERE file_id = ?1 ORDER BY start_line",
+            "SELECT r.id, r.name, r.root_path, r.indexed_at, r.revision, r.branch
+             FROM repositories r
+             JOIN project_repos p ON p.repo = r.name
+             WHERE p.project = ?1
+             ORDER BY r.name, r.id",
         )?;
-        let rows = stmt.query_map(params![file_id], |row| {
-            Ok(Symbol {
+        let rows = stmt.query_map(params![project], |row| {
+            Ok(Repository {
                 id: row.get(0)?,
-                file_id: row.get(1)?,
-                name: row.get(2)?,
-                qualified_name: row.get(10)?,
-                kind: row.get(3)?,
-                signature: row.get(4)?,
-                body: row.get(5)?,
-                body_hash: row.get(6)?,
-                start_line: row.get(7)?,
-                end_line: row.get(8)?,
-                parent_id: row.get(9)?,
-                source: row.get(11)?,
-                manifest_repo: row.get(12)?,
+                name: row.get(1)?,
+                root_path: row.get(2)?,
+                indexed_at: row.get(3)?,
+                revision: row.get(4)?,
+                branch: row.get(5)?,
             })
         })?;
-        rows.collect::<std::result::Result<Vec<_>, _>>()
-            .map_err(Into::into)
+        Ok(Some(rows.collect::<std::result::Result<Vec<_>, _>>()?))
     }
 
-    pub fn delete_symbols_by_file(&self, file_id: i64) -> Result<usize> {
-        // Remove from FTS index before deleting the content rows
+    /// Other indexed repositories sharing a project with `repo_id`.
+    pub fn project_sibling_repo_ids(&self, repo_id: i64) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT other.id
+             FROM repositories r
+             JOIN project_repos mine ON mine.repo = r.name
+             JOIN project_repos theirs ON theirs.project = mine.project
+             JOIN repositories other ON other.name = theirs.repo
+             WHERE r.id = ?1 AND other.id != ?1
+             ORDER BY other.id",
+        )?;
+        let ids = stmt
+            .query_map(params![repo_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Record which branch namespace is live (None = not tracked).
+    pub fn set_repository_branch(&self, repo_id: i64, branch: Option<&str>) -> Result<()> {
         self.conn.execute(
-            "DELETE FROM symbols_fts WHERE rowid IN (SELECT id FROM symbols WHERE file_id = ?1)",
-            params![file_id],
+            "UPDATE repositories SET branch = ?2 WHERE id = ?1",
+            params![repo_id, branch],
         )?;
-        let count = self
-            .conn
-            .execute("DELETE FROM symbols WHERE file_id = ?1", params![file_id])?;
-        Ok(count)
+        Ok(())
     }
 
-    pub fn find_symbol_by_name(&self, repo_id: i64, name: &str) -> Result<Option<Symbol>> {
-        let r = self
+    pub fn get_repository_branch(&self, repo_id: i64) -> Result<Option<String>> {
+        let branch = self
             .conn
             .query_row(
-                "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
-                        s.body_hash, s.start_line, s.end_line, s.parent_id,
-                        s.qualified_name, s.source, s.manifest_repo
-                 FROM symbols s
-                 JOIN files f ON f.id = s.file_id
-                 WHERE f.repo_id = ?1 AND s.name = ?2
-                 LIMIT 1",
-                params![repo_id, name],
-                |row| {
-                    Ok(Symbol {
-                        id: row.get(0)?,
-                        file_id: row.get(1)?,
-                        name: row.get(2)?,
-                        qualified_name: row.get(10)?,
-                        kind: row.get(3)?,
-                        signature: row.get(4)?,
-                        body: row.get(5)?,
-                        body_hash: row.get(6)?,
-                        start_line: row.get(7)?,
-                        end_line: row.get(8)?,
-                        parent_id: row.get(9)?,
-                        source: row.get(11)?,
-                        manifest_repo: row.get(12)?,
-                    })
-                },
+                "SELECT branch FROM repositories WHERE id = ?1",
+                params![repo_id],
+                |row| row.get(0),
             )
-            .optional()?;
-        Ok(r)
+            .optional()?
+            .flatten();
+        Ok(branch)
     }
 
-    pub fn find_symbol_by_name_any(&self, name: &str) -> Result<Option<Symbol>> {
-        let r = self
+    // -----------------------------------------------------------------------
+    // Branch namespaces
+    // -----------------------------------------------------------------------
+
+    /// Snapshot a live file's symbols, outgoing edges, and memory links under
+    /// `branch`, replacing any earlier snapshot of the same path. The live
+    /// file is left in place; callers remove or overwrite it afterwards.
+    pub fn park_file(&self, repo_id: i64, branch: &str, file: &FileRecord) -> Result<()> {
+        let (parked, _) = self.snapshot_file(file)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO branch_files (repo_id, branch, path, hash, snapshot)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![repo_id, branch, file.path, file.hash, serde_json::to_string(&parked)?],
+        )?;
+        Ok(())
+    }
+
+    /// A live file as a `ParkedFile`, with the ids of its symbols in snapshot
+    /// order.
+    fn snapshot_file(&self, file: &FileRecord) -> Result<(ParkedFile, Vec<i64>)> {
+        // Insertion order puts parents before children; keep it for restore.
+        let mut symbols = self.get_symbols_by_file(file.id)?;
+        symbols.sort_by_key(|s| s.id);
+        let index_of: std::collections::HashMap<i64, usize> =
+            symbols.iter().enumerate().map(|(i, s)| (s.id, i)).collect();
+        let parked_symbols = symbols
+            .iter()
+            .map(|s| ParkedSymbol {
+                name: s.name.clone(),
+                qualified_name: s.qualified_name.clone(),
+                kind: s.kind.clone(),
+                signature: s.signature.clone(),
+                body: s.body.clone(),
+                body_hash: s.body_hash.clone(),
+                start_line: s.start_line,
+                end_line: s.end_line,
+                visibility: s.visibility.clone(),
+                modifiers: s.modifiers.clone(),
+                signature_types: s.signature_types.clone(),
+                attributes: s.attributes.clone(),
+                parent: s.parent_id.and_then(|p| index_of.get(&p).copied()),
+            })
+            .collect();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT src.name, tgt.name, e.kind FROM edges e
+             JOIN symbols src ON src.id = e.source_id
+             JOIN symbols tgt ON tgt.id = e.target_id
+             WHERE src.file_id = ?1",
+        )?;
+        let edges = stmt
+            .query_map(params![file.id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let parked = ParkedFile {
+            language: file.language.clone(),
+            hash: file.hash.clone(),
+            symbols: parked_symbols,
+            edges,
+            memory_links: self.collect_memory_symbol_names(file.id)?,
+            routes: self.get_routes_for_file(file.id)?,
+            env_usages: self.get_env_usages_for_file(file.id)?,
+            sql_queries: self.get_sql_queries_for_file(file.id)?,
+            error_sites: self.get_error_sites_for_file(file.id)?,
+            line_count: self.conn.query_row(
+                "SELECT line_count FROM files WHERE id = ?1",
+                params![file.id],
+                |r| r.get(0),
+            )?,
+            partial: self.is_file_partial(file.id)?,
+            generated: self.is_file_generated(file.id)?,
+            encoding: file.encoding.clone(),
+            redactions: self.get_file_redactions(file.id)?,
+        };
+        Ok((parked, symbols.iter().map(|s| s.id).collect()))
+    }
+
+    /// The snapshot of `path` parked under `branch`, if any.
+    pub fn get_parked_file(&self, repo_id: i64, branch: &str, path: &str) -> Result<Option<ParkedFile>> {
+        let snapshot: Option<String> = self
             .conn
             .query_row(
-                "SELECT id, file_id, name, kind, signature, body,
-                        body_hash, start_line, end_line, parent_id,
-                        qualified_name, source, manifest_repo
-                 FROM symbols WHERE name = ?1 ORDER BY id LIMIT 1",
-                params![name],
-                |row| {
-                    Ok(Symbol {
-                        id: row.get(0)?,
-                        file_id: row.get(1)?,
-                        name: row.get(2)?,
-                        qualified_name: row.get(10)?,
-                        kind: row.get(3)?,
-                        signature: row.get(4)?,
-                        body: row.get(5)?,
-                        body_hash: row.get(6)?,
-                        start_line: row.get(7)?,
-                        end_line: row.get(8)?,
-                        parent_id: row.get(9)?,
-                        source: row.get(11)?,
-                        manifest_repo: row.get(12)?,
-                    })
-                },
+                "SELECT snapshot FROM branch_files WHERE repo_id = ?1 AND branch = ?2 AND path = ?3",
+                params![repo_id, branch, path],
+                |row| row.get(0),
             )
             .optional()?;
-        Ok(r)
+        snapshot
+            .map(|s| serde_json::from_str(&s).context("corrupt branch_files snapshot"))
+            .transpose()
     }
 
-    /// Load all symbols in a repo as a HashMap keyed by name.
-    /// For ambiguous names, prefers functions/methods over types.
-    pub fn get_all_symbol_names_for_repo(
-        &self,
-        repo_id: i64,
-    ) -> Result<std::collections::HashMap<String, i64>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT s.id, s.name, s.kind, s.qualified_name FROM symbols s
+    /// Make a parked snapshot the live version of `path`: replaces the file's
+    /// symbols and relinks memories by name. Edges are not restored here —
+    /// their targets may live in files restored later in the same switch.
+    /// Returns the file id.
+    pub fn restore_parked_file(&self, repo_id: i64, path: &str, parked: &ParkedFile) -> Result<i64> {
+        self.restore_snapshot(repo_id, path, parked, &[])
+    }
+
+    /// `restore_parked_file`, giving each symbol the id at its position in
+    /// `symbol_ids` when that id is still free.
+    fn restore_snapshot(&self, repo_id: i64, path: &str, parked: &ParkedFile, symbol_ids: &[i64]) -> Result<i64> {
+        let facts = FileFacts {
+            line_count: parked.line_count,
+            partial: parked.partial,
+            generated: parked.generated,
+            // Snapshots taken before encodings were recorded.
+            encoding: if parked.encoding.is_empty() { "UTF-8" } else { &parked.encoding },
+            package: None,
+        };
+        let file_id = self.upsert_parsed_file(repo_id, path, &parked.language, &parked.hash, &facts)?;
+        self.set_file_redactions(file_id, parked.redactions)?;
+        self.delete_edges_by_file(file_id)?;
+        self.delete_symbols_by_file(file_id)?;
+
+        let mut ids: Vec<i64> = Vec::with_capacity(parked.symbols.len());
+        for (i, sym) in parked.symbols.iter().enumerate() {
+            // Parents precede children, so their ids already exist.
+            let parent_id = sym.parent.and_then(|i| ids.get(i).copied());
+            let preferred = match symbol_ids.get(i) {
+                Some(&id) if !self.symbol_exists(id)? => Some(id),
+                _ => None,
+            };
+            let id = self.insert_symbol_row(
+                preferred,
+                file_id,
+                &sym.name,
+                &sym.qualified_name,
+                &sym.kind,
+                &sym.signature,
+                &sym.body,
+                &sym.body_hash,
+                sym.start_line,
+                sym.end_line,
+                parent_id,
+                &SymbolDetails {
+                    visibility: &sym.visibility,
+                    modifiers: &sym.modifiers,
+                    signature_types: sym.signature_types.as_deref(),
+                    attributes: &sym.attributes,
+                },
+            )?;
+            ids.push(id);
+        }
+
+        // Skip memories deleted while the snapshot was parked.
+        let mut links = Vec::new();
+        for link in &parked.memory_links {
+            let exists: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM memories WHERE id = ?1)",
+                params![link.0],
+                |row| row.get(0),
+            )?;
+            if exists {
+                links.push(link.clone());
+            }
+        }
+        if !links.is_empty() {
+            self.relink_memories_to_symbols(file_id, &links)?;
+        }
+        self.replace_routes(file_id, &parked.routes)?;
+        self.replace_env_usages(file_id, &parked.env_usages)?;
+        self.replace_sql_queries(file_id, &parked.sql_queries)?;
+        self.replace_error_sites(file_id, &parked.error_sites)?;
+        Ok(file_id)
+    }
+
+    /// Drop every snapshot parked under `branch`.
+    pub fn delete_parked_files(&self, repo_id: i64, branch: &str) -> Result<usize> {
+        let n = self.conn.execute(
+            "DELETE FROM branch_files WHERE repo_id = ?1 AND branch = ?2",
+            params![repo_id, branch],
+        )?;
+        Ok(n)
+    }
+
+    /// Drop the snapshots of branches not in `branches` (those deleted from
+    /// git). Returns the number of snapshots removed.
+    pub fn prune_parked_branches(&self, repo_id: i64, branches: &[String]) -> Result<usize> {
+        let parked: Vec<String> = self.count_parked_files(repo_id)?.into_iter().map(|(b, _)| b).collect();
+        let mut removed = 0;
+        for branch in parked.iter().filter(|b| !branches.contains(b)) {
+            removed += self.delete_parked_files(repo_id, branch)?;
+        }
+        Ok(removed)
+    }
+
+    /// Number of parked file snapshots per branch for a repository.
+    pub fn count_parked_files(&self, repo_id: i64) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT branch, COUNT(*) FROM branch_files WHERE repo_id = ?1
+             GROUP BY branch ORDER BY branch",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    // -----------------------------------------------------------------------
+    // Tombstones
+    // -----------------------------------------------------------------------
+
+    /// Remove a deleted file from the live index, keeping a tombstone: its
+    /// snapshot, symbol ids, and the edges other files had into it. Path
+    /// memories are not marked stale while the tombstone lasts. Returns
+    /// false when the file was not indexed.
+    pub fn tombstone_file(&self, repo_id: i64, rel_path: &str) -> Result<bool> {
+        let Some(file) = self.get_file_by_path(repo_id, rel_path)? else {
+            return Ok(false);
+        };
+        let (snapshot, symbol_ids) = self.snapshot_file(&file)?;
+        let incoming = self.incoming_edges(file.id)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO tombstones (repo_id, path, hash, snapshot, symbol_ids, incoming_edges)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                repo_id,
+                rel_path,
+                file.hash,
+                serde_json::to_string(&snapshot)?,
+                serde_json::to_string(&symbol_ids)?,
+                serde_json::to_string(&incoming)?,
+            ],
+        )?;
+        self.remove_file(repo_id, rel_path)
+    }
+
+    /// Edges other files have into the symbols of `file_id`, kept by name so
+    /// they can be put back once the file's symbols are replaced.
+    pub fn incoming_edges(&self, file_id: i64) -> Result<Vec<TombstoneEdge>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT sf.repo_id, sf.path, COALESCE(NULLIF(src.qualified_name, ''), src.name),
+                    COALESCE(NULLIF(tgt.qualified_name, ''), tgt.name), e.kind, e.call_sites
+             FROM edges e
+             JOIN symbols tgt ON tgt.id = e.target_id
+             JOIN symbols src ON src.id = e.source_id
+             JOIN files sf ON sf.id = src.file_id
+             WHERE tgt.file_id = ?1 AND src.file_id != ?1",
+        )?;
+        let incoming = stmt
+            .query_map(params![file_id], |row| {
+                Ok(TombstoneEdge {
+                    source_repo_id: row.get(0)?,
+                    source_path: row.get(1)?,
+                    source: row.get(2)?,
+                    target: row.get(3)?,
+                    kind: row.get(4)?,
+                    call_sites: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(incoming)
+    }
+
+    /// Put back `edges` (from `incoming_edges`) into the current symbols of
+    /// `file_id`. Edges whose target or source symbol is gone are dropped.
+    pub fn restore_incoming_edges(&self, file_id: i64, edges: &[TombstoneEdge]) -> Result<()> {
+        if edges.is_empty() {
+            return Ok(());
+        }
+        let key = |s: &Symbol| if s.qualified_name.is_empty() { s.name.clone() } else { s.qualified_name.clone() };
+        let by_key: std::collections::HashMap<String, i64> =
+            self.get_symbols_by_file(file_id)?.iter().map(|s| (key(s), s.id)).collect();
+        for edge in edges {
+            let Some(&tgt) = by_key.get(&edge.target) else {
+                continue;
+            };
+            let source: Option<i64> = self
+                .conn
+                .query_row(
+                    "SELECT s.id FROM symbols s JOIN files f ON f.id = s.file_id
+                     WHERE f.repo_id = ?1 AND f.path = ?2
+                       AND COALESCE(NULLIF(s.qualified_name, ''), s.name) = ?3
+                     ORDER BY s.start_line LIMIT 1",
+                    params![edge.source_repo_id, edge.source_path, edge.source],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(src) = source {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO edges (source_id, target_id, kind, call_sites) VALUES (?1, ?2, ?3, ?4)",
+                    params![src, tgt, edge.kind, edge.call_sites],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove and return the tombstone of `path`, if any.
+    pub fn take_tombstone(&self, repo_id: i64, path: &str) -> Result<Option<Tombstone>> {
+        let tombstone = self.get_tombstone(repo_id, path)?;
+        if tombstone.is_some() {
+            self.delete_tombstone(repo_id, path)?;
+        }
+        Ok(tombstone)
+    }
+
+    /// Drop the tombstone of `path`, if any. Re-indexing a file that came
+    /// back changed does this in the same transaction as the write, so a
+    /// failed re-index leaves the tombstone in place.
+    pub fn delete_tombstone(&self, repo_id: i64, path: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM tombstones WHERE repo_id = ?1 AND path = ?2",
+            params![repo_id, path],
+        )?;
+        Ok(())
+    }
+
+    /// The tombstone of `path`, if any, left in place.
+    pub fn get_tombstone(&self, repo_id: i64, path: &str) -> Result<Option<Tombstone>> {
+        let row: Option<(String, String, String, String, String)> = self
+            .conn
+            .query_row(
+                "SELECT hash, snapshot, symbol_ids, incoming_edges, deleted_at
+                 FROM tombstones WHERE repo_id = ?1 AND path = ?2",
+                params![repo_id, path],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .optional()?;
+        let Some((hash, snapshot, symbol_ids, incoming_edges, deleted_at)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(Tombstone {
+            hash,
+            snapshot: serde_json::from_str(&snapshot).context("corrupt tombstone snapshot")?,
+            symbol_ids: serde_json::from_str(&symbol_ids).context("corrupt tombstone symbol ids")?,
+            // Tombstones written before edges were kept by name hold bare
+            // source ids, which can't be trusted; their edges are dropped.
+            incoming_edges: serde_json::from_str(&incoming_edges).unwrap_or_default(),
+            deleted_at,
+        }))
+    }
+
+    /// Bring a tombstoned file back as it was: same symbol ids where still
+    /// free, memory links, its outgoing edges, and incoming edges whose
+    /// source symbols are still in their file. Returns the file id.
+    pub fn restore_tombstone(&self, repo_id: i64, path: &str, tombstone: &Tombstone) -> Result<i64> {
+        let file_id = self.restore_snapshot(repo_id, path, &tombstone.snapshot, &tombstone.symbol_ids)?;
+        let file_symbols = self.get_symbols_by_file(file_id)?;
+        let local = |name: &str| file_symbols.iter().find(|s| s.name == name).map(|s| s.id);
+
+        let symbol_map = self.resolvable_symbol_names(repo_id)?;
+        for (from, to, kind) in &tombstone.snapshot.edges {
+            if let (Some(src), Some(&tgt)) = (local(from), symbol_map.get(to)) {
+                if src != tgt {
+                    self.insert_edge(src, tgt, kind)?;
+                }
+            }
+        }
+        self.restore_incoming_edges(file_id, &tombstone.incoming_edges)?;
+        Ok(file_id)
+    }
+
+    /// Drop tombstones older than `grace_hours`. Memories whose only symbol
+    /// links were in them, and path memories left without a file, are marked
+    /// stale. Returns the number purged.
+    pub fn purge_tombstones(&self, grace_hours: u64) -> Result<usize> {
+        let cutoff = format!("-{grace_hours} hours");
+        let mut stmt = self.conn.prepare(
+            "SELECT snapshot FROM tombstones WHERE deleted_at <= datetime('now', ?1)",
+        )?;
+        let snapshots: Vec<String> = stmt
+            .query_map(params![cutoff], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+        if snapshots.is_empty() {
+            return Ok(0);
+        }
+        let purged = self.conn.execute(
+            "DELETE FROM tombstones WHERE deleted_at <= datetime('now', ?1)",
+            params![cutoff],
+        )?;
+        for snapshot in &snapshots {
+            let Ok(parked) = serde_json::from_str::<ParkedFile>(snapshot) else {
+                continue;
+            };
+            for (memory_id, _, _) in &parked.memory_links {
+                self.conn.execute(
+                    "UPDATE memories SET stale = 1, stale_reason = ?2, stale_at = datetime('now')
+                     WHERE id = ?1 AND stale = 0
+                       AND NOT EXISTS (SELECT 1 FROM memory_symbols WHERE memory_id = ?1)",
+                    params![memory_id, StaleReason::FileDeleted.as_str()],
+                )?;
+            }
+        }
+        self.mark_orphaned_path_memories_stale()?;
+        Ok(purged)
+    }
+
+    /// Remove every indexed or tombstoned file matching `deny`, outright: no
+    /// tombstone is left to bring it back. Returns the number of indexed
+    /// files removed.
+    pub fn purge_denied_files(&self, deny: &DenyList) -> Result<usize> {
+        if deny.is_empty() {
+            return Ok(0);
+        }
+        let mut stmt = self.conn.prepare("SELECT repo_id, path FROM files")?;
+        let files: Vec<(i64, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+        let mut stmt = self.conn.prepare("SELECT repo_id, path FROM tombstones")?;
+        let tombstones: Vec<(i64, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        let denied: Vec<_> = files.into_iter().filter(|(_, path)| deny.is_denied(path)).collect();
+        let buried: Vec<_> = tombstones.into_iter().filter(|(_, path)| deny.is_denied(path)).collect();
+        if denied.is_empty() && buried.is_empty() {
+            return Ok(0);
+        }
+        self.with_transaction(|| {
+            let mut removed = 0;
+            for (repo_id, path) in &denied {
+                if self.remove_file(*repo_id, path)? {
+                    removed += 1;
+                }
+            }
+            for (repo_id, path) in &buried {
+                self.conn.execute(
+                    "DELETE FROM tombstones WHERE repo_id = ?1 AND path = ?2",
+                    params![repo_id, path],
+                )?;
+            }
+            self.mark_orphaned_path_memories_stale()?;
+            Ok(removed)
+        })
+    }
+
+    /// Tombstoned files per repository.
+    pub fn count_tombstones(&self, repo_id: i64) -> Result<i64> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM tombstones WHERE repo_id = ?1",
+            params![repo_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    fn symbol_exists(&self, symbol_id: i64) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM symbols WHERE id = ?1)",
+            params![symbol_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    // -----------------------------------------------------------------------
+    // File CRUD
+    // -----------------------------------------------------------------------
+
+    pub fn upsert_file(
+        &self,
+        repo_id: i64,
+        path: &str,
+        language: &str,
+        hash: &str,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO files (repo_id, path, language, hash, indexed_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))
+             ON CONFLICT(repo_id, path) DO UPDATE SET language   = excluded.language,
+                                                      hash       = excluded.hash,
+                                                      indexed_at = excluded.indexed_at",
+            params![repo_id, path, language, hash],
+        )?;
+        // Always SELECT — last_insert_rowid is unreliable on the UPDATE path
+        // of an ON CONFLICT upsert.
+        let id: i64 = self.conn.query_row(
+            "SELECT id FROM files WHERE repo_id = ?1 AND path = ?2",
+            params![repo_id, path],
+            |r| r.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// `upsert_file` for a parsed file, writing what the indexer learned
+    /// about it in the same statement. A `package` of None keeps the stored
+    /// one.
+    pub fn upsert_parsed_file(
+        &self,
+        repo_id: i64,
+        path: &str,
+        language: &str,
+        hash: &str,
+        facts: &FileFacts,
+    ) -> Result<i64> {
+        let id = self
+            .conn
+            .prepare_cached(
+                "INSERT INTO files (repo_id, path, language, hash, indexed_at, line_count, partial, generated, encoding, package)
+                 VALUES (?1, ?2, ?3, ?4, datetime('now'), ?5, ?6, ?7, ?8, COALESCE(?9, ''))
+                 ON CONFLICT(repo_id, path) DO UPDATE SET language   = excluded.language,
+                                                          hash       = excluded.hash,
+                                                          indexed_at = excluded.indexed_at,
+                                                          line_count = excluded.line_count,
+                                                          partial    = excluded.partial,
+                                                          generated  = excluded.generated,
+                                                          encoding   = excluded.encoding,
+                                                          package    = COALESCE(?9, package)
+                 RETURNING id",
+            )?
+            .query_row(
+                params![
+                    repo_id,
+                    path,
+                    language,
+                    hash,
+                    facts.line_count,
+                    facts.partial,
+                    facts.generated,
+                    facts.encoding,
+                    facts.package
+                ],
+                |r| r.get(0),
+            )?;
+        Ok(id)
+    }
+
+    pub fn is_file_generated(&self, file_id: i64) -> Result<bool> {
+        let generated = self
+            .conn
+            .query_row("SELECT generated FROM files WHERE id = ?1", params![file_id], |r| r.get(0))
+            .optional()?;
+        Ok(generated.unwrap_or(false))
+    }
+
+    /// The subsets of `symbol_ids` whose file was indexed without bodies and
+    /// whose file was produced by a code generator, in that order.
+    pub fn symbol_file_flags(
+        &self,
+        symbol_ids: &[i64],
+    ) -> Result<(std::collections::HashSet<i64>, std::collections::HashSet<i64>)> {
+        let (mut partial, mut generated) = (std::collections::HashSet::new(), std::collections::HashSet::new());
+        if symbol_ids.is_empty() {
+            return Ok((partial, generated));
+        }
+        let placeholders: String = symbol_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT s.id, f.partial, f.generated FROM symbols s
+             JOIN files f ON f.id = s.file_id
+             WHERE s.id IN ({placeholders}) AND (f.partial OR f.generated)"
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(symbol_ids), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?, row.get::<_, bool>(2)?))
+        })?;
+        for row in rows {
+            let (id, is_partial, is_generated) = row?;
+            if is_partial {
+                partial.insert(id);
+            }
+            if is_generated {
+                generated.insert(id);
+            }
+        }
+        Ok((partial, generated))
+    }
+
+    /// Record how many secrets were masked in the file's stored symbols.
+    pub fn set_file_redactions(&self, file_id: i64, redactions: usize) -> Result<()> {
+        self.conn.execute(
+            "UPDATE files SET redactions = ?2 WHERE id = ?1",
+            params![file_id, redactions as i64],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_file_redactions(&self, file_id: i64) -> Result<usize> {
+        let redactions: Option<i64> = self
+            .conn
+            .query_row("SELECT redactions FROM files WHERE id = ?1", params![file_id], |r| r.get(0))
+            .optional()?;
+        Ok(redactions.unwrap_or(0) as usize)
+    }
+
+    pub fn is_file_partial(&self, file_id: i64) -> Result<bool> {
+        let partial = self
+            .conn
+            .query_row("SELECT partial FROM files WHERE id = ?1", params![file_id], |r| r.get(0))
+            .optional()?;
+        Ok(partial.unwrap_or(false))
+    }
+
+    pub fn get_file_by_path(&self, repo_id: i64, path: &str) -> Result<Option<FileRecord>> {
+        let r = self
+            .conn
+            .query_row(
+                "SELECT id, repo_id, path, language, hash, indexed_at, encoding
+                 FROM files WHERE repo_id = ?1 AND path = ?2",
+                params![repo_id, path],
+                |row| {
+                    Ok(FileRecord {
+                        id: row.get(0)?,
+                        repo_id: row.get(1)?,
+                        path: row.get(2)?,
+                        language: row.get(3)?,
+                        hash: row.get(4)?,
+                        indexed_at: row.get(5)?,
+                        encoding: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(r)
+    }
+
+    pub fn get_file_by_id(&self, file_id: i64) -> Result<Option<FileRecord>> {
+        let r = self
+            .conn
+            .query_row(
+                "SELECT id, repo_id, path, language, hash, indexed_at, encoding
+                 FROM files WHERE id = ?1",
+                params![file_id],
+                |row| {
+                    Ok(FileRecord {
+                        id: row.get(0)?,
+                        repo_id: row.get(1)?,
+                        path: row.get(2)?,
+                        language: row.get(3)?,
+                        hash: row.get(4)?,
+                        indexed_at: row.get(5)?,
+                        encoding: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(r)
+    }
+
+    /// Remove a file and all its symbols/edges from the index.
+    /// Returns true if a file record was actually deleted.
+    pub fn remove_file(&self, repo_id: i64, rel_path: &str) -> Result<bool> {
+        let file_id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM files WHERE repo_id = ?1 AND path = ?2",
+                params![repo_id, rel_path],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let file_id = match file_id {
+            Some(id) => id,
+            None => return Ok(false),
+        };
+        self.delete_edges_by_file(file_id)?;
+        self.delete_symbols_by_file(file_id)?;
+        self.conn
+            .execute("DELETE FROM files WHERE id = ?1", params![file_id])?;
+        self.clear_index_errors(repo_id, Some(rel_path), None)?;
+        Ok(true)
+    }
+
+    /// Convert a client-supplied path to Focal's canonical repo-relative form.
+    /// Absolute paths under a known repository root have the root stripped
+    /// (the innermost root, for nested repositories); everything else is only
+    /// normalized (see `paths::normalize_path`).
+    pub fn to_repo_relative_path(&self, path: &str) -> Result<String> {
+        Ok(self.split_repo_path(path)?.0)
+    }
+
+    /// `to_repo_relative_path`, with the id of the repository whose root
+    /// contained the path when it was absolute.
+    fn split_repo_path(&self, path: &str) -> Result<(String, Option<i64>)> {
+        let normalized = paths::normalize_path(path);
+        if paths::is_absolute(&normalized) {
+            let innermost = self
+                .list_repositories()?
+                .into_iter()
+                .filter_map(|repo| Some((paths::strip_root(&normalized, &repo.root_path)?, Some(repo.id))))
+                .min_by_key(|(rel, _)| rel.len());
+            if let Some(found) = innermost {
+                return Ok(found);
+            }
+        }
+        Ok((normalized, None))
+    }
+
+    /// Resolve a client-supplied file path (absolute, repo-relative, or a
+    /// trailing suffix such as `handler.rs`) to an indexed file. An exact
+    /// repo-relative match wins; otherwise the shortest path ending in
+    /// `/<suffix>` is chosen.
+    pub fn resolve_file_path(
+        &self,
+        file_path: &str,
+        repo_name: Option<&str>,
+    ) -> Result<Option<ResolvedFile>> {
+        let normalized = paths::normalize_path(file_path);
+        let repo_name = repo_name.map(|n| self.current_repo_name(n)).transpose()?;
+        let repo_name = repo_name.as_deref();
+
+        // Absolute path: only the repository whose root contains it can match.
+        let mut repo_filter: Option<i64> = None;
+        let mut rel = normalized.clone();
+        if paths::is_absolute(&normalized) {
+            let repos = self.list_repositories()?;
+            let owner = repos.iter().find(|r| {
+                repo_name.is_none_or(|n| r.name == n)
+                    && paths::strip_root(&normalized, &r.root_path).is_some()
+            });
+            match owner {
+                Some(repo) => {
+                    rel = paths::strip_root(&normalized, &repo.root_path).unwrap_or_default();
+                    repo_filter = Some(repo.id);
+                }
+                None => return Ok(None),
+            }
+        }
+        if rel.is_empty() {
+            return Ok(None);
+        }
+
+        let sql = "SELECT f.id, r.name, f.path, r.root_path FROM files f
+             JOIN repositories r ON r.id = f.repo_id
+             WHERE (f.path = ?1
+                    OR (?4 = 0 AND length(f.path) > length(?1)
+                        AND substr(f.path, length(f.path) - length(?1)) = '/' || ?1))
+               AND (?2 IS NULL OR r.name = ?2)
+               AND (?3 IS NULL OR r.id = ?3)
+             ORDER BY f.path = ?1 DESC, length(f.path), f.path
+             LIMIT 1";
+        let exact_only = repo_filter.is_some() as i64;
+        let resolved = self
+            .conn
+            .query_row(sql, params![rel, repo_name, repo_filter, exact_only], |row| {
+                Ok(ResolvedFile {
+                    file_id: row.get(0)?,
+                    repo: row.get(1)?,
+                    file_path: row.get(2)?,
+                    repo_root: row.get(3)?,
+                })
+            })
+            .optional()?;
+        Ok(resolved)
+    }
+
+    /// The file holding `symbol_id`, with its repository.
+    pub fn resolved_file_for_symbol(&self, symbol_id: i64) -> Result<Option<ResolvedFile>> {
+        let resolved = self
+            .conn
+            .query_row(
+                "SELECT f.id, r.name, f.path, r.root_path FROM symbols s
+                 JOIN files f ON f.id = s.file_id
+                 JOIN repositories r ON r.id = f.repo_id
+                 WHERE s.id = ?1",
+                params![symbol_id],
+                |row| {
+                    Ok(ResolvedFile {
+                        file_id: row.get(0)?,
+                        repo: row.get(1)?,
+                        file_path: row.get(2)?,
+                        repo_root: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(resolved)
+    }
+
+    pub fn get_file_hash(&self, repo_id: i64, path: &str) -> Result<Option<String>> {
+        let r = self
+            .conn
+            .query_row(
+                "SELECT hash FROM files WHERE repo_id = ?1 AND path = ?2",
+                params![repo_id, path],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(r)
+    }
+
+    pub fn get_files_for_repo(&self, repo_id: i64) -> Result<Vec<FileRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, repo_id, path, language, hash, indexed_at, encoding
+             FROM files WHERE repo_id = ?1 ORDER BY path",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| {
+            Ok(FileRecord {
+                id: row.get(0)?,
+                repo_id: row.get(1)?,
+                path: row.get(2)?,
+                language: row.get(3)?,
+                hash: row.get(4)?,
+                indexed_at: row.get(5)?,
+                encoding: row.get(6)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    pub fn get_file_path_for_symbol(&self, symbol_id: i64) -> Result<String> {
+        let path: String = self.conn.query_row(
+            "SELECT f.path FROM files f
+             JOIN symbols s ON s.file_id = f.id
+             WHERE s.id = ?1",
+            params![symbol_id],
+            |row| row.get(0),
+        )?;
+        Ok(path)
+    }
+
+    /// When the file holding `symbol_id` was last (re-)indexed.
+    pub fn file_indexed_at_for_symbol(&self, symbol_id: i64) -> Result<Option<String>> {
+        let indexed_at: Option<Option<String>> = self
+            .conn
+            .query_row(
+                "SELECT f.indexed_at FROM files f JOIN symbols s ON s.file_id = f.id WHERE s.id = ?1",
+                params![symbol_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(indexed_at.flatten())
+    }
+
+    pub fn get_repo_root_for_symbol(&self, symbol_id: i64) -> Result<String> {
+        let root: String = self.conn.query_row(
+            "SELECT r.root_path FROM repositories r
+             JOIN files f ON f.repo_id = r.id
+             JOIN symbols s ON s.file_id = f.id
+             WHERE s.id = ?1",
+            params![symbol_id],
+            |row| row.get(0),
+        )?;
+        Ok(root)
+    }
+
+    // -----------------------------------------------------------------------
+    // Symbol CRUD
+    // -----------------------------------------------------------------------
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_symbol(
+        &self,
+        file_id: i64,
+        name: &str,
+        qualified_name: &str,
+        kind: &str,
+        signature: &str,
+        body: &str,
+        body_hash: &str,
+        start_line: i64,
+        end_line: i64,
+        parent_id: Option<i64>,
+    ) -> Result<i64> {
+        self.insert_symbol_with_details(
+            file_id,
+            name,
+            qualified_name,
+            kind,
+            signature,
+            body,
+            body_hash,
+            start_line,
+            end_line,
+            parent_id,
+            &SymbolDetails::default(),
+        )
+    }
+
+    /// `insert_symbol` with the grammar-level details written in the same
+    /// statement.
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_symbol_with_details(
+        &self,
+        file_id: i64,
+        name: &str,
+        qualified_name: &str,
+        kind: &str,
+        signature: &str,
+        body: &str,
+        body_hash: &str,
+        start_line: i64,
+        end_line: i64,
+        parent_id: Option<i64>,
+        details: &SymbolDetails,
+    ) -> Result<i64> {
+        self.insert_symbol_row(
+            None,
+            file_id,
+            name,
+            qualified_name,
+            kind,
+            signature,
+            body,
+            body_hash,
+            start_line,
+            end_line,
+            parent_id,
+            details,
+        )
+    }
+
+    /// `insert_symbol_with_details` with an explicit id (None assigns the
+    /// next one).
+    #[allow(clippy::too_many_arguments)]
+    fn insert_symbol_row(
+        &self,
+        id: Option<i64>,
+        file_id: i64,
+        name: &str,
+        qualified_name: &str,
+        kind: &str,
+        signature: &str,
+        body: &str,
+        body_hash: &str,
+        start_line: i64,
+        end_line: i64,
+        parent_id: Option<i64>,
+        details: &SymbolDetails,
+    ) -> Result<i64> {
+        self.conn
+            .prepare_cached(
+                "INSERT INTO symbols (id, file_id, name, qualified_name, kind, signature, body, body_hash,
+                                      start_line, end_line, parent_id, visibility, modifiers, signature_types, attributes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            )?
+            .execute(params![
+                id,
+                file_id,
+                name,
+                qualified_name,
+                kind,
+                signature,
+                body,
+                body_hash,
+                start_line,
+                end_line,
+                parent_id,
+                details.visibility,
+                details.modifiers,
+                details.signature_types,
+                details.attributes,
+            ])?;
+        let id = self.conn.last_insert_rowid();
+        // Maintain FTS index incrementally
+        self.conn
+            .prepare_cached("INSERT INTO symbols_fts(rowid, name, signature, body) VALUES (?1, ?2, ?3, ?4)")?
+            .execute(params![id, name, signature, body])?;
+        Ok(id)
+    }
+
+    /// Replace the grammar-level details of an existing symbol.
+    pub fn set_symbol_details(
+        &self,
+        symbol_id: i64,
+        visibility: &str,
+        modifiers: &str,
+        signature_types: Option<&str>,
+        attributes: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE symbols SET visibility = ?2, modifiers = ?3, signature_types = ?4, attributes = ?5
+             WHERE id = ?1",
+            params![symbol_id, visibility, modifiers, signature_types, attributes],
+        )?;
+        Ok(())
+    }
+
+    /// Symbols in `file_id` whose line range contains `line`, outermost first;
+    /// the last entry is the innermost. Nesting is by `parent_id` where known
+    /// and by range width otherwise.
+    pub fn symbols_at_line(&self, file_id: i64, line: i64) -> Result<Vec<Symbol>> {
+        let mut chain: Vec<Symbol> = self
+            .get_symbols_by_file(file_id)?
+            .into_iter()
+            .filter(|s| s.start_line <= line && line <= s.end_line)
+            .collect();
+        chain.sort_by_key(|s| (std::cmp::Reverse(s.end_line - s.start_line), s.parent_id.is_some(), s.start_line));
+        Ok(chain)
+    }
+
+    /// Distinct files containing any of `symbol_ids`.
+    pub fn file_ids_for_symbols(&self, symbol_ids: &[i64]) -> Result<Vec<i64>> {
+        if symbol_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders: String = symbol_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT DISTINCT file_id FROM symbols WHERE id IN ({placeholders}) ORDER BY file_id"
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(symbol_ids), |row| row.get(0))?;
+        Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// On-disk paths of `file_ids` (repository root joined with the
+    /// repo-relative path). Unknown ids are skipped.
+    pub fn absolute_file_paths(&self, file_ids: &[i64]) -> Result<Vec<std::path::PathBuf>> {
+        if file_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders: String = file_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT r.root_path, f.path FROM files f
+             JOIN repositories r ON r.id = f.repo_id
+             WHERE f.id IN ({placeholders}) ORDER BY f.id"
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(file_ids), |row| {
+            let root: String = row.get(0)?;
+            let path: String = row.get(1)?;
+            Ok(std::path::Path::new(&root).join(path))
+        })?;
+        Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// File id → (repo id, repo-relative path) for each of `file_ids`.
+    /// Unknown ids are skipped.
+    pub fn file_locations(&self, file_ids: &[i64]) -> Result<std::collections::HashMap<i64, (i64, String)>> {
+        if file_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+        let placeholders: String = file_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let mut stmt = self.conn.prepare(&format!("SELECT id, repo_id, path FROM files WHERE id IN ({placeholders})"))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(file_ids), |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?;
+        Ok(rows.collect::<std::result::Result<_, _>>()?)
+    }
+
+    /// `(file_id, repo root, repo-relative path, indexed_at as unix seconds)`
+    /// for each of `file_ids`; files never stamped report 0.
+    pub fn file_index_times(&self, file_ids: &[i64]) -> Result<Vec<(i64, String, String, i64)>> {
+        if file_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders: String = file_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT f.id, r.root_path, f.path, COALESCE(CAST(strftime('%s', f.indexed_at) AS INTEGER), 0)
+             FROM files f
+             JOIN repositories r ON r.id = f.repo_id
+             WHERE f.id IN ({placeholders}) ORDER BY f.id"
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(file_ids), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+        Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// Mark a file as checked now without re-parsing it (its mtime moved but
+    /// its contents did not).
+    pub fn touch_file_indexed_at(&self, file_id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE files SET indexed_at = datetime('now') WHERE id = ?1",
+            params![file_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_symbols_by_file(&self, file_id: i64) -> Result<Vec<Symbol>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, file_id, name, kind, signature, body, body_hash,
+                    start_line, end_line, parent_id, qualified_name, source, manifest_repo, visibility, modifiers, signature_types, attributes
+             FROM symbols WHERE file_id = ?1 ORDER BY start_line",
+        )?;
+        let rows = stmt.query_map(params![file_id], |row| Symbol::from_row(row, 0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    pub fn delete_symbols_by_file(&self, file_id: i64) -> Result<usize> {
+        // Remove from FTS index before deleting the content rows
+        self.conn.execute(
+            "DELETE FROM symbols_fts WHERE rowid IN (SELECT id FROM symbols WHERE file_id = ?1)",
+            params![file_id],
+        )?;
+        let count = self
+            .conn
+            .execute("DELETE FROM symbols WHERE file_id = ?1", params![file_id])?;
+        Ok(count)
+    }
+
+    pub fn find_symbol_by_name(&self, repo_id: i64, name: &str) -> Result<Option<Symbol>> {
+        let r = self
+            .conn
+            .query_row(
+                "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
+                        s.body_hash, s.start_line, s.end_line, s.parent_id,
+                        s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes
+                 FROM symbols s
+                 JOIN files f ON f.id = s.file_id
+                 WHERE f.repo_id = ?1 AND s.name = ?2
+                 ORDER BY s.id LIMIT 1",
+                params![repo_id, name],
+                |row| Symbol::from_row(row, 0),
+            )
+            .optional()?;
+        Ok(r)
+    }
+
+    /// Every symbol named `name`, in one repository or all of them, including
+    /// methods stored under their type (`Server.name`, `Wrapper::name`).
+    pub fn symbols_named(&self, repo_id: Option<i64>, name: &str) -> Result<Vec<Symbol>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
+                    s.body_hash, s.start_line, s.end_line, s.parent_id,
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes
+             FROM symbols s
+             JOIN files f ON f.id = s.file_id
+             WHERE (?1 IS NULL OR f.repo_id = ?1)
+               AND (s.name = ?2
+                    OR substr(s.name, -length(?2) - 1) = '.' || ?2
+                    OR substr(s.name, -length(?2) - 2) = '::' || ?2)
+             ORDER BY f.path, s.start_line, s.id",
+        )?;
+        let rows = stmt.query_map(params![repo_id, name], |row| Symbol::from_row(row, 0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    pub fn get_symbol_by_id(&self, id: i64) -> Result<Option<Symbol>> {
+        let r = self
+            .conn
+            .query_row(
+                "SELECT id, file_id, name, kind, signature, body,
+                        body_hash, start_line, end_line, parent_id,
+                        qualified_name, source, manifest_repo, visibility, modifiers, signature_types, attributes
+                 FROM symbols WHERE id = ?1",
+                params![id],
+                |row| Symbol::from_row(row, 0),
+            )
+            .optional()?;
+        Ok(r)
+    }
+
+    pub fn find_symbol_by_name_any(&self, name: &str) -> Result<Option<Symbol>> {
+        let r = self
+            .conn
+            .query_row(
+                "SELECT id, file_id, name, kind, signature, body,
+                        body_hash, start_line, end_line, parent_id,
+                        qualified_name, source, manifest_repo, visibility, modifiers, signature_types, attributes
+                 FROM symbols WHERE name = ?1 ORDER BY id LIMIT 1",
+                params![name],
+                |row| Symbol::from_row(row, 0),
+            )
+            .optional()?;
+        Ok(r)
+    }
+
+    /// The symbol a name mentioned in prose refers to, in `repo_id` (None:
+    /// any repository). Matches a symbol name or qualified name exactly;
+    /// a qualified mention (`Config::new`, `utils.parse`) also matches the
+    /// end of a qualified name, with `::` and `.` interchangeable.
+    pub fn find_symbol_by_mention(&self, repo_id: Option<i64>, mention: &str) -> Result<Option<Symbol>> {
+        let qualified = mention.contains("::") || mention.contains('.');
+        let mut variants = vec![mention.to_string()];
+        if qualified {
+            for variant in [mention.replace("::", "."), mention.replace('.', "::")] {
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
+                    s.body_hash, s.start_line, s.end_line, s.parent_id,
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes
+             FROM symbols s
+             JOIN files f ON f.id = s.file_id
+             WHERE (?1 IS NULL OR f.repo_id = ?1)
+               AND (s.name = ?2 OR s.qualified_name = ?2
+                    OR (?3 AND (substr(s.qualified_name, -length(?2) - 1) = '.' || ?2
+                                OR substr(s.qualified_name, -length(?2) - 2) = '::' || ?2)))
+             ORDER BY (s.name = ?2 OR s.qualified_name = ?2) DESC, s.id
+             LIMIT 1",
+        )?;
+        for variant in &variants {
+            let found = stmt
+                .query_row(params![repo_id, variant, qualified], |row| Symbol::from_row(row, 0))
+                .optional()?;
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+
+    /// The names references in `repo_id` resolve against: its own symbols
+    /// and, behind them, those of repositories sharing a project with it
+    /// (`[projects]`).
+    pub fn resolvable_symbol_names(&self, repo_id: i64) -> Result<std::collections::HashMap<String, i64>> {
+        let mut map = self.get_all_symbol_names_for_repo(repo_id)?;
+        for sibling in self.project_sibling_repo_ids(repo_id)? {
+            for (name, id) in self.get_all_symbol_names_for_repo(sibling)? {
+                map.entry(name).or_insert(id);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Load all symbols in a repo as a HashMap keyed by name.
+    /// For ambiguous names, prefers functions/methods over types.
+    pub fn get_all_symbol_names_for_repo(
+        &self,
+        repo_id: i64,
+    ) -> Result<std::collections::HashMap<String, i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, s.name, s.kind, s.qualified_name FROM symbols s
              JOIN files f ON f.id = s.file_id
              WHERE f.repo_id = ?1
              ORDER BY CASE s.kind
                 WHEN 'function' THEN 0
                 WHEN 'method' THEN 1
                 ELSE 2
-             END",
+             END, s.id",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(3)?))
+        })?;
+        let mut map = std::collections::HashMap::new();
+        for r in rows {
+            let (id, name, qname) = r?;
+            map.entry(name).or_insert(id); // first wins (function/method preferred)
+            // Also index by qualified_name for cross-repo edge resolution
+            if !qname.is_empty() {
+                map.entry(qname).or_insert(id);
+            }
+        }
+        // Add shorter aliases for qualified names (e.g., "Config::new" → "new",
+        // Go's "Server.Start" → "Start", and every dotted suffix, so
+        // "checks.Orders.parse" → "Orders.parse" and "parse"). Only insert if
+        // no existing entry — standalone symbols take priority, then the
+        // lowest id.
+        let mut aliases: Vec<(String, i64)> = map
+            .iter()
+            .flat_map(|(name, &id)| {
+                let tail = name.rfind("::").map_or(name.as_str(), |pos| &name[pos + 2..]);
+                tail.match_indices('.')
+                    .map(|(pos, _)| &tail[pos + 1..])
+                    .chain((tail.len() < name.len()).then_some(tail))
+                    .map(move |short| (short.to_string(), id))
+            })
+            .collect();
+        aliases.sort_by_key(|&(_, id)| id);
+        for (short_name, id) in aliases {
+            map.entry(short_name).or_insert(id);
+        }
+        Ok(map)
+    }
+
+    /// `symbols` as query results: file path and linked memories filled in,
+    /// the presentation-layer fields (owners, coverage, packages) left empty.
+    pub fn symbol_results(&self, symbols: &[Symbol]) -> Vec<SymbolResult> {
+        let sym_ids: Vec<i64> = symbols.iter().map(|s| s.id).collect();
+        let mem_map = self.get_memories_for_symbols_batch(&sym_ids, false).unwrap_or_default();
+
+        symbols
+            .iter()
+            .map(|sym| {
+                let file_path = self
+                    .get_file_path_for_symbol(sym.id)
+                    .unwrap_or_else(|_| "<unknown>".to_string());
+                let memories = mem_map.get(&sym.id).cloned().unwrap_or_default();
+                SymbolResult {
+                    id: sym.id,
+                    name: sym.name.clone(),
+                    kind: sym.kind.clone(),
+                    signature: sym.signature.clone(),
+                    body: sym.body.clone(),
+                    file_path,
+                    repo_name: String::new(),
+                    start_line: sym.start_line,
+                    end_line: sym.end_line,
+                    memories,
+                    dependency_hints: Vec::new(),
+                    source: sym.source.clone(),
+                    manifest_repo: sym.manifest_repo.clone(),
+                    visibility: sym.visibility.clone(),
+                    modifiers: sym.modifiers.split_whitespace().map(String::from).collect(),
+                    types: parse_signature_types(sym.signature_types.clone()),
+                    attributes: sym.attributes.lines().map(String::from).collect(),
+                    owners: Vec::new(),
+                    coverage: None,
+                    partial: false,
+                    generated: false,
+                    package: String::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Rich symbol query: returns symbols with file path, repo name, and linked memories.
+    /// Filters are all optional — pass empty string or None to skip.
+    pub fn query_symbols_full(
+        &self,
+        name: &str,
+        kind: &str,
+        repo_name: &str,
+        visibility: &str,
+        attribute: &str,
+    ) -> Result<Vec<SymbolResult>> {
+        let mut sql = String::from(
+            "SELECT s.id, s.name, s.kind, s.signature, s.body, s.body_hash,
+                    f.path, r.name, s.start_line, s.end_line, s.source, s.manifest_repo,
+                    s.visibility, s.modifiers, s.signature_types, s.attributes
+             FROM symbols s
+             JOIN files f ON f.id = s.file_id
+             JOIN repositories r ON r.id = f.repo_id
+             WHERE 1=1",
+        );
+        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+        let mut idx = 1;
+
+        if !name.is_empty() {
+            sql.push_str(&format!(" AND s.name LIKE ?{idx}"));
+            param_values.push(Box::new(format!("%{name}%")));
+            idx += 1;
+        }
+        if !kind.is_empty() {
+            sql.push_str(&format!(" AND s.kind = ?{idx}"));
+            param_values.push(Box::new(kind.to_string()));
+            idx += 1;
+        }
+        if !repo_name.is_empty() {
+            sql.push_str(&format!(" AND r.name = ?{idx}"));
+            param_values.push(Box::new(self.current_repo_name(repo_name)?));
+            idx += 1;
+        }
+        if !visibility.is_empty() {
+            sql.push_str(&format!(" AND s.visibility = ?{idx}"));
+            param_values.push(Box::new(visibility.to_string()));
+            idx += 1;
+        }
+        if !attribute.is_empty() {
+            sql.push_str(&format!(" AND s.attributes LIKE ?{idx} ESCAPE '\\'"));
+            param_values.push(Box::new(format!("%{}%", like_escape(attribute))));
+            let _ = idx; // suppress unused warning
+        }
+
+        sql.push_str(" ORDER BY s.name, f.path, s.start_line, s.id LIMIT 200");
+
+        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|b| b.as_ref()).collect();
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_refs.as_slice(), |row| {
+            Ok(SymbolResult {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                kind: row.get(2)?,
+                signature: row.get(3)?,
+                body: row.get(4)?,
+                file_path: row.get(6)?,
+                repo_name: row.get(7)?,
+                start_line: row.get(8)?,
+                end_line: row.get(9)?,
+                memories: Vec::new(), // filled below
+                dependency_hints: Vec::new(), // filled later if requested
+                source: row.get(10)?,
+                manifest_repo: row.get(11)?,
+                visibility: row.get(12)?,
+                modifiers: row
+                    .get::<_, String>(13)?
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect(),
+                types: parse_signature_types(row.get(14)?),
+                attributes: row
+                    .get::<_, String>(15)?
+                    .lines()
+                    .map(String::from)
+                    .collect(),
+                owners: Vec::new(),
+                coverage: None,
+                partial: false,
+                generated: false,
+                package: String::new(),
+            })
+        })?;
+
+        let mut results: Vec<SymbolResult> = Vec::new();
+        for r in rows {
+            results.push(r?);
+        }
+
+        // Batch-load memories for all symbols in one query (avoids N+1)
+        let sym_ids: Vec<i64> = results.iter().map(|s| s.id).collect();
+        let mem_map = self.get_memories_for_symbols_batch(&sym_ids, false)?;
+        for sym in &mut results {
+            sym.memories = mem_map.get(&sym.id).cloned().unwrap_or_default();
+        }
+
+        Ok(results)
+    }
+
+    // -----------------------------------------------------------------------
+    // Edge CRUD
+    // -----------------------------------------------------------------------
+
+    pub fn insert_edge(&self, source_id: i64, target_id: i64, kind: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO edges (source_id, target_id, kind) VALUES (?1, ?2, ?3)",
+            params![source_id, target_id, kind],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Insert an edge together with the argument kinds of its call sites
+    /// (distinct lists, at most `MAX_CALL_SITES`). An existing edge gets
+    /// `call_sites` replaced; no sites store NULL.
+    pub fn insert_edge_with_call_sites(
+        &self,
+        source_id: i64,
+        target_id: i64,
+        kind: &str,
+        call_sites: &[Vec<String>],
+    ) -> Result<()> {
+        let sites = match call_sites {
+            [] => None,
+            sites => Some(serde_json::to_string(&sites[..sites.len().min(MAX_CALL_SITES)])?),
+        };
+        self.conn
+            .prepare_cached(
+                "INSERT INTO edges (source_id, target_id, kind, call_sites) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(source_id, target_id, kind) DO UPDATE SET call_sites = excluded.call_sites",
+            )?
+            .execute(params![source_id, target_id, kind, sites])?;
+        Ok(())
+    }
+
+    /// Outgoing edges: symbols that `symbol_id` depends on.
+    pub fn get_dependencies(&self, symbol_id: i64) -> Result<Vec<(Edge, Symbol)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.id, e.source_id, e.target_id, e.kind,
+                    s.id, s.file_id, s.name, s.kind, s.signature, s.body,
+                    s.body_hash, s.start_line, s.end_line, s.parent_id,
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes,
+                    e.call_sites
+             FROM edges e
+             JOIN symbols s ON s.id = e.target_id
+             JOIN files f ON f.id = s.file_id
+             WHERE e.source_id = ?1
+             ORDER BY s.name, f.path, s.start_line, e.kind",
+        )?;
+        let rows = stmt.query_map(params![symbol_id], |row| {
+            Ok((
+                Edge {
+                    id: row.get(0)?,
+                    source_id: row.get(1)?,
+                    target_id: row.get(2)?,
+                    kind: row.get(3)?,
+                    call_sites: parse_call_sites(row.get(21)?),
+                },
+                Symbol::from_row(row, 4)?,
+            ))
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Incoming edges: symbols that depend on `symbol_id`.
+    pub fn get_dependents(&self, symbol_id: i64) -> Result<Vec<(Edge, Symbol)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.id, e.source_id, e.target_id, e.kind,
+                    s.id, s.file_id, s.name, s.kind, s.signature, s.body,
+                    s.body_hash, s.start_line, s.end_line, s.parent_id,
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes,
+                    e.call_sites
+             FROM edges e
+             JOIN symbols s ON s.id = e.source_id
+             JOIN files f ON f.id = s.file_id
+             WHERE e.target_id = ?1
+             ORDER BY s.name, f.path, s.start_line, e.kind",
+        )?;
+        let rows = stmt.query_map(params![symbol_id], |row| {
+            Ok((
+                Edge {
+                    id: row.get(0)?,
+                    source_id: row.get(1)?,
+                    target_id: row.get(2)?,
+                    kind: row.get(3)?,
+                    call_sites: parse_call_sites(row.get(21)?),
+                },
+                Symbol::from_row(row, 4)?,
+            ))
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Return dependency hints for a symbol: names and kinds of symbols it
+    /// depends on via type_ref or imports edges. Used to warn the LLM about
+    /// interfaces/traits not included in the current context. External stubs
+    /// are named by their qualified name (`axum::get`).
+    pub fn get_dependency_hint_names(
+        &self,
+        symbol_id: i64,
+        _exclude_ids: &std::collections::HashSet<i64>,
+    ) -> Result<Vec<(String, String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT CASE s.kind WHEN 'external' THEN s.qualified_name ELSE s.name END, s.kind, e.kind
+             FROM edges e
+             JOIN symbols s ON s.id = e.target_id
+             WHERE e.source_id = ?1
+               AND e.kind IN ('type_ref', 'imports', 'calls')
+             ORDER BY s.name, s.id, e.kind",
+        )?;
+        let rows = stmt.query_map(params![symbol_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        let mut hints = Vec::new();
+        for r in rows {
+            let (name, kind, edge_kind) = r?;
+            // Only hint about symbols not already in the result set
+            // We can't check by ID here (we have names), so caller filters by ID
+            hints.push((name, kind, edge_kind));
+        }
+        Ok(hints)
+    }
+
+    /// Delete `kind` edges leaving symbols of `repo_id`.
+    pub fn delete_edges_of_kind(&self, repo_id: i64, kind: &str) -> Result<usize> {
+        Ok(self.conn.execute(
+            "DELETE FROM edges WHERE kind = ?2 AND source_id IN (
+                 SELECT s.id FROM symbols s JOIN files f ON f.id = s.file_id WHERE f.repo_id = ?1
+             )",
+            params![repo_id, kind],
+        )?)
+    }
+
+    pub fn delete_edges_by_file(&self, file_id: i64) -> Result<usize> {
+        let c1 = self.conn.execute(
+            "DELETE FROM edges WHERE source_id IN (SELECT id FROM symbols WHERE file_id = ?1)",
+            params![file_id],
+        )?;
+        let c2 = self.conn.execute(
+            "DELETE FROM edges WHERE target_id IN (SELECT id FROM symbols WHERE file_id = ?1)",
+            params![file_id],
+        )?;
+        Ok(c1 + c2)
+    }
+
+    // -----------------------------------------------------------------------
+    // Graph snapshots
+    // -----------------------------------------------------------------------
+
+    /// Snapshot key of every symbol in `repo_id` (see `GRAPH_KEYS`).
+    pub fn graph_symbol_keys(&self, repo_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("WITH {GRAPH_KEYS} SELECT key FROM graph_keys WHERE repo_id = ?1"))?;
+        let rows = stmt.query_map(params![repo_id], |row| row.get(0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Edges leaving `repo_id`'s symbols as `(source, target, kind)`, with
+    /// symbols as snapshot keys; targets may be in other repositories.
+    pub fn graph_edge_keys(&self, repo_id: i64) -> Result<Vec<(String, String, String)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "WITH {GRAPH_KEYS}
+             SELECT sk.key, tk.key, e.kind FROM edges e
+             JOIN graph_keys sk ON sk.id = e.source_id
+             JOIN graph_keys tk ON tk.id = e.target_id
+             WHERE sk.repo_id = ?1"
+        ))?;
+        let rows = stmt.query_map(params![repo_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Store a snapshot row; `graph` is the serialized symbol and edge lists.
+    pub fn insert_graph_snapshot(
+        &self,
+        repo_id: i64,
+        symbol_count: usize,
+        edge_count: usize,
+        graph_hash: &str,
+        graph: &str,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO graph_snapshots (repo_id, symbol_count, edge_count, graph_hash, graph)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![repo_id, symbol_count as i64, edge_count as i64, graph_hash, graph],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Id and graph hash of `repo_id`'s newest snapshot.
+    pub fn latest_graph_hash(&self, repo_id: i64) -> Result<Option<(i64, String)>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id, graph_hash FROM graph_snapshots WHERE repo_id = ?1 ORDER BY id DESC LIMIT 1",
+                params![repo_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    /// Mark snapshot `id` as taken now, for a run that left the graph as it was.
+    pub fn touch_graph_snapshot(&self, id: i64) -> Result<()> {
+        self.conn.execute("UPDATE graph_snapshots SET taken_at = datetime('now') WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Keep `repo_id`'s newest `keep` snapshots, and the symbol and edge
+    /// lists of only the newest `keep_graph`.
+    pub fn prune_graph_snapshots(&self, repo_id: i64, keep: usize, keep_graph: usize) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM graph_snapshots WHERE repo_id = ?1 AND id NOT IN
+                (SELECT id FROM graph_snapshots WHERE repo_id = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![repo_id, keep as i64],
+        )?;
+        self.conn.execute(
+            "UPDATE graph_snapshots SET graph = NULL WHERE repo_id = ?1 AND graph IS NOT NULL AND id NOT IN
+                (SELECT id FROM graph_snapshots WHERE repo_id = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![repo_id, keep_graph as i64],
+        )?;
+        Ok(())
+    }
+
+    /// `repo_id`'s newest `limit` snapshots, newest first.
+    pub fn recent_graph_snapshots(&self, repo_id: i64, limit: usize) -> Result<Vec<StoredGraphSnapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, taken_at, symbol_count, edge_count, graph_hash, graph FROM graph_snapshots
+             WHERE repo_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![repo_id, limit as i64], |row| {
+            Ok(StoredGraphSnapshot {
+                id: row.get(0)?,
+                taken_at: row.get(1)?,
+                symbol_count: row.get::<_, i64>(2)? as usize,
+                edge_count: row.get::<_, i64>(3)? as usize,
+                graph_hash: row.get(4)?,
+                graph: row.get(5)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    // -----------------------------------------------------------------------
+    // Module metrics
+    // -----------------------------------------------------------------------
+
+    /// Path and symbol count of every file in `repo_id`.
+    pub fn file_symbol_counts(&self, repo_id: i64) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT f.path, (SELECT COUNT(*) FROM symbols s WHERE s.file_id = f.id)
+             FROM files f WHERE f.repo_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Edges between two symbols of `repo_id`, as `(source id, source file
+    /// path, target id, target file path)`.
+    pub fn repo_internal_edges(&self, repo_id: i64) -> Result<Vec<(i64, String, i64, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.source_id, sf.path, e.target_id, tf.path FROM edges e
+             JOIN symbols s ON s.id = e.source_id
+             JOIN files sf ON sf.id = s.file_id
+             JOIN symbols t ON t.id = e.target_id
+             JOIN files tf ON tf.id = t.file_id
+             WHERE sf.repo_id = ?1 AND tf.repo_id = ?1",
         )?;
         let rows = stmt.query_map(params![repo_id], |row| {
-            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(3)?))
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
         })?;
-        let mut map = std::collections::HashMap::new();
-        for r in rows {
-            let (id, name, qname) = r?;
-            map.entry(name).or_insert(id); // first wins (function/method preferred)
-            // Also index by qualified_name for cross-repo edge resolution
-            if !qname.is_empty() {
-                map.entry(qname).or_insert(id);
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Replace the stored coupling metrics of `repo_id`.
+    pub fn replace_module_metrics(&self, repo_id: i64, metrics: &[ModuleMetrics]) -> Result<()> {
+        self.conn.execute("DELETE FROM module_metrics WHERE repo_id = ?1", params![repo_id])?;
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO module_metrics
+                (repo_id, module, files, symbols, afferent, efferent, instability, internal_edges, cohesion)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for m in metrics {
+            stmt.execute(params![
+                repo_id,
+                m.module,
+                m.files as i64,
+                m.symbols as i64,
+                m.afferent as i64,
+                m.efferent as i64,
+                m.instability,
+                m.internal_edges as i64,
+                m.cohesion,
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Stored coupling metrics of `repo_id`, by module path; empty if none
+    /// were computed yet.
+    pub fn get_module_metrics(&self, repo_id: i64) -> Result<Vec<ModuleMetrics>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT module, files, symbols, afferent, efferent, instability, internal_edges, cohesion
+             FROM module_metrics WHERE repo_id = ?1 ORDER BY module",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| {
+            Ok(ModuleMetrics {
+                module: row.get(0)?,
+                files: row.get::<_, i64>(1)? as usize,
+                symbols: row.get::<_, i64>(2)? as usize,
+                afferent: row.get::<_, i64>(3)? as usize,
+                efferent: row.get::<_, i64>(4)? as usize,
+                instability: row.get(5)?,
+                internal_edges: row.get::<_, i64>(6)? as usize,
+                cohesion: row.get(7)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    // -----------------------------------------------------------------------
+    // Route CRUD
+    // -----------------------------------------------------------------------
+
+    /// Replace the routes registered in a file with `routes`.
+    pub fn replace_routes(&self, file_id: i64, routes: &[ExtractedRoute]) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM routes WHERE file_id = ?1", params![file_id])?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO routes (file_id, method, path, handler, line) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for r in routes {
+            stmt.execute(params![file_id, r.method, r.path, r.handler, r.line as i64])?;
+        }
+        Ok(())
+    }
+
+    pub fn get_routes_for_file(&self, file_id: i64) -> Result<Vec<ExtractedRoute>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT method, path, handler, line FROM routes WHERE file_id = ?1 ORDER BY line, id",
+        )?;
+        let rows = stmt.query_map(params![file_id], |row| {
+            Ok(ExtractedRoute {
+                method: row.get(0)?,
+                path: row.get(1)?,
+                handler: row.get(2)?,
+                line: row.get::<_, i64>(3)? as usize,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Routes across the index, optionally narrowed to one repository, an
+    /// HTTP method (case-insensitive; `ANY` registrations always match) and a
+    /// path prefix. Handlers resolve by name, preferring the registering file.
+    pub fn list_routes(
+        &self,
+        repo_id: Option<i64>,
+        method: &str,
+        path_prefix: &str,
+    ) -> Result<Vec<RouteEntry>> {
+        let mut stmt = self.conn.prepare(
+            "WITH matched AS (
+                 SELECT r.method, r.path, r.handler, r.line, f.path AS file_path,
+                        repo.name AS repo_name,
+                        COALESCE(
+                            (SELECT s.id FROM symbols s
+                             WHERE s.file_id = r.file_id
+                               AND (s.name = r.handler OR s.qualified_name = r.handler)
+                             ORDER BY s.id LIMIT 1),
+                            (SELECT s.id FROM symbols s
+                             JOIN files sf ON sf.id = s.file_id
+                             WHERE sf.repo_id = f.repo_id
+                               AND (s.name = r.handler OR s.qualified_name = r.handler)
+                             ORDER BY s.id LIMIT 1)
+                        ) AS handler_id
+                 FROM routes r
+                 JOIN files f ON f.id = r.file_id
+                 JOIN repositories repo ON repo.id = f.repo_id
+                 WHERE (?1 IS NULL OR f.repo_id = ?1)
+                   AND (?2 = '' OR r.method = upper(?2) OR r.method = 'ANY')
+                   AND substr(r.path, 1, length(?3)) = ?3
+             )
+             SELECT m.method, m.path, m.handler, m.repo_name, m.file_path, m.line,
+                    hf.path, hs.start_line
+             FROM matched m
+             LEFT JOIN symbols hs ON hs.id = m.handler_id
+             LEFT JOIN files hf ON hf.id = hs.file_id
+             ORDER BY m.repo_name, m.path, m.method, m.file_path, m.line",
+        )?;
+        let rows = stmt.query_map(params![repo_id, method, path_prefix], |row| {
+            Ok(RouteEntry {
+                method: row.get(0)?,
+                path: row.get(1)?,
+                handler: row.get(2)?,
+                repo: row.get(3)?,
+                file_path: row.get(4)?,
+                line: row.get(5)?,
+                handler_file: row.get(6)?,
+                handler_line: row.get(7)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    // -----------------------------------------------------------------------
+    // Environment-variable usages
+    // -----------------------------------------------------------------------
+
+    /// Replace a file's environment-variable reads, linking each to the
+    /// innermost symbol containing its line. Call after the file's symbols
+    /// are inserted.
+    pub fn replace_env_usages(&self, file_id: i64, usages: &[EnvUsage]) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM env_usages WHERE file_id = ?1", params![file_id])?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO env_usages (file_id, symbol_id, name, line)
+             VALUES (?1, (SELECT id FROM symbols
+                          WHERE file_id = ?1 AND start_line <= ?3 AND end_line >= ?3
+                          ORDER BY end_line - start_line, id DESC LIMIT 1), ?2, ?3)",
+        )?;
+        for u in usages {
+            stmt.execute(params![file_id, u.name, u.line as i64])?;
+        }
+        Ok(())
+    }
+
+    pub fn get_env_usages_for_file(&self, file_id: i64) -> Result<Vec<EnvUsage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, line FROM env_usages WHERE file_id = ?1 ORDER BY line, id",
+        )?;
+        let rows = stmt.query_map(params![file_id], |row| {
+            Ok(EnvUsage {
+                name: row.get(0)?,
+                line: row.get::<_, i64>(1)? as usize,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Environment-variable reads, optionally narrowed to one repository and
+    /// to variable names containing `name` (case-insensitive).
+    pub fn list_env_usages(&self, repo_id: Option<i64>, name: &str) -> Result<Vec<EnvUsageEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT u.name, repo.name, f.path, u.line, s.name
+             FROM env_usages u
+             JOIN files f ON f.id = u.file_id
+             JOIN repositories repo ON repo.id = f.repo_id
+             LEFT JOIN symbols s ON s.id = u.symbol_id
+             WHERE (?1 IS NULL OR f.repo_id = ?1)
+               AND (?2 = '' OR u.name LIKE '%' || ?2 || '%' ESCAPE '\\')
+             ORDER BY u.name, repo.name, f.path, u.line",
+        )?;
+        let rows = stmt.query_map(params![repo_id, like_escape(name)], |row| {
+            Ok(EnvUsageEntry {
+                name: row.get(0)?,
+                repo: row.get(1)?,
+                file_path: row.get(2)?,
+                line: row.get(3)?,
+                symbol: row.get(4)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    // -----------------------------------------------------------------------
+    // Embedded SQL
+    // -----------------------------------------------------------------------
+
+    /// Replace a file's embedded SQL statements, linking each to the
+    /// innermost symbol containing its line. Call after the file's symbols
+    /// are inserted.
+    pub fn replace_sql_queries(&self, file_id: i64, queries: &[SqlQuery]) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM sql_queries WHERE file_id = ?1", params![file_id])?;
+        let mut insert_query = self.conn.prepare(
+            "INSERT INTO sql_queries (file_id, symbol_id, operation, query, line)
+             VALUES (?1, (SELECT id FROM symbols
+                          WHERE file_id = ?1 AND start_line <= ?4 AND end_line >= ?4
+                          ORDER BY end_line - start_line, id DESC LIMIT 1), ?2, ?3, ?4)",
+        )?;
+        let mut insert_table = self.conn.prepare(
+            "INSERT OR IGNORE INTO sql_query_tables (query_id, table_name, position)
+             VALUES (?1, ?2, ?3)",
+        )?;
+        for q in queries {
+            insert_query.execute(params![file_id, q.operation, q.text, q.line as i64])?;
+            let query_id = self.conn.last_insert_rowid();
+            for (i, table) in q.tables.iter().enumerate() {
+                insert_table.execute(params![query_id, table, i as i64])?;
             }
         }
-        // Add unqualified aliases for qualified names (e.g., "Config::new" → "new").
-        // Only insert if no existing entry — standalone symbols take priority.
-        let aliases: Vec<(String, i64)> = map
-            .iter()
-            .filter_map(|(name, &id)| {
-                name.rfind("::").map(|pos| (name[pos + 2..].to_string(), id))
+        Ok(())
+    }
+
+    pub fn get_sql_queries_for_file(&self, file_id: i64) -> Result<Vec<SqlQuery>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT q.operation, q.query, q.line,
+                    (SELECT group_concat(table_name, ' ') FROM
+                        (SELECT table_name FROM sql_query_tables
+                         WHERE query_id = q.id ORDER BY position))
+             FROM sql_queries q WHERE q.file_id = ?1 ORDER BY q.line, q.id",
+        )?;
+        let rows = stmt.query_map(params![file_id], |row| {
+            let tables: Option<String> = row.get(3)?;
+            Ok(SqlQuery {
+                operation: row.get(0)?,
+                text: row.get(1)?,
+                line: row.get::<_, i64>(2)? as usize,
+                tables: tables
+                    .unwrap_or_default()
+                    .split(' ')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect(),
             })
-            .collect();
-        for (short_name, id) in aliases {
-            map.entry(short_name).or_insert(id);
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Embedded SQL statements touching `table` (case-insensitive; `users`
+    /// also matches `public.users`), optionally narrowed to one repository
+    /// and operation.
+    pub fn find_queries_touching_table(
+        &self,
+        table: &str,
+        repo_id: Option<i64>,
+        operation: &str,
+    ) -> Result<Vec<SqlQueryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT q.operation, q.query, repo.name, f.path, q.line, s.name,
+                    (SELECT group_concat(table_name, ' ') FROM
+                        (SELECT table_name FROM sql_query_tables
+                         WHERE query_id = q.id ORDER BY position))
+             FROM sql_queries q
+             JOIN files f ON f.id = q.file_id
+             JOIN repositories repo ON repo.id = f.repo_id
+             LEFT JOIN symbols s ON s.id = q.symbol_id
+             WHERE EXISTS (SELECT 1 FROM sql_query_tables t
+                           WHERE t.query_id = q.id
+                             AND (t.table_name = lower(?1)
+                                  OR substr(t.table_name, -length(?1) - 1) = '.' || lower(?1)))
+               AND (?2 IS NULL OR f.repo_id = ?2)
+               AND (?3 = '' OR q.operation = upper(?3))
+             ORDER BY repo.name, f.path, q.line",
+        )?;
+        let rows = stmt.query_map(params![table, repo_id, operation], |row| {
+            let tables: Option<String> = row.get(6)?;
+            let query: String = row.get(1)?;
+            Ok(SqlQueryEntry {
+                operation: row.get(0)?,
+                // Literals are stored as found, so a credential in a
+                // connection string or WHERE clause is masked here.
+                query: crate::redact::redact(&query).0.into_owned(),
+                repo: row.get(2)?,
+                file_path: row.get(3)?,
+                line: row.get(4)?,
+                symbol: row.get(5)?,
+                tables: tables
+                    .unwrap_or_default()
+                    .split(' ')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect(),
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    // -----------------------------------------------------------------------
+    // Error sites
+    // -----------------------------------------------------------------------
+
+    /// Replace a file's error sites, linking each to the innermost symbol
+    /// containing its line. Call after the file's symbols are inserted.
+    pub fn replace_error_sites(&self, file_id: i64, sites: &[ErrorSite]) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM error_sites WHERE file_id = ?1", params![file_id])?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO error_sites (file_id, symbol_id, kind, line, snippet)
+             VALUES (?1, (SELECT id FROM symbols
+                          WHERE file_id = ?1 AND start_line <= ?3 AND end_line >= ?3
+                          ORDER BY end_line - start_line, id DESC LIMIT 1), ?2, ?3, ?4)",
+        )?;
+        for s in sites {
+            stmt.execute(params![file_id, s.kind, s.line as i64, s.snippet])?;
         }
-        Ok(map)
+        Ok(())
     }
 
-    /// Rich symbol query: returns symbols with file path, repo name, and linked memories.
-    /// Filters are all optional — pass empty string or None to skip.
-    pub fn query_symbols_full(
+    pub fn get_error_sites_for_file(&self, file_id: i64) -> Result<Vec<ErrorSite>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT kind, line, snippet FROM error_sites WHERE file_id = ?1 ORDER BY line, id",
+        )?;
+        let rows = stmt.query_map(params![file_id], |row| {
+            Ok(ErrorSite {
+                kind: row.get(0)?,
+                line: row.get::<_, i64>(1)? as usize,
+                snippet: row.get(2)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Error sites ordered by repository, file and line, optionally narrowed
+    /// to one repository, a repo-relative file or directory, and one kind.
+    pub fn list_error_sites(
         &self,
-        name: &str,
+        repo_id: Option<i64>,
+        path_prefix: &str,
         kind: &str,
-        repo_name: &str,
-    ) -> Result<Vec<SymbolResult>> {
-        let mut sql = String::from(
-            "SELECT s.id, s.name, s.kind, s.signature, s.body, s.body_hash,
-                    f.path, r.name, s.start_line, s.end_line, s.source, s.manifest_repo
-             FROM symbols s
-             JOIN files f ON f.id = s.file_id
-             JOIN repositories r ON r.id = f.repo_id
-             WHERE 1=1",
-        );
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-        let mut idx = 1;
+    ) -> Result<Vec<ErrorSiteEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.kind, repo.name, f.path, e.line, e.snippet, s.name
+             FROM error_sites e
+             JOIN files f ON f.id = e.file_id
+             JOIN repositories repo ON repo.id = f.repo_id
+             LEFT JOIN symbols s ON s.id = e.symbol_id
+             WHERE (?1 IS NULL OR f.repo_id = ?1)
+               AND (?2 = '' OR f.path = ?2 OR substr(f.path, 1, length(?2) + 1) = ?2 || '/')
+               AND (?3 = '' OR e.kind = ?3)
+             ORDER BY repo.name, f.path, e.line, e.id",
+        )?;
+        let rows = stmt.query_map(params![repo_id, path_prefix, kind], |row| {
+            Ok(ErrorSiteEntry {
+                kind: row.get(0)?,
+                repo: row.get(1)?,
+                file_path: row.get(2)?,
+                line: row.get(3)?,
+                snippet: row.get(4)?,
+                symbol: row.get(5)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    // -----------------------------------------------------------------------
+    // Packages
+    // -----------------------------------------------------------------------
+
+    /// Replace the packages of `repo_id` and reassign every file to its
+    /// innermost package.
+    pub fn replace_packages(&self, repo_id: i64, packages: &[Package]) -> Result<()> {
+        self.conn.execute("DELETE FROM packages WHERE repo_id = ?1", params![repo_id])?;
+        for package in packages {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO packages (repo_id, path, name, kind) VALUES (?1, ?2, ?3, ?4)",
+                params![repo_id, package.path, package.name, package.kind],
+            )?;
+        }
+        for file in self.get_files_for_repo(repo_id)? {
+            let name = package_for_path(packages, &file.path).map_or("", |p| p.name.as_str());
+            self.set_file_package(file.id, name)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the README paragraphs and package doc comments of `repo_id`.
+    pub fn replace_module_docs(&self, repo_id: i64, docs: &[ModuleDoc]) -> Result<()> {
+        self.conn.execute("DELETE FROM module_docs WHERE repo_id = ?1", params![repo_id])?;
+        for doc in docs {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO module_docs (repo_id, path, kind, doc) VALUES (?1, ?2, ?3, ?4)",
+                params![repo_id, doc.path, doc.kind, doc.doc],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// One doc per package of `repo` (see `module_docs::package_docs`).
+    pub fn get_package_docs(&self, repo: &Repository) -> Result<Vec<PackageDoc>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, kind, doc FROM module_docs WHERE repo_id = ?1 ORDER BY path")?;
+        let docs = stmt
+            .query_map(params![repo.id], |row| {
+                Ok(ModuleDoc { path: row.get(0)?, kind: row.get(1)?, doc: row.get(2)? })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(package_docs(&repo.name, &self.get_packages(repo.id)?, &docs))
+    }
+
+    pub fn get_packages(&self, repo_id: i64) -> Result<Vec<Package>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, path, kind FROM packages WHERE repo_id = ?1 ORDER BY path")?;
+        let rows = stmt.query_map(params![repo_id], |row| {
+            Ok(Package { name: row.get(0)?, path: row.get(1)?, kind: row.get(2)? })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    pub fn set_file_package(&self, file_id: i64, package: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE files SET package = ?2 WHERE id = ?1 AND package != ?2",
+            params![file_id, package],
+        )?;
+        Ok(())
+    }
+
+    /// Packages with their file, symbol and line counts, ordered by
+    /// repository and path.
+    pub fn list_package_stats(&self, repo_id: Option<i64>) -> Result<Vec<PackageStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT r.name, p.name, p.path, p.kind,
+                    (SELECT COUNT(*) FROM files f WHERE f.repo_id = p.repo_id AND f.package = p.name),
+                    (SELECT COUNT(*) FROM symbols s JOIN files f ON f.id = s.file_id
+                     WHERE f.repo_id = p.repo_id AND f.package = p.name),
+                    (SELECT COALESCE(SUM(f.line_count), 0) FROM files f
+                     WHERE f.repo_id = p.repo_id AND f.package = p.name),
+                    (SELECT COALESCE(GROUP_CONCAT(DISTINCT f.language), '') FROM files f
+                     WHERE f.repo_id = p.repo_id AND f.package = p.name)
+             FROM packages p
+             JOIN repositories r ON r.id = p.repo_id
+             WHERE (?1 IS NULL OR p.repo_id = ?1)
+             ORDER BY r.name, p.path",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| {
+            let languages: String = row.get(7)?;
+            let mut languages: Vec<String> =
+                languages.split(',').filter(|l| !l.is_empty()).map(String::from).collect();
+            languages.sort();
+            Ok(PackageStats {
+                repo: row.get(0)?,
+                name: row.get(1)?,
+                path: row.get(2)?,
+                kind: row.get(3)?,
+                file_count: row.get(4)?,
+                symbol_count: row.get(5)?,
+                line_count: row.get(6)?,
+                languages,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
 
-        if !name.is_empty() {
-            sql.push_str(&format!(" AND s.name LIKE ?{idx}"));
-            param_values.push(Box::new(format!("%{name}%")));
-            idx += 1;
-        }
-        if !kind.is_empty() {
-            sql.push_str(&format!(" AND s.kind = ?{idx}"));
-            param_values.push(Box::new(kind.to_string()));
-            idx += 1;
-        }
-        if !repo_name.is_empty() {
-            sql.push_str(&format!(" AND r.name = ?{idx}"));
-            param_values.push(Box::new(repo_name.to_string()));
-            let _ = idx; // suppress unused warning
+    /// Package name of each symbol's file, for symbols that have one.
+    pub fn get_packages_for_symbols(
+        &self,
+        symbol_ids: &[i64],
+    ) -> Result<std::collections::HashMap<i64, String>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT f.package FROM symbols s JOIN files f ON f.id = s.file_id WHERE s.id = ?1",
+        )?;
+        let mut map = std::collections::HashMap::new();
+        for &id in symbol_ids {
+            let package: Option<String> = stmt.query_row(params![id], |row| row.get(0)).optional()?;
+            if let Some(package) = package.filter(|p| !p.is_empty()) {
+                map.insert(id, package);
+            }
         }
+        Ok(map)
+    }
 
-        sql.push_str(" ORDER BY s.name LIMIT 200");
+    // -----------------------------------------------------------------------
+    // Declared dependencies
+    // -----------------------------------------------------------------------
 
-        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
-            param_values.iter().map(|b| b.as_ref()).collect();
+    /// Replace the declared dependencies of `repo_id`, dropping their import
+    /// links; edge resolution records the links again.
+    pub fn replace_dependencies(&self, repo_id: i64, dependencies: &[Dependency]) -> Result<()> {
+        self.conn.execute("DELETE FROM dependencies WHERE repo_id = ?1", params![repo_id])?;
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT INTO dependencies (repo_id, manifest, name, module, version, kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for dep in dependencies {
+            stmt.execute(params![repo_id, dep.manifest, dep.name, dep.module, dep.version, dep.kind])?;
+        }
+        Ok(())
+    }
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(SymbolResult {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                kind: row.get(2)?,
-                signature: row.get(3)?,
-                body: row.get(4)?,
-                file_path: row.get(6)?,
-                repo_name: row.get(7)?,
-                start_line: row.get(8)?,
-                end_line: row.get(9)?,
-                memories: Vec::new(), // filled below
-                dependency_hints: Vec::new(), // filled later if requested
-                source: row.get(10)?,
-                manifest_repo: row.get(11)?,
-            })
+    /// Declared dependencies of `repo_id` with their row ids.
+    pub fn get_repo_dependencies(&self, repo_id: i64) -> Result<Vec<(i64, Dependency)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, COALESCE(NULLIF(module, ''), name), version, kind, manifest
+             FROM dependencies WHERE repo_id = ?1 ORDER BY name, manifest",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| {
+            Ok((
+                row.get(0)?,
+                Dependency {
+                    name: row.get(1)?,
+                    module: row.get(2)?,
+                    version: row.get(3)?,
+                    kind: row.get(4)?,
+                    manifest: row.get(5)?,
+                },
+            ))
         })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
 
-        let mut results: Vec<SymbolResult> = Vec::new();
-        for r in rows {
-            results.push(r?);
+    /// Replace the dependencies `file_id` imports.
+    pub fn replace_dependency_imports(&self, file_id: i64, dependency_ids: &[i64]) -> Result<()> {
+        self.conn.execute("DELETE FROM dependency_imports WHERE file_id = ?1", params![file_id])?;
+        for id in dependency_ids {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO dependency_imports (dependency_id, file_id) VALUES (?1, ?2)",
+                params![id, file_id],
+            )?;
         }
+        Ok(())
+    }
 
-        // Batch-load memories for all symbols in one query (avoids N+1)
-        let sym_ids: Vec<i64> = results.iter().map(|s| s.id).collect();
-        let mem_map = self.get_memories_for_symbols_batch(&sym_ids, false)?;
-        for sym in &mut results {
-            sym.memories = mem_map.get(&sym.id).cloned().unwrap_or_default();
+    /// Declared dependencies with the files importing them, ordered by
+    /// repository, name and manifest. `name` matches as a substring and
+    /// `kind` exactly; empty strings skip the filter.
+    pub fn list_dependencies(&self, repo_id: Option<i64>, name: &str, kind: &str) -> Result<Vec<DependencyEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, r.name, d.name, d.version, d.kind, d.manifest
+             FROM dependencies d
+             JOIN repositories r ON r.id = d.repo_id
+             WHERE (?1 IS NULL OR d.repo_id = ?1)
+               AND (?2 = '' OR d.name LIKE '%' || ?2 || '%' ESCAPE '\\')
+               AND (?3 = '' OR d.kind = ?3)
+             ORDER BY r.name, d.name, d.manifest",
+        )?;
+        let rows = stmt
+            .query_map(params![repo_id, like_escape(name), kind], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    DependencyEntry {
+                        repo: row.get(1)?,
+                        name: row.get(2)?,
+                        version: row.get(3)?,
+                        kind: row.get(4)?,
+                        manifest: row.get(5)?,
+                        imported_by: Vec::new(),
+                    },
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut files = self.conn.prepare_cached(
+            "SELECT f.path FROM dependency_imports di JOIN files f ON f.id = di.file_id
+             WHERE di.dependency_id = ?1 ORDER BY f.path",
+        )?;
+        let mut out = Vec::with_capacity(rows.len());
+        for (id, mut entry) in rows {
+            entry.imported_by = files
+                .query_map(params![id], |row| row.get(0))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            out.push(entry);
         }
+        Ok(out)
+    }
 
-        Ok(results)
+    /// Id of the external stub for `name` in dependency `package`, creating
+    /// the stub (and its package's holder file in the `external` virtual
+    /// repository) on first use.
+    pub fn get_or_create_external_symbol(&self, package: &str, name: &str, language: &str) -> Result<i64> {
+        let qualified_name = external_qualified_name(language, package, name);
+        let existing = self
+            .conn
+            .query_row(
+                "SELECT s.id FROM symbols s
+                 JOIN files f ON f.id = s.file_id
+                 JOIN repositories r ON r.id = f.repo_id
+                 WHERE r.root_path = ?1 AND f.path = ?2 AND s.qualified_name = ?3",
+                params![EXTERNAL_ROOT, package, qualified_name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+        let repo_id = self.upsert_repository(EXTERNAL_REPO, EXTERNAL_ROOT)?;
+        let file_id = self.upsert_file(repo_id, package, language, "external")?;
+        self.conn.execute(
+            "INSERT INTO symbols (file_id, name, qualified_name, kind, signature,
+                                  start_line, end_line, source)
+             VALUES (?1, ?2, ?3, 'external', ?3, 0, 0, 'external')",
+            params![file_id, name, qualified_name],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "INSERT INTO symbols_fts(rowid, name, signature, body) VALUES (?1, ?2, ?3, '')",
+            params![id, name, qualified_name],
+        )?;
+        Ok(id)
+    }
+
+    /// Delete external stubs nothing points at any more, and holder files
+    /// left without stubs. Returns the number of stubs removed. Run after a
+    /// resolve pass or a set of file updates, not per file.
+    pub fn prune_external_symbols(&self) -> Result<usize> {
+        let orphans = "SELECT id FROM symbols
+                       WHERE file_id IN (SELECT f.id FROM files f
+                                         JOIN repositories r ON r.id = f.repo_id
+                                         WHERE r.root_path = ?1)
+                         AND NOT EXISTS (SELECT 1 FROM edges e WHERE e.target_id = symbols.id)";
+        self.conn
+            .execute(&format!("DELETE FROM symbols_fts WHERE rowid IN ({orphans})"), params![EXTERNAL_ROOT])?;
+        let removed = self
+            .conn
+            .execute(&format!("DELETE FROM symbols WHERE id IN ({orphans})"), params![EXTERNAL_ROOT])?;
+        if removed > 0 {
+            self.conn.execute(
+                "DELETE FROM files
+                 WHERE repo_id IN (SELECT id FROM repositories WHERE root_path = ?1)
+                   AND NOT EXISTS (SELECT 1 FROM symbols s WHERE s.file_id = files.id)",
+                params![EXTERNAL_ROOT],
+            )?;
+        }
+        Ok(removed)
     }
 
     // -----------------------------------------------------------------------
-    // Edge CRUD
+    // Index errors
     // -----------------------------------------------------------------------
 
-    pub fn insert_edge(&self, source_id: i64, target_id: i64, kind: &str) -> Result<i64> {
+    /// Forget recorded problems of a repository, narrowed to one file and/or
+    /// one kind when given.
+    pub fn clear_index_errors(&self, repo_id: i64, path: Option<&str>, kind: Option<&str>) -> Result<()> {
         self.conn.execute(
-            "INSERT OR IGNORE INTO edges (source_id, target_id, kind) VALUES (?1, ?2, ?3)",
-            params![source_id, target_id, kind],
+            "DELETE FROM index_errors
+             WHERE repo_id = ?1 AND (?2 IS NULL OR path = ?2) AND (?3 IS NULL OR kind = ?3)",
+            params![repo_id, path, kind],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_index_error(
+        &self,
+        repo_id: i64,
+        path: &str,
+        kind: &str,
+        message: &str,
+        line: Option<i64>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO index_errors (repo_id, path, kind, message, line)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![repo_id, path, kind, message, line],
         )?;
+        Ok(())
+    }
+
+    /// Append a tool call to the audit log. Like auto-observations, audit
+    /// rows are not index content and don't invalidate cached queries.
+    pub fn record_audit(&self, entry: &AuditEntry) -> Result<i64> {
+        self.quiet.store(true, Ordering::Relaxed);
+        let result = self.conn.execute(
+            "INSERT INTO audit_log (session_id, token, tool, params, outcome, response_tokens)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.session_id,
+                entry.token,
+                entry.tool,
+                entry.params,
+                entry.outcome,
+                entry.response_tokens as i64
+            ],
+        );
+        self.quiet.store(false, Ordering::Relaxed);
+        result?;
         Ok(self.conn.last_insert_rowid())
     }
 
-    /// Outgoing edges: symbols that `symbol_id` depends on.
-    pub fn get_dependencies(&self, symbol_id: i64) -> Result<Vec<(Edge, Symbol)>> {
+    /// Drop audit rows older than `retention_days`, lifting the no-delete
+    /// trigger for the purge only. Returns the number dropped.
+    pub fn purge_audit_log(&self, retention_days: u64) -> Result<usize> {
+        let cutoff = format!("-{retention_days} days");
+        self.with_transaction(|| {
+            self.conn.execute_batch("DROP TRIGGER IF EXISTS audit_log_no_delete;")?;
+            let purged = self.conn.execute(
+                "DELETE FROM audit_log WHERE at < datetime('now', ?1)",
+                params![cutoff],
+            )?;
+            self.conn.execute_batch(
+                "CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+                 BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;",
+            )?;
+            Ok(purged)
+        })
+    }
+
+    /// Audit rows matching `filter`, newest first.
+    pub fn query_audit_log(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>> {
         let mut stmt = self.conn.prepare(
-            "SELECT e.id, e.source_id, e.target_id, e.kind,
-                    s.id, s.file_id, s.name, s.kind, s.signature, s.body,
-                    s.body_hash, s.start_line, s.end_line, s.parent_id,
-                    s.qualified_name, s.source, s.manifest_repo
-             FROM edges e
-             JOIN symbols s ON s.id = e.target_id
-             WHERE e.source_id = ?1",
+            "SELECT id, at, session_id, token, tool, params, outcome, response_tokens
+             FROM audit_log
+             WHERE (?1 IS NULL OR session_id = ?1)
+               AND (?2 IS NULL OR token = ?2)
+               AND (?3 IS NULL OR tool = ?3)
+               AND (?4 IS NULL OR at >= ?4)
+             ORDER BY id DESC
+             LIMIT ?5",
         )?;
-        let rows = stmt.query_map(params![symbol_id], |row| {
-            Ok((
-                Edge {
+        let rows = stmt.query_map(
+            params![
+                filter.session_id,
+                filter.token,
+                filter.tool,
+                filter.since,
+                filter.effective_limit() as i64
+            ],
+            |row| {
+                Ok(AuditRecord {
                     id: row.get(0)?,
-                    source_id: row.get(1)?,
-                    target_id: row.get(2)?,
-                    kind: row.get(3)?,
-                },
-                Symbol {
-                    id: row.get(4)?,
-                    file_id: row.get(5)?,
-                    name: row.get(6)?,
-                    qualified_name: row.get(14)?,
-                    kind: row.get(7)?,
-                    signature: row.get(8)?,
-                    body: row.get(9)?,
-                    body_hash: row.get(10)?,
-                    start_line: row.get(11)?,
-                    end_line: row.get(12)?,
-                    parent_id: row.get(13)?,
-                    source: row.get(15)?,
-                    manifest_repo: row.get(16)?,
-                },
-            ))
+                    at: row.get(1)?,
+                    session_id: row.get(2)?,
+                    token: row.get(3)?,
+                    tool: row.get(4)?,
+                    params: row.get(5)?,
+                    outcome: row.get(6)?,
+                    response_tokens: row.get::<_, i64>(7)? as usize,
+                })
+            },
+        )?;
+        Ok(rows.collect::<std::result::Result<_, _>>()?)
+    }
+
+    /// Recorded problems ordered by repository and path, optionally narrowed
+    /// to one repository and one kind.
+    pub fn list_index_errors(&self, repo_id: Option<i64>, kind: &str) -> Result<Vec<IndexErrorEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT repo.name, e.path, e.kind, e.message, e.line, e.recorded_at
+             FROM index_errors e
+             JOIN repositories repo ON repo.id = e.repo_id
+             WHERE (?1 IS NULL OR e.repo_id = ?1) AND (?2 = '' OR e.kind = ?2)
+             ORDER BY repo.name, e.path, e.line, e.id",
+        )?;
+        let rows = stmt.query_map(params![repo_id, kind], |row| {
+            Ok(IndexErrorEntry {
+                repo: row.get(0)?,
+                path: row.get(1)?,
+                kind: row.get(2)?,
+                message: row.get(3)?,
+                line: row.get(4)?,
+                recorded_at: row.get(5)?,
+            })
         })?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
     }
 
-    /// Incoming edges: symbols that depend on `symbol_id`.
-    pub fn get_dependents(&self, symbol_id: i64) -> Result<Vec<(Edge, Symbol)>> {
+    // -----------------------------------------------------------------------
+    // Test links
+    // -----------------------------------------------------------------------
+
+    /// Replace every test link whose test lives in `repo_id`.
+    pub fn replace_test_links(&self, repo_id: i64, links: &[(i64, i64, &str)]) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM test_links WHERE test_id IN (
+                 SELECT s.id FROM symbols s JOIN files f ON f.id = s.file_id
+                 WHERE f.repo_id = ?1)",
+            params![repo_id],
+        )?;
+        let mut stmt = self.conn.prepare_cached(
+            "INSERT OR REPLACE INTO test_links (test_id, symbol_id, via) VALUES (?1, ?2, ?3)",
+        )?;
+        for (test_id, symbol_id, via) in links {
+            stmt.execute(params![test_id, symbol_id, via])?;
+        }
+        Ok(())
+    }
+
+    /// Tests exercising symbols named `name` (in `repo_id`, if given).
+    pub fn tests_for_symbol(&self, name: &str, repo_id: Option<i64>) -> Result<Vec<TestLinkEntry>> {
+        self.query_test_links("ts.name = ?1 AND (?2 IS NULL OR sf.repo_id = ?2)", name, repo_id)
+    }
+
+    /// Symbols exercised by tests named `name` (in `repo_id`, if given).
+    pub fn symbols_for_test(&self, name: &str, repo_id: Option<i64>) -> Result<Vec<TestLinkEntry>> {
+        self.query_test_links("t.name = ?1 AND (?2 IS NULL OR tf.repo_id = ?2)", name, repo_id)
+    }
+
+    /// Tests exercising the symbol with id `symbol_id`.
+    pub fn tests_for_symbol_id(&self, symbol_id: i64) -> Result<Vec<TestLinkEntry>> {
+        self.query_test_links("ts.id = ?1 AND ?2 IS NULL", symbol_id, None)
+    }
+
+    /// Test symbols linked to the symbol with id `symbol_id`, by file and line.
+    pub fn test_symbols_for(&self, symbol_id: i64) -> Result<Vec<Symbol>> {
         let mut stmt = self.conn.prepare(
-            "SELECT e.id, e.source_id, e.target_id, e.kind,
-                    s.id, s.file_id, s.name, s.kind, s.signature, s.body,
-                    s.body_hash, s.start_line, s.end_line, s.parent_id,
-                    s.qualified_name, s.source, s.manifest_repo
-             FROM edges e
-             JOIN symbols s ON s.id = e.source_id
-             WHERE e.target_id = ?1",
+            "SELECT t.id, t.file_id, t.name, t.kind, t.signature, t.body, t.body_hash,
+                    t.start_line, t.end_line, t.parent_id, t.qualified_name, t.source, t.manifest_repo,
+                    t.visibility, t.modifiers, t.signature_types, t.attributes
+             FROM test_links l
+             JOIN symbols t ON t.id = l.test_id
+             JOIN files f ON f.id = t.file_id
+             WHERE l.symbol_id = ?1
+             ORDER BY f.path, t.start_line",
         )?;
-        let rows = stmt.query_map(params![symbol_id], |row| {
-            Ok((
-                Edge {
-                    id: row.get(0)?,
-                    source_id: row.get(1)?,
-                    target_id: row.get(2)?,
-                    kind: row.get(3)?,
-                },
-                Symbol {
-                    id: row.get(4)?,
-                    file_id: row.get(5)?,
-                    name: row.get(6)?,
-                    qualified_name: row.get(14)?,
-                    kind: row.get(7)?,
-                    signature: row.get(8)?,
-                    body: row.get(9)?,
-                    body_hash: row.get(10)?,
-                    start_line: row.get(11)?,
-                    end_line: row.get(12)?,
-                    parent_id: row.get(13)?,
-                    source: row.get(15)?,
-                    manifest_repo: row.get(16)?,
-                },
-            ))
-        })?;
+        let rows = stmt.query_map(params![symbol_id], |row| Symbol::from_row(row, 0))?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
     }
 
-    /// Return dependency hints for a symbol: names and kinds of symbols it
-    /// depends on via type_ref or imports edges. Used to warn the LLM about
-    /// interfaces/traits not included in the current context.
-    pub fn get_dependency_hint_names(
+    fn query_test_links(
         &self,
-        symbol_id: i64,
-        _exclude_ids: &std::collections::HashSet<i64>,
-    ) -> Result<Vec<(String, String, String)>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT s.name, s.kind, e.kind
-             FROM edges e
-             JOIN symbols s ON s.id = e.target_id
-             WHERE e.source_id = ?1
-               AND e.kind IN ('type_ref', 'imports', 'calls')",
-        )?;
-        let rows = stmt.query_map(params![symbol_id], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
+        filter: &str,
+        key: impl rusqlite::ToSql,
+        repo_id: Option<i64>,
+    ) -> Result<Vec<TestLinkEntry>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT repo.name, t.name, tf.path, t.start_line,
+                    ts.name, ts.kind, sf.path, ts.start_line, l.via
+             FROM test_links l
+             JOIN symbols t  ON t.id = l.test_id
+             JOIN files tf   ON tf.id = t.file_id
+             JOIN symbols ts ON ts.id = l.symbol_id
+             JOIN files sf   ON sf.id = ts.file_id
+             JOIN repositories repo ON repo.id = tf.repo_id
+             WHERE {filter}
+             ORDER BY repo.name, tf.path, t.start_line, sf.path, ts.start_line"
+        ))?;
+        let rows = stmt.query_map(params![key, repo_id], |row| {
+            Ok(TestLinkEntry {
+                repo: row.get(0)?,
+                test: row.get(1)?,
+                test_file: row.get(2)?,
+                test_line: row.get(3)?,
+                symbol: row.get(4)?,
+                symbol_kind: row.get(5)?,
+                symbol_file: row.get(6)?,
+                symbol_line: row.get(7)?,
+                via: row.get(8)?,
+            })
         })?;
-        let mut hints = Vec::new();
-        for r in rows {
-            let (name, kind, edge_kind) = r?;
-            // Only hint about symbols not already in the result set
-            // We can't check by ID here (we have names), so caller filters by ID
-            hints.push((name, kind, edge_kind));
-        }
-        Ok(hints)
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
     }
 
-    pub fn delete_edges_by_file(&self, file_id: i64) -> Result<usize> {
-        let c1 = self.conn.execute(
-            "DELETE FROM edges WHERE source_id IN (SELECT id FROM symbols WHERE file_id = ?1)",
-            params![file_id],
+    // -----------------------------------------------------------------------
+    // Coverage
+    // -----------------------------------------------------------------------
+
+    /// Drop imported coverage for every symbol in a repository.
+    pub fn clear_coverage_for_repo(&self, repo_id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM symbol_coverage WHERE symbol_id IN (
+                 SELECT s.id FROM symbols s JOIN files f ON f.id = s.file_id
+                 WHERE f.repo_id = ?1)",
+            params![repo_id],
         )?;
-        let c2 = self.conn.execute(
-            "DELETE FROM edges WHERE target_id IN (SELECT id FROM symbols WHERE file_id = ?1)",
-            params![file_id],
+        Ok(())
+    }
+
+    pub fn set_symbol_coverage(&self, symbol_id: i64, lines_total: i64, lines_covered: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO symbol_coverage (symbol_id, lines_total, lines_covered)
+             VALUES (?1, ?2, ?3)",
+            params![symbol_id, lines_total, lines_covered],
         )?;
-        Ok(c1 + c2)
+        Ok(())
+    }
+
+    /// Coverage for the given symbols; symbols without imported coverage are
+    /// absent from the map.
+    pub fn get_coverage_for_symbols(
+        &self,
+        symbol_ids: &[i64],
+    ) -> Result<std::collections::HashMap<i64, SymbolCoverage>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT lines_total, lines_covered FROM symbol_coverage WHERE symbol_id = ?1",
+        )?;
+        let mut map = std::collections::HashMap::new();
+        for &id in symbol_ids {
+            let row = stmt
+                .query_row(params![id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+                .optional()?;
+            if let Some((total, covered)) = row {
+                map.insert(
+                    id,
+                    SymbolCoverage {
+                        covered: covered > 0,
+                        percent: round1(covered as f64 * 100.0 / total.max(1) as f64),
+                        lines_covered: covered,
+                        lines_total: total,
+                    },
+                );
+            }
+        }
+        Ok(map)
+    }
+
+    // -----------------------------------------------------------------------
+    // Memory CRUD
+    // -----------------------------------------------------------------------
+
+    pub fn save_memory(
+        &self,
+        content: &str,
+        category: &str,
+        symbol_ids: &[i64],
+    ) -> Result<i64> {
+        self.save_structured_memory(content, category, None, symbol_ids)
     }
 
-    // -----------------------------------------------------------------------
-    // Memory CRUD
-    // -----------------------------------------------------------------------
-
-    pub fn save_memory(
+    /// Save a manual memory with optional structured template fields.
+    /// `structured` must already be validated (see `memory::validate_fields`).
+    pub fn save_structured_memory(
         &self,
         content: &str,
         category: &str,
+        structured: Option<&serde_json::Value>,
         symbol_ids: &[i64],
     ) -> Result<i64> {
+        let structured_json = structured.map(|v| v.to_string());
         self.conn.execute(
-            "INSERT INTO memories (content, category, source, session_id)
-             VALUES (?1, ?2, 'manual', '')",
-            params![content, category],
+            "INSERT INTO memories (content, category, source, session_id, structured)
+             VALUES (?1, ?2, 'manual', '', ?3)",
+            params![content, category, structured_json],
         )?;
         let memory_id = self.conn.last_insert_rowid();
         self.conn.execute(
@@ -927,26 +4760,41 @@ impl Database {
         source: &str,
         session_id: &str,
         symbol_ids: &[i64],
+    ) -> Result<i64> {
+        // Observations are a session trail, not index content: recording one
+        // must not invalidate cached query results.
+        self.quiet.store(true, Ordering::Relaxed);
+        let result = self.write_auto_observation(content, source, session_id, symbol_ids);
+        self.quiet.store(false, Ordering::Relaxed);
+        result
+    }
+
+    fn write_auto_observation(
+        &self,
+        content: &str,
+        source: &str,
+        session_id: &str,
+        symbol_ids: &[i64],
     ) -> Result<i64> {
         // Dedup: if an observation from the same source in this session exists, update it
         let existing: Option<i64> = self.conn.query_row(
             "SELECT id FROM memories WHERE source = ?1 AND session_id = ?2 AND category = 'observation'
-             ORDER BY created_at DESC LIMIT 1",
+             ORDER BY created_at DESC, id DESC LIMIT 1",
             params![source, session_id],
             |row| row.get(0),
         ).optional()?;
 
         let memory_id = if let Some(id) = existing {
-            // Update existing observation content and timestamp
-            self.conn.execute(
-                "UPDATE memories SET content = ?1, created_at = datetime('now') WHERE id = ?2",
-                params![content, id],
-            )?;
-            // Refresh FTS index
+            // Unindex the old content while the row still holds it, then
+            // update content and timestamp
             self.conn.execute(
                 "DELETE FROM memories_fts WHERE rowid = ?1",
                 params![id],
             )?;
+            self.conn.execute(
+                "UPDATE memories SET content = ?1, created_at = datetime('now') WHERE id = ?2",
+                params![content, id],
+            )?;
             self.conn.execute(
                 "INSERT INTO memories_fts(rowid, content, category) VALUES (?1, ?2, 'observation')",
                 params![id, content],
@@ -986,16 +4834,151 @@ impl Database {
         Ok(())
     }
 
-    /// List memories, optionally filtering by category, staleness, and linked symbol name.
+    /// Link a memory to files or directories of `repo_id` (None: any
+    /// repository). An absolute path belongs to the repository whose root
+    /// contains it and is stored relative to that root; empty paths (the
+    /// repo root) are ignored.
+    pub fn link_memory_files(&self, memory_id: i64, paths: &[String], repo_id: Option<i64>) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO memory_files (memory_id, path, repo_id) VALUES (?1, ?2, ?3)",
+        )?;
+        for path in paths {
+            let (path, root_repo) = self.split_repo_path(path)?;
+            if !path.is_empty() {
+                stmt.execute(params![memory_id, path, root_repo.or(repo_id)])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the file/directory links of a memory.
+    pub fn set_memory_files(&self, memory_id: i64, paths: &[String], repo_id: Option<i64>) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM memory_files WHERE memory_id = ?1",
+            params![memory_id],
+        )?;
+        self.link_memory_files(memory_id, paths, repo_id)
+    }
+
+    pub fn get_file_paths_for_memory(&self, memory_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT path FROM memory_files WHERE memory_id = ?1 ORDER BY path",
+        )?;
+        let rows = stmt.query_map(params![memory_id], |row| row.get(0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Memories linked to `file_path` itself or to any directory containing
+    /// it. With `repo_id`, links scoped to another repository don't match.
+    pub fn get_memories_for_path(
+        &self,
+        repo_id: Option<i64>,
+        file_path: &str,
+        include_stale: bool,
+    ) -> Result<Vec<Memory>> {
+        let stale_filter = if include_stale { "" } else { " AND m.stale = 0" };
+        let sql = format!(
+            "SELECT DISTINCT m.id, m.content, m.category, m.source, m.session_id,
+                    m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
+             FROM memories m
+             JOIN memory_files mf ON mf.memory_id = m.id
+             WHERE (mf.path = ?1
+                    OR substr(?1, 1, length(mf.path) + 1) = mf.path || '/')
+               AND (mf.repo_id IS NULL OR ?2 IS NULL OR mf.repo_id = ?2){stale_filter}
+             ORDER BY m.created_at DESC, m.id DESC"
+        );
+        let (path, root_repo) = self.split_repo_path(file_path)?;
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![path, root_repo.or(repo_id)], |row| {
+            memory_from_row(row, 0)
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Mark path-linked memories stale when none of their paths match an
+    /// indexed file any more (the file or whole directory was deleted).
+    /// Returns the number of memories newly marked stale.
+    pub fn mark_orphaned_path_memories_stale(&self) -> Result<usize> {
+        let count = self.conn.execute(
+            "UPDATE memories SET stale = 1, stale_reason = ?1, stale_at = datetime('now')
+             WHERE stale = 0
+               AND id IN (SELECT memory_id FROM memory_files)
+               AND NOT EXISTS (
+                   SELECT 1 FROM memory_files mf
+                   JOIN files f
+                     ON (f.path = mf.path
+                         OR substr(f.path, 1, length(mf.path) + 1) = mf.path || '/')
+                    AND (mf.repo_id IS NULL OR f.repo_id = mf.repo_id)
+                   WHERE mf.memory_id = memories.id
+               )
+               AND NOT EXISTS (
+                   SELECT 1 FROM memory_files mf
+                   JOIN tombstones t
+                     ON (t.path = mf.path
+                         OR substr(t.path, 1, length(mf.path) + 1) = mf.path || '/')
+                    AND (mf.repo_id IS NULL OR t.repo_id = mf.repo_id)
+                   WHERE mf.memory_id = memories.id
+               )",
+            params![StaleReason::FileDeleted.as_str()],
+        )?;
+        Ok(count)
+    }
+
+    /// Record that `source_id` supersedes / refines / relates_to `target_id`.
+    /// The kind must already be validated (see `memory::validate_link_kind`).
+    pub fn link_memories(&self, source_id: i64, target_id: i64, kind: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO memory_links (source_id, target_id, kind) VALUES (?1, ?2, ?3)",
+            params![source_id, target_id, kind],
+        )?;
+        Ok(())
+    }
+
+    /// Follow `supersedes` links forward from `memory_id` and return the
+    /// newest memory in the chain (`memory_id` itself if nothing supersedes it).
+    pub fn latest_in_supersession_chain(&self, memory_id: i64) -> Result<i64> {
+        let mut current = memory_id;
+        let mut visited = std::collections::HashSet::new();
+        while visited.insert(current) {
+            let next: Option<i64> = self
+                .conn
+                .query_row(
+                    "SELECT ml.source_id FROM memory_links ml
+                     JOIN memories m ON m.id = ml.source_id
+                     WHERE ml.target_id = ?1 AND ml.kind = 'supersedes'
+                     ORDER BY m.created_at DESC, m.id DESC
+                     LIMIT 1",
+                    params![current],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match next {
+                Some(id) => current = id,
+                None => break,
+            }
+        }
+        Ok(current)
+    }
+
+    /// Number of memories, auto-observations included.
+    pub fn count_memories(&self) -> Result<i64> {
+        Ok(self.conn.query_row("SELECT COUNT(*) FROM memories", [], |r| r.get(0))?)
+    }
+
+    /// List memories, optionally filtering by category, staleness, and linked
+    /// symbol name. Superseded memories are hidden unless `include_superseded`.
     pub fn list_memories(
         &self,
         category: &str,
         include_stale: bool,
+        include_superseded: bool,
         symbol_name: &str,
     ) -> Result<Vec<Memory>> {
         let mut sql = String::from(
             "SELECT DISTINCT m.id, m.content, m.category, m.source, m.session_id,
-                    m.created_at, m.stale, m.needs_review
+                    m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
              FROM memories m",
         );
 
@@ -1019,29 +5002,26 @@ impl Database {
         if !include_stale {
             sql.push_str(" AND m.stale = 0");
         }
+        if !include_superseded {
+            sql.push_str(
+                " AND NOT EXISTS (SELECT 1 FROM memory_links ml
+                                  WHERE ml.target_id = m.id AND ml.kind = 'supersedes')",
+            );
+        }
         if !symbol_name.is_empty() {
             sql.push_str(&format!(" AND s.name = ?{idx}"));
             param_values.push(Box::new(symbol_name.to_string()));
             let _ = idx;
         }
 
-        sql.push_str(" ORDER BY m.created_at DESC");
+        sql.push_str(" ORDER BY m.created_at DESC, m.id DESC");
 
         let params_refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|b| b.as_ref()).collect();
 
         let mut stmt = self.conn.prepare(&sql)?;
         let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(Memory {
-                id: row.get(0)?,
-                content: row.get(1)?,
-                category: row.get(2)?,
-                source: row.get(3)?,
-                session_id: row.get(4)?,
-                created_at: row.get(5)?,
-                stale: row.get::<_, i64>(6)? != 0,
-                needs_review: row.get::<_, i64>(7)? != 0,
-            })
+            memory_from_row(row, 0)
         })?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
@@ -1054,32 +5034,23 @@ impl Database {
     ) -> Result<Vec<Memory>> {
         let sql = if include_stale {
             "SELECT m.id, m.content, m.category, m.source, m.session_id,
-                    m.created_at, m.stale, m.needs_review
+                    m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
              FROM memories m
              JOIN memory_symbols ms ON ms.memory_id = m.id
              WHERE ms.symbol_id = ?1
-             ORDER BY m.created_at DESC"
+             ORDER BY m.created_at DESC, m.id DESC"
         } else {
             "SELECT m.id, m.content, m.category, m.source, m.session_id,
-                    m.created_at, m.stale, m.needs_review
+                    m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
              FROM memories m
              JOIN memory_symbols ms ON ms.memory_id = m.id
              WHERE ms.symbol_id = ?1 AND m.stale = 0
-             ORDER BY m.created_at DESC"
+             ORDER BY m.created_at DESC, m.id DESC"
         };
 
         let mut stmt = self.conn.prepare(sql)?;
         let rows = stmt.query_map(params![symbol_id], |row| {
-            Ok(Memory {
-                id: row.get(0)?,
-                content: row.get(1)?,
-                category: row.get(2)?,
-                source: row.get(3)?,
-                session_id: row.get(4)?,
-                created_at: row.get(5)?,
-                stale: row.get::<_, i64>(6)? != 0,
-                needs_review: row.get::<_, i64>(7)? != 0,
-            })
+            memory_from_row(row, 0)
         })?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
@@ -1099,11 +5070,11 @@ impl Database {
         let stale_filter = if include_stale { "" } else { " AND m.stale = 0" };
         let sql = format!(
             "SELECT ms.symbol_id, m.id, m.content, m.category, m.source, m.session_id,
-                    m.created_at, m.stale, m.needs_review
+                    m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
              FROM memories m
              JOIN memory_symbols ms ON ms.memory_id = m.id
              WHERE ms.symbol_id IN ({placeholders}){stale_filter}
-             ORDER BY m.created_at DESC"
+             ORDER BY m.created_at DESC, m.id DESC"
         );
         let mut stmt = self.conn.prepare(&sql)?;
         let params: Vec<&dyn rusqlite::types::ToSql> =
@@ -1111,16 +5082,7 @@ impl Database {
         let rows = stmt.query_map(params.as_slice(), |row| {
             Ok((
                 row.get::<_, i64>(0)?,
-                Memory {
-                    id: row.get(1)?,
-                    content: row.get(2)?,
-                    category: row.get(3)?,
-                    source: row.get(4)?,
-                    session_id: row.get(5)?,
-                    created_at: row.get(6)?,
-                    stale: row.get::<_, i64>(7)? != 0,
-                    needs_review: row.get::<_, i64>(8)? != 0,
-                },
+                memory_from_row(row, 1)?,
             ))
         })?;
         let mut map: std::collections::HashMap<i64, Vec<Memory>> = std::collections::HashMap::new();
@@ -1135,20 +5097,11 @@ impl Database {
         let r = self
             .conn
             .query_row(
-                "SELECT id, content, category, source, session_id, created_at, stale, needs_review
+                "SELECT id, content, category, source, session_id, created_at, stale, needs_review, structured, pinned, stale_reason, stale_at
                  FROM memories WHERE id = ?1",
                 params![memory_id],
                 |row| {
-                    Ok(Memory {
-                        id: row.get(0)?,
-                        content: row.get(1)?,
-                        category: row.get(2)?,
-                        source: row.get(3)?,
-                        session_id: row.get(4)?,
-                        created_at: row.get(5)?,
-                        stale: row.get::<_, i64>(6)? != 0,
-                        needs_review: row.get::<_, i64>(7)? != 0,
-                    })
+                    memory_from_row(row, 0)
                 },
             )
             .optional()?;
@@ -1184,15 +5137,16 @@ impl Database {
         category: &str,
         symbol_ids: &[i64],
     ) -> Result<()> {
-        self.conn.execute(
-            "UPDATE memories SET content = ?1, category = ?2 WHERE id = ?3",
-            params![content, category, memory_id],
-        )?;
-        // Sync FTS index
+        // Sync FTS index: the delete tokenizes the row's current content, so
+        // it must run before the update
         self.conn.execute(
             "DELETE FROM memories_fts WHERE rowid = ?1",
             params![memory_id],
         )?;
+        self.conn.execute(
+            "UPDATE memories SET content = ?1, category = ?2 WHERE id = ?3",
+            params![content, category, memory_id],
+        )?;
         self.conn.execute(
             "INSERT INTO memories_fts(rowid, content, category) VALUES (?1, ?2, ?3)",
             params![memory_id, content, category],
@@ -1206,6 +5160,64 @@ impl Database {
         Ok(())
     }
 
+    /// Replace (or clear) the structured template fields of a memory.
+    pub fn set_memory_structured(
+        &self,
+        memory_id: i64,
+        structured: Option<&serde_json::Value>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE memories SET structured = ?1 WHERE id = ?2",
+            params![structured.map(|v| v.to_string()), memory_id],
+        )?;
+        Ok(())
+    }
+
+    /// Pin or unpin a memory. Returns false if the memory doesn't exist.
+    pub fn set_memory_pinned(&self, memory_id: i64, pinned: bool) -> Result<bool> {
+        let count = self.conn.execute(
+            "UPDATE memories SET pinned = ?1 WHERE id = ?2",
+            params![pinned as i64, memory_id],
+        )?;
+        Ok(count > 0)
+    }
+
+    /// All non-stale pinned memories, newest first.
+    pub fn list_pinned_memories(&self) -> Result<Vec<Memory>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, content, category, source, session_id, created_at, stale, needs_review, structured, pinned, stale_reason, stale_at
+             FROM memories
+             WHERE pinned = 1 AND stale = 0
+             ORDER BY created_at DESC, id DESC",
+        )?;
+        let rows = stmt.query_map([], |row| memory_from_row(row, 0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Age in days of each memory in `memory_ids`, from `created_at` to now.
+    pub fn get_memory_ages_days(
+        &self,
+        memory_ids: &[i64],
+    ) -> Result<std::collections::HashMap<i64, f64>> {
+        if memory_ids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+        let placeholders: String = memory_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "SELECT id, julianday('now') - julianday(created_at)
+             FROM memories WHERE id IN ({placeholders})"
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let params: Vec<&dyn rusqlite::types::ToSql> =
+            memory_ids.iter().map(|id| id as &dyn rusqlite::types::ToSql).collect();
+        let rows = stmt.query_map(params.as_slice(), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<f64>>(1)?.unwrap_or(0.0)))
+        })?;
+        rows.collect::<std::result::Result<_, _>>()
+            .map_err(Into::into)
+    }
+
     /// Collect (memory_id, symbol_name, body_hash) tuples for all memories linked
     /// to symbols in `file_id`. Used to re-link memories after re-indexing and
     /// detect body changes that warrant a needs_review flag.
@@ -1262,36 +5274,63 @@ impl Database {
                 if body_changed {
                     // Symbol name survived but implementation changed — flag for review
                     self.conn.execute(
-                        "UPDATE memories SET stale = 0, needs_review = 1 WHERE id = ?1",
+                        "UPDATE memories SET stale = 0, needs_review = 1, stale_reason = NULL, stale_at = NULL
+                         WHERE id = ?1",
                         params![memory_id],
                     )?;
                 } else {
                     // Symbol unchanged or hash not yet populated — clear both flags
                     self.conn.execute(
-                        "UPDATE memories SET stale = 0, needs_review = 0 WHERE id = ?1",
+                        "UPDATE memories SET stale = 0, needs_review = 0, stale_reason = NULL, stale_at = NULL
+                         WHERE id = ?1",
                         params![memory_id],
                     )?;
                 }
                 relinked += 1;
+            } else {
+                // Still stale from the re-index: the symbol itself is gone.
+                self.conn.execute(
+                    "UPDATE memories SET stale_reason = ?2 WHERE id = ?1 AND stale = 1",
+                    params![memory_id, StaleReason::SymbolRemoved.as_str()],
+                )?;
             }
         }
         Ok(relinked)
     }
 
-    /// Mark all memories linked to symbols in `file_id` as stale.
+    /// Mark all memories linked to symbols in `file_id` as stale. Memories
+    /// that already are keep their first reason and time.
     pub fn mark_memories_stale_for_file(&self, file_id: i64) -> Result<usize> {
         let count = self.conn.execute(
-            "UPDATE memories SET stale = 1
-             WHERE id IN (
+            "UPDATE memories SET stale = 1, stale_reason = ?2, stale_at = datetime('now')
+             WHERE stale = 0 AND id IN (
                  SELECT ms.memory_id FROM memory_symbols ms
                  JOIN symbols s ON s.id = ms.symbol_id
                  WHERE s.file_id = ?1
              )",
-            params![file_id],
+            params![file_id, StaleReason::FileReindexed.as_str()],
         )?;
         Ok(count)
     }
 
+    /// Mark a memory stale (reason `manual`) or fresh again by hand. A memory
+    /// that is already stale keeps its reason and time.
+    pub fn set_memory_stale(&self, memory_id: i64, stale: bool) -> Result<()> {
+        if stale {
+            self.conn.execute(
+                "UPDATE memories SET stale = 1, stale_reason = ?2, stale_at = datetime('now')
+                 WHERE id = ?1 AND stale = 0",
+                params![memory_id, StaleReason::Manual.as_str()],
+            )?;
+        } else {
+            self.conn.execute(
+                "UPDATE memories SET stale = 0, stale_reason = NULL, stale_at = NULL WHERE id = ?1",
+                params![memory_id],
+            )?;
+        }
+        Ok(())
+    }
+
     /// Delete auto-observations older than `max_age_days` days.
     /// Manual memories are never cleaned up.
     pub fn cleanup_old_auto_observations(&self, max_age_days: i64) -> Result<usize> {
@@ -1301,9 +5340,74 @@ impl Database {
                AND created_at < datetime('now', ?1)",
             params![format!("-{max_age_days} days")],
         )?;
+        // Irrelevance marks are session scratch data of the same kind.
+        self.conn.execute(
+            "DELETE FROM irrelevant_symbols WHERE marked_at < datetime('now', ?1)",
+            params![format!("-{max_age_days} days")],
+        )?;
         Ok(count)
     }
 
+    // -----------------------------------------------------------------------
+    // Irrelevant symbols
+    // -----------------------------------------------------------------------
+
+    /// Flag `symbol_ids` as off-topic for `session_id`. The mark is stored
+    /// against the symbol's repository and qualified name, so it still
+    /// applies after the file is re-indexed. Returns how many were not
+    /// already flagged; unknown ids are ignored.
+    pub fn mark_irrelevant(&self, session_id: &str, symbol_ids: &[i64], reason: &str) -> Result<usize> {
+        let mut added = 0;
+        for id in symbol_ids {
+            added += self.conn.execute(
+                "INSERT OR IGNORE INTO irrelevant_symbols (session_id, repo_id, qualified_name, reason)
+                 SELECT ?1, f.repo_id, COALESCE(NULLIF(s.qualified_name, ''), s.name), ?3
+                 FROM symbols s JOIN files f ON f.id = s.file_id
+                 WHERE s.id = ?2",
+                params![session_id, id, reason],
+            )?;
+        }
+        Ok(added)
+    }
+
+    /// Remove the flag from `symbol_ids`, or from every symbol when empty.
+    /// Returns how many flags were removed.
+    pub fn unmark_irrelevant(&self, session_id: &str, symbol_ids: &[i64]) -> Result<usize> {
+        if symbol_ids.is_empty() {
+            return Ok(self
+                .conn
+                .execute("DELETE FROM irrelevant_symbols WHERE session_id = ?1", params![session_id])?);
+        }
+        let mut removed = 0;
+        for id in symbol_ids {
+            removed += self.conn.execute(
+                "DELETE FROM irrelevant_symbols
+                 WHERE session_id = ?1
+                   AND (repo_id, qualified_name) IN (
+                       SELECT f.repo_id, COALESCE(NULLIF(s.qualified_name, ''), s.name)
+                       FROM symbols s JOIN files f ON f.id = s.file_id
+                       WHERE s.id = ?2)",
+                params![session_id, id],
+            )?;
+        }
+        Ok(removed)
+    }
+
+    /// Current ids of the symbols flagged as off-topic in `session_id`.
+    pub fn irrelevant_symbols(&self, session_id: &str) -> Result<std::collections::HashSet<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id
+             FROM irrelevant_symbols m
+             JOIN files f ON f.repo_id = m.repo_id
+             JOIN symbols s ON s.file_id = f.id
+                           AND (s.qualified_name = m.qualified_name
+                                OR (s.qualified_name = '' AND s.name = m.qualified_name))
+             WHERE m.session_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| row.get(0))?;
+        Ok(rows.collect::<std::result::Result<_, _>>()?)
+    }
+
     // -----------------------------------------------------------------------
     // Session Recovery
     // -----------------------------------------------------------------------
@@ -1317,23 +5421,14 @@ impl Database {
         let manual_memories = {
             let mut stmt = self.conn.prepare(
                 "SELECT m.id, m.content, m.category, m.source, m.session_id,
-                        m.created_at, m.stale, m.needs_review
+                        m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
                  FROM memories m
                  WHERE m.source = 'manual' AND m.stale = 0
-                 ORDER BY m.created_at DESC
+                 ORDER BY m.created_at DESC, m.id DESC
                  LIMIT 20",
             )?;
             let rows = stmt.query_map([], |row| {
-                Ok(Memory {
-                    id: row.get(0)?,
-                    content: row.get(1)?,
-                    category: row.get(2)?,
-                    source: row.get(3)?,
-                    session_id: row.get(4)?,
-                    created_at: row.get(5)?,
-                    stale: row.get::<_, i64>(6)? != 0,
-                    needs_review: row.get::<_, i64>(7)? != 0,
-                })
+                memory_from_row(row, 0)
             })?;
             rows.collect::<std::result::Result<Vec<_>, _>>()?
         };
@@ -1342,22 +5437,13 @@ impl Database {
         let auto_observations = {
             let mut stmt = self.conn.prepare(
                 "SELECT m.id, m.content, m.category, m.source, m.session_id,
-                        m.created_at, m.stale, m.needs_review
+                        m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
                  FROM memories m
                  WHERE m.session_id = ?1 AND m.source != 'manual' AND m.stale = 0
-                 ORDER BY m.created_at ASC",
+                 ORDER BY m.created_at ASC, m.id ASC",
             )?;
             let rows = stmt.query_map(params![session_id], |row| {
-                Ok(Memory {
-                    id: row.get(0)?,
-                    content: row.get(1)?,
-                    category: row.get(2)?,
-                    source: row.get(3)?,
-                    session_id: row.get(4)?,
-                    created_at: row.get(5)?,
-                    stale: row.get::<_, i64>(6)? != 0,
-                    needs_review: row.get::<_, i64>(7)? != 0,
-                })
+                memory_from_row(row, 0)
             })?;
             rows.collect::<std::result::Result<Vec<_>, _>>()?
         };
@@ -1424,37 +5510,22 @@ impl Database {
 
         let mut stmt = self.conn.prepare(
             "SELECT m.id, m.content, m.category, m.source, m.session_id,
-                    m.created_at, m.stale, m.needs_review
+                    m.created_at, m.stale, m.needs_review, m.structured, m.pinned, m.stale_reason, m.stale_at
              FROM memories_fts fts
              JOIN memories m ON m.id = fts.rowid
              WHERE memories_fts MATCH ?1
-             ORDER BY rank
+             ORDER BY rank, m.id DESC
              LIMIT ?2",
         )?;
         let rows = stmt.query_map(params![fts_query, max_results], |row| {
-            Ok(Memory {
-                id: row.get(0)?,
-                content: row.get(1)?,
-                category: row.get(2)?,
-                source: row.get(3)?,
-                session_id: row.get(4)?,
-                created_at: row.get(5)?,
-                stale: row.get::<_, i64>(6)? != 0,
-                needs_review: row.get::<_, i64>(7)? != 0,
-            })
+            memory_from_row(row, 0)
         })?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
     }
 
-    /// Full-text search over symbols. Filters by kind and repo_id are optional.
-    pub fn search_code(
-        &self,
-        query: &str,
-        kind: &str,
-        repo_id: Option<i64>,
-        max_results: i64,
-    ) -> Result<Vec<Symbol>> {
+    /// Full-text search over the symbols that match `filter`.
+    pub fn search_code(&self, query: &str, filter: &SearchFilter, max_results: i64) -> Result<Vec<Symbol>> {
         // Sanitize for FTS5: wrap each token in double quotes to prevent
         // FTS5 operators (AND, OR, NOT, NEAR, *, +, -) from being interpreted.
         // Inner double-quotes are escaped by doubling them.
@@ -1467,64 +5538,68 @@ impl Database {
         let mut sql = String::from(
             "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
                     s.body_hash, s.start_line, s.end_line, s.parent_id,
-                    s.qualified_name, s.source, s.manifest_repo
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes
              FROM symbols_fts fts
              JOIN symbols s ON s.id = fts.rowid",
         );
 
-        let need_repo_join = repo_id.is_some();
-        if need_repo_join {
-            sql.push_str(" JOIN files f ON f.id = s.file_id");
-        }
-
-        sql.push_str(" WHERE symbols_fts MATCH ?1");
-
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-        param_values.push(Box::new(fts_query));
-        let mut idx = 2;
+        // Files are joined for the filters and for the path tie-breaker.
+        sql.push_str(" JOIN files f ON f.id = s.file_id");
 
-        if !kind.is_empty() {
-            sql.push_str(&format!(" AND s.kind = ?{idx}"));
-            param_values.push(Box::new(kind.to_string()));
-            idx += 1;
-        }
-        if let Some(rid) = repo_id {
-            sql.push_str(&format!(" AND f.repo_id = ?{idx}"));
-            param_values.push(Box::new(rid));
-            let _ = idx;
-        }
+        // External stubs (`external://`) stand in for library symbols and
+        // are not search results.
+        sql.push_str(" WHERE symbols_fts MATCH ?1 AND s.source IS NOT 'external'");
+        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(fts_query)];
+        filter.push_conditions(&mut sql, &mut param_values);
 
-        sql.push_str(" ORDER BY rank LIMIT ?");
-        // We need the next param index
+        // Equal ranks fall back to name, path and line so results are stable.
         let limit_idx = param_values.len() + 1;
-        // Rewrite last push
-        sql = sql.replace(
-            " ORDER BY rank LIMIT ?",
-            &format!(" ORDER BY rank LIMIT ?{limit_idx}"),
-        );
+        sql.push_str(&format!(" ORDER BY rank, s.name, f.path, s.start_line LIMIT ?{limit_idx}"));
         param_values.push(Box::new(max_results));
 
         let params_refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|b| b.as_ref()).collect();
 
         let mut stmt = self.conn.prepare(&sql)?;
-        let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(Symbol {
-                id: row.get(0)?,
-                file_id: row.get(1)?,
-                name: row.get(2)?,
-                qualified_name: row.get(10)?,
-                kind: row.get(3)?,
-                signature: row.get(4)?,
-                body: row.get(5)?,
-                body_hash: row.get(6)?,
-                start_line: row.get(7)?,
-                end_line: row.get(8)?,
-                parent_id: row.get(9)?,
-                source: row.get(11)?,
-                manifest_repo: row.get(12)?,
-            })
-        })?;
+        let rows = stmt.query_map(params_refs.as_slice(), |row| Symbol::from_row(row, 0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Symbols whose body contains `text`: an FTS phrase query on the body
+    /// column first, then a case-insensitive LIKE scan when the phrase finds
+    /// nothing (e.g. text that is mostly punctuation).
+    pub fn find_symbols_containing(&self, text: &str, repo_id: Option<i64>, limit: i64) -> Result<Vec<Symbol>> {
+        let select = "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
+                    s.body_hash, s.start_line, s.end_line, s.parent_id,
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes";
+        let map = |row: &rusqlite::Row| Symbol::from_row(row, 0);
+
+        let phrase = format!("body : \"{}\"", text.replace('"', "\"\""));
+        let mut stmt = self.conn.prepare(&format!(
+            "{select} FROM symbols_fts fts
+             JOIN symbols s ON s.id = fts.rowid
+             JOIN files f ON f.id = s.file_id
+             WHERE symbols_fts MATCH ?1 AND (?2 IS NULL OR f.repo_id = ?2)
+             ORDER BY rank, f.path, s.start_line LIMIT ?3"
+        ))?;
+        // Text without any word characters is not a valid FTS phrase.
+        let found = match stmt.query_map(params![phrase, repo_id, limit], map) {
+            Ok(rows) => rows.collect::<std::result::Result<Vec<_>, _>>().unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+        if !found.is_empty() {
+            return Ok(found);
+        }
+
+        let pattern = format!("%{}%", like_escape(text));
+        let mut stmt = self.conn.prepare(&format!(
+            "{select} FROM symbols s
+             JOIN files f ON f.id = s.file_id
+             WHERE s.body LIKE ?1 ESCAPE '\\' AND (?2 IS NULL OR f.repo_id = ?2)
+             ORDER BY length(s.body), f.path, s.start_line LIMIT ?3"
+        ))?;
+        let rows = stmt.query_map(params![pattern, repo_id, limit], map)?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
     }
@@ -1536,13 +5611,12 @@ impl Database {
     pub fn search_code_with_recency(
         &self,
         query: &str,
-        kind: &str,
-        repo_id: Option<i64>,
+        filter: &SearchFilter,
         max_results: i64,
         recency_boost: f64,
     ) -> Result<Vec<Symbol>> {
         if recency_boost <= 0.0 {
-            return self.search_code(query, kind, repo_id, max_results);
+            return self.search_code(query, filter, max_results);
         }
 
         let fts_query: String = query
@@ -1556,38 +5630,23 @@ impl Database {
         // multiplier; older files get 1.0 (no penalty).
         let mut sql = "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
                     s.body_hash, s.start_line, s.end_line, s.parent_id,
-                    s.qualified_name, s.source, s.manifest_repo
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes
              FROM symbols_fts fts
              JOIN symbols s ON s.id = fts.rowid
              JOIN files f ON f.id = s.file_id".to_string();
 
-        if repo_id.is_some() {
-            // already joined files
-        }
-
-        sql.push_str(" WHERE symbols_fts MATCH ?1");
-
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-        param_values.push(Box::new(fts_query));
-        let mut idx = 2;
-
-        if !kind.is_empty() {
-            sql.push_str(&format!(" AND s.kind = ?{idx}"));
-            param_values.push(Box::new(kind.to_string()));
-            idx += 1;
-        }
-        if let Some(rid) = repo_id {
-            sql.push_str(&format!(" AND f.repo_id = ?{idx}"));
-            param_values.push(Box::new(rid));
-            idx += 1;
-        }
+        sql.push_str(" WHERE symbols_fts MATCH ?1 AND s.source IS NOT 'external'");
+        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(fts_query)];
+        filter.push_conditions(&mut sql, &mut param_values);
+        let idx = param_values.len() + 1;
 
         // rank is negative in FTS5 (lower = better), so we multiply by a
         // factor < 1.0 for recent files to make them rank higher.
         // recency_factor: 1.0 for old files, (1 - boost*decay) for recent.
         sql.push_str(&format!(
             " ORDER BY rank * (1.0 - ?{idx} * MAX(0.0, \
-             (julianday(f.indexed_at) - julianday('now', '-2 days')) / 2.0)) \
+             (julianday(f.indexed_at) - julianday('now', '-2 days')) / 2.0)), \
+             s.name, f.path, s.start_line \
              LIMIT ?{}",
             idx + 1
         ));
@@ -1598,23 +5657,7 @@ impl Database {
             param_values.iter().map(|b| b.as_ref()).collect();
 
         let mut stmt = self.conn.prepare(&sql)?;
-        let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(Symbol {
-                id: row.get(0)?,
-                file_id: row.get(1)?,
-                name: row.get(2)?,
-                qualified_name: row.get(10)?,
-                kind: row.get(3)?,
-                signature: row.get(4)?,
-                body: row.get(5)?,
-                body_hash: row.get(6)?,
-                start_line: row.get(7)?,
-                end_line: row.get(8)?,
-                parent_id: row.get(9)?,
-                source: row.get(11)?,
-                manifest_repo: row.get(12)?,
-            })
-        })?;
+        let rows = stmt.query_map(params_refs.as_slice(), |row| Symbol::from_row(row, 0))?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
     }
@@ -1627,6 +5670,8 @@ impl Database {
         &self,
         terms: &[&str],
         repo_id: Option<i64>,
+        package: &str,
+        include_generated: bool,
         limit: i64,
     ) -> Result<Vec<Symbol>> {
         if terms.is_empty() {
@@ -1640,7 +5685,7 @@ impl Database {
             param_values.push(Box::new(format!("%{term}%")));
         }
 
-        let repo_join = if repo_id.is_some() {
+        let repo_join = if repo_id.is_some() || !package.is_empty() || !include_generated {
             "JOIN files f ON f.id = s.file_id"
         } else {
             ""
@@ -1648,42 +5693,116 @@ impl Database {
         let mut sql = format!(
             "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
                     s.body_hash, s.start_line, s.end_line, s.parent_id,
-                    s.qualified_name, s.source, s.manifest_repo
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes
              FROM symbols s {repo_join} WHERE ({})",
             conditions.join(" OR ")
         );
 
+        if !include_generated {
+            sql.push_str(" AND f.generated = 0");
+        }
         if let Some(rid) = repo_id {
             sql.push_str(&format!(" AND f.repo_id = ?{}", param_values.len() + 1));
             param_values.push(Box::new(rid));
         }
-        sql.push_str(&format!(" LIMIT ?{}", param_values.len() + 1));
+        if !package.is_empty() {
+            sql.push_str(&format!(" AND f.package = ?{}", param_values.len() + 1));
+            param_values.push(Box::new(package.to_string()));
+        }
+        sql.push_str(&format!(" ORDER BY s.name, s.file_id, s.start_line LIMIT ?{}", param_values.len() + 1));
         param_values.push(Box::new(limit));
 
         let refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|b| b.as_ref()).collect();
         let mut stmt = self.conn.prepare(&sql)?;
-        let rows = stmt.query_map(refs.as_slice(), |row| {
-            Ok(Symbol {
-                id: row.get(0)?,
-                file_id: row.get(1)?,
-                name: row.get(2)?,
-                qualified_name: row.get(10)?,
-                kind: row.get(3)?,
-                signature: row.get(4)?,
-                body: row.get(5)?,
-                body_hash: row.get(6)?,
-                start_line: row.get(7)?,
-                end_line: row.get(8)?,
-                parent_id: row.get(9)?,
-                source: row.get(11)?,
-                manifest_repo: row.get(12)?,
-            })
-        })?;
+        let rows = stmt.query_map(refs.as_slice(), |row| Symbol::from_row(row, 0))?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
             .map_err(Into::into)
     }
 
+    // -----------------------------------------------------------------------
+    // Name suggestions
+    // -----------------------------------------------------------------------
+
+    /// Return up to `limit` indexed symbol names closest to `name`, for
+    /// "did you mean" hints when an exact lookup misses. Candidates are ranked
+    /// case-insensitively: prefix matches first, then substring matches, then
+    /// by edit distance. Names too far from the query are dropped entirely;
+    /// SQL pre-filters by length and substring so the edit distance only
+    /// runs over plausible names.
+    pub fn suggest_symbol_names(
+        &self,
+        name: &str,
+        repo_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<String>> {
+        let query = name.to_lowercase();
+        if query.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Allow roughly one typo per three characters, but at least two.
+        let query_len = query.chars().count();
+        let max_distance = (query_len / 3).max(2);
+        // A name the query merely contains must cover at least half of it,
+        // or one-letter names would match every query.
+        let min_len = query_len.div_ceil(2).min(query_len.saturating_sub(max_distance)).max(1);
+        let max_len = query_len + max_distance;
+
+        // Names of a plausible length, or containing the query.
+        let names: Vec<String> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT DISTINCT s.name FROM symbols s
+                 JOIN files f ON f.id = s.file_id
+                 WHERE (?1 IS NULL OR f.repo_id = ?1)
+                   AND (length(s.name) BETWEEN ?2 AND ?3 OR instr(lower(s.name), ?4) > 0)",
+            )?;
+            let rows = stmt.query_map(
+                params![repo_id, min_len as i64, max_len as i64, query],
+                |row| row.get::<_, String>(0),
+            )?;
+            rows.collect::<std::result::Result<Vec<_>, _>>()?
+        };
+
+        let mut scored: Vec<(u8, usize, String)> = names
+            .into_iter()
+            .filter_map(|candidate| {
+                let lower = candidate.to_lowercase();
+                let distance = edit_distance(&query, &lower);
+                let covers_half = lower.chars().count() * 2 >= query_len;
+                let tier = if lower.starts_with(&query) || (covers_half && query.starts_with(&lower)) {
+                    0
+                } else if lower.contains(&query) || (covers_half && query.contains(&lower)) {
+                    1
+                } else if distance <= max_distance {
+                    2
+                } else {
+                    return None;
+                };
+                Some((tier, distance, candidate))
+            })
+            .collect();
+
+        scored.sort();
+        Ok(scored.into_iter().take(limit).map(|(_, _, n)| n).collect())
+    }
+
+    /// Build a "symbol not found" error message, appending the closest known
+    /// names when there are any so the caller can retry without a blind search.
+    pub fn symbol_not_found_message(&self, name: &str, repo_id: Option<i64>) -> String {
+        let suggestions = self
+            .suggest_symbol_names(name, repo_id, 5)
+            .unwrap_or_default();
+        if suggestions.is_empty() {
+            format!("symbol '{name}' not found")
+        } else {
+            format!(
+                "symbol '{name}' not found. Did you mean: {}?",
+                suggestions.join(", ")
+            )
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Stats / Overview
     // -----------------------------------------------------------------------
@@ -1712,13 +5831,13 @@ impl Database {
         let repo_count: i64 = self
             .conn
             .query_row("SELECT COUNT(*) FROM repositories", [], |r| r.get(0))?;
-        let fts_ok = self
+        let fts_ok = self.fts_indexes_ok()?;
+        let index_error_count: i64 = self
             .conn
-            .execute(
-                "INSERT INTO symbols_fts(symbols_fts) VALUES ('integrity-check')",
-                [],
-            )
-            .is_ok();
+            .query_row("SELECT COUNT(DISTINCT repo_id || '/' || path) FROM index_errors", [], |r| r.get(0))?;
+        let redaction_count: i64 = self
+            .conn
+            .query_row("SELECT COALESCE(SUM(redactions), 0) FROM files", [], |r| r.get(0))?;
         Ok(HealthReport {
             db_size_bytes: db_size,
             symbol_count,
@@ -1727,38 +5846,310 @@ impl Database {
             memory_count,
             repo_count,
             fts_ok,
+            index_error_count,
+            redaction_count,
+            wal_size_bytes: self.wal_size_bytes(),
+        })
+    }
+
+    /// Fail unless a write transaction could be opened: the file is
+    /// read-only or the disk is gone. The attempt runs with no busy timeout,
+    /// so another connection holding the write lock is not waited out; that
+    /// returns `Ok(true)`, writable but contended. Nothing is written.
+    pub fn check_writable(&self) -> Result<bool> {
+        if self.conn.is_readonly(rusqlite::MAIN_DB)? {
+            anyhow::bail!("database opened read-only");
+        }
+        if !self.conn.is_autocommit() {
+            return Ok(false); // a write transaction is already open on this connection
+        }
+        let timeout_ms: i64 = self.conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0))?;
+        self.conn.busy_timeout(std::time::Duration::ZERO)?;
+        let attempt = self.conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK");
+        self.conn.busy_timeout(std::time::Duration::from_millis(timeout_ms.max(0) as u64))?;
+        match attempt {
+            Ok(()) => Ok(false),
+            Err(e) if e.sqlite_error_code() == Some(rusqlite::ErrorCode::DatabaseBusy) => Ok(true),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Size of the write-ahead log on disk, if the database is file-backed.
+    pub fn wal_size_bytes(&self) -> Option<u64> {
+        let path = self.conn.path().filter(|p| !p.is_empty())?;
+        Some(std::fs::metadata(format!("{path}-wal")).map_or(0, |m| m.len()))
+    }
+
+    /// Copy the WAL back into the database and truncate it to zero bytes.
+    /// Without this a long-running server's WAL only ever grows, because
+    /// automatic checkpoints never reset the file.
+    pub fn checkpoint_wal(&self) -> Result<WalCheckpoint> {
+        let bytes_before = self.wal_size_bytes().unwrap_or(0);
+        let busy: i64 = self
+            .conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+        Ok(WalCheckpoint {
+            busy: busy != 0,
+            bytes_before,
+            bytes_after: self.wal_size_bytes().unwrap_or(0),
+        })
+    }
+
+    /// Copy the whole database to a new file at `dest` with SQLite's online
+    /// backup API, which reads a consistent snapshot while writers carry on.
+    /// `key` encrypts the copy; pass the source's key for an encrypted
+    /// index. The copy uses a rollback journal, so it is one file.
+    pub fn backup_to(&self, dest: &std::path::Path, key: Option<&str>) -> Result<()> {
+        let dest_str = dest.to_string_lossy();
+        let mut conn = Connection::open(dest).with_context(|| format!("failed to create {dest_str}"))?;
+        if let Some(key) = key {
+            apply_key(&conn, &dest_str, key)?;
+        }
+        rusqlite::backup::Backup::new(&self.conn, &mut conn)?
+            .run_to_completion(1024, std::time::Duration::ZERO, None)
+            .with_context(|| format!("failed to back up to {dest_str}"))?;
+        conn.query_row("PRAGMA journal_mode = DELETE", [], |row| row.get::<_, String>(0))?;
+        Ok(())
+    }
+
+    /// Replace the whole database with the copy at `src` (keyed with `key`
+    /// when encrypted), then migrate it to the current schema.
+    pub fn restore_from(&mut self, src: &std::path::Path, key: Option<&str>) -> Result<()> {
+        let src_str = src.to_string_lossy();
+        let from = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+            .with_context(|| format!("failed to open {src_str}"))?;
+        if let Some(key) = key {
+            apply_key(&from, &src_str, key)?;
+        }
+        rusqlite::backup::Backup::new(&from, &mut self.conn)?
+            .run_to_completion(1024, std::time::Duration::ZERO, None)
+            .with_context(|| format!("failed to restore from {src_str}"))?;
+        if let Ok(mut dirty) = self.graph_dirty.lock() {
+            dirty.invalidate();
+        }
+        self.apply_pragmas()?;
+        self.migrate()
+    }
+
+    /// `PRAGMA quick_check`: fail unless the file's structure is intact.
+    pub fn quick_check(&self) -> Result<()> {
+        let result: String = self.conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        if result != "ok" {
+            anyhow::bail!("database is damaged: {result}");
+        }
+        Ok(())
+    }
+
+    // -----------------------------------------------------------------------
+    // Diagnostics
+    // -----------------------------------------------------------------------
+
+    pub fn list_repositories(&self) -> Result<Vec<Repository>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, root_path, indexed_at, revision, branch
+             FROM repositories ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Repository {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                root_path: row.get(2)?,
+                indexed_at: row.get(3)?,
+                revision: row.get(4)?,
+                branch: row.get(5)?,
+            })
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    }
+
+    /// Delete a repository with its files, symbols, and edges (via cascade).
+    /// Returns true if the repository existed.
+    pub fn delete_repository(&self, repo_id: i64) -> Result<bool> {
+        self.conn.execute(
+            "DELETE FROM symbols_fts WHERE rowid IN (
+                 SELECT s.id FROM symbols s JOIN files f ON s.file_id = f.id
+                 WHERE f.repo_id = ?1)",
+            params![repo_id],
+        )?;
+        let count = self
+            .conn
+            .execute("DELETE FROM repositories WHERE id = ?1", params![repo_id])?;
+        Ok(count > 0)
+    }
+
+    /// Row counts of `symbols` and of documents in `symbols_fts`. They should
+    /// match; a difference means FTS search misses or returns ghost rows.
+    pub fn fts_symbol_counts(&self) -> Result<(i64, i64)> {
+        let symbols: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM symbols", [], |r| r.get(0))?;
+        let fts_docs: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM symbols_fts_docsize", [], |r| r.get(0))?;
+        Ok((symbols, fts_docs))
+    }
+
+    /// Compare `symbols_fts` and `memories_fts` with their content tables and
+    /// repair drift: rows missing from the index are inserted, and index
+    /// entries whose row is gone are deleted. An external-content index can't
+    /// drop a deleted row's tokens without its text. If the integrity check
+    /// still fails after the incremental repair, the table is rebuilt.
+    pub fn check_fts_consistency(&self) -> Result<Vec<FtsRepair>> {
+        self.with_transaction(|| {
+            Ok(vec![
+                self.repair_fts("symbols_fts", "symbols", "name, signature, body", "name, signature, COALESCE(body, '')")?,
+                self.repair_fts("memories_fts", "memories", "content, category", "content, category")?,
+            ])
         })
     }
 
+    fn repair_fts(&self, fts: &'static str, content: &str, columns: &str, values: &str) -> Result<FtsRepair> {
+        let orphans = format!(
+            "SELECT d.id FROM {fts}_docsize d WHERE NOT EXISTS (SELECT 1 FROM {content} c WHERE c.id = d.id)"
+        );
+        // Virtual-table deletes don't report a change count
+        let removed: i64 = self.conn.query_row(&format!("SELECT COUNT(*) FROM ({orphans})"), [], |r| r.get(0))?;
+        self.conn.execute(&format!("DELETE FROM {fts} WHERE rowid IN ({orphans})"), [])?;
+        let inserted = self.conn.execute(
+            &format!(
+                "INSERT INTO {fts}(rowid, {columns})
+                 SELECT id, {values} FROM {content}
+                 WHERE NOT EXISTS (SELECT 1 FROM {fts}_docsize d WHERE d.id = {content}.id)"
+            ),
+            [],
+        )?;
+        let rebuilt = !self.fts_content_matches(fts);
+        if rebuilt {
+            self.conn.execute(&format!("INSERT INTO {fts}({fts}) VALUES ('rebuild')"), [])?;
+        }
+        Ok(FtsRepair { table: fts, inserted, removed: removed as usize, rebuilt })
+    }
+
+    /// Whether `symbols_fts` and `memories_fts` index exactly the rowids of
+    /// their content tables and pass FTS5's structural integrity check.
+    /// Rowids are compared through the primary keys and no text is read, so
+    /// this is cheap enough for every `get_health` call; `fts_content_ok`
+    /// also catches entries whose tokens no longer match their row.
+    pub fn fts_indexes_ok(&self) -> Result<bool> {
+        for (fts, content) in [("symbols_fts", "symbols"), ("memories_fts", "memories")] {
+            let drift: i64 = self.conn.query_row(
+                &format!(
+                    "SELECT (SELECT COUNT(*) FROM {content} c
+                             WHERE NOT EXISTS (SELECT 1 FROM {fts}_docsize d WHERE d.id = c.id))
+                          + (SELECT COUNT(*) FROM {fts}_docsize d
+                             WHERE NOT EXISTS (SELECT 1 FROM {content} c WHERE c.id = d.id))"
+                ),
+                [],
+                |r| r.get(0),
+            )?;
+            let structure_ok =
+                self.conn.execute(&format!("INSERT INTO {fts}({fts}) VALUES ('integrity-check')"), []).is_ok();
+            if drift > 0 || !structure_ok {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// FTS5's integrity check with content comparison on both FTS tables.
+    /// It re-tokenizes every row, so `focal doctor` runs it rather than
+    /// `get_health`.
+    pub fn fts_content_ok(&self) -> bool {
+        self.fts_content_matches("symbols_fts") && self.fts_content_matches("memories_fts")
+    }
+
+    fn fts_content_matches(&self, fts: &str) -> bool {
+        self.conn
+            .execute(&format!("INSERT INTO {fts}({fts}, rank) VALUES ('integrity-check', 1)"), [])
+            .is_ok()
+    }
+
+    /// Edges whose source or target symbol no longer exists.
+    pub fn count_orphaned_edges(&self) -> Result<i64> {
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM edges e
+             WHERE NOT EXISTS (SELECT 1 FROM symbols s WHERE s.id = e.source_id)
+                OR NOT EXISTS (SELECT 1 FROM symbols s WHERE s.id = e.target_id)",
+            [],
+            |r| r.get(0),
+        )?;
+        Ok(count)
+    }
+
+    pub fn delete_orphaned_edges(&self) -> Result<usize> {
+        let count = self.conn.execute(
+            "DELETE FROM edges
+             WHERE NOT EXISTS (SELECT 1 FROM symbols s WHERE s.id = edges.source_id)
+                OR NOT EXISTS (SELECT 1 FROM symbols s WHERE s.id = edges.target_id)",
+            [],
+        )?;
+        Ok(count)
+    }
+
+    /// Locally-indexed symbols with an empty body. Returns the total count and
+    /// up to `limit` examples as `path:name`. Manifest imports carry no bodies
+    /// by design and are excluded.
+    pub fn symbols_with_empty_body(&self, limit: i64) -> Result<(i64, Vec<String>)> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM symbols WHERE body = '' AND manifest_repo IS NULL",
+            [],
+            |r| r.get(0),
+        )?;
+        let mut stmt = self.conn.prepare(
+            "SELECT f.path || ':' || s.name FROM symbols s
+             JOIN files f ON s.file_id = f.id
+             WHERE s.body = '' AND s.manifest_repo IS NULL
+             ORDER BY f.path, s.start_line
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit], |r| r.get(0))?;
+        let examples = rows.collect::<std::result::Result<Vec<String>, _>>()?;
+        Ok((count, examples))
+    }
+
     pub fn get_repo_overview(&self, repo_name: &str) -> Result<Vec<RepoOverview>> {
         let mut sql = String::from(
-            "SELECT r.id, r.name, r.root_path FROM repositories r",
+            "SELECT r.id, r.name, r.root_path, r.revision, r.branch, r.indexed_at,
+                    CAST(strftime('%s', 'now') - strftime('%s', r.indexed_at) AS INTEGER)
+             FROM repositories r",
         );
         let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
 
         if !repo_name.is_empty() {
             sql.push_str(" WHERE r.name = ?1");
-            param_values.push(Box::new(repo_name.to_string()));
+            param_values.push(Box::new(self.current_repo_name(repo_name)?));
+        } else {
+            // The `external` stub repository is not a repository anyone indexed.
+            sql.push_str(" WHERE r.root_path != ?1");
+            param_values.push(Box::new(EXTERNAL_ROOT));
         }
 
         let params_refs: Vec<&dyn rusqlite::types::ToSql> =
             param_values.iter().map(|b| b.as_ref()).collect();
 
         let mut stmt = self.conn.prepare(&sql)?;
-        let repos: Vec<(i64, String, String)> = stmt
+        type RepoRow = (i64, String, String, Option<String>, Option<String>, Option<String>, Option<i64>);
+        let repos: Vec<RepoRow> = stmt
             .query_map(params_refs.as_slice(), |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?))
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         let mut out = Vec::new();
-        for (repo_id, name, root_path) in repos {
+        for (repo_id, name, root_path, revision, branch, indexed_at, index_age_secs) in repos {
             let file_count: i64 = self.conn.query_row(
                 "SELECT COUNT(*) FROM files WHERE repo_id = ?1",
                 params![repo_id],
                 |r| r.get(0),
             )?;
 
+            let line_count: i64 = self.conn.query_row(
+                "SELECT COALESCE(SUM(line_count), 0) FROM files WHERE repo_id = ?1",
+                params![repo_id],
+                |r| r.get(0),
+            )?;
+
             let symbol_count: i64 = self.conn.query_row(
                 "SELECT COUNT(*) FROM symbols s
                  JOIN files f ON f.id = s.file_id
@@ -1766,6 +6157,7 @@ impl Database {
                 params![repo_id],
                 |r| r.get(0),
             )?;
+            let (avg_symbol_lines, max_symbol_lines) = self.symbol_line_stats(repo_id)?;
 
             let memory_count: i64 = self.conn.query_row(
                 "SELECT COUNT(DISTINCT m.id) FROM memories m
@@ -1778,71 +6170,292 @@ impl Database {
             )?;
 
             let mut lang_stmt = self.conn.prepare(
-                "SELECT language, COUNT(*) as cnt FROM files
-                 WHERE repo_id = ?1 GROUP BY language ORDER BY cnt DESC",
+                "SELECT f.language, COUNT(*) as cnt,
+                        COALESCE(SUM((SELECT COUNT(*) FROM symbols s WHERE s.file_id = f.id)), 0)
+                 FROM files f
+                 WHERE f.repo_id = ?1 GROUP BY f.language ORDER BY cnt DESC, f.language",
             )?;
             let languages: Vec<LanguageCount> = lang_stmt
                 .query_map(params![repo_id], |row| {
                     Ok(LanguageCount {
                         language: row.get(0)?,
                         count: row.get(1)?,
+                        symbols: row.get(2)?,
                     })
                 })?
                 .collect::<std::result::Result<Vec<_>, _>>()?;
 
+            let symbol_kinds = self.kind_counts(
+                "SELECT s.kind, COUNT(*) AS cnt FROM symbols s
+                 JOIN files f ON f.id = s.file_id
+                 WHERE f.repo_id = ?1 GROUP BY s.kind ORDER BY cnt DESC, s.kind",
+                repo_id,
+            )?;
+            let edge_kinds = self.kind_counts(
+                "SELECT e.kind, COUNT(*) AS cnt FROM edges e
+                 JOIN symbols s ON s.id = e.source_id
+                 JOIN files f ON f.id = s.file_id
+                 WHERE f.repo_id = ?1 GROUP BY e.kind ORDER BY cnt DESC, e.kind",
+                repo_id,
+            )?;
+
+            let mut project_stmt = self.conn.prepare(
+                "SELECT project FROM project_repos WHERE repo = ?1 ORDER BY project",
+            )?;
+            let projects: Vec<String> = project_stmt
+                .query_map(params![name], |row| row.get(0))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
             out.push(RepoOverview {
                 name,
                 root_path,
+                revision,
+                branch,
                 file_count,
+                line_count,
                 symbol_count,
+                avg_symbol_lines,
+                max_symbol_lines,
                 memory_count,
                 languages,
+                symbol_kinds,
+                edge_kinds,
+                indexed_at,
+                index_age_secs,
+                projects,
+                docs: Vec::new(),
             });
         }
 
         Ok(out)
     }
 
-    /// Return symbols in a file as summaries (no body), optionally scoped to a repo.
-    /// Matches file path with a LIKE suffix pattern so callers can pass relative paths.
-    pub fn get_file_symbols_summary(
-        &self,
-        file_path: &str,
-        repo_name: Option<&str>,
-    ) -> Result<Vec<SymbolSummary>> {
-        let mut sql = String::from(
-            "SELECT s.name, s.kind, s.signature, s.start_line, s.end_line
-             FROM symbols s
-             JOIN files f ON f.id = s.file_id
-             JOIN repositories r ON r.id = f.repo_id
-             WHERE f.path LIKE ?1",
-        );
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-        let pattern = format!("%{file_path}");
-        param_values.push(Box::new(pattern));
+    /// Set `docs` on each overview from its repository's `module_docs`.
+    pub fn fill_package_docs(&self, overviews: &mut [RepoOverview]) -> Result<()> {
+        for overview in overviews {
+            if let Some(repo) = self.get_repository_by_path(&overview.root_path)? {
+                overview.docs = self.get_package_docs(&repo)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a `(kind, count)` aggregate over one repository (`?1`).
+    fn kind_counts(&self, sql: &str, repo_id: i64) -> Result<Vec<KindCount>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let counts = stmt
+            .query_map(params![repo_id], |row| Ok(KindCount { kind: row.get(0)?, count: row.get(1)? }))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(counts)
+    }
 
-        if let Some(rn) = repo_name {
-            sql.push_str(" AND r.name = ?2");
-            param_values.push(Box::new(rn.to_string()));
+    /// Overview of a configured project: each indexed member's overview,
+    /// summed counts, and how many edges cross between members. None when no
+    /// such project is configured.
+    pub fn get_project_overview(&self, project: &str) -> Result<Option<ProjectOverview>> {
+        let Some(members) = self.project_repositories(project)? else {
+            return Ok(None);
+        };
+        let mut repos = Vec::new();
+        for member in &members {
+            repos.extend(self.get_repo_overview(&member.name)?);
         }
 
-        sql.push_str(" ORDER BY s.start_line");
+        let mut languages: std::collections::HashMap<String, (i64, i64)> = std::collections::HashMap::new();
+        for lang in repos.iter().flat_map(|r| &r.languages) {
+            let entry = languages.entry(lang.language.clone()).or_default();
+            entry.0 += lang.count;
+            entry.1 += lang.symbols;
+        }
+        let mut languages: Vec<LanguageCount> = languages
+            .into_iter()
+            .map(|(language, (count, symbols))| LanguageCount { language, count, symbols })
+            .collect();
+        languages.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.language.cmp(&b.language)));
 
-        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
-            param_values.iter().map(|b| b.as_ref()).collect();
+        let mut symbol_kinds: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+        for kind in repos.iter().flat_map(|r| &r.symbol_kinds) {
+            *symbol_kinds.entry(kind.kind.clone()).or_default() += kind.count;
+        }
+        let mut symbol_kinds: Vec<KindCount> =
+            symbol_kinds.into_iter().map(|(kind, count)| KindCount { kind, count }).collect();
+        symbol_kinds.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.kind.cmp(&b.kind)));
+
+        let cross_repo_edges: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM edges e
+             JOIN symbols s1 ON s1.id = e.source_id JOIN files f1 ON f1.id = s1.file_id
+             JOIN symbols s2 ON s2.id = e.target_id JOIN files f2 ON f2.id = s2.file_id
+             JOIN repositories r1 ON r1.id = f1.repo_id
+             JOIN repositories r2 ON r2.id = f2.repo_id
+             WHERE f1.repo_id != f2.repo_id
+               AND r1.name IN (SELECT repo FROM project_repos WHERE project = ?1)
+               AND r2.name IN (SELECT repo FROM project_repos WHERE project = ?1)",
+            params![project],
+            |row| row.get(0),
+        )?;
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(SymbolSummary {
+        let mut stmt = self.conn.prepare(
+            "SELECT repo FROM project_repos
+             WHERE project = ?1 AND repo NOT IN (SELECT name FROM repositories)
+             ORDER BY repo",
+        )?;
+        let missing_repos = stmt
+            .query_map(params![project], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(Some(ProjectOverview {
+            name: project.to_string(),
+            file_count: repos.iter().map(|r| r.file_count).sum(),
+            line_count: repos.iter().map(|r| r.line_count).sum(),
+            symbol_count: repos.iter().map(|r| r.symbol_count).sum(),
+            languages,
+            symbol_kinds,
+            cross_repo_edges,
+            repos,
+            missing_repos,
+        }))
+    }
+
+    /// Mean (one decimal) and largest length in lines of a repository's
+    /// indexed symbols; manifest imports carry no line ranges and are skipped.
+    fn symbol_line_stats(&self, repo_id: i64) -> Result<(f64, i64)> {
+        let (avg, max): (Option<f64>, Option<i64>) = self.conn.query_row(
+            "SELECT AVG(s.end_line - s.start_line + 1), MAX(s.end_line - s.start_line + 1)
+             FROM symbols s JOIN files f ON f.id = s.file_id
+             WHERE f.repo_id = ?1 AND s.source = 'local'",
+            params![repo_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        Ok((round1(avg.unwrap_or(0.0)), max.unwrap_or(0)))
+    }
+
+    /// Line and symbol statistics for a repository: totals, per-kind
+    /// breakdown, and the `top` largest files and symbols.
+    pub fn get_size_report(&self, repo: &Repository, top: usize) -> Result<SizeReport> {
+        let (file_count, line_count): (i64, i64) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(line_count), 0) FROM files WHERE repo_id = ?1",
+            params![repo.id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        let (avg_symbol_lines, max_symbol_lines) = self.symbol_line_stats(repo.id)?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT s.kind, COUNT(*), SUM(s.end_line - s.start_line + 1)
+             FROM symbols s JOIN files f ON f.id = s.file_id
+             WHERE f.repo_id = ?1 AND s.source = 'local'
+             GROUP BY s.kind ORDER BY COUNT(*) DESC, s.kind",
+        )?;
+        let kinds = stmt
+            .query_map(params![repo.id], |row| {
+                let count: i64 = row.get(1)?;
+                let total_lines: i64 = row.get(2)?;
+                Ok(KindSize {
+                    kind: row.get(0)?,
+                    count,
+                    total_lines,
+                    avg_lines: round1(total_lines as f64 / count as f64),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let symbol_count = kinds.iter().map(|k| k.count).sum();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT f.path, f.language, f.line_count,
+                    (SELECT COUNT(*) FROM symbols s WHERE s.file_id = f.id)
+             FROM files f WHERE f.repo_id = ?1
+             ORDER BY f.line_count DESC, f.path LIMIT ?2",
+        )?;
+        let largest_files = stmt
+            .query_map(params![repo.id, top as i64], |row| {
+                Ok(FileSize {
+                    path: row.get(0)?,
+                    language: row.get(1)?,
+                    line_count: row.get(2)?,
+                    symbol_count: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT s.name, s.kind, f.path, s.start_line, s.end_line - s.start_line + 1 AS lines
+             FROM symbols s JOIN files f ON f.id = s.file_id
+             WHERE f.repo_id = ?1 AND s.source = 'local'
+             ORDER BY lines DESC, f.path, s.start_line LIMIT ?2",
+        )?;
+        let largest_symbols = stmt
+            .query_map(params![repo.id, top as i64], |row| {
+                Ok(SymbolSize {
+                    name: row.get(0)?,
+                    kind: row.get(1)?,
+                    file_path: row.get(2)?,
+                    start_line: row.get(3)?,
+                    lines: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(SizeReport {
+            repo: repo.name.clone(),
+            file_count,
+            line_count,
+            symbol_count,
+            avg_symbol_lines,
+            max_symbol_lines,
+            kinds,
+            largest_files,
+            largest_symbols,
+        })
+    }
+
+    /// Number of distinct dependents of each local symbol in `repo_id` that
+    /// has any. Self-edges don't count.
+    pub fn dependent_counts(&self, repo_id: i64) -> Result<std::collections::HashMap<i64, i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.target_id, COUNT(DISTINCT e.source_id)
+             FROM edges e
+             JOIN symbols s ON s.id = e.target_id
+             JOIN files f ON f.id = s.file_id
+             WHERE f.repo_id = ?1 AND s.source = 'local' AND e.source_id != e.target_id
+             GROUP BY e.target_id",
+        )?;
+        let rows = stmt.query_map(params![repo_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<std::result::Result<std::collections::HashMap<_, _>, _>>()?)
+    }
+
+    /// The `top` local symbols of `repo_id` with the most dependents.
+    pub fn get_hotspots(&self, repo_id: i64, top: usize) -> Result<Vec<Hotspot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.name, s.kind, f.path, s.start_line, COUNT(DISTINCT e.source_id) AS dependents
+             FROM edges e
+             JOIN symbols s ON s.id = e.target_id
+             JOIN files f ON f.id = s.file_id
+             WHERE f.repo_id = ?1 AND s.source = 'local' AND e.source_id != e.target_id
+             GROUP BY s.id
+             ORDER BY dependents DESC, f.path, s.start_line LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![repo_id, top as i64], |row| {
+            Ok(Hotspot {
                 name: row.get(0)?,
                 kind: row.get(1)?,
-                signature: row.get(2)?,
+                file_path: row.get(2)?,
                 start_line: row.get(3)?,
-                end_line: row.get(4)?,
+                dependents: row.get(4)?,
             })
         })?;
-        rows.collect::<std::result::Result<Vec<_>, _>>()
-            .map_err(Into::into)
+        Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// Symbols in a file, resolved via `resolve_file_path`. Empty when the
+    /// path doesn't match an indexed file.
+    pub fn get_file_symbols_summary(
+        &self,
+        file_path: &str,
+        repo_name: Option<&str>,
+    ) -> Result<Vec<SymbolSummary>> {
+        match self.resolve_file_path(file_path, repo_name)? {
+            Some(file) => self.get_skeleton(file.file_id, "standard"),
+            None => Ok(Vec::new()),
+        }
     }
 
     // -----------------------------------------------------------------------
@@ -1870,40 +6483,16 @@ impl Database {
             .map_err(Into::into)
     }
 
-    /// Find a file by path suffix match (LIKE %path), optionally scoped to a repo
-    /// by name, then return its skeleton (signatures only).
+    /// Resolve a file via `resolve_file_path`, optionally scoped to a repo by
+    /// name, then return its skeleton (signatures only).
     pub fn get_skeleton_by_path(
         &self,
         file_path: &str,
         repo_name: Option<&str>,
         detail: &str,
     ) -> Result<Vec<SymbolSummary>> {
-        let mut sql = String::from(
-            "SELECT f.id FROM files f
-             JOIN repositories r ON r.id = f.repo_id
-             WHERE f.path LIKE ?1",
-        );
-        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-        let pattern = format!("%{file_path}");
-        param_values.push(Box::new(pattern));
-
-        if let Some(rn) = repo_name {
-            sql.push_str(" AND r.name = ?2");
-            param_values.push(Box::new(rn.to_string()));
-        }
-
-        sql.push_str(" LIMIT 1");
-
-        let params_refs: Vec<&dyn rusqlite::types::ToSql> =
-            param_values.iter().map(|b| b.as_ref()).collect();
-
-        let file_id: Option<i64> = self
-            .conn
-            .query_row(&sql, params_refs.as_slice(), |row| row.get(0))
-            .optional()?;
-
-        match file_id {
-            Some(id) => self.get_skeleton(id, detail),
+        match self.resolve_file_path(file_path, repo_name)? {
+            Some(file) => self.get_skeleton(file.file_id, detail),
             None => Ok(Vec::new()),
         }
     }
@@ -1913,7 +6502,7 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT s.id, s.file_id, s.name, s.kind, s.signature, s.body,
                     s.body_hash, s.start_line, s.end_line, s.parent_id,
-                    s.qualified_name, s.source, s.manifest_repo,
+                    s.qualified_name, s.source, s.manifest_repo, s.visibility, s.modifiers, s.signature_types, s.attributes,
                     f.path, f.language
              FROM symbols s
              JOIN files f ON f.id = s.file_id
@@ -1922,23 +6511,9 @@ impl Database {
         )?;
         let rows = stmt.query_map(params![repo_id], |row| {
             Ok((
-                Symbol {
-                    id: row.get(0)?,
-                    file_id: row.get(1)?,
-                    name: row.get(2)?,
-                    kind: row.get(3)?,
-                    signature: row.get(4)?,
-                    body: row.get(5)?,
-                    body_hash: row.get(6)?,
-                    start_line: row.get(7)?,
-                    end_line: row.get(8)?,
-                    parent_id: row.get(9)?,
-                    qualified_name: row.get(10)?,
-                    source: row.get(11)?,
-                    manifest_repo: row.get(12)?,
-                },
-                row.get::<_, String>(13)?, // file path
-                row.get::<_, String>(14)?, // language
+                Symbol::from_row(row, 0)?,
+                row.get::<_, String>(17)?, // file path
+                row.get::<_, String>(18)?, // language
             ))
         })?;
         rows.collect::<std::result::Result<Vec<_>, _>>()
@@ -2025,6 +6600,33 @@ impl Database {
         Ok(id)
     }
 
+    /// Insert a symbol imported from a ctags or LSIF dump (no body, `source`
+    /// `imported`). Returns the new symbol ID.
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_imported_symbol(
+        &self,
+        file_id: i64,
+        name: &str,
+        qualified_name: &str,
+        kind: &str,
+        signature: &str,
+        start_line: i64,
+        end_line: i64,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO symbols (file_id, name, qualified_name, kind, signature,
+                                  body, body_hash, start_line, end_line, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, '', '', ?6, ?7, 'imported')",
+            params![file_id, name, qualified_name, kind, signature, start_line, end_line],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "INSERT INTO symbols_fts(rowid, name, signature, body) VALUES (?1, ?2, ?3, '')",
+            params![id, name, signature],
+        )?;
+        Ok(id)
+    }
+
     /// Check if a locally-indexed symbol with the given qualified_name exists.
     pub fn find_symbol_by_qualified_name_local(&self, qualified_name: &str) -> Result<bool> {
         let count: i64 = self.conn.query_row(
@@ -2082,3 +6684,67 @@ impl Database {
             .map_err(Into::into)
     }
 }
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Key a freshly opened SQLCipher connection and check the key by reading
+/// the schema; a wrong key only surfaces on the first read.
+#[cfg(feature = "encryption")]
+fn apply_key(conn: &Connection, path: &str, key: &str) -> Result<()> {
+    conn.pragma_update(None, "key", key)?;
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .with_context(|| format!("cannot decrypt {path}: wrong key?"))?;
+    Ok(())
+}
+
+/// Plain SQLite ignores `PRAGMA key`, so refuse rather than write plaintext.
+#[cfg(not(feature = "encryption"))]
+fn apply_key(_conn: &Connection, path: &str, _key: &str) -> Result<()> {
+    anyhow::bail!("cannot open {path}: encrypted databases need focal built with the `encryption` feature")
+}
+
+/// Map a memory row starting at column `offset`. Expects the columns in the
+/// order: id, content, category, source, session_id, created_at, stale,
+/// needs_review, structured, pinned, stale_reason, stale_at.
+fn memory_from_row(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<Memory> {
+    let structured: Option<String> = row.get(offset + 8)?;
+    Ok(Memory {
+        id: row.get(offset)?,
+        content: row.get(offset + 1)?,
+        category: row.get(offset + 2)?,
+        source: row.get(offset + 3)?,
+        session_id: row.get(offset + 4)?,
+        created_at: row.get(offset + 5)?,
+        stale: row.get::<_, i64>(offset + 6)? != 0,
+        needs_review: row.get::<_, i64>(offset + 7)? != 0,
+        structured: structured.and_then(|s| serde_json::from_str(&s).ok()),
+        pinned: row.get::<_, i64>(offset + 9)? != 0,
+        stale_reason: row.get(offset + 10)?,
+        stale_at: row.get(offset + 11)?,
+    })
+}
+
+/// Levenshtein distance over chars. Single-row DP — names are short, so the
+/// O(n*m) cost is negligible next to the query that loaded them.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev_diag = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b_chars.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            let next = (row[j] + 1).min(row[j + 1] + 1).min(prev_diag + cost);
+            prev_diag = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b_chars.len()]
+}
+
+/// Escape `%`, `_` and `\` in user input for a `LIKE ... ESCAPE '\'` pattern.
+fn like_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
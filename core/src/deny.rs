@@ -0,0 +1,44 @@
+use crate::owners::pattern_matches;
+
+// ---------------------------------------------------------------------------
+// Deny list
+// ---------------------------------------------------------------------------
+//
+// `[index] deny_paths` names files that must never reach a client, such as
+// `**/secrets/**` or `.env*`. The indexer skips them, and the MCP server
+// purges any that an earlier run indexed before a tool answers, so adding a
+// pattern takes effect without a full re-index.
+
+/// Repo-relative path patterns, matched gitignore-style like CODEOWNERS:
+/// a pattern without an interior `/` matches at any depth, a directory
+/// match covers everything beneath it, `*` stays within a segment and `**`
+/// spans segments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DenyList {
+    patterns: Vec<String>,
+}
+
+impl DenyList {
+    pub fn new(patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let patterns = patterns
+            .into_iter()
+            .map(Into::into)
+            .map(|p: String| p.trim().replace('\\', "/"))
+            .filter(|p| !p.is_empty())
+            .collect();
+        Self { patterns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// True when `rel_path` (repo-relative, forward slashes) matches a pattern.
+    pub fn is_denied(&self, rel_path: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern_matches(pattern, rel_path))
+    }
+}
@@ -0,0 +1,411 @@
+use serde::Serialize;
+
+// ---------------------------------------------------------------------------
+// Declared dependencies
+// ---------------------------------------------------------------------------
+//
+// Third-party packages declared in Cargo.toml, package.json, go.mod and
+// pyproject.toml. Import statements are matched against them after edge
+// resolution, which records the files that use each dependency.
+
+/// A dependency declared in one manifest.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Dependency {
+    pub name: String,
+    /// Name code imports it by: the dependency key of a renamed crate
+    /// (`json = { package = "serde_json" }` imports as `json`), the module
+    /// of a Python distribution that installs under another name (`PyYAML`
+    /// imports as `yaml`); otherwise `name`.
+    pub module: String,
+    /// Version requirement as written (`"0.8"`, `"^4.18.2"`, `v1.9.1`); for
+    /// path and git dependencies, `path:<dir>` or `git:<url>`.
+    pub version: String,
+    /// "prod", "dev", "build", "peer", "optional" or "indirect" (Go).
+    pub kind: String,
+    /// Repo-relative path of the manifest that declares it.
+    pub manifest: String,
+}
+
+/// Dependencies declared by the manifest at repo-relative `rel_path`. Empty
+/// for unrecognized or unparsable files.
+pub fn parse_dependencies(rel_path: &str, text: &str) -> Vec<Dependency> {
+    let file_name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    let mut deps = match file_name {
+        "Cargo.toml" => cargo_dependencies(text),
+        "package.json" => npm_dependencies(text),
+        "go.mod" => go_dependencies(text),
+        "pyproject.toml" => python_dependencies(text),
+        _ => Vec::new(),
+    };
+    for dep in &mut deps {
+        dep.manifest = rel_path.to_string();
+    }
+    deps
+}
+
+fn dependency(name: &str, version: String, kind: &str) -> Dependency {
+    Dependency {
+        name: name.to_string(),
+        module: name.to_string(),
+        version,
+        kind: kind.to_string(),
+        manifest: String::new(),
+    }
+}
+
+fn cargo_dependencies(text: &str) -> Vec<Dependency> {
+    let Ok(doc) = toml::from_str::<toml::Table>(text) else {
+        return Vec::new();
+    };
+    let mut tables: Vec<(&toml::Value, &str)> = Vec::new();
+    for (section, kind) in [("dependencies", "prod"), ("dev-dependencies", "dev"), ("build-dependencies", "build")] {
+        tables.extend(doc.get(section).map(|t| (t, kind)));
+        // [target.'cfg(unix)'.dependencies] and friends
+        if let Some(targets) = doc.get("target").and_then(|t| t.as_table()) {
+            tables.extend(targets.values().filter_map(|t| t.get(section)).map(|t| (t, kind)));
+        }
+    }
+    tables.extend(doc.get("workspace").and_then(|w| w.get("dependencies")).map(|t| (t, "prod")));
+
+    let mut deps = Vec::new();
+    for (table, kind) in tables {
+        let Some(table) = table.as_table() else { continue };
+        for (key, spec) in table {
+            // `serde_json = { package = "serde-json-alt", ... }` renames the
+            // crate; code still imports it by the key.
+            let name = spec.get("package").and_then(|p| p.as_str()).unwrap_or(key);
+            let version = match spec {
+                toml::Value::String(v) => v.clone(),
+                _ => cargo_version(spec),
+            };
+            deps.push(Dependency { module: key.clone(), ..dependency(name, version, kind) });
+        }
+    }
+    deps
+}
+
+fn cargo_version(spec: &toml::Value) -> String {
+    let field = |key: &str| spec.get(key).and_then(|v| v.as_str());
+    if let Some(v) = field("version") {
+        v.to_string()
+    } else if let Some(p) = field("path") {
+        format!("path:{p}")
+    } else if let Some(g) = field("git") {
+        format!("git:{g}")
+    } else if spec.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+        "workspace".to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn npm_dependencies(text: &str) -> Vec<Dependency> {
+    let Ok(doc) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Vec::new();
+    };
+    let sections = [
+        ("dependencies", "prod"),
+        ("devDependencies", "dev"),
+        ("peerDependencies", "peer"),
+        ("optionalDependencies", "optional"),
+    ];
+    let mut deps = Vec::new();
+    for (section, kind) in sections {
+        let Some(map) = doc.get(section).and_then(|s| s.as_object()) else { continue };
+        for (name, version) in map {
+            deps.push(dependency(name, version.as_str().unwrap_or_default().to_string(), kind));
+        }
+    }
+    deps
+}
+
+fn go_dependencies(text: &str) -> Vec<Dependency> {
+    let mut deps = Vec::new();
+    let mut in_block = false;
+    for line in text.lines() {
+        let line = line.trim();
+        let spec = if in_block {
+            if line.starts_with(')') {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if line == "require (" {
+            in_block = true;
+            continue;
+        } else if let Some(rest) = line.strip_prefix("require ") {
+            rest
+        } else {
+            continue;
+        };
+        let (spec, comment) = spec.split_once("//").unwrap_or((spec, ""));
+        let mut parts = spec.split_whitespace();
+        let (Some(name), Some(version)) = (parts.next(), parts.next()) else { continue };
+        let kind = if comment.trim() == "indirect" { "indirect" } else { "prod" };
+        deps.push(dependency(name, version.to_string(), kind));
+    }
+    deps
+}
+
+fn python_dependencies(text: &str) -> Vec<Dependency> {
+    let Ok(doc) = toml::from_str::<toml::Table>(text) else {
+        return Vec::new();
+    };
+    let mut deps = Vec::new();
+    let project = doc.get("project");
+    let requirements = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|r| r.as_str()).map(String::from).collect())
+            .unwrap_or_default()
+    };
+    for req in requirements(project.and_then(|p| p.get("dependencies"))) {
+        deps.extend(pep508(&req, "prod"));
+    }
+    if let Some(extras) = project.and_then(|p| p.get("optional-dependencies")).and_then(|o| o.as_table()) {
+        for reqs in extras.values() {
+            for req in requirements(Some(reqs)) {
+                deps.extend(pep508(&req, "optional"));
+            }
+        }
+    }
+
+    // Poetry: [tool.poetry.dependencies], [tool.poetry.group.<g>.dependencies]
+    let poetry = doc.get("tool").and_then(|t| t.get("poetry"));
+    let mut tables: Vec<(&toml::Value, &str)> = Vec::new();
+    tables.extend(poetry.and_then(|p| p.get("dependencies")).map(|t| (t, "prod")));
+    tables.extend(poetry.and_then(|p| p.get("dev-dependencies")).map(|t| (t, "dev")));
+    if let Some(groups) = poetry.and_then(|p| p.get("group")).and_then(|g| g.as_table()) {
+        tables.extend(groups.values().filter_map(|g| g.get("dependencies")).map(|t| (t, "dev")));
+    }
+    for (table, kind) in tables {
+        let Some(table) = table.as_table() else { continue };
+        for (name, spec) in table.iter().filter(|(name, _)| name.as_str() != "python") {
+            let version = match spec {
+                toml::Value::String(v) => v.clone(),
+                _ => spec.get("version").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            };
+            deps.push(python_dependency(name, version, kind));
+        }
+    }
+    deps
+}
+
+/// Split a PEP 508 requirement (`requests[socks]>=2.31; python_version<"4"`)
+/// into name and version specifier.
+fn pep508(req: &str, kind: &str) -> Option<Dependency> {
+    let req = req.split(';').next()?.trim();
+    let end = req
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(req.len());
+    let name = &req[..end];
+    if name.is_empty() {
+        return None;
+    }
+    let rest = req[end..].trim_start();
+    let version = match rest.strip_prefix('[') {
+        Some(r) => r.split_once(']').map_or("", |(_, v)| v),
+        None => rest,
+    };
+    Some(python_dependency(name, version.trim().to_string(), kind))
+}
+
+/// Distributions whose top-level module has an unrelated name, compared
+/// case-insensitively with `-`/`_` folded. Others import by their own name.
+const PYTHON_MODULES: &[(&str, &str)] = &[
+    ("beautifulsoup4", "bs4"),
+    ("opencv_python", "cv2"),
+    ("opencv_python_headless", "cv2"),
+    ("pillow", "PIL"),
+    ("psycopg2_binary", "psycopg2"),
+    ("pycryptodome", "Crypto"),
+    ("pyjwt", "jwt"),
+    ("python_dateutil", "dateutil"),
+    ("python_dotenv", "dotenv"),
+    ("pyyaml", "yaml"),
+    ("scikit_image", "skimage"),
+    ("scikit_learn", "sklearn"),
+];
+
+fn python_dependency(name: &str, version: String, kind: &str) -> Dependency {
+    let key = name.replace('-', "_").to_ascii_lowercase();
+    let module = PYTHON_MODULES.iter().find(|(dist, _)| *dist == key).map_or(name, |(_, module)| module);
+    Dependency { module: module.to_string(), ..dependency(name, version, kind) }
+}
+
+// ---------------------------------------------------------------------------
+// Import matching
+// ---------------------------------------------------------------------------
+
+/// External module roots named by an import reference (the statement text a
+/// grammar stores as an `imports` reference). Relative and crate-local
+/// imports yield nothing; Go yields the full import path.
+pub fn imported_modules(language: &str, import: &str) -> Vec<String> {
+    match language {
+        "rs" => {
+            let path = import.trim().trim_start_matches("pub ").trim_start_matches("use ").trim_start_matches("::");
+            let root = path.split(|c: char| c == ':' || c == ';' || c == '{' || c.is_whitespace()).next();
+            match root {
+                Some("crate" | "self" | "super" | "std" | "core" | "alloc" | "") | None => Vec::new(),
+                Some(root) => vec![root.to_string()],
+            }
+        }
+        "py" => {
+            let import = import.trim();
+            let modules: Vec<&str> = if let Some(rest) = import.strip_prefix("from ") {
+                rest.split_whitespace().next().into_iter().collect()
+            } else if let Some(rest) = import.strip_prefix("import ") {
+                rest.split(',').filter_map(|m| m.split_whitespace().next()).collect()
+            } else {
+                Vec::new()
+            };
+            modules
+                .into_iter()
+                .filter(|m| !m.starts_with('.'))
+                .filter_map(|m| m.split('.').next())
+                .map(String::from)
+                .collect()
+        }
+        "go" => quoted(import).into_iter().collect(),
+        _ => {
+            let Some(spec) = quoted(import) else { return Vec::new() };
+            if spec.starts_with('.') || spec.starts_with('/') {
+                return Vec::new();
+            }
+            let segments = if spec.starts_with('@') { 2 } else { 1 };
+            vec![spec.split('/').take(segments).collect::<Vec<_>>().join("/")]
+        }
+    }
+}
+
+/// The last quoted string in `text`, without its quotes.
+pub(crate) fn quoted(text: &str) -> Option<String> {
+    let end = text.rfind(['"', '\''])?;
+    let quote = text[end..].chars().next()?;
+    let start = text[..end].rfind(quote)?;
+    Some(text[start + 1..end].to_string())
+}
+
+/// Whether `module` (from `imported_modules`) refers to a dependency
+/// imported as `name` (its `Dependency::module`). Rust and Python compare
+/// with `-` and `_` folded (and case folded for Python); Go matches the
+/// module path or any package inside it.
+pub fn module_matches(language: &str, module: &str, name: &str) -> bool {
+    let fold = |s: &str| s.replace('-', "_");
+    match language {
+        "rs" => fold(module) == fold(name),
+        "py" => fold(module).eq_ignore_ascii_case(&fold(name)),
+        "go" => module == name || module.starts_with(&format!("{name}/")),
+        _ => module == name,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// External symbols
+// ---------------------------------------------------------------------------
+//
+// Calls into a dependency have no local target, so edge resolution used to
+// drop them. A name a file imports explicitly from a declared dependency
+// (`use axum::routing::get`, `from requests import get`, `import { useState }
+// from "react"`) resolves instead to a stub symbol of kind `external`, keyed
+// by package and name and kept in the `external` virtual repository. Callee
+// names are extracted without their qualifier, so `requests.get(...)` and
+// Go's `chi.NewRouter()` cannot be attributed and stay unresolved.
+
+/// Name of the virtual repository that holds external stubs.
+pub const EXTERNAL_REPO: &str = "external";
+/// Root path of the virtual repository that holds external stubs.
+pub const EXTERNAL_ROOT: &str = "external://";
+
+/// Names an import statement binds, as (imported name, local name) pairs.
+/// Glob, namespace and whole-module imports bind nothing callable by bare
+/// name and are skipped.
+pub fn imported_names(language: &str, import: &str) -> Vec<(String, String)> {
+    let mut names = Vec::new();
+    match language {
+        "rs" => {
+            let tree = import.trim().trim_start_matches("pub ").trim_start_matches("use ");
+            // `use serde_json;` binds the crate, not a callable name.
+            if tree.contains("::") {
+                rust_use_leaves(tree.trim_end_matches(';').trim(), &mut names);
+            }
+        }
+        "py" => {
+            if let Some((_, list)) = import.trim().strip_prefix("from ").and_then(|r| r.split_once(" import ")) {
+                let list = list.trim().trim_start_matches('(').trim_end_matches(')');
+                names.extend(list.split(',').filter_map(aliased));
+            }
+        }
+        "ts" | "tsx" => {
+            let Some(clause) = import.trim().strip_prefix("import ") else { return names };
+            let Some((clause, _)) = clause.rsplit_once(" from ") else { return names };
+            let clause = clause.trim().trim_start_matches("type ").trim();
+            let (default, named) = match clause.split_once('{') {
+                Some((default, named)) => (default, named.split('}').next().unwrap_or_default()),
+                None => (clause, ""),
+            };
+            let default = default.trim().trim_end_matches(',').trim();
+            if is_identifier(default) {
+                names.push((default.to_string(), default.to_string()));
+            }
+            names.extend(named.split(',').map(|n| n.trim().trim_start_matches("type ")).filter_map(aliased));
+        }
+        _ => {}
+    }
+    names
+}
+
+/// Leaves of a Rust use tree (`axum::{routing::get, Router as R}`).
+fn rust_use_leaves(tree: &str, out: &mut Vec<(String, String)>) {
+    if let Some(open) = tree.find('{') {
+        let inner = tree[open + 1..].trim_end().strip_suffix('}').unwrap_or(&tree[open + 1..]);
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in inner.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    rust_use_leaves(inner[start..i].trim(), out);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        rust_use_leaves(inner[start..].trim(), out);
+        return;
+    }
+    let leaf = tree.rsplit("::").next().unwrap_or(tree);
+    if let Some((name, local)) = aliased(leaf) {
+        if name != "self" && name != "*" {
+            out.push((name, local));
+        }
+    }
+}
+
+/// Split `name as alias` (or plain `name`) into (name, local name).
+pub(crate) fn aliased(item: &str) -> Option<(String, String)> {
+    let mut parts = item.split_whitespace();
+    let name = parts.next()?;
+    let local = match (parts.next(), parts.next()) {
+        (Some("as"), Some(alias)) => alias,
+        _ => name,
+    };
+    if name == "*" {
+        return None;
+    }
+    Some((name.to_string(), local.to_string()))
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Qualified name of the stub for `name` in `package`, written the way the
+/// language spells it (`axum::get`, `requests.get`).
+pub fn external_qualified_name(language: &str, package: &str, name: &str) -> String {
+    match language {
+        "rs" => format!("{package}::{name}"),
+        _ => format!("{package}.{name}"),
+    }
+}
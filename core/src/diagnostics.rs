@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::{RepoConfig, REPO_CONFIG_FILE};
+use crate::db::Database;
+use crate::dependencies::EXTERNAL_ROOT;
+use crate::grammar::GrammarRegistry;
+use crate::indexer::Indexer;
+
+/// Maximum example entries attached to a single diagnostic.
+const MAX_EXAMPLES: usize = 10;
+
+// ---------------------------------------------------------------------------
+// Report types
+// ---------------------------------------------------------------------------
+
+/// One concrete problem found in the index, with a command that fixes it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// Stable identifier: `missing_repo_root`, `unindexed_files`,
+    /// `duplicate_repos`, `invalid_repo_config`, `fts_mismatch`,
+    /// `orphaned_edges`, or `empty_bodies`.
+    pub check: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    #[serde(skip)]
+    pub repo_id: Option<i64>,
+    /// Number of affected rows / files.
+    pub count: i64,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+    /// Suggested command to resolve the problem.
+    pub fix: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticReport {
+    pub ok: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+// ---------------------------------------------------------------------------
+// Checks
+// ---------------------------------------------------------------------------
+
+/// Run every index self-check. Filesystem checks are skipped for manifest
+/// repositories (`manifest://…`) and external stubs (`external://`), which
+/// have no checkout on disk.
+pub fn diagnose_index(db: &Database, registry: &GrammarRegistry) -> Result<DiagnosticReport> {
+    let mut diagnostics = Vec::new();
+
+    for repo in db.list_repositories()? {
+        if repo.root_path.starts_with("manifest://") || repo.root_path == EXTERNAL_ROOT {
+            continue;
+        }
+        let root = Path::new(&repo.root_path);
+        if !root.is_dir() {
+            diagnostics.push(Diagnostic {
+                check: "missing_repo_root",
+                repo: Some(repo.name.clone()),
+                repo_id: Some(repo.id),
+                count: 1,
+                detail: format!("repository root {} no longer exists", repo.root_path),
+                examples: Vec::new(),
+                fix: "focal doctor --fix (drops the repository and its symbols)".to_string(),
+            });
+            continue;
+        }
+
+        if let Err(e) = RepoConfig::try_load(root) {
+            diagnostics.push(Diagnostic {
+                check: "invalid_repo_config",
+                repo: Some(repo.name.clone()),
+                repo_id: Some(repo.id),
+                count: 1,
+                detail: format!("{REPO_CONFIG_FILE} is ignored: {e}"),
+                examples: Vec::new(),
+                fix: format!("edit {}", root.join(REPO_CONFIG_FILE).display()),
+            });
+        }
+
+        let missing = Indexer::new(db, registry).find_unindexed_files(root, repo.id)?;
+        if !missing.is_empty() {
+            diagnostics.push(Diagnostic {
+                check: "unindexed_files",
+                repo: Some(repo.name.clone()),
+                repo_id: Some(repo.id),
+                count: missing.len() as i64,
+                detail: format!(
+                    "{} supported file(s) on disk are not in the index",
+                    missing.len()
+                ),
+                examples: missing.into_iter().take(MAX_EXAMPLES).collect(),
+                fix: format!("focal serve {} (startup indexing picks up new files)", repo.root_path),
+            });
+        }
+    }
+
+    for (root, repos) in db.duplicate_repositories()? {
+        diagnostics.push(Diagnostic {
+            check: "duplicate_repos",
+            repo: Some(repos[0].name.clone()),
+            repo_id: Some(repos[0].id),
+            count: repos.len() as i64,
+            detail: format!("{} repository rows point at {root}", repos.len()),
+            examples: repos.iter().map(|r| r.root_path.clone()).collect(),
+            fix: "focal doctor --fix (merges them into one repository)".to_string(),
+        });
+    }
+
+    let (symbols, fts_docs) = db.fts_symbol_counts()?;
+    if symbols != fts_docs || !db.fts_content_ok() {
+        let detail = if symbols != fts_docs {
+            format!("symbols has {symbols} rows but symbols_fts indexes {fts_docs}")
+        } else {
+            "symbols_fts or memories_fts no longer matches the text of its content table".to_string()
+        };
+        diagnostics.push(Diagnostic {
+            check: "fts_mismatch",
+            repo: None,
+            repo_id: None,
+            count: (symbols - fts_docs).abs().max(1),
+            detail,
+            examples: Vec::new(),
+            fix: "focal doctor --fix (repairs the FTS indexes)".to_string(),
+        });
+    }
+
+    let orphaned = db.count_orphaned_edges()?;
+    if orphaned > 0 {
+        diagnostics.push(Diagnostic {
+            check: "orphaned_edges",
+            repo: None,
+            repo_id: None,
+            count: orphaned,
+            detail: format!("{orphaned} edge(s) reference symbols that no longer exist"),
+            examples: Vec::new(),
+            fix: "focal doctor --fix (deletes the orphaned edges)".to_string(),
+        });
+    }
+
+    let (empty, examples) = db.symbols_with_empty_body(MAX_EXAMPLES as i64)?;
+    if empty > 0 {
+        diagnostics.push(Diagnostic {
+            check: "empty_bodies",
+            repo: None,
+            repo_id: None,
+            count: empty,
+            detail: format!("{empty} locally indexed symbol(s) have a zero-length body"),
+            examples,
+            fix: "focal serve <repo root> to re-parse; if bodies stay empty, the grammar \
+                  is not capturing bodies for that symbol kind"
+                .to_string(),
+        });
+    }
+
+    Ok(DiagnosticReport {
+        ok: diagnostics.is_empty(),
+        diagnostics,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Repairs
+// ---------------------------------------------------------------------------
+
+/// Apply the repairs that need only the database: drop repositories whose
+/// root is gone, merge duplicate repositories, repair FTS, and delete
+/// orphaned edges. Unindexed files and empty bodies need a re-index and are
+/// left alone. Returns a log of actions.
+pub fn apply_fixes(db: &Database, report: &DiagnosticReport) -> Result<Vec<String>> {
+    let mut actions = Vec::new();
+    for diag in &report.diagnostics {
+        match diag.check {
+            "missing_repo_root" => {
+                let Some(repo_id) = diag.repo_id else { continue };
+                if db.delete_repository(repo_id)? {
+                    let name = diag.repo.as_deref().unwrap_or_default();
+                    actions.push(format!("dropped repository '{name}'"));
+                }
+            }
+            "duplicate_repos" => {
+                let n = db.merge_duplicate_repositories()?;
+                if n > 0 {
+                    actions.push(format!("merged {n} duplicate repository row(s)"));
+                }
+            }
+            "fts_mismatch" => {
+                for repair in db.check_fts_consistency()? {
+                    if repair.rebuilt {
+                        actions.push(format!("rebuilt {}", repair.table));
+                    } else if repair.inserted + repair.removed > 0 {
+                        actions.push(format!(
+                            "{}: indexed {} missing row(s), dropped {} orphan(s)",
+                            repair.table, repair.inserted, repair.removed
+                        ));
+                    }
+                }
+            }
+            "orphaned_edges" => {
+                let n = db.delete_orphaned_edges()?;
+                actions.push(format!("deleted {n} orphaned edge(s)"));
+            }
+            _ => {}
+        }
+    }
+    Ok(actions)
+}
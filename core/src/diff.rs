@@ -0,0 +1,145 @@
+// ---------------------------------------------------------------------------
+// Line diff
+// ---------------------------------------------------------------------------
+
+/// Above this many cells in the LCS table the diff degrades to "replace
+/// everything between the common prefix and suffix" rather than allocating.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Result of `unified_diff`.
+#[derive(Debug, Clone, Default)]
+pub struct LineDiff {
+    /// Unified diff text (`---`/`+++` headers and `@@` hunks); empty when the
+    /// inputs are identical.
+    pub text: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Line-based unified diff of `old` → `new` with `context` unchanged lines
+/// around each change, in the format produced by `diff -u`.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str, context: usize) -> LineDiff {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&a, &b);
+
+    let added = ops.iter().filter(|(op, _)| *op == Op::Insert).count();
+    let removed = ops.iter().filter(|(op, _)| *op == Op::Delete).count();
+    if added == 0 && removed == 0 {
+        return LineDiff::default();
+    }
+
+    let mut text = format!("--- {old_label}\n+++ {new_label}\n");
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != Op::Equal)
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut i = 0;
+    while i < changes.len() {
+        // Extend the hunk while the next change is within 2×context lines.
+        let mut last = changes[i];
+        let mut j = i + 1;
+        while j < changes.len() && changes[j] - last <= 2 * context + 1 {
+            last = changes[j];
+            j += 1;
+        }
+        let start = changes[i].saturating_sub(context);
+        let end = (last + context + 1).min(ops.len());
+
+        let old_before = ops[..start].iter().filter(|(op, _)| *op != Op::Insert).count();
+        let new_before = ops[..start].iter().filter(|(op, _)| *op != Op::Delete).count();
+        let old_len = ops[start..end].iter().filter(|(op, _)| *op != Op::Insert).count();
+        let new_len = ops[start..end].iter().filter(|(op, _)| *op != Op::Delete).count();
+        text.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_before, old_len),
+            hunk_range(new_before, new_len)
+        ));
+        for (op, line) in &ops[start..end] {
+            let prefix = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            text.push(prefix);
+            text.push_str(line);
+            text.push('\n');
+        }
+        i = j;
+    }
+
+    LineDiff { text, added, removed }
+}
+
+/// `start,len` with diff's conventions: 1-based start, and for an empty range
+/// the line after which it sits.
+fn hunk_range(before: usize, len: usize) -> String {
+    match len {
+        0 => format!("{before},0"),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{len}", before + 1),
+    }
+}
+
+/// Edit script turning `a` into `b`: common prefix and suffix are trimmed,
+/// the middle is aligned by longest common subsequence.
+fn diff_ops<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (am, bm) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops: Vec<(Op, &str)> = a[..prefix].iter().map(|l| (Op::Equal, *l)).collect();
+
+    let (n, m) = (am.len(), bm.len());
+    if n * m > MAX_LCS_CELLS {
+        ops.extend(am.iter().map(|l| (Op::Delete, *l)));
+        ops.extend(bm.iter().map(|l| (Op::Insert, *l)));
+    } else {
+        // lcs[i][j] = LCS length of am[i..] and bm[j..]
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        let idx = |i: usize, j: usize| i * (m + 1) + j;
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[idx(i, j)] = if am[i] == bm[j] {
+                    lcs[idx(i + 1, j + 1)] + 1
+                } else {
+                    lcs[idx(i + 1, j)].max(lcs[idx(i, j + 1)])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if am[i] == bm[j] {
+                ops.push((Op::Equal, am[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[idx(i + 1, j)] >= lcs[idx(i, j + 1)] {
+                ops.push((Op::Delete, am[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Insert, bm[j]));
+                j += 1;
+            }
+        }
+        ops.extend(am[i..].iter().map(|l| (Op::Delete, *l)));
+        ops.extend(bm[j..].iter().map(|l| (Op::Insert, *l)));
+    }
+
+    ops.extend(a[a.len() - suffix..].iter().map(|l| (Op::Equal, *l)));
+    ops
+}
@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+
+// ---------------------------------------------------------------------------
+// Source decoding
+// ---------------------------------------------------------------------------
+//
+// Tree-sitter parses bytes, but symbol bodies, signatures and FTS text are
+// stored as UTF-8, and extractors drop text that is not valid UTF-8. Files
+// are therefore transcoded before parsing: a BOM decides the encoding when
+// present, valid UTF-8 passes through untouched, and anything else (Latin-1
+// comments, Shift_JIS string literals) goes through chardetng. Content hashes
+// are still computed over the raw bytes.
+
+/// Encoding recorded for a file that needed no transcoding.
+pub const DEFAULT_ENCODING: &str = "UTF-8";
+
+/// A source file as UTF-8 bytes, plus the encoding it was stored in.
+#[derive(Debug)]
+pub struct DecodedSource<'a> {
+    pub text: Cow<'a, [u8]>,
+    /// WHATWG name of the original encoding (`UTF-8`, `windows-1252`,
+    /// `UTF-16LE`, ...), with ` BOM` appended when a byte-order mark was
+    /// stripped.
+    pub encoding: String,
+}
+
+/// Transcode `bytes` to UTF-8, detecting the encoding from a BOM or, failing
+/// that, from the content. Malformed sequences become U+FFFD.
+pub fn decode_source(bytes: &[u8]) -> DecodedSource<'_> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let text = decode_with(encoding, &bytes[bom_len..]);
+        return DecodedSource { text, encoding: format!("{} BOM", encoding.name()) };
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return DecodedSource { text: Cow::Borrowed(bytes), encoding: DEFAULT_ENCODING.to_string() };
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    DecodedSource { text: decode_with(encoding, bytes), encoding: encoding.name().to_string() }
+}
+
+fn decode_with<'a>(encoding: &'static Encoding, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+    if encoding == UTF_8 {
+        return match String::from_utf8_lossy(bytes) {
+            Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+            Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+        };
+    }
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    Cow::Owned(text.into_owned().into_bytes())
+}
@@ -0,0 +1,124 @@
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+use crate::db::Database;
+
+// ---------------------------------------------------------------------------
+// At-rest encryption
+// ---------------------------------------------------------------------------
+//
+// With the `encryption` feature, rusqlite is built against SQLCipher and the
+// index can be stored encrypted. Encryption is chosen once, by `--encrypt`
+// when the database file is first created; later opens recognise an
+// encrypted file by its header and ask for the key without the flag.
+
+/// Environment variable holding the database key. Checked before the keychain.
+pub const KEY_ENV: &str = "FOCAL_DB_KEY";
+
+/// Keychain entry holding the key: service `focal`, account `index`.
+pub const KEYCHAIN_SERVICE: &str = "focal";
+pub const KEYCHAIN_ACCOUNT: &str = "index";
+
+/// Every plaintext SQLite file starts with this header; SQLCipher files
+/// start with random salt instead.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Whether this build can open encrypted databases.
+pub fn supported() -> bool {
+    cfg!(feature = "encryption")
+}
+
+/// True when `path` exists, is non-empty, and isn't a plaintext SQLite file.
+pub fn is_encrypted(path: &Path) -> bool {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut header = [0u8; 16];
+    match file.read(&mut header) {
+        Ok(0) | Err(_) => false,
+        Ok(n) => header[..n] != SQLITE_HEADER[..n],
+    }
+}
+
+/// The database key from `FOCAL_DB_KEY`, else from the OS keychain.
+pub fn load_key() -> Result<String> {
+    if let Some(key) = std::env::var(KEY_ENV).ok().filter(|k| !k.is_empty()) {
+        return Ok(key);
+    }
+    if let Some(key) = keychain_key() {
+        return Ok(key);
+    }
+    bail!(
+        "no database key: set {KEY_ENV} or store one in the OS keychain \
+         (service '{KEYCHAIN_SERVICE}', account '{KEYCHAIN_ACCOUNT}')"
+    )
+}
+
+/// The key for the index at `path`: loaded when the file is encrypted, None
+/// for a plaintext file.
+pub fn key_for(path: &Path) -> Result<Option<String>> {
+    if is_encrypted(path) {
+        return load_key().map(Some);
+    }
+    Ok(None)
+}
+
+/// Read the key with the platform's keychain CLI: `security` on macOS,
+/// `secret-tool` (libsecret) elsewhere. Windows has no stock CLI for this,
+/// so keys come from the environment there.
+fn keychain_key() -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security")
+            .args(["find-generic-password", "-s", KEYCHAIN_SERVICE, "-a", KEYCHAIN_ACCOUNT, "-w"])
+            .output()
+    } else if cfg!(windows) {
+        return None;
+    } else {
+        Command::new("secret-tool")
+            .args(["lookup", "service", KEYCHAIN_SERVICE, "account", KEYCHAIN_ACCOUNT])
+            .output()
+    }
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let key = String::from_utf8(output.stdout).ok()?.trim_end_matches(['\r', '\n']).to_string();
+    (!key.is_empty()).then_some(key)
+}
+
+/// Open (or create) the index at `path`. `encrypt` only matters when the file
+/// doesn't exist yet; an existing file keeps the format it was created with.
+pub fn open(path: &Path, encrypt: bool) -> Result<Database> {
+    let path_str = path.to_string_lossy();
+    let exists = path.metadata().is_ok_and(|m| m.len() > 0);
+    if exists && is_encrypted(path) {
+        return Database::open_encrypted(&path_str, &load_key()?);
+    }
+    if exists && encrypt {
+        bail!(
+            "{} already exists unencrypted; --encrypt only applies when the database is \
+             first created. Move it aside and re-index to encrypt it.",
+            path.display()
+        );
+    }
+    if encrypt {
+        if !supported() {
+            bail!("--encrypt needs focal built with the `encryption` feature");
+        }
+        let key = load_key().context("--encrypt needs a key")?;
+        return Database::open_encrypted(&path_str, &key);
+    }
+    Database::open(&path_str)
+}
+
+/// Open an existing index read-only, encrypted or not.
+pub fn open_read_only(path: &Path) -> Result<Database> {
+    let path_str = path.to_string_lossy();
+    if is_encrypted(path) {
+        return Database::open_read_only_encrypted(&path_str, &load_key()?);
+    }
+    Database::open_read_only(&path_str)
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+// ---------------------------------------------------------------------------
+// Environment-variable reads
+// ---------------------------------------------------------------------------
+
+/// A literal environment-variable name read at `line` (1-based).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvUsage {
+    pub name: String,
+    pub line: usize,
+}
+
+/// Call prefixes whose first argument is a quoted variable name, per language.
+fn call_patterns(language: &str) -> &'static [&'static str] {
+    match language {
+        "rs" => &["env::var(", "env::var_os(", "env!(", "option_env!("],
+        "go" => &["os.Getenv(", "os.LookupEnv(", "syscall.Getenv("],
+        "ts" | "tsx" => &["process.env["],
+        "py" => &["getenv(", "environ[", "environ.get(", "environ.setdefault("],
+        _ => &[],
+    }
+}
+
+/// Scan source text for environment-variable reads with a literal name:
+/// `std::env::var("X")` / `env!("X")`, `os.Getenv("X")`, `process.env.X` /
+/// `process.env["X"]`, `os.getenv("X")` / `os.environ["X"]`. Names built at
+/// runtime are not recognized.
+pub fn scan_env_usages(source: &str, language: &str) -> Vec<EnvUsage> {
+    let patterns = call_patterns(language);
+    let dotted = matches!(language, "ts" | "tsx");
+    let mut usages = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        for pattern in patterns {
+            for start in pattern_starts(line, pattern) {
+                if let Some(name) = quoted_name(&line[start + pattern.len()..]) {
+                    usages.push(EnvUsage { name, line: i + 1 });
+                }
+            }
+        }
+        if dotted {
+            for start in pattern_starts(line, "process.env.") {
+                let name = leading_ident(&line[start + "process.env.".len()..]);
+                if !name.is_empty() {
+                    usages.push(EnvUsage { name: name.to_string(), line: i + 1 });
+                }
+            }
+        }
+    }
+    usages
+}
+
+/// Byte offsets of `pattern` in `line` that are not the tail of a longer
+/// identifier (`dotenv::var(` does not match `env::var(`).
+fn pattern_starts(line: &str, pattern: &str) -> Vec<usize> {
+    line.match_indices(pattern)
+        .map(|(i, _)| i)
+        .filter(|&i| {
+            !line[..i]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        })
+        .collect()
+}
+
+/// The identifier inside a leading quoted string literal, if any.
+fn quoted_name(rest: &str) -> Option<String> {
+    let rest = rest.trim_start();
+    let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    let inner = &rest[1..];
+    let name = leading_ident(inner);
+    (!name.is_empty() && inner[name.len()..].starts_with(quote)).then(|| name.to_string())
+}
+
+fn leading_ident(s: &str) -> &str {
+    let end = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    &s[..end]
+}
@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::api::{doc_comment, python_docstring};
+use crate::db::{Database, Edge, Memory, Symbol, TestLinkEntry};
+use crate::encoding::decode_source;
+use crate::git::CommitEntry;
+
+// ---------------------------------------------------------------------------
+// Symbol explanation
+// ---------------------------------------------------------------------------
+//
+// Everything an agent usually fetches with five separate calls — definition,
+// docs, neighbours, memories, tests, history — folded into one report that
+// fits a token budget. The header is always included; the body comes next,
+// then the list sections in order until the budget runs out.
+
+/// Heuristic complexity of a symbol's body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Complexity {
+    pub lines: i64,
+    /// Decision points: conditionals, loops, match arms/cases, `&&`/`||`.
+    pub branches: usize,
+    /// McCabe estimate, `branches + 1`.
+    pub cyclomatic: usize,
+    /// Deepest block nesting inside the body.
+    pub max_nesting: usize,
+}
+
+/// A direct caller or callee.
+#[derive(Debug, Clone, Serialize)]
+pub struct Neighbor {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub line: i64,
+    /// Edge kind (`calls`, `type_ref`, `implements`, ...).
+    pub edge: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    pub name: String,
+    pub qualified_name: String,
+    pub kind: String,
+    pub repo: String,
+    pub file_path: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub signature: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub visibility: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Why the body was left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_note: Option<String>,
+    pub complexity: Complexity,
+    pub callers: Vec<Neighbor>,
+    pub callees: Vec<Neighbor>,
+    pub memories: Vec<Memory>,
+    pub tests: Vec<TestLinkEntry>,
+    pub history: Vec<CommitEntry>,
+    /// Entries dropped per section to stay within the budget.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub omitted: BTreeMap<&'static str, usize>,
+    pub total_tokens: usize,
+}
+
+/// Build the report for `sym`. `history` is passed in so callers can run git
+/// without holding the database; `body_sent` replaces the body with a note
+/// when the agent already has it.
+pub fn explain_symbol(
+    db: &Database,
+    sym: &Symbol,
+    history: Vec<CommitEntry>,
+    body_sent: bool,
+    max_tokens: usize,
+) -> Result<Explanation> {
+    let file = db
+        .get_file_by_id(sym.file_id)?
+        .with_context(|| format!("file for symbol '{}' not found", sym.name))?;
+    let repo = db
+        .list_repositories()?
+        .into_iter()
+        .find(|r| r.id == file.repo_id)
+        .with_context(|| format!("repository for '{}' not found", file.path))?;
+
+    let doc = match file.language.as_str() {
+        "py" => python_docstring(&sym.body),
+        lang => {
+            let source = std::fs::read(Path::new(&repo.root_path).join(&file.path))
+                .map(|raw| String::from_utf8_lossy(&decode_source(&raw).text).into_owned())
+                .unwrap_or_default();
+            let lines: Vec<&str> = source.lines().collect();
+            doc_comment(&lines, sym.start_line, lang)
+        }
+    };
+
+    let mut explanation = Explanation {
+        name: sym.name.clone(),
+        qualified_name: sym.qualified_name.clone(),
+        kind: sym.kind.clone(),
+        repo: repo.name,
+        file_path: file.path,
+        start_line: sym.start_line,
+        end_line: sym.end_line,
+        signature: sym.signature.clone(),
+        visibility: sym.visibility.clone(),
+        attributes: sym.attributes.lines().map(String::from).collect(),
+        doc,
+        body: None,
+        body_note: None,
+        complexity: complexity(sym, &file.language),
+        callers: Vec::new(),
+        callees: Vec::new(),
+        memories: Vec::new(),
+        tests: Vec::new(),
+        history: Vec::new(),
+        omitted: BTreeMap::new(),
+        total_tokens: 0,
+    };
+    let mut used = estimate_tokens(&serde_json::to_string(&explanation)?);
+
+    let body_tokens = estimate_tokens(&sym.body);
+    if body_sent {
+        explanation.body_note = Some("body sent earlier in this session".to_string());
+    } else if used + body_tokens > max_tokens {
+        explanation.body_note = Some(format!("body omitted: {body_tokens} tokens exceeds the budget"));
+    } else {
+        used += body_tokens;
+        explanation.body = Some(sym.body.clone());
+    }
+
+    let memories = db
+        .get_memories_for_symbols_batch(&[sym.id], false)?
+        .remove(&sym.id)
+        .unwrap_or_default();
+    let callers = neighbors(db, db.get_dependents(sym.id)?)?;
+    let callees = neighbors(db, db.get_dependencies(sym.id)?)?;
+    let tests = db.tests_for_symbol_id(sym.id)?;
+
+    let mut sections = SectionBudget::new(used, max_tokens);
+    explanation.memories = sections.fit("memories", memories)?;
+    explanation.callers = sections.fit("callers", callers)?;
+    explanation.callees = sections.fit("callees", callees)?;
+    explanation.tests = sections.fit("tests", tests)?;
+    explanation.history = sections.fit("history", history)?;
+    explanation.omitted = sections.omitted;
+    explanation.total_tokens = sections.used;
+    Ok(explanation)
+}
+
+/// Remaining token budget shared by the list sections of a report.
+pub(crate) struct SectionBudget {
+    pub(crate) used: usize,
+    max_tokens: usize,
+    pub(crate) omitted: BTreeMap<&'static str, usize>,
+}
+
+impl SectionBudget {
+    pub(crate) fn new(used: usize, max_tokens: usize) -> Self {
+        Self { used, max_tokens, omitted: BTreeMap::new() }
+    }
+
+    /// Keep the leading `items` that fit; record how many were dropped.
+    pub(crate) fn fit<T: Serialize>(&mut self, section: &'static str, mut items: Vec<T>) -> Result<Vec<T>> {
+        for (i, item) in items.iter().enumerate() {
+            let cost = estimate_tokens(&serde_json::to_string(item)?);
+            if self.used + cost > self.max_tokens {
+                self.omitted.insert(section, items.len() - i);
+                items.truncate(i);
+                break;
+            }
+            self.used += cost;
+        }
+        Ok(items)
+    }
+}
+
+fn neighbors(db: &Database, edges: Vec<(Edge, Symbol)>) -> Result<Vec<Neighbor>> {
+    edges
+        .into_iter()
+        .map(|(edge, sym)| {
+            Ok(Neighbor {
+                file_path: db.get_file_path_for_symbol(sym.id)?,
+                name: sym.name,
+                kind: sym.kind,
+                line: sym.start_line,
+                edge: edge.kind,
+            })
+        })
+        .collect()
+}
+
+/// Estimate `sym`'s complexity from its body text. Strings and comments are
+/// skipped; nesting is brace depth, or indentation depth for Python.
+pub fn complexity(sym: &Symbol, language: &str) -> Complexity {
+    let python = language == "py";
+    let code = strip_strings_and_comments(&sym.body, language);
+
+    let keywords: &[&str] = match language {
+        "py" => &["if", "elif", "for", "while", "except", "and", "or"],
+        "rs" => &["if", "for", "while"],
+        "go" => &["if", "for", "case"],
+        _ => &["if", "for", "while", "case", "catch"],
+    };
+    let mut branches = code
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| keywords.contains(word))
+        .count();
+    if !python {
+        branches += code.matches("&&").count() + code.matches("||").count();
+    }
+    if language == "rs" {
+        // A match with n arms adds n - 1 paths.
+        let arms = code.matches("=>").count();
+        let matches = code
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|w| *w == "match")
+            .count();
+        branches += arms.saturating_sub(matches);
+    }
+
+    let max_nesting = if python {
+        let mut widths: Vec<usize> = code
+            .lines()
+            .skip(1)
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .collect();
+        widths.sort_unstable();
+        widths.dedup();
+        widths.len().saturating_sub(1)
+    } else {
+        let mut depth: usize = 0;
+        let mut max_depth: usize = 0;
+        for c in code.chars() {
+            match c {
+                '{' => {
+                    depth += 1;
+                    max_depth = max_depth.max(depth);
+                }
+                '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        // The function's own braces don't count.
+        max_depth.saturating_sub(1)
+    };
+
+    Complexity {
+        lines: sym.end_line - sym.start_line + 1,
+        branches,
+        cyclomatic: branches + 1,
+        max_nesting,
+    }
+}
+
+/// `text` with string literal contents and comments blanked out, keeping
+/// line structure and indentation.
+fn strip_strings_and_comments(text: &str, language: &str) -> String {
+    let python = language == "py";
+    // Rust and Go use `'` for char/rune literals (and Rust for lifetimes).
+    let char_literals = matches!(language, "rs" | "go");
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+                out.push(c);
+            } else if c == '\n' {
+                out.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' | '`' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            '\'' if !char_literals => {
+                quote = Some(c);
+                out.push(c);
+            }
+            // Char literals (`'"'`, `'\''`); a lone `'` is a Rust lifetime.
+            '\'' => {
+                let mut ahead = chars.clone();
+                let literal = match ahead.next() {
+                    Some('\\') => ahead.nth(1) == Some('\''),
+                    Some(_) => ahead.next() == Some('\''),
+                    None => false,
+                };
+                if literal {
+                    chars = ahead;
+                }
+                out.push(c);
+            }
+            '#' if python => {
+                while chars.peek().is_some_and(|&n| n != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if !python && chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&n| n != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if !python && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    if n == '\n' {
+                        out.push(n);
+                    }
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// ~4 chars per token, as in the context engine.
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
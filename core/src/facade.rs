@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+
+use crate::builtins::BuiltinFilter;
+use crate::config::FocalConfig;
+use crate::context::{ContextCapsule, ContextEngine};
+use crate::db::{Database, Memory, ResolvedFile, SearchFilter, SymbolResult, SymbolSummary};
+use crate::deny::DenyList;
+use crate::grammar::GrammarRegistry;
+use crate::graph::{GraphEngine, ImpactReport};
+use crate::guardrails::Guardrails;
+use crate::indexer::{IndexStats, Indexer};
+use crate::reindex_scheduler::IndexerTarget;
+
+// ---------------------------------------------------------------------------
+// Focal — the embedding API
+// ---------------------------------------------------------------------------
+//
+// A stable entry point for tools that embed focal-core instead of talking to
+// `focal serve` over MCP:
+//
+//     let focal = Focal::builder().workspace("./repo").db_path("focal.db").build()?;
+//     focal.index()?;
+//     let capsule = focal.context("where are sessions refreshed", 4000)?;
+//     focal.save_memory("Sessions are refreshed lazily", "decision", &["refresh"])?;
+//
+// `Focal` owns the shared database handle, the workspace roots and the index
+// settings (`[edges]`, `[index] redact_secrets`, `deny_paths` and the size
+// guardrails), so the initial run, the file watcher, read-time refreshes and
+// embedders all index the same way. It is cheap to clone; clones share the
+// database. The MCP server is built on top of it and only adds session state.
+
+/// A handle to one focal index and the workspaces indexed into it.
+#[derive(Clone)]
+pub struct Focal {
+    db: Arc<Mutex<Database>>,
+    workspaces: Vec<PathBuf>,
+    registry: Arc<GrammarRegistry>,
+    builtins: BuiltinFilter,
+    redact: bool,
+    deny: DenyList,
+    guardrails: Guardrails,
+}
+
+/// Where the index lives and how to build it. Without `db_path`,
+/// `in_memory` or `database`, the index is the one `focal serve` uses
+/// (`$FOCAL_DB_PATH`, else `~/.focal/index.db`). Without `config`, settings
+/// come from `~/.focal/config.toml`.
+#[derive(Default)]
+pub struct FocalBuilder {
+    workspaces: Vec<PathBuf>,
+    db_path: Option<PathBuf>,
+    in_memory: bool,
+    encrypt: bool,
+    database: Option<Database>,
+    config: Option<FocalConfig>,
+}
+
+impl FocalBuilder {
+    /// Add a workspace root; `Focal::index` indexes each in order.
+    pub fn workspace(mut self, path: impl Into<PathBuf>) -> Self {
+        self.workspaces.push(path.into());
+        self
+    }
+
+    /// Add several workspace roots.
+    pub fn workspaces(mut self, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.workspaces.extend(paths);
+        self
+    }
+
+    /// Open (or create) the index at `path`.
+    pub fn db_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.db_path = Some(path.into());
+        self
+    }
+
+    /// Keep the index in memory; nothing is written to disk.
+    pub fn in_memory(mut self) -> Self {
+        self.in_memory = true;
+        self
+    }
+
+    /// Create a new index file encrypted with SQLCipher (see
+    /// `encryption::open`). Existing encrypted files open either way.
+    pub fn encrypted(mut self, encrypt: bool) -> Self {
+        self.encrypt = encrypt;
+        self
+    }
+
+    /// Use an already open database instead of opening one.
+    pub fn database(mut self, db: Database) -> Self {
+        self.database = Some(db);
+        self
+    }
+
+    /// Take index settings and the graph cache budget from `config` instead
+    /// of `~/.focal/config.toml`.
+    pub fn config(mut self, config: FocalConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn build(self) -> Result<Focal> {
+        let config = self.config.unwrap_or_else(FocalConfig::load);
+        let db = match self.database {
+            Some(db) => db,
+            None if self.in_memory => Database::open_in_memory()?,
+            None => {
+                let path = self.db_path.unwrap_or_else(crate::config::db_path);
+                if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(dir)?;
+                }
+                crate::encryption::open(&path, self.encrypt)?
+            }
+        };
+        // Files indexed before they matched `deny_paths`. The deny list only
+        // changes with the config, so once per handle is enough: indexing
+        // never stores a denied file.
+        let deny = config.index.deny_list();
+        let denied = db.purge_denied_files(&deny)?;
+        if denied > 0 {
+            tracing::info!(denied, "purged files matching deny_paths");
+        }
+        Ok(Focal {
+            db: Arc::new(Mutex::new(db.with_graph_cache_budget(config.memory.graph_cache_bytes()))),
+            workspaces: self.workspaces,
+            registry: Arc::new(GrammarRegistry::new()),
+            builtins: config.edges.builtin_filter(),
+            redact: config.index.redact_secrets(),
+            deny,
+            guardrails: config.index.guardrails(),
+        })
+    }
+}
+
+impl Focal {
+    pub fn builder() -> FocalBuilder {
+        FocalBuilder::default()
+    }
+
+    /// The shared database handle, for work that outlives one call. The
+    /// `Database` API is lower level than this facade and may change.
+    pub fn database(&self) -> &Arc<Mutex<Database>> {
+        &self.db
+    }
+
+    /// Lock the database for a sequence of lower-level calls.
+    pub(crate) fn lock(&self) -> Result<MutexGuard<'_, Database>> {
+        self.db.lock().map_err(|e| anyhow!("{e}"))
+    }
+
+    pub fn workspaces(&self) -> &[PathBuf] {
+        &self.workspaces
+    }
+
+    /// `[index] deny_paths`.
+    pub fn deny_list(&self) -> &DenyList {
+        &self.deny
+    }
+
+    /// An indexer over `db` with this handle's index settings.
+    pub(crate) fn indexer<'a>(&'a self, db: &'a Database) -> Indexer<'a> {
+        Indexer::new(db, &self.registry)
+            .with_builtin_filter(self.builtins.clone())
+            .with_redaction(self.redact)
+            .with_deny_list(self.deny.clone())
+            .with_guardrails(self.guardrails.clone())
+    }
+
+    /// A reindex scheduler target over the shared database with this
+    /// handle's index settings.
+    pub fn reindex_target(&self) -> IndexerTarget<'_> {
+        IndexerTarget::new(&self.db, &self.registry, self.builtins.clone())
+            .with_redaction(self.redact)
+            .with_deny_list(self.deny.clone())
+            .with_guardrails(self.guardrails.clone())
+    }
+
+    // -- Indexing -----------------------------------------------------------
+
+    /// Index every workspace, stopping at the first that fails.
+    pub fn index(&self) -> Result<Vec<IndexStats>> {
+        self.workspaces.iter().map(|root| self.index_workspace(root)).collect()
+    }
+
+    /// Index the tree at `root`, which need not be a configured workspace.
+    pub fn index_workspace(&self, root: &Path) -> Result<IndexStats> {
+        let db = self.lock()?;
+        self.indexer(&db).index_directory(root)
+    }
+
+    /// Re-parse the changed files under `root` matching the repo-relative,
+    /// gitignore-style `globs`; see `Indexer::reindex_paths`. The database
+    /// is locked per file, not for the whole run.
+    pub fn reindex_paths(&self, root: &Path, globs: &[String]) -> Result<IndexStats> {
+        self.reindex_target().reindex_paths(root, globs)
+    }
+
+    /// Re-index one file of a configured workspace. Returns false when the
+    /// file was skipped (unchanged, unsupported or denied).
+    pub fn index_file(&self, path: &Path) -> Result<bool> {
+        let path = crate::paths::canonicalize(path)?;
+        let root = self
+            .workspaces
+            .iter()
+            .filter_map(|w| crate::paths::canonicalize(w).ok())
+            .find(|w| path.starts_with(w))
+            .ok_or_else(|| anyhow!("{} is not inside a configured workspace", path.display()))?;
+        let db = self.lock()?;
+        self.indexer(&db).index_file(&path, &root)
+    }
+
+    // -- Queries ------------------------------------------------------------
+
+    /// Symbols named `name` across every repository; `kind` narrows them
+    /// (empty for any).
+    pub fn find_symbols(&self, name: &str, kind: &str) -> Result<Vec<SymbolResult>> {
+        self.query_symbols(name, kind, "", "", "")
+    }
+
+    /// `find_symbols` narrowed by repository name, visibility and attribute
+    /// as well; empty filters match anything.
+    pub fn query_symbols(
+        &self,
+        name: &str,
+        kind: &str,
+        repo: &str,
+        visibility: &str,
+        attribute: &str,
+    ) -> Result<Vec<SymbolResult>> {
+        self.lock()?.query_symbols_full(name, kind, repo, visibility, attribute)
+    }
+
+    /// Id of the repository named `name`, for `SearchFilter::repo`.
+    pub fn repo_id(&self, name: &str) -> Result<Option<i64>> {
+        self.lock()?.get_repo_id_by_name(name)
+    }
+
+    /// Full-text search over symbol names, signatures and docs.
+    pub fn search(&self, query: &str, max_results: i64) -> Result<Vec<SymbolResult>> {
+        self.search_filtered(query, &SearchFilter::default(), max_results)
+    }
+
+    /// `search` narrowed by `filter`.
+    pub fn search_filtered(&self, query: &str, filter: &SearchFilter, max_results: i64) -> Result<Vec<SymbolResult>> {
+        let db = self.lock()?;
+        let symbols = db.search_code(query, filter, max_results)?;
+        Ok(db.symbol_results(&symbols))
+    }
+
+    /// The indexed file `file_path` resolves to (see
+    /// `Database::resolve_file_path`) and the signatures of its symbols.
+    /// None when no indexed file matches.
+    pub fn skeleton(&self, file_path: &str, repo: Option<&str>) -> Result<Option<(ResolvedFile, Vec<SymbolSummary>)>> {
+        let db = self.lock()?;
+        let Some(file) = db.resolve_file_path(file_path, repo)? else {
+            return Ok(None);
+        };
+        let symbols = db.get_skeleton(file.file_id, "standard")?;
+        Ok(Some((file, symbols)))
+    }
+
+    /// Everything that transitively depends on `symbol`, up to `depth` hops.
+    pub fn impact(&self, symbol: &str, depth: usize) -> Result<ImpactReport> {
+        let db = self.lock()?;
+        GraphEngine::new(&db).impact_graph_report(symbol, depth, None)
+    }
+
+    /// A context capsule for `query` within `max_tokens`, as get_context
+    /// builds it for a fresh session.
+    pub fn context(&self, query: &str, max_tokens: usize) -> Result<ContextCapsule> {
+        let db = self.lock()?;
+        ContextEngine::new(&db).get_capsule(query, max_tokens, None, &HashSet::new())
+    }
+
+    // -- Memories -----------------------------------------------------------
+
+    /// Store a memory linked to the named symbols. With workspaces, a name
+    /// resolves in the first workspace's repository that has it; without,
+    /// in any repository. Names that aren't indexed are skipped. Returns the
+    /// memory id.
+    pub fn save_memory(&self, content: &str, category: &str, symbols: &[&str]) -> Result<i64> {
+        let db = self.lock()?;
+        let repo_ids = self.workspace_repo_ids(&db)?;
+        let mut symbol_ids = Vec::new();
+        for name in symbols {
+            let sym = if self.workspaces.is_empty() {
+                db.find_symbol_by_name_any(name)?
+            } else {
+                repo_ids.iter().find_map(|&rid| db.find_symbol_by_name(rid, name).transpose()).transpose()?
+            };
+            if let Some(sym) = sym {
+                symbol_ids.push(sym.id);
+            }
+        }
+        db.save_memory(content, category, &symbol_ids)
+    }
+
+    /// Ids of the indexed repositories of the workspaces, in workspace order.
+    pub(crate) fn workspace_repo_ids(&self, db: &Database) -> Result<Vec<i64>> {
+        let mut ids = Vec::new();
+        for root in &self.workspaces {
+            let Ok(root) = crate::paths::canonicalize(root) else { continue };
+            if let Some(repo) = db.get_repository_by_path(&root.to_string_lossy())? {
+                ids.push(repo.id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Current memories, optionally of one `category` (empty for all).
+    pub fn list_memories(&self, category: &str) -> Result<Vec<Memory>> {
+        self.lock()?.list_memories(category, false, false, "")
+    }
+
+    /// Full-text search over memories and observations.
+    pub fn search_memories(&self, query: &str, max_results: i64) -> Result<Vec<Memory>> {
+        self.lock()?.search_memories(query, max_results)
+    }
+
+    /// Returns false when no memory has that id.
+    pub fn delete_memory(&self, id: i64) -> Result<bool> {
+        self.lock()?.delete_memory(id)
+    }
+}
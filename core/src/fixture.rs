@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+// ---------------------------------------------------------------------------
+// Synthetic repositories
+// ---------------------------------------------------------------------------
+
+const VERBS: &[&str] = &[
+    "parse", "validate", "render", "load", "store", "compute", "resolve", "merge", "encode",
+    "schedule",
+];
+const NOUNS: &[&str] = &[
+    "order", "invoice", "session", "token", "config", "payload", "report", "cursor", "route",
+    "account", "snapshot", "header",
+];
+
+/// Shape of a generated repository.
+#[derive(Debug, Clone)]
+pub struct FixtureSpec {
+    pub files: usize,
+    pub functions_per_file: usize,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        Self { files: 200, functions_per_file: 10 }
+    }
+}
+
+/// What `generate_repo` wrote.
+#[derive(Debug, Clone, Default)]
+pub struct FixtureSummary {
+    pub files: usize,
+    pub functions: usize,
+    /// Call sites, each naming a function defined in the repository.
+    pub calls: usize,
+}
+
+/// Name of function `index` in file `file`, e.g. `validate_invoice_3_7`.
+/// Every name is unique, and the verb/noun prefix gives FTS queries
+/// something realistic to match.
+pub fn function_name(file: usize, index: usize) -> String {
+    let verb = VERBS[(file + index) % VERBS.len()];
+    let noun = NOUNS[(file * 7 + index) % NOUNS.len()];
+    format!("{verb}_{noun}_{file}_{index}")
+}
+
+/// Sample queries for search benchmarks: every verb/noun pair.
+pub fn sample_queries() -> Vec<String> {
+    VERBS
+        .iter()
+        .flat_map(|v| NOUNS.iter().map(move |n| format!("{v} {n}")))
+        .collect()
+}
+
+/// Write a deterministic multi-language repository under `root`: files
+/// rotate through Rust, Go, Python and TypeScript and are grouped twenty to
+/// a directory. Each function calls the previous one in its file, and the
+/// first function of each file calls into the previous file, so edge
+/// resolution has cross-file work to do.
+pub fn generate_repo(root: &Path, spec: &FixtureSpec) -> Result<FixtureSummary> {
+    let mut summary = FixtureSummary::default();
+    for file in 0..spec.files {
+        let dir = root.join(format!("module_{}", file / 20));
+        std::fs::create_dir_all(&dir)?;
+        let ext = ["rs", "go", "py", "ts"][file % 4];
+        let mut source = match ext {
+            "go" => format!("package module{}\n\n", file / 20),
+            _ => String::new(),
+        };
+        for index in 0..spec.functions_per_file {
+            let name = function_name(file, index);
+            let callee = match (index, file) {
+                (0, 0) => None,
+                (0, f) => Some(function_name(f - 1, 0)),
+                (i, f) => Some(function_name(f, i - 1)),
+            };
+            summary.calls += usize::from(callee.is_some());
+            source.push_str(&render_function(ext, &name, callee.as_deref()));
+            source.push('\n');
+            summary.functions += 1;
+        }
+        std::fs::write(dir.join(format!("file_{file}.{ext}")), source)?;
+        summary.files += 1;
+    }
+    Ok(summary)
+}
+
+fn render_function(ext: &str, name: &str, callee: Option<&str>) -> String {
+    let words = name.split('_').take(2).collect::<Vec<_>>().join(" the ");
+    match ext {
+        "rs" => {
+            let call = callee.map_or("0".to_string(), |c| format!("{c}(input)"));
+            format!("/// Steps to {words}.\npub fn {name}(input: u64) -> u64 {{\n    let base = {call};\n    base.wrapping_add(input)\n}}\n")
+        }
+        "go" => {
+            let call = callee.map_or("0".to_string(), |c| format!("{c}(input)"));
+            format!("// {name} steps to {words}.\nfunc {name}(input int) int {{\n\tbase := {call}\n\treturn base + input\n}}\n")
+        }
+        "py" => {
+            let call = callee.map_or("0".to_string(), |c| format!("{c}(value)"));
+            format!("def {name}(value):\n    \"\"\"Steps to {words}.\"\"\"\n    base = {call}\n    return base + value\n")
+        }
+        _ => {
+            let call = callee.map_or("0".to_string(), |c| format!("{c}(value)"));
+            format!("/** Steps to {words}. */\nexport function {name}(value: number): number {{\n  const base = {call};\n  return base + value;\n}}\n")
+        }
+    }
+}
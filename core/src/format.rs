@@ -0,0 +1,286 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::db::SymbolSummary;
+
+// ---------------------------------------------------------------------------
+// Response formats
+// ---------------------------------------------------------------------------
+//
+// Pretty-printed JSON spends a fifth or more of its tokens on indentation and
+// repeated field names. Read tools take a `format`: `json` (the default),
+// `compact-json` (same document, no whitespace) or `text`, a terse rendering
+// with one line per symbol or flat record and skeletons indented by nesting.
+
+/// Fields folded into a symbol's header line.
+const HEADER_FIELDS: &[&str] = &["name", "kind", "signature", "file_path", "start_line", "end_line", "line"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    CompactJson,
+    Text,
+}
+
+impl OutputFormat {
+    /// Parse a `format` parameter; None is `Json`.
+    pub fn parse(format: Option<&str>) -> Result<Self> {
+        match format.map(str::trim).unwrap_or("json") {
+            "" | "json" => Ok(Self::Json),
+            "compact-json" | "compact_json" | "compact" => Ok(Self::CompactJson),
+            "text" => Ok(Self::Text),
+            other => bail!("unknown format '{other}': expected json, compact-json or text"),
+        }
+    }
+}
+
+/// `include` / `exclude` lists of top-level result fields. The identifying
+/// `id` and `name` are always kept.
+#[derive(Debug, Clone, Default)]
+pub struct FieldMask {
+    include: Option<HashSet<String>>,
+    exclude: HashSet<String>,
+}
+
+impl FieldMask {
+    /// Build a mask, rejecting names not in `known`.
+    pub fn new(include: Option<&[String]>, exclude: Option<&[String]>, known: &[&str]) -> Result<Self> {
+        for field in include.into_iter().chain(exclude).flatten() {
+            if !known.contains(&field.as_str()) {
+                bail!("unknown field '{field}': expected one of {}", known.join(", "));
+            }
+        }
+        Ok(Self {
+            include: include.map(|fields| fields.iter().cloned().collect()),
+            exclude: exclude.into_iter().flatten().cloned().collect(),
+        })
+    }
+
+    /// Whether `field` survives the mask.
+    pub fn keeps(&self, field: &str) -> bool {
+        if matches!(field, "id" | "name") {
+            return true;
+        }
+        self.include.as_ref().is_none_or(|fields| fields.contains(field)) && !self.exclude.contains(field)
+    }
+
+    /// `value` as JSON with masked fields removed from it, or from each
+    /// element when it is an array.
+    pub fn apply<T: Serialize>(&self, value: &T) -> Result<Value> {
+        let mut value = serde_json::to_value(value)?;
+        let objects: Vec<&mut Map<String, Value>> = match &mut value {
+            Value::Array(items) => items.iter_mut().filter_map(Value::as_object_mut).collect(),
+            Value::Object(map) => vec![map],
+            _ => Vec::new(),
+        };
+        for map in objects {
+            map.retain(|key, _| self.keeps(key));
+        }
+        Ok(value)
+    }
+}
+
+/// Serialize `value` in `format`.
+pub fn render<T: Serialize>(value: &T, format: OutputFormat) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        OutputFormat::CompactJson => serde_json::to_string(value)?,
+        OutputFormat::Text => {
+            let mut out = String::new();
+            write_value(&mut out, &serde_json::to_value(value)?, 0);
+            out.truncate(out.trim_end().len());
+            out
+        }
+    })
+}
+
+/// A file's symbols as `{repo, file_path, symbols}`; in text, one line per
+/// symbol indented by how many other symbols enclose it.
+pub fn render_skeleton(repo: &str, file_path: &str, symbols: &[SymbolSummary], format: OutputFormat) -> Result<String> {
+    if format != OutputFormat::Text {
+        let response = serde_json::json!({
+            "repo": repo,
+            "file_path": file_path,
+            "symbols": symbols,
+        });
+        return render(&response, format);
+    }
+    let mut out = format!("{repo}:{file_path}");
+    // End lines of the symbols enclosing the current one.
+    let mut open: Vec<i64> = Vec::new();
+    for sym in symbols {
+        while open.last().is_some_and(|&end| end < sym.start_line) {
+            open.pop();
+        }
+        out.push('\n');
+        out.push_str(&"  ".repeat(open.len() + 1));
+        out.push_str(&headline(&sym.signature, &sym.kind, &sym.name));
+        out.push_str(&format!("  {}-{}", sym.start_line, sym.end_line));
+        open.push(sym.end_line);
+    }
+    Ok(out)
+}
+
+fn write_value(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(map) if is_symbol(map) => write_symbol(out, map, indent),
+        Value::Object(map) => {
+            for (key, field) in map.iter().filter(|(_, v)| !is_empty(v)) {
+                write_field(out, key, field, indent);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                match item {
+                    Value::Object(map) if !is_symbol(map) && is_flat(map) => {
+                        line(out, indent, &format!("- {}", record_line(map)));
+                    }
+                    Value::Object(map) if !is_symbol(map) => {
+                        // First line of the entry gets the `- ` marker.
+                        let start = out.len();
+                        write_value(out, item, indent + 2);
+                        if out.len() > start + indent + 2 {
+                            out.replace_range(start + indent..start + indent + 2, "- ");
+                        }
+                    }
+                    Value::Object(_) | Value::Array(_) => write_value(out, item, indent),
+                    scalar => line(out, indent, &format!("- {}", scalar_text(scalar))),
+                }
+            }
+        }
+        scalar => write_block(out, &scalar_text(scalar), indent),
+    }
+}
+
+/// `key: value` for scalars; nested values and multi-line strings go on the
+/// lines below, indented.
+fn write_field(out: &mut String, key: &str, value: &Value, indent: usize) {
+    match value {
+        Value::String(s) if s.contains('\n') => {
+            line(out, indent, &format!("{key}:"));
+            write_block(out, s, indent + 2);
+        }
+        Value::Object(_) | Value::Array(_) => {
+            line(out, indent, &format!("{key}:"));
+            write_value(out, value, indent + 2);
+        }
+        scalar => line(out, indent, &format!("{key}: {}", scalar_text(scalar))),
+    }
+}
+
+/// `signature  path:start-end key=value ...`, then bodies and nested fields
+/// on the lines below.
+fn write_symbol(out: &mut String, map: &Map<String, Value>, indent: usize) {
+    let text = |key: &str| map.get(key).and_then(Value::as_str).unwrap_or("");
+    let mut header = headline(text("signature"), text("kind"), text("name"));
+    let start = map.get("start_line").or_else(|| map.get("line")).and_then(Value::as_i64);
+    let end = map.get("end_line").and_then(Value::as_i64);
+    let location = match (start, end) {
+        (Some(start), Some(end)) if end != start => format!("{start}-{end}"),
+        (Some(start), _) => start.to_string(),
+        _ => String::new(),
+    };
+    match (text("file_path"), location.as_str()) {
+        ("", "") => {}
+        ("", location) => header.push_str(&format!("  L{location}")),
+        (path, "") => header.push_str(&format!("  {path}")),
+        (path, location) => header.push_str(&format!("  {path}:{location}")),
+    }
+
+    let mut nested = Vec::new();
+    for (key, value) in map.iter().filter(|(k, v)| !HEADER_FIELDS.contains(&k.as_str()) && !is_empty(v)) {
+        match value {
+            Value::String(s) if s.contains('\n') => nested.push((key, value)),
+            Value::Object(_) | Value::Array(_) => nested.push((key, value)),
+            scalar => header.push_str(&format!(" {key}={}", scalar_text(scalar))),
+        }
+    }
+    line(out, indent, &header);
+    for (key, value) in nested {
+        write_field(out, key, value, indent + 4);
+    }
+}
+
+/// A flat record as `key=value ...  path:line`, with its location last.
+fn record_line(map: &Map<String, Value>) -> String {
+    let mut fields: Vec<String> = map
+        .iter()
+        .filter(|(k, v)| !is_empty(v) && !matches!(k.as_str(), "file_path" | "line" | "start_line" | "end_line"))
+        .map(|(key, value)| format!("{key}={}", scalar_text(value)))
+        .collect();
+    let start = map.get("start_line").or_else(|| map.get("line")).and_then(Value::as_i64);
+    let end = map.get("end_line").and_then(Value::as_i64);
+    let mut location = map.get("file_path").and_then(Value::as_str).unwrap_or("").to_string();
+    match (start, end) {
+        (Some(start), Some(end)) if end != start => location.push_str(&format!(":{start}-{end}")),
+        (Some(start), _) if location.is_empty() => location = format!("L{start}"),
+        (Some(start), _) => location.push_str(&format!(":{start}")),
+        _ => {}
+    }
+    if !location.is_empty() {
+        fields.push(format!(" {location}"));
+    }
+    fields.join(" ")
+}
+
+/// A symbol's signature on one line, or `kind name` when it has none.
+fn headline(signature: &str, kind: &str, name: &str) -> String {
+    let signature = signature.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !signature.is_empty() {
+        signature
+    } else if kind.is_empty() {
+        name.to_string()
+    } else {
+        format!("{kind} {name}")
+    }
+}
+
+fn is_symbol(map: &Map<String, Value>) -> bool {
+    map.get("name").is_some_and(Value::is_string) && map.get("kind").is_some_and(Value::is_string)
+}
+
+/// An object whose fields all fit on one line.
+fn is_flat(map: &Map<String, Value>) -> bool {
+    map.values().all(|v| match v {
+        Value::String(s) => !s.contains('\n'),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => true,
+    })
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn write_block(out: &mut String, text: &str, indent: usize) {
+    for text_line in text.lines() {
+        line(out, indent, text_line);
+    }
+}
+
+fn line(out: &mut String, indent: usize, text: &str) {
+    let text = text.trim_end();
+    if !text.is_empty() {
+        out.push_str(&" ".repeat(indent));
+        out.push_str(text);
+    }
+    out.push('\n');
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::indexer::{is_go_file, Indexer};
+
+// ---------------------------------------------------------------------------
+// Read-time freshness
+// ---------------------------------------------------------------------------
+//
+// The watcher reindexes asynchronously, so an agent that edits a file and
+// immediately asks about it can be served the old body. Tools that answer
+// about specific symbols first check their files: any file modified on disk
+// after it was indexed is reindexed on the spot, within a small time budget,
+// and the answer is computed from the fresh index.
+
+/// Time spent reindexing before answering; files beyond it are left to the
+/// watcher.
+pub const DEFAULT_REFRESH_BUDGET: Duration = Duration::from_millis(200);
+
+/// An indexed file whose on-disk copy is newer than its index entry, or
+/// which no longer exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleFile {
+    pub file_id: i64,
+    pub root: PathBuf,
+    pub path: PathBuf,
+}
+
+/// What a refresh did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RefreshStats {
+    /// Files re-parsed or removed; the index changed.
+    pub reindexed: usize,
+    /// Files whose mtime moved but whose contents had not changed.
+    pub unchanged: usize,
+    /// Stale files skipped once the budget ran out.
+    pub deferred: usize,
+    /// Files and repo-wide passes that failed; the rest of the refresh
+    /// still ran.
+    pub errors: Vec<String>,
+}
+
+/// Files among `file_ids` modified on disk since they were indexed.
+pub fn stale_files(db: &Database, file_ids: &[i64]) -> Result<Vec<StaleFile>> {
+    let mut stale = Vec::new();
+    for (file_id, root, rel_path, indexed_at) in db.file_index_times(file_ids)? {
+        let root = PathBuf::from(root);
+        let path = root.join(&rel_path);
+        let is_stale = match modified_secs(&path) {
+            Some(mtime) => mtime > indexed_at,
+            // Deleted (or unreadable): the index still has it.
+            None => true,
+        };
+        if is_stale {
+            stale.push(StaleFile { file_id, root, path });
+        }
+    }
+    Ok(stale)
+}
+
+/// Reindex `stale` files in order until `budget` is spent. Deleted files are
+/// removed; files whose contents are unchanged are re-stamped so they are not
+/// checked again. The budget is checked before every file, and a file or
+/// repo-wide pass that fails is recorded in `errors` without stopping the
+/// others. The repo-wide passes of roots with changes always run, since the
+/// index is inconsistent until they do.
+pub fn refresh(indexer: &Indexer, db: &Database, stale: &[StaleFile], budget: Duration) -> RefreshStats {
+    let start = Instant::now();
+    let mut stats = RefreshStats::default();
+    // Roots with changes, and whether a Go file among them changed; their
+    // repo-wide passes run once at the end.
+    let mut touched: BTreeMap<&Path, bool> = BTreeMap::new();
+    for file in stale {
+        if start.elapsed() >= budget {
+            stats.deferred += 1;
+            continue;
+        }
+        let changed = if file.path.exists() {
+            indexer.index_one_file(&file.path, &file.root)
+        } else {
+            indexer.remove_one_file(&file.path, &file.root)
+        };
+        match changed {
+            Ok(true) => {
+                stats.reindexed += 1;
+                *touched.entry(&file.root).or_default() |= is_go_file(&file.path);
+            }
+            Ok(false) => match db.touch_file_indexed_at(file.file_id) {
+                Ok(()) => stats.unchanged += 1,
+                Err(e) => stats.errors.push(format!("{}: {e:#}", file.path.display())),
+            },
+            Err(e) => stats.errors.push(format!("{}: {e:#}", file.path.display())),
+        }
+    }
+    for (root, go_changed) in touched {
+        if go_changed {
+            if let Err(e) = indexer.refresh_go_implements(root) {
+                stats.errors.push(format!("{}: Go implements refresh failed: {e:#}", root.display()));
+            }
+        }
+        if let Err(e) = indexer.finish_file_updates(root) {
+            stats.errors.push(format!("{}: finishing updates failed: {e:#}", root.display()));
+        }
+    }
+    stats
+}
+
+fn modified_secs(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
@@ -0,0 +1,97 @@
+// ---------------------------------------------------------------------------
+// Generated-code detection
+// ---------------------------------------------------------------------------
+//
+// Protobuf stubs, client SDKs and compiled bundles repeat every name in the
+// schema they were generated from, so they crowd hand-written code out of
+// FTS results while never being the place to make a change. A file counts
+// as generated when:
+//
+// - its name ends in a generator suffix (`.pb.go`, `_pb2.py`,
+//   `*_generated.ts`, ...),
+// - its header (the first `HEADER_LINES` lines) carries a generator marker:
+//   Go's `Code generated ... DO NOT EDIT.`, `@generated`, protoc's
+//   `Generated by the protocol buffer compiler`, C#'s `<auto-generated>`, a
+//   "this file is automatically generated" notice, or an "auto-generated"
+//   notice on a line that also says "do not edit" (hand-written doc
+//   comments say "auto-generated by" too), or
+// - it ends in a `sourceMappingURL` comment, i.e. it is compiled output
+//   with a source map next to it.
+//
+// The flag is stored in `files.generated`. Searches and context pivots skip
+// generated files unless asked to include them.
+
+/// File-name suffixes code generators emit.
+pub const GENERATED_SUFFIXES: &[&str] = &[
+    ".pb.go",
+    ".pb.gw.go",
+    "_grpc.pb.go",
+    ".gen.go",
+    "_gen.go",
+    "_generated.go",
+    "_generated.rs",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    "_pb2.pyi",
+    "_pb.js",
+    "_pb.ts",
+    "_pb.d.ts",
+    "_grpc_pb.js",
+    ".pb.ts",
+    ".generated.ts",
+    "_generated.ts",
+    ".generated.js",
+    ".min.js",
+    ".js.map",
+];
+
+/// Lines at the top of a file searched for a generator marker.
+const HEADER_LINES: usize = 12;
+
+/// Lower-case markers that identify a generated file's header.
+const HEADER_MARKERS: &[&str] = &[
+    "@generated",
+    "generated by the protocol buffer compiler",
+    "<auto-generated",
+    "this file is automatically generated",
+    "this file was automatically generated",
+    "this file is auto-generated",
+    "this file was auto-generated",
+    "this file is autogenerated",
+    "this file was autogenerated",
+];
+
+/// Lower-case generator notices that only count on a line that also says
+/// "do not edit".
+const NOTICES: &[&str] = &["code generated", "auto-generated", "autogenerated", "automatically generated"];
+
+/// The longest generator suffix `name` ends with.
+pub fn generated_suffix(name: &str) -> Option<&'static str> {
+    GENERATED_SUFFIXES.iter().copied().filter(|s| name.ends_with(s)).max_by_key(|s| s.len())
+}
+
+/// Whether the file at `rel_path` with content `source` was produced by a
+/// code generator.
+pub fn is_generated(rel_path: &str, source: &[u8]) -> bool {
+    let name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    if generated_suffix(name).is_some() {
+        return true;
+    }
+    let head = &source[..source.len().min(4096)];
+    let header = String::from_utf8_lossy(head);
+    if header.lines().take(HEADER_LINES).any(is_marker_line) {
+        return true;
+    }
+    let tail = &source[source.len().saturating_sub(512)..];
+    String::from_utf8_lossy(tail)
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| line.contains("sourceMappingURL="))
+}
+
+fn is_marker_line(line: &str) -> bool {
+    let line = line.to_ascii_lowercase();
+    (line.contains("do not edit") && NOTICES.iter().any(|n| line.contains(n)))
+        || HEADER_MARKERS.iter().any(|m| line.contains(m))
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::grammar::{ExtractedSymbol, GrammarRegistry};
+
+// ---------------------------------------------------------------------------
+// Revision lookup
+// ---------------------------------------------------------------------------
+
+/// A file blob in a git tree.
+#[derive(Debug, Clone)]
+pub struct TreeEntry {
+    /// Repo-relative path, forward slashes.
+    pub path: String,
+    pub oid: String,
+    pub size: u64,
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Resolve a ref (`origin/main`, `v1.2`, a short SHA) to a full commit SHA.
+pub fn resolve_commit(root: &Path, rev: &str) -> Result<String> {
+    let spec = format!("{rev}^{{commit}}");
+    let out = git(root, &["rev-parse", "--verify", "--quiet", &spec])
+        .with_context(|| format!("unknown revision '{rev}'"))?;
+    Ok(String::from_utf8_lossy(&out).trim().to_string())
+}
+
+/// The checked-out branch name, or None for a detached HEAD or a directory
+/// that is not a git checkout.
+pub fn current_branch(root: &Path) -> Option<String> {
+    let out = git(root, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok()?;
+    let branch = String::from_utf8_lossy(&out).trim().to_string();
+    (!branch.is_empty()).then_some(branch)
+}
+
+/// Names of the local branches of the checkout at `root`.
+pub fn local_branches(root: &Path) -> Result<Vec<String>> {
+    let out = git(root, &["for-each-ref", "--format=%(refname:short)", "refs/heads"])?;
+    Ok(String::from_utf8_lossy(&out).lines().map(str::to_string).collect())
+}
+
+/// Uncommitted changes under `root` as a zero-context unified diff with
+/// root-relative paths: the staged changes when `staged`, otherwise every
+/// change in the working tree against HEAD.
+pub fn working_diff(root: &Path, staged: bool) -> Result<String> {
+    let base = if staged { "--cached" } else { "HEAD" };
+    let out = git(root, &["diff", "--relative", "--no-color", "--no-ext-diff", "-U0", base])?;
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// The directory git runs hooks from (honours `core.hooksPath` and linked
+/// worktrees), as an absolute path.
+pub fn hooks_dir(root: &Path) -> Result<PathBuf> {
+    let out = git(root, &["rev-parse", "--git-path", "hooks"]).context("not a git checkout")?;
+    let dir = PathBuf::from(String::from_utf8_lossy(&out).trim());
+    Ok(if dir.is_absolute() { dir } else { root.join(dir) })
+}
+
+/// Every blob reachable from `commit`'s tree. Submodules and symlinks are
+/// skipped: neither has indexable content of its own.
+pub fn list_tree(root: &Path, commit: &str) -> Result<Vec<TreeEntry>> {
+    let out = git(root, &["ls-tree", "-r", "-l", "-z", commit])?;
+    // Records look like "<mode> <type> <oid> <size>\t<path>\0"
+    let entries = out
+        .split(|&b| b == 0)
+        .filter(|rec| !rec.is_empty())
+        .filter_map(|rec| {
+            let rec = String::from_utf8_lossy(rec);
+            let (meta, path) = rec.split_once('\t')?;
+            let mut fields = meta.split_whitespace();
+            let mode = fields.next()?;
+            let kind = fields.next()?;
+            let oid = fields.next()?;
+            let size = fields.next()?.parse().ok()?;
+            if kind != "blob" || mode == "120000" {
+                return None;
+            }
+            Some(TreeEntry {
+                path: path.to_string(),
+                oid: oid.to_string(),
+                size,
+            })
+        })
+        .collect();
+    Ok(entries)
+}
+
+// ---------------------------------------------------------------------------
+// Blob reading
+// ---------------------------------------------------------------------------
+
+/// Reads blob contents through one long-lived `git cat-file --batch` process
+/// instead of spawning `git show` per file.
+pub struct BlobReader {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl BlobReader {
+    pub fn new(root: &Path) -> Result<Self> {
+        let mut child = Command::new("git")
+            .args(["cat-file", "--batch"])
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("failed to run git cat-file")?;
+        let stdin = child.stdin.take().context("git cat-file: no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().context("git cat-file: no stdout")?);
+        Ok(Self { child, stdin, stdout })
+    }
+
+    /// Contents of the object `oid`.
+    pub fn read(&mut self, oid: &str) -> Result<Vec<u8>> {
+        writeln!(self.stdin, "{oid}")?;
+        self.stdin.flush()?;
+
+        // Header: "<oid> <type> <size>\n", or "<oid> missing\n"
+        let mut header = String::new();
+        self.stdout.read_line(&mut header)?;
+        let size: usize = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [_, _, size] => size.parse().context("git cat-file: bad header")?,
+            _ => bail!("git cat-file: object {oid} not found"),
+        };
+
+        let mut content = vec![0; size];
+        self.stdout.read_exact(&mut content)?;
+        // Trailing newline after the content
+        let mut newline = [0u8; 1];
+        self.stdout.read_exact(&mut newline)?;
+        Ok(content)
+    }
+}
+
+impl Drop for BlobReader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A commit's tree, readable by repo-relative path.
+pub struct RevisionTree {
+    pub commit: String,
+    pub entries: Vec<TreeEntry>,
+    oids: HashMap<String, String>,
+    reader: BlobReader,
+}
+
+impl RevisionTree {
+    /// Resolve `rev` in the repository at `root` and list its tree.
+    pub fn open(root: &Path, rev: &str) -> Result<Self> {
+        let commit = resolve_commit(root, rev)?;
+        let entries = list_tree(root, &commit)?;
+        let oids = entries
+            .iter()
+            .map(|e| (e.path.clone(), e.oid.clone()))
+            .collect();
+        let reader = BlobReader::new(root)?;
+        Ok(Self { commit, entries, oids, reader })
+    }
+
+    /// Contents of `rel_path` at this revision; None when the path is not in the tree.
+    pub fn read(&mut self, rel_path: &str) -> Result<Option<Vec<u8>>> {
+        match self.oids.get(rel_path) {
+            Some(oid) => self.reader.read(oid).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// History
+// ---------------------------------------------------------------------------
+
+/// One commit from `git log`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitEntry {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub message: String,
+}
+
+/// The last `max` commits touching `rel_path`, newest first.
+pub fn file_log(root: &Path, rel_path: &str, max: usize) -> Result<Vec<CommitEntry>> {
+    let stdout = git(
+        root,
+        &["log", "--format=%H%n%an%n%aI%n%s%n---", &format!("-{max}"), "--", rel_path],
+    )?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    Ok(stdout
+        .split("---\n")
+        .filter(|s| !s.trim().is_empty())
+        .filter_map(|block| {
+            let lines: Vec<&str> = block.trim().lines().collect();
+            (lines.len() >= 4).then(|| CommitEntry {
+                hash: lines[0].to_string(),
+                author: lines[1].to_string(),
+                date: lines[2].to_string(),
+                message: lines[3..].join(" "),
+            })
+        })
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// Symbols at a revision
+// ---------------------------------------------------------------------------
+
+/// Contents of `rel_path` as of `rev` (`git show rev:path`).
+pub fn show_file(root: &Path, rev: &str, rel_path: &str) -> Result<Vec<u8>> {
+    git(root, &["show", &format!("{rev}:{rel_path}")])
+        .with_context(|| format!("{rel_path} not found at '{rev}'"))
+}
+
+/// Parse `rel_path` as of `rev` and return the first symbol named `name`
+/// (breadth-first, so top-level definitions win over nested ones).
+pub fn symbol_at_revision(
+    root: &Path,
+    rev: &str,
+    rel_path: &str,
+    name: &str,
+    registry: &GrammarRegistry,
+) -> Result<Option<ExtractedSymbol>> {
+    let ext = Path::new(rel_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let grammar = registry
+        .for_extension(ext)
+        .with_context(|| format!("no grammar for {rel_path}"))?;
+    let source = show_file(root, rev, rel_path)?;
+
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&grammar.language())?;
+    let tree = parser
+        .parse(&source, None)
+        .context("parse returned None")?;
+
+    fn find(symbols: Vec<ExtractedSymbol>, name: &str) -> Option<ExtractedSymbol> {
+        let mut nested = Vec::new();
+        for sym in symbols {
+            if sym.name == name {
+                return Some(sym);
+            }
+            nested.extend(sym.children);
+        }
+        if nested.is_empty() {
+            None
+        } else {
+            find(nested, name)
+        }
+    }
+    Ok(find(grammar.extract_symbols(&source, &tree), name))
+}
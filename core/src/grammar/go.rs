@@ -1,6 +1,6 @@
 use tree_sitter::{Language, Node, Tree};
 
-use super::{ExtractedReference, ExtractedSymbol, Grammar, SymbolKind};
+use super::{call_arguments, error_site, ErrorSite, ExtractedReference, ExtractedRoute, ExtractedSymbol, Grammar, Param, SymbolKind, TypeSignature, Visibility, HTTP_METHODS};
 
 pub struct GoGrammar;
 
@@ -18,6 +18,11 @@ impl Grammar for GoGrammar {
         let pkg = extract_package_name(&root, source).unwrap_or_default();
         let mut symbols = Vec::new();
         extract_top_level_symbols(&root, source, &pkg, &mut symbols);
+        if let Some(constraint) = build_constraint(&root, source) {
+            for sym in &mut symbols {
+                sym.attributes.insert(0, constraint.clone());
+            }
+        }
         symbols
     }
 
@@ -25,8 +30,21 @@ impl Grammar for GoGrammar {
         let root = tree.root_node();
         let mut refs = Vec::new();
         collect_references(&root, source, &mut refs);
+        collect_import_references(&root, source, &mut refs);
         refs
     }
+
+    fn extract_routes(&self, source: &[u8], tree: &Tree) -> Vec<ExtractedRoute> {
+        let mut routes = Vec::new();
+        collect_routes(&tree.root_node(), source, &mut routes);
+        routes
+    }
+
+    fn extract_error_sites(&self, source: &[u8], tree: &Tree) -> Vec<ErrorSite> {
+        let mut sites = Vec::new();
+        collect_error_sites(&tree.root_node(), source, &mut sites);
+        sites
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -93,6 +111,7 @@ fn extract_function(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedSy
     let body_node = node.child_by_field_name("body");
     let signature = extract_signature(node, &body_node, source);
     let body = node_text(node, source);
+    let visibility = visibility_of(&name);
     Some(ExtractedSymbol {
         name,
         qualified_name,
@@ -102,22 +121,36 @@ fn extract_function(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedSy
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility,
+        modifiers: Vec::new(),
+        types: Some(types_of(node, source)),
+        attributes: directives_of(node, source),
     })
 }
 
+/// Methods are named after their receiver type (`Server.Start`), so
+/// same-named methods of different types stay distinct symbols.
 fn extract_method(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedSymbol> {
     let name_node = node.child_by_field_name("name")?;
-    let name = node_text(&name_node, source);
+    let method = node_text(&name_node, source);
     let receiver_type = extract_receiver_type(node, source);
-    let qualified_name = match (&receiver_type, pkg.is_empty()) {
-        (Some(recv), false) => format!("{pkg}.{recv}.{name}"),
-        (Some(recv), true) => format!("{recv}.{name}"),
-        (None, false) => format!("{pkg}.{name}"),
-        (None, true) => name.clone(),
+    let name = match &receiver_type {
+        Some(recv) => format!("{recv}.{method}"),
+        None => method.clone(),
+    };
+    let qualified_name = if pkg.is_empty() {
+        name.clone()
+    } else {
+        format!("{pkg}.{name}")
     };
     let body_node = node.child_by_field_name("body");
     let signature = extract_signature(node, &body_node, source);
     let body = node_text(node, source);
+    // A method is only reachable from other packages through an exported type.
+    let visibility = match (visibility_of(&method), &receiver_type) {
+        (Visibility::Public, Some(recv)) => visibility_of(recv),
+        (v, _) => v,
+    };
     Some(ExtractedSymbol {
         name,
         qualified_name,
@@ -127,25 +160,27 @@ fn extract_method(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedSymb
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility,
+        modifiers: Vec::new(),
+        types: Some(types_of(node, source)),
+        attributes: directives_of(node, source),
     })
 }
 
 /// Extract the receiver type name from a method_declaration's parameter_list.
-/// For `func (s *Server) Start()`, returns `Some("Server")`.
+/// For `func (s *Server) Start()`, returns `Some("Server")`; generic
+/// receivers (`func (l *List[T]) Push`) yield the bare type, `List`.
 fn extract_receiver_type(node: &Node, source: &[u8]) -> Option<String> {
     let receiver = node.child_by_field_name("receiver")?;
-    // Walk the parameter_list looking for a type_identifier (possibly inside pointer_type)
-    let mut stack: Vec<Node> = vec![receiver];
-    while let Some(n) = stack.pop() {
-        if n.kind() == "type_identifier" {
-            return Some(node_text(&n, source));
-        }
-        let mut cursor = n.walk();
-        for child in n.children(&mut cursor) {
-            stack.push(child);
-        }
+    let mut ty = receiver.named_child(0)?.child_by_field_name("type")?;
+    loop {
+        ty = match ty.kind() {
+            "type_identifier" => return Some(node_text(&ty, source)),
+            "pointer_type" | "parenthesized_type" => ty.named_child(0)?,
+            "generic_type" => ty.child_by_field_name("type")?,
+            _ => return None,
+        };
     }
-    None
 }
 
 fn extract_type_decl(
@@ -156,7 +191,7 @@ fn extract_type_decl(
 ) {
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
-        if child.kind() == "type_spec" {
+        if matches!(child.kind(), "type_spec" | "type_alias") {
             if let Some(sym) = extract_type_spec(&child, source, pkg) {
                 out.push(sym);
             }
@@ -164,12 +199,16 @@ fn extract_type_decl(
     }
 }
 
+/// A `type_spec` (`type List[T any] struct{...}`) or `type_alias`
+/// (`type Set[T comparable] = map[T]struct{}`). Type parameters are kept in
+/// the signature but not in the name.
 fn extract_type_spec(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedSymbol> {
     let name_node = node.child_by_field_name("name")?;
     let name = node_text(&name_node, source);
     let type_node = node.child_by_field_name("type")?;
 
     let kind = match type_node.kind() {
+        _ if node.kind() == "type_alias" => SymbolKind::TypeAlias,
         "struct_type" => SymbolKind::Struct,
         "interface_type" => SymbolKind::Interface,
         _ => SymbolKind::TypeAlias,
@@ -178,7 +217,12 @@ fn extract_type_spec(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedS
     // Use the parent type_declaration node for line range if available
     let decl_node = node.parent().unwrap_or(*node);
     let body = node_text(&decl_node, source);
-    let signature = format!("type {name} {}", type_node.kind().replace('_', " "));
+    let type_params = node
+        .child_by_field_name("type_parameters")
+        .map(|p| node_text(&p, source))
+        .unwrap_or_default();
+    let assign = if node.kind() == "type_alias" { " =" } else { "" };
+    let signature = format!("type {name}{type_params}{assign} {}", type_node.kind().replace('_', " "));
     let qualified_name = if pkg.is_empty() {
         name.clone()
     } else {
@@ -186,6 +230,7 @@ fn extract_type_spec(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedS
     };
 
     Some(ExtractedSymbol {
+        visibility: visibility_of(&name),
         name,
         qualified_name,
         kind,
@@ -194,6 +239,9 @@ fn extract_type_spec(node: &Node, source: &[u8], pkg: &str) -> Option<ExtractedS
         start_line: decl_node.start_position().row + 1,
         end_line: decl_node.end_position().row + 1,
         children: Vec::new(),
+        modifiers: Vec::new(),
+        types: None,
+        attributes: directives_of(&decl_node, source),
     })
 }
 
@@ -216,22 +264,119 @@ fn extract_const_or_var(
                     format!("{pkg}.{name}")
                 };
                 let body = node_text(node, source);
-                let signature = extract_declaration_line(&body);
+                // `var handler = func(w http.ResponseWriter, r *http.Request) {...}`
+                // is a named function.
+                let (kind, signature, types) = match func_literal_value(&child) {
+                    Some(func) => (
+                        SymbolKind::Function,
+                        extract_signature(node, &func.child_by_field_name("body"), source),
+                        Some(types_of(&func, source)),
+                    ),
+                    None => (kind.clone(), extract_declaration_line(&body), None),
+                };
                 out.push(ExtractedSymbol {
+                    visibility: visibility_of(&name),
                     name,
                     qualified_name,
-                    kind: kind.clone(),
+                    kind,
                     signature,
                     body,
                     start_line: node.start_position().row + 1,
                     end_line: node.end_position().row + 1,
                     children: Vec::new(),
+                    modifiers: Vec::new(),
+                    types,
+                    attributes: directives_of(node, source),
                 });
             }
         }
     }
 }
 
+/// The `func` literal a single-valued var spec is initialised with.
+fn func_literal_value<'t>(spec: &Node<'t>) -> Option<Node<'t>> {
+    let values = spec.child_by_field_name("value")?;
+    let value = values.named_child(0).filter(|_| values.named_child_count() == 1)?;
+    (value.kind() == "func_literal").then_some(value)
+}
+
+/// Parameter and result types of a function or method (the receiver is not
+/// a parameter). `a, b int` yields two params; a variadic type keeps `...`.
+fn types_of(node: &Node, source: &[u8]) -> TypeSignature {
+    let mut params = Vec::new();
+    if let Some(list) = node.child_by_field_name("parameters") {
+        let mut cursor = list.walk();
+        for decl in list.named_children(&mut cursor) {
+            let type_name = decl.child_by_field_name("type").map(|t| {
+                let ty = node_text(&t, source);
+                if decl.kind() == "variadic_parameter_declaration" {
+                    format!("...{ty}")
+                } else {
+                    ty
+                }
+            });
+            let mut names_cursor = decl.walk();
+            let names: Vec<String> = decl
+                .children_by_field_name("name", &mut names_cursor)
+                .map(|n| node_text(&n, source))
+                .collect();
+            if names.is_empty() {
+                params.push(Param { name: String::new(), type_name });
+            } else {
+                for name in names {
+                    params.push(Param { name, type_name: type_name.clone() });
+                }
+            }
+        }
+    }
+    let returns = node
+        .child_by_field_name("result")
+        .map(|r| node_text(&r, source));
+    TypeSignature { params, returns }
+}
+
+/// The file's `//go:build` constraint, which applies to every declaration
+/// in it. Constraints must appear before the package clause.
+fn build_constraint(root: &Node, source: &[u8]) -> Option<String> {
+    let mut cursor = root.walk();
+    let result = root
+        .children(&mut cursor)
+        .take_while(|c| c.kind() == "comment")
+        .map(|c| node_text(&c, source))
+        .find(|text| text.starts_with("//go:build "));
+    result
+}
+
+/// `//go:` directives (`//go:noinline`, `//go:generate ...`) in the comment
+/// block directly above a declaration.
+fn directives_of(node: &Node, source: &[u8]) -> Vec<String> {
+    let mut directives = Vec::new();
+    let mut row = node.start_position().row;
+    let mut prev = node.prev_named_sibling();
+    while let Some(comment) = prev {
+        if comment.kind() != "comment" || comment.end_position().row + 1 != row {
+            break;
+        }
+        let text = node_text(&comment, source);
+        if text.starts_with("//go:") {
+            directives.push(text.trim_end().to_string());
+        }
+        row = comment.start_position().row;
+        prev = comment.prev_named_sibling();
+    }
+    directives.reverse();
+    directives
+}
+
+/// Go exports exactly the identifiers that start with an uppercase letter.
+fn visibility_of(name: &str) -> Visibility {
+    if name.chars().next().is_some_and(char::is_uppercase) {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    }
+}
+
 /// Build a signature from everything before the body block.
 /// For `func HandleRequest(w http.ResponseWriter, r *http.Request) {`,
 /// the signature is `func HandleRequest(w http.ResponseWriter, r *http.Request)`.
@@ -240,7 +385,7 @@ fn extract_signature(node: &Node, body_node: &Option<Node>, source: &[u8]) -> St
         Some(body) => {
             let start = node.start_byte();
             let end = body.start_byte();
-            let raw = &source[start..end];
+            let raw = source.get(start..end).unwrap_or_default();
             String::from_utf8_lossy(raw).trim().to_string()
         }
         None => node_text(node, source),
@@ -256,13 +401,72 @@ fn extract_declaration_line(body: &str) -> String {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Interface method sets
+// ---------------------------------------------------------------------------
+
+/// What a type must provide to satisfy an interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceMembers {
+    /// Names of the methods it declares.
+    pub methods: Vec<String>,
+    /// Interfaces it embeds, by bare name; qualified (`io.Reader`) as written.
+    pub embedded: Vec<String>,
+}
+
+/// Members of interface `name` declared in `decl`, the text of its `type`
+/// declaration. None when `decl` doesn't declare that interface, or when the
+/// interface is a constraint (`~int | ~string`) that no method set satisfies.
+pub fn interface_members(decl: &str, name: &str) -> Option<InterfaceMembers> {
+    let source = format!("package p\n{decl}\n");
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tree_sitter_go::LANGUAGE.into()).ok()?;
+    let tree = parser.parse(source.as_bytes(), None)?;
+    let source = source.as_bytes();
+
+    let mut stack = vec![tree.root_node()];
+    let mut spec = None;
+    while let Some(node) = stack.pop() {
+        if node.kind() == "type_spec"
+            && node.child_by_field_name("name").is_some_and(|n| node_text(&n, source) == name)
+        {
+            spec = Some(node);
+            break;
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.named_children(&mut cursor));
+    }
+    let interface = spec?.child_by_field_name("type").filter(|t| t.kind() == "interface_type")?;
+
+    let mut members = InterfaceMembers::default();
+    let mut cursor = interface.walk();
+    for elem in interface.named_children(&mut cursor) {
+        match elem.kind() {
+            "method_elem" => members.methods.push(node_text(&elem.child_by_field_name("name")?, source)),
+            "type_elem" => {
+                if elem.named_child_count() != 1 {
+                    return None;
+                }
+                let embedded = elem.named_child(0)?;
+                match embedded.kind() {
+                    "type_identifier" | "qualified_type" => members.embedded.push(node_text(&embedded, source)),
+                    _ => return None,
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(members)
+}
+
 // ---------------------------------------------------------------------------
 // Reference extraction
 // ---------------------------------------------------------------------------
 
 /// Walk the entire tree collecting call references. For each call_expression,
 /// figure out which enclosing function/method it lives in, and record
-/// (from_symbol, to_name, "calls").
+/// (from_symbol, to_name, "calls"). Calls through the enclosing method's
+/// receiver (`s.flush()` in a `Server` method) name the receiver type.
 fn collect_references(
     root: &Node,
     source: &[u8],
@@ -270,16 +474,34 @@ fn collect_references(
 ) {
     let mut stack: Vec<Node> = vec![*root];
     while let Some(node) = stack.pop() {
-        if node.kind() == "call_expression" {
-            if let Some(callee) = extract_callee(&node, source) {
-                let from = find_enclosing_function(&node, source)
-                    .unwrap_or_default();
-                refs.push(ExtractedReference {
-                    from_symbol: from,
-                    to_name: callee,
-                    kind: "calls".to_string(),
-                });
-            }
+        let call = match node.kind() {
+            "call_expression" => extract_callee(&node, source).map(|callee| {
+                let args = node
+                    .child_by_field_name("arguments")
+                    .map(|a| call_arguments(&a, argument_kind));
+                (callee, args)
+            }),
+            // `Sum[float64](ys)` parses as a conversion to a generic type.
+            "type_conversion_expression" => node
+                .child_by_field_name("type")
+                .filter(|t| t.kind() == "generic_type")
+                .and_then(|t| t.child_by_field_name("type"))
+                .and_then(|t| type_name(&t, source))
+                .map(|callee| {
+                    let args = node.child_by_field_name("operand").map(|o| vec![argument_kind(&o)]);
+                    (callee, args)
+                }),
+            _ => None,
+        };
+        if let Some((callee, args)) = call {
+            let from = find_enclosing_function(&node, source)
+                .unwrap_or_default();
+            refs.push(ExtractedReference {
+                from_symbol: from,
+                to_name: callee,
+                kind: "calls".to_string(),
+                args,
+            });
         }
         // Push children in reverse order so we visit left-to-right
         let mut cursor = node.walk();
@@ -290,40 +512,311 @@ fn collect_references(
     }
 }
 
+/// Collect each `import_spec` (`"net/http"`, `chi "github.com/go-chi/chi/v5"`)
+/// as an import reference.
+fn collect_import_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedReference>) {
+    let mut cursor = root.walk();
+    for decl in root.children(&mut cursor).filter(|c| c.kind() == "import_declaration") {
+        let mut stack = vec![decl];
+        while let Some(node) = stack.pop() {
+            if node.kind() == "import_spec" {
+                refs.push(ExtractedReference {
+                    from_symbol: String::new(),
+                    to_name: node_text(&node, source),
+                    kind: "imports".to_string(),
+                    args: None,
+                });
+                continue;
+            }
+            let mut inner = node.walk();
+            stack.extend(node.children(&mut inner));
+        }
+    }
+}
+
+/// Kind of one call argument (see `ExtractedReference::args`).
+fn argument_kind(arg: &Node) -> String {
+    let kind = match arg.kind() {
+        "interpreted_string_literal" | "raw_string_literal" | "rune_literal" => "string",
+        "int_literal" | "float_literal" | "imaginary_literal" => "number",
+        "true" | "false" => "bool",
+        "nil" => "null",
+        "composite_literal" => match arg.child_by_field_name("type").map(|t| t.kind()) {
+            Some("slice_type" | "array_type" | "implicit_length_array_type") => "array",
+            _ => "object",
+        },
+        "func_literal" => "function",
+        "variadic_argument" => "spread",
+        "unary_expression" => {
+            return match arg.child_by_field_name("operand") {
+                // `-1`, `&Config{...}`
+                Some(operand) if arg.child_by_field_name("operator").is_some_and(|o| matches!(o.kind(), "-" | "&")) => {
+                    argument_kind(&operand)
+                }
+                _ => "expr".to_string(),
+            }
+        }
+        "parenthesized_expression" => {
+            return arg.named_child(0).map_or_else(|| "expr".to_string(), |inner| argument_kind(&inner))
+        }
+        _ => "expr",
+    };
+    kind.to_string()
+}
+
 /// Extract the callee name from a call_expression node.
-/// Handles `Println(...)` and `fmt.Println(...)` (strips the package prefix).
+/// Handles `Println(...)` and `fmt.Println(...)` (strips the package prefix),
+/// and explicit instantiations such as `New[T]()`. A call through an index
+/// that doesn't read as a type argument (`handlers[i]()`) names nothing.
 fn extract_callee(node: &Node, source: &[u8]) -> Option<String> {
-    let func_node = node.child_by_field_name("function")?;
+    callee_name(&node.child_by_field_name("function")?, source)
+}
+
+fn callee_name(func_node: &Node, source: &[u8]) -> Option<String> {
     match func_node.kind() {
-        "identifier" => Some(node_text(&func_node, source)),
-        "selector_expression" => {
-            // e.g. fmt.Println — extract the field (right-hand side)
-            let field = func_node.child_by_field_name("field")?;
-            Some(node_text(&field, source))
+        "identifier" => Some(node_text(func_node, source)),
+        "selector_expression" => selector_target(func_node, source),
+        "index_expression" => {
+            if !is_type_argument(&func_node.child_by_field_name("index")?, source) {
+                return None;
+            }
+            callee_name(&func_node.child_by_field_name("operand")?, source)
         }
+        "type_instantiation_expression" => type_name(&func_node.child_by_field_name("type")?, source),
+        // `go func() {...}()` calls nothing by name.
+        "func_literal" => None,
         _ => {
             // Fallback: grab the raw text
-            Some(node_text(&func_node, source))
+            Some(node_text(func_node, source))
         }
     }
 }
 
-/// Walk up from `node` to find the nearest enclosing function_declaration or
-/// method_declaration, and return its name.
-fn find_enclosing_function(node: &Node, source: &[u8]) -> Option<String> {
+/// Whether an index expression reads as a type argument: a type literal
+/// (`[]int`, `*T`), a predeclared type, or a capitalised name (`User`, `T`,
+/// `pkg.Item`).
+fn is_type_argument(node: &Node, source: &[u8]) -> bool {
+    const PREDECLARED: &[&str] = &[
+        "any", "bool", "byte", "comparable", "complex64", "complex128", "error", "float32", "float64",
+        "int", "int8", "int16", "int32", "int64", "rune", "string",
+        "uint", "uint8", "uint16", "uint32", "uint64", "uintptr",
+    ];
+    let type_like = |name: &str| {
+        PREDECLARED.contains(&name) || name.starts_with(|c: char| c.is_uppercase())
+    };
+    match node.kind() {
+        "identifier" | "type_identifier" => type_like(&node_text(node, source)),
+        "selector_expression" => node
+            .child_by_field_name("field")
+            .is_some_and(|f| node_text(&f, source).starts_with(|c: char| c.is_uppercase())),
+        kind => kind.ends_with("_type"),
+    }
+}
+
+/// `List` for `List`, `Do` for `pkg.Do`.
+fn type_name(node: &Node, source: &[u8]) -> Option<String> {
+    match node.kind() {
+        "type_identifier" => Some(node_text(node, source)),
+        "qualified_type" => Some(node_text(&node.child_by_field_name("name")?, source)),
+        _ => None,
+    }
+}
+
+/// The field of a selector (`fmt.Println` → `Println`), qualified with the
+/// receiver type when the operand is the enclosing method's receiver
+/// (`s.flush` in `func (s *Server) ...` → `Server.flush`).
+fn selector_target(selector: &Node, source: &[u8]) -> Option<String> {
+    let field = node_text(&selector.child_by_field_name("field")?, source);
+    let operand = selector.child_by_field_name("operand").filter(|o| o.kind() == "identifier");
+    let receiver = enclosing_declaration(selector).and_then(|decl| receiver_binding(&decl, source));
+    match (operand, receiver) {
+        (Some(operand), Some((var, recv))) if node_text(&operand, source) == var => Some(format!("{recv}.{field}")),
+        _ => Some(field),
+    }
+}
+
+/// The receiver's variable and type name: `(s *Server)` → ("s", "Server").
+fn receiver_binding(method: &Node, source: &[u8]) -> Option<(String, String)> {
+    let receiver = method.child_by_field_name("receiver")?;
+    let decl = receiver.named_child(0)?;
+    let var = node_text(&decl.child_by_field_name("name")?, source);
+    Some((var, extract_receiver_type(method, source)?))
+}
+
+/// The package-level `var` or `const` spec containing `node`.
+fn enclosing_spec<'t>(node: &Node<'t>) -> Option<Node<'t>> {
     let mut current = node.parent();
     while let Some(n) = current {
-        match n.kind() {
-            "function_declaration" | "method_declaration" => {
-                let name_node = n.child_by_field_name("name")?;
-                return Some(node_text(&name_node, source));
-            }
-            _ => current = n.parent(),
+        if matches!(n.kind(), "var_spec" | "const_spec") {
+            return Some(n);
         }
+        current = n.parent();
     }
     None
 }
 
+fn enclosing_declaration<'t>(node: &Node<'t>) -> Option<Node<'t>> {
+    let mut current = node.parent();
+    while let Some(n) = current {
+        if matches!(n.kind(), "function_declaration" | "method_declaration") {
+            return Some(n);
+        }
+        current = n.parent();
+    }
+    None
+}
+
+/// Walk up from `node` to find the nearest enclosing function_declaration or
+/// method_declaration, and return its symbol name (`Server.Start` for methods).
+/// Outside any declaration, calls belong to the package-level `var` or
+/// `const` they initialise, so closures in `var handler = func() {...}` are
+/// attributed to `handler`.
+fn find_enclosing_function(node: &Node, source: &[u8]) -> Option<String> {
+    let Some(decl) = enclosing_declaration(node) else {
+        let spec = enclosing_spec(node)?;
+        return Some(node_text(&spec.child_by_field_name("name")?, source));
+    };
+    let name = node_text(&decl.child_by_field_name("name")?, source);
+    match extract_receiver_type(&decl, source).filter(|_| decl.kind() == "method_declaration") {
+        Some(recv) => Some(format!("{recv}.{name}")),
+        None => Some(name),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Route extraction
+// ---------------------------------------------------------------------------
+
+/// Gin/Echo (`r.GET("/users", list)`), chi (`r.Get(...)`), net/http and
+/// gorilla/mux (`HandleFunc("/users", list)`, optionally `.Methods("GET")`).
+/// Go 1.22 patterns (`"GET /users/{id}"`) carry their method in the path.
+fn collect_routes(root: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "call_expression" {
+            route_call(&node, source, routes);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    routes.sort_by_key(|r| r.line);
+}
+
+fn route_call(node: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    let Some(function) = node.child_by_field_name("function").filter(|f| f.kind() == "selector_expression") else {
+        return;
+    };
+    let Some(field) = function.child_by_field_name("field").map(|f| node_text(&f, source)) else {
+        return;
+    };
+    let mut methods = match field.as_str() {
+        "Any" | "Handle" | "HandleFunc" => vec!["ANY".to_string()],
+        f if HTTP_METHODS.contains(&f.to_lowercase().as_str()) => vec![f.to_uppercase()],
+        _ => return,
+    };
+
+    let Some(args) = node.child_by_field_name("arguments") else {
+        return;
+    };
+    let mut cursor = args.walk();
+    let args: Vec<Node> = args.named_children(&mut cursor).collect();
+    if args.len() < 2 {
+        return;
+    }
+    let Some(mut path) = string_value(&args[0], source) else {
+        return;
+    };
+    // Go 1.22 ServeMux: "GET /users/{id}"
+    if let Some((method, rest)) = path.split_once(' ') {
+        if HTTP_METHODS.contains(&method.to_lowercase().as_str()) {
+            methods = vec![method.to_string()];
+            path = rest.trim().to_string();
+        }
+    }
+    if !path.starts_with('/') {
+        return;
+    }
+    // gorilla/mux: r.HandleFunc(...).Methods("GET", "POST")
+    if let Some(chained) = methods_call(node, source) {
+        methods = chained;
+    }
+
+    let last = args[args.len() - 1];
+    let handler = match last.kind() {
+        "identifier" => node_text(&last, source),
+        "selector_expression" => selector_target(&last, source).unwrap_or_default(),
+        _ => String::new(),
+    };
+    for method in methods {
+        routes.push(ExtractedRoute {
+            method,
+            path: path.clone(),
+            handler: handler.clone(),
+            line: args[0].start_position().row + 1,
+        });
+    }
+}
+
+/// String arguments of a `.Methods(...)` call chained onto `node`.
+fn methods_call(node: &Node, source: &[u8]) -> Option<Vec<String>> {
+    let selector = node.parent().filter(|p| p.kind() == "selector_expression")?;
+    let field = selector.child_by_field_name("field")?;
+    if node_text(&field, source) != "Methods" {
+        return None;
+    }
+    let call = selector.parent().filter(|p| p.kind() == "call_expression")?;
+    let args = call.child_by_field_name("arguments")?;
+    let mut cursor = args.walk();
+    let methods: Vec<String> = args
+        .named_children(&mut cursor)
+        .filter_map(|a| string_value(&a, source))
+        .map(|m| m.to_uppercase())
+        .collect();
+    (!methods.is_empty()).then_some(methods)
+}
+
+/// Contents of an interpreted ("...") or raw (`...`) string literal.
+fn string_value(node: &Node, source: &[u8]) -> Option<String> {
+    match node.kind() {
+        "interpreted_string_literal" => Some(node_text(node, source).trim_matches('"').to_string()),
+        "raw_string_literal" => Some(node_text(node, source).trim_matches('`').to_string()),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Error-site extraction
+// ---------------------------------------------------------------------------
+
+/// Assignments that drop an error: `x, _ := f()`, `_ = f()`, `_ = err`.
+/// The error is assumed to be the last result, per Go convention.
+fn collect_error_sites(root: &Node, source: &[u8], sites: &mut Vec<ErrorSite>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        if matches!(node.kind(), "assignment_statement" | "short_var_declaration") {
+            let last_left = node
+                .child_by_field_name("left")
+                .and_then(|l| l.named_child(l.named_child_count().saturating_sub(1) as u32));
+            let right = node.child_by_field_name("right");
+            let right_single = right.filter(|r| r.named_child_count() == 1).and_then(|r| r.named_child(0));
+            let blank = last_left.is_some_and(|l| node_text(&l, source) == "_");
+            let drops_error = right_single.is_some_and(|r| {
+                r.kind() == "call_expression" || (r.kind() == "identifier" && node_text(&r, source) == "err")
+            });
+            if blank && drops_error {
+                sites.push(error_site("ignored_error", &node, source));
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    sites.sort_by_key(|s| s.line);
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
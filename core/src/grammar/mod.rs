@@ -5,6 +5,8 @@ pub mod typescript;
 
 use std::path::Path;
 
+use serde::{Deserialize, Serialize};
+
 // ---------------------------------------------------------------------------
 // Symbol kinds
 // ---------------------------------------------------------------------------
@@ -40,10 +42,55 @@ impl SymbolKind {
     }
 }
 
+/// Declared visibility of a symbol, normalized across languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Exported: Go capitalized names, Rust `pub`, TS `export`/public members,
+    /// Python names without a leading underscore.
+    Public,
+    /// Visible within the crate or a parent module (`pub(crate)`, `pub(super)`).
+    Internal,
+    /// TS `protected` class members.
+    Protected,
+    Private,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Internal => "internal",
+            Visibility::Protected => "protected",
+            Visibility::Private => "private",
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Extracted data
 // ---------------------------------------------------------------------------
 
+/// One declared parameter of a function or method.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Param {
+    /// Name as written, including rest/splat markers (`...args`, `*args`).
+    /// Empty for unnamed Go parameters.
+    pub name: String,
+    /// Declared type; None when the parameter is untyped.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+}
+
+/// Parameter and return types of a callable, stored alongside the raw
+/// signature so tools can match on types without re-parsing it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeSignature {
+    pub params: Vec<Param>,
+    /// Declared return type (Go multiple results keep their parentheses).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub returns: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ExtractedSymbol {
     pub name: String,
@@ -54,6 +101,41 @@ pub struct ExtractedSymbol {
     pub start_line: usize,
     pub end_line: usize,
     pub children: Vec<ExtractedSymbol>,
+    pub visibility: Visibility,
+    /// Declaration modifiers in source order: `async`, `static`, `const`,
+    /// `unsafe`, `extern`, `abstract`, `readonly`, `default`, ...
+    pub modifiers: Vec<String>,
+    /// Parameter and return types; None for symbols that are not callable.
+    pub types: Option<TypeSignature>,
+    /// Attributes and annotations as written: `#[derive(Serialize)]`,
+    /// `@app.route("/users")`, `//go:build linux`.
+    pub attributes: Vec<String>,
+}
+
+/// An HTTP route registration: `method path` handled by `handler`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractedRoute {
+    /// Upper-case HTTP method, or `ANY` when the registration accepts all.
+    pub method: String,
+    /// Path as registered (`/users/:id`, `/users/{id}`); prefixes applied
+    /// through router groups held in variables are not resolved.
+    pub path: String,
+    /// Name of the handler symbol; empty for inline closures.
+    pub handler: String,
+    /// 1-based line of the registration.
+    pub line: usize,
+}
+
+/// An error that is discarded or escalated to a panic instead of handled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorSite {
+    /// `unwrap`, `expect`, `ignored_error`, `bare_except`,
+    /// `swallowed_exception`, or `empty_catch`.
+    pub kind: String,
+    /// 1-based line.
+    pub line: usize,
+    /// The source line, trimmed.
+    pub snippet: String,
 }
 
 #[derive(Debug, Clone)]
@@ -61,6 +143,12 @@ pub struct ExtractedReference {
     pub from_symbol: String,
     pub to_name: String,
     pub kind: String, // "calls", "type_ref", "imports"
+    /// Call sites only: the kind of each argument, in order — `string`,
+    /// `number`, `bool`, `null`, `array`, `object`, `function`, `spread`
+    /// or `expr`. Python keyword arguments are `name=kind`. None when the
+    /// argument list is not visible (macros, tagged templates) or for
+    /// non-call references.
+    pub args: Option<Vec<String>>,
 }
 
 // ---------------------------------------------------------------------------
@@ -76,6 +164,16 @@ pub trait Grammar: Send + Sync {
         source: &[u8],
         tree: &tree_sitter::Tree,
     ) -> Vec<ExtractedReference>;
+    /// HTTP route registrations for the web frameworks this language's
+    /// grammar recognizes. Defaults to none.
+    fn extract_routes(&self, _source: &[u8], _tree: &tree_sitter::Tree) -> Vec<ExtractedRoute> {
+        Vec::new()
+    }
+    /// Places where an error is discarded or turned into a panic. Defaults
+    /// to none.
+    fn extract_error_sites(&self, _source: &[u8], _tree: &tree_sitter::Tree) -> Vec<ErrorSite> {
+        Vec::new()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -125,3 +223,59 @@ impl Default for GrammarRegistry {
         Self::new()
     }
 }
+
+// ---------------------------------------------------------------------------
+// Shared helpers
+// ---------------------------------------------------------------------------
+
+/// HTTP verbs accepted as route-registration method names, lower-case.
+pub(crate) const HTTP_METHODS: &[&str] = &["get", "post", "put", "delete", "patch", "head", "options"];
+
+/// Join a controller/scope prefix and a route path with exactly one `/`
+/// between them and a leading `/`.
+pub(crate) fn join_route_path(prefix: &str, path: &str) -> String {
+    let parts: Vec<&str> = [prefix, path]
+        .iter()
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty())
+        .collect();
+    format!("/{}", parts.join("/"))
+}
+
+/// An `ErrorSite` at `node`, with its first source line as the snippet.
+pub(crate) fn error_site(kind: &str, node: &tree_sitter::Node, source: &[u8]) -> ErrorSite {
+    let start = node.start_byte();
+    let line_start = source[..start].iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    let line_end = source[start..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(source.len(), |i| start + i);
+    ErrorSite {
+        kind: kind.to_string(),
+        line: node.start_position().row + 1,
+        snippet: String::from_utf8_lossy(&source[line_start..line_end]).trim().to_string(),
+    }
+}
+
+/// Anonymous keyword children of `node` that appear in `keywords`, in source
+/// order (e.g. `static` and `async` on a TS method).
+pub(crate) fn keyword_modifiers(node: &tree_sitter::Node, keywords: &[&str]) -> Vec<String> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|c| !c.is_named() && keywords.contains(&c.kind()))
+        .map(|c| c.kind().to_string())
+        .collect()
+}
+
+/// Classify each argument of a call's argument list with `kind_of`, skipping
+/// comments. See `ExtractedReference::args` for the kinds.
+pub(crate) fn call_arguments(
+    args: &tree_sitter::Node,
+    kind_of: impl Fn(&tree_sitter::Node) -> String,
+) -> Vec<String> {
+    let mut cursor = args.walk();
+    args.named_children(&mut cursor)
+        .filter(|c| !c.is_extra())
+        .map(|c| kind_of(&c))
+        .collect()
+}
@@ -1,6 +1,6 @@
 use tree_sitter::{Language, Node, Tree};
 
-use super::{ExtractedReference, ExtractedSymbol, Grammar, SymbolKind};
+use super::{call_arguments, error_site, keyword_modifiers, ErrorSite, ExtractedReference, ExtractedRoute, ExtractedSymbol, Grammar, Param, SymbolKind, TypeSignature, Visibility, HTTP_METHODS};
 
 pub struct PythonGrammar;
 
@@ -27,6 +27,18 @@ impl Grammar for PythonGrammar {
         collect_import_references(&root, source, &mut refs);
         refs
     }
+
+    fn extract_routes(&self, source: &[u8], tree: &Tree) -> Vec<ExtractedRoute> {
+        let mut routes = Vec::new();
+        collect_routes(&tree.root_node(), source, &mut routes);
+        routes
+    }
+
+    fn extract_error_sites(&self, source: &[u8], tree: &Tree) -> Vec<ErrorSite> {
+        let mut sites = Vec::new();
+        collect_error_sites(&tree.root_node(), source, &mut sites);
+        sites
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -36,22 +48,40 @@ impl Grammar for PythonGrammar {
 fn extract_top_level_symbols(node: &Node, source: &[u8], out: &mut Vec<ExtractedSymbol>) {
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
-        match child.kind() {
-            "function_definition" => {
-                if let Some(sym) = extract_function(&child, source) {
-                    out.push(sym);
-                }
-            }
-            "class_definition" => {
-                if let Some(sym) = extract_class(&child, source) {
-                    out.push(sym);
-                }
-            }
-            _ => {}
+        if let Some(sym) = extract_definition(&child, source) {
+            out.push(sym);
+        }
+    }
+}
+
+/// A top-level `def` or `class`. Decorators are unwrapped into `attributes`.
+fn extract_definition(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
+    match node.kind() {
+        "function_definition" => extract_function(node, source),
+        "class_definition" => extract_class(node, source),
+        "type_alias_statement" => extract_type_alias(node, source),
+        "expression_statement" => extract_lambda(node, source).or_else(|| extract_closure_table(node, source)),
+        "decorated_definition" => {
+            let definition = node.child_by_field_name("definition")?;
+            let mut sym = extract_definition(&definition, source)?;
+            sym.attributes = decorators_of(node, source);
+            Some(sym)
         }
+        _ => None,
     }
 }
 
+/// `@decorator` lines of a `decorated_definition`, in source order.
+fn decorators_of(node: &Node, source: &[u8]) -> Vec<String> {
+    let mut cursor = node.walk();
+    let decorators = node
+        .children(&mut cursor)
+        .filter(|c| c.kind() == "decorator")
+        .map(|c| node_text(&c, source))
+        .collect();
+    decorators
+}
+
 fn extract_function(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
     let name_node = node.child_by_field_name("name")?;
     let name = node_text(&name_node, source);
@@ -67,6 +97,10 @@ fn extract_function(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility: visibility_of(&name_node, source),
+        modifiers: keyword_modifiers(node, &["async"]),
+        types: Some(types_of(node, source)),
+        attributes: Vec::new(),
     })
 }
 
@@ -75,16 +109,26 @@ fn extract_class(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
     let name = node_text(&name_node, source);
     let body = node_text(node, source);
 
-    // Extract methods from the class body block with ClassName::method qualified names
+    // Extract methods from the class body block with ClassName.method qualified names
     let mut children = Vec::new();
     if let Some(body_node) = node.child_by_field_name("body") {
         let mut cursor = body_node.walk();
         for child in body_node.children(&mut cursor) {
-            if child.kind() == "function_definition" {
-                if let Some(mut method) = extract_method(&child, source) {
-                    method.qualified_name = format!("{name}::{}", method.name);
-                    children.push(method);
-                }
+            let method = match child.kind() {
+                "function_definition" => extract_method(&child, source),
+                "decorated_definition" => child
+                    .child_by_field_name("definition")
+                    .filter(|d| d.kind() == "function_definition")
+                    .and_then(|d| extract_method(&d, source))
+                    .map(|mut m| {
+                        m.attributes = decorators_of(&child, source);
+                        m
+                    }),
+                _ => None,
+            };
+            if let Some(mut method) = method {
+                method.qualified_name = format!("{name}.{}", method.name);
+                children.push(method);
             }
         }
     }
@@ -101,9 +145,108 @@ fn extract_class(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children,
+        visibility: visibility_of(&name_node, source),
+        modifiers: Vec::new(),
+        types: None,
+        attributes: Vec::new(),
+    })
+}
+
+/// A PEP 695 `type Pairs[T] = list[tuple[T, T]]`. The name drops the type
+/// parameters; the signature is the whole statement.
+fn extract_type_alias(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
+    let left = node.child_by_field_name("left")?;
+    let mut name_node = left.named_child(0)?;
+    if name_node.kind() == "generic_type" {
+        name_node = name_node.named_child(0)?;
+    }
+    if name_node.kind() != "identifier" {
+        return None;
+    }
+    let name = node_text(&name_node, source);
+    let body = node_text(node, source);
+    Some(ExtractedSymbol {
+        qualified_name: name.clone(),
+        name,
+        kind: SymbolKind::TypeAlias,
+        signature: body.clone(),
+        body,
+        start_line: node.start_position().row + 1,
+        end_line: node.end_position().row + 1,
+        children: Vec::new(),
+        visibility: visibility_of(&name_node, source),
+        modifiers: Vec::new(),
+        types: None,
+        attributes: Vec::new(),
+    })
+}
+
+/// A module-level `handler = lambda event: ...` is a named function.
+fn extract_lambda(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
+    let (name_node, assignment, lambda) = lambda_binding(node)?;
+    let name = node_text(&name_node, source);
+    let body = node_text(node, source);
+    Some(ExtractedSymbol {
+        qualified_name: name.clone(),
+        name,
+        kind: SymbolKind::Function,
+        signature: extract_signature(&assignment, &lambda.child_by_field_name("body"), source),
+        body,
+        start_line: node.start_position().row + 1,
+        end_line: node.end_position().row + 1,
+        children: Vec::new(),
+        visibility: visibility_of(&name_node, source),
+        modifiers: Vec::new(),
+        types: Some(types_of(&lambda, source)),
+        attributes: Vec::new(),
+    })
+}
+
+/// A module-level `handlers = {"a": lambda e: ...}` is a constant that owns
+/// the calls in its lambdas. Assignments without a lambda are not symbols.
+fn extract_closure_table(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
+    let name_node = closure_binding(node)?;
+    let name = node_text(&name_node, source);
+    let body = node_text(node, source);
+    Some(ExtractedSymbol {
+        qualified_name: name.clone(),
+        name,
+        kind: SymbolKind::Const,
+        signature: body.lines().next().unwrap_or_default().trim_end().to_string(),
+        body,
+        start_line: node.start_position().row + 1,
+        end_line: node.end_position().row + 1,
+        children: Vec::new(),
+        visibility: visibility_of(&name_node, source),
+        modifiers: Vec::new(),
+        types: None,
+        attributes: Vec::new(),
     })
 }
 
+/// The name of a `name = ...` statement whose value holds a lambda.
+fn closure_binding<'t>(statement: &Node<'t>) -> Option<Node<'t>> {
+    let assignment = statement.named_child(0).filter(|a| a.kind() == "assignment")?;
+    let name = assignment.child_by_field_name("left").filter(|l| l.kind() == "identifier")?;
+    let mut stack = vec![assignment.child_by_field_name("right")?];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "lambda" {
+            return Some(name);
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.named_children(&mut cursor));
+    }
+    None
+}
+
+/// The name, assignment and lambda of a `name = lambda ...` statement.
+fn lambda_binding<'t>(statement: &Node<'t>) -> Option<(Node<'t>, Node<'t>, Node<'t>)> {
+    let assignment = statement.named_child(0).filter(|a| a.kind() == "assignment")?;
+    let name = assignment.child_by_field_name("left").filter(|l| l.kind() == "identifier")?;
+    let lambda = assignment.child_by_field_name("right").filter(|r| r.kind() == "lambda")?;
+    Some((name, assignment, lambda))
+}
+
 fn extract_method(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
     let name_node = node.child_by_field_name("name")?;
     let name = node_text(&name_node, source);
@@ -119,9 +262,56 @@ fn extract_method(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility: visibility_of(&name_node, source),
+        modifiers: keyword_modifiers(node, &["async"]),
+        types: Some(types_of(node, source)),
+        attributes: Vec::new(),
     })
 }
 
+/// Parameter and return annotations of a `def`. Splat parameters keep their
+/// `*`/`**`; the bare `*` and `/` separators are not parameters.
+fn types_of(node: &Node, source: &[u8]) -> TypeSignature {
+    let mut params = Vec::new();
+    if let Some(list) = node.child_by_field_name("parameters") {
+        let mut cursor = list.walk();
+        for param in list.named_children(&mut cursor) {
+            let type_name = param.child_by_field_name("type").map(|t| node_text(&t, source));
+            let name = match param.kind() {
+                "identifier" | "list_splat_pattern" | "dictionary_splat_pattern" => {
+                    node_text(&param, source)
+                }
+                "default_parameter" | "typed_default_parameter" => param
+                    .child_by_field_name("name")
+                    .map(|n| node_text(&n, source))
+                    .unwrap_or_default(),
+                // The name of a typed_parameter is its first, unlabeled child.
+                "typed_parameter" => param
+                    .named_child(0)
+                    .map(|n| node_text(&n, source))
+                    .unwrap_or_default(),
+                _ => continue,
+            };
+            params.push(Param { name, type_name });
+        }
+    }
+    let returns = node
+        .child_by_field_name("return_type")
+        .map(|r| node_text(&r, source));
+    TypeSignature { params, returns }
+}
+
+/// Python's convention: a leading underscore marks a name private, except
+/// dunders (`__init__`), which are part of the public protocol.
+fn visibility_of(name_node: &Node, source: &[u8]) -> Visibility {
+    let name = node_text(name_node, source);
+    if name.starts_with('_') && !(name.starts_with("__") && name.ends_with("__")) {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    }
+}
+
 /// Build signature from everything before the body block.
 /// For Python, body is a `block` child. The signature includes `def name(params) -> type:`.
 fn extract_signature(node: &Node, body_node: &Option<Node>, source: &[u8]) -> String {
@@ -129,7 +319,7 @@ fn extract_signature(node: &Node, body_node: &Option<Node>, source: &[u8]) -> St
         Some(body) => {
             let start = node.start_byte();
             let end = body.start_byte();
-            let raw = &source[start..end];
+            let raw = source.get(start..end).unwrap_or_default();
             String::from_utf8_lossy(raw).trim().to_string()
         }
         None => node_text(node, source),
@@ -146,10 +336,16 @@ fn collect_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedRefere
         if node.kind() == "call" {
             if let Some(callee) = extract_callee(&node, source) {
                 let from = find_enclosing_function(&node, source).unwrap_or_default();
+                // `sum(x for x in xs)` passes one generator.
+                let args = node.child_by_field_name("arguments").map(|a| match a.kind() {
+                    "argument_list" => call_arguments(&a, |arg| argument_kind(arg, source)),
+                    _ => vec!["expr".to_string()],
+                });
                 refs.push(ExtractedReference {
                     from_symbol: from,
                     to_name: callee,
                     kind: "calls".to_string(),
+                    args,
                 });
             }
         }
@@ -161,12 +357,50 @@ fn collect_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedRefere
     }
 }
 
+/// Kind of one call argument (see `ExtractedReference::args`); keyword
+/// arguments are `name=kind`.
+fn argument_kind(arg: &Node, source: &[u8]) -> String {
+    let kind = match arg.kind() {
+        "string" | "concatenated_string" => "string",
+        "integer" | "float" => "number",
+        "true" | "false" => "bool",
+        "none" => "null",
+        "list" | "tuple" | "set" => "array",
+        "dictionary" => "object",
+        "lambda" => "function",
+        "list_splat" | "dictionary_splat" => "spread",
+        "keyword_argument" => {
+            let name = arg.child_by_field_name("name").map(|n| node_text(&n, source)).unwrap_or_default();
+            let value = arg
+                .child_by_field_name("value")
+                .map_or_else(|| "expr".to_string(), |v| argument_kind(&v, source));
+            return format!("{name}={value}");
+        }
+        "unary_operator" if arg.child_by_field_name("operator").is_some_and(|o| o.kind() == "-") => {
+            return arg
+                .child_by_field_name("argument")
+                .map_or_else(|| "expr".to_string(), |inner| argument_kind(&inner, source))
+        }
+        "parenthesized_expression" => {
+            return arg.named_child(0).map_or_else(|| "expr".to_string(), |inner| argument_kind(&inner, source))
+        }
+        _ => "expr",
+    };
+    kind.to_string()
+}
+
 /// Extract callee from a `call` node.
-/// Handles `foo()` and `obj.method()`.
+/// Handles `foo()`, `obj.method()` and subscripted generics such as
+/// `Stack[int]()`, whose type arguments are dropped. A call through a
+/// subscript that doesn't read as type arguments (`handlers["a"]()`)
+/// names nothing.
 fn extract_callee(node: &Node, source: &[u8]) -> Option<String> {
-    let func_node = node.child_by_field_name("function")?;
+    callee_name(&node.child_by_field_name("function")?, source)
+}
+
+fn callee_name(func_node: &Node, source: &[u8]) -> Option<String> {
     match func_node.kind() {
-        "identifier" => Some(node_text(&func_node, source)),
+        "identifier" => Some(node_text(func_node, source)),
         "attribute" => {
             // e.g. os.listdir — take the last identifier (the attribute name)
             let mut cursor = func_node.walk();
@@ -178,7 +412,37 @@ fn extract_callee(node: &Node, source: &[u8]) -> Option<String> {
                 .find(|c| c.kind() == "identifier")
                 .map(|n| node_text(n, source))
         }
-        _ => Some(node_text(&func_node, source)),
+        "subscript" => {
+            let mut cursor = func_node.walk();
+            let mut arguments = func_node.children_by_field_name("subscript", &mut cursor);
+            if !arguments.all(|a| is_type_argument(&a, source)) {
+                return None;
+            }
+            callee_name(&func_node.child_by_field_name("value")?, source)
+        }
+        // `(lambda: ...)()` calls nothing by name.
+        "lambda" | "parenthesized_expression" => None,
+        _ => Some(node_text(func_node, source)),
+    }
+}
+
+/// Whether a subscript element reads as a type: a builtin type, a
+/// capitalised name (`T`, `typing.Any`), `None`, or a subscripted one of
+/// those (`list[int]`).
+fn is_type_argument(node: &Node, source: &[u8]) -> bool {
+    const BUILTIN_TYPES: &[&str] =
+        &["int", "float", "complex", "str", "bytes", "bool", "object", "list", "dict", "set", "frozenset", "tuple", "type"];
+    match node.kind() {
+        "identifier" => {
+            let name = node_text(node, source);
+            BUILTIN_TYPES.contains(&name.as_str()) || name.starts_with(|c: char| c.is_uppercase())
+        }
+        "attribute" => node
+            .child_by_field_name("attribute")
+            .is_some_and(|a| node_text(&a, source).starts_with(|c: char| c.is_uppercase())),
+        "none" => true,
+        "subscript" => node.child_by_field_name("value").is_some_and(|v| is_type_argument(&v, source)),
+        _ => false,
     }
 }
 
@@ -193,6 +457,7 @@ fn collect_import_references(root: &Node, source: &[u8], refs: &mut Vec<Extracte
                     from_symbol: String::new(),
                     to_name: text,
                     kind: "imports".to_string(),
+                    args: None,
                 });
             }
             _ => {}
@@ -200,17 +465,178 @@ fn collect_import_references(root: &Node, source: &[u8], refs: &mut Vec<Extracte
     }
 }
 
-/// Walk up to find the nearest enclosing function_definition.
+/// The qualified name of the extracted symbol a call belongs to: the
+/// top-level function (nested functions and lambdas included), the method
+/// as `Class.method`, the class itself for calls in its body, or the
+/// module-level name a lambda is bound to or stored under. Lambdas passed
+/// straight to a module-level call have no owner.
 fn find_enclosing_function(node: &Node, source: &[u8]) -> Option<String> {
+    let mut definitions = Vec::new();
     let mut current = node.parent();
     while let Some(n) = current {
-        if n.kind() == "function_definition" {
-            let name_node = n.child_by_field_name("name")?;
-            return Some(node_text(&name_node, source));
+        match n.kind() {
+            "function_definition" | "class_definition" => definitions.push(n),
+            // Calls in a module-level lambda belong to the name it is bound to.
+            "expression_statement" if definitions.is_empty() && n.parent().is_some_and(|p| p.kind() == "module") => {
+                let name = lambda_binding(&n).map(|(name, _, _)| name).or_else(|| closure_binding(&n));
+                return name.map(|name| node_text(&name, source));
+            }
+            _ => {}
         }
         current = n.parent();
     }
-    None
+    let name_of = |n: &Node| n.child_by_field_name("name").map(|name| node_text(&name, source));
+    let mut outermost = definitions.iter().rev();
+    let top = outermost.next()?;
+    let top_name = name_of(top)?;
+    match outermost.next() {
+        Some(method) if top.kind() == "class_definition" && method.kind() == "function_definition" => {
+            Some(format!("{top_name}.{}", name_of(method)?))
+        }
+        _ => Some(top_name),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Route extraction
+// ---------------------------------------------------------------------------
+
+/// Flask/FastAPI style route decorators on functions:
+/// `@app.route("/users", methods=["GET", "POST"])`, `@router.get("/users/{id}")`,
+/// `@app.api_route("/x", methods=[...])`.
+fn collect_routes(root: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "decorated_definition" {
+            let handler = node
+                .child_by_field_name("definition")
+                .filter(|d| d.kind() == "function_definition")
+                .and_then(|d| d.child_by_field_name("name"))
+                .map(|n| node_text(&n, source));
+            if let Some(handler) = handler {
+                let mut cursor = node.walk();
+                for decorator in node.children(&mut cursor).filter(|c| c.kind() == "decorator") {
+                    route_from_decorator(&decorator, source, &handler, routes);
+                }
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    routes.sort_by_key(|r| r.line);
+}
+
+fn route_from_decorator(
+    decorator: &Node,
+    source: &[u8],
+    handler: &str,
+    routes: &mut Vec<ExtractedRoute>,
+) {
+    let Some(call) = decorator.named_child(0).filter(|c| c.kind() == "call") else {
+        return;
+    };
+    let Some(verb) = call
+        .child_by_field_name("function")
+        .filter(|f| f.kind() == "attribute")
+        .and_then(|f| f.child_by_field_name("attribute"))
+        .map(|a| node_text(&a, source))
+    else {
+        return;
+    };
+    let Some(args) = call.child_by_field_name("arguments") else {
+        return;
+    };
+
+    let mut path = None;
+    let mut methods = Vec::new();
+    let mut cursor = args.walk();
+    for arg in args.named_children(&mut cursor) {
+        match arg.kind() {
+            "string" if path.is_none() => path = Some(string_value(&arg, source)),
+            "keyword_argument" => {
+                let key = arg.child_by_field_name("name").map(|n| node_text(&n, source));
+                let value = arg.child_by_field_name("value");
+                if let (Some("methods"), Some(list)) = (key.as_deref(), value) {
+                    let mut inner = list.walk();
+                    methods.extend(
+                        list.named_children(&mut inner)
+                            .filter(|m| m.kind() == "string")
+                            .map(|m| string_value(&m, source).to_uppercase()),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some(path) = path.filter(|p| p.starts_with('/')) else {
+        return;
+    };
+
+    let methods = match verb.as_str() {
+        "route" | "api_route" if methods.is_empty() => vec!["GET".to_string()],
+        "route" | "api_route" => methods,
+        v if HTTP_METHODS.contains(&v) => vec![v.to_uppercase()],
+        _ => return,
+    };
+    for method in methods {
+        routes.push(ExtractedRoute {
+            method,
+            path: path.clone(),
+            handler: handler.to_string(),
+            line: decorator.start_position().row + 1,
+        });
+    }
+}
+
+/// Contents of a string literal without prefix or quotes.
+fn string_value(node: &Node, source: &[u8]) -> String {
+    let mut cursor = node.walk();
+    let content = node
+        .children(&mut cursor)
+        .find(|c| c.kind() == "string_content")
+        .map(|c| node_text(&c, source))
+        .unwrap_or_default();
+    content
+}
+
+// ---------------------------------------------------------------------------
+// Error-site extraction
+// ---------------------------------------------------------------------------
+
+/// `except:` with no exception type, and handlers whose body is only `pass`.
+fn collect_error_sites(root: &Node, source: &[u8], sites: &mut Vec<ErrorSite>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "except_clause" {
+            let mut cursor = node.walk();
+            let named: Vec<Node> = node
+                .named_children(&mut cursor)
+                .filter(|c| c.kind() != "comment")
+                .collect();
+            let body = named.iter().find(|c| c.kind() == "block");
+            if named.len() == 1 && body.is_some() {
+                sites.push(error_site("bare_except", &node, source));
+            } else if body.is_some_and(|b| is_only_pass(b)) {
+                sites.push(error_site("swallowed_exception", &node, source));
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    sites.sort_by_key(|s| s.line);
+}
+
+fn is_only_pass(block: &Node) -> bool {
+    let mut cursor = block.walk();
+    let statements: Vec<Node> = block
+        .named_children(&mut cursor)
+        .filter(|c| c.kind() != "comment")
+        .collect();
+    matches!(statements[..], [s] if s.kind() == "pass_statement")
 }
 
 // ---------------------------------------------------------------------------
@@ -1,6 +1,6 @@
 use tree_sitter::{Language, Node, Tree};
 
-use super::{ExtractedReference, ExtractedSymbol, Grammar, SymbolKind};
+use super::{call_arguments, error_site, keyword_modifiers, ErrorSite, ExtractedReference, ExtractedRoute, ExtractedSymbol, Grammar, Param, SymbolKind, TypeSignature, Visibility, HTTP_METHODS};
 
 pub struct RustGrammar;
 
@@ -27,6 +27,18 @@ impl Grammar for RustGrammar {
         collect_import_references(&root, source, &mut refs);
         refs
     }
+
+    fn extract_routes(&self, source: &[u8], tree: &Tree) -> Vec<ExtractedRoute> {
+        let mut routes = Vec::new();
+        collect_routes(&tree.root_node(), source, &mut routes);
+        routes
+    }
+
+    fn extract_error_sites(&self, source: &[u8], tree: &Tree) -> Vec<ErrorSite> {
+        let mut sites = Vec::new();
+        collect_error_sites(&tree.root_node(), source, &mut sites);
+        sites
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -101,23 +113,36 @@ fn extract_function(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility: visibility_of(node, source),
+        modifiers: function_modifiers(node),
+        types: Some(types_of(node, source)),
+        attributes: attributes_of(node, source),
     })
 }
 
 /// Extract a named symbol (struct, enum, trait, const, type alias, module).
-/// For structs/enums/traits the name is a `type_identifier` child.
-/// For const/static/type items the name is an `identifier` or `type_identifier` child.
+/// The name is the item's `name` field, so `static CONFIG: Config` is named
+/// `CONFIG` and `struct Wrapper<T>` is named `Wrapper`; type parameters stay
+/// in the signature.
 fn extract_named_symbol(
     node: &Node,
     source: &[u8],
     kind: SymbolKind,
 ) -> Option<ExtractedSymbol> {
-    // Try type_identifier first (struct, enum, trait, type alias), then identifier (const, static, mod)
-    let name = find_child_by_kind(node, "type_identifier")
+    let name = node
+        .child_by_field_name("name")
+        .or_else(|| find_child_by_kind(node, "type_identifier"))
         .or_else(|| find_child_by_kind(node, "identifier"))
         .map(|n| node_text(&n, source))?;
     let body = node_text(node, source);
     let signature = extract_declaration_line(&body);
+    let mut modifiers = Vec::new();
+    if node.kind() == "static_item" {
+        modifiers.push("static".to_string());
+        if find_child_by_kind(node, "mutable_specifier").is_some() {
+            modifiers.push("mut".to_string());
+        }
+    }
     Some(ExtractedSymbol {
         qualified_name: name.clone(),
         name,
@@ -127,15 +152,28 @@ fn extract_named_symbol(
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility: visibility_of(node, source),
+        modifiers,
+        types: None,
+        attributes: attributes_of(node, source),
     })
 }
 
 /// Walk an `impl_item`'s `declaration_list` and extract methods.
 /// Methods are prefixed with the impl type name (e.g. `Config::new`) to
-/// disambiguate identically-named methods across different types.
+/// disambiguate identically-named methods across different types. For
+/// `impl Display for Config` that is the implementing type, not the trait.
+/// Trait-impl methods take no modifier: they are as visible as the type, if
+/// it is declared earlier in the file, and public otherwise.
 fn extract_impl(node: &Node, source: &[u8], out: &mut Vec<ExtractedSymbol>) {
-    let type_name = find_child_by_kind(node, "type_identifier")
-        .map(|n| node_text(&n, source));
+    let type_name = node
+        .child_by_field_name("type")
+        .and_then(|t| base_type_name(&t, source));
+    let trait_visibility = node.child_by_field_name("trait").map(|_| {
+        out.iter()
+            .find(|s| Some(&s.name) == type_name.as_ref() && s.kind != SymbolKind::Method)
+            .map_or(Visibility::Public, |s| s.visibility)
+    });
 
     let decl_list = match find_child_by_kind(node, "declaration_list") {
         Some(d) => d,
@@ -164,18 +202,103 @@ fn extract_impl(node: &Node, source: &[u8], out: &mut Vec<ExtractedSymbol>) {
                 start_line: child.start_position().row + 1,
                 end_line: child.end_position().row + 1,
                 children: Vec::new(),
+                visibility: trait_visibility.unwrap_or_else(|| visibility_of(&child, source)),
+                modifiers: function_modifiers(&child),
+                types: Some(types_of(&child, source)),
+                attributes: attributes_of(&child, source),
             });
         }
     }
 }
 
+/// The bare name of a type: `Wrapper<T, N>`, `crate::store::Cache<T>` and
+/// `&'a Config` become `Wrapper`, `Cache` and `Config`.
+fn base_type_name(node: &Node, source: &[u8]) -> Option<String> {
+    match node.kind() {
+        "type_identifier" | "primitive_type" => Some(node_text(node, source)),
+        "generic_type" | "reference_type" | "pointer_type" => {
+            base_type_name(&node.child_by_field_name("type")?, source)
+        }
+        "scoped_type_identifier" => Some(node_text(&node.child_by_field_name("name")?, source)),
+        _ => None,
+    }
+}
+
+/// Parameter and return types of a `fn`. A `self` receiver is recorded with
+/// its written form (`&mut self`) as the type.
+fn types_of(node: &Node, source: &[u8]) -> TypeSignature {
+    let mut params = Vec::new();
+    if let Some(list) = node.child_by_field_name("parameters") {
+        let mut cursor = list.walk();
+        for param in list.named_children(&mut cursor) {
+            match param.kind() {
+                "self_parameter" => params.push(Param {
+                    name: "self".to_string(),
+                    type_name: Some(node_text(&param, source)),
+                }),
+                "parameter" => params.push(Param {
+                    name: param
+                        .child_by_field_name("pattern")
+                        .map(|p| node_text(&p, source))
+                        .unwrap_or_default(),
+                    type_name: param.child_by_field_name("type").map(|t| node_text(&t, source)),
+                }),
+                _ => {}
+            }
+        }
+    }
+    let returns = node
+        .child_by_field_name("return_type")
+        .map(|r| node_text(&r, source));
+    TypeSignature { params, returns }
+}
+
+/// Outer attributes (`#[derive(Debug)]`, `#[cfg(test)]`) above an item, in
+/// source order. Doc and plain comments between them are skipped.
+fn attributes_of(node: &Node, source: &[u8]) -> Vec<String> {
+    let mut attributes = Vec::new();
+    let mut prev = node.prev_named_sibling();
+    while let Some(sibling) = prev {
+        match sibling.kind() {
+            "attribute_item" => attributes.push(node_text(&sibling, source)),
+            "line_comment" | "block_comment" => {}
+            _ => break,
+        }
+        prev = sibling.prev_named_sibling();
+    }
+    attributes.reverse();
+    attributes
+}
+
+/// Declared visibility: `pub` is public, restricted forms (`pub(crate)`,
+/// `pub(super)`, `pub(in path)`) are internal, no modifier is private.
+fn visibility_of(node: &Node, source: &[u8]) -> Visibility {
+    match find_child_by_kind(node, "visibility_modifier") {
+        Some(v) if node_text(&v, source) == "pub" => Visibility::Public,
+        Some(_) => Visibility::Internal,
+        None => Visibility::Private,
+    }
+}
+
+/// `async`, `const`, `unsafe` and `extern` from a fn's `function_modifiers`.
+fn function_modifiers(node: &Node) -> Vec<String> {
+    let Some(mods) = find_child_by_kind(node, "function_modifiers") else {
+        return Vec::new();
+    };
+    let mut out = keyword_modifiers(&mods, &["async", "const", "unsafe", "default"]);
+    if find_child_by_kind(&mods, "extern_modifier").is_some() {
+        out.push("extern".to_string());
+    }
+    out
+}
+
 /// Build a signature from everything before the body block.
 fn extract_signature(node: &Node, body_node: &Option<Node>, source: &[u8]) -> String {
     match body_node {
         Some(body) => {
             let start = node.start_byte();
             let end = body.start_byte();
-            let raw = &source[start..end];
+            let raw = source.get(start..end).unwrap_or_default();
             String::from_utf8_lossy(raw).trim().to_string()
         }
         None => node_text(node, source),
@@ -202,10 +325,14 @@ fn collect_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedRefere
         if node.kind() == "call_expression" {
             if let Some(callee) = extract_callee(&node, source) {
                 let from = find_enclosing_function(&node, source).unwrap_or_default();
+                let args = node
+                    .child_by_field_name("arguments")
+                    .map(|a| call_arguments(&a, argument_kind));
                 refs.push(ExtractedReference {
                     from_symbol: from,
                     to_name: callee,
                     kind: "calls".to_string(),
+                    args,
                 });
             }
         }
@@ -218,6 +345,7 @@ fn collect_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedRefere
                     from_symbol: from,
                     to_name: callee,
                     kind: "calls".to_string(),
+                    args: None,
                 });
             }
         }
@@ -229,12 +357,39 @@ fn collect_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedRefere
     }
 }
 
+/// Kind of one call argument (see `ExtractedReference::args`).
+fn argument_kind(arg: &Node) -> String {
+    let kind = match arg.kind() {
+        "string_literal" | "raw_string_literal" | "char_literal" => "string",
+        "integer_literal" | "float_literal" => "number",
+        "boolean_literal" => "bool",
+        "array_expression" => "array",
+        "struct_expression" => "object",
+        "closure_expression" => "function",
+        // `&[1, 2]`, `&mut buf`, `-1`
+        "reference_expression" | "unary_expression" | "parenthesized_expression" => {
+            if arg.kind() == "unary_expression" && arg.child(0).is_none_or(|op| op.kind() != "-") {
+                return "expr".to_string();
+            }
+            let mut cursor = arg.walk();
+            let inner = arg.named_children(&mut cursor).last();
+            return inner.map_or_else(|| "expr".to_string(), |inner| argument_kind(&inner));
+        }
+        _ => "expr",
+    };
+    kind.to_string()
+}
+
 /// Extract the callee name from a `call_expression`.
-/// Handles `foo()`, `Foo::bar()`, `self.method()`.
+/// Handles `foo()`, `Foo::bar()`, `self.method()` and turbofish calls such
+/// as `parse::<i32>()`, whose type arguments are dropped.
 fn extract_callee(node: &Node, source: &[u8]) -> Option<String> {
-    let func_node = node.child_by_field_name("function")?;
+    callee_name(&node.child_by_field_name("function")?, source)
+}
+
+fn callee_name(func_node: &Node, source: &[u8]) -> Option<String> {
     match func_node.kind() {
-        "identifier" => Some(node_text(&func_node, source)),
+        "identifier" => Some(node_text(func_node, source)),
         "scoped_identifier" => {
             // e.g. String::from — take the last identifier
             let mut last = None;
@@ -248,10 +403,13 @@ fn extract_callee(node: &Node, source: &[u8]) -> Option<String> {
         }
         "field_expression" => {
             // e.g. self.method — take the field_identifier
-            find_child_by_kind(&func_node, "field_identifier")
+            find_child_by_kind(func_node, "field_identifier")
                 .map(|n| node_text(&n, source))
         }
-        _ => Some(node_text(&func_node, source)),
+        "generic_function" => callee_name(&func_node.child_by_field_name("function")?, source),
+        // `(|| ...)()` calls nothing by name.
+        "closure_expression" | "parenthesized_expression" => None,
+        _ => Some(node_text(func_node, source)),
     }
 }
 
@@ -265,23 +423,249 @@ fn collect_import_references(root: &Node, source: &[u8], refs: &mut Vec<Extracte
                 from_symbol: String::new(),
                 to_name: text,
                 kind: "imports".to_string(),
+                args: None,
             });
         }
     }
 }
 
-/// Walk up from a node to find the nearest enclosing function_item and return its name.
+/// Walk up from a node to the nearest enclosing function_item and return its
+/// symbol name (`Config::new` inside an impl). Closures belong to the item
+/// they are written in, so calls in `static CLIENT: LazyLock<Client> =
+/// LazyLock::new(|| Client::new())` are attributed to `CLIENT`.
 fn find_enclosing_function(node: &Node, source: &[u8]) -> Option<String> {
     let mut current = node.parent();
     while let Some(n) = current {
-        if n.kind() == "function_item" {
-            return find_child_by_kind(&n, "identifier").map(|id| node_text(&id, source));
+        match n.kind() {
+            "function_item" => {
+                let name = node_text(&n.child_by_field_name("name")?, source);
+                let impl_type = n
+                    .parent()
+                    .filter(|list| list.kind() == "declaration_list")
+                    .and_then(|list| list.parent())
+                    .filter(|item| item.kind() == "impl_item")
+                    .and_then(|item| base_type_name(&item.child_by_field_name("type")?, source));
+                return Some(match impl_type {
+                    Some(t) => format!("{t}::{name}"),
+                    None => name,
+                });
+            }
+            "const_item" | "static_item" => {
+                return n.child_by_field_name("name").map(|id| node_text(&id, source));
+            }
+            _ => current = n.parent(),
         }
-        current = n.parent();
     }
     None
 }
 
+// ---------------------------------------------------------------------------
+// Route extraction
+// ---------------------------------------------------------------------------
+
+/// axum `.route("/users", get(list).post(create))`, actix
+/// `.route("/users", web::get().to(list))`, and actix attribute macros
+/// (`#[get("/users/{id}")]`).
+fn collect_routes(root: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        match node.kind() {
+            "call_expression" => route_call(&node, source, routes),
+            "function_item" => route_attributes(&node, source, routes),
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    routes.sort_by_key(|r| r.line);
+}
+
+/// `<router>.route("/path", <method router>)`.
+fn route_call(node: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    let Some(function) = node.child_by_field_name("function").filter(|f| f.kind() == "field_expression") else {
+        return;
+    };
+    if function.child_by_field_name("field").map(|f| node_text(&f, source)).as_deref() != Some("route") {
+        return;
+    }
+    let Some(args) = node.child_by_field_name("arguments") else {
+        return;
+    };
+    let mut cursor = args.walk();
+    let args: Vec<Node> = args.named_children(&mut cursor).collect();
+    let [path, method_router] = args[..] else {
+        return;
+    };
+    let line = path.start_position().row + 1;
+    let Some(path) = string_value(&path, source).filter(|p| p.starts_with('/')) else {
+        return;
+    };
+    let mut handlers = Vec::new();
+    method_handlers(&method_router, source, &mut handlers);
+    handlers.reverse();
+    for (method, handler) in handlers {
+        routes.push(ExtractedRoute {
+            method,
+            path: path.clone(),
+            handler,
+            line,
+        });
+    }
+}
+
+/// (METHOD, handler) pairs of a method router expression, outermost first:
+/// `get(a).post(b)` yields POST b, GET a; `web::get().to(a)` yields GET a.
+fn method_handlers(expr: &Node, source: &[u8], out: &mut Vec<(String, String)>) {
+    if expr.kind() != "call_expression" {
+        return;
+    }
+    let Some(function) = expr.child_by_field_name("function") else {
+        return;
+    };
+    let first_arg = expr
+        .child_by_field_name("arguments")
+        .and_then(|a| a.named_child(0));
+    let handler = first_arg.map(|a| handler_name(&a, source)).unwrap_or_default();
+
+    match function.kind() {
+        // get(handler), routing::get(handler)
+        "identifier" | "scoped_identifier" => {
+            let verb = last_segment(&function, source);
+            if HTTP_METHODS.contains(&verb.as_str()) {
+                out.push((verb.to_uppercase(), handler));
+            }
+        }
+        // <router>.post(handler), web::get().to(handler)
+        "field_expression" => {
+            let field = function
+                .child_by_field_name("field")
+                .map(|f| node_text(&f, source))
+                .unwrap_or_default();
+            let Some(value) = function.child_by_field_name("value") else {
+                return;
+            };
+            if field == "to" {
+                let verb = value
+                    .child_by_field_name("function")
+                    .map(|f| last_segment(&f, source))
+                    .unwrap_or_default();
+                if HTTP_METHODS.contains(&verb.as_str()) {
+                    out.push((verb.to_uppercase(), handler));
+                }
+                return;
+            }
+            if HTTP_METHODS.contains(&field.as_str()) {
+                out.push((field.to_uppercase(), handler));
+            }
+            method_handlers(&value, source, out);
+        }
+        _ => {}
+    }
+}
+
+/// actix-web route macros on a handler fn: `#[get("/users")]`,
+/// `#[actix_web::post("/users")]`.
+fn route_attributes(node: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    let Some(handler) = find_child_by_kind(node, "identifier").map(|n| node_text(&n, source)) else {
+        return;
+    };
+    let mut prev = node.prev_named_sibling();
+    while let Some(sibling) = prev.filter(|s| matches!(s.kind(), "attribute_item" | "line_comment" | "block_comment")) {
+        prev = sibling.prev_named_sibling();
+        let Some(attr) = find_child_by_kind(&sibling, "attribute") else {
+            continue;
+        };
+        let Some(name) = attr.named_child(0).map(|n| last_segment(&n, source)) else {
+            continue;
+        };
+        if !HTTP_METHODS.contains(&name.as_str()) {
+            continue;
+        }
+        let path = attr
+            .child_by_field_name("arguments")
+            .and_then(|t| find_child_by_kind(&t, "string_literal").and_then(|s| string_value(&s, source)));
+        if let Some(path) = path {
+            routes.push(ExtractedRoute {
+                method: name.to_uppercase(),
+                path,
+                handler: handler.clone(),
+                line: sibling.start_position().row + 1,
+            });
+        }
+    }
+}
+
+/// `handler`, `handlers::users::list` → `list`; closures have no name.
+fn handler_name(node: &Node, source: &[u8]) -> String {
+    match node.kind() {
+        "identifier" | "scoped_identifier" => last_segment(node, source),
+        _ => String::new(),
+    }
+}
+
+/// Final segment of a (possibly scoped) identifier.
+fn last_segment(node: &Node, source: &[u8]) -> String {
+    match node.kind() {
+        "scoped_identifier" => node
+            .child_by_field_name("name")
+            .map(|n| node_text(&n, source))
+            .unwrap_or_default(),
+        _ => node_text(node, source),
+    }
+}
+
+/// Contents of a plain string literal.
+fn string_value(node: &Node, source: &[u8]) -> Option<String> {
+    if node.kind() != "string_literal" {
+        return None;
+    }
+    Some(
+        find_child_by_kind(node, "string_content")
+            .map(|c| node_text(&c, source))
+            .unwrap_or_default(),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Error-site extraction
+// ---------------------------------------------------------------------------
+
+/// `.unwrap()` and `.expect(..)` calls outside test code (`#[test]`
+/// functions and `#[cfg(test)]` items).
+fn collect_error_sites(root: &Node, source: &[u8], sites: &mut Vec<ErrorSite>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        match node.kind() {
+            "function_item" | "mod_item" | "impl_item" if is_test_item(&node, source) => continue,
+            "call_expression" => {
+                let method = node
+                    .child_by_field_name("function")
+                    .filter(|f| f.kind() == "field_expression")
+                    .and_then(|f| f.child_by_field_name("field"))
+                    .map(|f| node_text(&f, source));
+                if let Some(method @ ("unwrap" | "expect")) = method.as_deref() {
+                    sites.push(error_site(method, &node, source));
+                }
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    sites.sort_by_key(|s| s.line);
+}
+
+fn is_test_item(node: &Node, source: &[u8]) -> bool {
+    attributes_of(node, source).iter().any(|a| {
+        let inner = a.trim_start_matches("#[").trim_end_matches(']');
+        inner == "test" || inner.ends_with("::test") || inner.starts_with("tokio::test") || inner == "cfg(test)"
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
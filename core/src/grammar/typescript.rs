@@ -1,6 +1,6 @@
 use tree_sitter::{Language, Node, Tree};
 
-use super::{ExtractedReference, ExtractedSymbol, Grammar, SymbolKind};
+use super::{call_arguments, error_site, join_route_path, keyword_modifiers, ErrorSite, ExtractedReference, ExtractedRoute, ExtractedSymbol, Grammar, Param, SymbolKind, TypeSignature, Visibility, HTTP_METHODS};
 
 pub struct TypeScriptGrammar;
 pub struct TsxGrammar;
@@ -28,6 +28,18 @@ impl Grammar for TypeScriptGrammar {
         collect_import_references(&root, source, &mut refs);
         refs
     }
+
+    fn extract_routes(&self, source: &[u8], tree: &Tree) -> Vec<ExtractedRoute> {
+        let mut routes = Vec::new();
+        collect_routes(&tree.root_node(), source, &mut routes);
+        routes
+    }
+
+    fn extract_error_sites(&self, source: &[u8], tree: &Tree) -> Vec<ErrorSite> {
+        let mut sites = Vec::new();
+        collect_error_sites(&tree.root_node(), source, &mut sites);
+        sites
+    }
 }
 
 impl Grammar for TsxGrammar {
@@ -53,6 +65,18 @@ impl Grammar for TsxGrammar {
         collect_import_references(&root, source, &mut refs);
         refs
     }
+
+    fn extract_routes(&self, source: &[u8], tree: &Tree) -> Vec<ExtractedRoute> {
+        let mut routes = Vec::new();
+        collect_routes(&tree.root_node(), source, &mut routes);
+        routes
+    }
+
+    fn extract_error_sites(&self, source: &[u8], tree: &Tree) -> Vec<ErrorSite> {
+        let mut sites = Vec::new();
+        collect_error_sites(&tree.root_node(), source, &mut sites);
+        sites
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -68,7 +92,7 @@ fn extract_top_level_symbols(node: &Node, source: &[u8], out: &mut Vec<Extracted
                     out.push(sym);
                 }
             }
-            "class_declaration" => {
+            "class_declaration" | "abstract_class_declaration" => {
                 if let Some(sym) = extract_class(&child, source) {
                     out.push(sym);
                 }
@@ -86,15 +110,75 @@ fn extract_top_level_symbols(node: &Node, source: &[u8], out: &mut Vec<Extracted
             "lexical_declaration" => {
                 extract_const_declaration(&child, source, out);
             }
+            // `namespace Foo {}` parses as an expression statement; exported
+            // ones are declarations.
+            "expression_statement" | "internal_module" | "module" => {
+                let module = match child.kind() {
+                    "expression_statement" => child.named_child(0).filter(|m| m.kind() == "internal_module"),
+                    _ => Some(child),
+                };
+                if let Some(sym) = module.and_then(|m| extract_namespace(&m, source)) {
+                    out.push(sym);
+                }
+            }
             "export_statement" => {
                 // Unwrap export and process the inner declaration
-                extract_top_level_symbols(&child, source, out);
+                let mut exported = Vec::new();
+                extract_top_level_symbols(&child, source, &mut exported);
+                let default = keyword_modifiers(&child, &["default"]);
+                // `@Decorator export class ...` attaches the decorator to the export
+                let decorators = decorators_of(&child, source);
+                for mut sym in exported {
+                    sym.visibility = Visibility::Public;
+                    sym.modifiers.splice(0..0, default.iter().cloned());
+                    sym.attributes.splice(0..0, decorators.iter().cloned());
+                    out.push(sym);
+                }
             }
             _ => {}
         }
     }
 }
 
+/// A `namespace Validation { ... }` (or `module`) as a Module symbol whose
+/// children are its declarations, qualified `Validation.isValid`. Nested
+/// namespaces nest; `namespace A.B` is named `A.B`.
+fn extract_namespace(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
+    let name_node = node.child_by_field_name("name").filter(|n| n.kind() != "string")?;
+    let name = node_text(&name_node, source);
+    let body_node = node.child_by_field_name("body");
+    let mut children = Vec::new();
+    if let Some(body) = &body_node {
+        extract_top_level_symbols(body, source, &mut children);
+    }
+    for child in &mut children {
+        qualify(child, &name);
+    }
+    Some(ExtractedSymbol {
+        qualified_name: name.clone(),
+        name,
+        kind: SymbolKind::Module,
+        signature: extract_signature(node, &body_node, source),
+        body: node_text(node, source),
+        start_line: node.start_position().row + 1,
+        end_line: node.end_position().row + 1,
+        children,
+        visibility: Visibility::Private,
+        modifiers: Vec::new(),
+        types: None,
+        attributes: Vec::new(),
+    })
+}
+
+/// Prefix the qualified names of `sym` and everything below it with a
+/// namespace.
+fn qualify(sym: &mut ExtractedSymbol, namespace: &str) {
+    sym.qualified_name = format!("{namespace}.{}", sym.qualified_name);
+    for child in &mut sym.children {
+        qualify(child, namespace);
+    }
+}
+
 fn extract_function(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
     let name_node = node.child_by_field_name("name")?;
     let name = node_text(&name_node, source);
@@ -110,6 +194,10 @@ fn extract_function(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility: Visibility::Private,
+        modifiers: keyword_modifiers(node, &["async"]),
+        types: Some(types_of(node, source)),
+        attributes: Vec::new(),
     })
 }
 
@@ -118,16 +206,31 @@ fn extract_class(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
     let name = node_text(&name_node, source);
     let body = node_text(node, source);
 
-    // Extract methods as children with ClassName::method qualified names
+    // Extract methods as children with ClassName.method qualified names
     let mut children = Vec::new();
     if let Some(class_body) = node.child_by_field_name("body") {
         let mut cursor = class_body.walk();
+        // Method decorators are siblings that precede the method in the class body
+        let mut decorators = Vec::new();
         for child in class_body.children(&mut cursor) {
-            if child.kind() == "method_definition" {
-                if let Some(mut method) = extract_method(&child, source) {
-                    method.qualified_name = format!("{name}::{}", method.name);
-                    children.push(method);
+            match child.kind() {
+                "decorator" => decorators.push(node_text(&child, source)),
+                "method_definition" => {
+                    if let Some(mut method) = extract_method(&child, source) {
+                        method.qualified_name = format!("{name}.{}", method.name);
+                        method.attributes = std::mem::take(&mut decorators);
+                        children.push(method);
+                    }
+                }
+                // `handleClick = () => {...}` fields are methods bound to the instance.
+                "public_field_definition" if function_value(&child).is_some() => {
+                    if let Some(mut method) = extract_field_function(&child, source) {
+                        method.qualified_name = format!("{name}.{}", method.name);
+                        method.attributes = std::mem::take(&mut decorators);
+                        children.push(method);
+                    }
                 }
+                _ => decorators.clear(),
             }
         }
     }
@@ -144,6 +247,33 @@ fn extract_class(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children,
+        visibility: Visibility::Private,
+        modifiers: keyword_modifiers(node, &["abstract"]),
+        types: None,
+        attributes: decorators_of(node, source),
+    })
+}
+
+fn extract_field_function(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
+    let name_node = node.child_by_field_name("name")?;
+    let name = node_text(&name_node, source);
+    let func = function_value(node)?;
+    let signature = extract_signature(node, &func.child_by_field_name("body"), source);
+    let mut modifiers = keyword_modifiers(node, &["static"]);
+    modifiers.extend(keyword_modifiers(&func, &["async"]));
+    Some(ExtractedSymbol {
+        qualified_name: name.clone(),
+        name,
+        kind: SymbolKind::Method,
+        signature,
+        body: node_text(node, source),
+        start_line: node.start_position().row + 1,
+        end_line: node.end_position().row + 1,
+        children: Vec::new(),
+        visibility: member_visibility(node, &name_node, source),
+        modifiers,
+        types: Some(types_of(&func, source)),
+        attributes: Vec::new(),
     })
 }
 
@@ -162,6 +292,10 @@ fn extract_method(node: &Node, source: &[u8]) -> Option<ExtractedSymbol> {
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility: member_visibility(node, &name_node, source),
+        modifiers: keyword_modifiers(node, &["static", "async", "get", "set"]),
+        types: Some(types_of(node, source)),
+        attributes: Vec::new(),
     })
 }
 
@@ -183,6 +317,10 @@ fn extract_named_symbol(
         start_line: node.start_position().row + 1,
         end_line: node.end_position().row + 1,
         children: Vec::new(),
+        visibility: Visibility::Private,
+        modifiers: Vec::new(),
+        types: None,
+        attributes: Vec::new(),
     })
 }
 
@@ -200,29 +338,117 @@ fn extract_const_declaration(node: &Node, source: &[u8], out: &mut Vec<Extracted
             if let Some(name_node) = child.child_by_field_name("name") {
                 let name = node_text(&name_node, source);
                 let body = node_text(node, source);
-                let signature = extract_declaration_line(&body);
+                // `const handler = async (req: Request) => {...}` is a named function.
+                let (kind, signature, modifiers, types) = match function_value(&child) {
+                    Some(func) => (
+                        SymbolKind::Function,
+                        extract_signature(node, &func.child_by_field_name("body"), source),
+                        keyword_modifiers(&func, &["async"]),
+                        Some(types_of(&func, source)),
+                    ),
+                    None => (SymbolKind::Const, extract_declaration_line(&body), Vec::new(), None),
+                };
                 out.push(ExtractedSymbol {
                     qualified_name: name.clone(),
                     name,
-                    kind: SymbolKind::Const,
+                    kind,
                     signature,
                     body,
                     start_line: node.start_position().row + 1,
                     end_line: node.end_position().row + 1,
                     children: Vec::new(),
+                    visibility: Visibility::Private,
+                    modifiers,
+                    types,
+                    attributes: Vec::new(),
                 });
             }
         }
     }
 }
 
+/// The arrow function or function expression a declarator is initialised with.
+fn function_value<'t>(declarator: &Node<'t>) -> Option<Node<'t>> {
+    let value = declarator.child_by_field_name("value")?;
+    matches!(value.kind(), "arrow_function" | "function_expression" | "generator_function").then_some(value)
+}
+
+/// True for a declarator of a top-level or namespace-level `const`, the
+/// only variables extracted as symbols.
+fn is_top_level_const(declarator: &Node) -> bool {
+    let Some(decl) = declarator.parent().filter(|d| d.kind() == "lexical_declaration") else {
+        return false;
+    };
+    let namespace_body = |block: Node| {
+        block.kind() == "statement_block"
+            && block.parent().is_some_and(|m| matches!(m.kind(), "internal_module" | "module"))
+    };
+    find_child_by_kind(&decl, "const").is_some()
+        && decl.parent().is_some_and(|p| matches!(p.kind(), "program" | "export_statement") || namespace_body(p))
+}
+
+/// Parameter and return types of a function or method, with the `:` of each
+/// type annotation dropped. Untyped parameters have no type.
+fn types_of(node: &Node, source: &[u8]) -> TypeSignature {
+    let annotation = |n: Option<Node>| {
+        n.map(|t| node_text(&t, source).trim_start_matches(':').trim().to_string())
+    };
+    let mut params = Vec::new();
+    if let Some(list) = node.child_by_field_name("parameters") {
+        let mut cursor = list.walk();
+        for param in list.named_children(&mut cursor) {
+            if !matches!(param.kind(), "required_parameter" | "optional_parameter") {
+                continue;
+            }
+            params.push(Param {
+                name: param
+                    .child_by_field_name("pattern")
+                    .map(|p| node_text(&p, source))
+                    .unwrap_or_default(),
+                type_name: annotation(param.child_by_field_name("type")),
+            });
+        }
+    }
+    TypeSignature {
+        params,
+        returns: annotation(node.child_by_field_name("return_type")),
+    }
+}
+
+/// `@decorator(...)` children of a class declaration or export statement.
+fn decorators_of(node: &Node, source: &[u8]) -> Vec<String> {
+    let mut cursor = node.walk();
+    let decorators = node
+        .children_by_field_name("decorator", &mut cursor)
+        .map(|d| node_text(&d, source))
+        .collect();
+    decorators
+}
+
+/// Class member visibility: an explicit `public`/`protected`/`private`
+/// modifier, `#name` private fields, otherwise public. Top-level
+/// declarations start private and become public when wrapped in `export`.
+fn member_visibility(node: &Node, name_node: &Node, source: &[u8]) -> Visibility {
+    if name_node.kind() == "private_property_identifier" {
+        return Visibility::Private;
+    }
+    match find_child_by_kind(node, "accessibility_modifier")
+        .map(|m| node_text(&m, source))
+        .as_deref()
+    {
+        Some("private") => Visibility::Private,
+        Some("protected") => Visibility::Protected,
+        _ => Visibility::Public,
+    }
+}
+
 /// Build a signature from everything before the body block.
 fn extract_signature(node: &Node, body_node: &Option<Node>, source: &[u8]) -> String {
     match body_node {
         Some(body) => {
             let start = node.start_byte();
             let end = body.start_byte();
-            let raw = &source[start..end];
+            let raw = source.get(start..end).unwrap_or_default();
             String::from_utf8_lossy(raw).trim().to_string()
         }
         None => node_text(node, source),
@@ -248,20 +474,31 @@ fn collect_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedRefere
         if node.kind() == "call_expression" {
             if let Some(callee) = extract_callee(&node, source) {
                 let from = find_enclosing_function(&node, source).unwrap_or_default();
+                // A tagged template (`sql\`...\``) has no argument list.
+                let args = node
+                    .child_by_field_name("arguments")
+                    .filter(|a| a.kind() == "arguments")
+                    .map(|a| call_arguments(&a, argument_kind));
                 refs.push(ExtractedReference {
                     from_symbol: from,
                     to_name: callee,
                     kind: "calls".to_string(),
+                    args,
                 });
             }
         }
         if node.kind() == "new_expression" {
             if let Some(callee) = extract_new_callee(&node, source) {
                 let from = find_enclosing_function(&node, source).unwrap_or_default();
+                // `new Foo` without parentheses passes no arguments.
+                let args = node
+                    .child_by_field_name("arguments")
+                    .map_or_else(Vec::new, |a| call_arguments(&a, argument_kind));
                 refs.push(ExtractedReference {
                     from_symbol: from,
                     to_name: callee,
                     kind: "calls".to_string(),
+                    args: Some(args),
                 });
             }
         }
@@ -273,6 +510,30 @@ fn collect_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedRefere
     }
 }
 
+/// Kind of one call argument (see `ExtractedReference::args`).
+fn argument_kind(arg: &Node) -> String {
+    let kind = match arg.kind() {
+        "string" | "template_string" => "string",
+        "number" => "number",
+        "true" | "false" => "bool",
+        "null" | "undefined" => "null",
+        "array" => "array",
+        "object" => "object",
+        "arrow_function" | "function_expression" | "function" | "generator_function" => "function",
+        "spread_element" => "spread",
+        "unary_expression" if arg.child_by_field_name("operator").is_some_and(|o| o.kind() == "-") => {
+            return arg
+                .child_by_field_name("argument")
+                .map_or_else(|| "expr".to_string(), |inner| argument_kind(&inner))
+        }
+        "parenthesized_expression" | "as_expression" | "satisfies_expression" | "non_null_expression" => {
+            return arg.named_child(0).map_or_else(|| "expr".to_string(), |inner| argument_kind(&inner))
+        }
+        _ => "expr",
+    };
+    kind.to_string()
+}
+
 /// Extract callee from a `call_expression`.
 /// Handles `foo()` and `obj.method()`.
 fn extract_callee(node: &Node, source: &[u8]) -> Option<String> {
@@ -284,6 +545,8 @@ fn extract_callee(node: &Node, source: &[u8]) -> Option<String> {
             find_child_by_kind(&func_node, "property_identifier")
                 .map(|n| node_text(&n, source))
         }
+        // An immediately invoked function expression calls nothing by name.
+        "parenthesized_expression" | "arrow_function" | "function_expression" => None,
         _ => Some(node_text(&func_node, source)),
     }
 }
@@ -301,37 +564,240 @@ fn extract_new_callee(node: &Node, source: &[u8]) -> Option<String> {
 }
 
 /// Collect import statements as import references.
+/// Import statements and re-exports (`export * from './x'`) are `imports`
+/// references; a bare `export { a as b }` list is an `exports` reference.
+/// Barrel resolution (`crate::barrels`) reads both.
 fn collect_import_references(root: &Node, source: &[u8], refs: &mut Vec<ExtractedReference>) {
     let mut cursor = root.walk();
     for child in root.children(&mut cursor) {
-        if child.kind() == "import_statement" {
-            let text = node_text(&child, source);
-            refs.push(ExtractedReference {
-                from_symbol: String::new(),
-                to_name: text,
-                kind: "imports".to_string(),
-            });
-        }
+        let kind = match child.kind() {
+            "import_statement" => "imports",
+            "export_statement" if child.child_by_field_name("source").is_some() => "imports",
+            "export_statement"
+                if child.child_by_field_name("declaration").is_none()
+                    && find_child_by_kind(&child, "export_clause").is_some() =>
+            {
+                "exports"
+            }
+            _ => continue,
+        };
+        refs.push(ExtractedReference {
+            from_symbol: String::new(),
+            to_name: node_text(&child, source),
+            kind: kind.to_string(),
+            args: None,
+        });
     }
 }
 
-/// Walk up to find the nearest enclosing function or method.
+/// Walk up to find the nearest enclosing function or method, by its
+/// qualified name (`Validation.Checker.run`). Anonymous functions belong to
+/// the symbol they are written in: a top-level `const` (whatever its value)
+/// or an arrow-function class field.
 fn find_enclosing_function(node: &Node, source: &[u8]) -> Option<String> {
     let mut current = node.parent();
     while let Some(n) = current {
         match n.kind() {
-            "function_declaration" => {
-                let name_node = n.child_by_field_name("name")?;
-                return Some(node_text(&name_node, source));
+            "function_declaration" | "method_definition" => break,
+            "variable_declarator" if is_top_level_const(&n) => break,
+            "public_field_definition" if function_value(&n).is_some() => break,
+            _ => current = n.parent(),
+        }
+    }
+    let owner = current?;
+    let mut name = node_text(&owner.child_by_field_name("name")?, source);
+    // Prefix the enclosing classes and namespaces, as `extract_class` and
+    // `extract_namespace` qualify their children.
+    let mut ancestor = owner.parent();
+    while let Some(n) = ancestor {
+        if matches!(n.kind(), "class_declaration" | "abstract_class_declaration" | "internal_module" | "module") {
+            if let Some(outer) = n.child_by_field_name("name").filter(|o| o.kind() != "string") {
+                name = format!("{}.{name}", node_text(&outer, source));
+            }
+        }
+        ancestor = n.parent();
+    }
+    Some(name)
+}
+
+// ---------------------------------------------------------------------------
+// Route extraction
+// ---------------------------------------------------------------------------
+
+/// Express-style registrations (`app.get("/users", handler)`,
+/// `router.post("/users", auth, create)`) and Nest controllers
+/// (`@Controller("users")` with `@Get(":id")` methods).
+fn collect_routes(root: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        match node.kind() {
+            "call_expression" => {
+                if let Some(route) = express_route(&node, source) {
+                    routes.push(route);
+                }
+            }
+            "class_declaration" | "abstract_class_declaration" => {
+                nest_routes(&node, source, routes);
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    routes.sort_by_key(|r| r.line);
+}
+
+/// `<router>.<verb>("/path", ...handlers)`: the path must be a literal
+/// starting with `/` and at least one handler must follow, which keeps
+/// `map.get("/key")` out. The last argument is the handler.
+fn express_route(node: &Node, source: &[u8]) -> Option<ExtractedRoute> {
+    let function = node.child_by_field_name("function")?;
+    if function.kind() != "member_expression" {
+        return None;
+    }
+    let verb = node_text(&function.child_by_field_name("property")?, source);
+    let method = match verb.as_str() {
+        "all" => "ANY".to_string(),
+        v if HTTP_METHODS.contains(&v) => v.to_uppercase(),
+        _ => return None,
+    };
+    let args = node.child_by_field_name("arguments")?;
+    let mut cursor = args.walk();
+    let args: Vec<Node> = args.named_children(&mut cursor).collect();
+    if args.len() < 2 {
+        return None;
+    }
+    let path = string_value(&args[0], source).filter(|p| p.starts_with('/'))?;
+    let last = args[args.len() - 1];
+    let handler = match last.kind() {
+        "identifier" => node_text(&last, source),
+        "member_expression" => last
+            .child_by_field_name("property")
+            .map(|p| node_text(&p, source))
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+    Some(ExtractedRoute {
+        method,
+        path,
+        handler,
+        line: args[0].start_position().row + 1,
+    })
+}
+
+/// Routes of a Nest controller: the `@Controller` prefix joined with each
+/// method's `@Get`/`@Post`/... path.
+fn nest_routes(class: &Node, source: &[u8], routes: &mut Vec<ExtractedRoute>) {
+    // Decorators of an exported class live on the export statement.
+    let mut decorators = decorator_nodes(class);
+    if let Some(parent) = class.parent().filter(|p| p.kind() == "export_statement") {
+        decorators.extend(decorator_nodes(&parent));
+    }
+    let Some(prefix) = decorators
+        .iter()
+        .find_map(|d| decorator_call(d, source).filter(|(name, _)| name == "Controller"))
+        .map(|(_, arg)| arg.unwrap_or_default())
+    else {
+        return;
+    };
+    let Some(body) = class.child_by_field_name("body") else {
+        return;
+    };
+
+    let mut pending: Vec<(String, Option<String>, usize)> = Vec::new();
+    let mut cursor = body.walk();
+    for child in body.children(&mut cursor) {
+        match child.kind() {
+            "decorator" => {
+                if let Some((name, arg)) = decorator_call(&child, source) {
+                    pending.push((name, arg, child.start_position().row + 1));
+                }
             }
             "method_definition" => {
-                let name_node = n.child_by_field_name("name")?;
-                return Some(node_text(&name_node, source));
+                let handler = child
+                    .child_by_field_name("name")
+                    .map(|n| node_text(&n, source))
+                    .unwrap_or_default();
+                for (name, arg, line) in pending.drain(..) {
+                    let method = match name.as_str() {
+                        "All" => "ANY".to_string(),
+                        n if HTTP_METHODS.contains(&n.to_lowercase().as_str()) => n.to_uppercase(),
+                        _ => continue,
+                    };
+                    routes.push(ExtractedRoute {
+                        method,
+                        path: join_route_path(&prefix, arg.as_deref().unwrap_or_default()),
+                        handler: handler.clone(),
+                        line,
+                    });
+                }
             }
-            _ => current = n.parent(),
+            _ => pending.clear(),
         }
     }
-    None
+}
+
+fn decorator_nodes<'a>(node: &Node<'a>) -> Vec<Node<'a>> {
+    let mut cursor = node.walk();
+    let decorators = node.children_by_field_name("decorator", &mut cursor).collect();
+    decorators
+}
+
+/// `@Name(arg, ...)` → (Name, first argument when it is a string literal).
+fn decorator_call(decorator: &Node, source: &[u8]) -> Option<(String, Option<String>)> {
+    let call = decorator.named_child(0).filter(|c| c.kind() == "call_expression")?;
+    let function = call.child_by_field_name("function")?;
+    if function.kind() != "identifier" {
+        return None;
+    }
+    let arg = call
+        .child_by_field_name("arguments")
+        .and_then(|a| a.named_child(0))
+        .and_then(|a| string_value(&a, source));
+    Some((node_text(&function, source), arg))
+}
+
+/// Value of a string literal or a template string without substitutions.
+fn string_value(node: &Node, source: &[u8]) -> Option<String> {
+    match node.kind() {
+        "string" => Some(
+            find_child_by_kind(node, "string_fragment")
+                .map(|f| node_text(&f, source))
+                .unwrap_or_default(),
+        ),
+        "template_string" if find_child_by_kind(node, "template_substitution").is_none() => {
+            Some(node_text(node, source).trim_matches('`').to_string())
+        }
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Error-site extraction
+// ---------------------------------------------------------------------------
+
+/// `catch` blocks with no statements (comments do not count).
+fn collect_error_sites(root: &Node, source: &[u8], sites: &mut Vec<ErrorSite>) {
+    let mut stack: Vec<Node> = vec![*root];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "catch_clause" {
+            let empty = node.child_by_field_name("body").is_some_and(|body| {
+                let mut cursor = body.walk();
+                let empty = body.named_children(&mut cursor).all(|c| c.kind() == "comment");
+                empty
+            });
+            if empty {
+                sites.push(error_site("empty_catch", &node, source));
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    sites.sort_by_key(|s| s.line);
 }
 
 // ---------------------------------------------------------------------------
@@ -1,20 +1,81 @@
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
 use serde::Serialize;
 
-use crate::db::{Database, Symbol};
+use crate::db::{parse_call_sites, Database, Symbol, SymbolCoverage};
+use crate::graph_cache::{CachedEdge, GraphCache};
+
+/// BFS levels at least this wide are expanded on several threads.
+const PARALLEL_FRONTIER: usize = 1024;
 
 // ---------------------------------------------------------------------------
 // ImpactNode — one node in the blast-radius graph
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ImpactNode {
+    #[serde(skip)]
+    pub id: i64,
     pub name: String,
     pub kind: String,
     pub file_path: String,
     pub distance: usize,
     pub edge_kind: String,
+    /// For a `calls` edge, the argument kinds at each distinct call site —
+    /// which callers a parameter change breaks, and how.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub call_sites: Vec<Vec<String>>,
+    /// Line coverage from the last imported report, when available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<SymbolCoverage>,
+}
+
+/// Result of an impact traversal. `truncated` says why it stopped before
+/// reaching the requested depth — `max_nodes` or `timeout` — and is None when
+/// `nodes` is the full blast radius.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactReport {
+    pub nodes: Vec<ImpactNode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<String>,
+}
+
+/// Bounds on one traversal. Hitting either stops an impact BFS, which
+/// returns what was reached so far, nearest dependents first; a file-path
+/// search fails instead.
+#[derive(Debug, Clone, Copy)]
+pub struct TraversalLimits {
+    pub max_nodes: usize,
+    pub timeout: Duration,
+}
+
+impl TraversalLimits {
+    pub const DEFAULT_MAX_NODES: usize = 10_000;
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+}
+
+impl Default for TraversalLimits {
+    fn default() -> Self {
+        Self { max_nodes: Self::DEFAULT_MAX_NODES, timeout: Self::DEFAULT_TIMEOUT }
+    }
+}
+
+/// One file on a `find_file_path` chain. `via` is the symbol edge that leads
+/// into it from the previous file (None for the first file).
+#[derive(Debug, Clone, Serialize)]
+pub struct FileHop {
+    pub file_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via: Option<FileLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileLink {
+    pub from_symbol: String,
+    pub to_symbol: String,
+    pub kind: String,
 }
 
 // ---------------------------------------------------------------------------
@@ -23,61 +84,110 @@ pub struct ImpactNode {
 
 pub struct GraphEngine<'a> {
     db: &'a Database,
+    limits: TraversalLimits,
 }
 
 impl<'a> GraphEngine<'a> {
     pub fn new(db: &'a Database) -> Self {
-        Self { db }
+        Self { db, limits: TraversalLimits::default() }
+    }
+
+    /// Bound impact traversals by `limits` instead of the defaults.
+    pub fn with_limits(mut self, limits: TraversalLimits) -> Self {
+        self.limits = limits;
+        self
     }
 
     /// BFS traversal of reverse edges (dependents) to find the blast radius
     /// of changing `symbol_name`. Returns all symbols transitively affected,
-    /// up to `max_depth` hops away.
+    /// up to `max_depth` hops away, within the engine's traversal limits.
     pub fn impact_graph(
         &self,
         symbol_name: &str,
         max_depth: usize,
         repo_id: Option<i64>,
     ) -> anyhow::Result<Vec<ImpactNode>> {
+        Ok(self.impact_graph_report(symbol_name, max_depth, repo_id)?.nodes)
+    }
+
+    /// `impact_graph`, reporting whether a traversal limit cut it short.
+    pub fn impact_graph_report(
+        &self,
+        symbol_name: &str,
+        max_depth: usize,
+        repo_id: Option<i64>,
+    ) -> anyhow::Result<ImpactReport> {
         let root = self.resolve_symbol(symbol_name, repo_id)?;
+        self.impact_report(&[root.id], max_depth)
+    }
 
-        let mut visited = HashSet::new();
-        visited.insert(root.id);
+    /// Blast radius of changing every symbol in `root_ids` at once: a single
+    /// BFS over reverse edges, so a dependent reachable from several roots is
+    /// reported once, at its shortest distance. Roots themselves are excluded.
+    pub fn impact_of(&self, root_ids: &[i64], max_depth: usize) -> anyhow::Result<Vec<ImpactNode>> {
+        Ok(self.impact_report(root_ids, max_depth)?.nodes)
+    }
 
-        let mut queue: VecDeque<(i64, usize)> = VecDeque::new();
-        queue.push_back((root.id, 0));
+    /// `impact_of`, reporting whether a traversal limit cut it short.
+    ///
+    /// The BFS walks the in-memory graph cache one level at a time, so a
+    /// truncated report still holds every dependent nearer than the level it
+    /// stopped in. Wide levels are expanded on several threads (see
+    /// `incoming_edges`) and merged in frontier order, so the result does
+    /// not depend on scheduling.
+    pub fn impact_report(&self, root_ids: &[i64], max_depth: usize) -> anyhow::Result<ImpactReport> {
+        let started = Instant::now();
+        let graph = self.db.graph()?;
 
-        let mut results = Vec::new();
+        let mut visited: HashSet<i64> = root_ids.iter().copied().collect();
+        let mut frontier: Vec<i64> = root_ids.to_vec();
+        let mut results: Vec<ImpactNode> = Vec::new();
+        let mut truncated = None;
 
-        while let Some((current_id, depth)) = queue.pop_front() {
-            if depth >= max_depth {
-                continue;
+        'bfs: for depth in 0..max_depth {
+            if started.elapsed() > self.limits.timeout {
+                truncated = Some("timeout".to_string());
+                break;
             }
-
-            // Reverse edges: who depends on current_id?
-            let dependents = self.db.get_dependents(current_id)?;
-
-            for (edge, sym) in dependents {
-                if visited.insert(sym.id) {
-                    let file_path = self
-                        .db
-                        .get_file_path_for_symbol(sym.id)
-                        .unwrap_or_else(|_| "<unknown>".to_string());
-
-                    results.push(ImpactNode {
-                        name: sym.name.clone(),
-                        kind: sym.kind.clone(),
-                        file_path,
-                        distance: depth + 1,
-                        edge_kind: edge.kind.clone(),
-                    });
-
-                    queue.push_back((sym.id, depth + 1));
+            let mut next = Vec::new();
+            for edge in incoming_edges(&graph, &frontier) {
+                let Some(source) = graph.symbol(edge.source_id) else { continue };
+                if !visited.insert(edge.source_id) {
+                    continue;
+                }
+                if results.len() >= self.limits.max_nodes {
+                    truncated = Some("max_nodes".to_string());
+                    break 'bfs;
                 }
+                results.push(ImpactNode {
+                    id: edge.source_id,
+                    name: source.name.clone(),
+                    kind: source.kind.clone(),
+                    file_path: graph.file_path(source.file_id).unwrap_or("<unknown>").to_string(),
+                    distance: depth + 1,
+                    edge_kind: edge.kind.clone(),
+                    call_sites: parse_call_sites(edge.call_sites.clone()),
+                    coverage: None,
+                });
+                next.push(edge.source_id);
+            }
+            if next.is_empty() {
+                break;
             }
+            frontier = next;
+        }
+        drop(graph);
+
+        // BFS order depends on edge order; report by distance, then name/path.
+        results.sort_by(|a, b| (a.distance, &a.name, &a.file_path).cmp(&(b.distance, &b.name, &b.file_path)));
+
+        let ids: Vec<i64> = results.iter().map(|n| n.id).collect();
+        let mut coverage = self.db.get_coverage_for_symbols(&ids)?;
+        for node in &mut results {
+            node.coverage = coverage.remove(&node.id);
         }
 
-        Ok(results)
+        Ok(ImpactReport { nodes: results, truncated })
     }
 
     /// BFS pathfinding through forward edges (dependencies) from `from_name`
@@ -100,11 +210,8 @@ impl<'a> GraphEngine<'a> {
         let mut queue: VecDeque<Vec<i64>> = VecDeque::new();
         queue.push_back(vec![source.id]);
 
-        // Cache symbol data for reconstruction at the end
-        let mut symbol_cache: HashMap<i64, Symbol> = HashMap::new();
+        let graph = self.db.graph()?;
         let target_id = target.id;
-        symbol_cache.insert(source.id, source);
-        symbol_cache.insert(target_id, target);
 
         while let Some(path) = queue.pop_front() {
             if found_paths.len() >= max_paths || queue.len() > MAX_QUEUE_SIZE {
@@ -123,28 +230,99 @@ impl<'a> GraphEngine<'a> {
             }
 
             let visited_on_path: HashSet<i64> = path.iter().copied().collect();
-            let deps = self.db.get_dependencies(current_id)?;
-
-            for (_edge, dep_sym) in deps {
-                if !visited_on_path.contains(&dep_sym.id) {
-                    symbol_cache.entry(dep_sym.id).or_insert_with(|| dep_sym.clone());
+            for edge in graph.outgoing(current_id) {
+                if !visited_on_path.contains(&edge.target_id) && graph.symbol(edge.target_id).is_some() {
                     let mut new_path = path.clone();
-                    new_path.push(dep_sym.id);
+                    new_path.push(edge.target_id);
                     queue.push_back(new_path);
                 }
             }
         }
+        drop(graph);
 
         // Reconstruct symbol paths from ID paths
-        Ok(found_paths
-            .into_iter()
-            .map(|id_path| {
-                id_path
-                    .into_iter()
-                    .filter_map(|id| symbol_cache.get(&id).cloned())
-                    .collect()
-            })
-            .collect())
+        let mut symbols: HashMap<i64, Symbol> = HashMap::new();
+        symbols.insert(source.id, source);
+        symbols.insert(target_id, target);
+        let mut paths = Vec::with_capacity(found_paths.len());
+        for id_path in found_paths {
+            let mut path = Vec::with_capacity(id_path.len());
+            for id in id_path {
+                let sym = match symbols.entry(id) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => match self.db.get_symbol_by_id(id)? {
+                        Some(sym) => entry.insert(sym),
+                        None => continue,
+                    },
+                };
+                path.push(sym.clone());
+            }
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Shortest chain of files from `from_file` to `to_file`, where one file
+    /// leads to another when any of its symbols depends on a symbol there.
+    /// Each hop names one such edge (the first by symbol name). None when
+    /// `to_file` is unreachable. The search visits at most `max_nodes` files
+    /// within the engine's timeout, and fails when it stops at a limit
+    /// before finding a chain, since that proves nothing either way.
+    pub fn find_file_path(&self, from_file: i64, to_file: i64) -> anyhow::Result<Option<Vec<FileHop>>> {
+        let started = Instant::now();
+        let start = self
+            .db
+            .get_file_by_id(from_file)?
+            .ok_or_else(|| anyhow::anyhow!("file {from_file} not indexed"))?;
+        // file id → (previous file id, hop into it)
+        let mut came_from: HashMap<i64, (i64, FileHop)> = HashMap::new();
+        let mut queue = VecDeque::from([from_file]);
+        let mut found = from_file == to_file;
+
+        let graph = self.db.graph()?;
+        while let Some(current) = queue.pop_front() {
+            if found {
+                break;
+            }
+            if started.elapsed() > self.limits.timeout {
+                anyhow::bail!("no chain found before the {:?} timeout", self.limits.timeout);
+            }
+            for edge in graph.file_dependencies(current) {
+                if edge.target_file_id == from_file || came_from.contains_key(&edge.target_file_id) {
+                    continue;
+                }
+                if came_from.len() >= self.limits.max_nodes {
+                    anyhow::bail!("no chain found within {} files (max_nodes)", self.limits.max_nodes);
+                }
+                let hop = FileHop {
+                    file_path: edge.target_path,
+                    via: Some(FileLink {
+                        from_symbol: edge.source_symbol,
+                        to_symbol: edge.target_symbol,
+                        kind: edge.kind,
+                    }),
+                };
+                came_from.insert(edge.target_file_id, (current, hop));
+                if edge.target_file_id == to_file {
+                    found = true;
+                    break;
+                }
+                queue.push_back(edge.target_file_id);
+            }
+        }
+        if !found {
+            return Ok(None);
+        }
+
+        let mut chain = Vec::new();
+        let mut current = to_file;
+        while let Some((previous, hop)) = came_from.remove(&current) {
+            chain.push(hop);
+            current = previous;
+        }
+        chain.push(FileHop { file_path: start.path, via: None });
+        chain.reverse();
+        Ok(Some(chain))
     }
 
     /// Resolve a symbol name to a `Symbol`, optionally scoped to a repo.
@@ -157,6 +335,24 @@ impl<'a> GraphEngine<'a> {
             Some(rid) => self.db.find_symbol_by_name(rid, name)?,
             None => self.db.find_symbol_by_name_any(name)?,
         };
-        sym.ok_or_else(|| anyhow::anyhow!("symbol '{}' not found", name))
+        sym.ok_or_else(|| anyhow::anyhow!(self.db.symbol_not_found_message(name, repo_id)))
+    }
+}
+
+/// Incoming edges of every symbol in `frontier`, in frontier order. A wide
+/// frontier is split into one chunk per core and expanded on scoped
+/// threads; the cache is only read, so they share it without locking.
+fn incoming_edges<'g>(graph: &'g GraphCache, frontier: &[i64]) -> Vec<&'g CachedEdge> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads == 1 || frontier.len() < PARALLEL_FRONTIER {
+        return frontier.iter().flat_map(|&id| graph.incoming(id)).collect();
     }
+    let chunk = frontier.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = frontier
+            .chunks(chunk)
+            .map(|ids| scope.spawn(move || ids.iter().flat_map(|&id| graph.incoming(id)).collect::<Vec<_>>()))
+            .collect();
+        workers.into_iter().flat_map(|w| w.join().expect("impact BFS worker panicked")).collect()
+    })
 }
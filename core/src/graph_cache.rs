@@ -0,0 +1,444 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::db::FileEdge;
+
+// ---------------------------------------------------------------------------
+// Graph cache
+// ---------------------------------------------------------------------------
+//
+// An in-memory copy of the edge graph with each symbol's name, kind and file,
+// so `GraphEngine` traversals walk adjacency lists instead of issuing a query
+// per visited node. The database owns one cache for the whole index, since
+// edges cross repositories (project siblings, external stubs).
+//
+// Nothing is loaded until the first traversal. After that, the connection's
+// update hook marks every `files`, `symbols` and `edges` row written through
+// it as dirty, and the next traversal reloads just those rows. A re-index of
+// one file therefore costs a few lookups, not a rebuild. The cache is rebuilt
+// from scratch when another connection commits (`PRAGMA data_version`) or when
+// more than `DIRTY_LIMIT` rows are pending.
+//
+// Rows are reloaded as they stand, so a rollback before the next sync needs no
+// special care. A sync inside an open transaction may read rows that are later
+// rolled back, which fires no hook; such a sync leaves its rows marked dirty
+// (or, after a full build, the whole cache stale) for the next one.
+//
+// The cache tracks an estimate of its own size. One larger than its budget
+// (`[memory] graph_cache_mb`) still serves the traversal that loaded it, but
+// is released as soon as that traversal ends: every traversal then pays for a
+// full load, and memory between traversals stays flat.
+
+/// Dirty rows past which a full rebuild is cheaper than row-by-row reloads.
+pub const DIRTY_LIMIT: usize = 100_000;
+
+/// Default size the cache may keep between traversals.
+pub const DEFAULT_BUDGET_BYTES: usize = 512 * 1024 * 1024;
+
+/// Rough per-entry cost of a hash map slot and a `String` header, on top of
+/// the string contents.
+const ENTRY_OVERHEAD: usize = 48;
+
+/// Rows written since the cache last synced. Fed by the update hook.
+#[derive(Debug, Default)]
+pub(crate) struct DirtyRows {
+    /// Off until the cache is first built, so plain indexing runs pay nothing.
+    tracking: bool,
+    /// Too many rows, or a rollback: the sets no longer describe the changes.
+    overflow: bool,
+    files: HashSet<i64>,
+    symbols: HashSet<i64>,
+    edges: HashSet<i64>,
+}
+
+impl DirtyRows {
+    pub(crate) fn mark(&mut self, table: &str, rowid: i64) {
+        if !self.tracking || self.overflow {
+            return;
+        }
+        let rows = match table {
+            "files" => &mut self.files,
+            "symbols" => &mut self.symbols,
+            "edges" => &mut self.edges,
+            _ => return,
+        };
+        rows.insert(rowid);
+        if self.files.len() + self.symbols.len() + self.edges.len() > DIRTY_LIMIT {
+            self.invalidate();
+        }
+    }
+
+    /// Force a rebuild on the next sync.
+    pub(crate) fn invalidate(&mut self) {
+        if self.tracking {
+            *self = Self { tracking: true, overflow: true, ..Self::default() };
+        }
+    }
+
+    /// Mark `rows` dirty again.
+    fn restore(&mut self, rows: DirtyRows) {
+        if self.overflow {
+            return;
+        }
+        self.files.extend(rows.files);
+        self.symbols.extend(rows.symbols);
+        self.edges.extend(rows.edges);
+    }
+}
+
+/// What `get_health` reports about the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GraphCacheStats {
+    /// Whether a graph is loaded right now.
+    pub resident: bool,
+    pub symbols: usize,
+    pub edges: usize,
+    /// Estimated heap size of the loaded graph.
+    pub bytes: usize,
+    pub budget_bytes: usize,
+    pub builds: u64,
+    /// Times the graph was released for exceeding its budget.
+    pub evictions: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedSymbol {
+    pub name: String,
+    pub kind: String,
+    pub file_id: i64,
+    pub start_line: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedEdge {
+    pub id: i64,
+    pub source_id: i64,
+    pub target_id: i64,
+    pub kind: String,
+    /// Raw `call_sites` column; see `db::parse_call_sites`.
+    pub call_sites: Option<String>,
+}
+
+/// Adjacency lists hold edge ids in the order `get_dependencies` and
+/// `get_dependents` return them: by the neighbour's name, path and line, then
+/// edge kind.
+#[derive(Debug)]
+pub struct GraphCache {
+    /// `PRAGMA data_version` when last synced; None before the first build.
+    synced_at: Option<i64>,
+    /// Full builds so far.
+    builds: u64,
+    evictions: u64,
+    budget_bytes: usize,
+    /// Estimated size of the maps below.
+    bytes: usize,
+    files: HashMap<i64, String>,
+    symbols: HashMap<i64, CachedSymbol>,
+    file_symbols: HashMap<i64, Vec<i64>>,
+    edges: HashMap<i64, CachedEdge>,
+    outgoing: HashMap<i64, Vec<i64>>,
+    incoming: HashMap<i64, Vec<i64>>,
+}
+
+type EdgeKey<'a> = (&'a str, &'a str, i64, &'a str, i64);
+
+impl Default for GraphCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET_BYTES)
+    }
+}
+
+fn file_bytes(path: &str) -> usize {
+    ENTRY_OVERHEAD + path.len()
+}
+
+/// The symbol, its slot in `file_symbols`.
+fn symbol_bytes(symbol: &CachedSymbol) -> usize {
+    ENTRY_OVERHEAD + std::mem::size_of::<CachedSymbol>() + symbol.name.len() + symbol.kind.len() + 8
+}
+
+/// The edge, its slots in `outgoing` and `incoming`.
+fn edge_bytes(edge: &CachedEdge) -> usize {
+    ENTRY_OVERHEAD
+        + std::mem::size_of::<CachedEdge>()
+        + edge.kind.len()
+        + edge.call_sites.as_ref().map_or(0, String::len)
+        + 16
+}
+
+impl GraphCache {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            synced_at: None,
+            builds: 0,
+            evictions: 0,
+            budget_bytes,
+            bytes: 0,
+            files: HashMap::new(),
+            symbols: HashMap::new(),
+            file_symbols: HashMap::new(),
+            edges: HashMap::new(),
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn set_budget(&mut self, budget_bytes: usize) {
+        self.budget_bytes = budget_bytes;
+    }
+
+    pub fn stats(&self) -> GraphCacheStats {
+        GraphCacheStats {
+            resident: self.synced_at.is_some(),
+            symbols: self.symbols.len(),
+            edges: self.edges.len(),
+            bytes: self.bytes,
+            budget_bytes: self.budget_bytes,
+            builds: self.builds,
+            evictions: self.evictions,
+        }
+    }
+
+    /// Drop the graph if it's larger than its budget, and stop tracking
+    /// writes until the next build.
+    pub(crate) fn evict_if_over_budget(&mut self, dirty: &Mutex<DirtyRows>) {
+        if self.bytes <= self.budget_bytes {
+            return;
+        }
+        *self = Self { builds: self.builds, evictions: self.evictions + 1, ..Self::new(self.budget_bytes) };
+        if let Ok(mut dirty) = dirty.lock() {
+            *dirty = DirtyRows::default();
+        }
+    }
+
+    /// Bring the cache up to date: a full build the first time or after
+    /// outside changes, otherwise a reload of the dirty rows.
+    pub(crate) fn sync(&mut self, conn: &Connection, data_version: i64, dirty: &Mutex<DirtyRows>) -> Result<()> {
+        let lock = || dirty.lock().map_err(|_| anyhow::anyhow!("graph cache lock poisoned"));
+        let (stale, pending) = {
+            let mut dirty = lock()?;
+            let stale = dirty.overflow || self.synced_at != Some(data_version);
+            (stale, std::mem::replace(&mut *dirty, DirtyRows { tracking: true, ..DirtyRows::default() }))
+        };
+        self.synced_at = None;
+        if stale {
+            self.rebuild(conn)?;
+        } else {
+            self.apply(conn, &pending)?;
+        }
+        self.synced_at = Some(data_version);
+
+        if !conn.is_autocommit() {
+            let mut dirty = lock()?;
+            if stale {
+                dirty.invalidate();
+            } else {
+                dirty.restore(pending);
+            }
+        }
+        Ok(())
+    }
+
+    fn rebuild(&mut self, conn: &Connection) -> Result<()> {
+        *self = Self { builds: self.builds + 1, evictions: self.evictions, ..Self::new(self.budget_bytes) };
+        let mut stmt = conn.prepare("SELECT id, path FROM files")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let path: String = row.get(1)?;
+            self.bytes += file_bytes(&path);
+            self.files.insert(row.get(0)?, path);
+        }
+        let mut stmt = conn.prepare("SELECT id, name, kind, file_id, start_line FROM symbols")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let (id, symbol) = symbol_from_row(row)?;
+            self.bytes += symbol_bytes(&symbol);
+            self.file_symbols.entry(symbol.file_id).or_default().push(id);
+            self.symbols.insert(id, symbol);
+        }
+        let mut stmt = conn.prepare("SELECT id, source_id, target_id, kind, call_sites FROM edges")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let edge = edge_from_row(row)?;
+            self.bytes += edge_bytes(&edge);
+            self.outgoing.entry(edge.source_id).or_default().push(edge.id);
+            self.incoming.entry(edge.target_id).or_default().push(edge.id);
+            self.edges.insert(edge.id, edge);
+        }
+
+        let (mut outgoing, mut incoming) = (std::mem::take(&mut self.outgoing), std::mem::take(&mut self.incoming));
+        for list in outgoing.values_mut() {
+            list.sort_by(|a, b| self.target_key(*a).cmp(&self.target_key(*b)));
+        }
+        for list in incoming.values_mut() {
+            list.sort_by(|a, b| self.source_key(*a).cmp(&self.source_key(*b)));
+        }
+        (self.outgoing, self.incoming) = (outgoing, incoming);
+        Ok(())
+    }
+
+    /// Reload each dirty row; rows that no longer exist are dropped. Files
+    /// and symbols go first, since edges are ordered by their endpoints, and
+    /// all dirty edges leave the lists before any return, so the lists stay
+    /// sorted.
+    fn apply(&mut self, conn: &Connection, dirty: &DirtyRows) -> Result<()> {
+        for &id in &dirty.files {
+            let path: Option<String> = conn
+                .query_row("SELECT path FROM files WHERE id = ?1", params![id], |r| r.get(0))
+                .optional()?;
+            if let Some(old) = self.files.remove(&id) {
+                self.bytes -= file_bytes(&old);
+            }
+            if let Some(path) = path {
+                self.bytes += file_bytes(&path);
+                self.files.insert(id, path);
+            }
+        }
+        for &id in &dirty.symbols {
+            if let Some(old) = self.symbols.remove(&id) {
+                self.bytes -= symbol_bytes(&old);
+                if let Some(list) = self.file_symbols.get_mut(&old.file_id) {
+                    list.retain(|&s| s != id);
+                }
+            }
+            let symbol = conn
+                .query_row(
+                    "SELECT id, name, kind, file_id, start_line FROM symbols WHERE id = ?1",
+                    params![id],
+                    symbol_from_row,
+                )
+                .optional()?;
+            if let Some((id, symbol)) = symbol {
+                self.bytes += symbol_bytes(&symbol);
+                self.file_symbols.entry(symbol.file_id).or_default().push(id);
+                self.symbols.insert(id, symbol);
+            }
+        }
+        for &id in &dirty.edges {
+            self.remove_edge(id);
+        }
+        for &id in &dirty.edges {
+            let edge = conn
+                .query_row(
+                    "SELECT id, source_id, target_id, kind, call_sites FROM edges WHERE id = ?1",
+                    params![id],
+                    edge_from_row,
+                )
+                .optional()?;
+            if let Some(edge) = edge {
+                self.insert_edge(edge);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_edge(&mut self, id: i64) {
+        let Some(edge) = self.edges.remove(&id) else { return };
+        self.bytes -= edge_bytes(&edge);
+        if let Some(list) = self.outgoing.get_mut(&edge.source_id) {
+            list.retain(|&e| e != id);
+        }
+        if let Some(list) = self.incoming.get_mut(&edge.target_id) {
+            list.retain(|&e| e != id);
+        }
+    }
+
+    fn insert_edge(&mut self, edge: CachedEdge) {
+        let (id, source_id, target_id) = (edge.id, edge.source_id, edge.target_id);
+        self.bytes += edge_bytes(&edge);
+        self.edges.insert(id, edge);
+        let out_at = self.outgoing.get(&source_id).map_or(0, |list| {
+            list.partition_point(|&e| self.target_key(e) < self.target_key(id))
+        });
+        self.outgoing.entry(source_id).or_default().insert(out_at, id);
+        let in_at = self.incoming.get(&target_id).map_or(0, |list| {
+            list.partition_point(|&e| self.source_key(e) < self.source_key(id))
+        });
+        self.incoming.entry(target_id).or_default().insert(in_at, id);
+    }
+
+    /// Sort key of edge `id` in its source's outgoing list.
+    fn target_key(&self, id: i64) -> EdgeKey<'_> {
+        let edge = &self.edges[&id];
+        self.key(edge.target_id, edge)
+    }
+
+    /// Sort key of edge `id` in its target's incoming list.
+    fn source_key(&self, id: i64) -> EdgeKey<'_> {
+        let edge = &self.edges[&id];
+        self.key(edge.source_id, edge)
+    }
+
+    fn key<'a>(&'a self, neighbour: i64, edge: &'a CachedEdge) -> EdgeKey<'a> {
+        match self.symbols.get(&neighbour) {
+            Some(s) => (&s.name, self.file_path(s.file_id).unwrap_or(""), s.start_line, &edge.kind, edge.id),
+            None => ("", "", 0, &edge.kind, edge.id),
+        }
+    }
+
+    pub fn symbol(&self, id: i64) -> Option<&CachedSymbol> {
+        self.symbols.get(&id)
+    }
+
+    pub fn file_path(&self, file_id: i64) -> Option<&str> {
+        self.files.get(&file_id).map(String::as_str)
+    }
+
+    /// Edges out of `symbol_id`: what it depends on.
+    pub fn outgoing(&self, symbol_id: i64) -> impl Iterator<Item = &CachedEdge> {
+        self.outgoing.get(&symbol_id).into_iter().flatten().map(|id| &self.edges[id])
+    }
+
+    /// Edges into `symbol_id`: what depends on it.
+    pub fn incoming(&self, symbol_id: i64) -> impl Iterator<Item = &CachedEdge> {
+        self.incoming.get(&symbol_id).into_iter().flatten().map(|id| &self.edges[id])
+    }
+
+    /// Edges from a symbol in `file_id` to a symbol in another file, ordered
+    /// by target path, source name, target name and kind.
+    pub fn file_dependencies(&self, file_id: i64) -> Vec<FileEdge> {
+        let mut edges: Vec<FileEdge> = Vec::new();
+        for &source_id in self.file_symbols.get(&file_id).into_iter().flatten() {
+            let source = &self.symbols[&source_id];
+            for edge in self.outgoing(source_id) {
+                let Some(target) = self.symbols.get(&edge.target_id) else { continue };
+                if target.file_id == file_id {
+                    continue;
+                }
+                edges.push(FileEdge {
+                    target_file_id: target.file_id,
+                    target_path: self.file_path(target.file_id).unwrap_or_default().to_string(),
+                    source_symbol: source.name.clone(),
+                    target_symbol: target.name.clone(),
+                    kind: edge.kind.clone(),
+                });
+            }
+        }
+        edges.sort_by(|a, b| {
+            (&a.target_path, &a.source_symbol, &a.target_symbol, &a.kind)
+                .cmp(&(&b.target_path, &b.source_symbol, &b.target_symbol, &b.kind))
+        });
+        edges
+    }
+}
+
+fn symbol_from_row(row: &rusqlite::Row) -> rusqlite::Result<(i64, CachedSymbol)> {
+    Ok((
+        row.get(0)?,
+        CachedSymbol { name: row.get(1)?, kind: row.get(2)?, file_id: row.get(3)?, start_line: row.get(4)? },
+    ))
+}
+
+fn edge_from_row(row: &rusqlite::Row) -> rusqlite::Result<CachedEdge> {
+    Ok(CachedEdge {
+        id: row.get(0)?,
+        source_id: row.get(1)?,
+        target_id: row.get(2)?,
+        kind: row.get(3)?,
+        call_sites: row.get(4)?,
+    })
+}
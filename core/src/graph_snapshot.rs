@@ -0,0 +1,174 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::db::{Database, StoredGraphSnapshot};
+
+// ---------------------------------------------------------------------------
+// Graph snapshots
+// ---------------------------------------------------------------------------
+//
+// Every full index run (`index_directory`, `index_revision`) that changed the
+// graph ends by recording the repository's symbols and outgoing edges, keyed
+// by file path and qualified name rather than by row id, since ids change
+// whenever a file is re-parsed. A run that left the graph as it was only
+// bumps the newest snapshot's time, so no JSON is written for it. After
+// a refactor, `graph_diff` lists what connectivity changed, so an agent can
+// check that a rename or a moved function didn't drop or add call edges it
+// didn't intend to.
+
+/// Recorded graphs whose counts and hash are kept per repository.
+pub const SNAPSHOT_HISTORY: usize = 20;
+
+/// Runs whose full symbol and edge lists are kept: the latest, and the one
+/// before it to compare against.
+pub const SNAPSHOTS_WITH_GRAPH: usize = 2;
+
+/// A symbol is `path::qualified_name`, e.g. `src/db.rs::db::Database::open`,
+/// with `@start_line` appended when the qualified name repeats in the file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    pub symbols: BTreeSet<String>,
+    pub edges: BTreeSet<SnapshotEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SnapshotEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: String,
+}
+
+impl GraphSnapshot {
+    /// The current symbols and edges of `repo_id`.
+    pub fn capture(db: &Database, repo_id: i64) -> Result<Self> {
+        let symbols = db.graph_symbol_keys(repo_id)?.into_iter().collect();
+        let edges = db
+            .graph_edge_keys(repo_id)?
+            .into_iter()
+            .map(|(source, target, kind)| SnapshotEdge { source, target, kind })
+            .collect();
+        Ok(Self { symbols, edges })
+    }
+
+    /// SHA-256 of the sorted symbol and edge lists: equal hashes mean the
+    /// same graph.
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for symbol in &self.symbols {
+            hasher.update(symbol.as_bytes());
+            hasher.update(b"\n");
+        }
+        for edge in &self.edges {
+            hasher.update(format!("{} -{}-> {}\n", edge.source, edge.kind, edge.target).as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Record `repo_id`'s graph as of now and prune old snapshots. An
+/// unchanged graph only refreshes the newest snapshot's time.
+pub fn record(db: &Database, repo_id: i64) -> Result<()> {
+    let snapshot = GraphSnapshot::capture(db, repo_id)?;
+    let hash = snapshot.hash();
+    if let Some((id, latest)) = db.latest_graph_hash(repo_id)? {
+        if latest == hash {
+            return db.touch_graph_snapshot(id);
+        }
+    }
+    let graph = serde_json::to_string(&snapshot)?;
+    db.insert_graph_snapshot(repo_id, snapshot.symbols.len(), snapshot.edges.len(), &hash, &graph)?;
+    db.prune_graph_snapshots(repo_id, SNAPSHOT_HISTORY, SNAPSHOTS_WITH_GRAPH)
+}
+
+/// Counts and hash of one side of a diff. `taken_at` is None for the live
+/// index.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    pub symbols: usize,
+    pub edges: usize,
+    pub hash: String,
+}
+
+impl From<&StoredGraphSnapshot> for SnapshotSummary {
+    fn from(stored: &StoredGraphSnapshot) -> Self {
+        Self {
+            taken_at: Some(stored.taken_at.clone()),
+            symbols: stored.symbol_count,
+            edges: stored.edge_count,
+            hash: stored.graph_hash.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphDiff {
+    pub from: SnapshotSummary,
+    pub to: SnapshotSummary,
+    pub changed: bool,
+    pub symbols_added: Vec<String>,
+    pub symbols_removed: Vec<String>,
+    pub edges_added: Vec<SnapshotEdge>,
+    pub edges_removed: Vec<SnapshotEdge>,
+    /// Entries left out of the four lists above by `limit`.
+    pub omitted: usize,
+    /// Recent recorded graphs, newest first.
+    pub history: Vec<SnapshotSummary>,
+}
+
+/// Changes between the last two recorded graphs of `repo_id` or, with `live`,
+/// between the last run and the index as it stands now (incremental
+/// re-indexes included). Each list holds at most `limit` entries. None when
+/// too few runs are recorded.
+pub fn graph_diff(db: &Database, repo_id: i64, live: bool, limit: usize) -> Result<Option<GraphDiff>> {
+    let recent = db.recent_graph_snapshots(repo_id, SNAPSHOT_HISTORY)?;
+    let (old, new, from, to) = if live {
+        let Some(latest) = recent.first() else { return Ok(None) };
+        let now = GraphSnapshot::capture(db, repo_id)?;
+        let to = SnapshotSummary {
+            taken_at: None,
+            symbols: now.symbols.len(),
+            edges: now.edges.len(),
+            hash: now.hash(),
+        };
+        (stored_graph(latest)?, now, SnapshotSummary::from(latest), to)
+    } else {
+        let [latest, previous, ..] = recent.as_slice() else { return Ok(None) };
+        (stored_graph(previous)?, stored_graph(latest)?, previous.into(), latest.into())
+    };
+
+    let mut omitted = 0;
+    let mut take = |items: Vec<String>| -> Vec<String> {
+        omitted += items.len().saturating_sub(limit);
+        items.into_iter().take(limit).collect()
+    };
+    let symbols_added = take(new.symbols.difference(&old.symbols).cloned().collect());
+    let symbols_removed = take(old.symbols.difference(&new.symbols).cloned().collect());
+    let mut take_edges = |items: Vec<SnapshotEdge>| -> Vec<SnapshotEdge> {
+        omitted += items.len().saturating_sub(limit);
+        items.into_iter().take(limit).collect()
+    };
+    let edges_added = take_edges(new.edges.difference(&old.edges).cloned().collect());
+    let edges_removed = take_edges(old.edges.difference(&new.edges).cloned().collect());
+
+    Ok(Some(GraphDiff {
+        changed: from.hash != to.hash,
+        from,
+        to,
+        symbols_added,
+        symbols_removed,
+        edges_added,
+        edges_removed,
+        omitted,
+        history: recent.iter().map(SnapshotSummary::from).collect(),
+    }))
+}
+
+fn stored_graph(stored: &StoredGraphSnapshot) -> Result<GraphSnapshot> {
+    let graph = stored.graph.as_deref().context("snapshot graph was pruned")?;
+    serde_json::from_str(graph).context("corrupt graph snapshot")
+}
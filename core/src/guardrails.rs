@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+// ---------------------------------------------------------------------------
+// Index size guardrails
+// ---------------------------------------------------------------------------
+//
+// Pointing focal at a vendored browser checkout by accident once produced a
+// 30 GB index. Two guardrails keep a walk from getting there:
+//
+// - Caps: a full walk that finds more indexable files than `max_files`, or
+//   leaves the repository with more symbols than `max_symbols`, fails with
+//   `LimitExceeded` and its transaction is rolled back. `focal index --force`
+//   (or answering yes at the prompt) indexes anyway and records a waiver on
+//   the repository, so later runs and `focal serve` don't trip again.
+// - Generated directories: a directory where more than
+//   `generated_dir_files` source files, and at least half of its source
+//   files, share one generated name pattern (`*.pb.go`, `case_#.ts`) is
+//   skipped by every walk and by the watcher. Verdicts are cached per
+//   directory until its mtime changes, so per-file updates don't list every
+//   ancestor again.
+
+pub const DEFAULT_MAX_FILES: usize = 100_000;
+pub const DEFAULT_MAX_SYMBOLS: usize = 2_000_000;
+pub const DEFAULT_GENERATED_DIR_FILES: usize = 200;
+
+/// Size limits for one repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Guardrails {
+    pub max_files: usize,
+    pub max_symbols: usize,
+    /// 0 disables generated-directory skipping.
+    pub generated_dir_files: usize,
+    verdicts: DirVerdicts,
+}
+
+/// A generated directory's pattern and matching file count.
+type Verdict = Option<(String, usize)>;
+
+/// `generated_dir` results by directory and the mtime they were computed
+/// at. Clones share the cache; it is not part of the settings, so all
+/// caches compare equal.
+#[derive(Debug, Clone, Default)]
+struct DirVerdicts(Arc<Mutex<HashMap<PathBuf, (SystemTime, Verdict)>>>);
+
+impl PartialEq for DirVerdicts {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for DirVerdicts {}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FILES, DEFAULT_MAX_SYMBOLS, DEFAULT_GENERATED_DIR_FILES)
+    }
+}
+
+impl Guardrails {
+    pub fn new(max_files: usize, max_symbols: usize, generated_dir_files: usize) -> Self {
+        Self { max_files, max_symbols, generated_dir_files, verdicts: DirVerdicts::default() }
+    }
+
+    /// The name pattern shared by more than `generated_dir_files` of the
+    /// source files directly in `dir`, when they are also at least half of
+    /// them. `is_source` tells source files from the rest by name, so a
+    /// directory of numbered images doesn't hide the code next to them.
+    pub fn generated_dir(&self, dir: &Path, is_source: impl Fn(&str) -> bool) -> Verdict {
+        if self.generated_dir_files == 0 {
+            return None;
+        }
+        let mtime = std::fs::metadata(dir).and_then(|m| m.modified()).ok();
+        if let Some(mtime) = mtime {
+            let verdicts = self.verdicts.0.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((_, verdict)) = verdicts.get(dir).filter(|(at, _)| *at == mtime) {
+                return verdict.clone();
+            }
+        }
+        let verdict = self.scan_dir(dir, is_source);
+        if let Some(mtime) = mtime {
+            let mut verdicts = self.verdicts.0.lock().unwrap_or_else(|e| e.into_inner());
+            verdicts.insert(dir.to_path_buf(), (mtime, verdict.clone()));
+        }
+        verdict
+    }
+
+    fn scan_dir(&self, dir: &Path, is_source: impl Fn(&str) -> bool) -> Verdict {
+        let mut files = 0;
+        let mut patterns: HashMap<String, usize> = HashMap::new();
+        for entry in std::fs::read_dir(dir).ok()?.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !entry.file_type().is_ok_and(|t| t.is_file()) || !is_source(&name) {
+                continue;
+            }
+            files += 1;
+            if let Some(pattern) = generated_pattern(&name) {
+                *patterns.entry(pattern).or_default() += 1;
+            }
+        }
+        patterns
+            .into_iter()
+            .filter(|&(_, n)| n > self.generated_dir_files && n * 2 >= files)
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+    }
+}
+
+/// The generated-name pattern of a file name: `*` plus a generator suffix
+/// (see `generated`), else the name with digit runs replaced by `#`. None
+/// for names that look hand-written.
+pub fn generated_pattern(name: &str) -> Option<String> {
+    if let Some(suffix) = crate::generated::generated_suffix(name) {
+        return Some(format!("*{suffix}"));
+    }
+    let mut pattern = String::with_capacity(name.len());
+    let mut numbered = false;
+    let mut in_digits = false;
+    for c in name.chars() {
+        if c.is_ascii_digit() {
+            if !in_digits {
+                pattern.push('#');
+            }
+            numbered = true;
+            in_digits = true;
+        } else {
+            pattern.push(c);
+            in_digits = false;
+        }
+    }
+    numbered.then_some(pattern)
+}
+
+/// A full walk stopped by a guardrail cap. Indexing errors carry it, so
+/// callers can `downcast_ref` it and offer to retry with `force`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub root: String,
+    /// `files` or `symbols`.
+    pub limit: &'static str,
+    /// How many the walk had reached when it stopped.
+    pub count: usize,
+    pub max: usize,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = if self.limit == "files" { "max_files_per_repo" } else { "max_symbols_per_repo" };
+        write!(
+            f,
+            "{} has more than {} indexable {} (reached {}); exclude what shouldn't be indexed, \
+             raise [index] {key}, or run `focal index --force {}`",
+            self.root, self.max, self.limit, self.count, self.root
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+// ---------------------------------------------------------------------------
+// Git hook installation
+// ---------------------------------------------------------------------------
+//
+// `focal hook install` keeps the index fresh for users who don't run the
+// long-lived watcher: after every commit and checkout, git runs
+// `focal reindex --changed-only` in the background. Existing hooks are kept —
+// the Focal block is appended once, delimited by marker comments.
+
+/// Hooks Focal installs into.
+pub const HOOKS: &[&str] = &["post-commit", "post-checkout"];
+
+const BEGIN_MARKER: &str = "# >>> focal reindex >>>";
+const END_MARKER: &str = "# <<< focal reindex <<<";
+
+/// What happened to one hook file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookChange {
+    Created(PathBuf),
+    Appended(PathBuf),
+    AlreadyInstalled(PathBuf),
+}
+
+/// The shell lines that reindex `root` with `binary`. Output is discarded and
+/// the process detached so commits and checkouts never wait on indexing.
+pub fn hook_block(binary: &Path, root: &Path) -> String {
+    format!(
+        "{BEGIN_MARKER}\n{} reindex --changed-only {} >/dev/null 2>&1 &\n{END_MARKER}\n",
+        shell_quote(binary),
+        shell_quote(root)
+    )
+}
+
+/// `path` as one POSIX shell word: single-quoted, with embedded single
+/// quotes written as `'\''`.
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''"))
+}
+
+/// Write or extend the post-commit and post-checkout hooks of the checkout
+/// at `root` so they invoke `binary reindex --changed-only`.
+pub fn install_hooks(root: &Path, binary: &Path) -> Result<Vec<HookChange>> {
+    let dir = crate::git::hooks_dir(root)?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let block = hook_block(binary, root);
+
+    let mut changes = Vec::new();
+    for name in HOOKS {
+        let path = dir.join(name);
+        let change = match std::fs::read_to_string(&path) {
+            Ok(existing) if existing.contains(BEGIN_MARKER) => HookChange::AlreadyInstalled(path.clone()),
+            Ok(existing) => {
+                let sep = if existing.ends_with('\n') { "\n" } else { "\n\n" };
+                std::fs::write(&path, format!("{existing}{sep}{block}"))
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                HookChange::Appended(path.clone())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                std::fs::write(&path, format!("#!/bin/sh\n{block}"))
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                HookChange::Created(path.clone())
+            }
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        make_executable(&path)?;
+        changes.push(change);
+    }
+    Ok(changes)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = std::fs::metadata(path)?.permissions();
+    perms.set_mode(perms.mode() | 0o755);
+    std::fs::set_permissions(path, perms).with_context(|| format!("failed to chmod {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::db::{Database, TestLinkEntry};
+use crate::graph::{GraphEngine, ImpactNode, ImpactReport};
+
+// ---------------------------------------------------------------------------
+// Diff impact
+// ---------------------------------------------------------------------------
+//
+// Maps the hunks of a unified diff onto indexed symbols by line range, then
+// walks reverse edges from those symbols to find what else the patch can
+// break and which tests exercise any of it.
+
+/// Lines a diff touches in one file, 1-based, numbered on each side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    /// Path as it appears in the diff, without the `a/`/`b/` prefix.
+    pub path: String,
+    /// Removed lines, and for added ones the old line they sit next to.
+    pub old_lines: Vec<i64>,
+    /// Added lines, and for removed ones the new line they sit before.
+    /// Deleted files keep their old numbers.
+    pub new_lines: Vec<i64>,
+}
+
+/// Which side of a diff the index holds: `Old` for a patch not yet applied,
+/// `New` for changes already on disk (and re-indexed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSide {
+    Old,
+    New,
+}
+
+impl FileChange {
+    pub fn lines(&self, side: DiffSide) -> &[i64] {
+        match side {
+            DiffSide::Old => &self.old_lines,
+            DiffSide::New => &self.new_lines,
+        }
+    }
+}
+
+/// A symbol whose line range intersects a changed line.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedSymbol {
+    #[serde(skip)]
+    pub id: i64,
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: i64,
+    pub end_line: i64,
+}
+
+/// Blast radius of a diff.
+#[derive(Debug, Serialize)]
+pub struct DiffImpact {
+    /// Innermost symbols touched by the diff.
+    pub changed: Vec<ChangedSymbol>,
+    /// Transitive dependents of `changed`, nearest first.
+    pub impacted: Vec<ImpactNode>,
+    /// Set when a traversal limit cut `impacted` short; see `ImpactReport`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<String>,
+    /// Tests linked to a changed or impacted symbol, one entry per test.
+    pub tests: Vec<TestLinkEntry>,
+    /// Changed files with no indexed symbols (unsupported language, not yet
+    /// indexed, or outside the repository).
+    pub unindexed_files: Vec<String>,
+}
+
+/// Parse unified diff text (`git diff`, `diff -u`) into the lines changed in
+/// each file, on both sides. On its own side a line counts where it is; on
+/// the other, a deletion counts as the new line it sits before and an
+/// addition as the old line it sits next to, so removing a function's last
+/// statement or appending one still hits the function.
+pub fn parse_unified_diff(text: &str) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = Vec::new();
+    let mut old_path: Option<String> = None;
+    let mut deleted_file = false;
+    let mut hunk = Hunk::default();
+
+    for line in text.lines() {
+        // Inside a hunk, "--- x" is a removed "-- x" line, not a header.
+        if hunk.old_left == 0 && hunk.new_left == 0 {
+            if let Some(rest) = line.strip_prefix("--- ") {
+                old_path = diff_path(rest);
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("+++ ") {
+                let new_path = diff_path(rest);
+                deleted_file = new_path.is_none();
+                if let Some(path) = new_path.or_else(|| old_path.clone()) {
+                    changes.push(FileChange { path, old_lines: Vec::new(), new_lines: Vec::new() });
+                }
+                continue;
+            }
+            if let Some(header) = line.strip_prefix("@@ ") {
+                hunk = Hunk::parse(header).unwrap_or_default();
+                continue;
+            }
+        }
+        let Some(current) = changes.last_mut() else {
+            continue;
+        };
+        match line.as_bytes().first() {
+            Some(b'+') if hunk.new_left > 0 => {
+                current.new_lines.push(hunk.new_line);
+                current.old_lines.push(hunk.old_line.max(1));
+                hunk.new_line += 1;
+                hunk.new_left -= 1;
+            }
+            Some(b'-') if hunk.old_left > 0 => {
+                current.old_lines.push(hunk.old_line);
+                current.new_lines.push(if deleted_file { hunk.old_line } else { hunk.new_line.max(1) });
+                hunk.old_line += 1;
+                hunk.old_left -= 1;
+            }
+            Some(b' ') | None if hunk.old_left > 0 && hunk.new_left > 0 => {
+                hunk.old_line += 1;
+                hunk.new_line += 1;
+                hunk.old_left -= 1;
+                hunk.new_left -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    for change in &mut changes {
+        for lines in [&mut change.old_lines, &mut change.new_lines] {
+            lines.sort_unstable();
+            lines.dedup();
+        }
+    }
+    changes.retain(|c| !c.new_lines.is_empty());
+    changes
+}
+
+/// Position within the current hunk: the next line number on each side and
+/// how many lines of each side remain.
+#[derive(Debug, Default)]
+struct Hunk {
+    old_line: i64,
+    old_left: i64,
+    new_line: i64,
+    new_left: i64,
+}
+
+impl Hunk {
+    /// `-12,3 +14,5 @@ fn x`; an omitted length means 1.
+    fn parse(header: &str) -> Option<Self> {
+        let mut parts = header.split_whitespace();
+        let old = parts.next()?.strip_prefix('-')?;
+        let new = parts.next()?.strip_prefix('+')?;
+        let range = |r: &str| -> Option<(i64, i64)> {
+            match r.split_once(',') {
+                Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+                None => Some((r.parse().ok()?, 1)),
+            }
+        };
+        let (old_line, old_left) = range(old)?;
+        let (new_line, new_left) = range(new)?;
+        Some(Self { old_line, old_left, new_line, new_left })
+    }
+}
+
+/// `a/src/x.rs` → `src/x.rs`; `/dev/null` → None. Trailing timestamps from
+/// `diff -u` are dropped.
+fn diff_path(raw: &str) -> Option<String> {
+    let raw = raw.split('\t').next().unwrap_or(raw).trim();
+    if raw == "/dev/null" {
+        return None;
+    }
+    let path = raw
+        .strip_prefix("a/")
+        .or_else(|| raw.strip_prefix("b/"))
+        .unwrap_or(raw);
+    Some(crate::paths::normalize_path(path))
+}
+
+/// Resolve `diff` against the index of `repo_id`, which holds its `side`, and
+/// compute its impact up to `max_depth` hops. Diff paths must be relative to
+/// the repository root.
+pub fn diff_impact(db: &Database, repo_id: i64, diff: &str, side: DiffSide, max_depth: usize) -> Result<DiffImpact> {
+    let mut changed = Vec::new();
+    let mut unindexed_files = Vec::new();
+
+    for file in parse_unified_diff(diff) {
+        let Some(record) = db.get_file_by_path(repo_id, &file.path)? else {
+            unindexed_files.push(file.path);
+            continue;
+        };
+        let symbols = db.get_symbols_by_file(record.id)?;
+        let touched: Vec<_> = symbols
+            .iter()
+            .filter(|s| file.lines(side).iter().any(|&l| s.start_line <= l && l <= s.end_line))
+            .collect();
+        // A touched method implies its touched class; report only the method.
+        let parents: HashSet<i64> = touched.iter().filter_map(|s| s.parent_id).collect();
+        changed.extend(touched.into_iter().filter(|s| !parents.contains(&s.id)).map(|s| ChangedSymbol {
+            id: s.id,
+            name: s.name.clone(),
+            kind: s.kind.clone(),
+            file_path: record.path.clone(),
+            start_line: s.start_line,
+            end_line: s.end_line,
+        }));
+    }
+
+    let root_ids: Vec<i64> = changed.iter().map(|s| s.id).collect();
+    let ImpactReport { nodes: impacted, truncated } = GraphEngine::new(db).impact_report(&root_ids, max_depth)?;
+
+    let mut tests = Vec::new();
+    let mut seen_tests = HashSet::new();
+    for id in root_ids.iter().chain(impacted.iter().map(|n| &n.id)) {
+        for link in db.tests_for_symbol_id(*id)? {
+            if seen_tests.insert((link.test_file.clone(), link.test.clone())) {
+                tests.push(link);
+            }
+        }
+    }
+
+    Ok(DiffImpact { changed, impacted, truncated, tests, unindexed_files })
+}
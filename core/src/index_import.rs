@@ -0,0 +1,627 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::db::Database;
+use crate::deny::DenyList;
+
+// ---------------------------------------------------------------------------
+// ctags / LSIF import
+// ---------------------------------------------------------------------------
+//
+// Languages without a tree-sitter grammar can still be searched, skeletoned
+// and annotated with memories by importing a dump another tool produced:
+//
+// - ctags: a classic `tags` file or Universal Ctags `--output-format=json`.
+//   Symbols only; ctags records no references.
+// - LSIF: the JSON-lines (or JSON array) graph emitted by `lsif-*` indexers.
+//   Definitions come from ranges tagged `definition`; every other range whose
+//   result set leads to a definition becomes an edge from the innermost
+//   definition enclosing it — `calls` for functions, methods and
+//   constructors, `type_ref` otherwise.
+//
+// Imported files belong to the repository at the dump's root and carry the
+// hash `imported`, which the indexer's removal passes leave alone, since the
+// files are never part of a walk. Their symbols have `source = 'imported'`
+// and no body. A file the indexer parses natively is skipped by the import,
+// and once a grammar exists the next index run replaces the imported
+// symbols, relinking memories by name as for any re-index. Re-importing a
+// dump replaces the previous import and drops files it no longer lists.
+// Paths matching `[index] deny_paths` are never imported. A root that is not
+// indexed yet gets a repository named after its directory, suffixed `-2`,
+// `-3`… when another repository already has that name.
+
+/// `files.hash` of a file whose symbols were imported rather than parsed.
+pub const IMPORTED_HASH: &str = "imported";
+
+/// One definition from a dump. Paths are repo-relative; lines are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedSymbol {
+    pub path: String,
+    pub name: String,
+    pub qualified_name: String,
+    pub kind: String,
+    pub signature: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub language: String,
+}
+
+/// A reference between two entries of `ImportedIndex::symbols`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImportedEdge {
+    pub source: usize,
+    pub target: usize,
+    pub kind: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportedIndex {
+    pub symbols: Vec<ImportedSymbol>,
+    pub edges: Vec<ImportedEdge>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Ctags,
+    Lsif,
+}
+
+impl DumpFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "ctags" | "tags" => Ok(Self::Ctags),
+            "lsif" => Ok(Self::Lsif),
+            other => bail!("unknown dump format '{other}': expected ctags or lsif"),
+        }
+    }
+
+    /// LSIF when the first entry is a graph vertex or edge, else ctags.
+    pub fn detect(text: &str) -> Self {
+        let head = text.trim_start().trim_start_matches('[').trim_start();
+        let first = head.lines().next().unwrap_or("").trim_end_matches(',');
+        match serde_json::from_str::<Value>(first) {
+            Ok(v) if v.get("type").and_then(Value::as_str).is_some_and(|t| t == "vertex" || t == "edge") => {
+                Self::Lsif
+            }
+            _ => Self::Ctags,
+        }
+    }
+}
+
+/// What an import stored.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportStats {
+    pub files: usize,
+    pub symbols: usize,
+    pub edges: usize,
+    /// Files in the dump that the indexer parses natively.
+    pub skipped_files: usize,
+    /// Files of a previous import that this dump no longer lists.
+    pub removed_files: usize,
+    /// Files in the dump matching `[index] deny_paths`.
+    pub denied_files: usize,
+}
+
+/// Read and parse the dump at `path`; `format` None detects it.
+pub fn load(path: &Path, format: Option<DumpFormat>, root: &Path) -> Result<ImportedIndex> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    match format.unwrap_or_else(|| DumpFormat::detect(&text)) {
+        DumpFormat::Ctags => parse_ctags(&text, root),
+        DumpFormat::Lsif => parse_lsif(&text, root),
+    }
+    .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// A dump path relative to `root`: absolute paths and `file://` URIs under
+/// it are stripped, everything else is only normalized.
+fn relative_path(path: &str, root: &Path) -> String {
+    let path = path.strip_prefix("file://").unwrap_or(path);
+    let path = percent_decode(path);
+    crate::paths::strip_root(&path, &root.to_string_lossy())
+        .unwrap_or_else(|| crate::paths::normalize_path(path.trim_start_matches("./")))
+}
+
+/// Undo `%XX` escapes in a URI path.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Language of a file without one recorded in the dump: its extension.
+fn language_of(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// -- ctags ------------------------------------------------------------------
+
+/// ctags kinds that are not definitions worth indexing.
+const SKIPPED_CTAGS_KINDS: &[&str] = &["local", "parameter", "label", "prototype", "externvar", "file"];
+
+/// Focal's name for a ctags kind: long names as Universal Ctags prints them
+/// with `--fields=+K`, and the unambiguous one-letter defaults.
+fn ctags_kind(kind: &str) -> String {
+    match kind {
+        "f" | "func" | "subroutine" | "procedure" => "function",
+        "c" => "class",
+        "s" => "struct",
+        "i" => "interface",
+        "g" => "enum",
+        "t" | "typedef" | "alias" => "type",
+        "v" | "var" => "variable",
+        "n" | "namespace" | "package" => "module",
+        "member" => "field",
+        "enumerator" => "constant",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Fields naming an enclosing scope (`class:Foo`) in a classic tags line.
+const SCOPE_FIELDS: &[&str] = &[
+    "class", "struct", "namespace", "module", "interface", "enum", "function", "method", "union", "package",
+    "impl", "trait",
+];
+
+#[derive(Default)]
+struct CtagsEntry {
+    name: String,
+    path: String,
+    pattern: String,
+    line: Option<i64>,
+    end: Option<i64>,
+    kind: String,
+    scope: String,
+    signature: String,
+    language: String,
+}
+
+/// Parse a classic tags file or Universal Ctags JSON output. Entries without
+/// a `line` field are located by their search pattern in the file under
+/// `root`, and dropped when it can't be found.
+pub fn parse_ctags(text: &str, root: &Path) -> Result<ImportedIndex> {
+    let mut index = ImportedIndex::default();
+    let mut sources: HashMap<String, Option<String>> = HashMap::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() || line.starts_with("!_TAG_") {
+            continue;
+        }
+        let entry = if line.starts_with('{') {
+            match ctags_json_entry(line).with_context(|| format!("line {}", n + 1))? {
+                Some(entry) => entry,
+                None => continue,
+            }
+        } else {
+            ctags_line_entry(line).with_context(|| format!("line {}: not a tags entry", n + 1))?
+        };
+        if SKIPPED_CTAGS_KINDS.contains(&entry.kind.as_str()) {
+            continue;
+        }
+        let path = relative_path(&entry.path, root);
+        let start_line = match entry.line {
+            Some(line) => line,
+            None => {
+                let source = sources
+                    .entry(path.clone())
+                    .or_insert_with(|| std::fs::read_to_string(root.join(&path)).ok());
+                match source.as_deref().and_then(|s| find_pattern(s, &entry.pattern)) {
+                    Some(line) => line,
+                    None => continue,
+                }
+            }
+        };
+        let qualified_name = match entry.scope.as_str() {
+            "" => entry.name.clone(),
+            scope => format!("{scope}.{}", entry.name),
+        };
+        let language = match entry.language.as_str() {
+            "" => language_of(&path),
+            language => language.to_lowercase(),
+        };
+        let signature = match entry.signature.as_str() {
+            "" => entry.name.clone(),
+            signature => format!("{}{signature}", entry.name),
+        };
+        index.symbols.push(ImportedSymbol {
+            path,
+            qualified_name,
+            kind: ctags_kind(&entry.kind),
+            signature,
+            start_line,
+            end_line: entry.end.unwrap_or(start_line).max(start_line),
+            language,
+            name: entry.name,
+        });
+    }
+    Ok(index)
+}
+
+/// One `{"_type": "tag", ...}` line; None for pseudo-tags.
+fn ctags_json_entry(line: &str) -> Result<Option<CtagsEntry>> {
+    let v: Value = serde_json::from_str(line)?;
+    if v.get("_type").and_then(Value::as_str) != Some("tag") {
+        return Ok(None);
+    }
+    let str_field = |key: &str| v.get(key).and_then(Value::as_str).unwrap_or("").to_string();
+    let (Some(name), Some(path)) = (v.get("name").and_then(Value::as_str), v.get("path").and_then(Value::as_str))
+    else {
+        bail!("tag without name or path");
+    };
+    Ok(Some(CtagsEntry {
+        name: name.to_string(),
+        path: path.to_string(),
+        pattern: str_field("pattern"),
+        line: v.get("line").and_then(Value::as_i64),
+        end: v.get("end").and_then(Value::as_i64),
+        kind: str_field("kind"),
+        scope: str_field("scope"),
+        signature: str_field("signature"),
+        language: str_field("language"),
+    }))
+}
+
+/// `name<TAB>path<TAB>address;"<TAB>kind<TAB>key:value...`
+fn ctags_line_entry(line: &str) -> Option<CtagsEntry> {
+    let mut parts = line.splitn(3, '\t');
+    let name = parts.next()?;
+    let path = parts.next()?;
+    let rest = parts.next()?;
+    let (address, fields) = match rest.split_once(";\"\t") {
+        Some((address, fields)) => (address, fields),
+        None => (rest.trim_end_matches(";\""), ""),
+    };
+    let mut entry = CtagsEntry {
+        name: name.to_string(),
+        path: path.to_string(),
+        ..CtagsEntry::default()
+    };
+    match address.parse::<i64>() {
+        Ok(line) => entry.line = Some(line),
+        Err(_) => entry.pattern = address.to_string(),
+    }
+    for field in fields.split('\t').filter(|f| !f.is_empty()) {
+        match field.split_once(':') {
+            None => entry.kind = field.to_string(),
+            Some(("kind", kind)) => entry.kind = kind.to_string(),
+            Some(("line", line)) => entry.line = line.parse().ok(),
+            Some(("end", end)) => entry.end = end.parse().ok(),
+            Some(("signature", signature)) => entry.signature = signature.to_string(),
+            Some(("language", language)) => entry.language = language.to_string(),
+            Some(("scope", scope)) => entry.scope = scope.split_once(':').map_or(scope, |(_, s)| s).to_string(),
+            Some((key, scope)) if SCOPE_FIELDS.contains(&key) => entry.scope = scope.to_string(),
+            Some(_) => {}
+        }
+    }
+    Some(entry)
+}
+
+/// 1-based line a ctags search pattern (`/^text$/`) matches in `source`.
+fn find_pattern(source: &str, pattern: &str) -> Option<i64> {
+    let body = pattern
+        .strip_prefix('/')
+        .and_then(|p| p.strip_suffix('/'))
+        .or_else(|| pattern.strip_prefix('?').and_then(|p| p.strip_suffix('?')))?;
+    let (anchored_start, body) = match body.strip_prefix('^') {
+        Some(body) => (true, body),
+        None => (false, body),
+    };
+    let (anchored_end, body) = match body.strip_suffix('$') {
+        Some(body) if !body.ends_with('\\') => (true, body),
+        _ => (false, body),
+    };
+    let needle = body.replace("\\/", "/").replace("\\?", "?").replace("\\\\", "\\");
+    let position = source.lines().position(|line| match (anchored_start, anchored_end) {
+        (true, true) => line == needle,
+        (true, false) => line.starts_with(&needle),
+        (false, true) => line.ends_with(&needle),
+        (false, false) => line.contains(&needle),
+    })?;
+    Some(position as i64 + 1)
+}
+
+// -- LSIF -------------------------------------------------------------------
+
+/// Focal's name for an LSP `SymbolKind`; None for kinds not worth indexing
+/// (type parameters).
+fn lsp_kind(kind: i64) -> Option<&'static str> {
+    Some(match kind {
+        1 => "file",
+        2..=4 => "module",
+        5 => "class",
+        6 | 9 => "method",
+        7 => "property",
+        8 => "field",
+        10 => "enum",
+        11 => "interface",
+        12 => "function",
+        14 | 22 => "constant",
+        23 => "struct",
+        24 | 25 => "function",
+        26 => return None,
+        _ => "variable",
+    })
+}
+
+struct LsifRange {
+    start_line: i64,
+    /// Lines the definition spans (`fullRange`), else the range itself.
+    span: (i64, i64),
+    definition: Option<(String, i64)>,
+}
+
+/// Parse an LSIF dump; see the module comment for what becomes an edge.
+pub fn parse_lsif(text: &str, root: &Path) -> Result<ImportedIndex> {
+    let entries: Vec<Value> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text)?
+    } else {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .enumerate()
+            .map(|(n, l)| serde_json::from_str(l).with_context(|| format!("line {}", n + 1)))
+            .collect::<Result<_>>()?
+    };
+
+    let id_of = |v: &Value| v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok()));
+    let line_of = |v: &Value, end: &str| v.get(end).and_then(|p| p.get("line")).and_then(Value::as_i64).map(|l| l + 1);
+
+    let mut project_root: Option<String> = None;
+    let mut documents: HashMap<i64, (String, String)> = HashMap::new();
+    let mut ranges: HashMap<i64, LsifRange> = HashMap::new();
+    let mut range_document: HashMap<i64, i64> = HashMap::new();
+    let mut next: HashMap<i64, i64> = HashMap::new();
+    let mut reference_results: HashMap<i64, i64> = HashMap::new();
+    let mut reference_items: Vec<(i64, Vec<i64>)> = Vec::new();
+
+    for entry in &entries {
+        let Some(id) = entry.get("id").and_then(id_of) else { continue };
+        let label = entry.get("label").and_then(Value::as_str).unwrap_or("");
+        match (entry.get("type").and_then(Value::as_str), label) {
+            (Some("vertex"), "metaData") => {
+                project_root = entry.get("projectRoot").and_then(Value::as_str).map(String::from);
+            }
+            (Some("vertex"), "document") => {
+                let uri = entry.get("uri").and_then(Value::as_str).unwrap_or("").to_string();
+                let language = entry.get("languageId").and_then(Value::as_str).unwrap_or("").to_string();
+                documents.insert(id, (uri, language));
+            }
+            (Some("vertex"), "range") => {
+                let (Some(start), Some(end)) = (line_of(entry, "start"), line_of(entry, "end")) else { continue };
+                let tag = entry.get("tag");
+                let definition = tag
+                    .filter(|t| matches!(t.get("type").and_then(Value::as_str), Some("definition" | "declaration")))
+                    .and_then(|t| Some((t.get("text")?.as_str()?.to_string(), t.get("kind")?.as_i64()?)));
+                let full = tag.and_then(|t| t.get("fullRange"));
+                let span = match full.and_then(|f| Some((line_of(f, "start")?, line_of(f, "end")?))) {
+                    Some(span) => span,
+                    None => (start, end),
+                };
+                ranges.insert(id, LsifRange { start_line: start, span, definition });
+            }
+            (Some("edge"), _) => {
+                let out = entry.get("outV").and_then(id_of);
+                let ins: Vec<i64> = match entry.get("inVs") {
+                    Some(Value::Array(vs)) => vs.iter().filter_map(id_of).collect(),
+                    _ => entry.get("inV").and_then(id_of).into_iter().collect(),
+                };
+                let Some(out) = out else { continue };
+                match label {
+                    "contains" => range_document.extend(ins.iter().map(|&r| (r, out))),
+                    "next" => next.extend(ins.first().map(|&set| (out, set))),
+                    "textDocument/references" => {
+                        reference_results.extend(ins.first().map(|&result| (result, out)));
+                    }
+                    "item" => {
+                        if let Some(document) = entry.get("document").and_then(id_of) {
+                            range_document.extend(ins.iter().map(|&r| (r, document)));
+                        }
+                        if entry.get("property").and_then(Value::as_str) == Some("references") {
+                            reference_items.push((out, ins));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let project_root = project_root.map(|r| percent_decode(r.strip_prefix("file://").unwrap_or(&r)));
+    let document_path = |document: i64| -> Option<(String, String)> {
+        let (uri, language) = documents.get(&document)?;
+        let decoded = percent_decode(uri.strip_prefix("file://").unwrap_or(uri));
+        let path = project_root
+            .as_deref()
+            .and_then(|r| crate::paths::strip_root(&decoded, r))
+            .unwrap_or_else(|| relative_path(uri, root));
+        let language = if language.is_empty() { language_of(&path) } else { language.clone() };
+        Some((path, language))
+    };
+    // Every result set a range leads to, following `next` chains.
+    let result_sets = |mut id: i64| {
+        let mut sets = Vec::new();
+        while let Some(&set) = next.get(&id) {
+            if sets.contains(&set) {
+                break;
+            }
+            sets.push(set);
+            id = set;
+        }
+        sets
+    };
+
+    // Definitions, in document and line order for stable ids.
+    let mut definitions: Vec<(i64, &LsifRange)> =
+        ranges.iter().filter(|(_, r)| r.definition.is_some()).map(|(&id, r)| (id, r)).collect();
+    definitions.sort_by_key(|(id, r)| (range_document.get(id).copied(), r.start_line, *id));
+
+    let mut index = ImportedIndex::default();
+    let mut by_set: HashMap<i64, usize> = HashMap::new();
+    let mut by_document: HashMap<i64, Vec<usize>> = HashMap::new();
+    let mut definition_ranges: HashSet<i64> = HashSet::new();
+    for (range_id, range) in definitions {
+        let Some(&document) = range_document.get(&range_id) else { continue };
+        let Some((path, language)) = document_path(document) else { continue };
+        let Some((name, kind)) = &range.definition else { continue };
+        let Some(kind) = lsp_kind(*kind) else { continue };
+        let idx = index.symbols.len();
+        index.symbols.push(ImportedSymbol {
+            path,
+            name: name.clone(),
+            qualified_name: name.clone(),
+            kind: kind.to_string(),
+            signature: name.clone(),
+            start_line: range.span.0,
+            end_line: range.span.1.max(range.span.0),
+            language,
+        });
+        definition_ranges.insert(range_id);
+        for set in result_sets(range_id) {
+            by_set.entry(set).or_insert(idx);
+        }
+        by_document.entry(document).or_default().push(idx);
+    }
+
+    // References: ranges whose result set resolves to a definition, either
+    // through their own `next` chain or a references item.
+    let mut references: Vec<(i64, usize)> = Vec::new();
+    for &range_id in ranges.keys() {
+        if let Some(target) = result_sets(range_id).iter().find_map(|set| by_set.get(set)) {
+            references.push((range_id, *target));
+        }
+    }
+    for (result, items) in &reference_items {
+        let target = reference_results.get(result).and_then(|&set| {
+            std::iter::once(set).chain(result_sets(set)).find_map(|s| by_set.get(&s))
+        });
+        if let Some(&target) = target {
+            references.extend(items.iter().map(|&r| (r, target)));
+        }
+    }
+
+    let mut edges: HashSet<ImportedEdge> = HashSet::new();
+    for (range_id, target) in references {
+        if definition_ranges.contains(&range_id) {
+            continue;
+        }
+        let (Some(document), Some(range)) = (range_document.get(&range_id), ranges.get(&range_id)) else { continue };
+        let line = range.start_line;
+        let enclosing = by_document.get(document).and_then(|symbols| {
+            symbols
+                .iter()
+                .filter(|&&i| index.symbols[i].start_line <= line && line <= index.symbols[i].end_line)
+                .min_by_key(|&&i| index.symbols[i].end_line - index.symbols[i].start_line)
+        });
+        let Some(&source) = enclosing else { continue };
+        if source == target {
+            continue;
+        }
+        let kind = match index.symbols[target].kind.as_str() {
+            "function" | "method" => "calls",
+            _ => "type_ref",
+        };
+        edges.insert(ImportedEdge { source, target, kind: kind.to_string() });
+    }
+    index.edges = edges.into_iter().collect();
+    index.edges.sort_by_key(|e| (e.source, e.target, e.kind.clone()));
+    Ok(index)
+}
+
+// -- Import -----------------------------------------------------------------
+
+/// Store `index` under the repository rooted at `root`, replacing the
+/// previous import; files `deny` matches are left out. See the module
+/// comment for how it meets native indexing.
+pub fn import(db: &Database, root: &Path, index: &ImportedIndex, deny: &DenyList) -> Result<ImportStats> {
+    let root = crate::paths::canonicalize(root).with_context(|| format!("failed to canonicalize {}", root.display()))?;
+    let root_str = root.to_string_lossy().to_string();
+
+    let mut by_path: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    let mut denied = HashSet::new();
+    for (i, symbol) in index.symbols.iter().enumerate() {
+        if deny.is_denied(&symbol.path) {
+            denied.insert(symbol.path.as_str());
+        } else {
+            by_path.entry(symbol.path.as_str()).or_default().push(i);
+        }
+    }
+
+    db.with_transaction(|| {
+        let repo_id = match db.get_repository_by_path(&root_str)? {
+            Some(repo) => repo.id,
+            None => {
+                let name = root.file_name().map_or_else(|| root_str.clone(), |n| n.to_string_lossy().to_string());
+                db.create_repository_unique(&name, &root_str)?
+            }
+        };
+        let mut stats = ImportStats { denied_files: denied.len(), ..ImportStats::default() };
+        for file in db.get_files_for_repo(repo_id)? {
+            if file.hash == IMPORTED_HASH && !by_path.contains_key(file.path.as_str()) {
+                db.remove_file(repo_id, &file.path)?;
+                stats.removed_files += 1;
+            }
+        }
+        if stats.removed_files > 0 {
+            db.mark_orphaned_path_memories_stale()?;
+        }
+
+        let mut ids: Vec<Option<i64>> = vec![None; index.symbols.len()];
+        for (path, symbols) in &by_path {
+            if db.get_file_by_path(repo_id, path)?.is_some_and(|f| f.hash != IMPORTED_HASH) {
+                stats.skipped_files += 1;
+                continue;
+            }
+            let file_id = db.upsert_file(repo_id, path, &index.symbols[symbols[0]].language, IMPORTED_HASH)?;
+            db.mark_memories_stale_for_file(file_id)?;
+            let memory_links = db.collect_memory_symbol_names(file_id)?;
+            db.delete_edges_by_file(file_id)?;
+            db.delete_symbols_by_file(file_id)?;
+            for &i in symbols {
+                let s = &index.symbols[i];
+                ids[i] = Some(db.insert_imported_symbol(
+                    file_id,
+                    &s.name,
+                    &s.qualified_name,
+                    &s.kind,
+                    &s.signature,
+                    s.start_line,
+                    s.end_line,
+                )?);
+            }
+            if !memory_links.is_empty() {
+                db.relink_memories_to_symbols(file_id, &memory_links)?;
+            }
+            stats.files += 1;
+            stats.symbols += symbols.len();
+        }
+
+        for edge in &index.edges {
+            if let (Some(source), Some(target)) = (ids[edge.source], ids[edge.target]) {
+                db.insert_edge(source, target, &edge.kind)?;
+                stats.edges += 1;
+            }
+        }
+        Ok(stats)
+    })
+}
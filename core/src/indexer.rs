@@ -1,12 +1,28 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
 use sha2::{Digest, Sha256};
-use walkdir::WalkDir;
-
-use crate::db::Database;
-use crate::grammar::{ExtractedSymbol, GrammarRegistry};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::barrels::{Barrels, Module, ModuleLinks};
+use crate::builtins::BuiltinFilter;
+use crate::db::{Database, FileFacts, Symbol, SymbolDetails, MAX_CALL_SITES};
+use crate::deny::DenyList;
+use crate::dependencies::{imported_modules, imported_names, module_matches, parse_dependencies, Dependency};
+use crate::encoding::{decode_source, DecodedSource};
+use crate::env::{scan_env_usages, EnvUsage};
+use crate::module_docs::{self, is_package_entry, is_readme};
+use crate::packages::{is_manifest, package_for_path, parse_manifest};
+use crate::redact::{count_secrets, redact_symbols};
+use crate::sniff::{sniff, Content};
+use crate::sql::{extract_sql_queries, SqlQuery};
+use crate::generated::is_generated;
+use crate::git::RevisionTree;
+use crate::guardrails::{Guardrails, LimitExceeded};
+use crate::index_import::IMPORTED_HASH;
+use crate::grammar::{go, ErrorSite, ExtractedReference, ExtractedRoute, ExtractedSymbol, Grammar, GrammarRegistry};
 
 // ---------------------------------------------------------------------------
 // Stats
@@ -16,11 +32,278 @@ use crate::grammar::{ExtractedSymbol, GrammarRegistry};
 pub struct IndexStats {
     pub files_indexed: usize,
     pub files_skipped: usize,
+    /// Oversized or minified files indexed without symbol bodies (included
+    /// in `files_indexed`).
+    pub files_partial: usize,
+    /// Minified files (included in `files_partial`).
+    pub files_minified: usize,
+    /// Binary files with a source extension (included in `files_skipped`).
+    pub files_binary: usize,
+    /// Indexed files gone from disk, removed by `reindex_changed` and
+    /// `reindex_paths`.
+    pub files_removed: usize,
     pub symbols_extracted: usize,
     pub edges_created: usize,
+    /// Links from test functions to the symbols they exercise.
+    pub test_links: usize,
+    /// Secrets masked in stored symbol bodies and signatures.
+    pub redactions: usize,
+    /// Directories skipped as generated output, as
+    /// `path (N files like pattern)`.
+    pub generated_dirs: Vec<String>,
     pub errors: Vec<String>,
 }
 
+/// Files a rescan has to touch, from `Indexer::plan_rescan`. Paths are
+/// repo-relative; `changed` pairs them with the absolute path.
+pub(crate) struct RescanPlan {
+    pub repo_id: i64,
+    /// New files and files whose hash differs from the stored one.
+    pub changed: Vec<(String, PathBuf)>,
+    /// Indexed files gone from disk.
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// What `index_directory` would do to a tree, from `Indexer::dry_run`.
+#[derive(Debug, Default, Serialize)]
+pub struct DryRun {
+    pub root: String,
+    /// Files that would be parsed, by language.
+    pub languages: BTreeMap<String, FileCount>,
+    /// Oversized or minified files that would be indexed without bodies
+    /// (included in `languages`).
+    pub partial: usize,
+    /// Generated files that would be indexed but left out of search
+    /// (included in `languages`).
+    pub generated: usize,
+    /// Files that would not be indexed, by reason: `unsupported`, `denied`,
+    /// `oversized`, `binary` or `unreadable`.
+    pub skipped: BTreeMap<&'static str, SkippedFiles>,
+    /// Directories pruned by the exclude list.
+    pub excluded_dirs: Vec<String>,
+    /// Directories skipped as generated output, as
+    /// `path (N files like pattern)`.
+    pub generated_dirs: Vec<String>,
+    /// Rough size the index would grow by, from the bytes parsed.
+    pub estimated_db_bytes: u64,
+    /// The file cap `index_directory` would refuse this tree over, unless
+    /// the caps are waived for it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_cap: Option<usize>,
+}
+
+impl DryRun {
+    /// Files that would be parsed.
+    pub fn files(&self) -> usize {
+        self.languages.values().map(|c| c.files).sum()
+    }
+
+    /// Bytes of source that would be parsed.
+    pub fn bytes(&self) -> u64 {
+        self.languages.values().map(|c| c.bytes).sum()
+    }
+
+    fn skip(&mut self, reason: &'static str, rel_path: String) {
+        let skipped = self.skipped.entry(reason).or_default();
+        skipped.files += 1;
+        if skipped.examples.len() < DRY_RUN_EXAMPLES {
+            skipped.examples.push(rel_path);
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct FileCount {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SkippedFiles {
+    pub files: usize,
+    /// The first few, as repo-relative paths.
+    pub examples: Vec<String>,
+}
+
+/// Outcome of activating a different branch namespace.
+#[derive(Debug, Default)]
+pub struct BranchSwitch {
+    pub from: String,
+    pub to: String,
+    /// Files whose previous-branch version was parked.
+    pub parked: usize,
+    /// Files restored from the new branch's parked snapshots without parsing.
+    pub restored: usize,
+    /// Files parsed from disk (no usable snapshot).
+    pub reindexed: usize,
+}
+
+/// What `Indexer::resurrect` did with a path's tombstone.
+enum Tombstoned {
+    /// Restored as the live file with this id.
+    Restored(i64),
+    /// Changed (or absent); memory links to carry over. The tombstone stays
+    /// until the re-parsed file is written.
+    Links(Vec<(i64, String, String)>),
+}
+
+// ---------------------------------------------------------------------------
+// Single-file parsing
+// ---------------------------------------------------------------------------
+
+/// The database-free half of `Indexer::index_file`: reads, hashes and parses
+/// one file. Callers sharing the database behind a lock run it with the lock
+/// released and hand the result to `Indexer::write_parsed`.
+pub(crate) struct FileParser<'a> {
+    pub(crate) registry: &'a GrammarRegistry,
+    pub(crate) redact_secrets: bool,
+    pub(crate) deny: &'a DenyList,
+    pub(crate) guardrails: &'a Guardrails,
+    pub(crate) max_file_size: u64,
+    pub(crate) max_partial_file_size: u64,
+}
+
+/// A file read and hashed by `FileParser::read`, not yet parsed.
+pub(crate) struct SourceFile {
+    root: PathBuf,
+    rel_path: String,
+    language: String,
+    ext: String,
+    bytes: Vec<u8>,
+    hash: String,
+    content: Content,
+}
+
+/// A file parsed by `FileParser::parse`, ready for `Indexer::write_parsed`.
+pub(crate) struct ParsedFile {
+    root: PathBuf,
+    rel_path: String,
+    hash: String,
+    /// None for a binary or oversized file, which is dropped from the index.
+    parse: Option<Box<FileParse>>,
+}
+
+struct FileParse {
+    language: String,
+    source: Vec<u8>,
+    encoding: String,
+    partial: bool,
+    symbols: Vec<ExtractedSymbol>,
+    redactions: usize,
+    refs: Vec<ExtractedReference>,
+    routes: Vec<ExtractedRoute>,
+    env_usages: Vec<EnvUsage>,
+    sql_queries: Vec<SqlQuery>,
+    error_sites: Vec<ErrorSite>,
+    panics: Vec<String>,
+    syntax: Option<(usize, i64)>,
+}
+
+impl FileParser<'_> {
+    /// Read and hash `file_path` under `root`. None when it isn't indexed:
+    /// no grammar, denied, or in a generated directory. A file over the
+    /// partial size limit is returned unread, so `parse` drops its old copy.
+    pub(crate) fn read(&self, file_path: &Path, root: &Path) -> Result<Option<SourceFile>> {
+        let root = crate::paths::canonicalize(root)?;
+        let Some(ext) = file_path.extension().and_then(|e| e.to_str()) else {
+            return Ok(None);
+        };
+        if self.registry.for_extension(ext).is_none() {
+            return Ok(None);
+        }
+        let rel_path = crate::paths::repo_relative(file_path, &root);
+        if self.deny.is_denied(&rel_path) {
+            return Ok(None);
+        }
+        if in_generated_dir(self.guardrails, self.registry, file_path, &root) {
+            return Ok(None);
+        }
+
+        let language = self.registry.detect_language(file_path).unwrap_or(ext).to_string();
+        if std::fs::metadata(file_path)?.len() > self.max_partial_file_size {
+            // Left unread and unhashed; treated as binary so the parse drops
+            // an indexed copy instead of keeping its old symbols.
+            return Ok(Some(SourceFile {
+                root,
+                rel_path,
+                language,
+                ext: ext.to_string(),
+                bytes: Vec::new(),
+                hash: String::new(),
+                content: Content::Binary,
+            }));
+        }
+        let bytes = std::fs::read(file_path)?;
+        let content = sniff(&bytes);
+        let hash = {
+            let mut hasher = Sha256::new();
+            hasher.update(&bytes);
+            format!("{:x}", hasher.finalize())
+        };
+        Ok(Some(SourceFile { root, rel_path, language, ext: ext.to_string(), bytes, hash, content }))
+    }
+
+    /// Parse `file` and extract everything `Indexer::write_parsed` stores.
+    pub(crate) fn parse(&self, file: SourceFile) -> Result<ParsedFile> {
+        let SourceFile { root, rel_path, language, ext, bytes, hash, content } = file;
+        if content == Content::Binary {
+            return Ok(ParsedFile { root, rel_path, hash, parse: None });
+        }
+        let grammar = self
+            .registry
+            .for_extension(&ext)
+            .ok_or_else(|| anyhow::anyhow!("no grammar for .{ext}"))?;
+        let partial = content == Content::Minified || bytes.len() as u64 > self.max_file_size;
+        let DecodedSource { text: source, encoding } = decode_source(&bytes);
+
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&grammar.language())?;
+        let tree = parser
+            .parse(&source, None)
+            .ok_or_else(|| anyhow::anyhow!("parse returned None"))?;
+        let mut panics = Vec::new();
+        let mut symbols = guarded("symbol extraction", &mut panics, || grammar.extract_symbols(&source, &tree));
+        let redactions = if self.redact_secrets {
+            redact_symbols(&mut symbols);
+            count_secrets(&String::from_utf8_lossy(&source))
+        } else {
+            0
+        };
+        let refs = guarded("reference extraction", &mut panics, || {
+            grammar.extract_references(&source, &tree)
+        });
+        let routes = guarded("route extraction", &mut panics, || grammar.extract_routes(&source, &tree));
+        let env_usages = guarded("env scan", &mut panics, || {
+            scan_env_usages(&String::from_utf8_lossy(&source), &language)
+        });
+        let sql_queries = guarded("SQL extraction", &mut panics, || extract_sql_queries(&source, &tree));
+        let error_sites = guarded("error-site extraction", &mut panics, || {
+            grammar.extract_error_sites(&source, &tree)
+        });
+        for message in &panics {
+            tracing::warn!(path = %root.join(&rel_path).display(), "{message}");
+        }
+        let syntax = syntax_errors(&tree);
+        let parse = FileParse {
+            language,
+            source: source.into_owned(),
+            encoding,
+            partial,
+            symbols,
+            redactions,
+            refs,
+            routes,
+            env_usages,
+            sql_queries,
+            error_sites,
+            panics,
+            syntax,
+        };
+        Ok(ParsedFile { root, rel_path, hash, parse: Some(Box::new(parse)) })
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Indexer
 // ---------------------------------------------------------------------------
@@ -30,8 +313,30 @@ pub struct Indexer<'a> {
     registry: &'a GrammarRegistry,
     exclude_patterns: HashSet<String>,
     max_file_size: u64,
+    max_partial_file_size: u64,
+    builtins: BuiltinFilter,
+    redact_secrets: bool,
+    deny: DenyList,
+    guardrails: Guardrails,
+    force: bool,
 }
 
+/// Example paths kept per skip reason in a `DryRun`.
+const DRY_RUN_EXAMPLES: usize = 5;
+
+/// Index bytes per byte of parsed source, bodies and FTS included (2.2 to
+/// 3.0 on Rust codebases), and per byte of a file indexed without bodies.
+const DB_BYTES_PER_SOURCE_BYTE: f64 = 2.5;
+const DB_BYTES_PER_PARTIAL_BYTE: f64 = 0.1;
+
+/// Size above which a file is indexed without symbol bodies (500 KB), and
+/// above which it is skipped (20 MB), unless configured otherwise.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 500 * 1024;
+pub const DEFAULT_MAX_PARTIAL_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// Parsed files between symbol-cap checks during a walk.
+const SYMBOL_CHECK_INTERVAL: usize = 1024;
+
 impl<'a> Indexer<'a> {
     pub fn new(db: &'a Database, registry: &'a GrammarRegistry) -> Self {
         Self {
@@ -45,7 +350,13 @@ impl<'a> Indexer<'a> {
                 "dist".to_string(),
                 "__pycache__".to_string(),
             ]),
-            max_file_size: 500 * 1024, // 500 KB
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_partial_file_size: DEFAULT_MAX_PARTIAL_FILE_SIZE,
+            builtins: BuiltinFilter::default(),
+            redact_secrets: true,
+            deny: DenyList::default(),
+            guardrails: Guardrails::default(),
+            force: false,
         }
     }
 
@@ -59,11 +370,60 @@ impl<'a> Indexer<'a> {
         self
     }
 
+    /// Files larger than `max_file_size` but no larger than this are indexed
+    /// partially: names, signatures and line ranges, without symbol bodies.
+    /// Larger files are skipped.
+    pub fn with_max_partial_file_size(mut self, size: u64) -> Self {
+        self.max_partial_file_size = size;
+        self
+    }
+
+    /// Calls to names this filter reports as builtins get no edge.
+    pub fn with_builtin_filter(mut self, filter: BuiltinFilter) -> Self {
+        self.builtins = filter;
+        self
+    }
+
+    /// Mask API keys, tokens and private keys in stored bodies (default on).
+    pub fn with_redaction(mut self, enabled: bool) -> Self {
+        self.redact_secrets = enabled;
+        self
+    }
+
+    /// Never index files matching `deny` (`[index] deny_paths`). Files an
+    /// earlier run indexed are purged when a directory or revision is indexed.
+    pub fn with_deny_list(mut self, deny: DenyList) -> Self {
+        self.deny = deny;
+        self
+    }
+
+    /// File and symbol caps and generated-directory skipping (see
+    /// `guardrails`).
+    pub fn with_guardrails(mut self, guardrails: Guardrails) -> Self {
+        self.guardrails = guardrails;
+        self
+    }
+
+    /// Index past the file and symbol caps, and waive them for the
+    /// repository from now on.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Mask secrets in `symbols`; returns how many `source` contains.
+    fn redact(&self, symbols: &mut [ExtractedSymbol], source: &[u8]) -> usize {
+        if !self.redact_secrets {
+            return 0;
+        }
+        redact_symbols(symbols);
+        count_secrets(&String::from_utf8_lossy(source))
+    }
+
     /// Main entry point: walk a directory, parse supported files, store symbols,
     /// then resolve cross-file call edges.
     pub fn index_directory(&self, root: &Path) -> Result<IndexStats> {
-        let root = root
-            .canonicalize()
+        let root = crate::paths::canonicalize(root)
             .with_context(|| format!("failed to canonicalize {}", root.display()))?;
 
         let repo_name = root
@@ -72,20 +432,50 @@ impl<'a> Indexer<'a> {
             .unwrap_or_else(|| root.to_string_lossy().to_string());
 
         let root_str = root.to_string_lossy().to_string();
+        let first_index = self.db.get_repository_by_path(&root_str)?.is_none();
         let repo_id = self.db.upsert_repository(&repo_name, &root_str)?;
+        // The index now reflects the working tree, not a pinned revision.
+        self.db.set_repository_revision(repo_id, None)?;
 
-        self.db.with_transaction(|| {
+        let mut stats = self.db.with_transaction(|| {
+            // Swap in the checked-out branch's namespace before the walk, so
+            // only files that differ from its snapshot get parsed.
+            self.sync_branch(&root)?;
             let mut stats = IndexStats::default();
+            // Unreadable files are re-checked by this walk.
+            self.db.clear_index_errors(repo_id, None, Some("read"))?;
+            self.db.purge_denied_files(&self.deny)?;
+            let capped = self.capped(repo_id)?;
+            let mut packages = Vec::new();
+            let mut dependencies = Vec::new();
+            let mut docs = Vec::new();
+            let mut generated_dirs = Vec::new();
+            let mut indexable = 0;
 
             // Phase 1: walk files, parse symbols, store in DB
-            for entry in WalkDir::new(&root)
-                .into_iter()
-                .filter_entry(|e| !self.is_excluded(e.path()))
-            {
+            for entry in WalkDir::new(&root).into_iter().filter_entry(|e| {
+                if self.is_excluded(e.path()) {
+                    return false;
+                }
+                match self.generated_dir(e) {
+                    Some((pattern, files)) => {
+                        let rel_path = crate::paths::repo_relative(e.path(), &root);
+                        generated_dirs.push(format!("{rel_path} ({files} files like {pattern})"));
+                        false
+                    }
+                    None => true,
+                }
+            }) {
                 let entry = match entry {
                     Ok(e) => e,
                     Err(err) => {
+                        let rel_path = err
+                            .path()
+                            .map(|p| crate::paths::repo_relative(p, &root))
+                            .unwrap_or_default();
                         stats.errors.push(format!("walk error: {err}"));
+                        self.db
+                            .record_index_error(repo_id, &rel_path, "read", &format!("walk error: {err}"), None)?;
                         continue;
                     }
                 };
@@ -96,6 +486,24 @@ impl<'a> Indexer<'a> {
 
                 let path = entry.path();
 
+                let rel_path = crate::paths::repo_relative(path, &root);
+                if self.deny.is_denied(&rel_path) {
+                    continue;
+                }
+                if is_manifest(&rel_path) {
+                    if let Ok(text) = std::fs::read_to_string(path) {
+                        packages.extend(parse_manifest(&rel_path, &text));
+                        dependencies.extend(parse_dependencies(&rel_path, &text));
+                    }
+                    continue;
+                }
+                if is_readme(&rel_path) {
+                    if let Ok(text) = std::fs::read_to_string(path) {
+                        docs.extend(module_docs::extract(&rel_path, &text));
+                    }
+                    continue;
+                }
+
                 // Check grammar support by extension
                 let ext = match path.extension().and_then(|e| e.to_str()) {
                     Some(e) => e,
@@ -110,193 +518,1053 @@ impl<'a> Indexer<'a> {
                 let metadata = match std::fs::metadata(path) {
                     Ok(m) => m,
                     Err(err) => {
-                        stats.errors.push(format!("{}: metadata error: {err}", path.display()));
+                        self.note_error(&mut stats, repo_id, path, &rel_path, "read", &format!("metadata error: {err}"))?;
                         continue;
                     }
                 };
-                if metadata.len() > self.max_file_size {
+                if metadata.len() > self.max_partial_file_size {
                     stats.files_skipped += 1;
                     continue;
                 }
+                indexable += 1;
+                if capped && indexable > self.guardrails.max_files {
+                    return Err(self.limit_exceeded(&root, "files", indexable, self.guardrails.max_files));
+                }
+
+                // Read file
+                let source = match std::fs::read(path) {
+                    Ok(s) => s,
+                    Err(err) => {
+                        self.note_error(&mut stats, repo_id, path, &rel_path, "read", &format!("read error: {err}"))?;
+                        continue;
+                    }
+                };
+
+                if is_package_entry(&rel_path) {
+                    docs.extend(module_docs::extract(&rel_path, &String::from_utf8_lossy(&source)));
+                }
+                let parsed = stats.files_indexed;
+                self.index_source(repo_id, path, &rel_path, grammar, &source, &mut stats)?;
+                if capped && stats.files_indexed > parsed && stats.files_indexed % SYMBOL_CHECK_INTERVAL == 0 {
+                    self.check_symbol_cap(repo_id, &root)?;
+                }
+            }
+            if capped {
+                self.check_symbol_cap(repo_id, &root)?;
+            }
+            stats.generated_dirs = generated_dirs;
+
+            // Phase 2: resolve cross-file edges (and imports of dependencies)
+            self.db.replace_dependencies(repo_id, &dependencies)?;
+            let edge_count =
+                self.resolve_edges(repo_id, &mut |rel| std::fs::read(root.join(rel)).ok(), &mut stats)?;
+            self.db.replace_packages(repo_id, &packages)?;
+            self.db.replace_module_docs(repo_id, &docs)?;
+            stats.edges_created = edge_count;
+            stats.test_links = crate::test_map::map_tests(self.db, repo_id)?;
+            crate::graph_snapshot::record(self.db, repo_id)?;
+            crate::coupling::refresh(self.db, repo_id)?;
+
+            Ok(stats)
+        })?;
+        if first_index {
+            self.resolve_project_dependents(repo_id, &mut stats)?;
+        }
+        Ok(stats)
+    }
+
+    /// Re-resolve the edges of every repository sharing a project with a
+    /// newly indexed `repo_id`: their references to it found no target
+    /// when they were indexed. Errors are collected into `stats`.
+    fn resolve_project_dependents(&self, repo_id: i64, stats: &mut IndexStats) -> Result<()> {
+        let siblings = self.db.project_sibling_repo_ids(repo_id)?;
+        if siblings.is_empty() {
+            return Ok(());
+        }
+        for repo in self.db.list_repositories()?.into_iter().filter(|r| siblings.contains(&r.id)) {
+            let root = PathBuf::from(&repo.root_path);
+            let mut sibling_stats = IndexStats::default();
+            let resolved = self.db.with_transaction(|| {
+                self.resolve_edges(repo.id, &mut |rel| std::fs::read(root.join(rel)).ok(), &mut sibling_stats)
+            });
+            if let Err(e) = resolved {
+                stats.errors.push(format!("{}: re-resolving edges failed: {e:#}", repo.name));
+            }
+            stats.errors.extend(sibling_stats.errors);
+        }
+        Ok(())
+    }
+
+    /// Walk `root` as `index_directory` would, with the same excludes,
+    /// grammars, deny list and limits, and report what it would index and
+    /// skip. Files are read to sniff their content but never parsed, and
+    /// nothing is written to the index.
+    pub fn dry_run(&self, root: &Path) -> Result<DryRun> {
+        let root = crate::paths::canonicalize(root)
+            .with_context(|| format!("failed to canonicalize {}", root.display()))?;
+        let mut run = DryRun { root: root.to_string_lossy().to_string(), ..Default::default() };
+        let (mut excluded_dirs, mut generated_dirs) = (Vec::new(), Vec::new());
+        let (mut full_bytes, mut partial_bytes) = (0u64, 0u64);
+
+        let walk = WalkDir::new(&root).into_iter().filter_entry(|e| {
+            if self.is_excluded(e.path()) {
+                if e.depth() > 0 && e.file_type().is_dir() {
+                    excluded_dirs.push(crate::paths::repo_relative(e.path(), &root));
+                }
+                return false;
+            }
+            match self.generated_dir(e) {
+                Some((pattern, files)) => {
+                    let rel_path = crate::paths::repo_relative(e.path(), &root);
+                    generated_dirs.push(format!("{rel_path} ({files} files like {pattern})"));
+                    false
+                }
+                None => true,
+            }
+        });
+        for entry in walk {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(err) => {
+                    let rel_path = err.path().map(|p| crate::paths::repo_relative(p, &root)).unwrap_or_default();
+                    run.skip("unreadable", rel_path);
+                    continue;
+                }
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let rel_path = crate::paths::repo_relative(path, &root);
+            if self.deny.is_denied(&rel_path) {
+                run.skip("denied", rel_path);
+                continue;
+            }
+            // Manifests and READMEs are read for packages and module docs,
+            // not indexed as source.
+            if is_manifest(&rel_path) || is_readme(&rel_path) {
+                continue;
+            }
+            let supported = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| self.registry.for_extension(ext).is_some());
+            if !supported {
+                run.skip("unsupported", rel_path);
+                continue;
+            }
+            match entry.metadata() {
+                Ok(m) if m.len() > self.max_partial_file_size => {
+                    run.skip("oversized", rel_path);
+                    continue;
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    run.skip("unreadable", rel_path);
+                    continue;
+                }
+            }
+            let Ok(source) = std::fs::read(path) else {
+                run.skip("unreadable", rel_path);
+                continue;
+            };
+            let content = sniff(&source);
+            if content == Content::Binary {
+                run.skip("binary", rel_path);
+                continue;
+            }
+            let bytes = source.len() as u64;
+            if content == Content::Minified || bytes > self.max_file_size {
+                run.partial += 1;
+                partial_bytes += bytes;
+            } else {
+                full_bytes += bytes;
+            }
+            if is_generated(&rel_path, &source) {
+                run.generated += 1;
+            }
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+            let language = self.registry.detect_language(path).unwrap_or(ext);
+            let count = run.languages.entry(language.to_string()).or_default();
+            count.files += 1;
+            count.bytes += bytes;
+        }
+        run.excluded_dirs = excluded_dirs;
+        run.generated_dirs = generated_dirs;
+        run.estimated_db_bytes = (full_bytes as f64 * DB_BYTES_PER_SOURCE_BYTE
+            + partial_bytes as f64 * DB_BYTES_PER_PARTIAL_BYTE) as u64;
+
+        let waived = self.force
+            || match self.db.get_repository_by_path(&run.root)? {
+                Some(repo) => self.db.limits_waived(repo.id)?,
+                None => false,
+            };
+        if !waived && run.files() > self.guardrails.max_files {
+            run.file_cap = Some(self.guardrails.max_files);
+        }
+        Ok(run)
+    }
+
+    /// Index the tree of a git revision (`origin/main`, a tag, a SHA) instead
+    /// of the working tree: blobs are read from the object database, so
+    /// uncommitted edits and untracked files never reach the index. Files
+    /// indexed earlier that are absent from the revision are removed, and the
+    /// resolved commit SHA is recorded on the repository. The size caps apply
+    /// as in `index_directory`.
+    pub fn index_revision(&self, root: &Path, rev: &str) -> Result<IndexStats> {
+        let root = crate::paths::canonicalize(root)
+            .with_context(|| format!("failed to canonicalize {}", root.display()))?;
+        let mut tree = RevisionTree::open(&root, rev)?;
+
+        let repo_name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root.to_string_lossy().to_string());
+        let root_str = root.to_string_lossy().to_string();
+        let repo_id = self.db.upsert_repository(&repo_name, &root_str)?;
+
+        self.db.with_transaction(|| {
+            let mut stats = IndexStats::default();
+            self.db.clear_index_errors(repo_id, None, Some("read"))?;
+            self.db.purge_denied_files(&self.deny)?;
+            let capped = self.capped(repo_id)?;
+
+            // Phase 1: read supported blobs at the revision
+            let entries = tree.entries.clone();
+            let mut in_tree = HashSet::new();
+            let mut packages = Vec::new();
+            let mut dependencies = Vec::new();
+            let mut docs = Vec::new();
+            for entry in &entries {
+                let path = root.join(&entry.path);
+                if self.is_excluded(Path::new(&entry.path)) || self.deny.is_denied(&entry.path) {
+                    continue;
+                }
+                if is_manifest(&entry.path) {
+                    if let Ok(Some(bytes)) = tree.read(&entry.path) {
+                        let text = String::from_utf8_lossy(&bytes);
+                        packages.extend(parse_manifest(&entry.path, &text));
+                        dependencies.extend(parse_dependencies(&entry.path, &text));
+                    }
+                    continue;
+                }
+                if is_readme(&entry.path) {
+                    if let Ok(Some(bytes)) = tree.read(&entry.path) {
+                        docs.extend(module_docs::extract(&entry.path, &String::from_utf8_lossy(&bytes)));
+                    }
+                    continue;
+                }
+                let ext = match path.extension().and_then(|e| e.to_str()) {
+                    Some(e) => e,
+                    None => continue,
+                };
+                let grammar = match self.registry.for_extension(ext) {
+                    Some(g) => g,
+                    None => continue,
+                };
+                // Over-size files are dropped below, like in a walk, rather
+                // than keeping what the working tree last had for them.
+                if entry.size > self.max_partial_file_size {
+                    stats.files_skipped += 1;
+                    continue;
+                }
+                in_tree.insert(entry.path.clone());
+                if capped && in_tree.len() > self.guardrails.max_files {
+                    return Err(self.limit_exceeded(&root, "files", in_tree.len(), self.guardrails.max_files));
+                }
+
+                let source = match tree.read(&entry.path) {
+                    Ok(Some(s)) => s,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        let message = format!("read error: {err}");
+                        self.note_error(&mut stats, repo_id, Path::new(&entry.path), &entry.path, "read", &message)?;
+                        continue;
+                    }
+                };
+                if is_package_entry(&entry.path) {
+                    docs.extend(module_docs::extract(&entry.path, &String::from_utf8_lossy(&source)));
+                }
+                let parsed = stats.files_indexed;
+                self.index_source(repo_id, &path, &entry.path, grammar, &source, &mut stats)?;
+                if capped && stats.files_indexed > parsed && stats.files_indexed % SYMBOL_CHECK_INTERVAL == 0 {
+                    self.check_symbol_cap(repo_id, &root)?;
+                }
+            }
+
+            // Drop files the revision does not contain (local-only or since
+            // deleted); imported files were never part of a walk
+            let mut removed = false;
+            for file in self.db.get_files_for_repo(repo_id)? {
+                if file.hash != IMPORTED_HASH && !in_tree.contains(&file.path) {
+                    removed |= self.db.remove_file(repo_id, &file.path)?;
+                }
+            }
+            if removed {
+                self.db.mark_orphaned_path_memories_stale()?;
+            }
+            if capped {
+                self.check_symbol_cap(repo_id, &root)?;
+            }
+
+            // Phase 2: resolve cross-file edges
+            self.db.replace_dependencies(repo_id, &dependencies)?;
+            let edge_count =
+                self.resolve_edges(repo_id, &mut |rel| tree.read(rel).ok().flatten(), &mut stats)?;
+            self.db.replace_packages(repo_id, &packages)?;
+            self.db.replace_module_docs(repo_id, &docs)?;
+            stats.edges_created = edge_count;
+            stats.test_links = crate::test_map::map_tests(self.db, repo_id)?;
+            crate::graph_snapshot::record(self.db, repo_id)?;
+            crate::coupling::refresh(self.db, repo_id)?;
+
+            self.db.set_repository_revision(repo_id, Some(&tree.commit))?;
+            // Contents were replaced wholesale, so no branch namespace is live.
+            self.db.set_repository_branch(repo_id, None)?;
+            Ok(stats)
+        })
+    }
+
+    /// Activate the namespace of the branch checked out at `root`. When it
+    /// differs from the recorded one, every live file whose on-disk content
+    /// changed is parked under the old branch; then each such path is restored
+    /// from the new branch's snapshot when the hashes match, or parsed from
+    /// disk otherwise. Files identical on both branches are never touched.
+    /// Returns None when nothing changed (same branch, detached HEAD, or no
+    /// git checkout); the first call only records the branch. Snapshots of
+    /// the activated branch and of branches deleted from git are dropped.
+    pub fn sync_branch(&self, root: &Path) -> Result<Option<BranchSwitch>> {
+        let root = crate::paths::canonicalize(root)?;
+        let Some(current) = crate::git::current_branch(&root) else {
+            return Ok(None);
+        };
+        let root_str = root.to_string_lossy().to_string();
+
+        // The recorded branch is read and replaced in one transaction, so two
+        // callers racing on a checkout can't both park the same files.
+        self.db.with_transaction(|| {
+            let repo_id = match self.db.get_repository_by_path(&root_str)? {
+                Some(repo) => repo.id,
+                None => return Ok(None),
+            };
+            let previous = match self.db.get_repository_branch(repo_id)? {
+                Some(prev) if prev == current => return Ok(None),
+                Some(prev) => prev,
+                None => {
+                    self.db.set_repository_branch(repo_id, Some(&current))?;
+                    return Ok(None);
+                }
+            };
+            let on_disk = self.hash_files_on_disk(&root);
+
+            let mut switch = BranchSwitch {
+                from: previous.clone(),
+                to: current.clone(),
+                ..Default::default()
+            };
+
+            // Park the old branch's version of every file that differs
+            let mut live = HashSet::new();
+            for file in self.db.get_files_for_repo(repo_id)? {
+                if file.hash == IMPORTED_HASH {
+                    continue;
+                }
+                if on_disk.get(&file.path).is_some_and(|(_, hash)| *hash == file.hash) {
+                    live.insert(file.path);
+                    continue;
+                }
+                self.db.park_file(repo_id, &previous, &file)?;
+                self.db.remove_file(repo_id, &file.path)?;
+                switch.parked += 1;
+            }
+
+            // Bring in the new branch's version: snapshot if current, else parse
+            let mut pending_edges: Vec<(i64, Vec<Reference>)> = Vec::new();
+            let mut stats = IndexStats::default();
+            for (rel_path, (path, hash)) in &on_disk {
+                if live.contains(rel_path) {
+                    continue;
+                }
+                if let Some(parked) = self.db.get_parked_file(repo_id, &current, rel_path)? {
+                    if parked.hash == *hash {
+                        let file_id = self.db.restore_parked_file(repo_id, rel_path, &parked)?;
+                        pending_edges.push((file_id, parked.edges));
+                        switch.restored += 1;
+                        continue;
+                    }
+                }
+
+                let Some(grammar) = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .and_then(|ext| self.registry.for_extension(ext))
+                else {
+                    continue;
+                };
+                let Ok(source) = std::fs::read(path) else {
+                    continue;
+                };
+                self.index_source(repo_id, path, rel_path, grammar, &source, &mut stats)?;
+                if let Some(file) = self.db.get_file_by_path(repo_id, rel_path)? {
+                    pending_edges.push((file.id, file_references(grammar, &source)));
+                }
+                switch.reindexed += 1;
+            }
+
+            // Edges last: targets may live in any file restored above
+            let symbol_map = self.db.resolvable_symbol_names(repo_id)?;
+            for (file_id, refs) in &pending_edges {
+                let file_symbols = self.db.get_symbols_by_file(*file_id)?;
+                for (from, to, kind) in refs {
+                    let source_sym = file_symbols.iter().find(|s| s.name == *from);
+                    if let (Some(src), Some(&tgt_id)) = (source_sym, symbol_map.get(to)) {
+                        if src.id != tgt_id {
+                            self.db.insert_edge(src.id, tgt_id, kind)?;
+                        }
+                    }
+                }
+            }
+
+            if switch.parked > 0 {
+                self.db.mark_orphaned_path_memories_stale()?;
+            }
+            // The live index now holds the current branch; its snapshots are
+            // taken afresh when it is left again.
+            self.db.delete_parked_files(repo_id, &current)?;
+            if let Ok(branches) = crate::git::local_branches(&root) {
+                self.db.prune_parked_branches(repo_id, &branches)?;
+            }
+            self.db.set_repository_branch(repo_id, Some(&current))?;
+            Ok(Some(switch))
+        })
+    }
+
+    /// Bring the index up to date with the working tree by touching only what
+    /// changed: the branch namespace is synced, every indexable file is hashed
+    /// and re-parsed only when its hash differs from the stored one, and
+    /// indexed files gone from disk are removed. Unlike `index_directory`,
+    /// repo-wide edge resolution and manifest parsing are skipped, so this is
+    /// cheap enough for git hooks. The size caps apply as in a full index,
+    /// and the update is rolled back when it would exceed them. Falls back to
+    /// a full index for a root that has never been indexed.
+    pub fn reindex_changed(&self, root: &Path) -> Result<IndexStats> {
+        let root = crate::paths::canonicalize(root)
+            .with_context(|| format!("failed to canonicalize {}", root.display()))?;
+        if self.db.get_repository_by_path(&root.to_string_lossy())?.is_none() {
+            return self.index_directory(&root);
+        }
+        self.db.with_transaction(|| {
+            self.sync_branch(&root)?;
+            let Some(plan) = self.plan_rescan(&root)? else {
+                return self.index_directory(&root);
+            };
+            let capped = self.capped(plan.repo_id)?;
+            let files = plan.unchanged + plan.changed.len();
+            if capped && files > self.guardrails.max_files {
+                return Err(self.limit_exceeded(&root, "files", files, self.guardrails.max_files));
+            }
+            let stats = self.apply_plan(&plan, &root)?;
+            if capped {
+                self.check_symbol_cap(plan.repo_id, &root)?;
+            }
+            Ok(stats)
+        })
+    }
+
+    /// Re-index the changed files of `plan` and remove its deleted ones,
+    /// collecting per-file errors, then refresh Go `implements` edges once.
+    fn apply_plan(&self, plan: &RescanPlan, root: &Path) -> Result<IndexStats> {
+        let mut stats = IndexStats { files_skipped: plan.unchanged, ..IndexStats::default() };
+        let mut go_changed = false;
+        for (rel_path, path) in &plan.changed {
+            go_changed |= self.reindex_planned(plan.repo_id, rel_path, path, root, &mut stats);
+        }
+        for rel_path in &plan.deleted {
+            go_changed |= self.remove_planned(rel_path, root, &mut stats);
+        }
+        if go_changed {
+            self.refresh_go_implements(root)?;
+        }
+        if stats.files_indexed + stats.files_removed > 0 {
+            stats.test_links = self.finish_file_updates(root)?;
+        }
+        Ok(stats)
+    }
+
+    /// Re-index one changed file of a plan into `stats`. Returns true when a
+    /// Go file changed, so the caller refreshes `implements` edges.
+    pub(crate) fn reindex_planned(
+        &self,
+        repo_id: i64,
+        rel_path: &str,
+        path: &Path,
+        root: &Path,
+        stats: &mut IndexStats,
+    ) -> bool {
+        let indexed = self.index_one_file(path, root);
+        self.record_planned(repo_id, rel_path, path, indexed, stats)
+    }
+
+    /// Count the outcome of re-indexing one changed file of a plan into
+    /// `stats`. Returns true when a Go file changed.
+    pub(crate) fn record_planned(
+        &self,
+        repo_id: i64,
+        rel_path: &str,
+        path: &Path,
+        indexed: Result<bool>,
+        stats: &mut IndexStats,
+    ) -> bool {
+        let indexed = indexed.and_then(|changed| {
+            if !changed {
+                return Ok(None);
+            }
+            let symbols = match self.db.get_file_by_path(repo_id, rel_path)? {
+                Some(file) => self.db.get_symbols_by_file(file.id)?.len(),
+                None => 0,
+            };
+            Ok(Some(symbols))
+        });
+        match indexed {
+            Ok(Some(symbols)) => {
+                stats.files_indexed += 1;
+                stats.symbols_extracted += symbols;
+                is_go_file(path)
+            }
+            Ok(None) => {
+                stats.files_skipped += 1;
+                false
+            }
+            Err(e) => {
+                stats.errors.push(format!("{rel_path}: {e:#}"));
+                false
+            }
+        }
+    }
+
+    /// Remove one deleted file of a plan into `stats`. Returns true when a Go
+    /// file was removed.
+    pub(crate) fn remove_planned(&self, rel_path: &str, root: &Path, stats: &mut IndexStats) -> bool {
+        match self.remove_one_file(&root.join(rel_path), root) {
+            Ok(true) => {
+                stats.files_removed += 1;
+                is_go_file(Path::new(rel_path))
+            }
+            Ok(false) => false,
+            Err(e) => {
+                stats.errors.push(format!("{rel_path}: {e:#}"));
+                false
+            }
+        }
+    }
+
+    /// The files `reindex_changed` would touch under an indexed root, found by
+    /// hashing the tree; denied files are purged first. None when the root
+    /// has never been indexed. The branch namespace is not synced, so the
+    /// caller does that first.
+    pub(crate) fn plan_rescan(&self, root: &Path) -> Result<Option<RescanPlan>> {
+        let Some(repo) = self.db.get_repository_by_path(&root.to_string_lossy())? else {
+            return Ok(None);
+        };
+        self.db.purge_denied_files(&self.deny)?;
+        let mut on_disk = self.hash_files_on_disk(root);
+        let indexed: HashMap<String, String> = self
+            .db
+            .get_files_for_repo(repo.id)?
+            .into_iter()
+            .map(|f| (f.path, f.hash))
+            .collect();
+
+        let mut plan = RescanPlan { repo_id: repo.id, changed: Vec::new(), deleted: Vec::new(), unchanged: 0 };
+        // Imported files never show up in `on_disk`; that is not a deletion.
+        plan.deleted = indexed
+            .iter()
+            .filter(|(p, h)| *h != IMPORTED_HASH && !on_disk.contains_key(*p))
+            .map(|(p, _)| p.clone())
+            .collect();
+        plan.deleted.sort();
+        on_disk.retain(|rel_path, (_, hash)| {
+            let unchanged = indexed.get(rel_path) == Some(hash);
+            plan.unchanged += usize::from(unchanged);
+            !unchanged
+        });
+        plan.changed = on_disk.into_iter().map(|(rel_path, (path, _))| (rel_path, path)).collect();
+        plan.changed.sort();
+        Ok(Some(plan))
+    }
+
+    /// `reindex_changed` narrowed to the files matching `globs`, for when a
+    /// code generator has just rewritten a directory or one corner of the
+    /// tree is known to be stale. Globs are repo-relative and gitignore-style,
+    /// as in CODEOWNERS: `gen/` covers everything under any `gen` directory,
+    /// `/api/*.go` one level of the root's `api`, `**/*_pb.rs` any depth.
+    /// Only the literal leading directories of anchored globs are walked.
+    /// Matching files are re-parsed when their hash changed, and matching
+    /// indexed files gone from disk are removed. Errors on a root that has
+    /// never been indexed, and on globs that leave the root.
+    pub fn reindex_paths(&self, root: &Path, globs: &[String]) -> Result<IndexStats> {
+        let root = crate::paths::canonicalize(root)
+            .with_context(|| format!("failed to canonicalize {}", root.display()))?;
+        if self.db.get_repository_by_path(&root.to_string_lossy())?.is_none() {
+            bail!("{} is not indexed; run `focal index` first", root.display());
+        }
+        self.db.with_transaction(|| {
+            self.sync_branch(&root)?;
+            let plan = self.plan_paths(&root, globs)?;
+            self.apply_plan(&plan, &root)
+        })
+    }
+
+    /// The files `reindex_paths` would touch: every supported file matching
+    /// `globs`, changed or not (the re-index skips unchanged ones), and the
+    /// matching indexed files gone from disk. `root` must be canonical. Globs
+    /// with `..` segments are refused, and so is a literal base directory
+    /// that resolves outside the root through a symlink.
+    pub(crate) fn plan_paths(&self, root: &Path, globs: &[String]) -> Result<RescanPlan> {
+        let Some(repo) = self.db.get_repository_by_path(&root.to_string_lossy())? else {
+            bail!("{} is not indexed; run `focal index` first", root.display());
+        };
+        let globs: Vec<String> = globs
+            .iter()
+            .map(|g| g.trim().replace('\\', "/"))
+            .filter(|g| !g.trim_matches('/').is_empty())
+            .collect();
+        if globs.is_empty() {
+            bail!("no path patterns given");
+        }
+        if let Some(glob) = globs.iter().find(|g| g.split('/').any(|seg| seg == "..")) {
+            bail!("'{glob}' leaves the repository; patterns are relative to its root");
+        }
+        let matches = |rel_path: &str| globs.iter().any(|g| crate::owners::pattern_matches(g, rel_path));
+
+        let mut plan = RescanPlan { repo_id: repo.id, changed: Vec::new(), deleted: Vec::new(), unchanged: 0 };
+        let mut seen = HashSet::new();
+        for base in globs.iter().map(|g| glob_base(g)) {
+            let Ok(base) = crate::paths::canonicalize(&root.join(base)) else {
+                continue;
+            };
+            if !base.starts_with(root) {
+                bail!("{} is outside {}", base.display(), root.display());
+            }
+            for entry in WalkDir::new(&base)
+                .into_iter()
+                .filter_entry(|e| !self.is_excluded(e.path()) && self.generated_dir(e).is_none())
+                .filter_map(|e| e.ok())
+            {
+                let path = entry.path();
+                let supported = path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|ext| self.registry.for_extension(ext).is_some());
+                if !entry.file_type().is_file() || !supported {
+                    continue;
+                }
+                let rel_path = crate::paths::repo_relative(path, root);
+                if matches(&rel_path) && seen.insert(rel_path.clone()) {
+                    plan.changed.push((rel_path, path.to_path_buf()));
+                }
+            }
+        }
 
-                // Read file
-                let source = match std::fs::read(path) {
-                    Ok(s) => s,
-                    Err(err) => {
-                        stats.errors.push(format!("{}: read error: {err}", path.display()));
-                        continue;
-                    }
-                };
-
-                // Compute SHA-256
-                let hash = {
-                    let mut hasher = Sha256::new();
-                    hasher.update(&source);
-                    format!("{:x}", hasher.finalize())
-                };
+        for file in self.db.get_files_for_repo(repo.id)? {
+            if file.hash == IMPORTED_HASH || seen.contains(&file.path) || !matches(&file.path) {
+                continue;
+            }
+            if !root.join(&file.path).exists() {
+                plan.deleted.push(file.path);
+            }
+        }
+        Ok(plan)
+    }
 
-                // Relative path within repo
-                let rel_path = path
-                    .strip_prefix(&root)
-                    .unwrap_or(path)
-                    .to_string_lossy()
-                    .to_string();
-
-                // Skip if hash unchanged
-                if let Some(existing_hash) = self.db.get_file_hash(repo_id, &rel_path)? {
-                    if existing_hash == hash {
-                        stats.files_skipped += 1;
-                        continue;
-                    }
-                }
+    /// Content hash of every indexable file under `root`, keyed by
+    /// repo-relative path. Unreadable and over-limit files are left out.
+    fn hash_files_on_disk(&self, root: &Path) -> HashMap<String, (PathBuf, String)> {
+        let mut on_disk = HashMap::new();
+        for entry in WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| !self.is_excluded(e.path()) && self.generated_dir(e).is_none())
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            let supported = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| self.registry.for_extension(ext).is_some());
+            if !entry.file_type().is_file() || !supported {
+                continue;
+            }
+            if entry.metadata().map(|m| m.len() > self.max_partial_file_size).unwrap_or(true) {
+                continue;
+            }
+            let rel_path = crate::paths::repo_relative(path, root);
+            if self.deny.is_denied(&rel_path) {
+                continue;
+            }
+            if let Ok(source) = std::fs::read(path) {
+                let hash = format!("{:x}", Sha256::digest(&source));
+                on_disk.insert(rel_path, (path.to_path_buf(), hash));
+            }
+        }
+        on_disk
+    }
 
-                // Detect language name
-                let language = self
-                    .registry
-                    .detect_language(path)
-                    .unwrap_or(ext);
+    /// Store one file's symbols: skip when the content hash is unchanged,
+    /// otherwise replace its symbols and re-link memories by name. `path` is
+    /// used for language detection and error messages only.
+    fn index_source(
+        &self,
+        repo_id: i64,
+        path: &Path,
+        rel_path: &str,
+        grammar: &dyn Grammar,
+        source: &[u8],
+        stats: &mut IndexStats,
+    ) -> Result<()> {
+        // Binary files never reach the parser; one that used to be text
+        // drops out of the index as if deleted
+        let content = sniff(source);
+        if content == Content::Binary {
+            stats.files_skipped += 1;
+            stats.files_binary += 1;
+            if self.drop_file(repo_id, rel_path)? {
+                stats.files_removed += 1;
+            }
+            return Ok(());
+        }
 
-                // Upsert file record
-                let file_id = self.db.upsert_file(repo_id, &rel_path, language, &hash)?;
+        // Compute SHA-256
+        let hash = {
+            let mut hasher = Sha256::new();
+            hasher.update(source);
+            format!("{:x}", hasher.finalize())
+        };
 
-                // Mark linked memories stale (file was re-indexed)
-                let _ = self.db.mark_memories_stale_for_file(file_id);
+        // Skip if hash unchanged
+        let existing_hash = self.db.get_file_hash(repo_id, rel_path)?;
+        if existing_hash.as_deref() == Some(hash.as_str()) {
+            stats.files_skipped += 1;
+            return Ok(());
+        }
+        let mut carried_links = Vec::new();
+        if existing_hash.is_none() {
+            match self.resurrect(repo_id, rel_path, &hash)? {
+                Tombstoned::Restored(file_id) => {
+                    stats.files_indexed += 1;
+                    stats.symbols_extracted += self.db.get_symbols_by_file(file_id)?.len();
+                    return Ok(());
+                }
+                Tombstoned::Links(links) => carried_links = links,
+            }
+        }
 
-                // Snapshot memory->symbol_name links before deletion so we can
-                // re-link to the new symbol IDs after re-insertion.
-                let memory_links = self
-                    .db
-                    .collect_memory_symbol_names(file_id)
-                    .unwrap_or_default();
+        // Problems recorded for the previous version no longer apply.
+        self.db.clear_index_errors(repo_id, Some(rel_path), None)?;
+
+        // Detect language name
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        let language = self
+            .registry
+            .detect_language(path)
+            .unwrap_or(ext);
+
+        // Upsert file record
+        let minified = content == Content::Minified;
+        let partial = minified || source.len() as u64 > self.max_file_size;
+        let decoded = decode_source(source);
+        let source: &[u8] = &decoded.text;
+        let facts = FileFacts {
+            line_count: count_lines(source),
+            partial,
+            generated: is_generated(rel_path, source),
+            encoding: &decoded.encoding,
+            package: None,
+        };
+        self.db.delete_tombstone(repo_id, rel_path)?;
+        let file_id = self.db.upsert_parsed_file(repo_id, rel_path, language, &hash, &facts)?;
 
-                // Clear old symbols (and edges referencing them)
-                let _ = self.db.delete_edges_by_file(file_id);
-                let _ = self.db.delete_symbols_by_file(file_id);
+        // Mark linked memories stale (file was re-indexed)
+        let _ = self.db.mark_memories_stale_for_file(file_id);
 
-                // Parse with tree-sitter
-                let mut parser = tree_sitter::Parser::new();
-                let ts_lang = grammar.language();
-                if let Err(err) = parser.set_language(&ts_lang) {
-                    stats.errors.push(format!("{}: set_language error: {err}", path.display()));
-                    continue;
-                }
+        // Snapshot memory->symbol_name links before deletion so we can
+        // re-link to the new symbol IDs after re-insertion.
+        let mut memory_links = self
+            .db
+            .collect_memory_symbol_names(file_id)
+            .unwrap_or_default();
+        memory_links.extend(carried_links);
 
-                let tree = match parser.parse(&source, None) {
-                    Some(t) => t,
-                    None => {
-                        stats.errors.push(format!("{}: parse returned None", path.display()));
-                        continue;
-                    }
-                };
+        // Clear old symbols (and edges referencing them)
+        let _ = self.db.delete_edges_by_file(file_id);
+        let _ = self.db.delete_symbols_by_file(file_id);
 
-                // Extract and insert symbols
-                let symbols = grammar.extract_symbols(&source, &tree);
-                let inserted = self.insert_symbols_recursive(file_id, &symbols, None, &rel_path, language)?;
-                stats.symbols_extracted += inserted;
-                stats.files_indexed += 1;
+        // Parse with tree-sitter
+        let mut parser = tree_sitter::Parser::new();
+        let ts_lang = grammar.language();
+        if let Err(err) = parser.set_language(&ts_lang) {
+            self.note_error(stats, repo_id, path, rel_path, "parse", &format!("set_language error: {err}"))?;
+            return Ok(());
+        }
 
-                // Re-link memories to new symbols by matching names
-                if !memory_links.is_empty() {
-                    let _ = self.db.relink_memories_to_symbols(file_id, &memory_links);
-                }
+        let tree = match parser.parse(source, None) {
+            Some(t) => t,
+            None => {
+                self.note_error(stats, repo_id, path, rel_path, "parse", "parse returned None")?;
+                return Ok(());
             }
+        };
+        if let Some((count, line)) = syntax_errors(&tree) {
+            self.db
+                .record_index_error(repo_id, rel_path, "syntax", &syntax_message(count), Some(line))?;
+        }
 
-            // Phase 2: resolve cross-file edges
-            let edge_count = self.resolve_edges(repo_id, &root)?;
-            stats.edges_created = edge_count;
-
-            Ok(stats)
-        })
+        // Extract and insert symbols. Each step is guarded so a panic on a
+        // malformed file costs that step only, not the file or the run.
+        let mut panics = Vec::new();
+        let mut symbols = guarded("symbol extraction", &mut panics, || grammar.extract_symbols(source, &tree));
+        let redactions = self.redact(&mut symbols, source);
+        let routes = guarded("route extraction", &mut panics, || grammar.extract_routes(source, &tree));
+        let env_usages = guarded("env scan", &mut panics, || {
+            scan_env_usages(&String::from_utf8_lossy(source), language)
+        });
+        let sql_queries = guarded("SQL extraction", &mut panics, || extract_sql_queries(source, &tree));
+        let error_sites = guarded("error-site extraction", &mut panics, || {
+            grammar.extract_error_sites(source, &tree)
+        });
+        for message in &panics {
+            self.note_error(stats, repo_id, path, rel_path, "panic", message)?;
+        }
+        let inserted = self.insert_symbols_recursive(file_id, &symbols, None, rel_path, language, partial)?;
+        self.db.set_file_redactions(file_id, redactions)?;
+        stats.symbols_extracted += inserted;
+        stats.redactions += redactions;
+        stats.files_partial += usize::from(partial);
+        stats.files_minified += usize::from(minified);
+        self.db.replace_routes(file_id, &routes)?;
+        self.db.replace_env_usages(file_id, &env_usages)?;
+        self.db.replace_sql_queries(file_id, &sql_queries)?;
+        self.db.replace_error_sites(file_id, &error_sites)?;
+        stats.files_indexed += 1;
+
+        // Re-link memories to new symbols by matching names
+        if !memory_links.is_empty() {
+            let _ = self.db.relink_memories_to_symbols(file_id, &memory_links);
+        }
+        Ok(())
     }
 
     /// Re-index a single file. Determines the repo from the path, checks hash,
-    /// and updates symbols + edges if changed. Returns true if re-indexed.
+    /// and updates symbols + edges if changed. A file that turned binary is
+    /// removed as if deleted, as is one grown past the partial size limit.
+    /// Returns true if re-indexed or removed.
     pub fn index_file(&self, file_path: &Path, root: &Path) -> Result<bool> {
-        let root = root.canonicalize()?;
-        let repo_name = root
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| root.to_string_lossy().to_string());
-        let root_str = root.to_string_lossy().to_string();
-        let repo_id = self.db.upsert_repository(&repo_name, &root_str)?;
+        let indexed = self.index_one_file(file_path, root)?;
+        if indexed && is_go_file(file_path) {
+            self.refresh_go_implements(root)?;
+        }
+        if indexed {
+            self.finish_file_updates(root)?;
+        }
+        Ok(indexed)
+    }
 
-        let ext = match file_path.extension().and_then(|e| e.to_str()) {
-            Some(e) => e,
-            None => return Ok(false),
-        };
-        let grammar = match self.registry.for_extension(ext) {
-            Some(g) => g,
-            None => return Ok(false),
+    /// `index_file` without the repo-wide passes (Go `implements`,
+    /// `finish_file_updates`), for batches that run them once when done.
+    pub(crate) fn index_one_file(&self, file_path: &Path, root: &Path) -> Result<bool> {
+        let parser = self.parser();
+        let Some(file) = parser.read(file_path, root)? else {
+            return Ok(false);
         };
+        if self.is_unchanged(&file)? {
+            return Ok(false);
+        }
+        self.write_parsed(parser.parse(file)?)
+    }
 
-        let source = std::fs::read(file_path)?;
-        let hash = {
-            let mut hasher = Sha256::new();
-            hasher.update(&source);
-            format!("{:x}", hasher.finalize())
-        };
+    /// This indexer's settings for parsing one file without the database.
+    pub(crate) fn parser(&self) -> FileParser<'_> {
+        FileParser {
+            registry: self.registry,
+            redact_secrets: self.redact_secrets,
+            deny: &self.deny,
+            guardrails: &self.guardrails,
+            max_file_size: self.max_file_size,
+            max_partial_file_size: self.max_partial_file_size,
+        }
+    }
 
-        let rel_path = file_path
-            .strip_prefix(&root)
-            .unwrap_or(file_path)
-            .to_string_lossy()
-            .to_string();
+    /// True when `file` is indexed with the same contents.
+    pub(crate) fn is_unchanged(&self, file: &SourceFile) -> Result<bool> {
+        let Some(repo) = self.db.get_repository_by_path(&file.root.to_string_lossy())? else {
+            return Ok(false);
+        };
+        Ok(self.db.get_file_hash(repo.id, &file.rel_path)?.as_deref() == Some(file.hash.as_str()))
+    }
 
-        if let Some(existing_hash) = self.db.get_file_hash(repo_id, &rel_path)? {
-            if existing_hash == hash {
-                return Ok(false); // unchanged
+    /// Store a file parsed by `FileParser::parse`: drop it when it turned out
+    /// binary, restore its tombstone when one matches, otherwise replace its
+    /// symbols and resolve its edges. Returns false when it was unchanged.
+    pub(crate) fn write_parsed(&self, file: ParsedFile) -> Result<bool> {
+        let ParsedFile { root, rel_path, hash, parse } = file;
+        let repo_name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root.to_string_lossy().to_string());
+        let repo_id = self.db.upsert_repository(&repo_name, &root.to_string_lossy())?;
+        let Some(parse) = parse else {
+            return self.drop_file(repo_id, &rel_path);
+        };
+        let FileParse {
+            language,
+            source,
+            encoding,
+            partial,
+            symbols,
+            redactions,
+            refs,
+            routes,
+            env_usages,
+            sql_queries,
+            error_sites,
+            panics,
+            syntax,
+        } = *parse;
+        let language = language.as_str();
+
+        let existing_hash = self.db.get_file_hash(repo_id, &rel_path)?;
+        if existing_hash.as_deref() == Some(hash.as_str()) {
+            return Ok(false); // unchanged
+        }
+        let mut carried_links = Vec::new();
+        if existing_hash.is_none() {
+            match self.db.with_transaction(|| self.resurrect(repo_id, &rel_path, &hash))? {
+                Tombstoned::Restored(_) => return Ok(true),
+                Tombstoned::Links(links) => carried_links = links,
             }
         }
 
-        let language = self.registry.detect_language(file_path).unwrap_or(ext);
-
-        // Parse outside the transaction — this is pure computation
-        let mut parser = tree_sitter::Parser::new();
-        parser.set_language(&grammar.language())?;
-        let tree = parser
-            .parse(&source, None)
-            .ok_or_else(|| anyhow::anyhow!("parse returned None"))?;
-        let symbols = grammar.extract_symbols(&source, &tree);
-        let refs = grammar.extract_references(&source, &tree);
-
         // All DB mutations wrapped in a transaction for atomicity
         self.db.with_transaction(|| {
-            let file_id = self.db.upsert_file(repo_id, &rel_path, language, &hash)?;
+            self.db.set_repository_revision(repo_id, None)?;
+            let packages = self.db.get_packages(repo_id)?;
+            let facts = FileFacts {
+                line_count: count_lines(&source),
+                partial,
+                generated: is_generated(&rel_path, &source),
+                encoding: &encoding,
+                package: Some(package_for_path(&packages, &rel_path).map_or("", |p| p.name.as_str())),
+            };
+            self.db.delete_tombstone(repo_id, &rel_path)?;
+            let file_id = self.db.upsert_parsed_file(repo_id, &rel_path, language, &hash, &facts)?;
             let _ = self.db.mark_memories_stale_for_file(file_id);
-            let memory_links = self
+            let mut memory_links = self
                 .db
                 .collect_memory_symbol_names(file_id)
                 .unwrap_or_default();
+            memory_links.extend(carried_links);
+            // Replacing the symbols drops the edges other files have into
+            // them; they are put back by name once the new symbols exist.
+            let incoming = self.db.incoming_edges(file_id)?;
             let _ = self.db.delete_edges_by_file(file_id);
             let _ = self.db.delete_symbols_by_file(file_id);
+            self.db.clear_index_errors(repo_id, Some(&rel_path), None)?;
+            if let Some((count, line)) = syntax {
+                self.db
+                    .record_index_error(repo_id, &rel_path, "syntax", &syntax_message(count), Some(line))?;
+            }
+            for message in &panics {
+                self.db.record_index_error(repo_id, &rel_path, "panic", message, None)?;
+            }
 
-            self.insert_symbols_recursive(file_id, &symbols, None, &rel_path, language)?;
+            self.insert_symbols_recursive(file_id, &symbols, None, &rel_path, language, partial)?;
+            self.db.restore_incoming_edges(file_id, &incoming)?;
+            self.db.set_file_redactions(file_id, redactions)?;
+            self.db.replace_routes(file_id, &routes)?;
+            self.db.replace_env_usages(file_id, &env_usages)?;
+            self.db.replace_sql_queries(file_id, &sql_queries)?;
+            self.db.replace_error_sites(file_id, &error_sites)?;
 
             if !memory_links.is_empty() {
                 let _ = self.db.relink_memories_to_symbols(file_id, &memory_links);
             }
 
+            let declared = self.db.get_repo_dependencies(repo_id)?;
+            self.db
+                .replace_dependency_imports(file_id, &used_dependencies(&declared, language, &refs))?;
+            let external = external_names(&declared, language, &refs);
+
             // Re-resolve edges for this file using the repo-wide symbol map
-            let symbol_map = self.db.get_all_symbol_names_for_repo(repo_id)?;
+            let symbol_map = self.db.resolvable_symbol_names(repo_id)?;
             let file_symbols = self.db.get_symbols_by_file(file_id)?;
+            let links = module_links(language, &refs).filter(|l| !l.imports.is_empty());
+            let file_ids: HashMap<String, i64> = match links {
+                Some(_) => self.db.get_files_for_repo(repo_id)?.into_iter().map(|f| (f.path, f.id)).collect(),
+                None => HashMap::new(),
+            };
+            let mut barrels = Barrels::new(file_ids.keys().cloned());
+            let mut read = |path: &str| std::fs::read(root.join(path)).ok();
+            let mut edges = FileEdges::default();
             for r in &refs {
-                let source_sym = file_symbols.iter().find(|s| s.name == r.from_symbol);
-                let target_id = symbol_map.get(&r.to_name);
-                if let (Some(src), Some(&tgt_id)) = (source_sym, target_id) {
+                let Some(src) = source_symbol(&file_symbols, r, &rel_path, language) else {
+                    continue;
+                };
+                let imported = links
+                    .as_ref()
+                    .and_then(|links| self.barrel_target(&mut barrels, &file_ids, &rel_path, links, r, &mut read));
+                let target = match imported {
+                    Some(id) => Some(id),
+                    None => self.resolve_target(&symbol_map, &external, language, r)?,
+                };
+                if let Some(tgt_id) = target {
                     if src.id != tgt_id {
-                        let _ = self.db.insert_edge(src.id, tgt_id, &r.kind);
+                        edges.add(src.id, tgt_id, r);
                     }
                 }
             }
+            edges.write(self.db)?;
 
             Ok(true)
         })
     }
 
+    /// Re-infer the Go `implements` edges of the repository at `root` after
+    /// single-file updates touched Go files.
+    pub(crate) fn refresh_go_implements(&self, root: &Path) -> Result<()> {
+        let root = crate::paths::canonicalize(root)?;
+        if let Some(repo) = self.db.get_repository_by_path(&root.to_string_lossy())? {
+            self.db.with_transaction(|| self.infer_go_implements(repo.id))?;
+        }
+        Ok(())
+    }
+
+    /// The repo-wide passes single-file updates of the repository at `root`
+    /// defer until they are done: re-link its tests (`test_map::map_tests`)
+    /// and prune external stubs nothing calls any more. Returns the number
+    /// of test links.
+    pub(crate) fn finish_file_updates(&self, root: &Path) -> Result<usize> {
+        let root = crate::paths::canonicalize(root)?;
+        self.db.with_transaction(|| {
+            self.db.prune_external_symbols()?;
+            match self.db.get_repository_by_path(&root.to_string_lossy())? {
+                Some(repo) => crate::test_map::map_tests(self.db, repo.id),
+                None => Ok(0),
+            }
+        })
+    }
+
     /// Remove a deleted file's symbols and edges from the index.
     /// Returns true if the file was found and removed.
     pub fn remove_deleted_file(&self, file_path: &Path, root: &Path) -> Result<bool> {
-        let root = root.canonicalize()?;
+        let removed = self.remove_one_file(file_path, root)?;
+        if removed {
+            self.finish_file_updates(root)?;
+        }
+        Ok(removed)
+    }
+
+    /// `remove_deleted_file` without `finish_file_updates`, for batches.
+    pub(crate) fn remove_one_file(&self, file_path: &Path, root: &Path) -> Result<bool> {
+        let root = crate::paths::canonicalize(root)?;
         let repo_name = root
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
@@ -304,13 +1572,35 @@ impl<'a> Indexer<'a> {
         let root_str = root.to_string_lossy().to_string();
         let repo_id = self.db.upsert_repository(&repo_name, &root_str)?;
 
-        let rel_path = file_path
-            .strip_prefix(&root)
-            .unwrap_or(file_path)
-            .to_string_lossy()
-            .to_string();
+        let rel_path = crate::paths::repo_relative(file_path, &root);
+        self.drop_file(repo_id, &rel_path)
+    }
+
+    /// Tombstone `rel_path` and remove it from the index, for a file deleted
+    /// from disk or turned binary. Returns true if it was indexed.
+    fn drop_file(&self, repo_id: i64, rel_path: &str) -> Result<bool> {
+        let removed = self.db.with_transaction(|| self.db.tombstone_file(repo_id, rel_path))?;
+        if removed {
+            self.db.mark_orphaned_path_memories_stale()?;
+        }
+        Ok(removed)
+    }
 
-        self.db.remove_file(repo_id, &rel_path)
+    /// A file appearing where one was deleted within the grace period: restore
+    /// the tombstone when the content is unchanged, so symbol ids, memory
+    /// links and edges come back as they were; otherwise hand back its memory
+    /// links for relinking against the re-parsed symbols, and leave it for
+    /// the write to delete.
+    fn resurrect(&self, repo_id: i64, rel_path: &str, hash: &str) -> Result<Tombstoned> {
+        let Some(tombstone) = self.db.get_tombstone(repo_id, rel_path)? else {
+            return Ok(Tombstoned::Links(Vec::new()));
+        };
+        if tombstone.hash == hash {
+            self.db.delete_tombstone(repo_id, rel_path)?;
+            let file_id = self.db.restore_tombstone(repo_id, rel_path, &tombstone)?;
+            return Ok(Tombstoned::Restored(file_id));
+        }
+        Ok(Tombstoned::Links(tombstone.snapshot.memory_links))
     }
 
     /// Recursively insert extracted symbols and their children. Returns the count inserted.
@@ -323,6 +1613,7 @@ impl<'a> Indexer<'a> {
         parent_id: Option<i64>,
         rel_path: &str,
         language: &str,
+        partial: bool,
     ) -> Result<usize> {
         let mut count = 0;
         for sym in symbols {
@@ -332,17 +1623,31 @@ impl<'a> Indexer<'a> {
                 hasher.update(sym.body.as_bytes());
                 format!("{:x}", hasher.finalize())
             };
-            let sym_id = self.db.insert_symbol(
+            let types = sym
+                .types
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?;
+            let modifiers = sym.modifiers.join(" ");
+            let attributes = sym.attributes.join("\n");
+            let sym_id = self.db.insert_symbol_with_details(
                 file_id,
                 &sym.name,
                 &qualified,
                 sym.kind.as_str(),
                 &sym.signature,
-                &sym.body,
+                // Partial files keep the hash (change detection) but not the body.
+                if partial { "" } else { &sym.body },
                 &body_hash,
                 sym.start_line as i64,
                 sym.end_line as i64,
                 parent_id,
+                &SymbolDetails {
+                    visibility: sym.visibility.as_str(),
+                    modifiers: &modifiers,
+                    signature_types: types.as_deref(),
+                    attributes: &attributes,
+                },
             )?;
             count += 1;
             count += self.insert_symbols_recursive(
@@ -351,6 +1656,7 @@ impl<'a> Indexer<'a> {
                 Some(sym_id),
                 rel_path,
                 language,
+                partial,
             )?;
         }
         Ok(count)
@@ -361,15 +1667,24 @@ impl<'a> Indexer<'a> {
     ///
     /// Uses a pre-built name->id HashMap instead of per-reference SQL lookups.
     /// This turns O(refs * query_cost) into O(refs) with a single up-front query.
-    fn resolve_edges(&self, repo_id: i64, root: &Path) -> Result<usize> {
+    /// `read` supplies a file's contents by repo-relative path.
+    fn resolve_edges(
+        &self,
+        repo_id: i64,
+        read: &mut dyn FnMut(&str) -> Option<Vec<u8>>,
+        stats: &mut IndexStats,
+    ) -> Result<usize> {
+        // Every file's references are extracted again below.
+        self.db.clear_index_errors(repo_id, None, Some("edge_resolution"))?;
         // Build name→id map once for the whole repo
-        let symbol_map = self.db.get_all_symbol_names_for_repo(repo_id)?;
+        let symbol_map = self.db.resolvable_symbol_names(repo_id)?;
+        let declared = self.db.get_repo_dependencies(repo_id)?;
         let files = self.db.get_files_for_repo(repo_id)?;
+        let file_ids: HashMap<String, i64> = files.iter().map(|f| (f.path.clone(), f.id)).collect();
+        let mut barrels = Barrels::new(file_ids.keys().cloned());
         let mut edge_count = 0;
 
         for file_record in &files {
-            let abs_path = root.join(&file_record.path);
-
             let ext = match PathBuf::from(&file_record.path)
                 .extension()
                 .and_then(|e| e.to_str())
@@ -384,10 +1699,11 @@ impl<'a> Indexer<'a> {
                 None => continue,
             };
 
-            let source = match std::fs::read(&abs_path) {
-                Ok(s) => s,
-                Err(_) => continue,
+            let raw = match read(&file_record.path) {
+                Some(s) => s,
+                None => continue,
             };
+            let source = decode_source(&raw).text;
 
             let mut parser = tree_sitter::Parser::new();
             let ts_lang = grammar.language();
@@ -399,25 +1715,245 @@ impl<'a> Indexer<'a> {
                 None => continue,
             };
 
-            let refs = grammar.extract_references(&source, &tree);
+            let mut panics = Vec::new();
+            let refs = guarded("reference extraction", &mut panics, || {
+                grammar.extract_references(&source, &tree)
+            });
+            for message in &panics {
+                let path = Path::new(&file_record.path);
+                self.note_error(stats, repo_id, path, &file_record.path, "edge_resolution", message)?;
+            }
+            let used = used_dependencies(&declared, &file_record.language, &refs);
+            self.db.replace_dependency_imports(file_record.id, &used)?;
+            let external = external_names(&declared, &file_record.language, &refs);
             let file_symbols = self.db.get_symbols_by_file(file_record.id)?;
+            let links = module_links(&file_record.language, &refs);
 
+            let mut edges = FileEdges::default();
             for r in &refs {
-                let source_sym = file_symbols.iter().find(|s| s.name == r.from_symbol);
-                let target_id = symbol_map.get(&r.to_name);
-
-                if let (Some(src), Some(&tgt_id)) = (source_sym, target_id) {
+                let Some(src) = source_symbol(&file_symbols, r, &file_record.path, &file_record.language) else {
+                    continue;
+                };
+                let imported = links.as_ref().and_then(|links| {
+                    self.barrel_target(&mut barrels, &file_ids, &file_record.path, links, r, &mut *read)
+                });
+                let target = match imported {
+                    Some(id) => Some(id),
+                    None => self.resolve_target(&symbol_map, &external, &file_record.language, r)?,
+                };
+                if let Some(tgt_id) = target {
                     if src.id != tgt_id {
-                        self.db.insert_edge(src.id, tgt_id, &r.kind)?;
+                        edges.add(src.id, tgt_id, r);
                         edge_count += 1;
                     }
                 }
             }
+            edges.write(self.db)?;
         }
+        self.db.prune_external_symbols()?;
+        edge_count += self.infer_go_implements(repo_id)?;
 
         Ok(edge_count)
     }
 
+    /// Replace the `implements` edges from each Go type to the indexed
+    /// interfaces its method set satisfies. Go has no `implements` clause,
+    /// so satisfaction is inferred by method name: pointer and value
+    /// receivers count alike and signatures are not compared. Types,
+    /// methods and interfaces are keyed by package (directory), so
+    /// same-named types of different packages keep their own method sets;
+    /// an interface requiring an unexported method is only satisfied within
+    /// its package. Interfaces without methods, constraints, and interfaces
+    /// embedding one that isn't indexed (`io.Reader`) are skipped.
+    fn infer_go_implements(&self, repo_id: i64) -> Result<usize> {
+        self.db.delete_edges_of_kind(repo_id, "implements")?;
+        let mut interfaces: HashMap<GoName, (i64, go::InterfaceMembers)> = HashMap::new();
+        let mut qualified: HashMap<String, GoName> = HashMap::new();
+        let mut types: HashMap<GoName, i64> = HashMap::new();
+        let mut method_sets: HashMap<GoName, HashSet<String>> = HashMap::new();
+        for file in self.db.get_files_for_repo(repo_id)?.iter().filter(|f| f.language == "go") {
+            let package = file.path.rsplit_once('/').map_or("", |(dir, _)| dir).to_string();
+            for sym in self.db.get_symbols_by_file(file.id)? {
+                match sym.kind.as_str() {
+                    "interface" => {
+                        if let Some(members) = go::interface_members(&sym.body, &sym.name) {
+                            let key = (package.clone(), sym.name);
+                            qualified.entry(sym.qualified_name).or_insert_with(|| key.clone());
+                            interfaces.entry(key).or_insert((sym.id, members));
+                        }
+                    }
+                    "struct" | "type_alias" => {
+                        types.entry((package.clone(), sym.name)).or_insert(sym.id);
+                    }
+                    "method" => {
+                        if let Some((recv, method)) = sym.name.split_once('.') {
+                            method_sets
+                                .entry((package.clone(), recv.to_string()))
+                                .or_default()
+                                .insert(method.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut count = 0;
+        for (key, (interface_id, _)) in &interfaces {
+            let Some(required) = required_methods(key, &interfaces, &qualified, &mut Vec::new()) else {
+                continue;
+            };
+            if required.is_empty() {
+                continue;
+            }
+            let unexported = required.iter().any(|m| !m.starts_with(|c: char| c.is_uppercase()));
+            for (type_key, methods) in &method_sets {
+                if unexported && type_key.0 != key.0 {
+                    continue;
+                }
+                if let Some(&type_id) = types.get(type_key) {
+                    if required.is_subset(methods) {
+                        self.db.insert_edge(type_id, *interface_id, "implements")?;
+                        count += 1;
+                    }
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Target of reference `r`: a symbol of the repository by name, else the
+    /// external stub for a name the file imported from a dependency. A call
+    /// to a builtin no repository symbol declares resolves to nothing.
+    fn resolve_target(
+        &self,
+        symbol_map: &HashMap<String, i64>,
+        external: &HashMap<String, (String, String)>,
+        language: &str,
+        r: &ExtractedReference,
+    ) -> Result<Option<i64>> {
+        if let Some(&id) = symbol_map.get(&r.to_name) {
+            return Ok(Some(id));
+        }
+        if r.kind == "calls" && self.builtins.is_builtin(language, &r.to_name) {
+            return Ok(None);
+        }
+        match external.get(&r.to_name) {
+            Some((package, name)) if r.kind != "imports" => {
+                Ok(Some(self.db.get_or_create_external_symbol(package, name, language)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Target of a call to a name `path` imports from another TS/JS file of
+    /// the repository, following barrel re-exports to the declaring module.
+    /// `read` supplies file contents by repo-relative path.
+    fn barrel_target(
+        &self,
+        barrels: &mut Barrels,
+        file_ids: &HashMap<String, i64>,
+        path: &str,
+        links: &ModuleLinks,
+        r: &ExtractedReference,
+        read: &mut dyn FnMut(&str) -> Option<Vec<u8>>,
+    ) -> Option<i64> {
+        if r.kind != "calls" {
+            return None;
+        }
+        barrels.resolve_import(path, links, &r.to_name, &mut |target| {
+            let file_id = *file_ids.get(target)?;
+            let grammar = self.registry.for_extension(Path::new(target).extension()?.to_str()?)?;
+            let refs = file_references(grammar, &decode_source(&read(target)?).text);
+            let links = ModuleLinks::parse(refs.iter().map(|(_, to, kind)| (kind.as_str(), to.as_str())));
+            Some(Module::new(links, &self.db.get_symbols_by_file(file_id).ok()?))
+        })
+    }
+
+    /// Files under `root` that `index_directory` would index (supported
+    /// extension, not excluded, under the size limit) but that have no file
+    /// record for `repo_id`. Returned as sorted repo-relative paths.
+    pub fn find_unindexed_files(&self, root: &Path, repo_id: i64) -> Result<Vec<String>> {
+        let indexed: HashSet<String> = self
+            .db
+            .get_files_for_repo(repo_id)?
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+
+        let mut missing = Vec::new();
+        for entry in WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| !self.is_excluded(e.path()) && self.generated_dir(e).is_none())
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let supported = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| self.registry.for_extension(ext).is_some());
+            if !supported {
+                continue;
+            }
+            if entry.metadata().map(|m| m.len() > self.max_partial_file_size).unwrap_or(true) {
+                continue;
+            }
+            let rel_path = crate::paths::repo_relative(path, root);
+            if !indexed.contains(&rel_path) && !self.deny.is_denied(&rel_path) {
+                missing.push(rel_path);
+            }
+        }
+        missing.sort();
+        Ok(missing)
+    }
+
+    /// Report a per-file failure in the run's stats and persist it for
+    /// `get_index_errors`.
+    fn note_error(
+        &self,
+        stats: &mut IndexStats,
+        repo_id: i64,
+        path: &Path,
+        rel_path: &str,
+        kind: &str,
+        message: &str,
+    ) -> Result<()> {
+        stats.errors.push(format!("{}: {message}", path.display()));
+        self.db.record_index_error(repo_id, rel_path, kind, message, None)
+    }
+
+    /// A directory below the walk root that `guardrails` identifies as
+    /// generated output, with its pattern and matching file count.
+    fn generated_dir(&self, entry: &DirEntry) -> Option<(String, usize)> {
+        if entry.depth() == 0 || !entry.file_type().is_dir() {
+            return None;
+        }
+        self.guardrails.generated_dir(entry.path(), |name| has_grammar(self.registry, name))
+    }
+
+    fn limit_exceeded(&self, root: &Path, limit: &'static str, count: usize, max: usize) -> anyhow::Error {
+        LimitExceeded { root: root.display().to_string(), limit, count, max }.into()
+    }
+
+    /// Whether the size caps apply to `repo_id`; `force` waives them for good.
+    fn capped(&self, repo_id: i64) -> Result<bool> {
+        if self.force {
+            self.db.set_limits_waived(repo_id, true)?;
+        }
+        Ok(!self.db.limits_waived(repo_id)?)
+    }
+
+    fn check_symbol_cap(&self, repo_id: i64, root: &Path) -> Result<()> {
+        let symbols = self.db.count_repo_symbols(repo_id)?;
+        if symbols > self.guardrails.max_symbols {
+            return Err(self.limit_exceeded(root, "symbols", symbols, self.guardrails.max_symbols));
+        }
+        Ok(())
+    }
+
     /// Returns true if any component of the path matches an exclude pattern.
     fn is_excluded(&self, path: &Path) -> bool {
         for component in path.components() {
@@ -429,40 +1965,273 @@ impl<'a> Indexer<'a> {
     }
 }
 
+/// A reference by name: (from symbol, to symbol, edge kind).
+type Reference = (String, String, String);
+
+/// Edges resolved from one file's references, each with the distinct
+/// argument lists of its call sites, so every edge is written once.
+#[derive(Default)]
+struct FileEdges {
+    edges: BTreeMap<(i64, i64, String), Vec<Vec<String>>>,
+}
+
+impl FileEdges {
+    fn add(&mut self, source_id: i64, target_id: i64, r: &ExtractedReference) {
+        let sites = self.edges.entry((source_id, target_id, r.kind.clone())).or_default();
+        if let Some(args) = &r.args {
+            if sites.len() < MAX_CALL_SITES && !sites.contains(args) {
+                sites.push(args.clone());
+            }
+        }
+    }
+
+    fn write(self, db: &Database) -> Result<()> {
+        for ((source_id, target_id, kind), sites) in self.edges {
+            db.insert_edge_with_call_sites(source_id, target_id, &kind, &sites)?;
+        }
+        Ok(())
+    }
+}
+
+/// The directory a `reindex_paths` glob can match under: its literal
+/// leading segments when anchored, the root otherwise.
+fn glob_base(glob: &str) -> String {
+    let trimmed = glob.trim_end_matches('/');
+    if !trimmed.starts_with('/') && !trimmed.contains('/') {
+        return String::new();
+    }
+    trimmed
+        .trim_start_matches('/')
+        .split('/')
+        .take_while(|seg| !seg.contains(['*', '?']))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Parse `source` and return its references.
+fn file_references(grammar: &dyn Grammar, source: &[u8]) -> Vec<Reference> {
+    let mut parser = tree_sitter::Parser::new();
+    if parser.set_language(&grammar.language()).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+    grammar
+        .extract_references(source, &tree)
+        .into_iter()
+        .map(|r| (r.from_symbol, r.to_name, r.kind))
+        .collect()
+}
+
+/// Whether a directory between `root` and `path` would be skipped by a walk
+/// as generated output.
+fn in_generated_dir(guardrails: &Guardrails, registry: &GrammarRegistry, path: &Path, root: &Path) -> bool {
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root) && *dir != root)
+        .any(|dir| guardrails.generated_dir(dir, |name| has_grammar(registry, name)).is_some())
+}
+
+/// Whether a file name has an extension with a grammar.
+fn has_grammar(registry: &GrammarRegistry, name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| registry.for_extension(ext).is_some())
+}
+
+/// Run one extraction step, turning a panic into a message in `panics` and
+/// an empty result, so the file keeps what the other steps extracted.
+fn guarded<T: Default>(step: &str, panics: &mut Vec<String>, f: impl FnOnce() -> T) -> T {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_string());
+            panics.push(format!("{step} panicked ({msg}); kept partial results"));
+            T::default()
+        }
+    }
+}
+
+/// Imports and re-exports of a TS/JS file; None for other languages.
+fn module_links(language: &str, refs: &[ExtractedReference]) -> Option<ModuleLinks> {
+    matches!(language, "ts" | "tsx")
+        .then(|| ModuleLinks::parse(refs.iter().map(|r| (r.kind.as_str(), r.to_name.as_str()))))
+}
+
+/// Ids of the declared dependencies that `refs` import, deduplicated.
+fn used_dependencies(declared: &[(i64, Dependency)], language: &str, refs: &[ExtractedReference]) -> Vec<i64> {
+    let mut used: Vec<i64> = refs
+        .iter()
+        .filter(|r| r.kind == "imports")
+        .flat_map(|r| imported_modules(language, &r.to_name))
+        .flat_map(|module| {
+            declared
+                .iter()
+                .filter(move |(_, dep)| module_matches(language, &module, &dep.module))
+                .map(|(id, _)| *id)
+        })
+        .collect();
+    used.sort_unstable();
+    used.dedup();
+    used
+}
+
+/// Names `refs` import from declared dependencies, keyed by the local name
+/// a call would use, with the dependency and the name it exports.
+fn external_names(
+    declared: &[(i64, Dependency)],
+    language: &str,
+    refs: &[ExtractedReference],
+) -> HashMap<String, (String, String)> {
+    let mut names = HashMap::new();
+    for r in refs.iter().filter(|r| r.kind == "imports") {
+        let dependency = imported_modules(language, &r.to_name).into_iter().find_map(|module| {
+            declared.iter().find(|(_, dep)| module_matches(language, &module, &dep.module))
+        });
+        let Some((_, dep)) = dependency else { continue };
+        for (name, local) in imported_names(language, &r.to_name) {
+            names.insert(local, (dep.name.clone(), name));
+        }
+    }
+    names
+}
+
+/// A Go type or interface: its package directory and name.
+type GoName = (String, String);
+
+/// Whether `path` is a Go source file, whose changes need a fresh
+/// `implements` pass.
+pub(crate) fn is_go_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "go")
+}
+
+/// Every method interface `key` requires, following embedded interfaces:
+/// bare names within its package, qualified ones (`store.Getter`) by
+/// qualified name. None when one of them isn't indexed or they embed each
+/// other in a cycle.
+fn required_methods(
+    key: &GoName,
+    interfaces: &HashMap<GoName, (i64, go::InterfaceMembers)>,
+    qualified: &HashMap<String, GoName>,
+    visiting: &mut Vec<GoName>,
+) -> Option<HashSet<String>> {
+    if visiting.contains(key) {
+        return None;
+    }
+    let (_, members) = interfaces.get(key)?;
+    visiting.push(key.clone());
+    let mut required: HashSet<String> = members.methods.iter().cloned().collect();
+    for embedded in &members.embedded {
+        let embedded_key = match embedded.contains('.') {
+            true => qualified.get(embedded)?.clone(),
+            false => (key.0.clone(), embedded.clone()),
+        };
+        required.extend(required_methods(&embedded_key, interfaces, qualified, visiting)?);
+    }
+    visiting.pop();
+    Some(required)
+}
+
+/// Number of ERROR/MISSING nodes in `tree` and the 1-based line of the
+/// first, or None when the file parsed cleanly.
+fn syntax_errors(tree: &tree_sitter::Tree) -> Option<(usize, i64)> {
+    if !tree.root_node().has_error() {
+        return None;
+    }
+    let mut count = 0;
+    let mut first: Option<i64> = None;
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if node.is_error() || node.is_missing() {
+            count += 1;
+            let line = node.start_position().row as i64 + 1;
+            first = Some(first.map_or(line, |f| f.min(line)));
+            continue;
+        }
+        if node.has_error() {
+            let mut cursor = node.walk();
+            stack.extend(node.children(&mut cursor));
+        }
+    }
+    Some((count.max(1), first.unwrap_or(1)))
+}
+
+fn syntax_message(count: usize) -> String {
+    format!("{count} syntax error(s); symbols were extracted from the parts that parsed")
+}
+
+/// Number of lines in `source`; a final line without a newline counts.
+fn count_lines(source: &[u8]) -> i64 {
+    let newlines = source.iter().filter(|&&b| b == b'\n').count();
+    let unterminated = source.last().is_some_and(|&b| b != b'\n');
+    (newlines + usize::from(unterminated)) as i64
+}
+
 // ---------------------------------------------------------------------------
 // Qualified name enrichment
 // ---------------------------------------------------------------------------
 
 /// Enrich a symbol's grammar-produced qualified_name with file-derived module context.
 /// Go grammars already produce `package.Symbol`, so they pass through unchanged.
-/// Rust/TS/Python get a module prefix derived from the file path.
+/// Rust/TS/Python get a module prefix derived from the file path: `db::Config::new`,
+/// and the dotted `utils.Validation.isValid` and `pkg.mod.Class.method`.
 fn compute_qualified_name(sym: &ExtractedSymbol, rel_path: &str, language: &str) -> String {
     // Go: grammar already produces package.Function — use as-is
     if language == "go" {
         return sym.qualified_name.clone();
     }
+    let local = if sym.qualified_name.is_empty() { &sym.name } else { &sym.qualified_name };
+    module_qualified(local, rel_path, language)
+}
 
-    // For Rust/TS/Python: derive module prefix from file path
+/// `local` (a grammar-level qualified name such as `Type::method`) prefixed
+/// with the module of `rel_path`, joined with the language's separator.
+fn module_qualified(local: &str, rel_path: &str, language: &str) -> String {
     let module = file_to_module(rel_path, language);
-    if module.is_empty() {
-        return sym.qualified_name.clone();
+    match language {
+        _ if module.is_empty() => local.to_string(),
+        "py" | "ts" => format!("{module}.{local}"),
+        _ => format!("{module}::{local}"),
     }
+}
 
-    if sym.qualified_name.is_empty() || sym.qualified_name == sym.name {
-        format!("{module}::{}", sym.name)
-    } else {
-        // Already has some qualification (e.g., Type::method)
-        format!("{module}::{}", sym.qualified_name)
-    }
+/// The symbol of `file_symbols` a reference comes from. Grammars name it by
+/// its grammar-level qualified name, so two methods or namespace members
+/// sharing a bare name stay apart; Go, whose qualified names carry the
+/// package instead, and older references fall back to the bare name.
+fn source_symbol<'s>(file_symbols: &'s [Symbol], r: &ExtractedReference, rel_path: &str, language: &str) -> Option<&'s Symbol> {
+    let qualified = module_qualified(&r.from_symbol, rel_path, language);
+    file_symbols
+        .iter()
+        .find(|s| s.qualified_name == qualified)
+        .or_else(|| file_symbols.iter().find(|s| s.name == r.from_symbol))
 }
 
 /// Derive a module path from a file's relative path within the repo.
 ///
 /// Rust: `src/grammar/mod.rs` → `grammar`, `src/db.rs` → `db`
-/// TS/Python: `utils.ts` → `utils`, `lib/helpers.py` → `helpers`
-fn file_to_module(rel_path: &str, language: &str) -> String {
+/// Python: the dotted package path, `src/pkg/mod.py` → `pkg.mod`,
+/// `pkg/__init__.py` → `pkg`
+/// TS: `utils.ts` → `utils`
+pub(crate) fn file_to_module(rel_path: &str, language: &str) -> String {
     let p = std::path::Path::new(rel_path);
 
+    if language == "py" {
+        let stripped = rel_path.strip_prefix("src/").unwrap_or(rel_path);
+        let module = stripped.rsplit_once('.').map_or(stripped, |(stem, _)| stem);
+        let module = module.strip_suffix("/__init__").unwrap_or(module);
+        if module == "__init__" {
+            return String::new();
+        }
+        return module.replace('/', ".");
+    }
+
     if language == "rs" {
         let stripped = rel_path.strip_prefix("src/").unwrap_or(rel_path);
         let sp = std::path::Path::new(stripped);
@@ -484,7 +2253,7 @@ fn file_to_module(rel_path: &str, language: &str) -> String {
                 .join("::")
         }
     } else {
-        // TS/Python: use filename without extension
+        // TS: use filename without extension
         p.file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("")
@@ -1,9 +1,64 @@
+pub mod api;
+pub mod audit;
+pub mod backup;
+pub mod barrels;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod builtins;
 pub mod config;
 pub mod context;
+pub mod coupling;
+pub mod coverage;
 pub mod db;
+pub mod deny;
+pub mod dependencies;
+pub mod diagnostics;
+pub mod diff;
+pub mod encoding;
+pub mod encryption;
+pub mod env;
+pub mod explain;
+pub mod facade;
+#[cfg(feature = "bench")]
+pub mod fixture;
+pub mod format;
+pub mod freshness;
+pub mod generated;
+pub mod git;
 pub mod grammar;
 pub mod graph;
+pub mod graph_cache;
+pub mod graph_snapshot;
+pub mod guardrails;
+pub mod hooks;
+pub mod impact;
+pub mod index_import;
 pub mod indexer;
+pub mod log_source;
 pub mod manifest;
+#[cfg(feature = "server")]
 pub mod mcp;
+pub mod memory;
+pub mod module_docs;
+pub mod onboarding;
+pub mod owners;
+pub mod packages;
+pub mod paths;
+pub mod probes;
+pub mod proximity;
+pub mod query_cache;
+pub mod rate_limit;
+pub mod redact;
+pub mod reindex_scheduler;
+pub mod rename;
+pub mod report;
+pub mod session_budget;
+pub mod sniff;
+pub mod sql;
+pub mod stacktrace;
+pub mod test_map;
+pub mod tree_query;
 pub mod watcher;
+pub mod working_set;
+
+pub use facade::{Focal, FocalBuilder};
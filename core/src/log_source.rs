@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::db::{Database, Symbol};
+
+// ---------------------------------------------------------------------------
+// Log-line → source lookup
+// ---------------------------------------------------------------------------
+//
+// A log line is its format string with the placeholders filled in. Tokens
+// that look like filled-in values (anything with a digit, paths, quoted
+// strings, the value half of `key=value`) and log-level prefixes are dropped;
+// the literal runs left between them are searched for in symbol bodies.
+
+/// Fragments shorter than this are too common to search on their own.
+const MIN_FRAGMENT_LEN: usize = 4;
+/// Candidates fetched per fragment.
+const CANDIDATES_PER_FRAGMENT: i64 = 50;
+
+const LOG_LEVELS: &[&str] = &[
+    "trace", "debug", "info", "notice", "warn", "warning", "error", "err", "fatal", "critical", "panic",
+];
+
+/// A symbol whose body contains part of the log line's literal text.
+#[derive(Debug, Serialize)]
+pub struct LogSourceMatch {
+    #[serde(skip)]
+    pub symbol_id: i64,
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    /// Line of the first matched fragment.
+    pub line: i64,
+    /// Share of the line's literal text found in the body, 0-1.
+    pub score: f64,
+    pub matched: Vec<String>,
+    /// Body line containing the first matched fragment.
+    pub excerpt: String,
+}
+
+/// The literal runs of `message`, longest first, with values and log-level
+/// tokens removed. `"ERROR user 42 failed to connect to db-7: timeout"` →
+/// `["failed to connect to", "timeout", "user"]`. Fragments shorter than
+/// `MIN_FRAGMENT_LEN` are kept only when nothing longer exists.
+pub fn literal_fragments(message: &str) -> Vec<String> {
+    let mut fragments: Vec<String> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let flush = |current: &mut Vec<&str>, fragments: &mut Vec<String>| {
+        let text = current.join(" ");
+        let text = text.trim_matches(|c: char| !c.is_alphanumeric() && c != '_');
+        if !text.is_empty() && !fragments.iter().any(|f| f == text) {
+            fragments.push(text.to_string());
+        }
+        current.clear();
+    };
+
+    for token in message.split_whitespace() {
+        if let Some((key, _)) = token.split_once('=') {
+            // `key=value`: the key (and `=`) is literal, the value is not.
+            if !key.is_empty() && !is_variable(key) {
+                current.push(&token[..=key.len()]);
+            }
+            flush(&mut current, &mut fragments);
+        } else if is_variable(token) || is_log_level(token) {
+            flush(&mut current, &mut fragments);
+        } else {
+            current.push(token);
+        }
+    }
+    flush(&mut current, &mut fragments);
+
+    fragments.sort_by_key(|f| std::cmp::Reverse(f.len()));
+    if fragments.first().is_some_and(|f| f.len() >= MIN_FRAGMENT_LEN) {
+        fragments.retain(|f| f.len() >= MIN_FRAGMENT_LEN);
+    }
+    fragments
+}
+
+fn is_variable(token: &str) -> bool {
+    token.chars().any(|c| c.is_ascii_digit())
+        || token.contains(['/', '\\', '"', '\'', '`', '@'])
+}
+
+fn is_log_level(token: &str) -> bool {
+    let word = token.trim_matches(|c: char| !c.is_alphabetic());
+    !word.is_empty() && LOG_LEVELS.contains(&word.to_ascii_lowercase().as_str())
+}
+
+/// Find the symbols most likely to have produced `message`, best first.
+/// Candidates come from each fragment's body search; each is then scored by
+/// how much of the literal text its body contains (case-sensitive). When a
+/// symbol and the symbol nested in it score the same, only the inner one —
+/// the function that actually logs — is kept.
+pub fn find_log_source(
+    db: &Database,
+    message: &str,
+    repo_id: Option<i64>,
+    max_results: usize,
+) -> Result<Vec<LogSourceMatch>> {
+    let fragments = literal_fragments(message);
+    let total: usize = fragments.iter().map(String::len).sum();
+    if total == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut candidates: HashMap<i64, Symbol> = HashMap::new();
+    for fragment in fragments.iter().take(5) {
+        for sym in db.find_symbols_containing(fragment, repo_id, CANDIDATES_PER_FRAGMENT)? {
+            candidates.entry(sym.id).or_insert(sym);
+        }
+    }
+
+    let mut scored: Vec<(f64, &Symbol, Vec<String>)> = candidates
+        .values()
+        .filter_map(|sym| {
+            let matched: Vec<String> = fragments.iter().filter(|f| sym.body.contains(f.as_str())).cloned().collect();
+            let score = matched.iter().map(String::len).sum::<usize>() as f64 / total as f64;
+            (score > 0.0).then_some((score, sym, matched))
+        })
+        .collect();
+
+    let scores: HashMap<i64, f64> = scored.iter().map(|(score, sym, _)| (sym.id, *score)).collect();
+    let shadowed: HashSet<i64> = scored
+        .iter()
+        .filter_map(|(score, sym, _)| {
+            let parent = sym.parent_id?;
+            (scores.get(&parent).is_some_and(|p| p <= score)).then_some(parent)
+        })
+        .collect();
+    scored.retain(|(_, sym, _)| !shadowed.contains(&sym.id));
+    scored.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then_with(|| (a.1.end_line - a.1.start_line).cmp(&(b.1.end_line - b.1.start_line)))
+            .then_with(|| a.1.id.cmp(&b.1.id))
+    });
+    scored.truncate(max_results);
+
+    scored
+        .into_iter()
+        .map(|(score, sym, matched)| {
+            let first = matched.first().map(String::as_str).unwrap_or_default();
+            let (offset, excerpt) = sym
+                .body
+                .lines()
+                .enumerate()
+                .find(|(_, l)| l.contains(first))
+                .map(|(i, l)| (i as i64, l.trim().to_string()))
+                .unwrap_or_default();
+            Ok(LogSourceMatch {
+                symbol_id: sym.id,
+                name: sym.name.clone(),
+                kind: sym.kind.clone(),
+                file_path: db.get_file_path_for_symbol(sym.id)?,
+                line: sym.start_line + offset,
+                score: (score * 100.0).round() / 100.0,
+                matched,
+                excerpt,
+            })
+        })
+        .collect()
+}
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use clap::{Parser, Subcommand};
 use rmcp::ServiceExt;
@@ -11,11 +11,16 @@ use rmcp::transport::streamable_http_server::{
 };
 use tokio_util::sync::CancellationToken;
 
-use focal_core::db::Database;
+use focal_core::db::{Database, SearchFilter};
 use focal_core::grammar::GrammarRegistry;
+use focal_core::guardrails::LimitExceeded;
+use focal_core::index_import::DumpFormat;
 use focal_core::indexer::Indexer;
 use focal_core::mcp::FocalServer;
+use focal_core::reindex_scheduler::{HotFiles, ReindexScheduler, DEFAULT_QUEUE_CAPACITY};
+use focal_core::report::{default_report_path, write_report, IndexReport};
 use focal_core::watcher::FileWatcher;
+use focal_core::Focal;
 
 #[derive(Parser)]
 #[command(name = "focal", about = "Structural code index for Claude Code")]
@@ -24,7 +29,8 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Workspace root paths to index (backwards-compatible shorthand for `focal serve`)
+    /// Workspace root paths to index (backwards-compatible shorthand for
+    /// `focal serve`); defaults to $FOCAL_WORKSPACES
     #[arg(global = false)]
     paths: Vec<PathBuf>,
 
@@ -33,20 +39,89 @@ struct Cli {
     http: bool,
 
     /// HTTP port (only with --http)
-    #[arg(long, default_value = "3100")]
+    #[arg(long, env = focal_core::config::PORT_ENV, default_value = "3100")]
     port: u16,
+
+    /// HTTP bind address (only with --http); 0.0.0.0 inside containers
+    #[arg(long, env = focal_core::config::HOST_ENV, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Create the database encrypted (first run only; needs the `encryption` feature)
+    #[arg(long)]
+    encrypt: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Index workspace(s) and serve MCP (default behavior)
     Serve {
-        #[arg(required = true)]
+        /// Workspace roots; defaults to $FOCAL_WORKSPACES
         paths: Vec<PathBuf>,
         #[arg(long)]
         http: bool,
-        #[arg(long, default_value = "3100")]
+        #[arg(long, env = focal_core::config::PORT_ENV, default_value = "3100")]
         port: u16,
+        /// HTTP bind address; 0.0.0.0 inside containers
+        #[arg(long, env = focal_core::config::HOST_ENV, default_value = "127.0.0.1")]
+        host: String,
+        /// Create the database encrypted (first run only; needs the `encryption` feature)
+        #[arg(long)]
+        encrypt: bool,
+    },
+    /// Index a workspace once and exit (for CI index builds)
+    Index {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Index this git revision (branch, tag, or SHA) instead of the working tree
+        #[arg(long)]
+        rev: Option<String>,
+        /// Create the database encrypted (first run only; needs the `encryption` feature)
+        #[arg(long)]
+        encrypt: bool,
+        /// Index past the per-repository file and symbol caps, and waive them for this repository
+        #[arg(long)]
+        force: bool,
+        /// Report what would be indexed and skipped, and the estimated database size, without writing anything
+        #[arg(long, conflicts_with_all = ["rev", "encrypt"])]
+        dry_run: bool,
+        /// Print the dry-run report as JSON (only with --dry-run)
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+    },
+    /// Re-index a workspace; with --changed-only, only files whose content changed; with globs, only matching files
+    Reindex {
+        /// Workspace root (default: the current directory), then repo-relative,
+        /// gitignore-style globs (`gen/`, `/api/*.go`). Globs alone reindex the
+        /// indexed repository containing the current directory.
+        targets: Vec<String>,
+        /// Skip unchanged files and repo-wide edge resolution (used by git hooks)
+        #[arg(long)]
+        changed_only: bool,
+        /// Create the database encrypted (first run only; needs the `encryption` feature)
+        #[arg(long)]
+        encrypt: bool,
+        /// Index past the per-repository file and symbol caps, and waive them for this repository
+        #[arg(long)]
+        force: bool,
+    },
+    /// Map uncommitted changes to symbols and print their blast radius and tests
+    Impact {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Only staged changes (default: all changes against HEAD)
+        #[arg(long)]
+        staged: bool,
+        /// Reverse-dependency hops to follow
+        #[arg(long, default_value = "2")]
+        depth: usize,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Manage git hooks that keep the index fresh without the watcher
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
     },
     /// Run interactive setup wizard
     Init,
@@ -65,6 +140,94 @@ enum Commands {
         #[arg(long)]
         git: Option<String>,
     },
+    /// Import a ctags or LSIF dump for files no grammar covers
+    ImportTags {
+        /// The `tags` file, ctags JSON output, or LSIF dump
+        dump: PathBuf,
+        /// Workspace root the dump's paths are relative to
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+        /// `ctags` or `lsif` (detected when omitted)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Check the index for problems and optionally repair them
+    Doctor {
+        /// Apply database-only repairs (drop missing repos, merge duplicate repos, repair FTS, delete orphaned edges)
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Snapshot the index, memories included, into the backup directory
+    Backup {
+        /// Backup directory (default `[backup] dir`, else ~/.focal/backups)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// List the backups, oldest first, instead of making one
+        #[arg(long)]
+        list: bool,
+    },
+    /// Replace the index with a backup; the current index is backed up first
+    Restore {
+        /// Backup file to restore (default: the newest in the backup directory)
+        backup: Option<PathBuf>,
+        /// Backup directory (default `[backup] dir`, else ~/.focal/backups)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Full-text search the index and exit (read-only, no server needed)
+    Query {
+        query: String,
+        /// Filter by symbol kind (function, method, class, ...)
+        #[arg(long)]
+        kind: Option<String>,
+        /// Limit to this repository name
+        #[arg(long)]
+        repo: Option<String>,
+        #[arg(long, default_value = "20")]
+        max_results: i64,
+        /// Also match symbols in generated files (protobuf stubs, compiled bundles)
+        #[arg(long)]
+        include_generated: bool,
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a file's signatures-only skeleton and exit (read-only)
+    Skeleton {
+        file: String,
+        /// Repository name, when the path is ambiguous across repos
+        #[arg(long)]
+        repo: Option<String>,
+        /// Print the skeleton as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Index a generated synthetic repo and report throughput and latencies
+    #[cfg(feature = "bench")]
+    #[command(hide = true)]
+    Bench {
+        /// Number of generated source files
+        #[arg(long, default_value = "200")]
+        files: usize,
+        /// Functions per generated file
+        #[arg(long, default_value = "10")]
+        functions: usize,
+        /// Search and capsule calls to time
+        #[arg(long, default_value = "100")]
+        iterations: usize,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HookAction {
+    /// Add `focal reindex --changed-only` to the post-commit and post-checkout hooks
+    Install {
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
 }
 
 fn run_init_wizard() -> anyhow::Result<()> {
@@ -83,7 +246,7 @@ fn run_init_wizard() -> anyhow::Result<()> {
     let workspace_path = if workspace.is_empty() {
         cwd.clone()
     } else {
-        PathBuf::from(workspace).canonicalize()?
+        focal_core::paths::canonicalize(&PathBuf::from(workspace))?
     };
 
     // Resolve binary path
@@ -119,22 +282,21 @@ fn run_init_wizard() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn run_export(path: PathBuf, output: Option<PathBuf>) -> anyhow::Result<()> {
-    let workspace = path.canonicalize()?;
+/// The index database path, with its directory created.
+fn create_db_path() -> anyhow::Result<PathBuf> {
+    let db_path = focal_core::config::db_path();
+    if let Some(dir) = db_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(db_path)
+}
 
-    let db_dir = dirs::home_dir()
-        .expect("failed to determine home directory")
-        .join(".focal");
-    let db_path = db_dir.join("index.db");
+fn run_export(path: PathBuf, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let workspace = focal_core::paths::canonicalize(&path)?;
 
-    if !db_path.exists() {
-        anyhow::bail!(
-            "no Focal database found at {}. Run 'focal serve' first.",
-            db_path.display()
-        );
-    }
+    let db_path = existing_db_path()?;
 
-    let db = focal_core::db::Database::open(&db_path.to_string_lossy())?;
+    let db = focal_core::encryption::open(&db_path, false)?;
 
     let repo = db
         .get_repository_by_path(&workspace.to_string_lossy())?
@@ -175,12 +337,8 @@ fn run_import(
     dir: Option<PathBuf>,
     git: Option<String>,
 ) -> anyhow::Result<()> {
-    let db_dir = dirs::home_dir()
-        .expect("failed to determine home directory")
-        .join(".focal");
-    std::fs::create_dir_all(&db_dir)?;
-    let db_path = db_dir.join("index.db");
-    let db = focal_core::db::Database::open(&db_path.to_string_lossy())?;
+    let db_path = create_db_path()?;
+    let db = focal_core::encryption::open(&db_path, false)?;
 
     let mut manifests_to_import: Vec<PathBuf> = Vec::new();
 
@@ -228,19 +386,487 @@ fn run_import(
     Ok(())
 }
 
-async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result<()> {
+fn run_import_tags(dump: PathBuf, root: PathBuf, format: Option<String>) -> anyhow::Result<()> {
+    let format = format.as_deref().map(DumpFormat::parse).transpose()?;
+    let root = focal_core::paths::canonicalize(&root)?;
+    let index = focal_core::index_import::load(&dump, format, &root)?;
+
+    let db_path = create_db_path()?;
+    let db = focal_core::encryption::open(&db_path, false)?;
+    let deny = focal_core::config::FocalConfig::load().index.deny_list();
+    let stats = focal_core::index_import::import(&db, &root, &index, &deny)?;
+    eprintln!(
+        "Imported {} symbols and {} edges in {} files from {}",
+        stats.symbols,
+        stats.edges,
+        stats.files,
+        dump.display()
+    );
+    if stats.skipped_files > 0 {
+        eprintln!("Skipped {} files already indexed by a grammar", stats.skipped_files);
+    }
+    if stats.removed_files > 0 {
+        eprintln!("Removed {} files no longer in the dump", stats.removed_files);
+    }
+    if stats.denied_files > 0 {
+        eprintln!("Skipped {} files matching [index] deny_paths", stats.denied_files);
+    }
+    Ok(())
+}
+
+fn run_index(
+    path: PathBuf,
+    rev: Option<String>,
+    globs: &[String],
+    changed_only: bool,
+    encrypt: bool,
+    force: bool,
+) -> anyhow::Result<()> {
+    let db_path = create_db_path()?;
+    let db = focal_core::encryption::open(&db_path, encrypt)?;
+
+    let registry = GrammarRegistry::new();
+    let config = focal_core::config::FocalConfig::load();
+    db.sync_repo_names(&config.repo_names())?;
+    db.sync_projects(&config.projects)?;
+    let indexer = |force| {
+        Indexer::new(&db, &registry)
+            .with_builtin_filter(config.edges.builtin_filter())
+            .with_redaction(config.index.redact_secrets())
+            .with_deny_list(config.index.deny_list())
+            .with_guardrails(config.index.guardrails())
+            .with_force(force)
+    };
+    let run = |indexer: Indexer| match &rev {
+        Some(rev) => indexer.index_revision(&path, rev),
+        None if !globs.is_empty() => indexer.reindex_paths(&path, globs),
+        None if changed_only => indexer.reindex_changed(&path),
+        None => indexer.index_directory(&path),
+    };
+    let start = Instant::now();
+    let stats = match run(indexer(force)) {
+        Ok(stats) => stats,
+        Err(e) => match e.downcast_ref::<LimitExceeded>() {
+            Some(limit) if confirm_past_limit(limit)? => run(indexer(true))?,
+            _ => return Err(e),
+        },
+    };
+    let mut report = IndexReport::default();
+    report.add(&path, &stats);
+    report.finish(start.elapsed());
+    if let Err(e) = write_report(&default_report_path(), &report) {
+        eprintln!("warning: {e:#}");
+    }
+
+    for err in &stats.errors {
+        eprintln!("warning: {err}");
+    }
+    for dir in &stats.generated_dirs {
+        eprintln!("skipped generated directory {dir}");
+    }
+    if stats.files_removed > 0 {
+        eprintln!("Removed {} files no longer on disk", stats.files_removed);
+    }
+    let revision = focal_core::paths::canonicalize(&path)
+        .ok()
+        .and_then(|root| db.get_repository_by_path(&root.to_string_lossy()).ok().flatten())
+        .and_then(|repo| repo.revision);
+    eprintln!(
+        "Indexed {} files ({} skipped{}{}), {} symbols, {} edges{}",
+        stats.files_indexed,
+        stats.files_skipped,
+        match stats.files_binary {
+            0 => String::new(),
+            n => format!(", {n} binary"),
+        },
+        match stats.files_partial {
+            0 => String::new(),
+            n if stats.files_minified > 0 => format!(", {n} oversized or minified without bodies"),
+            n => format!(", {n} oversized without bodies"),
+        },
+        stats.symbols_extracted,
+        stats.edges_created,
+        revision.map(|r| format!(" at {r}")).unwrap_or_default()
+    );
+
+    Ok(())
+}
+
+fn run_index_dry_run(path: PathBuf, force: bool, json: bool) -> anyhow::Result<()> {
+    // The index is only consulted for cap waivers, so it needn't exist.
+    let db_path = focal_core::config::db_path();
+    let db = match db_path.exists() {
+        true => focal_core::encryption::open_read_only(&db_path)?,
+        false => Database::open_in_memory()?,
+    };
+    let registry = GrammarRegistry::new();
+    let config = focal_core::config::FocalConfig::load();
+    let run = Indexer::new(&db, &registry)
+        .with_deny_list(config.index.deny_list())
+        .with_guardrails(config.index.guardrails())
+        .with_force(force)
+        .dry_run(&path)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&run)?);
+        return Ok(());
+    }
+
+    let size = |bytes: u64| match bytes {
+        0..1_048_576 => format!("{} KB", bytes.div_ceil(1024)),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    };
+    println!("Dry run of {} (nothing written)", run.root);
+    println!("Would index {} files ({}):", run.files(), size(run.bytes()));
+    for (language, count) in &run.languages {
+        println!("  {language:<12} {:>7} files  {:>10}", count.files, size(count.bytes));
+    }
+    if run.partial > 0 {
+        println!("  {} oversized or minified, indexed without bodies", run.partial);
+    }
+    if run.generated > 0 {
+        println!("  {} generated, left out of search", run.generated);
+    }
+    let skipped: usize = run.skipped.values().map(|s| s.files).sum();
+    if skipped > 0 {
+        println!("Would skip {skipped} files:");
+        for (reason, files) in &run.skipped {
+            println!("  {reason:<12} {:>7} files  e.g. {}", files.files, files.examples.join(", "));
+        }
+    }
+    if !run.excluded_dirs.is_empty() {
+        println!("Excluded directories: {}", run.excluded_dirs.join(", "));
+    }
+    for dir in &run.generated_dirs {
+        println!("Generated directory: {dir}");
+    }
+    println!("Estimated database growth: ~{}", size(run.estimated_db_bytes));
+    if let Some(cap) = run.file_cap {
+        println!(
+            "Over the {cap}-file cap: `focal index` would refuse this tree; pass --force or raise [index] max_files_per_repo"
+        );
+    }
+    Ok(())
+}
+
+/// Split `focal reindex` positionals into a root and path globs. The first is
+/// the root when it is a directory that is an indexed repository or lies
+/// outside every indexed one (`focal reindex ~/src/shop 'gen/'`); otherwise
+/// all of them are globs for the indexed repository containing the current
+/// directory (`focal reindex 'gen/' '**/*_pb.rs'`).
+fn split_reindex_targets(mut targets: Vec<String>) -> anyhow::Result<(PathBuf, Vec<String>)> {
+    if targets.is_empty() {
+        return Ok((PathBuf::from("."), targets));
+    }
+    let roots: Vec<PathBuf> = open_index_read_only()
+        .and_then(|db| db.list_repositories())
+        .map(|repos| repos.into_iter().map(|r| PathBuf::from(r.root_path)).collect())
+        .unwrap_or_default();
+    let containing = |path: &std::path::Path| {
+        roots.iter().filter(|r| path.starts_with(r)).max_by_key(|r| r.as_os_str().len()).cloned()
+    };
+    if let Ok(first) = focal_core::paths::canonicalize(std::path::Path::new(&targets[0])) {
+        if first.is_dir() && containing(&first).is_none_or(|r| r == first) {
+            targets.remove(0);
+            return Ok((first, targets));
+        }
+    }
+    let cwd = focal_core::paths::canonicalize(std::path::Path::new("."))?;
+    let root = containing(&cwd).ok_or_else(|| {
+        anyhow::anyhow!("{} is not inside an indexed repository; pass its root first", cwd.display())
+    })?;
+    Ok((root, targets))
+}
+
+/// Ask on a terminal whether to index past a guardrail cap. Never asks when
+/// stdin isn't interactive (CI, hooks), so those runs fail instead.
+fn confirm_past_limit(limit: &LimitExceeded) -> anyhow::Result<bool> {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    eprintln!("{limit}");
+    eprint!("Index it anyway? [y/N]: ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
+/// The index database path (see `config::db_path`), failing when no index
+/// has been built there yet.
+fn existing_db_path() -> anyhow::Result<PathBuf> {
+    let db_path = focal_core::config::db_path();
+    if !db_path.exists() {
+        anyhow::bail!("no Focal database found at {}. Run 'focal index' first.", db_path.display());
+    }
+    Ok(db_path)
+}
+
+/// Open the index read-only so one-shot commands never block, or get
+/// blocked by, a running `focal serve`.
+fn open_index_read_only() -> anyhow::Result<Database> {
+    focal_core::encryption::open_read_only(&existing_db_path()?)
+}
+
+fn run_query(
+    query: String,
+    kind: Option<String>,
+    repo: Option<String>,
+    max_results: i64,
+    include_generated: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let db = open_index_read_only()?;
+    let repo_id = match &repo {
+        Some(name) => Some(
+            db.get_repo_id_by_name(name)?
+                .ok_or_else(|| anyhow::anyhow!("repository '{name}' is not indexed"))?,
+        ),
+        None => None,
+    };
+    let filter = SearchFilter {
+        kind: kind.as_deref().unwrap_or(""),
+        include_generated,
+        ..SearchFilter::repo(repo_id.as_ref())
+    };
+    let symbols = db.search_code(&query, &filter, max_results)?;
+    // The index is opened read-only here, so denied files an older run
+    // indexed are filtered rather than purged.
+    let deny = focal_core::config::FocalConfig::load().index.deny_list();
+
+    let mut results = Vec::with_capacity(symbols.len());
+    for sym in &symbols {
+        let file_path = db.get_file_path_for_symbol(sym.id)?;
+        if deny.is_denied(&file_path) {
+            continue;
+        }
+        results.push(serde_json::json!({
+            "name": sym.name,
+            "kind": sym.kind,
+            "signature": sym.signature,
+            "file_path": file_path,
+            "start_line": sym.start_line,
+            "end_line": sym.end_line,
+        }));
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+    for r in &results {
+        println!(
+            "{}:{}\t{}\t{}",
+            r["file_path"].as_str().unwrap_or_default(),
+            r["start_line"],
+            r["kind"].as_str().unwrap_or_default(),
+            r["signature"].as_str().unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+fn run_skeleton(file: String, repo: Option<String>, json: bool) -> anyhow::Result<()> {
+    let db = open_index_read_only()?;
+    let deny = focal_core::config::FocalConfig::load().index.deny_list();
+    let resolved = db
+        .resolve_file_path(&file, repo.as_deref())?
+        .filter(|resolved| !deny.is_denied(&resolved.file_path))
+        .ok_or_else(|| anyhow::anyhow!("file '{file}' not found in the index"))?;
+    let symbols = db.get_skeleton(resolved.file_id, "standard")?;
+
+    if json {
+        let response = serde_json::json!({
+            "repo": resolved.repo,
+            "file_path": resolved.file_path,
+            "symbols": symbols,
+        });
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        return Ok(());
+    }
+    for sym in &symbols {
+        println!("{:>5}  {}", sym.start_line, sym.signature);
+    }
+    Ok(())
+}
+
+fn run_impact(path: PathBuf, staged: bool, depth: usize, json: bool) -> anyhow::Result<()> {
+    let root = focal_core::paths::canonicalize(&path)?;
+    let db = open_index_read_only()?;
+    let repo = db
+        .get_repository_by_path(&root.to_string_lossy())?
+        .ok_or_else(|| anyhow::anyhow!("no index found for {}. Run 'focal index' first.", root.display()))?;
+    let diff = focal_core::git::working_diff(&root, staged)?;
+    let impact = focal_core::impact::diff_impact(&db, repo.id, &diff, focal_core::impact::DiffSide::New, depth.min(5))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&impact)?);
+        return Ok(());
+    }
+    if impact.changed.is_empty() {
+        println!("No indexed symbols changed.");
+    } else {
+        println!("Changed symbols ({}):", impact.changed.len());
+        for sym in &impact.changed {
+            println!("  {} {}  {}:{}-{}", sym.kind, sym.name, sym.file_path, sym.start_line, sym.end_line);
+        }
+    }
+    if !impact.impacted.is_empty() {
+        println!("Impacted ({}):", impact.impacted.len());
+        for node in &impact.impacted {
+            println!("  [{}] {} {}  {}", node.distance, node.kind, node.name, node.file_path);
+        }
+    }
+    if !impact.tests.is_empty() {
+        println!("Tests to run ({}):", impact.tests.len());
+        for test in &impact.tests {
+            println!("  {}  {}:{}", test.test, test.test_file, test.test_line);
+        }
+    }
+    if !impact.unindexed_files.is_empty() {
+        println!("Not indexed: {}", impact.unindexed_files.join(", "));
+    }
+    Ok(())
+}
+
+fn run_hook_install(path: PathBuf) -> anyhow::Result<()> {
+    use focal_core::hooks::HookChange;
+
+    let root = focal_core::paths::canonicalize(&path)?;
+    let binary = std::env::current_exe()?;
+    for change in focal_core::hooks::install_hooks(&root, &binary)? {
+        match change {
+            HookChange::Created(p) => eprintln!("Created {}", p.display()),
+            HookChange::Appended(p) => eprintln!("Added focal reindex to {}", p.display()),
+            HookChange::AlreadyInstalled(p) => eprintln!("{} already runs focal reindex", p.display()),
+        }
+    }
+    Ok(())
+}
+
+fn run_doctor(fix: bool) -> anyhow::Result<()> {
+    let db_path = existing_db_path()?;
+
+    let db = focal_core::encryption::open(&db_path, false)?;
+    let registry = GrammarRegistry::new();
+    let report = focal_core::diagnostics::diagnose_index(&db, &registry)?;
+
+    if report.ok {
+        eprintln!("No problems found.");
+        return Ok(());
+    }
+    for diag in &report.diagnostics {
+        let scope = diag.repo.as_deref().map(|r| format!(" [{r}]")).unwrap_or_default();
+        eprintln!("{}{scope}: {}", diag.check, diag.detail);
+        for example in &diag.examples {
+            eprintln!("    {example}");
+        }
+        eprintln!("  fix: {}", diag.fix);
+    }
+
+    if fix {
+        for action in focal_core::diagnostics::apply_fixes(&db, &report)? {
+            eprintln!("fixed: {action}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run_backup(dir: Option<PathBuf>, list: bool) -> anyhow::Result<()> {
+    let config = focal_core::config::FocalConfig::load().backup;
+    let dir = dir.unwrap_or_else(|| config.dir());
+    if list {
+        for path in focal_core::backup::list(&dir)? {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
+
+    // A read-only connection, so a running server is neither blocked nor
+    // blocking; the backup API still reads a consistent snapshot.
+    let db_path = existing_db_path()?;
+    let db = focal_core::encryption::open_read_only(&db_path)?;
+    let key = focal_core::encryption::key_for(&db_path)?;
+    let snapshot = focal_core::backup::create(&db, &dir, config.keep(), key.as_deref())?;
+    for path in &snapshot.removed {
+        eprintln!("removed old backup {}", path.display());
+    }
+    eprintln!(
+        "Backed up {} ({} memories, {} bytes) to {}",
+        db_path.display(),
+        snapshot.memories,
+        snapshot.bytes,
+        snapshot.path.display()
+    );
+    Ok(())
+}
+
+fn run_restore(backup: Option<PathBuf>, dir: Option<PathBuf>) -> anyhow::Result<()> {
+    let config = focal_core::config::FocalConfig::load().backup;
+    let dir = dir.unwrap_or_else(|| config.dir());
+    let src = match backup {
+        Some(path) => path,
+        None => focal_core::backup::latest(&dir)?
+            .ok_or_else(|| anyhow::anyhow!("no backups in {}; run 'focal backup' first", dir.display()))?,
+    };
+
+    let db_path = create_db_path()?;
+    // The snapshot decides the key: the current index may be too damaged to tell.
+    let key = focal_core::encryption::key_for(&src)?;
+    match focal_core::backup::restore_index(&db_path, &src, &dir, config.keep(), key.as_deref())? {
+        focal_core::backup::IndexRestore::Replaced(restored) => eprintln!(
+            "Restored {} ({} memories) into {}. The previous index was saved as {}; \
+             restart any running focal server.",
+            restored.from.display(),
+            restored.memories,
+            db_path.display(),
+            restored.previous.path.display()
+        ),
+        focal_core::backup::IndexRestore::SetAside { from, memories, corrupt } => eprintln!(
+            "Restored {} ({memories} memories) into {}. The previous index couldn't be opened \
+             and was moved to {}; restart any running focal server.",
+            from.display(),
+            db_path.display(),
+            corrupt.display()
+        ),
+    }
+    Ok(())
+}
+
+/// `paths`, or the roots in `FOCAL_WORKSPACES` when none were given.
+fn workspaces_or_env(paths: Vec<PathBuf>) -> anyhow::Result<Vec<PathBuf>> {
+    if !paths.is_empty() {
+        return Ok(paths);
+    }
+    let paths = focal_core::config::workspaces_from_env();
+    if paths.is_empty() {
+        anyhow::bail!(
+            "no workspace paths: pass them as arguments or set {}",
+            focal_core::config::WORKSPACES_ENV
+        );
+    }
+    Ok(paths)
+}
+
+async fn run_serve(paths: Vec<PathBuf>, http: bool, host: &str, port: u16, encrypt: bool) -> anyhow::Result<()> {
     tracing::info!(?paths, "starting focal");
 
-    // Resolve DB path: ~/.focal/index.db
-    let db_dir = dirs::home_dir()
-        .expect("failed to determine home directory")
-        .join(".focal");
-    std::fs::create_dir_all(&db_dir)?;
-    let db_path = db_dir.join("index.db");
-    let db_path_str = db_path.to_string_lossy().to_string();
+    // Read config.toml once, so every subsystem starts from the same settings
+    let mut config = focal_core::config::FocalConfig::load();
+    let audit_config = std::mem::take(&mut config.audit);
+    let manifests = std::mem::take(&mut config.manifests);
+    let backup_config = std::mem::take(&mut config.backup);
+    let database_config = std::mem::take(&mut config.database);
+    let soft_cap = config.session.soft_cap_tokens;
+    let http_config = std::mem::take(&mut config.http);
 
-    tracing::info!(db = %db_path_str, "opening database");
-    let db = Database::open(&db_path_str)?;
+    // $FOCAL_DB_PATH, else index.db in the focal directory
+    let db_path = create_db_path()?;
+
+    tracing::info!(db = %db_path.display(), "opening database");
+    let db = focal_core::encryption::open(&db_path, encrypt)?;
 
     // Clean up auto-observations older than 90 days
     let cleaned = db.cleanup_old_auto_observations(90)?;
@@ -248,33 +874,58 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
         tracing::info!(cleaned, "purged old auto-observations");
     }
 
-    // Wrap DB in Arc<Mutex<>> before spawning background work
-    let db = Arc::new(Mutex::new(db));
-    let workspace_roots: Vec<_> = paths.clone();
+    // Drop tombstones of files deleted longer ago than the grace period
+    let grace_hours = config.index.tombstone_grace_hours();
+    let watcher_batch_size = config.index.watcher_batch_size();
+    let purged = db.purge_tombstones(grace_hours)?;
+    if purged > 0 {
+        tracing::info!(purged, "purged expired tombstones");
+    }
+
+    // Drop audit rows past their retention
+    let retention_days = audit_config.retention_days();
+    if retention_days > 0 {
+        let purged = db.purge_audit_log(retention_days)?;
+        if purged > 0 {
+            tracing::info!(purged, "purged expired audit rows");
+        }
+    }
+
+    // Merge repository rows that point at one tree under different spellings
+    let merged = db.merge_duplicate_repositories()?;
+    if merged > 0 {
+        tracing::info!(merged, "merged duplicate repositories");
+    }
+
+    // Apply configured repository names, then group repositories into the
+    // configured projects before indexing, so references resolve across members
+    db.sync_repo_names(&config.repo_names())?;
+    db.sync_projects(&config.projects)?;
+
+    let query_cache_bytes = config.memory.query_cache_bytes();
+
+    // Share the DB and index settings with background work and every session
+    let focal = Focal::builder().database(db).workspaces(paths.clone()).config(config).build()?;
+    let db = Arc::clone(focal.database());
 
     // Index each workspace root in the background so MCP starts serving immediately
     let indexing_complete = Arc::new(AtomicBool::new(false));
+    let probes = Arc::new(
+        focal_core::probes::Probes::new(Arc::clone(&indexing_complete))
+            .with_unready_during_reindex(http_config.unready_during_reindex),
+    );
     {
-        let db_clone = Arc::clone(&db);
-        let paths = paths.clone();
+        let focal = focal.clone();
         let indexing_complete_clone = Arc::clone(&indexing_complete);
+        let report_path = default_report_path();
         tokio::task::spawn_blocking(move || {
-            let registry = GrammarRegistry::new();
-            for path in &paths {
+            let start = Instant::now();
+            let mut report = IndexReport::default();
+            for path in focal.workspaces() {
                 tracing::info!(path = %path.display(), "indexing workspace");
-                let result = {
-                    let db = match db_clone.lock() {
-                        Ok(db) => db,
-                        Err(e) => {
-                            tracing::error!(error = %e, "failed to lock DB for indexing");
-                            continue;
-                        }
-                    };
-                    let indexer = Indexer::new(&db, &registry);
-                    indexer.index_directory(path)
-                };
-                match result {
+                match focal.index_workspace(path) {
                     Ok(stats) => {
+                        report.add(path, &stats);
                         tracing::info!(
                             files_indexed = stats.files_indexed,
                             files_skipped = stats.files_skipped,
@@ -289,9 +940,14 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
                     }
                     Err(e) => {
                         tracing::error!(path = %path.display(), error = %e, "failed to index workspace");
+                        report.add_failure(path, &e);
                     }
                 }
             }
+            report.finish(start.elapsed());
+            if let Err(e) = write_report(&report_path, &report) {
+                tracing::warn!(error = %e, "failed to write indexing report");
+            }
             indexing_complete_clone.store(true, Ordering::Relaxed);
             tracing::info!("background indexing finished");
         });
@@ -301,10 +957,7 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
     {
         let db_clone = Arc::clone(&db);
         tokio::task::spawn_blocking(move || {
-            let config = focal_core::config::FocalConfig::load();
-
-            let has_work = !config.manifests.auto_import.is_empty()
-                || !config.manifests.auto_import_git.is_empty();
+            let has_work = !manifests.auto_import.is_empty() || !manifests.auto_import_git.is_empty();
             if !has_work {
                 return;
             }
@@ -318,7 +971,7 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
             };
 
             // Filesystem imports
-            for path_str in &config.manifests.auto_import {
+            for path_str in &manifests.auto_import {
                 let path = std::path::Path::new(path_str);
                 if path.is_dir() {
                     if let Ok(entries) = std::fs::read_dir(path) {
@@ -347,7 +1000,7 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
             }
 
             // Git imports — skip silently on network failure
-            for url in &config.manifests.auto_import_git {
+            for url in &manifests.auto_import_git {
                 if let Ok(m) = focal_core::manifest::fetch_manifest(url) {
                     match focal_core::manifest::import_manifest(&db, &m) {
                         Ok((s, e)) => tracing::info!(symbols = s, edges = e, repo = %m.repo, "auto-imported git manifest"),
@@ -358,11 +1011,30 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
         });
     }
 
-    // Spawn file watcher for incremental re-indexing
+    // Spawn file watcher for incremental re-indexing. Files MCP sessions have
+    // read are recorded in `hot_files` and reindexed first.
+    let hot_files = HotFiles::new();
     {
         let db_clone = Arc::clone(&db);
-        let roots: Vec<PathBuf> = paths.clone();
-        let registry = GrammarRegistry::new();
+        // Canonical roots so relative CLI paths (".") and Windows verbatim
+        // prefixes compare correctly against absolute watcher event paths.
+        let roots: Vec<PathBuf> = paths
+            .iter()
+            .map(|p| focal_core::paths::canonicalize(p).unwrap_or_else(|_| p.clone()))
+            .collect();
+        let focal = focal.clone();
+        let probes = Arc::clone(&probes);
+        let mut scheduler = ReindexScheduler::new(roots.clone(), DEFAULT_QUEUE_CAPACITY)
+            .with_batch_size(watcher_batch_size)
+            .with_hot_files(hot_files.clone());
+        // Batch auto-observations are grouped under one session per server run.
+        let watcher_session = format!(
+            "watcher-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        );
         tokio::spawn(async move {
             let watcher = match FileWatcher::new(&roots, 500) {
                 Ok(w) => w,
@@ -375,70 +1047,117 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
             loop {
                 let changed = watcher.wait_for_changes(Duration::from_secs(60));
                 if changed.is_empty() {
+                    // Idle: expire tombstones past their grace period.
+                    let purged = db_clone.lock().map(|db| db.purge_tombstones(grace_hours));
+                    match purged {
+                        Ok(Ok(purged)) if purged > 0 => tracing::info!(purged, "purged expired tombstones"),
+                        Ok(Err(e)) => tracing::warn!(error = %e, "tombstone purge failed"),
+                        _ => {}
+                    }
+                }
+                scheduler.enqueue(changed);
+                if scheduler.is_empty() {
                     continue;
                 }
-                let mut reindexed = 0;
-                let mut removed = 0;
-                for path in &changed {
-                    let root = roots.iter().find(|r| path.starts_with(r));
-                    if let Some(root) = root {
-                        // Lock per-file to avoid blocking MCP handlers for the
-                        // entire batch duration.
-                        if !path.exists() {
-                            // File was deleted — clean up stale symbols/edges
-                            let result = {
-                                let db = match db_clone.lock() {
-                                    Ok(db) => db,
-                                    Err(e) => {
-                                        tracing::error!(error = %e, "failed to lock DB");
-                                        continue;
-                                    }
-                                };
-                                let indexer = Indexer::new(&db, &registry);
-                                indexer.remove_deleted_file(path, root)
-                            };
-                            match result {
-                                Ok(true) => removed += 1,
-                                Ok(false) => {}
-                                Err(e) => tracing::warn!(path = %path.display(), error = %e, "remove error"),
-                            }
-                            continue;
-                        }
-                        let result = {
-                            let db = match db_clone.lock() {
-                                Ok(db) => db,
-                                Err(e) => {
-                                    tracing::error!(error = %e, "failed to lock DB for re-index");
-                                    continue;
-                                }
-                            };
-                            let indexer = Indexer::new(&db, &registry);
-                            indexer.index_file(path, root)
-                        };
-                        match result {
-                            Ok(true) => reindexed += 1,
-                            Ok(false) => {}
-                            Err(e) => tracing::warn!(path = %path.display(), error = %e, "re-index error"),
-                        }
-                    }
+                let mut target = focal.reindex_target().with_session_id(watcher_session.clone());
+                let stats = {
+                    let _reindexing = probes.begin_reindex();
+                    scheduler.run(&mut target)
+                };
+                for err in &stats.errors {
+                    tracing::warn!(error = %err, "file watcher error");
+                }
+                if stats.reindexed > 0 || stats.removed > 0 || stats.rescanned > 0 {
+                    tracing::info!(
+                        reindexed = stats.reindexed,
+                        removed = stats.removed,
+                        rescanned = stats.rescanned,
+                        batches = stats.batches,
+                        "file watcher processed changes"
+                    );
+                }
+            }
+        });
+    }
+
+    // Checkpoint the WAL periodically; nothing else ever truncates it.
+    let checkpoint_minutes = database_config.wal_checkpoint_minutes();
+    if checkpoint_minutes > 0 {
+        let db_clone = Arc::clone(&db);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(checkpoint_minutes * 60));
+            interval.tick().await; // the first tick fires immediately
+            loop {
+                interval.tick().await;
+                let checkpoint = db_clone.lock().map(|db| db.checkpoint_wal());
+                match checkpoint {
+                    Ok(Ok(c)) if c.busy => tracing::warn!(wal_bytes = c.bytes_after, "WAL checkpoint blocked by a reader"),
+                    Ok(Ok(c)) => tracing::debug!(wal_bytes = c.bytes_before, "WAL checkpointed"),
+                    Ok(Err(e)) => tracing::warn!(error = %e, "WAL checkpoint failed"),
+                    Err(e) => tracing::warn!(error = %e, "WAL checkpoint skipped: failed to lock DB"),
                 }
-                if reindexed > 0 || removed > 0 {
-                    tracing::info!(reindexed, removed, "file watcher processed changes");
+            }
+        });
+    }
+
+    // Back up the index at startup and then whenever the newest backup is
+    // older than the interval, over a separate read-only connection so tool
+    // calls never wait on it.
+    let backup_hours = backup_config.interval_hours();
+    if backup_hours > 0 {
+        let db_path = db_path.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(focal_core::backup::CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                let (dir, keep, db_path) = (backup_config.dir(), backup_config.keep(), db_path.clone());
+                let backup = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+                    if !focal_core::backup::due(&dir, backup_hours)? {
+                        return Ok(None);
+                    }
+                    let db = focal_core::encryption::open_read_only(&db_path)?;
+                    let key = focal_core::encryption::key_for(&db_path)?;
+                    focal_core::backup::create(&db, &dir, keep, key.as_deref()).map(Some)
+                })
+                .await;
+                match backup {
+                    Ok(Ok(Some(s))) => tracing::info!(path = %s.path.display(), memories = s.memories, "index backed up"),
+                    Ok(Ok(None)) => {}
+                    Ok(Err(e)) => tracing::warn!(error = %e, "index backup failed"),
+                    Err(e) => tracing::warn!(error = %e, "index backup task panicked"),
                 }
             }
         });
     }
 
+    let audit = audit_config.enabled(http);
+
     if http {
         let ct = CancellationToken::new();
 
         let indexing_complete_http = Arc::clone(&indexing_complete);
+        let tool_slots = match http_config.max_concurrent_tools() {
+            0 => None,
+            n => Some(Arc::new(tokio::sync::Semaphore::new(n))),
+        };
+        let session_slots = http_config.max_concurrent_tools_per_session();
+        let limiter = Arc::new(focal_core::rate_limit::RateLimiter::new(
+            http_config.requests_per_minute(),
+            http_config.burst(),
+        ));
         let service: StreamableHttpService<FocalServer, LocalSessionManager> =
             StreamableHttpService::new(
                 {
-                    let db = Arc::clone(&db);
-                    let roots = workspace_roots.clone();
-                    move || Ok(FocalServer::new(Arc::clone(&db), roots.clone(), Arc::clone(&indexing_complete_http)))
+                    let focal = focal.clone();
+                    let tool_slots = tool_slots.clone();
+                    move || {
+                        Ok(FocalServer::new(focal.clone(), Arc::clone(&indexing_complete_http))
+                            .with_session_soft_cap(soft_cap)
+                            .with_query_cache_budget(query_cache_bytes)
+                            .with_audit(audit)
+                            .with_concurrency_limits(tool_slots.clone(), session_slots)
+                            .with_hot_files(hot_files.clone()))
+                    }
                 },
                 Default::default(),
                 StreamableHttpServerConfig {
@@ -448,10 +1167,60 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
                 },
             );
 
-        let router = axum::Router::new().nest_service("/mcp", service);
-        let bind_addr = format!("127.0.0.1:{port}");
-        let tcp_listener = tokio::net::TcpListener::bind(&bind_addr).await?;
-        tracing::info!(addr = %bind_addr, "serving MCP over HTTP");
+        let admin_db = Arc::clone(&db);
+        let admin_token = audit_config.admin_token.clone();
+        let mcp = axum::Router::new().nest_service("/mcp", service);
+        let mcp = if limiter.is_enabled() {
+            mcp.layer(axum::middleware::from_fn_with_state(limiter, focal_core::rate_limit::enforce))
+        } else {
+            mcp
+        };
+        let router = mcp
+            .route(
+                "/healthz",
+                axum::routing::get({
+                    let probes = Arc::clone(&probes);
+                    move || {
+                        let probes = Arc::clone(&probes);
+                        async move { json_response(probes.liveness()) }
+                    }
+                }),
+            )
+            .route(
+                "/readyz",
+                axum::routing::get({
+                    let probes = Arc::clone(&probes);
+                    let db = Arc::clone(&db);
+                    move || {
+                        let (probes, db) = (Arc::clone(&probes), Arc::clone(&db));
+                        async move { json_response(probes.readiness(&db)) }
+                    }
+                }),
+            )
+            .route(
+            "/admin/audit",
+            axum::routing::get(
+                move |headers: axum::http::HeaderMap, axum::extract::RawQuery(query): axum::extract::RawQuery| {
+                    let db = Arc::clone(&admin_db);
+                    let admin_token = admin_token.clone();
+                    async move {
+                        let authorization =
+                            headers.get(axum::http::header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+                        json_response(match db.lock() {
+                            Ok(db) => focal_core::audit::admin_response(
+                                &db,
+                                admin_token.as_deref(),
+                                authorization,
+                                query.as_deref().unwrap_or(""),
+                            ),
+                            Err(e) => (500, serde_json::json!({ "error": format!("lock error: {e}") }).to_string()),
+                        })
+                    }
+                },
+            ),
+        );
+        let tcp_listener = tokio::net::TcpListener::bind((host, port)).await?;
+        tracing::info!(addr = %tcp_listener.local_addr()?, "serving MCP over HTTP");
 
         axum::serve(tcp_listener, router)
             .with_graceful_shutdown(async move {
@@ -468,13 +1237,58 @@ async fn run_serve(paths: Vec<PathBuf>, http: bool, port: u16) -> anyhow::Result
 
     // Serve MCP over stdio
     tracing::info!("serving MCP over stdio");
-    let server = FocalServer::new(db, workspace_roots, Arc::clone(&indexing_complete));
+    let server =
+        FocalServer::new(focal, Arc::clone(&indexing_complete))
+            .with_session_soft_cap(soft_cap)
+            .with_query_cache_budget(query_cache_bytes)
+            .with_hot_files(hot_files)
+            .with_audit(audit);
     let running = server.serve(rmcp::transport::stdio()).await?;
     running.waiting().await?;
 
     Ok(())
 }
 
+/// A `(status, JSON body)` pair as an HTTP response.
+fn json_response((status, body): (u16, String)) -> impl axum::response::IntoResponse {
+    (
+        axum::http::StatusCode::from_u16(status).unwrap_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR),
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        body,
+    )
+}
+
+#[cfg(feature = "bench")]
+fn run_bench(files: usize, functions: usize, iterations: usize, json: bool) -> anyhow::Result<()> {
+    let root = std::env::temp_dir().join(format!("focal-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&root)?;
+    let spec = focal_core::fixture::FixtureSpec { files, functions_per_file: functions };
+    let result = focal_core::bench::run_bench(&root, &spec, iterations);
+    let _ = std::fs::remove_dir_all(&root);
+    let report = result?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    println!(
+        "index    {} files, {} symbols, {} edges in {} ms ({} files/s, {} symbols/s)",
+        report.files,
+        report.symbols,
+        report.edges,
+        report.index_ms,
+        report.files_per_sec,
+        report.symbols_per_sec
+    );
+    for (label, l) in [("search", &report.search), ("capsule", &report.capsule)] {
+        println!(
+            "{label:<8} p50 {} µs, p95 {} µs, max {} µs over {} calls",
+            l.p50_us, l.p95_us, l.max_us, l.samples
+        );
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
@@ -488,21 +1302,42 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Some(Commands::Serve { paths, http, port }) => {
-            run_serve(paths, http, port).await
+        Some(Commands::Serve { paths, http, port, host, encrypt }) => {
+            run_serve(workspaces_or_env(paths)?, http, &host, port, encrypt).await
+        }
+        Some(Commands::Index { path, dry_run: true, force, json, .. }) => run_index_dry_run(path, force, json),
+        Some(Commands::Index { path, rev, encrypt, force, .. }) => run_index(path, rev, &[], false, encrypt, force),
+        Some(Commands::Reindex { targets, changed_only, encrypt, force }) => {
+            let (path, globs) = split_reindex_targets(targets)?;
+            run_index(path, None, &globs, changed_only, encrypt, force)
         }
+        Some(Commands::Impact { path, staged, depth, json }) => run_impact(path, staged, depth, json),
+        Some(Commands::Hook { action: HookAction::Install { path } }) => run_hook_install(path),
         Some(Commands::Init) => run_init_wizard(),
         Some(Commands::Export { path, output }) => run_export(path, output),
         Some(Commands::Import { source, dir, git }) => run_import(source, dir, git),
+        Some(Commands::ImportTags { dump, root, format }) => run_import_tags(dump, root, format),
+        Some(Commands::Doctor { fix }) => run_doctor(fix),
+        Some(Commands::Backup { dir, list }) => run_backup(dir, list),
+        Some(Commands::Restore { backup, dir }) => run_restore(backup, dir),
+        Some(Commands::Query { query, kind, repo, max_results, include_generated, json }) => {
+            run_query(query, kind, repo, max_results, include_generated, json)
+        }
+        Some(Commands::Skeleton { file, repo, json }) => run_skeleton(file, repo, json),
+        #[cfg(feature = "bench")]
+        Some(Commands::Bench { files, functions, iterations, json }) => {
+            run_bench(files, functions, iterations, json)
+        }
         None => {
             // Backwards compat: bare `focal /path [--http] [--port N]` maps to serve
-            if cli.paths.is_empty() {
+            let paths = if cli.paths.is_empty() { focal_core::config::workspaces_from_env() } else { cli.paths };
+            if paths.is_empty() {
                 // No subcommand and no paths — print help
                 use clap::CommandFactory;
                 Cli::command().print_help()?;
                 std::process::exit(0);
             }
-            run_serve(cli.paths, cli.http, cli.port).await
+            run_serve(paths, cli.http, &cli.host, cli.port, cli.encrypt).await
         }
     }
 }
@@ -1,18 +1,38 @@
-use std::collections::{HashSet, VecDeque};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use rmcp::handler::server::router::tool::ToolRouter;
 use rmcp::handler::server::wrapper::Parameters;
-use rmcp::model::{ServerCapabilities, ServerInfo};
-use rmcp::{ServerHandler, tool, tool_handler, tool_router};
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::model::{
+    CallToolRequestParams, CallToolResult, ListToolsResult, PaginatedRequestParams, ServerCapabilities, ServerInfo,
+    Tool,
+};
+use rmcp::service::RequestContext;
+use rmcp::{ErrorData, RoleServer, ServerHandler, tool, tool_router};
 use rmcp::schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-
-use crate::context::ContextEngine;
-use crate::db::{Database, Symbol, SymbolResult};
-use crate::graph::GraphEngine;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::audit::{bearer_token, token_fingerprint, AuditEntry};
+use crate::context::{ContextEngine, Expansion};
+use crate::db::{
+    parse_signature_types, Database, Repository, SearchFilter, Symbol, SymbolResult, SYMBOL_RESULT_FIELDS,
+};
+use crate::facade::Focal;
+use crate::format::{render, render_skeleton, FieldMask, OutputFormat};
+use crate::freshness::{refresh, stale_files, DEFAULT_REFRESH_BUDGET};
+use crate::graph::{GraphEngine, TraversalLimits};
+use crate::owners::CodeOwners;
+use crate::proximity::Focus;
+use crate::query_cache::QueryCache;
+use crate::reindex_scheduler::HotFiles;
+use crate::report::{default_report_path, read_report};
+use crate::session_budget::SessionBudget;
+use crate::tree_query::TreeQueryLimits;
+use crate::working_set::WorkingSet;
 
 // ---------------------------------------------------------------------------
 // Parameter structs — each tool gets its own params type with doc comments
@@ -27,6 +47,18 @@ pub struct QuerySymbolParams {
     pub kind: Option<String>,
     /// Optional repository name filter
     pub repo: Option<String>,
+    /// Project name from `[projects]` config: search every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Optional visibility filter: "public", "internal", "protected" or "private"
+    pub visibility: Option<String>,
+    /// Optional attribute/decorator substring filter (e.g. "derive(Serialize", "@app.route")
+    pub attribute: Option<String>,
+    /// Only return these result fields (`id` and `name` are always kept), e.g. ["signature", "file_path", "start_line"]
+    pub include: Option<Vec<String>>,
+    /// Drop these result fields, e.g. ["body", "memories"]
+    pub exclude: Option<Vec<String>>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -35,6 +67,8 @@ pub struct GetDependenciesParams {
     pub symbol_name: String,
     /// Max traversal depth (1-3, default 1)
     pub depth: Option<u32>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -43,14 +77,85 @@ pub struct GetDependentsParams {
     pub symbol_name: String,
     /// Max traversal depth (1-3, default 1)
     pub depth: Option<u32>,
+    /// Only direct callers that pass exactly this many arguments at some
+    /// call site (e.g. 3 to find `foo(a, b, c)`)
+    pub arg_count: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
 pub struct GetFileSymbolsParams {
-    /// File path (relative within the repo or absolute)
+    /// File path: repo-relative, absolute, or a unique suffix such as "handler.rs"
+    pub file_path: String,
+    /// Optional repository name filter
+    pub repo: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SymbolAtLocationParams {
+    /// File path: repo-relative, absolute, or a unique suffix such as "handler.rs"
     pub file_path: String,
+    /// 1-based line number, e.g. from a stack trace or diff hunk
+    pub line: i64,
+    /// Optional repository name filter
+    pub repo: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ResolveStacktraceParams {
+    /// Raw stack trace: Rust panic/backtrace, Go panic, Python traceback, or JS error stack
+    pub trace: String,
+    /// Token budget for symbol bodies (default 8000); frames beyond it get signatures only
+    pub max_tokens: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FindLogSourceParams {
+    /// A log line or error message as printed at runtime
+    pub message: String,
     /// Optional repository name filter
     pub repo: Option<String>,
+    /// Max candidates (default 10)
+    pub max_results: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ExplainSymbolParams {
+    /// Name of the symbol to explain
+    pub symbol_name: String,
+    /// Optional repository name filter
+    pub repo: Option<String>,
+    /// Token budget for the whole report (default 4000)
+    pub max_tokens: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct PlanRenameParams {
+    /// Current name of the symbol to rename; a method by itself (`Start`) or with its type (`Server.Start`)
+    pub symbol_name: String,
+    /// The name it should get; for a method, without the type or with the same one
+    pub new_name: String,
+    /// File defining the symbol, when the name is ambiguous: repo-relative, absolute, or a unique suffix
+    pub file_path: Option<String>,
+    /// A line inside the definition, when the file defines the name more than once
+    pub line: Option<i64>,
+    /// Optional repository name filter
+    pub repo: Option<String>,
+    /// Reference locations to return (default 500); `files` always lists every file
+    pub max_references: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -61,6 +166,30 @@ pub struct SaveMemoryParams {
     pub category: String,
     /// Optional symbol names to link this memory to
     pub symbol_names: Option<Vec<String>>,
+    /// Optional structured fields for templated categories. "decision":
+    /// {decision, context?, consequences?}; "invariant": {scope, rule};
+    /// "bug_fix": {symptom, fix, root_cause?}. When set, `content` may be
+    /// empty and is derived from the fields.
+    pub fields: Option<serde_json::Value>,
+    /// Pin the memory so it is always preferred when get_context attaches memories
+    pub pinned: Option<bool>,
+    /// Optional file or directory paths (repo-relative or absolute) this memory is
+    /// about. Directory links apply to every file beneath them.
+    pub file_paths: Option<Vec<String>>,
+    /// Repository relative `file_paths` and mentioned symbols belong to
+    /// (default: paths in any repository, symbols in the workspace's)
+    pub repo: Option<String>,
+    /// Optional relationships from this memory to existing ones
+    pub links: Option<Vec<MemoryLinkParams>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MemoryLinkParams {
+    /// Relationship kind: "supersedes", "refines", or "relates_to".
+    /// Superseded memories are hidden from listings and context capsules.
+    pub kind: String,
+    /// ID of the existing memory this one relates to
+    pub memory_id: i64,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -71,6 +200,10 @@ pub struct ListMemoriesParams {
     pub include_stale: Option<bool>,
     /// Filter by linked symbol name
     pub symbol_name: Option<String>,
+    /// Include memories superseded by a newer memory (default false)
+    pub include_superseded: Option<bool>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -89,6 +222,19 @@ pub struct UpdateMemoryParams {
     pub category: Option<String>,
     /// New symbol names to link (replaces existing links)
     pub symbol_names: Option<Vec<String>>,
+    /// New structured fields (replaces existing fields; validated against the
+    /// template for the memory's category)
+    pub fields: Option<serde_json::Value>,
+    /// Pin or unpin the memory
+    pub pinned: Option<bool>,
+    /// Mark the memory stale (reason `manual`) or fresh again
+    pub stale: Option<bool>,
+    /// New file or directory paths to link (replaces existing path links)
+    pub file_paths: Option<Vec<String>>,
+    /// Repository relative `file_paths` belong to (default: any repository)
+    pub repo: Option<String>,
+    /// Relationships to add from this memory to existing ones
+    pub links: Option<Vec<MemoryLinkParams>>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -99,14 +245,38 @@ pub struct SearchCodeParams {
     pub kind: Option<String>,
     /// Optional repository name filter
     pub repo: Option<String>,
+    /// Project name from `[projects]` config: search every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Optional visibility filter: "public", "internal", "protected" or "private"
+    pub visibility: Option<String>,
+    /// Optional attribute/decorator substring filter (e.g. "derive(Serialize", "@app.route")
+    pub attribute: Option<String>,
+    /// Optional monorepo package filter (a name from list_packages)
+    pub package: Option<String>,
+    /// Focus symbol name or file path: results within a few graph hops of it or in the same directory rank higher
+    pub near: Option<String>,
+    /// Also match symbols in generated files (protobuf stubs, `Code generated` headers, compiled bundles); default false
+    pub include_generated: Option<bool>,
     /// Max results to return (default 20)
     pub max_results: Option<i64>,
+    /// Only return these result fields (`id` and `name` are always kept), e.g. ["signature", "file_path", "start_line"]
+    pub include: Option<Vec<String>>,
+    /// Drop these result fields, e.g. ["body", "memories"]
+    pub exclude: Option<Vec<String>>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
 pub struct GetRepoOverviewParams {
     /// Optional repository name (omit for all repos)
     pub repo: Option<String>,
+    /// Project name from `[projects]` config: totals across its member repositories and the edges between them
+    pub project: Option<String>,
+    /// Include each package's README first paragraph or top-level doc comment (default false)
+    pub docs: Option<bool>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -117,16 +287,33 @@ pub struct GetContextParams {
     pub max_tokens: Option<usize>,
     /// Optional repository name filter
     pub repo: Option<String>,
+    /// Fraction of max_tokens reserved for memories, 0.0-1.0 (default 0.1, or the repo's .focal.toml).
+    /// Memories that don't fit are listed in `memory_refs` as id + preview.
+    pub memory_budget: Option<f64>,
+    /// Only pick pivot symbols from this monorepo package (a name from list_packages)
+    pub package: Option<String>,
+    /// Focus symbol name or file path: pivots within a few graph hops of it or in the same directory rank higher
+    pub near: Option<String>,
+    /// Also return the skeleton (signatures and line ranges) of each pivot's file, budget permitting (default false)
+    pub include_file_skeletons: Option<bool>,
+    /// How pivots are expanded: "graph" (default; callers/callees by intent), "siblings" (same class/parent or file, good for adding a similar implementation), or "both"
+    pub expansion: Option<String>,
+    /// Also pick pivots from generated files (protobuf stubs, `Code generated` headers, compiled bundles); default false
+    pub include_generated: Option<bool>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
 pub struct GetSkeletonParams {
-    /// File path (relative to repo root)
+    /// File path: repo-relative, absolute, or a unique suffix such as "handler.rs"
     pub file_path: String,
     /// Optional repository name
     pub repo: Option<String>,
     /// Detail level: minimal, standard, verbose (default: standard)
     pub detail: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -135,8 +322,66 @@ pub struct GetImpactGraphParams {
     pub symbol_name: String,
     /// Max traversal depth (1-5, default 2). Higher values find more transitive dependents but take longer.
     pub depth: Option<usize>,
+    /// Stop after this many affected symbols (default 10000)
+    pub max_nodes: Option<usize>,
+    /// Return `{nodes, truncated?}` instead of the bare array, to tell whether a traversal limit cut the result short (default false)
+    pub report: Option<bool>,
     /// Optional repository name filter
     pub repo: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetImpactForDiffParams {
+    /// Unified diff text (e.g. `git diff` output), paths relative to the repository root. Omit to diff the repository's working tree with git.
+    pub diff: Option<String>,
+    /// Without `diff`: only staged changes (default false — every uncommitted change against HEAD)
+    pub staged: Option<bool>,
+    /// Max traversal depth for dependents (1-5, default 2)
+    pub depth: Option<usize>,
+    /// Repository name (default: the first workspace root)
+    pub repo: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetOnboardingContextParams {
+    /// Repository name (default: the first workspace root)
+    pub repo: Option<String>,
+    /// Token budget for the capsule (default 3000)
+    pub max_tokens: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GraphDiffParams {
+    /// Repository name (default: the first workspace root)
+    pub repo: Option<String>,
+    /// Compare the last index run with the index as it is now, including
+    /// incremental re-indexes since (default false: the last two recorded
+    /// graphs)
+    pub live: Option<bool>,
+    /// Max entries per added/removed list (default 100)
+    pub limit: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetCouplingReportParams {
+    /// Repository name (default: the first workspace root)
+    pub repo: Option<String>,
+    /// Order: "module" (default), "instability", "afferent", "efferent" (highest first) or "cohesion" (lowest first)
+    pub sort: Option<String>,
+    /// Max modules returned (default 50)
+    pub limit: Option<usize>,
+    /// Recompute from the current edges instead of using the metrics stored by the last index run
+    pub refresh: Option<bool>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -145,6 +390,8 @@ pub struct SearchMemoryParams {
     pub query: String,
     /// Max results (default 10)
     pub max_results: Option<i64>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -155,16 +402,277 @@ pub struct BatchQueryParams {
     pub max_tokens: Option<usize>,
     /// Whether to include full bodies (default true)
     pub include_body: Option<bool>,
+    /// Only return these result fields (`id` and `name` are always kept), e.g. ["signature", "file_path", "start_line"]
+    pub include: Option<Vec<String>>,
+    /// Drop these result fields, e.g. ["body", "memories"]
+    pub exclude: Option<Vec<String>>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per symbol)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetLastIndexReportParams {
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetHealthParams {
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DiagnoseIndexParams {
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ResetWorkingSetParams {}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetSessionBudgetParams {
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MarkIrrelevantParams {
+    /// `symbol_id`s of capsule items that were off-topic
+    pub symbol_ids: Option<Vec<i64>>,
+    /// Symbol names that were off-topic (first match per name)
+    pub symbol_names: Option<Vec<String>>,
+    /// Repository `symbol_names` are looked up in (default: the workspace root)
+    pub repo: Option<String>,
+    /// Optional note on why, kept with the mark
+    pub reason: Option<String>,
+    /// Remove the marks instead; with no symbols given, clears every mark of this session
+    pub unmark: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetOwnersParams {
+    /// Symbol whose file ownership to look up (one of symbol_name / file_path is required)
+    pub symbol_name: Option<String>,
+    /// File path: repo-relative, absolute, or a unique suffix
+    pub file_path: Option<String>,
+    /// Optional repository name filter
+    pub repo: Option<String>,
+    /// Also list top git contributors to the file via `git shortlog` (default false)
+    pub include_git: Option<bool>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetPublicApiParams {
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Only files under this repo-relative directory or file, e.g. "src/payments"
+    pub path_prefix: Option<String>,
+    /// Include doc comments / docstrings (default true)
+    pub include_docs: Option<bool>,
+    /// Maximum symbols per repository (default 200)
+    pub max_symbols: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CompareSymbolsParams {
+    /// First symbol (the "old" side of the diff)
+    pub symbol_a: String,
+    /// Second symbol (the "new" side); defaults to symbol_a, for comparing
+    /// one symbol across repos or revisions
+    pub symbol_b: Option<String>,
+    /// Repository for symbol_a
+    pub repo_a: Option<String>,
+    /// Repository for symbol_b (defaults to repo_a)
+    pub repo_b: Option<String>,
+    /// Git revision (branch, tag, or SHA) to read symbol_a from instead of the index
+    pub rev_a: Option<String>,
+    /// Git revision to read symbol_b from instead of the index
+    pub rev_b: Option<String>,
+    /// Unchanged lines of context around each change (default 3)
+    pub context_lines: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ListRoutesParams {
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// HTTP method, e.g. "GET" (routes registered for any method always match)
+    pub method: Option<String>,
+    /// Only routes whose path starts with this prefix, e.g. "/api/users"
+    pub path_prefix: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ListEnvVarsParams {
+    /// Only variables whose name contains this text (case-insensitive), e.g. "DATABASE_URL"
+    pub name: Option<String>,
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FindQueriesTouchingTableParams {
+    /// Table name, case-insensitive; "users" also matches schema-qualified "public.users"
+    pub table: String,
+    /// Only statements of this kind: "SELECT", "INSERT", "UPDATE", "DELETE", ...
+    pub operation: Option<String>,
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FindUnhandledErrorsParams {
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Only files under this repo-relative directory or file, e.g. "src/payments"
+    pub path_prefix: Option<String>,
+    /// Only one pattern: "unwrap", "expect", "ignored_error", "bare_except",
+    /// "swallowed_exception", or "empty_catch"
+    pub kind: Option<String>,
+    /// Maximum sites to return (default 200)
+    pub max_results: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RunTreeQueryParams {
+    /// Tree-sitter query in S-expression syntax, e.g.
+    /// `(call_expression function: (field_expression field: (field_identifier) @m (#eq? @m "unwrap")))`.
+    /// Captures named with a leading `_` are matched but not returned.
+    pub query: String,
+    /// Language the query is written for: "rs", "go", "ts", "tsx", "js", "py"
+    pub language: String,
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Only files under this repo-relative directory or file, e.g. "src/payments"
+    pub path_prefix: Option<String>,
+    /// Maximum matches to return (default 100)
+    pub max_results: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetSizeReportParams {
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// How many of the largest files and symbols to list (default 10)
+    pub top: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ListPackagesParams {
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ListDependenciesParams {
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Dependency name substring, e.g. "axum" or "@tanstack"
+    pub name: Option<String>,
+    /// Only this kind: "prod", "dev", "build", "peer", "optional" or "indirect"
+    pub kind: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GetIndexErrorsParams {
+    /// Repository name (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Project name from `[projects]` config: every member repository (instead of `repo`)
+    pub project: Option<String>,
+    /// Only this kind: "read", "parse", "syntax", "panic" or "edge_resolution"
+    pub kind: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ImportCoverageParams {
+    /// Path to an lcov tracefile (`lcov.info`) or Cobertura XML report
+    pub path: String,
+    /// Repository the report covers (defaults to the first workspace)
+    pub repo: Option<String>,
+    /// "lcov" or "cobertura" (detected from the content by default)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ReindexPathsParams {
+    /// Repo-relative, gitignore-style patterns: `gen/` (any `gen` directory),
+    /// `/api/*.go` (anchored at the root), `**/*_pb.rs`
+    pub globs: Vec<String>,
+    /// Repository to reindex (defaults to the first workspace)
+    pub repo: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TestsForSymbolParams {
+    /// Name of the production symbol (function, method, type)
+    pub symbol_name: String,
+    /// Repository the tests live in (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
-pub struct GetHealthParams {}
+pub struct SymbolsForTestParams {
+    /// Name of the test function, e.g. "test_parse_header" or "TestParseHeader"
+    pub test_name: String,
+    /// Repository the test lives in (defaults to every indexed repository)
+    pub repo: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
 
 #[derive(Deserialize, JsonSchema)]
 pub struct RecoverSessionParams {
     /// Session ID to recover (e.g. "session-1708617600000").
     /// If omitted, recovers the current session.
     pub session_id: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -175,14 +683,8 @@ pub struct GetSymbolHistoryParams {
     pub max_entries: Option<usize>,
     /// Optional repository name filter
     pub repo: Option<String>,
-}
-
-#[derive(Serialize)]
-struct CommitEntry {
-    hash: String,
-    author: String,
-    date: String,
-    message: String,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -205,13 +707,29 @@ pub struct SearchLogicFlowParams {
     pub repo: Option<String>,
     /// Maximum number of paths to return (default 3)
     pub max_paths: Option<usize>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FindFilePathParams {
+    /// File the chain starts from: repo-relative, absolute, or a unique suffix such as "cli/main.rs"
+    pub from_file: String,
+    /// File the chain should reach, in the same forms
+    pub to_file: String,
+    /// Give up after visiting this many files (default 10000)
+    pub max_nodes: Option<usize>,
+    /// Optional repository name filter for both files
+    pub repo: Option<String>,
+    /// Response format: "json" (default), "compact-json", or "text" (terse, one line per record)
+    pub format: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
 // Dependency graph traversal result
 // ---------------------------------------------------------------------------
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 struct DepNode {
     name: String,
     kind: String,
@@ -219,6 +737,23 @@ struct DepNode {
     file_path: String,
     edge_kind: String,
     depth: u32,
+    /// Argument kinds at each distinct call site of a `calls` edge.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    call_sites: Vec<Vec<String>>,
+}
+
+/// One side of a `compare_symbols` diff.
+#[derive(Serialize)]
+struct ComparedSymbol {
+    symbol: String,
+    repo: String,
+    file_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rev: Option<String>,
+    start_line: i64,
+    end_line: i64,
+    #[serde(skip)]
+    body: String,
 }
 
 // ---------------------------------------------------------------------------
@@ -240,7 +775,7 @@ fn build_recovery_summary(data: &crate::db::SessionRecoveryData) -> String {
             data.manual_memories.len()
         ));
         for m in &data.manual_memories {
-            parts.push(format!("  - [{}] {}", m.category, m.content));
+            parts.push(format!("  - [{}] {}", m.category, crate::memory::render_memory(m)));
         }
     }
 
@@ -308,26 +843,44 @@ fn build_recovery_summary(data: &crate::db::SessionRecoveryData) -> String {
 // FocalServer
 // ---------------------------------------------------------------------------
 
+/// Permits held for the duration of one tool call: (session, global).
+type ToolSlots<'a> = (Option<SemaphorePermit<'a>>, Option<SemaphorePermit<'a>>);
+
 #[derive(Clone)]
 pub struct FocalServer {
-    db: Arc<Mutex<Database>>,
-    #[allow(dead_code)]
-    workspace_roots: Vec<PathBuf>,
+    /// Database, workspace roots and index settings, shared by every session.
+    focal: Focal,
     indexing_complete: Arc<AtomicBool>,
     session_id: String,
     /// Symbol IDs whose full bodies have already been sent in this session.
     /// On subsequent requests, these symbols get skeleton + placeholder note
     /// instead of the full body, saving ~95% tokens on repeated lookups.
     sent_symbols: Arc<Mutex<HashSet<i64>>>,
+    /// Symbols and files this session has touched; get_context biases its
+    /// pivots toward them.
+    working_set: Arc<Mutex<WorkingSet>>,
+    /// Tokens served per tool this session, and the optional soft cap that
+    /// shrinks get_context budgets.
+    session_budget: Arc<Mutex<SessionBudget>>,
+    /// Results of repeated read-only queries, valid until the index changes.
+    query_cache: Arc<Mutex<QueryCache>>,
+    /// Where indexing runs write their summary (`get_last_index_report`).
+    report_path: PathBuf,
+    /// Files this session read, shared with the reindex scheduler so they
+    /// are reindexed first after a change.
+    hot_files: Option<HotFiles>,
+    /// Record every tool call in `audit_log` (shared HTTP deployments).
+    audit: bool,
+    /// Tool calls allowed to run at once across all sessions, shared by
+    /// every server instance of an HTTP host.
+    tool_slots: Option<Arc<Semaphore>>,
+    /// Tool calls allowed to run at once in this session.
+    session_slots: Option<Arc<Semaphore>>,
     tool_router: ToolRouter<Self>,
 }
 
 impl FocalServer {
-    pub fn new(
-        db: Arc<Mutex<Database>>,
-        workspace_roots: Vec<PathBuf>,
-        indexing_complete: Arc<AtomicBool>,
-    ) -> Self {
+    pub fn new(focal: Focal, indexing_complete: Arc<AtomicBool>) -> Self {
         let session_id = format!(
             "session-{}",
             std::time::SystemTime::now()
@@ -336,15 +889,149 @@ impl FocalServer {
                 .as_millis()
         );
         Self {
-            db,
-            workspace_roots,
+            focal,
             indexing_complete,
             session_id,
             sent_symbols: Arc::new(Mutex::new(HashSet::new())),
+            working_set: Arc::new(Mutex::new(WorkingSet::new())),
+            session_budget: Arc::new(Mutex::new(SessionBudget::new(None))),
+            query_cache: Arc::new(Mutex::new(QueryCache::default())),
+            report_path: default_report_path(),
+            hot_files: None,
+            audit: false,
+            tool_slots: None,
+            session_slots: None,
             tool_router: Self::tool_router(),
         }
     }
 
+    /// Read the indexing report from `path` instead of `report::default_report_path`.
+    pub fn with_report_path(mut self, path: PathBuf) -> Self {
+        self.report_path = path;
+        self
+    }
+
+    /// Shrink get_context budgets once the session has served `cap` tokens
+    /// in total (`[session] soft_cap_tokens`).
+    pub fn with_session_soft_cap(mut self, cap: Option<usize>) -> Self {
+        self.session_budget = Arc::new(Mutex::new(SessionBudget::new(cap)));
+        self
+    }
+
+    /// Cap this session's cached query results at `bytes`
+    /// (`[memory] query_cache_mb`; 0 disables the cache).
+    pub fn with_query_cache_budget(mut self, bytes: usize) -> Self {
+        self.query_cache = Arc::new(Mutex::new(QueryCache::default().with_budget(bytes)));
+        self
+    }
+
+    /// Report files this session reads to the reindex scheduler.
+    pub fn with_hot_files(mut self, hot_files: HotFiles) -> Self {
+        self.hot_files = Some(hot_files);
+        self
+    }
+
+    /// Record each tool call — session, bearer-token fingerprint, tool,
+    /// arguments, outcome — in the append-only audit log.
+    pub fn with_audit(mut self, enabled: bool) -> Self {
+        self.audit = enabled;
+        self
+    }
+
+    /// Queue tool calls beyond `per_session` running in this session, or
+    /// beyond the permits of the shared `global` semaphore. 0 disables the
+    /// per-session cap.
+    pub fn with_concurrency_limits(mut self, global: Option<Arc<Semaphore>>, per_session: usize) -> Self {
+        self.tool_slots = global;
+        self.session_slots = (per_session > 0).then(|| Arc::new(Semaphore::new(per_session)));
+        self
+    }
+
+    /// Wait for a free slot in this session, then in the server. Waiters are
+    /// served in arrival order, so a session flooding the queue can't jump
+    /// ahead of the others.
+    async fn acquire_tool_slots(&self) -> Result<ToolSlots<'_>, ErrorData> {
+        let closed = |_| ErrorData::internal_error("server is shutting down", None);
+        let session = match &self.session_slots {
+            Some(slots) => Some(slots.acquire().await.map_err(closed)?),
+            None => None,
+        };
+        let global = match &self.tool_slots {
+            Some(slots) => Some(slots.acquire().await.map_err(closed)?),
+            None => None,
+        };
+        Ok((session, global))
+    }
+
+    /// Append a tool call to the audit log. Failures are logged, not
+    /// surfaced: the call has already been answered.
+    fn record_audit(&self, tool: &str, params: String, token: Option<String>, outcome: &str, tokens: usize) {
+        let entry = AuditEntry {
+            session_id: self.session_id.clone(),
+            token,
+            tool: tool.to_string(),
+            params,
+            outcome: outcome.to_string(),
+            response_tokens: tokens,
+        };
+        let recorded = self.focal.lock().map(|db| db.record_audit(&entry));
+        match recorded {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::warn!(error = %e, tool, "failed to record audit entry"),
+            Err(e) => tracing::warn!(error = %e, tool, "audit entry skipped: failed to lock DB"),
+        }
+    }
+
+    fn output_format(format: Option<&str>) -> Result<OutputFormat, String> {
+        OutputFormat::parse(format).map_err(|e| e.to_string())
+    }
+
+    /// Run `compute` through the query cache: its result is reused for the
+    /// same `tool` and `key` until the index changes.
+    fn cached<T>(
+        &self,
+        db: &Database,
+        tool: &'static str,
+        key: impl std::fmt::Debug,
+        compute: impl FnOnce(&Database) -> Result<T, String>,
+    ) -> Result<T, String>
+    where
+        T: Clone + Serialize + Send + Sync + 'static,
+    {
+        let version = db.data_version().map_err(|e| format!("db error: {e}"))?;
+        let key = format!("{key:?}");
+        let hit = self.query_cache.lock().map_err(|e| format!("lock error: {e}"))?.get(tool, &key, version);
+        if let Some(hit) = hit {
+            return Ok(hit);
+        }
+        let value = compute(db)?;
+        self.query_cache
+            .lock()
+            .map_err(|e| format!("lock error: {e}"))?
+            .insert(tool, key, version, value.clone());
+        Ok(value)
+    }
+
+    /// Reindex the files among `file_ids` that changed on disk since they
+    /// were indexed, within `DEFAULT_REFRESH_BUDGET`. True when the index
+    /// changed and results computed before the call are out of date.
+    fn refresh_stale(&self, db: &Database, file_ids: &[i64]) -> bool {
+        let stale = match stale_files(db, file_ids) {
+            Ok(stale) if !stale.is_empty() => stale,
+            _ => return false,
+        };
+        let Ok(before) = db.data_version() else { return false };
+        let indexer = self.focal.indexer(db);
+        for error in refresh(&indexer, db, &stale, DEFAULT_REFRESH_BUDGET).errors {
+            tracing::warn!(%error, "read-time refresh failed");
+        }
+        db.data_version().is_ok_and(|after| after != before)
+    }
+
+    fn field_mask(include: Option<&[String]>, exclude: Option<&[String]>) -> Result<FieldMask, String> {
+        FieldMask::new(include, exclude, SYMBOL_RESULT_FIELDS).map_err(|e| e.to_string())
+    }
+
     /// Resolve a list of symbol names to their IDs. Unknown names are silently skipped.
     fn resolve_symbol_ids(db: &Database, names: &[String]) -> Vec<i64> {
         let mut ids = Vec::new();
@@ -356,18 +1043,105 @@ impl FocalServer {
         ids
     }
 
+    /// Resolve a client-supplied file path (absolute, relative, `./`-prefixed,
+    /// or Windows-separated) to an indexed file, or explain why it can't be.
+    fn resolve_file(
+        db: &Database,
+        file_path: &str,
+        repo: Option<&str>,
+    ) -> Result<crate::db::ResolvedFile, String> {
+        db.resolve_file_path(file_path, repo)
+            .map_err(|e| format!("query error: {e}"))?
+            .ok_or_else(|| format!("file '{file_path}' not found in the index"))
+    }
+
+    /// Locate `name` in the index (optionally within `repo`); with `rev`, read
+    /// its body from that git revision of the same file instead.
+    fn compared_symbol(
+        db: &Database,
+        name: &str,
+        repo: Option<&str>,
+        rev: Option<&str>,
+    ) -> Result<ComparedSymbol, String> {
+        let repo_id = match repo {
+            Some(repo_name) => db
+                .get_repo_id_by_name(repo_name)
+                .map_err(|e| format!("repo lookup error: {e}"))?,
+            None => None,
+        };
+        let sym = match repo_id {
+            Some(rid) => db.find_symbol_by_name(rid, name),
+            None => db.find_symbol_by_name_any(name),
+        }
+        .map_err(|e| format!("db error: {e}"))?
+        .ok_or_else(|| db.symbol_not_found_message(name, repo_id))?;
+        let path = db
+            .get_file_path_for_symbol(sym.id)
+            .map_err(|e| format!("file path error: {e}"))?;
+        let file = Self::resolve_file(db, &path, None)?;
+
+        let mut compared = ComparedSymbol {
+            symbol: sym.name,
+            repo: file.repo,
+            file_path: file.file_path,
+            rev: None,
+            start_line: sym.start_line,
+            end_line: sym.end_line,
+            body: sym.body,
+        };
+        if let Some(rev) = rev {
+            let registry = crate::grammar::GrammarRegistry::new();
+            let at_rev = crate::git::symbol_at_revision(
+                std::path::Path::new(&file.repo_root),
+                rev,
+                &compared.file_path,
+                name,
+                &registry,
+            )
+            .map_err(|e| format!("git error: {e}"))?
+            .ok_or_else(|| format!("symbol '{name}' not found in {} at '{rev}'", compared.file_path))?;
+            compared.rev = Some(rev.to_string());
+            compared.start_line = at_rev.start_line as i64;
+            compared.end_line = at_rev.end_line as i64;
+            compared.body = at_rev.body;
+        }
+        Ok(compared)
+    }
+
+    /// Check memory relationships before writing anything: kinds must be
+    /// known, targets must exist, and a memory cannot link to itself.
+    fn validate_memory_links(
+        db: &Database,
+        source_id: Option<i64>,
+        links: &[MemoryLinkParams],
+    ) -> Result<(), String> {
+        for link in links {
+            crate::memory::validate_link_kind(&link.kind)?;
+            if Some(link.memory_id) == source_id {
+                return Err(format!("memory {} cannot link to itself", link.memory_id));
+            }
+            db.get_memory_by_id(link.memory_id)
+                .map_err(|e| format!("query error: {e}"))?
+                .ok_or_else(|| format!("linked memory {} not found", link.memory_id))?;
+        }
+        Ok(())
+    }
+
     /// Walk the dependency graph breadth-first up to `max_depth` levels.
     /// `direction` selects outgoing (dependencies) or incoming (dependents).
+    /// With `arg_count`, the first level keeps only `calls` edges with a call
+    /// site passing that many arguments.
     fn traverse_graph(
         db: &Database,
         start_name: &str,
         max_depth: u32,
         direction: GraphDirection,
+        arg_count: Option<usize>,
     ) -> Result<Vec<DepNode>, String> {
         let sym = db
             .find_symbol_by_name_any(start_name)
             .map_err(|e| format!("db error: {e}"))?
-            .ok_or_else(|| format!("symbol '{start_name}' not found"))?;
+            .ok_or_else(|| db.symbol_not_found_message(start_name, None))?;
 
         let mut visited = HashSet::new();
         visited.insert(sym.id);
@@ -387,6 +1161,11 @@ impl FocalServer {
             .map_err(|e| format!("db error: {e}"))?;
 
             for (edge, dep_sym) in edges {
+                if let Some(count) = arg_count.filter(|_| current_depth == 0) {
+                    if !edge.call_sites.iter().any(|args| args.len() == count) {
+                        continue;
+                    }
+                }
                 if visited.insert(dep_sym.id) {
                     let file_path = db
                         .get_file_path_for_symbol(dep_sym.id)
@@ -399,6 +1178,7 @@ impl FocalServer {
                         file_path,
                         edge_kind: edge.kind.clone(),
                         depth: current_depth + 1,
+                        call_sites: edge.call_sites,
                     });
 
                     queue.push_back((dep_sym.id, current_depth + 1));
@@ -406,83 +1186,320 @@ impl FocalServer {
             }
         }
 
+        results.sort_by(|a, b| (a.depth, &a.name, &a.file_path).cmp(&(b.depth, &b.name, &b.file_path)));
         Ok(results)
     }
 
-    /// Enrich raw `Symbol` records with file paths and linked memories.
-    /// Uses a single batch query for memories instead of per-symbol lookups.
-    fn enrich_symbols(db: &Database, symbols: &[Symbol]) -> Vec<SymbolResult> {
-        let sym_ids: Vec<i64> = symbols.iter().map(|s| s.id).collect();
-        let mem_map = db.get_memories_for_symbols_batch(&sym_ids, false).unwrap_or_default();
+    /// Record symbols whose full bodies were sent: they get a placeholder on
+    /// later requests and join the working set.
+    fn mark_sent(&self, ids: impl IntoIterator<Item = i64>) {
+        let ids: Vec<i64> = ids.into_iter().collect();
+        if let Ok(mut sent) = self.sent_symbols.lock() {
+            sent.extend(&ids);
+        }
+        if self.hot_files.is_some() {
+            let file_ids = self.focal.lock().ok().and_then(|db| db.file_ids_for_symbols(&ids).ok());
+            self.touch_hot_files(&file_ids.unwrap_or_default());
+        }
+        if let Ok(mut working_set) = self.working_set.lock() {
+            working_set.touch_symbols(ids);
+        }
+    }
 
-        symbols
-            .iter()
-            .map(|sym| {
-                let file_path = db
-                    .get_file_path_for_symbol(sym.id)
-                    .unwrap_or_else(|_| "<unknown>".to_string());
-                let memories = mem_map.get(&sym.id).cloned().unwrap_or_default();
-                SymbolResult {
-                    id: sym.id,
-                    name: sym.name.clone(),
-                    kind: sym.kind.clone(),
-                    signature: sym.signature.clone(),
-                    body: sym.body.clone(),
-                    file_path,
-                    repo_name: String::new(),
-                    start_line: sym.start_line,
-                    end_line: sym.end_line,
-                    memories,
-                    dependency_hints: Vec::new(),
-                    source: sym.source.clone(),
-                    manifest_repo: sym.manifest_repo.clone(),
-                }
-            })
-            .collect()
+    /// Add a file the agent viewed to the working set.
+    fn touch_file(&self, file_id: i64) {
+        self.touch_hot_files(&[file_id]);
+        if let Ok(mut working_set) = self.working_set.lock() {
+            working_set.touch_file(file_id);
+        }
     }
 
-    /// Replace the body of manifest-imported symbols with an informational message.
-    /// Called at the presentation layer before serialization — keeps DB queries and
-    /// graph traversal unaware of manifest provenance.
-    fn annotate_manifest_bodies(results: &mut [SymbolResult]) {
-        for r in results.iter_mut() {
-            if r.source == "manifest" {
-                r.body = format!(
-                    "[Source body not available — imported from manifest: {}. Clone the repo for full source.]",
-                    r.manifest_repo.as_deref().unwrap_or("unknown")
-                );
-            }
+    fn touch_hot_files(&self, file_ids: &[i64]) {
+        let Some(hot_files) = &self.hot_files else { return };
+        if let Some(paths) = self.focal.lock().ok().and_then(|db| db.absolute_file_paths(file_ids).ok()) {
+            hot_files.touch(paths);
         }
     }
-}
 
-enum GraphDirection {
-    Dependencies,
-    Dependents,
-}
+    /// The engine `get_context` and `explain_context` run, with its
+    /// session-shrunk token budget and repository filter.
+    fn context_engine<'a>(
+        &self,
+        db: &'a Database,
+        params: &GetContextParams,
+    ) -> Result<(ContextEngine<'a>, usize, Option<i64>), String> {
+        let expansion = Expansion::parse(params.expansion.as_deref()).map_err(|e| e.to_string())?;
+        let max_tokens = {
+            let budget = self.session_budget.lock().map_err(|e| format!("lock error: {e}"))?;
+            budget.shrink(params.max_tokens.unwrap_or(12_000))
+        };
 
-// ---------------------------------------------------------------------------
-// Tool definitions — #[tool_router] generates Self::tool_router()
-// ---------------------------------------------------------------------------
+        let repo_id = if let Some(ref repo_name) = params.repo {
+            db.get_repo_id_by_name(repo_name)
+                .map_err(|e| format!("repo lookup error: {e}"))?
+        } else {
+            None
+        };
 
-#[tool_router]
-impl FocalServer {
-    #[tool(description = "Look up symbols by name, optionally filtered by kind and repository. Returns full symbol details including signature, body, file path, and linked memories.")]
-    fn query_symbol(
-        &self,
-        Parameters(params): Parameters<QuerySymbolParams>,
-    ) -> Result<String, String> {
-        let mut results = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            let name = params.name.as_str();
-            let kind = params.kind.as_deref().unwrap_or("");
-            let repo = params.repo.as_deref().unwrap_or("");
+        let mut engine = ContextEngine::new(db);
+        if let Some(fraction) = params.memory_budget {
+            engine = engine.with_memory_budget_fraction(fraction);
+        }
+        if let Some(package) = &params.package {
+            engine = engine.with_package(package);
+        }
+        if let Some(near) = &params.near {
+            engine = engine.with_focus(Self::resolve_focus(db, near, params.repo.as_deref())?);
+        }
+        engine = engine
+            .with_file_skeletons(params.include_file_skeletons.unwrap_or(false))
+            .with_expansion(expansion)
+            .with_generated(params.include_generated.unwrap_or(false));
+        let focus = self
+            .working_set
+            .lock()
+            .map_err(|e| format!("lock error: {e}"))?
+            .focus(db)
+            .map_err(|e| format!("working set error: {e}"))?;
+        if let Some(focus) = focus {
+            engine = engine.with_working_set(focus);
+        }
+        let irrelevant = db
+            .irrelevant_symbols(&self.session_id)
+            .map_err(|e| format!("db error: {e}"))?;
+        Ok((engine.with_excluded(irrelevant), max_tokens, repo_id))
+    }
 
-            let results = db
-                .query_symbols_full(name, kind, repo)
-                .map_err(|e| format!("query error: {e}"))?;
+    /// Resolve a `near` parameter, failing when it names neither an indexed
+    /// file nor a symbol.
+    fn resolve_focus(db: &Database, near: &str, repo: Option<&str>) -> Result<Focus, String> {
+        Focus::resolve(db, near, repo)
+            .map_err(|e| format!("focus lookup error: {e}"))?
+            .ok_or_else(|| format!("`near` '{near}' is neither an indexed file nor a symbol"))
+    }
 
-            if !results.is_empty() {
+    /// The repository named `name`, or the one indexed from the first
+    /// workspace root.
+    fn resolve_repo(&self, db: &Database, name: Option<&str>) -> Result<Repository, String> {
+        let repos = db.list_repositories().map_err(|e| format!("db error: {e}"))?;
+        match name {
+            Some(name) => {
+                let id = db.get_repo_id_by_name(name).map_err(|e| format!("db error: {e}"))?;
+                repos.into_iter().find(|r| Some(r.id) == id)
+            }
+            None => {
+                let root = self
+                    .focal
+                    .workspaces()
+                    .first()
+                    .map(|p| crate::paths::canonicalize(p).unwrap_or_else(|_| p.clone()))
+                    .ok_or_else(|| "no workspace root configured; pass `repo`".to_string())?;
+                repos.into_iter().find(|r| Path::new(&r.root_path) == root)
+            }
+        }
+        .ok_or_else(|| "repository not indexed".to_string())
+    }
+
+    /// Id of the repository a memory's path links are scoped to; None
+    /// leaves them matching any repository.
+    fn memory_repo_id(db: &Database, name: Option<&str>) -> Result<Option<i64>, String> {
+        let Some(name) = name else { return Ok(None) };
+        db.get_repo_id_by_name(name)
+            .map_err(|e| format!("repo lookup error: {e}"))?
+            .map(Some)
+            .ok_or_else(|| format!("unknown repository: {name}"))
+    }
+
+    /// Indexed members of a configured project.
+    fn project_members(db: &Database, project: &str) -> Result<Vec<Repository>, String> {
+        db.project_repositories(project)
+            .map_err(|e| format!("project lookup error: {e}"))?
+            .ok_or_else(|| format!("project '{project}' not configured"))
+    }
+
+    /// Refuse a tool call that passes both `repo` and `project`.
+    fn exclusive_scope(repo: Option<&str>, project: Option<&str>) -> Result<(), String> {
+        match (repo, project) {
+            (Some(_), Some(_)) => Err("pass either `repo` or `project`, not both".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a symbol named `name` is indexed, in `repo_id` if given.
+    fn symbol_exists(db: &Database, name: &str, repo_id: Option<i64>) -> bool {
+        let found = match repo_id {
+            Some(repo_id) => db.find_symbol_by_name(repo_id, name),
+            None => db.find_symbol_by_name_any(name),
+        };
+        found.ok().flatten().is_some()
+    }
+
+    /// Repository ids selected by a tool's `repo` or `project` parameter;
+    /// None when neither is given (every repository).
+    fn repo_scope(db: &Database, repo: Option<&str>, project: Option<&str>) -> Result<Option<Vec<i64>>, String> {
+        Self::exclusive_scope(repo, project)?;
+        match (repo, project) {
+            (Some(name), _) => db
+                .get_repo_id_by_name(name)
+                .map_err(|e| format!("repo lookup error: {e}"))?
+                .map(|id| Some(vec![id]))
+                .ok_or_else(|| format!("repository '{name}' not found")),
+            (None, Some(project)) => {
+                Ok(Some(Self::project_members(db, project)?.iter().map(|r| r.id).collect()))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Run a query that filters by one optional repository over `scope`:
+    /// once unfiltered, or once per repository with the rows concatenated.
+    fn per_repo<T>(
+        scope: Option<&[i64]>,
+        mut query: impl FnMut(Option<i64>) -> anyhow::Result<Vec<T>>,
+    ) -> anyhow::Result<Vec<T>> {
+        let Some(ids) = scope else {
+            return query(None);
+        };
+        let mut rows = Vec::new();
+        for &id in ids {
+            rows.extend(query(Some(id))?);
+        }
+        Ok(rows)
+    }
+
+    /// Fill `owners` from each repository's CODEOWNERS file. Parsed once per
+    /// repository per call; repositories without CODEOWNERS are skipped.
+    fn attach_owners(db: &Database, results: &mut [SymbolResult]) {
+        let mut cache: HashMap<String, Option<Arc<CodeOwners>>> = HashMap::new();
+        for r in results.iter_mut() {
+            let Ok(root) = db.get_repo_root_for_symbol(r.id) else {
+                continue;
+            };
+            let owners = cache
+                .entry(root)
+                .or_insert_with_key(|root| CodeOwners::load_cached(std::path::Path::new(root)));
+            if let Some(owners) = owners {
+                r.owners = owners.owners_for(&r.file_path);
+            }
+        }
+    }
+
+    /// Fill `coverage` from the last imported coverage report.
+    fn attach_coverage(db: &Database, results: &mut [SymbolResult]) {
+        let ids: Vec<i64> = results.iter().map(|r| r.id).collect();
+        let Ok(mut coverage) = db.get_coverage_for_symbols(&ids) else {
+            return;
+        };
+        for r in results.iter_mut() {
+            r.coverage = coverage.remove(&r.id);
+        }
+    }
+
+    /// Fill `package` from each symbol's file.
+    fn attach_packages(db: &Database, results: &mut [SymbolResult]) {
+        let ids: Vec<i64> = results.iter().map(|r| r.id).collect();
+        let Ok(mut packages) = db.get_packages_for_symbols(&ids) else {
+            return;
+        };
+        for r in results.iter_mut() {
+            r.package = packages.remove(&r.id).unwrap_or_default();
+        }
+    }
+
+    /// Flag symbols from files indexed without bodies or produced by a code
+    /// generator.
+    fn mark_file_flags(db: &Database, results: &mut [SymbolResult]) {
+        let ids: Vec<i64> = results.iter().map(|r| r.id).collect();
+        let (partial, generated) = match db.symbol_file_flags(&ids) {
+            Ok(flags) => flags,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to read file flags of results");
+                return;
+            }
+        };
+        for r in results.iter_mut() {
+            r.partial = partial.contains(&r.id);
+            r.generated = generated.contains(&r.id);
+        }
+    }
+
+    /// Replace the body of manifest-imported symbols with an informational message.
+    /// Called at the presentation layer before serialization — keeps DB queries and
+    /// graph traversal unaware of manifest provenance.
+    fn annotate_manifest_bodies(results: &mut [SymbolResult]) {
+        for r in results.iter_mut() {
+            if r.source == "manifest" {
+                r.body = format!(
+                    "[Source body not available — imported from manifest: {}. Clone the repo for full source.]",
+                    r.manifest_repo.as_deref().unwrap_or("unknown")
+                );
+            }
+        }
+    }
+}
+
+enum GraphDirection {
+    Dependencies,
+    Dependents,
+}
+
+// ---------------------------------------------------------------------------
+// Tool definitions — #[tool_router] generates Self::tool_router()
+// ---------------------------------------------------------------------------
+
+#[tool_router]
+impl FocalServer {
+    #[tool(description = "Look up symbols by name, optionally filtered by kind, repository, visibility, and attribute (e.g. all structs deriving Serialize). Returns full symbol details including signature, body, file path, and linked memories.")]
+    fn query_symbol(
+        &self,
+        Parameters(params): Parameters<QuerySymbolParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let mask = Self::field_mask(params.include.as_deref(), params.exclude.as_deref())?;
+        let mut results = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let name = params.name.as_str();
+            let kind = params.kind.as_deref().unwrap_or("");
+            let repo = params.repo.as_deref().unwrap_or("");
+            let visibility = params.visibility.as_deref().unwrap_or("");
+            let attribute = params.attribute.as_deref().unwrap_or("");
+            Self::exclusive_scope(params.repo.as_deref(), params.project.as_deref())?;
+            let repos: Vec<String> = match &params.project {
+                Some(project) => Self::project_members(&db, project)?.into_iter().map(|r| r.name).collect(),
+                None => vec![repo.to_string()],
+            };
+
+            let key = (name, kind, repo, params.project.as_deref(), visibility, attribute);
+            let compute = |db: &Database| {
+                let mut results = Vec::new();
+                for repo in &repos {
+                    results.extend(
+                        db.query_symbols_full(name, kind, repo, visibility, attribute)
+                            .map_err(|e| format!("query error: {e}"))?,
+                    );
+                }
+                if repos.len() > 1 {
+                    // Same order and limit as a single-repository query.
+                    results.sort_by(|a, b| {
+                        (&a.name, &a.file_path, a.start_line, a.id).cmp(&(&b.name, &b.file_path, b.start_line, b.id))
+                    });
+                    results.truncate(200);
+                }
+                Self::attach_owners(db, &mut results);
+                Self::attach_coverage(db, &mut results);
+                Self::mark_file_flags(db, &mut results);
+                Self::attach_packages(db, &mut results);
+                Ok(results)
+            };
+            let mut results = self.cached(&db, "query_symbol", key, compute)?;
+            // Bodies edited since the last index are re-read before answering.
+            let sym_ids: Vec<i64> = results.iter().map(|r| r.id).collect();
+            let file_ids = db.file_ids_for_symbols(&sym_ids).unwrap_or_default();
+            if self.refresh_stale(&db, &file_ids) {
+                results = self.cached(&db, "query_symbol", key, compute)?;
+            }
+
+            if !results.is_empty() {
                 let sym_ids: Vec<i64> = results.iter().map(|r| r.id).collect();
                 let _ = db.save_auto_observation(
                     &format!("Explored '{}' ({} results)", params.name, results.len()),
@@ -497,14 +1514,13 @@ impl FocalServer {
 
         Self::annotate_manifest_bodies(&mut results);
 
-        // Record symbol IDs as sent (full bodies were included)
-        if let Ok(mut sent) = self.sent_symbols.lock() {
-            for r in &results {
-                sent.insert(r.id);
-            }
+        // Record symbol IDs as sent, unless the field mask dropped their bodies
+        if mask.keeps("body") {
+            self.mark_sent(results.iter().map(|r| r.id));
         }
 
-        serde_json::to_string_pretty(&results).map_err(|e| format!("json error: {e}"))
+        let results = mask.apply(&results).map_err(|e| format!("json error: {e}"))?;
+        render(&results, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Get symbols that this symbol depends on (outgoing edges in the dependency graph). Traverses up to `depth` levels (max 3).")]
@@ -512,15 +1528,13 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<GetDependenciesParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
         let nodes = {
             let max_depth = params.depth.unwrap_or(1).min(3);
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            let nodes = Self::traverse_graph(
-                &db,
-                &params.symbol_name,
-                max_depth,
-                GraphDirection::Dependencies,
-            )?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let nodes = self.cached(&db, "get_dependencies", (&params.symbol_name, max_depth), |db| {
+                Self::traverse_graph(db, &params.symbol_name, max_depth, GraphDirection::Dependencies, None)
+            })?;
 
             if !nodes.is_empty() {
                 let _ = db.save_auto_observation(
@@ -536,23 +1550,21 @@ impl FocalServer {
 
             nodes
         };
-        serde_json::to_string_pretty(&nodes).map_err(|e| format!("json error: {e}"))
+        render(&nodes, format).map_err(|e| format!("json error: {e}"))
     }
 
-    #[tool(description = "Get symbols that depend on this symbol (incoming edges in the dependency graph). Traverses up to `depth` levels (max 3).")]
+    #[tool(description = "Get symbols that depend on this symbol (incoming edges in the dependency graph). Traverses up to `depth` levels (max 3). Call edges carry `call_sites`: the argument kinds (string, number, bool, null, array, object, function, spread, expr; Python keywords as name=kind) of each distinct call. Pass `arg_count` to keep only direct callers passing that many arguments.")]
     fn get_dependents(
         &self,
         Parameters(params): Parameters<GetDependentsParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
         let nodes = {
             let max_depth = params.depth.unwrap_or(1).min(3);
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            let nodes = Self::traverse_graph(
-                &db,
-                &params.symbol_name,
-                max_depth,
-                GraphDirection::Dependents,
-            )?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let nodes = self.cached(&db, "get_dependents", (&params.symbol_name, max_depth, params.arg_count), |db| {
+                Self::traverse_graph(db, &params.symbol_name, max_depth, GraphDirection::Dependents, params.arg_count)
+            })?;
 
             if !nodes.is_empty() {
                 let _ = db.save_auto_observation(
@@ -568,7 +1580,7 @@ impl FocalServer {
 
             nodes
         };
-        serde_json::to_string_pretty(&nodes).map_err(|e| format!("json error: {e}"))
+        render(&nodes, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "List all symbols in a file (signatures only, no bodies). Useful for understanding file structure without consuming token budget on full source.")]
@@ -576,48 +1588,340 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<GetFileSymbolsParams>,
     ) -> Result<String, String> {
-        let summaries = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            db.get_file_symbols_summary(&params.file_path, params.repo.as_deref())
+        let format = Self::output_format(params.format.as_deref())?;
+        let (file, summaries) = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            if let Ok(file) = Self::resolve_file(&db, &params.file_path, params.repo.as_deref()) {
+                self.refresh_stale(&db, &[file.file_id]);
+            }
+            self.cached(&db, "get_file_symbols", (&params.file_path, &params.repo), |db| {
+                let file = Self::resolve_file(db, &params.file_path, params.repo.as_deref())?;
+                let summaries = db
+                    .get_skeleton(file.file_id, "standard")
+                    .map_err(|e| format!("query error: {e}"))?;
+                Ok((file, summaries))
+            })?
+        };
+        self.touch_file(file.file_id);
+        render_skeleton(&file.repo, &file.file_path, &summaries, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Map a file position to code: returns the innermost indexed symbol containing `line` (with body and memories) plus its enclosing symbols, outermost first. Use with a stack-trace frame, compiler error, or diff hunk to continue with symbol-level tools. `symbol` is null when the line falls outside every symbol.")]
+    fn symbol_at_location(
+        &self,
+        Parameters(params): Parameters<SymbolAtLocationParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let (file, chain, symbol) = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let mut file = Self::resolve_file(&db, &params.file_path, params.repo.as_deref())?;
+            if self.refresh_stale(&db, &[file.file_id]) {
+                file = Self::resolve_file(&db, &params.file_path, params.repo.as_deref())?;
+            }
+            let mut chain = db
+                .symbols_at_line(file.file_id, params.line)
+                .map_err(|e| format!("query error: {e}"))?;
+            let innermost = chain.pop();
+            let mut symbol = db.symbol_results(innermost.as_slice());
+            Self::attach_owners(&db, &mut symbol);
+            Self::attach_coverage(&db, &mut symbol);
+            Self::mark_file_flags(&db, &mut symbol);
+            (file, chain, symbol.pop())
+        };
+        self.touch_file(file.file_id);
+        self.mark_sent(symbol.as_ref().map(|s| s.id));
+        let enclosing: Vec<_> = chain
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "name": s.name,
+                    "kind": s.kind,
+                    "start_line": s.start_line,
+                    "end_line": s.end_line,
+                })
+            })
+            .collect();
+        let response = serde_json::json!({
+            "repo": file.repo,
+            "file_path": file.file_path,
+            "line": params.line,
+            "symbol": symbol,
+            "enclosing": enclosing,
+        });
+        render(&response, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "One-call debug context from a stack trace. Parses Rust panics/backtraces, Go panics, Python tracebacks and JS stacks into frames (innermost first), maps each file:line to the innermost indexed symbol, and attaches bodies innermost-first within `max_tokens`. Frames outside the index (stdlib, dependencies) have a null `symbol`; symbols already sent this session or repeated by recursion get a note instead of a body.")]
+    fn resolve_stacktrace(
+        &self,
+        Parameters(params): Parameters<ResolveStacktraceParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let resolution = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let sent = self.sent_symbols.lock().map_err(|e| format!("lock error: {e}"))?;
+            crate::stacktrace::resolve_stacktrace(&db, &params.trace, params.max_tokens.unwrap_or(8_000), &sent)
                 .map_err(|e| format!("query error: {e}"))?
         };
-        serde_json::to_string_pretty(&summaries).map_err(|e| format!("json error: {e}"))
+        if resolution.frames.is_empty() {
+            return Err("no stack frames recognized in `trace`".to_string());
+        }
+        self.mark_sent(
+            resolution
+                .frames
+                .iter()
+                .filter_map(|f| f.symbol.as_ref())
+                .filter(|sym| sym.body.is_some())
+                .map(|sym| sym.symbol_id),
+        );
+        render(&resolution, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Find the code that printed a log line or error message. Strips the variable parts (numbers, IDs, paths, quoted values, `key=value` values, log levels), searches symbol bodies for the remaining literal text (FTS phrase, then substring fallback), and returns candidate symbols ranked by how much of that text they contain, with the matching line.")]
+    fn find_log_source(
+        &self,
+        Parameters(params): Parameters<FindLogSourceParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let matches = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let repo_id = if let Some(ref repo_name) = params.repo {
+                db.get_repo_id_by_name(repo_name)
+                    .map_err(|e| format!("repo lookup error: {e}"))?
+            } else {
+                None
+            };
+            crate::log_source::find_log_source(&db, &params.message, repo_id, params.max_results.unwrap_or(10))
+                .map_err(|e| format!("search error: {e}"))?
+        };
+        let response = serde_json::json!({
+            "fragments": crate::log_source::literal_fragments(&params.message),
+            "matches": matches,
+        });
+        render(&response, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "One-stop report on a symbol: signature, doc comment, body, complexity estimate (branches, cyclomatic, nesting), direct callers and callees, linked memories, linked tests, and recent git history of its file — all within `max_tokens`. The body is replaced by a note when it was already sent this session or doesn't fit; list sections are filled in that order and `omitted` counts what was cut. Use instead of chaining query_symbol, get_dependents, get_dependencies and get_symbol_history.")]
+    fn explain_symbol(
+        &self,
+        Parameters(params): Parameters<ExplainSymbolParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let (sym, file_path, repo_root) = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let repo_id = if let Some(ref repo_name) = params.repo {
+                db.get_repo_id_by_name(repo_name)
+                    .map_err(|e| format!("repo lookup error: {e}"))?
+            } else {
+                None
+            };
+            let find = |db: &Database| {
+                match repo_id {
+                    Some(rid) => db.find_symbol_by_name(rid, &params.symbol_name),
+                    None => db.find_symbol_by_name_any(&params.symbol_name),
+                }
+                .map_err(|e| format!("db error: {e}"))?
+                .ok_or_else(|| db.symbol_not_found_message(&params.symbol_name, repo_id))
+            };
+            let mut sym = find(&db)?;
+            if self.refresh_stale(&db, &[sym.file_id]) {
+                sym = find(&db)?;
+            }
+            let file_path = db
+                .get_file_path_for_symbol(sym.id)
+                .map_err(|e| format!("file path error: {e}"))?;
+            let repo_root = db
+                .get_repo_root_for_symbol(sym.id)
+                .map_err(|e| format!("repo lookup error: {e}"))?;
+            (sym, file_path, repo_root)
+        };
+
+        // History is best-effort: no git, or a file outside any repository,
+        // just leaves it empty.
+        let history = crate::git::file_log(Path::new(&repo_root), &file_path, 5).unwrap_or_default();
+
+        let explanation = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let body_sent = self
+                .sent_symbols
+                .lock()
+                .map(|sent| sent.contains(&sym.id))
+                .unwrap_or(false);
+            crate::explain::explain_symbol(&db, &sym, history, body_sent, params.max_tokens.unwrap_or(4_000))
+                .map_err(|e| format!("explain error: {e}"))?
+        };
+        if explanation.body.is_some() {
+            self.mark_sent([sym.id]);
+        }
+        render(&explanation, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Plan renaming a symbol without changing anything. Returns every reference location (file, line, column, line text; the definition first), found by scanning the definition and each symbol with a graph edge to it for the name, plus the affected files with counts. Also lists `unconfirmed` mentions in symbols without an edge (unresolved qualified calls, strings, comments) to check by hand, `collisions` with symbols already called `new_name` (same file, a file the rename edits, or elsewhere in the repo), `memories` linked to the symbol or naming it, which need updating, and `unlocated` referrers that never spell the name (import aliases). If the name is ambiguous, pass `file_path` and, if needed, `line`.")]
+    fn plan_rename(
+        &self,
+        Parameters(params): Parameters<PlanRenameParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let repo_id = match &params.repo {
+            Some(repo_name) => Some(
+                db.get_repo_id_by_name(repo_name)
+                    .map_err(|e| format!("repo lookup error: {e}"))?
+                    .ok_or_else(|| format!("repository '{repo_name}' not found"))?,
+            ),
+            None => None,
+        };
+        let find = |db: &Database| -> Result<Symbol, String> {
+            let mut candidates: Vec<Symbol> = match &params.file_path {
+                Some(path) => {
+                    let file = Self::resolve_file(db, path, params.repo.as_deref())?;
+                    db.get_symbols_by_file(file.file_id)
+                        .map_err(|e| format!("db error: {e}"))?
+                        .into_iter()
+                        .filter(|s| {
+                            s.name == params.symbol_name || crate::rename::last_segment(&s.name) == params.symbol_name
+                        })
+                        .collect()
+                }
+                None => db.symbols_named(repo_id, &params.symbol_name).map_err(|e| format!("db error: {e}"))?,
+            };
+            if let Some(line) = params.line {
+                candidates.retain(|s| s.start_line <= line && line <= s.end_line);
+            }
+            match candidates.len() {
+                0 => Err(db.symbol_not_found_message(&params.symbol_name, repo_id)),
+                1 => Ok(candidates.remove(0)),
+                n => {
+                    let listed: Vec<String> = candidates
+                        .iter()
+                        .take(10)
+                        .map(|s| {
+                            let path = db.get_file_path_for_symbol(s.id).unwrap_or_default();
+                            format!("{path}:{} ({})", s.start_line, s.kind)
+                        })
+                        .collect();
+                    Err(format!(
+                        "{n} symbols are named '{}'; pass file_path (and line) to pick one: {}",
+                        params.symbol_name,
+                        listed.join(", ")
+                    ))
+                }
+            }
+        };
+        let mut sym = find(&db)?;
+        let mut file_ids = vec![sym.file_id];
+        file_ids.extend(
+            db.get_dependents(sym.id)
+                .map_err(|e| format!("db error: {e}"))?
+                .into_iter()
+                .map(|(_, s)| s.file_id),
+        );
+        if self.refresh_stale(&db, &file_ids) {
+            sym = find(&db)?;
+        }
+        let plan = crate::rename::plan_rename(&db, &sym, &params.new_name, params.max_references.unwrap_or(500))
+            .map_err(|e| format!("rename plan error: {e}"))?;
+        render(&plan, format).map_err(|e| format!("json error: {e}"))
     }
 
-    #[tool(description = "Store a decision, insight, or architectural note as a persistent memory. Optionally link it to specific symbols so it surfaces in future context lookups.")]
+    #[tool(description = "Store a decision, insight, or architectural note as a persistent memory. Optionally link it to specific symbols, or to files/directories via `file_paths`, so it surfaces in future context lookups. Use `links` to mark it as superseding, refining, or relating to earlier memories. Categories \"decision\", \"invariant\", and \"bug_fix\" accept structured `fields` validated against a template. Symbols mentioned in the content (backticked names, qualified or not, or CamelCase identifiers) are linked automatically, within `repo` or the workspace repositories, and listed in `auto_linked`. Pinned memories are always preferred when get_context attaches memories.")]
     fn save_memory(
         &self,
         Parameters(params): Parameters<SaveMemoryParams>,
     ) -> Result<String, String> {
-        let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
         let symbol_ids = params
             .symbol_names
             .as_ref()
             .map(|names| Self::resolve_symbol_ids(&db, names))
             .unwrap_or_default();
 
+        let structured = params
+            .fields
+            .as_ref()
+            .map(|f| crate::memory::validate_fields(&params.category, f))
+            .transpose()?;
+        let content = match &structured {
+            Some(fields) if params.content.trim().is_empty() => {
+                crate::memory::render_fields(&params.category, fields)
+            }
+            _ => params.content.clone(),
+        };
+
+        if let Some(links) = &params.links {
+            Self::validate_memory_links(&db, None, links)?;
+        }
+        let repo_id = Self::memory_repo_id(&db, params.repo.as_deref())?;
+
+        // Auto-link symbols mentioned in the content (backticked names,
+        // CamelCase tokens) that resolve in `repo`, else in the workspace
+        // repositories, and weren't passed explicitly. Reported back so the
+        // agent can correct bad links.
+        let scope: Vec<Option<i64>> = match repo_id {
+            Some(id) => vec![Some(id)],
+            None => {
+                let ids = self.focal.workspace_repo_ids(&db).map_err(|e| format!("db error: {e}"))?;
+                if ids.is_empty() { vec![None] } else { ids.into_iter().map(Some).collect() }
+            }
+        };
+        let mut symbol_ids = symbol_ids;
+        let mut auto_linked: Vec<String> = Vec::new();
+        for name in crate::memory::extract_symbol_mentions(&content) {
+            for &rid in &scope {
+                let Some(sym) = db
+                    .find_symbol_by_mention(rid, &name)
+                    .map_err(|e| format!("query error: {e}"))?
+                else {
+                    continue;
+                };
+                if !symbol_ids.contains(&sym.id) {
+                    symbol_ids.push(sym.id);
+                    auto_linked.push(sym.name);
+                }
+                break;
+            }
+        }
+
         let id = db
-            .save_memory(&params.content, &params.category, &symbol_ids)
+            .save_structured_memory(&content, &params.category, structured.as_ref(), &symbol_ids)
             .map_err(|e| format!("save error: {e}"))?;
+        if params.pinned == Some(true) {
+            db.set_memory_pinned(id, true)
+                .map_err(|e| format!("save error: {e}"))?;
+        }
+        if let Some(paths) = &params.file_paths {
+            db.link_memory_files(id, paths, repo_id)
+                .map_err(|e| format!("save error: {e}"))?;
+        }
+        for link in params.links.iter().flatten() {
+            db.link_memories(id, link.memory_id, &link.kind)
+                .map_err(|e| format!("save error: {e}"))?;
+        }
 
-        Ok(format!("{{\"memory_id\": {id}}}"))
+        let response = serde_json::json!({
+            "memory_id": id,
+            "auto_linked": auto_linked,
+        });
+        serde_json::to_string_pretty(&response).map_err(|e| format!("json error: {e}"))
     }
 
-    #[tool(description = "List stored memories, optionally filtered by category, staleness, or linked symbol name.")]
+    #[tool(description = "List stored memories, optionally filtered by category, staleness, or linked symbol name. Stale memories carry `stale_reason` (file_reindexed, symbol_removed, file_deleted, manual) and `stale_at`. Memories superseded by a newer one are hidden unless include_superseded is set.")]
     fn list_memories(
         &self,
         Parameters(params): Parameters<ListMemoriesParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
         let memories = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             let category = params.category.as_deref().unwrap_or("");
             let include_stale = params.include_stale.unwrap_or(false);
             let symbol_name = params.symbol_name.as_deref().unwrap_or("");
+            let include_superseded = params.include_superseded.unwrap_or(false);
 
-            db.list_memories(category, include_stale, symbol_name)
+            db.list_memories(category, include_stale, include_superseded, symbol_name)
                 .map_err(|e| format!("query error: {e}"))?
         };
-        serde_json::to_string_pretty(&memories).map_err(|e| format!("json error: {e}"))
+        render(&memories, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Delete a memory by its ID.")]
@@ -625,8 +1929,8 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<DeleteMemoryParams>,
     ) -> Result<String, String> {
-        let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-        let deleted = db
+        let deleted = self
+            .focal
             .delete_memory(params.memory_id)
             .map_err(|e| format!("delete error: {e}"))?;
 
@@ -637,12 +1941,12 @@ impl FocalServer {
         }
     }
 
-    #[tool(description = "Update an existing memory's content, category, or symbol links. Only provided fields are changed; omitted fields keep their current values.")]
+    #[tool(description = "Update an existing memory's content, category, symbol or file links, pinned or stale flag, or add relationships to other memories. Only provided fields are changed; omitted fields keep their current values.")]
     fn update_memory(
         &self,
         Parameters(params): Parameters<UpdateMemoryParams>,
     ) -> Result<String, String> {
-        let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
 
         let current = db
             .get_memory_by_id(params.memory_id)
@@ -658,35 +1962,109 @@ impl FocalServer {
                 .unwrap_or_default(),
         };
 
+        let structured = params
+            .fields
+            .as_ref()
+            .map(|f| crate::memory::validate_fields(category, f))
+            .transpose()?;
+        if let Some(links) = &params.links {
+            Self::validate_memory_links(&db, Some(params.memory_id), links)?;
+        }
+        let repo_id = Self::memory_repo_id(&db, params.repo.as_deref())?;
+
         db.update_memory(params.memory_id, content, category, &symbol_ids)
             .map_err(|e| format!("update error: {e}"))?;
+        if let Some(fields) = &structured {
+            db.set_memory_structured(params.memory_id, Some(fields))
+                .map_err(|e| format!("update error: {e}"))?;
+        }
+        if let Some(pinned) = params.pinned {
+            db.set_memory_pinned(params.memory_id, pinned)
+                .map_err(|e| format!("update error: {e}"))?;
+        }
+        if let Some(stale) = params.stale {
+            db.set_memory_stale(params.memory_id, stale)
+                .map_err(|e| format!("update error: {e}"))?;
+        }
+        if let Some(paths) = &params.file_paths {
+            db.set_memory_files(params.memory_id, paths, repo_id)
+                .map_err(|e| format!("update error: {e}"))?;
+        }
+        for link in params.links.iter().flatten() {
+            db.link_memories(params.memory_id, link.memory_id, &link.kind)
+                .map_err(|e| format!("update error: {e}"))?;
+        }
 
         Ok(format!("{{\"updated\": true, \"memory_id\": {}}}", params.memory_id))
     }
 
-    #[tool(description = "Full-text search across all indexed symbol names, signatures, and bodies using SQLite FTS5. Returns matching symbols ranked by relevance.")]
+    #[tool(description = "Full-text search across all indexed symbol names, signatures, and bodies using SQLite FTS5. Returns matching symbols ranked by relevance, optionally filtered by kind, repository, visibility, and attribute. Pass `near` (a symbol or file you are working on) to rank results close to it in the dependency graph or directory tree higher.")]
     fn search_code(
         &self,
         Parameters(params): Parameters<SearchCodeParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let mask = Self::field_mask(params.include.as_deref(), params.exclude.as_deref())?;
         let mut results = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             let kind = params.kind.as_deref().unwrap_or("");
             let max_results = params.max_results.unwrap_or(20);
 
-            // Resolve repo name to ID if provided
-            let repo_id = if let Some(ref repo_name) = params.repo {
-                db.get_repo_id_by_name(repo_name)
-                    .map_err(|e| format!("repo lookup error: {e}"))?
-            } else {
-                None
+            // Resolve repo name (or project members) to IDs if provided
+            let repo_ids = match &params.project {
+                Some(_) => Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?,
+                None => match &params.repo {
+                    Some(repo_name) => db
+                        .get_repo_id_by_name(repo_name)
+                        .map_err(|e| format!("repo lookup error: {e}"))?
+                        .map(|id| vec![id]),
+                    None => None,
+                },
             };
 
-            let symbols = db
-                .search_code(&params.query, kind, repo_id, max_results)
-                .map_err(|e| format!("search error: {e}"))?;
+            let key = (
+                &params.query,
+                kind,
+                &params.repo,
+                &params.project,
+                &params.visibility,
+                &params.attribute,
+                &params.package,
+                &params.near,
+                params.include_generated,
+                max_results,
+            );
+            let results = self.cached(&db, "search_code", key, |db| {
+                let focus = params
+                    .near
+                    .as_deref()
+                    .map(|near| Self::resolve_focus(db, near, params.repo.as_deref()))
+                    .transpose()?;
+                // With a focus, over-fetch so nearby lower-ranked matches can move up.
+                let fetch = if focus.is_some() { (max_results * 3).max(50) } else { max_results };
+
+                let filter = SearchFilter {
+                    kind,
+                    repo_ids: repo_ids.as_deref(),
+                    visibility: params.visibility.as_deref().unwrap_or(""),
+                    attribute: params.attribute.as_deref().unwrap_or(""),
+                    package: params.package.as_deref().unwrap_or(""),
+                    include_generated: params.include_generated.unwrap_or(false),
+                };
+                let mut symbols =
+                    db.search_code(&params.query, &filter, fetch).map_err(|e| format!("search error: {e}"))?;
+                if let Some(focus) = &focus {
+                    symbols = focus.rerank(db, symbols).map_err(|e| format!("search error: {e}"))?;
+                    symbols.truncate(usize::try_from(max_results).unwrap_or(0));
+                }
 
-            let results = Self::enrich_symbols(&db, &symbols);
+                let mut results = db.symbol_results(&symbols);
+                Self::attach_owners(db, &mut results);
+                Self::attach_coverage(db, &mut results);
+                Self::mark_file_flags(db, &mut results);
+                Self::attach_packages(db, &mut results);
+                Ok(results)
+            })?;
 
             if !results.is_empty() {
                 let sym_ids: Vec<i64> = results.iter().map(|r| r.id).collect();
@@ -703,14 +2081,13 @@ impl FocalServer {
 
         Self::annotate_manifest_bodies(&mut results);
 
-        // Record symbol IDs as sent (full bodies were included)
-        if let Ok(mut sent) = self.sent_symbols.lock() {
-            for r in &results {
-                sent.insert(r.id);
-            }
+        // Record symbol IDs as sent, unless the field mask dropped their bodies
+        if mask.keeps("body") {
+            self.mark_sent(results.iter().map(|r| r.id));
         }
 
-        serde_json::to_string_pretty(&results).map_err(|e| format!("json error: {e}"))
+        let results = mask.apply(&results).map_err(|e| format!("json error: {e}"))?;
+        render(&results, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Full-text search across stored memories and observations. Finds memories by content, useful for recalling architectural decisions, patterns, and prior insights.")]
@@ -718,47 +2095,308 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<SearchMemoryParams>,
     ) -> Result<String, String> {
-        let results = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            let max = params.max_results.unwrap_or(10);
-            db.search_memories(&params.query, max)
-                .map_err(|e| format!("search error: {e}"))?
+        let format = Self::output_format(params.format.as_deref())?;
+        let max = params.max_results.unwrap_or(10);
+        let results = self
+            .focal
+            .search_memories(&params.query, max)
+            .map_err(|e| format!("search error: {e}"))?;
+        render(&results, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Start here on an unfamiliar codebase. Returns a fixed-budget orientation capsule for one repository: overview (size, languages, branch), pinned memories, entry points (`main` functions and HTTP routes), package map, hotspots (symbols with the most dependents), and key public APIs ranked by dependents with one-line docs. Sections are filled in that order within `max_tokens`; `omitted` counts what was cut.")]
+    fn get_onboarding_context(
+        &self,
+        Parameters(params): Parameters<GetOnboardingContextParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let capsule = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let repo = self.resolve_repo(&db, params.repo.as_deref())?;
+            crate::onboarding::onboarding_capsule(&db, &repo, params.max_tokens.unwrap_or(3_000))
+                .map_err(|e| format!("onboarding error: {e}"))?
         };
-        serde_json::to_string_pretty(&results).map_err(|e| format!("json error: {e}"))
+        render(&capsule, format).map_err(|e| format!("json error: {e}"))
     }
 
-    #[tool(description = "Get an overview of indexed repositories including file counts, symbol counts, memory counts, and language breakdown.")]
+    #[tool(description = "Get an overview of indexed repositories including file, line, symbol, and memory counts, average/max symbol length, files and symbols per language, symbols and edges per kind, and index age. With `docs`, also each package's README first paragraph or top-level doc comment. A good first call in an unfamiliar repository.")]
     fn get_repo_overview(
         &self,
         Parameters(params): Parameters<GetRepoOverviewParams>,
     ) -> Result<String, String> {
-        let overview = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            let repo_name = params.repo.as_deref().unwrap_or("");
-            db.get_repo_overview(repo_name)
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        Self::exclusive_scope(params.repo.as_deref(), params.project.as_deref())?;
+        if let Some(project) = &params.project {
+            let mut overview = db
+                .get_project_overview(project)
                 .map_err(|e| format!("overview error: {e}"))?
+                .ok_or_else(|| format!("project '{project}' not configured"))?;
+            if params.docs.unwrap_or(false) {
+                db.fill_package_docs(&mut overview.repos).map_err(|e| format!("overview error: {e}"))?;
+            }
+            return render(&overview, format).map_err(|e| format!("json error: {e}"));
+        }
+        let repo_name = params.repo.as_deref().unwrap_or("");
+        let mut overview = db
+            .get_repo_overview(repo_name)
+            .map_err(|e| format!("overview error: {e}"))?;
+        if params.docs.unwrap_or(false) {
+            db.fill_package_docs(&mut overview).map_err(|e| format!("overview error: {e}"))?;
+        }
+        render(&overview, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Size statistics per repository: total lines indexed, symbol counts and average/total lines by kind, average/max symbol length, and the largest files and symbols. Use to gauge scale before planning work or to sanity-check index coverage.")]
+    fn get_size_report(
+        &self,
+        Parameters(params): Parameters<GetSizeReportParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let repos: Vec<_> = db
+            .list_repositories()
+            .map_err(|e| format!("repo lookup error: {e}"))?
+            .into_iter()
+            .filter(|r| scope.as_ref().is_none_or(|ids| ids.contains(&r.id)))
+            .collect();
+        let reports = repos
+            .iter()
+            .map(|repo| db.get_size_report(repo, params.top.unwrap_or(10)))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|e| format!("size report error: {e}"))?;
+        render(&reports, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "List the packages of a monorepo — directories with their own Cargo.toml, package.json, go.mod or pyproject.toml — with file, symbol and line counts and languages. Pass a package name to search_code or get_context to scope them to one package.")]
+    fn list_packages(
+        &self,
+        Parameters(params): Parameters<ListPackagesParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let packages = Self::per_repo(scope.as_deref(), |repo_id| db.list_package_stats(repo_id))
+            .map_err(|e| format!("query error: {e}"))?;
+        render(&packages, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "List third-party dependencies declared in Cargo.toml, package.json, go.mod and pyproject.toml: name, version requirement, kind (prod/dev/build/peer/optional/indirect) and declaring manifest, plus the files whose imports use each one. Answers \"which version of X do we use, and where\".")]
+    fn list_dependencies(
+        &self,
+        Parameters(params): Parameters<ListDependenciesParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let deps = Self::per_repo(scope.as_deref(), |repo_id| {
+            db.list_dependencies(
+                repo_id,
+                params.name.as_deref().unwrap_or(""),
+                params.kind.as_deref().unwrap_or(""),
+            )
+        })
+        .map_err(|e| format!("query error: {e}"))?;
+        render(&deps, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "List files that failed to index cleanly and why: unreadable files (read), parser failures (parse), files with syntax errors whose symbols may be incomplete (syntax, with the first error line), extractor panics (panic) and reference-extraction failures (edge_resolution). Entries clear when the file is next indexed cleanly. Use when a symbol you expect is missing.")]
+    fn get_index_errors(
+        &self,
+        Parameters(params): Parameters<GetIndexErrorsParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let errors = Self::per_repo(scope.as_deref(), |repo_id| {
+            db.list_index_errors(repo_id, params.kind.as_deref().unwrap_or(""))
+        })
+        .map_err(|e| format!("query error: {e}"))?;
+        let result = serde_json::json!({
+            "total": errors.len(),
+            "errors": errors,
+        });
+        render(&result, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Summary of the last indexing run (`focal index` or server startup): finish time, duration, roots, files indexed/skipped/partial, symbols, edges, test links and per-file errors. Same JSON as last_index.json beside the index database.")]
+    fn get_last_index_report(
+        &self,
+        Parameters(params): Parameters<GetLastIndexReportParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let report = read_report(&self.report_path)
+            .map_err(|e| format!("report error: {e}"))?
+            .ok_or_else(|| format!("no indexing run has written {} yet", self.report_path.display()))?;
+        render(&report, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Coupling metrics per directory from the dependency graph: afferent coupling (symbols elsewhere that depend on the directory), efferent coupling (symbols elsewhere it depends on), instability Ce/(Ca+Ce), internal edges and cohesion (share of its outgoing edges that stay inside). Computed at each index run; pass `refresh` after incremental changes. Sort by instability to find volatile modules, by afferent coupling to find the ones everything leans on.")]
+    fn get_coupling_report(
+        &self,
+        Parameters(params): Parameters<GetCouplingReportParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let sort = params.sort.as_deref().unwrap_or("module");
+        if !["module", "instability", "afferent", "efferent", "cohesion"].contains(&sort) {
+            return Err(format!(
+                "unknown sort '{sort}'; expected module, instability, afferent, efferent or cohesion"
+            ));
+        }
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let repo = self.resolve_repo(&db, params.repo.as_deref())?;
+        let stored = match params.refresh.unwrap_or(false) {
+            true => Vec::new(),
+            false => db.get_module_metrics(repo.id).map_err(|e| format!("query error: {e}"))?,
         };
-        serde_json::to_string_pretty(&overview).map_err(|e| format!("json error: {e}"))
+        // Indexes from before coupling metrics were stored have none yet.
+        let mut metrics = match stored.is_empty() {
+            true => crate::coupling::refresh(&db, repo.id).map_err(|e| format!("coupling error: {e}"))?,
+            false => stored,
+        };
+        crate::coupling::sort_metrics(&mut metrics, sort);
+        let total = metrics.len();
+        metrics.truncate(params.limit.unwrap_or(50));
+        render(
+            &serde_json::json!({
+                "repo": repo.name,
+                "modules": metrics,
+                "total_modules": total,
+            }),
+            format,
+        )
+        .map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "What changed in a repository's dependency graph: symbols and edges added and removed between the last two index runs that changed the graph (`focal index` or server startup), or with `live`, since the last run. Symbols are `path::qualified_name` (`@line` added when a name repeats in its file). Also returns symbol/edge counts and a graph hash for recent recorded graphs; `changed` is false when the hashes match. Use after a refactor to confirm connectivity changed only where intended.")]
+    fn graph_diff(
+        &self,
+        Parameters(params): Parameters<GraphDiffParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let repo = self.resolve_repo(&db, params.repo.as_deref())?;
+        let live = params.live.unwrap_or(false);
+        let diff = crate::graph_snapshot::graph_diff(&db, repo.id, live, params.limit.unwrap_or(100))
+            .map_err(|e| format!("graph diff error: {e}"))?
+            .ok_or_else(|| match live {
+                true => format!("no index run recorded for '{}' yet", repo.name),
+                false => format!(
+                    "'{}' has fewer than two recorded graphs; pass `live` to compare with the current index",
+                    repo.name
+                ),
+            })?;
+        render(&diff, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "List the tests that exercise a symbol, found at index time from each test's direct calls and from naming conventions (`TestFoo`, `test_foo` → `foo`). Each entry says whether the link came from a call, the name, or both. Use before changing a symbol to know which tests to run.")]
+    fn tests_for_symbol(
+        &self,
+        Parameters(params): Parameters<TestsForSymbolParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let repo_id = match &params.repo {
+            Some(name) => Some(
+                db.get_repo_id_by_name(name)
+                    .map_err(|e| format!("repo lookup error: {e}"))?
+                    .ok_or_else(|| format!("repository '{name}' not found"))?,
+            ),
+            None => None,
+        };
+        let links = db
+            .tests_for_symbol(&params.symbol_name, repo_id)
+            .map_err(|e| format!("query error: {e}"))?;
+        if links.is_empty() && !Self::symbol_exists(&db, &params.symbol_name, repo_id) {
+            return Err(db.symbol_not_found_message(&params.symbol_name, repo_id));
+        }
+        render(&links, format).map_err(|e| format!("json error: {e}"))
     }
 
-    #[tool(description = "Retrieve focused, token-budgeted context for a query. Detects intent (debug/refactor/modify/explore), finds pivot symbols via FTS5, expands to adjacent symbols via the dependency graph, and attaches relevant memories. Pivots include full bodies on first request; subsequent requests for the same symbols within this session return skeleton + note (progressive disclosure). Respects the token budget throughout.")]
+    #[tool(description = "List the production symbols a test exercises: the symbols it calls directly plus those its name points at (`TestParseHeader` → `parse_header`). The inverse of tests_for_symbol.")]
+    fn symbols_for_test(
+        &self,
+        Parameters(params): Parameters<SymbolsForTestParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let repo_id = match &params.repo {
+            Some(name) => Some(
+                db.get_repo_id_by_name(name)
+                    .map_err(|e| format!("repo lookup error: {e}"))?
+                    .ok_or_else(|| format!("repository '{name}' not found"))?,
+            ),
+            None => None,
+        };
+        let links = db
+            .symbols_for_test(&params.test_name, repo_id)
+            .map_err(|e| format!("query error: {e}"))?;
+        if links.is_empty() && !Self::symbol_exists(&db, &params.test_name, repo_id) {
+            return Err(db.symbol_not_found_message(&params.test_name, repo_id));
+        }
+        render(&links, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Import an lcov or Cobertura coverage report and map its line hits onto the symbols of one repository (`repo`, or the first workspace), replacing coverage it imported earlier. Afterwards symbol results and impact graphs carry `coverage` (covered flag and percentage of instrumented lines executed), so untested code in a blast radius stands out. Coverage for a file is dropped when the file is re-indexed.")]
+    fn import_coverage(
+        &self,
+        Parameters(params): Parameters<ImportCoverageParams>,
+    ) -> Result<String, String> {
+        let text = std::fs::read_to_string(&params.path)
+            .map_err(|e| format!("cannot read '{}': {e}", params.path))?;
+        let hits = crate::coverage::parse_report(&text, params.format.as_deref().unwrap_or(""))
+            .map_err(|e| format!("parse error: {e}"))?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        // One repository per report: suffix path matches would otherwise
+        // replace the coverage of every repository sharing a relative path.
+        let repo = self.resolve_repo(&db, params.repo.as_deref())?;
+        let import = crate::coverage::import_coverage(&db, &repo, &hits)
+            .map_err(|e| format!("import error: {e}"))?;
+        if import.files_matched == 0 {
+            return Err(format!(
+                "none of the {} files in the report match a file indexed in '{}'",
+                hits.len(),
+                repo.name
+            ));
+        }
+        serde_json::to_string_pretty(&import).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Re-parse only the files matching the given globs, without walking the whole repository. Use after a code generation step or when one directory is known to be stale. Globs are repo-relative and gitignore-style (`gen/`, `/api/*.go`, `**/*_pb.rs`). Matching files are re-parsed when their content changed, new ones are indexed, and indexed ones gone from disk are removed.")]
+    fn reindex_paths(
+        &self,
+        Parameters(params): Parameters<ReindexPathsParams>,
+    ) -> Result<String, String> {
+        let repo = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            self.resolve_repo(&db, params.repo.as_deref())?
+        };
+        let stats = self
+            .focal
+            .reindex_paths(Path::new(&repo.root_path), &params.globs)
+            .map_err(|e| format!("reindex error: {e:#}"))?;
+        let value = serde_json::json!({
+            "repo": repo.name,
+            "files_reindexed": stats.files_indexed,
+            "files_skipped": stats.files_skipped,
+            "files_removed": stats.files_removed,
+            "symbols": stats.symbols_extracted,
+            "errors": stats.errors,
+        });
+        serde_json::to_string_pretty(&value).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Retrieve focused, token-budgeted context for a query. Detects intent (debug/refactor/modify/explore), finds pivot symbols via FTS5 (boosted toward `near`, a focus symbol or file, when given), expands to adjacent symbols via the dependency graph (or, with `expansion`, to the pivots' siblings in the same class or file), and attaches the most relevant memories within a memory budget (overflow is listed as `memory_refs`). With `include_file_skeletons`, also outlines each pivot's file so its neighbours are visible. Pivots include full bodies on first request; subsequent requests for the same symbols within this session return skeleton + note (progressive disclosure). Respects the token budget throughout.")]
     fn get_context(
         &self,
         Parameters(params): Parameters<GetContextParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
         let capsule = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             let sent = self.sent_symbols.lock().map_err(|e| format!("lock error: {e}"))?;
-            let max_tokens = params.max_tokens.unwrap_or(12_000);
-
-            let repo_id = if let Some(ref repo_name) = params.repo {
-                db.get_repo_id_by_name(repo_name)
-                    .map_err(|e| format!("repo lookup error: {e}"))?
-            } else {
-                None
-            };
-
-            let engine = ContextEngine::new(&db);
+            let (engine, max_tokens, repo_id) = self.context_engine(&db, &params)?;
             let capsule = engine
                 .get_capsule(&params.query, max_tokens, repo_id, &sent)
                 .map_err(|e| format!("context error: {e}"))?;
@@ -781,17 +2419,32 @@ impl FocalServer {
         };
 
         // Record newly-sent symbol IDs (those with full bodies, not placeholders)
-        {
-            if let Ok(mut sent) = self.sent_symbols.lock() {
-                for item in &capsule.items {
-                    if item.is_pivot && !item.body.starts_with("(full body") {
-                        sent.insert(item.symbol_id);
-                    }
-                }
-            }
-        }
+        self.mark_sent(
+            capsule
+                .items
+                .iter()
+                .filter(|item| item.is_pivot && !item.body.starts_with("(full body"))
+                .map(|item| item.symbol_id),
+        );
 
-        serde_json::to_string_pretty(&capsule).map_err(|e| format!("json error: {e}"))
+        render(&capsule, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Explain how get_context would assemble a capsule for a query, without returning any bodies: the detected intent and template, the FTS query after intent keywords are stripped, every pivot candidate with its score and why it was kept or dropped (over budget, past the pivot cutoff, marked irrelevant), each neighbour considered during expansion, and tokens spent per section. Takes the same parameters as get_context; nothing is marked as sent.")]
+    fn explain_context(
+        &self,
+        Parameters(params): Parameters<GetContextParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let trace = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let sent = self.sent_symbols.lock().map_err(|e| format!("lock error: {e}"))?;
+            let (engine, max_tokens, repo_id) = self.context_engine(&db, &params)?;
+            engine
+                .explain(&params.query, max_tokens, repo_id, &sent)
+                .map_err(|e| format!("context error: {e}"))?
+        };
+        render(&trace, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Token-efficient file view: returns signatures and types without implementation bodies. 70-90% fewer tokens than full source.")]
@@ -799,23 +2452,38 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<GetSkeletonParams>,
     ) -> Result<String, String> {
-        let results = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+        let format = Self::output_format(params.format.as_deref())?;
+        let (file, results) = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             let detail = params.detail.as_deref().unwrap_or("standard");
-            db.get_skeleton_by_path(&params.file_path, params.repo.as_deref(), detail)
-                .map_err(|e| format!("query error: {e}"))?
+            if let Ok(file) = Self::resolve_file(&db, &params.file_path, params.repo.as_deref()) {
+                self.refresh_stale(&db, &[file.file_id]);
+            }
+            self.cached(&db, "get_skeleton", (&params.file_path, &params.repo, detail), |db| {
+                let file = Self::resolve_file(db, &params.file_path, params.repo.as_deref())?;
+                let results = db
+                    .get_skeleton(file.file_id, detail)
+                    .map_err(|e| format!("query error: {e}"))?;
+                Ok((file, results))
+            })?
         };
-        serde_json::to_string_pretty(&results).map_err(|e| format!("json error: {e}"))
+        self.touch_file(file.file_id);
+        render_skeleton(&file.repo, &file.file_path, &results, format).map_err(|e| format!("json error: {e}"))
     }
 
-    #[tool(description = "Compute the blast radius of changing a symbol. Traverses reverse dependency edges (who depends on this?) via BFS, returning all transitively affected symbols up to `depth` hops away.")]
+    #[tool(description = "Compute the blast radius of changing a symbol. Traverses reverse dependency edges (who depends on this?) via BFS, returning all transitively affected symbols up to `depth` hops away. Symbols reached over a call edge list the argument kinds of each distinct call (`call_sites`), so callers affected by a parameter change can be told apart. Traversal stops at `max_nodes` affected symbols or a 5-second budget; the partial result still holds every dependent nearer than where it stopped. With `report: true` the response is `{nodes, truncated?}`, where `truncated` (`max_nodes` or `timeout`) marks such a cut.")]
     fn get_impact_graph(
         &self,
         Parameters(params): Parameters<GetImpactGraphParams>,
     ) -> Result<String, String> {
-        let nodes = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+        let format = Self::output_format(params.format.as_deref())?;
+        let report = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             let max_depth = params.depth.unwrap_or(2).min(5);
+            let limits = TraversalLimits {
+                max_nodes: params.max_nodes.unwrap_or(TraversalLimits::DEFAULT_MAX_NODES),
+                ..TraversalLimits::default()
+            };
 
             let repo_id = if let Some(ref repo_name) = params.repo {
                 db.get_repo_id_by_name(repo_name)
@@ -824,11 +2492,15 @@ impl FocalServer {
                 None
             };
 
-            let engine = GraphEngine::new(&db);
-            let nodes = engine
-                .impact_graph(&params.symbol_name, max_depth, repo_id)
-                .map_err(|e| format!("graph error: {e}"))?;
+            let key = (&params.symbol_name, max_depth, limits.max_nodes, repo_id);
+            let report = self.cached(&db, "get_impact_graph", key, |db| {
+                GraphEngine::new(db)
+                    .with_limits(limits)
+                    .impact_graph_report(&params.symbol_name, max_depth, repo_id)
+                    .map_err(|e| format!("graph error: {e}"))
+            })?;
 
+            let nodes = &report.nodes;
             if !nodes.is_empty() {
                 let _ = db.save_auto_observation(
                     &format!(
@@ -841,9 +2513,47 @@ impl FocalServer {
                 );
             }
 
-            nodes
+            report
         };
-        serde_json::to_string_pretty(&nodes).map_err(|e| format!("json error: {e}"))
+        if let Some(reason) = &report.truncated {
+            tracing::info!(symbol = %params.symbol_name, reason, "impact graph truncated");
+        }
+        if params.report.unwrap_or(false) {
+            render(&report, format).map_err(|e| format!("json error: {e}"))
+        } else {
+            render(&report.nodes, format).map_err(|e| format!("json error: {e}"))
+        }
+    }
+
+    #[tool(description = "Blast radius of a patch before applying it. Maps the hunks of a unified diff (or, with no `diff`, the repository's uncommitted changes via git) onto indexed symbols by line range, then returns the changed symbols, their transitive dependents up to `depth` hops, the tests linked to any of them, and changed files that are not indexed.")]
+    fn get_impact_for_diff(
+        &self,
+        Parameters(params): Parameters<GetImpactForDiffParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let repo = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            self.resolve_repo(&db, params.repo.as_deref())?
+        };
+
+        // Run git without holding the DB lock. A given patch isn't applied
+        // yet, so the index holds its old side; uncommitted changes are on
+        // disk and indexed by the watcher.
+        let (diff, side) = match params.diff {
+            Some(diff) => (diff, crate::impact::DiffSide::Old),
+            None => (
+                crate::git::working_diff(std::path::Path::new(&repo.root_path), params.staged.unwrap_or(false))
+                    .map_err(|e| format!("git error: {e:#}"))?,
+                crate::impact::DiffSide::New,
+            ),
+        };
+
+        let impact = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let max_depth = params.depth.unwrap_or(2).min(5);
+            crate::impact::diff_impact(&db, repo.id, &diff, side, max_depth).map_err(|e| format!("impact error: {e}"))?
+        };
+        render(&impact, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Find call/dependency paths between two symbols. Traverses forward dependency edges via BFS to discover how `from_symbol` reaches `to_symbol`. Returns up to `max_paths` distinct paths, each as an ordered list of symbol names.")]
@@ -851,8 +2561,9 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<SearchLogicFlowParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
         let result: Vec<Vec<String>> = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             let max_paths = params.max_paths.unwrap_or(3);
 
             let repo_id = if let Some(ref repo_name) = params.repo {
@@ -873,7 +2584,37 @@ impl FocalServer {
                 .map(|path| path.into_iter().map(|s| s.name).collect())
                 .collect()
         };
-        serde_json::to_string_pretty(&result).map_err(|e| format!("json error: {e}"))
+        render(&result, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Shortest dependency chain between two files: how code in `from_file` ends up reaching `to_file` (e.g. \"how does the CLI touch the billing module\"). Symbol edges are lifted to files; each hop names one symbol edge that crosses into the next file. The search gives up with an error after visiting `max_nodes` files or 5 seconds. Use search_logic_flow for symbol-level paths.")]
+    fn find_file_path(
+        &self,
+        Parameters(params): Parameters<FindFilePathParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let from = Self::resolve_file(&db, &params.from_file, params.repo.as_deref())?;
+        let to = Self::resolve_file(&db, &params.to_file, params.repo.as_deref())?;
+        let limits = TraversalLimits {
+            max_nodes: params.max_nodes.unwrap_or(TraversalLimits::DEFAULT_MAX_NODES),
+            ..TraversalLimits::default()
+        };
+        let chain = GraphEngine::new(&db)
+            .with_limits(limits)
+            .find_file_path(from.file_id, to.file_id)
+            .map_err(|e| format!("graph error: {e}"))?
+            .ok_or_else(|| format!("no dependency chain from {} to {}", from.file_path, to.file_path))?;
+        render(
+            &serde_json::json!({
+                "from": from.file_path,
+                "to": to.file_path,
+                "hops": chain.len() - 1,
+                "path": chain,
+            }),
+            format,
+        )
+        .map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Fetch multiple symbols in a single call within a token budget. More efficient than multiple query_symbol calls when you need several specific symbols. Includes dependency hints when a symbol implements a trait/interface or imports types not in the result set.")]
@@ -881,9 +2622,11 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<BatchQueryParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let mask = Self::field_mask(params.include.as_deref(), params.exclude.as_deref())?;
         let mut results = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            let include_body = params.include_body.unwrap_or(true);
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let include_body = params.include_body.unwrap_or(true) && mask.keeps("body");
             let budget = params.max_tokens.unwrap_or(8000);
             let mut used = 0usize;
             let mut out: Vec<(crate::db::Symbol, String)> = Vec::new();
@@ -925,6 +2668,7 @@ impl FocalServer {
                             continue;
                         }
                         let relation = match edge_kind.as_str() {
+                            _ if dep_kind == "external" => format!("Uses third-party `{dep_name}` (not indexed)"),
                             "type_ref" => format!("References {dep_kind} `{dep_name}` (not in context)"),
                             "imports" => format!("Imports `{dep_name}` (not in context)"),
                             "calls" => format!("Calls `{dep_name}` (not in context)"),
@@ -938,7 +2682,8 @@ impl FocalServer {
                 }
             }
 
-            out.into_iter()
+            let mut results: Vec<SymbolResult> = out
+                .into_iter()
                 .map(|(sym, file_path)| {
                     let memories = mem_map.remove(&sym.id).unwrap_or_default();
                     let dependency_hints = hint_map.remove(&sym.id).unwrap_or_default();
@@ -960,37 +2705,373 @@ impl FocalServer {
                         dependency_hints,
                         source: sym.source.clone(),
                         manifest_repo: sym.manifest_repo.clone(),
+                        visibility: sym.visibility.clone(),
+                        modifiers: sym.modifiers.split_whitespace().map(String::from).collect(),
+                        types: parse_signature_types(sym.signature_types.clone()),
+                        attributes: sym.attributes.lines().map(String::from).collect(),
+                        owners: Vec::new(),
+                        coverage: None,
+                        partial: false,
+                        generated: false,
+                        package: String::new(),
                     }
                 })
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+            Self::attach_owners(&db, &mut results);
+            Self::attach_coverage(&db, &mut results);
+            Self::mark_file_flags(&db, &mut results);
+            Self::attach_packages(&db, &mut results);
+            results
         };
 
         Self::annotate_manifest_bodies(&mut results);
 
-        // Track sent symbols for progressive disclosure
-        if let Ok(mut sent) = self.sent_symbols.lock() {
-            for r in &results {
-                sent.insert(r.id);
-            }
+        // Track sent symbols for progressive disclosure (only if bodies were returned)
+        if mask.keeps("body") {
+            self.mark_sent(results.iter().map(|r| r.id));
         }
-        serde_json::to_string_pretty(&results).map_err(|e| format!("json error: {e}"))
+        let results = mask.apply(&results).map_err(|e| format!("json error: {e}"))?;
+        render(&results, format).map_err(|e| format!("json error: {e}"))
     }
 
-    #[tool(description = "Report database health: size, row counts, FTS integrity. Useful for diagnosing index issues.")]
+    #[tool(description = "Report database health: size, WAL size, row counts, FTS integrity. A failed FTS check is repaired on the spot and the repairs are listed. Useful for diagnosing index issues.")]
     fn get_health(
         &self,
-        Parameters(_): Parameters<GetHealthParams>,
+        Parameters(params): Parameters<GetHealthParams>,
     ) -> Result<String, String> {
-        let report = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
-            db.get_health()
-                .map_err(|e| format!("health check error: {e}"))?
+        let format = Self::output_format(params.format.as_deref())?;
+        let (report, fts_repairs, graph_cache) = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let mut report = db.get_health()
+                .map_err(|e| format!("health check error: {e}"))?;
+            // Drift is repaired rather than only reported; fts_ok then
+            // reflects the index after the repair.
+            let fts_repairs = if report.fts_ok {
+                Vec::new()
+            } else {
+                let repairs = db.check_fts_consistency().map_err(|e| format!("FTS repair error: {e}"))?;
+                report.fts_ok = db.fts_indexes_ok().map_err(|e| format!("health check error: {e}"))?;
+                repairs
+            };
+            (report, fts_repairs, db.graph_cache_stats().map_err(|e| format!("health check error: {e}"))?)
         };
         let mut value = serde_json::to_value(&report).map_err(|e| format!("json error: {e}"))?;
+        if !fts_repairs.is_empty() {
+            value["fts_repairs"] = serde_json::to_value(&fts_repairs).map_err(|e| format!("json error: {e}"))?;
+        }
         value["indexing_complete"] = serde_json::Value::Bool(
             self.indexing_complete.load(Ordering::Relaxed),
         );
-        serde_json::to_string_pretty(&value).map_err(|e| format!("json error: {e}"))
+        let cache = self.query_cache.lock().map_err(|e| format!("lock error: {e}"))?.stats();
+        value["query_cache"] = serde_json::json!({
+            "hits": cache.hits,
+            "misses": cache.misses,
+            "entries": cache.entries,
+            "bytes": cache.bytes,
+            "budget_bytes": cache.budget_bytes,
+            "evictions": cache.evictions,
+        });
+        value["graph_cache"] = serde_json::to_value(graph_cache).map_err(|e| format!("json error: {e}"))?;
+        render(&value, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Run index self-checks and report concrete problems with a suggested fix for each: repositories whose root path no longer exists, supported files on disk missing from the index, a .focal.toml that does not parse, FTS indexes out of sync with symbols, orphaned edges, and symbols with empty bodies.")]
+    fn diagnose_index(
+        &self,
+        Parameters(params): Parameters<DiagnoseIndexParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let report = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let registry = crate::grammar::GrammarRegistry::new();
+            crate::diagnostics::diagnose_index(&db, &registry)
+                .map_err(|e| format!("diagnose error: {e}"))?
+        };
+        render(&report, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Look up who owns a file or symbol according to the repository's CODEOWNERS file (.github/CODEOWNERS, CODEOWNERS, or docs/CODEOWNERS). Returns the owners and the matching rule; with include_git, also the top git contributors to the file.")]
+    fn get_owners(
+        &self,
+        Parameters(params): Parameters<GetOwnersParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let (repo, repo_root, file_path) = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            match (&params.symbol_name, &params.file_path) {
+                (_, Some(path)) => {
+                    let file = Self::resolve_file(&db, path, params.repo.as_deref())?;
+                    (file.repo, file.repo_root, file.file_path)
+                }
+                (Some(name), None) => {
+                    let repo_id = match &params.repo {
+                        Some(repo_name) => db
+                            .get_repo_id_by_name(repo_name)
+                            .map_err(|e| format!("repo lookup error: {e}"))?,
+                        None => None,
+                    };
+                    let sym = match repo_id {
+                        Some(rid) => db.find_symbol_by_name(rid, name),
+                        None => db.find_symbol_by_name_any(name),
+                    }
+                    .map_err(|e| format!("db error: {e}"))?
+                    .ok_or_else(|| db.symbol_not_found_message(name, repo_id))?;
+                    // The symbol's own file, not a same-named path elsewhere.
+                    let file = db
+                        .resolved_file_for_symbol(sym.id)
+                        .map_err(|e| format!("file path error: {e}"))?
+                        .ok_or_else(|| format!("file of symbol '{name}' not found"))?;
+                    (file.repo, file.repo_root, file.file_path)
+                }
+                (None, None) => return Err("one of symbol_name or file_path is required".to_string()),
+            }
+        };
+
+        let root = std::path::Path::new(&repo_root);
+        let codeowners = CodeOwners::load_cached(root);
+        let rule = codeowners.as_ref().and_then(|c| c.rule_for(&file_path));
+        let mut response = serde_json::json!({
+            "repo": repo,
+            "file_path": file_path,
+            "owners": rule.map(|r| r.owners.clone()).unwrap_or_default(),
+            "rule": rule.map(|r| r.pattern.clone()),
+            "codeowners_found": codeowners.is_some(),
+        });
+        if params.include_git.unwrap_or(false) {
+            response["contributors"] =
+                match crate::owners::git_contributors(root, &file_path, 5) {
+                    Ok(c) => serde_json::to_value(c).map_err(|e| format!("json error: {e}"))?,
+                    Err(e) => serde_json::Value::String(e),
+                };
+        }
+        render(&response, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "List a repository's public API: exported symbols only (Go capitalized names, Rust `pub`, TS/JS `export`, Python names without a leading underscore), grouped by module, with signatures and doc comments. Use as the starting point for documenting a package or reviewing its surface.")]
+    fn get_public_api(
+        &self,
+        Parameters(params): Parameters<GetPublicApiParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let repos: Vec<_> = db
+            .list_repositories()
+            .map_err(|e| format!("repo lookup error: {e}"))?
+            .into_iter()
+            .filter(|r| scope.as_ref().is_none_or(|ids| ids.contains(&r.id)))
+            .collect();
+
+        let reports = repos
+            .iter()
+            .map(|repo| {
+                crate::api::public_api(
+                    &db,
+                    repo,
+                    params.path_prefix.as_deref(),
+                    params.include_docs.unwrap_or(true),
+                    params.max_symbols.unwrap_or(200),
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|e| format!("api error: {e}"))?;
+        render(&reports, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Unified diff between two symbols' bodies (e.g. parse_v1 vs parse_v2), or one symbol across repositories (repo_a/repo_b) or git revisions (rev_a/rev_b: branch, tag, or SHA). Returns both locations, added/removed line counts, and the diff — cheaper than reading both bodies.")]
+    fn compare_symbols(
+        &self,
+        Parameters(params): Parameters<CompareSymbolsParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let (a, b) = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let name_b = params.symbol_b.as_deref().unwrap_or(&params.symbol_a);
+            let repo_b = params.repo_b.as_deref().or(params.repo_a.as_deref());
+            let a = Self::compared_symbol(
+                &db,
+                &params.symbol_a,
+                params.repo_a.as_deref(),
+                params.rev_a.as_deref(),
+            )?;
+            let b = Self::compared_symbol(&db, name_b, repo_b, params.rev_b.as_deref())?;
+            (a, b)
+        };
+
+        let label = |side: &ComparedSymbol| match &side.rev {
+            Some(rev) => format!("{}:{} ({rev})", side.file_path, side.symbol),
+            None => format!("{}:{}", side.file_path, side.symbol),
+        };
+        let diff = crate::diff::unified_diff(
+            &a.body,
+            &b.body,
+            &label(&a),
+            &label(&b),
+            params.context_lines.unwrap_or(3),
+        );
+
+        let response = serde_json::json!({
+            "a": a,
+            "b": b,
+            "identical": diff.added == 0 && diff.removed == 0,
+            "added": diff.added,
+            "removed": diff.removed,
+            "diff": diff.text,
+        });
+        render(&response, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "List HTTP routes registered in indexed code (axum/actix, Express/Nest, Gin/Echo/chi/net/http/gorilla, Flask/FastAPI) as method + path -> handler, with where each route is registered and where its handler is defined. Filter by repository, method, or path prefix to map a URL to the code that serves it.")]
+    fn list_routes(
+        &self,
+        Parameters(params): Parameters<ListRoutesParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let routes = Self::per_repo(scope.as_deref(), |repo_id| {
+            db.list_routes(
+                repo_id,
+                params.method.as_deref().unwrap_or(""),
+                params.path_prefix.as_deref().unwrap_or(""),
+            )
+        })
+        .map_err(|e| format!("route lookup error: {e}"))?;
+        render(&routes, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "List environment variables read by indexed code (std::env::var / env!, os.Getenv / os.LookupEnv, process.env.X, os.getenv / os.environ) with every read site and the symbol containing it. Answers \"where is DATABASE_URL read?\" without grepping. Only literal variable names are recognized.")]
+    fn list_env_vars(
+        &self,
+        Parameters(params): Parameters<ListEnvVarsParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let mut usages = Self::per_repo(scope.as_deref(), |repo_id| {
+            db.list_env_usages(repo_id, params.name.as_deref().unwrap_or(""))
+        })
+        .map_err(|e| format!("env lookup error: {e}"))?;
+        usages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Rows arrive ordered by name; group consecutive reads of one variable.
+        let mut vars: Vec<serde_json::Value> = Vec::new();
+        for chunk in usages.chunk_by(|a, b| a.name == b.name) {
+            vars.push(serde_json::json!({
+                "name": chunk[0].name,
+                "read_count": chunk.len(),
+                "reads": chunk,
+            }));
+        }
+        render(&vars, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Find SQL statements embedded in string literals that read or write a database table, with the code symbol containing each one. Use before a schema change (renaming or dropping a column, splitting a table) to see every query it affects. Filter by operation (SELECT/INSERT/UPDATE/DELETE) to separate readers from writers.")]
+    fn find_queries_touching_table(
+        &self,
+        Parameters(params): Parameters<FindQueriesTouchingTableParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let queries = Self::per_repo(scope.as_deref(), |repo_id| {
+            db.find_queries_touching_table(&params.table, repo_id, params.operation.as_deref().unwrap_or(""))
+        })
+        .map_err(|e| format!("query lookup error: {e}"))?;
+        let response = serde_json::json!({
+            "table": params.table,
+            "query_count": queries.len(),
+            "queries": queries,
+        });
+        render(&response, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Audit error handling: find errors that are discarded or turned into panics — Rust `.unwrap()`/`.expect()` outside tests, Go `x, _ := f()` / `_ = err`, Python bare `except:` and `except ...: pass`, TS/JS empty `catch {}` — grouped by file with the enclosing symbol and source line. Replaces a repo-wide grep when hardening a module.")]
+    fn find_unhandled_errors(
+        &self,
+        Parameters(params): Parameters<FindUnhandledErrorsParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+        let prefix = params
+            .path_prefix
+            .as_deref()
+            .map(crate::paths::normalize_path)
+            .unwrap_or_default();
+        let sites = Self::per_repo(scope.as_deref(), |repo_id| {
+            db.list_error_sites(repo_id, &prefix, params.kind.as_deref().unwrap_or(""))
+        })
+        .map_err(|e| format!("error site lookup error: {e}"))?;
+        let total = sites.len();
+        let max_results = params.max_results.unwrap_or(200);
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for site in &sites {
+            *counts.entry(site.kind.as_str()).or_default() += 1;
+        }
+        // Rows arrive ordered by repo and file; group consecutive sites.
+        let mut files: Vec<serde_json::Value> = Vec::new();
+        for chunk in sites[..total.min(max_results)]
+            .chunk_by(|a, b| a.repo == b.repo && a.file_path == b.file_path)
+        {
+            let entries: Vec<_> = chunk
+                .iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "kind": s.kind,
+                        "line": s.line,
+                        "symbol": s.symbol,
+                        "snippet": s.snippet,
+                    })
+                })
+                .collect();
+            files.push(serde_json::json!({
+                "repo": chunk[0].repo,
+                "file_path": chunk[0].file_path,
+                "count": chunk.len(),
+                "sites": entries,
+            }));
+        }
+        let response = serde_json::json!({
+            "total": total,
+            "truncated": total > max_results,
+            "by_kind": counts,
+            "files": files,
+        });
+        render(&response, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Run a user-written tree-sitter query (S-expression patterns with @captures and #eq?/#match? predicates) against indexed files of one language, read from disk at request time, and return each match's captures with file, line, and column. Captured text has secrets masked. A scan stops after 20,000 files, 10,000 matches or 10 seconds and says so in `stopped`. For structural searches no dedicated tool covers, e.g. every `match` without a wildcard arm or every React component calling a given hook.")]
+    fn run_tree_query(
+        &self,
+        Parameters(params): Parameters<RunTreeQueryParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let registry = crate::grammar::GrammarRegistry::new();
+        let grammar = registry
+            .for_extension(&params.language)
+            .ok_or_else(|| format!("unsupported language '{}'", params.language))?;
+        // Files are read and parsed with the database unlocked.
+        let targets = {
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+            let scope = Self::repo_scope(&db, params.repo.as_deref(), params.project.as_deref())?;
+            let repos: Vec<_> = db
+                .list_repositories()
+                .map_err(|e| format!("repo lookup error: {e}"))?
+                .into_iter()
+                .filter(|r| scope.as_ref().is_none_or(|ids| ids.contains(&r.id)))
+                .collect();
+            crate::tree_query::query_targets(&db, &repos, grammar, params.path_prefix.as_deref())
+                .map_err(|e| format!("query error: {e}"))?
+        };
+        let limits = TreeQueryLimits {
+            max_results: params.max_results.unwrap_or(TreeQueryLimits::DEFAULT_MAX_RESULTS),
+            ..TreeQueryLimits::default()
+        };
+        let report = crate::tree_query::query_files(&targets, grammar, &params.query, &limits)
+            .map_err(|e| format!("query error: {e}"))?;
+        render(&report, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Get git commit history for a specific symbol's file. Shows who last changed it and why. Requires git to be available in PATH.")]
@@ -998,8 +3079,9 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<GetSymbolHistoryParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
         let (file_path, repo_root) = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             let repo_id = if let Some(ref name) = params.repo {
                 db.get_repo_id_by_name(name)
                     .map_err(|e| format!("repo error: {e}"))?
@@ -1011,14 +3093,15 @@ impl FocalServer {
                 None => db.find_symbol_by_name_any(&params.symbol_name),
             }
             .map_err(|e| format!("db error: {e}"))?
-            .ok_or_else(|| format!("symbol '{}' not found", params.symbol_name))?;
+            .ok_or_else(|| db.symbol_not_found_message(&params.symbol_name, repo_id))?;
 
             let fp = db
                 .get_file_path_for_symbol(sym.id)
                 .map_err(|e| format!("file path error: {e}"))?;
 
             let root = self
-                .workspace_roots
+                .focal
+                .workspaces()
                 .first()
                 .map(|p| p.to_string_lossy().to_string())
                 .ok_or_else(|| "no workspace root configured".to_string())?;
@@ -1027,46 +3110,10 @@ impl FocalServer {
         };
 
         let max = params.max_entries.unwrap_or(5);
-
-        let output = std::process::Command::new("git")
-            .args([
-                "log",
-                "--format=%H%n%an%n%aI%n%s%n---",
-                &format!("-{max}"),
-                "--",
-                &file_path,
-            ])
-            .current_dir(&repo_root)
-            .output()
+        let commits = crate::git::file_log(Path::new(&repo_root), &file_path, max)
             .map_err(|e| format!("git error: {e}"))?;
 
-        if !output.status.success() {
-            return Err(format!(
-                "git log failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let commits: Vec<CommitEntry> = stdout
-            .split("---\n")
-            .filter(|s| !s.trim().is_empty())
-            .filter_map(|block| {
-                let lines: Vec<&str> = block.trim().lines().collect();
-                if lines.len() >= 4 {
-                    Some(CommitEntry {
-                        hash: lines[0].to_string(),
-                        author: lines[1].to_string(),
-                        date: lines[2].to_string(),
-                        message: lines[3..].join(" "),
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        serde_json::to_string_pretty(&commits).map_err(|e| format!("json error: {e}"))
+        render(&commits, format).map_err(|e| format!("json error: {e}"))
     }
 
     #[tool(description = "Recover session state after context compaction. Returns architectural decisions, recently accessed files, and symbols previously viewed. Call this after a context window reset to restore working memory. Resets progressive disclosure so previously-sent symbol bodies will be re-sent fresh on next request.")]
@@ -1074,13 +3121,14 @@ impl FocalServer {
         &self,
         Parameters(params): Parameters<RecoverSessionParams>,
     ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
         let target_session = params
             .session_id
             .as_deref()
             .unwrap_or(&self.session_id);
 
         let data = {
-            let db = self.db.lock().map_err(|e| format!("lock error: {e}"))?;
+            let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
             db.get_session_recovery(target_session)
                 .map_err(|e| format!("recovery error: {e}"))?
         };
@@ -1100,7 +3148,7 @@ impl FocalServer {
         let decisions: Vec<String> = data
             .manual_memories
             .iter()
-            .map(|m| format!("[{}] {}", m.category, m.content))
+            .map(|m| format!("[{}] {}", m.category, crate::memory::render_memory(m)))
             .collect();
 
         let summary = build_recovery_summary(&data);
@@ -1114,16 +3162,135 @@ impl FocalServer {
             observation_count: data.auto_observations.len(),
         };
 
-        serde_json::to_string_pretty(&recovery).map_err(|e| format!("json error: {e}"))
+        render(&recovery, format).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Flag symbols that came back in a get_context capsule but were off-topic for the current task. For the rest of this session get_context leaves them out — as pivots and as neighbours — instead of spending tokens on the same noise again; the capsule's `skipped_irrelevant` counts how many were dropped. Pass `unmark: true` to undo.")]
+    fn mark_irrelevant(
+        &self,
+        Parameters(params): Parameters<MarkIrrelevantParams>,
+    ) -> Result<String, String> {
+        let db = self.focal.lock().map_err(|e| format!("lock error: {e}"))?;
+        let mut ids = params.symbol_ids.unwrap_or_default();
+        let mut unknown = Vec::new();
+        let names = params.symbol_names.unwrap_or_default();
+        if !names.is_empty() {
+            let repo = self.resolve_repo(&db, params.repo.as_deref())?;
+            for name in names {
+                match db.find_symbol_by_name(repo.id, &name).map_err(|e| format!("db error: {e}"))? {
+                    Some(sym) => ids.push(sym.id),
+                    None => unknown.push(name),
+                }
+            }
+        }
+
+        let response = if params.unmark.unwrap_or(false) {
+            let removed = db
+                .unmark_irrelevant(&self.session_id, &ids)
+                .map_err(|e| format!("db error: {e}"))?;
+            serde_json::json!({ "unmarked": removed, "unknown": unknown })
+        } else {
+            if ids.is_empty() {
+                return Err("pass `symbol_ids` or `symbol_names` to mark".to_string());
+            }
+            let added = db
+                .mark_irrelevant(&self.session_id, &ids, params.reason.as_deref().unwrap_or(""))
+                .map_err(|e| format!("db error: {e}"))?;
+            serde_json::json!({ "marked": added, "unknown": unknown })
+        };
+        serde_json::to_string_pretty(&response).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Clear this session's working set — the symbols and files viewed so far, which get_context uses to prefer nearby pivots. Call when switching to an unrelated task so earlier work stops steering results. Progressive disclosure (already-sent bodies) is unaffected.")]
+    fn reset_working_set(
+        &self,
+        Parameters(_): Parameters<ResetWorkingSetParams>,
+    ) -> Result<String, String> {
+        let mut working_set = self.working_set.lock().map_err(|e| format!("lock error: {e}"))?;
+        let response = serde_json::json!({
+            "cleared_symbols": working_set.symbol_count(),
+            "cleared_files": working_set.file_count(),
+        });
+        working_set.clear();
+        serde_json::to_string_pretty(&response).map_err(|e| format!("json error: {e}"))
+    }
+
+    #[tool(description = "Tokens this session has received so far: the running total, calls and tokens per tool (heaviest first), and — when a soft cap is configured — the tokens remaining and the share of a requested get_context budget still granted. Calls before this one are counted.")]
+    fn get_session_budget(
+        &self,
+        Parameters(params): Parameters<GetSessionBudgetParams>,
+    ) -> Result<String, String> {
+        let format = Self::output_format(params.format.as_deref())?;
+        let budget = self.session_budget.lock().map_err(|e| format!("lock error: {e}"))?;
+        render(&budget.report(&self.session_id), format).map_err(|e| format!("json error: {e}"))
     }
 }
 
 // ---------------------------------------------------------------------------
-// ServerHandler — #[tool_handler] wires call_tool + list_tools to the router
+// ServerHandler — call_tool routes to the tools, records the size of each
+// response in the session budget and, when enabled, audits the call
 // ---------------------------------------------------------------------------
 
-#[tool_handler]
 impl ServerHandler for FocalServer {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let tool = request.name.to_string();
+        let audited = self.audit.then(|| {
+            let params = request
+                .arguments
+                .as_ref()
+                .map(|args| serde_json::Value::Object(args.clone()).to_string())
+                .unwrap_or_else(|| "{}".to_string());
+            let token = context
+                .extensions
+                .get::<http::request::Parts>()
+                .and_then(|parts| parts.headers.get(http::header::AUTHORIZATION))
+                .and_then(|value| value.to_str().ok())
+                .and_then(bearer_token)
+                .map(token_fingerprint);
+            (params, token)
+        });
+        let _slots = self.acquire_tool_slots().await?;
+        let result = match self.tool_router.call(ToolCallContext::new(self, request, context)).await {
+            Ok(result) => result,
+            Err(e) => {
+                if let Some((params, token)) = audited {
+                    self.record_audit(&tool, params, token, "error", 0);
+                }
+                return Err(e);
+            }
+        };
+        let tokens: usize = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text())
+            .map(|text| text.text.len().div_ceil(4))
+            .sum();
+        if let Ok(mut budget) = self.session_budget.lock() {
+            budget.record(&tool, tokens);
+        }
+        if let Some((params, token)) = audited {
+            let outcome = if result.is_error == Some(true) { "error" } else { "ok" };
+            self.record_audit(&tool, params, token, outcome, tokens);
+        }
+        Ok(result)
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        Ok(ListToolsResult { tools: self.tool_router.list_all(), meta: None, next_cursor: None })
+    }
+
+    fn get_tool(&self, name: &str) -> Option<Tool> {
+        self.tool_router.get(name).cloned()
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
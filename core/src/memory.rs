@@ -0,0 +1,230 @@
+use serde_json::{Map, Value};
+
+use crate::db::Memory;
+
+// ---------------------------------------------------------------------------
+// Staleness
+// ---------------------------------------------------------------------------
+
+/// Why a memory was marked stale, stored in `memories.stale_reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleReason {
+    /// A linked symbol's file was re-parsed.
+    FileReindexed,
+    /// A linked symbol no longer exists after its file was re-parsed.
+    SymbolRemoved,
+    /// The linked file (or every file under a linked directory) was deleted.
+    FileDeleted,
+    /// Marked stale by hand via update_memory.
+    Manual,
+}
+
+impl StaleReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::FileReindexed => "file_reindexed",
+            Self::SymbolRemoved => "symbol_removed",
+            Self::FileDeleted => "file_deleted",
+            Self::Manual => "manual",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Structured memory kinds
+// ---------------------------------------------------------------------------
+
+/// A memory template: a category whose memories may carry a fixed set of
+/// named fields instead of (or alongside) free text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryKind {
+    /// Category name the template applies to (matches `memories.category`).
+    pub name: &'static str,
+    /// Fields that must be present and non-empty.
+    pub required: &'static [&'static str],
+    /// Fields that may be present. Anything outside required + optional is rejected.
+    pub optional: &'static [&'static str],
+}
+
+/// Built-in templates. Field order here is the order used when rendering.
+pub const MEMORY_KINDS: &[MemoryKind] = &[
+    MemoryKind {
+        name: "decision",
+        required: &["decision"],
+        optional: &["context", "consequences"],
+    },
+    MemoryKind {
+        name: "invariant",
+        required: &["scope", "rule"],
+        optional: &[],
+    },
+    MemoryKind {
+        name: "bug_fix",
+        required: &["symptom", "fix"],
+        optional: &["root_cause"],
+    },
+];
+
+impl MemoryKind {
+    /// Look up the template for a category, if one exists.
+    pub fn for_category(category: &str) -> Option<&'static MemoryKind> {
+        MEMORY_KINDS.iter().find(|k| k.name == category)
+    }
+
+    /// All fields in render order: required first, then optional.
+    fn fields(&self) -> impl Iterator<Item = &'static str> {
+        self.required.iter().chain(self.optional.iter()).copied()
+    }
+}
+
+/// Validate `fields` against the template for `category`. Returns the
+/// normalized object (string values trimmed, empty optional fields dropped)
+/// or a human-readable error describing what the template expects.
+pub fn validate_fields(category: &str, fields: &Value) -> Result<Value, String> {
+    let kind = MemoryKind::for_category(category).ok_or_else(|| {
+        let known: Vec<&str> = MEMORY_KINDS.iter().map(|k| k.name).collect();
+        format!(
+            "category '{category}' has no structured template (structured kinds: {})",
+            known.join(", ")
+        )
+    })?;
+
+    let obj = fields
+        .as_object()
+        .ok_or_else(|| format!("fields for '{category}' must be a JSON object"))?;
+
+    let mut normalized = Map::new();
+    for (key, value) in obj {
+        if !kind.fields().any(|f| f == key) {
+            return Err(format!(
+                "unknown field '{key}' for '{category}' (expected: {})",
+                kind.fields().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        let text = value
+            .as_str()
+            .ok_or_else(|| format!("field '{key}' for '{category}' must be a string"))?
+            .trim();
+        if !text.is_empty() {
+            normalized.insert(key.clone(), Value::String(text.to_string()));
+        }
+    }
+
+    for required in kind.required {
+        if !normalized.contains_key(*required) {
+            return Err(format!(
+                "missing required field '{required}' for '{category}'"
+            ));
+        }
+    }
+
+    Ok(Value::Object(normalized))
+}
+
+/// Render structured fields as a single line, e.g.
+/// `decision: Use WAL; context: readers block writers`.
+/// Field order follows the template; unknown categories fall back to key order.
+pub fn render_fields(category: &str, fields: &Value) -> String {
+    let Some(obj) = fields.as_object() else {
+        return String::new();
+    };
+    let keys: Vec<String> = match MemoryKind::for_category(category) {
+        Some(kind) => kind.fields().map(str::to_string).collect(),
+        None => obj.keys().cloned().collect(),
+    };
+    keys.iter()
+        .filter_map(|k| {
+            obj.get(k)
+                .and_then(Value::as_str)
+                .map(|v| format!("{k}: {v}"))
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Render a memory for display: free-text memories use their content as-is;
+/// structured memories show their fields, prefixed by the content when it
+/// adds something beyond the rendered fields.
+pub fn render_memory(mem: &Memory) -> String {
+    let Some(fields) = &mem.structured else {
+        return mem.content.clone();
+    };
+    let rendered = render_fields(&mem.category, fields);
+    if mem.content.is_empty() || mem.content == rendered {
+        rendered
+    } else {
+        format!("{} ({rendered})", mem.content)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Memory relationships
+// ---------------------------------------------------------------------------
+
+/// Directed relationship kinds between memories (`source kind target`).
+/// `supersedes` hides the target from listings and capsules in favour of the
+/// source; `refines` and `relates_to` are informational.
+pub const MEMORY_LINK_KINDS: &[&str] = &["supersedes", "refines", "relates_to"];
+
+/// Check a relationship kind against `MEMORY_LINK_KINDS`.
+pub fn validate_link_kind(kind: &str) -> Result<(), String> {
+    if MEMORY_LINK_KINDS.contains(&kind) {
+        Ok(())
+    } else {
+        Err(format!(
+            "unknown memory link kind '{kind}' (expected: {})",
+            MEMORY_LINK_KINDS.join(", ")
+        ))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Symbol mention extraction
+// ---------------------------------------------------------------------------
+
+/// Extract identifiers in free text that plausibly name indexed symbols:
+/// backticked spans (`parse_input`, `Config::new()`) and bare CamelCase
+/// tokens (`HandleRequest`). Returned in first-seen order, deduplicated.
+/// Callers resolve these against the index; unresolved names are dropped.
+pub fn extract_symbol_mentions(content: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut push = |name: &str| {
+        let name = name.trim().trim_end_matches("()");
+        if !name.is_empty() && !out.iter().any(|n| n == name) {
+            out.push(name.to_string());
+        }
+    };
+
+    // Backticked spans. Only single-token spans count — `foo bar` is prose.
+    for (i, span) in content.split('`').enumerate() {
+        if i % 2 == 1 && !span.trim().contains(char::is_whitespace) {
+            push(span);
+        }
+    }
+
+    // Bare CamelCase tokens: uppercase first letter plus another uppercase
+    // letter after a lowercase one (HandleRequest, not HTTP or Handle).
+    for token in content.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        if is_camel_case(token) {
+            push(token);
+        }
+    }
+
+    out
+}
+
+fn is_camel_case(token: &str) -> bool {
+    let mut chars = token.chars();
+    if !chars.next().is_some_and(|c| c.is_ascii_uppercase()) {
+        return false;
+    }
+    let mut seen_lower = false;
+    for c in chars {
+        if c.is_ascii_lowercase() {
+            seen_lower = true;
+        } else if c.is_ascii_uppercase() && seen_lower {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,239 @@
+use serde::Serialize;
+
+use crate::packages::{package_for_path, Package};
+
+// ---------------------------------------------------------------------------
+// Module and package documentation
+// ---------------------------------------------------------------------------
+//
+// Human-written intent, captured during a full index: the first paragraph of
+// each README and the top-level doc comment of each package entry file —
+// Rust `//!` in `lib.rs`/`main.rs`, a Go `// Package foo` comment, a Python
+// `__init__.py` docstring, and a JS/TS `index` file's `@packageDocumentation`
+// / `@module` / `@fileoverview` block. `get_repo_overview` with `docs` shows
+// one per package: its README, else its shallowest doc comment.
+
+/// Longest paragraph kept, in characters.
+pub const MAX_DOC_CHARS: usize = 600;
+
+/// A doc paragraph extracted from one file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModuleDoc {
+    /// Repo-relative path of the README or source file.
+    pub path: String,
+    /// "readme" or "doc_comment".
+    pub kind: String,
+    pub doc: String,
+}
+
+/// The doc shown for one package (or the repository root).
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageDoc {
+    /// Package name; the repository name for the root.
+    pub package: String,
+    /// Repo-relative package directory; empty for the root.
+    pub dir: String,
+    /// File the doc was taken from.
+    pub source: String,
+    pub doc: String,
+}
+
+/// True for `README`, `README.md`, `readme.rst` and the like.
+pub fn is_readme(rel_path: &str) -> bool {
+    let file_name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    stem.eq_ignore_ascii_case("readme")
+        && matches!(file_name.rsplit_once('.').map(|(_, ext)| ext), None | Some("md" | "markdown" | "rst" | "txt"))
+}
+
+/// The doc paragraph of the file at `rel_path`, if it has one: a README's
+/// first prose paragraph, or an entry file's top-level doc comment.
+pub fn extract(rel_path: &str, text: &str) -> Option<ModuleDoc> {
+    let (kind, doc) = if is_readme(rel_path) {
+        ("readme", readme_paragraph(text)?)
+    } else {
+        ("doc_comment", doc_comment(rel_path, text)?)
+    };
+    Some(ModuleDoc { path: rel_path.to_string(), kind: kind.to_string(), doc })
+}
+
+/// First paragraph of prose: headings, badges, HTML and reStructuredText
+/// underlines are skipped.
+pub fn readme_paragraph(text: &str) -> Option<String> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        let underline = !line.is_empty() && line.chars().all(|c| matches!(c, '=' | '-' | '~' | '*'));
+        if underline && lines.len() == 1 {
+            // The line above was a setext or reStructuredText title.
+            lines.clear();
+            continue;
+        }
+        let decoration = underline
+            || line.starts_with('#')
+            || line.starts_with('<')
+            || line.starts_with("![")
+            || line.starts_with("[![")
+            || line.starts_with("..");
+        if line.is_empty() || decoration {
+            if !lines.is_empty() {
+                break;
+            }
+            continue;
+        }
+        lines.push(line);
+    }
+    paragraph(lines)
+}
+
+fn doc_comment(rel_path: &str, text: &str) -> Option<String> {
+    entry_doc(rel_path)?(text)
+}
+
+/// True for the source files `extract` reads a doc comment from; callers
+/// skip decoding every other source file.
+pub fn is_package_entry(rel_path: &str) -> bool {
+    entry_doc(rel_path).is_some()
+}
+
+/// The doc-comment reader for a package entry file.
+fn entry_doc(rel_path: &str) -> Option<fn(&str) -> Option<String>> {
+    let file_name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    let (stem, ext) = file_name.rsplit_once('.')?;
+    match ext {
+        "rs" if matches!(stem, "lib" | "main") => Some(rust_inner_doc),
+        "go" if !stem.ends_with("_test") => Some(go_package_doc),
+        "py" if stem == "__init__" => Some(python_docstring),
+        "ts" | "tsx" | "js" | "jsx" | "mjs" if stem == "index" => Some(js_file_doc),
+        _ => None,
+    }
+}
+
+/// `//!` lines (or a `/*! */` block) at the top of the file.
+fn rust_inner_doc(text: &str) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut rest = text.lines().map(str::trim).skip_while(|l| l.is_empty());
+    let first = rest.next()?;
+    if let Some(block) = first.strip_prefix("/*!") {
+        let body = std::iter::once(block).chain(rest).take_while(|l| !l.starts_with("*/"));
+        for line in body {
+            let line = line.split("*/").next().unwrap_or(line);
+            lines.push(line.trim_start_matches('*').trim());
+        }
+        return first_paragraph(lines);
+    }
+    for line in std::iter::once(first).chain(rest) {
+        match line.strip_prefix("//!") {
+            Some(doc) => lines.push(doc.trim()),
+            None => break,
+        }
+    }
+    first_paragraph(lines)
+}
+
+/// The comment directly above `package`, when it starts with "Package".
+fn go_package_doc(text: &str) -> Option<String> {
+    let mut comment: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("package ") {
+            break;
+        }
+        match line.strip_prefix("//") {
+            Some(doc) if !doc.starts_with("go:") && !doc.starts_with(" +build") => comment.push(doc.trim()),
+            _ => comment.clear(),
+        }
+    }
+    if !comment.first()?.starts_with("Package ") {
+        return None;
+    }
+    first_paragraph(comment)
+}
+
+/// The module docstring: the first statement, when it is a string literal.
+fn python_docstring(text: &str) -> Option<String> {
+    let body = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| l.is_empty() || l.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let body = body.trim_start_matches(['r', 'R', 'u', 'U']);
+    let quote = ["\"\"\"", "'''"].into_iter().find(|q| body.starts_with(q))?;
+    let inner = &body[quote.len()..];
+    let end = inner.find(quote)?;
+    first_paragraph(inner[..end].lines().map(str::trim).collect())
+}
+
+/// A leading `/** */` block tagged as file or package documentation.
+fn js_file_doc(text: &str) -> Option<String> {
+    let start = text.trim_start();
+    let block = start.strip_prefix("/**")?;
+    let block = &block[..block.find("*/")?];
+    let tagged = ["@packageDocumentation", "@module", "@fileoverview", "@file"]
+        .iter()
+        .any(|tag| block.contains(tag));
+    if !tagged {
+        return None;
+    }
+    let lines = block
+        .lines()
+        .map(|l| l.trim().trim_start_matches('*').trim())
+        .filter(|l| !l.starts_with('@'))
+        .collect();
+    first_paragraph(lines)
+}
+
+/// Lines up to the first blank line after some text.
+fn first_paragraph(lines: Vec<&str>) -> Option<String> {
+    let lines = lines
+        .into_iter()
+        .skip_while(|l| l.is_empty())
+        .take_while(|l| !l.is_empty())
+        .collect();
+    paragraph(lines)
+}
+
+fn paragraph(lines: Vec<&str>) -> Option<String> {
+    let text = lines.join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= MAX_DOC_CHARS {
+        return Some(text);
+    }
+    let cut: String = text.chars().take(MAX_DOC_CHARS).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+/// One doc per package directory (plus the root, named `repo_name`): the
+/// README in that directory if there is one, else the doc comment of the
+/// shallowest entry file belonging to the package.
+pub fn package_docs(repo_name: &str, packages: &[Package], docs: &[ModuleDoc]) -> Vec<PackageDoc> {
+    let mut chosen: std::collections::BTreeMap<String, (&str, &ModuleDoc)> = std::collections::BTreeMap::new();
+    for doc in docs {
+        let (name, dir) = match package_for_path(packages, &doc.path) {
+            Some(p) => (p.name.as_str(), p.path.clone()),
+            None => (repo_name, String::new()),
+        };
+        // A README below the package directory describes that directory only.
+        if doc.kind == "readme" && doc.path.rsplit_once('/').map_or("", |(d, _)| d) != dir {
+            continue;
+        }
+        let rank = |d: &ModuleDoc| (d.kind != "readme", d.path.matches('/').count());
+        match chosen.get(&dir) {
+            Some((_, current)) if rank(current) <= rank(doc) => {}
+            _ => {
+                chosen.insert(dir, (name, doc));
+            }
+        }
+    }
+    chosen
+        .into_iter()
+        .map(|(dir, (package, doc))| PackageDoc {
+            package: package.to_string(),
+            dir,
+            source: doc.path.clone(),
+            doc: doc.doc.clone(),
+        })
+        .collect()
+}
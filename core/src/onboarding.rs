@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::api::{public_api, ApiSymbol};
+use crate::db::{Database, Hotspot, Memory, PackageStats, RepoOverview, Repository};
+use crate::explain::{estimate_tokens, SectionBudget};
+
+// ---------------------------------------------------------------------------
+// Onboarding capsule
+// ---------------------------------------------------------------------------
+//
+// The first call in a fresh session on an unfamiliar repository: what it is,
+// where execution starts, how it is split up, which symbols everything leans
+// on, and what the team pinned as must-know. Sections are filled in that
+// order until the token budget runs out.
+
+/// Hotspots and key APIs considered before the budget is applied.
+const MAX_CANDIDATES: usize = 30;
+
+/// Where execution enters the code: a `main` function or an HTTP route.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryPoint {
+    /// "main" or "route".
+    pub kind: String,
+    /// Function name, or `METHOD /path -> handler` for routes.
+    pub name: String,
+    pub file_path: String,
+    pub line: i64,
+}
+
+/// A public symbol and how many symbols depend on it.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyApi {
+    #[serde(flatten)]
+    pub symbol: ApiSymbol,
+    pub dependents: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OnboardingCapsule {
+    pub overview: RepoOverview,
+    pub pinned_memories: Vec<Memory>,
+    pub entry_points: Vec<EntryPoint>,
+    pub packages: Vec<PackageStats>,
+    pub hotspots: Vec<Hotspot>,
+    /// Public symbols with the most dependents; docs cut to their first line.
+    pub key_apis: Vec<KeyApi>,
+    /// Entries dropped per section to stay within the budget.
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub omitted: std::collections::BTreeMap<&'static str, usize>,
+    pub total_tokens: usize,
+}
+
+/// Build the capsule for `repo` within `max_tokens`. The overview is always
+/// included.
+pub fn onboarding_capsule(db: &Database, repo: &Repository, max_tokens: usize) -> Result<OnboardingCapsule> {
+    let overview = db
+        .get_repo_overview(&repo.name)?
+        .into_iter()
+        .next()
+        .with_context(|| format!("repository '{}' not found", repo.name))?;
+
+    let mut entry_points: Vec<EntryPoint> = db
+        .query_symbols_full("main", "function", &repo.name, "", "")?
+        .into_iter()
+        .filter(|s| s.name == "main")
+        .map(|s| EntryPoint { kind: "main".to_string(), name: s.name, file_path: s.file_path, line: s.start_line })
+        .collect();
+    entry_points.extend(db.list_routes(Some(repo.id), "", "")?.into_iter().map(|r| EntryPoint {
+        kind: "route".to_string(),
+        name: format!("{} {} -> {}", r.method, r.path, r.handler),
+        file_path: r.file_path,
+        line: r.line,
+    }));
+
+    let dependents = db.dependent_counts(repo.id)?;
+    let mut key_apis: Vec<KeyApi> = public_api(db, repo, None, true, usize::MAX)?
+        .modules
+        .into_iter()
+        .flat_map(|m| m.symbols)
+        .map(|mut symbol| {
+            symbol.doc = symbol.doc.and_then(|d| d.lines().next().map(String::from));
+            KeyApi { dependents: dependents.get(&symbol.id).copied().unwrap_or(0), symbol }
+        })
+        .collect();
+    key_apis.sort_by(|a, b| {
+        b.dependents
+            .cmp(&a.dependents)
+            .then_with(|| (&a.symbol.file_path, a.symbol.line).cmp(&(&b.symbol.file_path, b.symbol.line)))
+    });
+    key_apis.truncate(MAX_CANDIDATES);
+
+    let mut capsule = OnboardingCapsule {
+        overview,
+        pinned_memories: Vec::new(),
+        entry_points: Vec::new(),
+        packages: Vec::new(),
+        hotspots: Vec::new(),
+        key_apis: Vec::new(),
+        omitted: Default::default(),
+        total_tokens: 0,
+    };
+    let mut sections = SectionBudget::new(estimate_tokens(&serde_json::to_string(&capsule)?), max_tokens);
+    capsule.pinned_memories = sections.fit("pinned_memories", db.list_pinned_memories()?)?;
+    capsule.entry_points = sections.fit("entry_points", entry_points)?;
+    capsule.packages = sections.fit("packages", db.list_package_stats(Some(repo.id))?)?;
+    capsule.hotspots = sections.fit("hotspots", db.get_hotspots(repo.id, MAX_CANDIDATES)?)?;
+    capsule.key_apis = sections.fit("key_apis", key_apis)?;
+    capsule.omitted = sections.omitted;
+    capsule.total_tokens = sections.used;
+    Ok(capsule)
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+// ---------------------------------------------------------------------------
+// CODEOWNERS parsing
+// ---------------------------------------------------------------------------
+
+/// Where GitHub looks for a CODEOWNERS file, in precedence order.
+pub const CODEOWNERS_LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// A parsed CODEOWNERS file with the mtime and size it was read at.
+type Parsed = ((SystemTime, u64), Arc<CodeOwners>);
+
+/// Parsed CODEOWNERS files by path.
+static LOADED: LazyLock<Mutex<HashMap<PathBuf, Parsed>>> = LazyLock::new(Default::default);
+
+/// One `pattern owner1 owner2 …` line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnerRule {
+    pub pattern: String,
+    pub owners: Vec<String>,
+}
+
+/// Parsed CODEOWNERS file. Rules keep file order; as on GitHub, the last
+/// matching rule wins.
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<OwnerRule>,
+}
+
+impl CodeOwners {
+    pub fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?.to_string();
+                // Inline comments end the owner list.
+                let owners = parts
+                    .take_while(|p| !p.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                Some(OwnerRule { pattern, owners })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Load the first CODEOWNERS file found under `root`, if any.
+    pub fn load(root: &Path) -> Option<Self> {
+        CODEOWNERS_LOCATIONS
+            .iter()
+            .find_map(|loc| std::fs::read_to_string(root.join(loc)).ok())
+            .map(|text| Self::parse(&text))
+    }
+
+    /// `load` for per-query lookups: the parsed file is kept until its mtime
+    /// or size changes, so a call costs a `stat` per location checked.
+    pub fn load_cached(root: &Path) -> Option<Arc<Self>> {
+        let (path, stamp) = CODEOWNERS_LOCATIONS.iter().find_map(|loc| {
+            let path = root.join(loc);
+            let meta = std::fs::metadata(&path).ok().filter(|m| m.is_file())?;
+            Some((path, (meta.modified().ok()?, meta.len())))
+        })?;
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, owners)) = loaded.get(&path).filter(|(at, _)| *at == stamp) {
+            return Some(Arc::clone(owners));
+        }
+        let owners = Arc::new(Self::parse(&std::fs::read_to_string(&path).ok()?));
+        loaded.insert(path, (stamp, Arc::clone(&owners)));
+        Some(owners)
+    }
+
+    /// The rule that decides ownership of `rel_path` (repo-relative, forward
+    /// slashes). A matching rule with no owners explicitly unassigns the path.
+    pub fn rule_for(&self, rel_path: &str) -> Option<&OwnerRule> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| pattern_matches(&rule.pattern, rel_path))
+    }
+
+    /// Owners of `rel_path`; empty when no rule matches.
+    pub fn owners_for(&self, rel_path: &str) -> Vec<String> {
+        self.rule_for(rel_path)
+            .map(|rule| rule.owners.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// gitignore-style matching as used by CODEOWNERS:
+/// - a leading `/` or any interior `/` anchors the pattern at the repo root;
+///   otherwise it matches at any depth
+/// - a pattern matching a directory covers everything beneath it, except
+///   when its last segment is a bare `*` (`docs/*` is one level only)
+/// - a trailing `/` matches directories only
+/// - `*` and `?` match within a segment, `**` matches any number of segments
+pub(crate) fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.starts_with('/') || trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return false;
+    }
+
+    let mut pat: Vec<&str> = trimmed.split('/').collect();
+    if !anchored {
+        pat.insert(0, "**");
+    }
+    let segs: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let one_level = pat.last() == Some(&"*");
+
+    (1..=segs.len()).any(|k| {
+        let is_file = k == segs.len();
+        if is_file && dir_only {
+            return false;
+        }
+        if !is_file && one_level {
+            return false;
+        }
+        match_segments(&pat, &segs[..k])
+    })
+}
+
+fn match_segments(pat: &[&str], segs: &[&str]) -> bool {
+    match pat.split_first() {
+        None => segs.is_empty(),
+        Some((&"**", rest)) => (0..=segs.len()).any(|i| match_segments(rest, &segs[i..])),
+        Some((p, rest)) => match segs.split_first() {
+            Some((s, seg_rest)) => glob_segment(p, s) && match_segments(rest, seg_rest),
+            None => false,
+        },
+    }
+}
+
+/// Match a single path segment against a pattern with `*` and `?`.
+fn glob_segment(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+// ---------------------------------------------------------------------------
+// Git contributors
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Contributor {
+    pub name: String,
+    pub email: String,
+    pub commits: u32,
+}
+
+/// Top contributors to `rel_path` by commit count, via `git shortlog`.
+/// Requires git in PATH and `root` to be a git checkout.
+pub fn git_contributors(root: &Path, rel_path: &str, limit: usize) -> Result<Vec<Contributor>, String> {
+    let output = std::process::Command::new("git")
+        .args(["shortlog", "-sne", "HEAD", "--", rel_path])
+        .current_dir(root)
+        .output()
+        .map_err(|e| format!("git error: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git shortlog failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // Lines look like "    12\tJane Doe <jane@example.com>"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let contributors = stdout
+        .lines()
+        .filter_map(|line| {
+            let (count, who) = line.trim().split_once('\t')?;
+            let commits = count.trim().parse().ok()?;
+            let (name, email) = match who.rsplit_once(" <") {
+                Some((name, email)) => (name.trim(), email.trim_end_matches('>')),
+                None => (who.trim(), ""),
+            };
+            Some(Contributor {
+                name: name.to_string(),
+                email: email.to_string(),
+                commits,
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(contributors)
+}
@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+// ---------------------------------------------------------------------------
+// Package boundaries
+// ---------------------------------------------------------------------------
+//
+// A package is a directory with its own manifest: a Cargo crate, an npm
+// package, a Go module or a Python project. Workspace roots (a Cargo.toml
+// with only `[workspace]`, a package.json without a name) are not packages
+// themselves; their members are found by the same walk. Every indexed file
+// belongs to the innermost package directory that contains it, if any.
+
+/// Manifest file names recognized as package roots.
+pub const MANIFEST_FILES: &[&str] = &["Cargo.toml", "package.json", "go.mod", "pyproject.toml"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Package {
+    pub name: String,
+    /// Repo-relative directory of the manifest; empty for the repository root.
+    pub path: String,
+    /// "cargo", "npm", "go" or "python".
+    pub kind: String,
+}
+
+/// True when `rel_path` names a manifest `parse_manifest` understands.
+pub fn is_manifest(rel_path: &str) -> bool {
+    let file_name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    MANIFEST_FILES.contains(&file_name)
+}
+
+/// Read the package declared by the manifest at repo-relative `rel_path`.
+/// None for workspace-only manifests, unnamed packages and unparsable files.
+pub fn parse_manifest(rel_path: &str, text: &str) -> Option<Package> {
+    let (dir, file_name) = match rel_path.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", rel_path),
+    };
+    let (name, kind) = match file_name {
+        "Cargo.toml" => {
+            let doc: toml::Table = toml::from_str(text).ok()?;
+            (doc.get("package")?.get("name")?.as_str()?.to_string(), "cargo")
+        }
+        "pyproject.toml" => {
+            let doc: toml::Table = toml::from_str(text).ok()?;
+            let name = doc
+                .get("project")
+                .and_then(|p| p.get("name"))
+                .or_else(|| doc.get("tool")?.get("poetry")?.get("name"))?;
+            (name.as_str()?.to_string(), "python")
+        }
+        "package.json" => {
+            let doc: serde_json::Value = serde_json::from_str(text).ok()?;
+            (doc.get("name")?.as_str()?.to_string(), "npm")
+        }
+        "go.mod" => {
+            let module = text
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("module "))?
+                .trim()
+                .trim_matches('"');
+            (module.to_string(), "go")
+        }
+        _ => return None,
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(Package { name, path: dir.to_string(), kind: kind.to_string() })
+}
+
+/// The innermost package whose directory contains repo-relative `rel_path`.
+pub fn package_for_path<'a>(packages: &'a [Package], rel_path: &str) -> Option<&'a Package> {
+    packages
+        .iter()
+        .filter(|p| p.path.is_empty() || rel_path.starts_with(&format!("{}/", p.path)))
+        .max_by_key(|p| p.path.len())
+}
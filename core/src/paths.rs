@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+// ---------------------------------------------------------------------------
+// Path normalization
+// ---------------------------------------------------------------------------
+//
+// Every file path Focal stores or returns is repo-relative with forward
+// slashes (`src/db.rs`), regardless of platform. Tools accept whatever the
+// client sends — absolute paths, `./`-prefixed paths, Windows separators —
+// and funnel it through these helpers before touching the database.
+//
+// Windows specifics: `std::fs::canonicalize` returns verbatim paths
+// (`\\?\C:\repo`) while notify and clients report plain ones (`C:\repo`),
+// and drive letters may differ in case. Comparisons therefore go through
+// `strip_root`/`is_under`, which normalize both sides first.
+
+/// Normalize separators and dot segments: backslashes become `/`, `.` and
+/// empty segments are dropped, and trailing slashes are removed. A leading
+/// `/` (Unix absolute) is preserved. `..` segments are kept as-is. A Windows
+/// verbatim prefix (`\\?\C:\…`) is removed.
+pub fn normalize_path(path: &str) -> String {
+    let path = path.trim().replace('\\', "/");
+    let path = match path.strip_prefix("//?/") {
+        Some(rest) if has_drive_letter(rest) => rest.to_string(),
+        _ => path,
+    };
+    let absolute = path.starts_with('/');
+    let joined = path
+        .split('/')
+        .filter(|seg| !seg.is_empty() && *seg != ".")
+        .collect::<Vec<_>>()
+        .join("/");
+    if absolute {
+        format!("/{joined}")
+    } else {
+        joined
+    }
+}
+
+/// True for normalized absolute paths: `/…` or a Windows drive (`C:/…`).
+pub fn is_absolute(normalized: &str) -> bool {
+    normalized.starts_with('/') || has_drive_letter(normalized)
+}
+
+/// Strip `root` from an absolute `path` (both in any form accepted by
+/// `normalize_path`). Returns the repo-relative remainder, or None when
+/// `path` is not under `root`. Drive-letter paths compare case-insensitively.
+pub fn strip_root(path: &str, root: &str) -> Option<String> {
+    let path = normalize_path(path);
+    let root = normalize_path(root);
+    if root.is_empty() || path.len() < root.len() {
+        return None;
+    }
+    let (head, rest) = path.split_at(root.len());
+    let same_root = if has_drive_letter(&root) {
+        head.eq_ignore_ascii_case(&root)
+    } else {
+        head == root
+    };
+    if !same_root {
+        return None;
+    }
+    if rest.is_empty() {
+        Some(String::new())
+    } else {
+        rest.strip_prefix('/').map(str::to_string)
+    }
+}
+
+/// True if `path` is `root` or lies beneath it. Falls back to normalized
+/// string comparison so verbatim vs plain paths and drive-letter case
+/// differences on Windows still match.
+pub fn is_under(path: &Path, root: &Path) -> bool {
+    path.starts_with(root)
+        || strip_root(&path.to_string_lossy(), &root.to_string_lossy()).is_some()
+}
+
+/// Canonical repo-relative path for a file discovered under `root`.
+pub fn repo_relative(path: &Path, root: &Path) -> String {
+    if let Ok(rel) = path.strip_prefix(root) {
+        return normalize_path(&rel.to_string_lossy());
+    }
+    strip_root(&path.to_string_lossy(), &root.to_string_lossy())
+        .unwrap_or_else(|| normalize_path(&path.to_string_lossy()))
+}
+
+/// `std::fs::canonicalize` without the Windows verbatim prefix, so stored
+/// repository roots match the plain paths reported by the file watcher and
+/// typed by users. std re-applies the prefix internally for long paths
+/// (> 260 chars) on every file operation, so dropping it here is safe.
+pub fn canonicalize(path: &Path) -> std::io::Result<PathBuf> {
+    path.canonicalize().map(simplify_verbatim)
+}
+
+/// The form a repository root is stored in: the canonical path when it
+/// exists (resolving symlinked homes and macOS's `/private`), else the
+/// normalized string (no trailing slash). Pseudo-roots with a scheme
+/// (`manifest://…`, `external://`) are returned unchanged.
+pub fn normalize_root(root: &str) -> String {
+    if root.contains("://") {
+        return root.to_string();
+    }
+    match canonicalize(Path::new(root)) {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(_) => normalize_path(root),
+    }
+}
+
+/// Strip a `\\?\` prefix from drive-letter paths. UNC and other verbatim
+/// forms are returned unchanged.
+pub fn simplify_verbatim(path: PathBuf) -> PathBuf {
+    let simplified = path
+        .to_str()
+        .and_then(|s| s.strip_prefix(r"\\?\"))
+        .filter(|rest| has_drive_letter(rest))
+        .map(PathBuf::from);
+    simplified.unwrap_or(path)
+}
+
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
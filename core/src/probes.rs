@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
+
+use crate::db::Database;
+
+// ---------------------------------------------------------------------------
+// Liveness and readiness probes
+// ---------------------------------------------------------------------------
+//
+// `focal serve --http` answers `GET /healthz` while the process is up and
+// `GET /readyz` once it can serve useful answers: the startup index is done
+// and the database accepts writes. Watcher reindexing only makes the host
+// unready when `[http] unready_during_reindex` is set, since tools keep
+// working (against slightly older data) while it runs.
+
+#[derive(Debug)]
+pub struct Probes {
+    indexing_complete: Arc<AtomicBool>,
+    reindexing: AtomicBool,
+    unready_during_reindex: bool,
+}
+
+/// Marks a reindex in progress until dropped.
+pub struct ReindexGuard<'a>(&'a AtomicBool);
+
+impl Drop for ReindexGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Probes {
+    pub fn new(indexing_complete: Arc<AtomicBool>) -> Self {
+        Self { indexing_complete, reindexing: AtomicBool::new(false), unready_during_reindex: false }
+    }
+
+    /// Report unready while a reindex runs (`[http] unready_during_reindex`).
+    pub fn with_unready_during_reindex(mut self, enabled: bool) -> Self {
+        self.unready_during_reindex = enabled;
+        self
+    }
+
+    /// Mark a reindex as running for the lifetime of the returned guard.
+    pub fn begin_reindex(&self) -> ReindexGuard<'_> {
+        self.reindexing.store(true, Ordering::Relaxed);
+        ReindexGuard(&self.reindexing)
+    }
+
+    /// `GET /healthz`: `(status, JSON body)`. Answering at all is the signal.
+    pub fn liveness(&self) -> (u16, String) {
+        (200, serde_json::json!({ "status": "ok" }).to_string())
+    }
+
+    /// `GET /readyz`: 200 when ready, 503 with the reasons otherwise. A
+    /// database busy with another writer counts as writable but contended.
+    /// Neither the mutex nor SQLite's write lock is waited for, so probes
+    /// never queue behind a write or hold up the tool calls behind them.
+    pub fn readiness(&self, db: &Mutex<Database>) -> (u16, String) {
+        let indexing_complete = self.indexing_complete.load(Ordering::Relaxed);
+        let reindexing = self.reindexing.load(Ordering::Relaxed);
+        let db_writable = match db.try_lock() {
+            Ok(db) => db.check_writable().map_err(|e| format!("{e:#}")),
+            Err(TryLockError::WouldBlock) => Ok(true),
+            Err(TryLockError::Poisoned(_)) => Err("database lock poisoned".to_string()),
+        };
+
+        let mut reasons = Vec::new();
+        if !indexing_complete {
+            reasons.push("initial index in progress".to_string());
+        }
+        if reindexing && self.unready_during_reindex {
+            reasons.push("reindex in progress".to_string());
+        }
+        if let Err(e) = &db_writable {
+            reasons.push(format!("database not writable: {e}"));
+        }
+        let ready = reasons.is_empty();
+        let body = serde_json::json!({
+            "ready": ready,
+            "indexing_complete": indexing_complete,
+            "reindexing": reindexing,
+            "db_writable": db_writable.is_ok(),
+            "db_contended": db_writable.as_ref().is_ok_and(|contended| *contended),
+            "reasons": reasons,
+        });
+        (if ready { 200 } else { 503 }, body.to_string())
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+
+use crate::db::{Database, Symbol};
+
+// ---------------------------------------------------------------------------
+// Focus proximity
+// ---------------------------------------------------------------------------
+//
+// Relevant code is usually close to where the agent is already working. A
+// focus — a symbol or a file — boosts search results that are a few graph
+// hops away from it (edges followed in both directions) or live in the same
+// file or directory. The session's working set is a focus too, built from
+// many symbols and files and weighted lower than an explicit `near`.
+
+/// Graph hops explored around the focus.
+const MAX_HOPS: usize = 2;
+/// Cap on symbols visited by the hop search, so a hub focus stays cheap.
+const MAX_VISITED: usize = 2_000;
+/// Rank score lost per result position; a boost of 0.5 is worth 10 places.
+const RANK_STEP: f64 = 0.05;
+
+/// A resolved `near` parameter or working set.
+#[derive(Debug, Clone)]
+pub struct Focus {
+    /// (repo id, repo-relative path) of the focus files.
+    files: HashSet<(i64, String)>,
+    /// Hop distance from the focus symbols, up to `MAX_HOPS`.
+    hops: HashMap<i64, usize>,
+    /// Multiplier applied to `boost`.
+    weight: f64,
+}
+
+impl Focus {
+    /// Resolve `near` as a file path first, then as a symbol name. None when
+    /// it matches neither.
+    pub fn resolve(db: &Database, near: &str, repo_name: Option<&str>) -> Result<Option<Self>> {
+        let (repo_id, file_path, roots) = if let Some(file) = db.resolve_file_path(near, repo_name)? {
+            let Some(record) = db.get_file_by_id(file.file_id)? else {
+                return Ok(None);
+            };
+            let roots = db.get_symbols_by_file(file.file_id)?.iter().map(|s| s.id).collect();
+            (record.repo_id, record.path, roots)
+        } else {
+            let repo_id = match repo_name {
+                Some(name) => db.get_repo_id_by_name(name)?,
+                None => None,
+            };
+            let sym = match repo_id {
+                Some(rid) => db.find_symbol_by_name(rid, near)?,
+                None => db.find_symbol_by_name_any(near)?,
+            };
+            let Some(sym) = sym else {
+                return Ok(None);
+            };
+            let Some(record) = db.get_file_by_id(sym.file_id)? else {
+                return Ok(None);
+            };
+            (record.repo_id, record.path, vec![sym.id])
+        };
+        Ok(Some(Self {
+            files: HashSet::from([(repo_id, file_path)]),
+            hops: hop_distances(db, &roots)?,
+            weight: 1.0,
+        }))
+    }
+
+    /// Focus on several symbols and files at once (a working set). The files
+    /// of `symbol_ids` count as focus files too. None when both are empty.
+    pub fn from_ids(db: &Database, symbol_ids: &[i64], file_ids: &[i64]) -> Result<Option<Self>> {
+        if symbol_ids.is_empty() && file_ids.is_empty() {
+            return Ok(None);
+        }
+        let mut file_set: Vec<i64> = file_ids.to_vec();
+        file_set.extend(db.file_ids_for_symbols(symbol_ids)?);
+        let files = db.file_locations(&file_set)?.into_values().collect();
+        Ok(Some(Self { files, hops: hop_distances(db, symbol_ids)?, weight: 1.0 }))
+    }
+
+    /// Scale every boost by `weight`.
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Boost in 0–1 (times the weight) for a symbol in `file_path` of
+    /// `repo_id`: the closer of its graph distance (0 hops 1.0, 1 hop 0.75,
+    /// 2 hops 0.5) and its path distance to the nearest focus file (same file
+    /// 0.75, same directory 0.5, parent directory 0.25).
+    pub fn boost(&self, symbol_id: i64, repo_id: i64, file_path: &str) -> f64 {
+        let graph = match self.hops.get(&symbol_id) {
+            Some(0) => 1.0,
+            Some(1) => 0.75,
+            Some(_) => 0.5,
+            None => 0.0,
+        };
+        let dir = parent_dir(file_path);
+        let path = self
+            .files
+            .iter()
+            .filter(|(repo, _)| *repo == repo_id)
+            .map(|(_, focus)| {
+                if file_path == focus {
+                    0.75
+                } else if dir == parent_dir(focus) {
+                    0.5
+                } else if dir == parent_dir(parent_dir(focus)) {
+                    0.25
+                } else {
+                    0.0
+                }
+            })
+            .fold(0.0, f64::max);
+        f64::max(graph, path) * self.weight
+    }
+
+    /// Re-order `symbols` (best first) by their original rank plus the focus
+    /// boost. Rank contributes 1.0 for the first result, `RANK_STEP` less for
+    /// each position after it; ties keep the original order.
+    pub fn rerank(&self, db: &Database, symbols: Vec<Symbol>) -> Result<Vec<Symbol>> {
+        Self::rerank_all(db, &[self], symbols)
+    }
+
+    /// `rerank` with the boosts of several foci summed.
+    pub fn rerank_all(db: &Database, foci: &[&Focus], symbols: Vec<Symbol>) -> Result<Vec<Symbol>> {
+        Ok(Self::score_all(db, foci, symbols)?.into_iter().map(|(_, sym)| sym).collect())
+    }
+
+    /// `rerank_all` keeping each symbol's score: rank score plus summed
+    /// boosts, best first. With no foci the input order is kept.
+    pub fn score_all(db: &Database, foci: &[&Focus], symbols: Vec<Symbol>) -> Result<Vec<(f64, Symbol)>> {
+        let files = if foci.is_empty() {
+            HashMap::new()
+        } else {
+            db.file_locations(&symbols.iter().map(|s| s.file_id).collect::<Vec<_>>())?
+        };
+        let mut scored: Vec<(f64, Symbol)> = symbols
+            .into_iter()
+            .enumerate()
+            .map(|(rank, sym)| {
+                let boost = match files.get(&sym.file_id) {
+                    Some((repo_id, path)) => foci.iter().map(|f| f.boost(sym.id, *repo_id, path)).sum(),
+                    None => 0.0,
+                };
+                ((1.0 - rank as f64 * RANK_STEP).max(0.0) + boost, sym)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored)
+    }
+}
+
+/// Hop distance of every symbol within `MAX_HOPS` of `roots`, following
+/// edges in both directions over the in-memory graph (ids only).
+fn hop_distances(db: &Database, roots: &[i64]) -> Result<HashMap<i64, usize>> {
+    let graph = db.graph()?;
+    let mut hops: HashMap<i64, usize> = roots.iter().map(|&id| (id, 0)).collect();
+    let mut queue: VecDeque<(i64, usize)> = roots.iter().map(|&id| (id, 0)).collect();
+    while let Some((id, depth)) = queue.pop_front() {
+        if depth >= MAX_HOPS || hops.len() >= MAX_VISITED {
+            continue;
+        }
+        let neighbors = graph.incoming(id).map(|e| e.source_id).chain(graph.outgoing(id).map(|e| e.target_id));
+        for neighbor in neighbors {
+            if let std::collections::hash_map::Entry::Vacant(entry) = hops.entry(neighbor) {
+                entry.insert(depth + 1);
+                queue.push_back((neighbor, depth + 1));
+            }
+        }
+    }
+    Ok(hops)
+}
+
+/// `a/b/c.rs` → `a/b`; a top-level file → "".
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
@@ -0,0 +1,187 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::db::DataVersion;
+
+// ---------------------------------------------------------------------------
+// Query result cache
+// ---------------------------------------------------------------------------
+//
+// Agents repeat themselves: the same query_symbol or get_dependencies call
+// often comes back several times in one session. Results are cached by
+// (tool, parameters) and tagged with the index's `DataVersion`; any write to
+// the index changes the version and drops every entry, so a hit is always
+// what SQLite would have returned.
+//
+// Besides the entry cap, the cache holds to a byte budget (`[memory]
+// query_cache_mb`). An entry's size is the length of its JSON encoding plus
+// its key: close to what the result occupies, and cheap next to computing it.
+
+/// Entries kept before the least recently used is evicted.
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// Default byte budget per cache (one per session).
+pub const DEFAULT_BUDGET_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    /// Estimated size of the cached results.
+    pub bytes: usize,
+    pub budget_bytes: usize,
+    /// Entries dropped to stay within the entry cap or the byte budget.
+    pub evictions: u64,
+}
+
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    last_used: u64,
+    bytes: usize,
+}
+
+/// LRU cache of tool results, valid for one `DataVersion` at a time.
+pub struct QueryCache {
+    capacity: usize,
+    budget_bytes: usize,
+    version: Option<DataVersion>,
+    entries: HashMap<(&'static str, String), Entry>,
+    bytes: usize,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+/// Byte count of whatever is written to it.
+struct CountingWriter(usize);
+
+impl std::io::Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Length of `value`'s JSON encoding, without building it.
+pub fn approx_size<T: Serialize>(value: &T) -> usize {
+    let mut counter = CountingWriter(0);
+    match serde_json::to_writer(&mut counter, value) {
+        Ok(()) => counter.0,
+        Err(_) => 0,
+    }
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            budget_bytes: DEFAULT_BUDGET_BYTES,
+            version: None,
+            entries: HashMap::new(),
+            bytes: 0,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Keep the cached results within `bytes`.
+    pub fn with_budget(mut self, bytes: usize) -> Self {
+        self.budget_bytes = bytes;
+        self
+    }
+
+    /// The cached result of `tool` with `params`, if computed at `version`.
+    pub fn get<T: Clone + 'static>(&mut self, tool: &'static str, params: &str, version: DataVersion) -> Option<T> {
+        self.sync_version(version);
+        self.tick += 1;
+        let hit = self
+            .entries
+            .get_mut(&(tool, params.to_string()))
+            .and_then(|entry| {
+                entry.last_used = self.tick;
+                entry.value.downcast_ref::<T>().cloned()
+            });
+        match hit {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        hit
+    }
+
+    /// Store `value` as the result of `tool` with `params` at `version`,
+    /// evicting the least recently used entries to make room. A value larger
+    /// than the whole budget isn't stored.
+    pub fn insert<T: Serialize + Send + Sync + 'static>(
+        &mut self,
+        tool: &'static str,
+        params: String,
+        version: DataVersion,
+        value: T,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.sync_version(version);
+        let key = (tool, params);
+        if let Some(old) = self.entries.remove(&key) {
+            self.bytes -= old.bytes;
+        }
+        let bytes = key.0.len() + key.1.len() + approx_size(&value);
+        if bytes > self.budget_bytes {
+            return;
+        }
+        while !self.entries.is_empty()
+            && (self.entries.len() >= self.capacity || self.bytes + bytes > self.budget_bytes)
+        {
+            self.evict_oldest();
+        }
+        self.tick += 1;
+        self.bytes += bytes;
+        self.entries.insert(key, Entry { value: Arc::new(value), last_used: self.tick, bytes });
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            budget_bytes: self.budget_bytes,
+            evictions: self.evictions,
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone());
+        if let Some(entry) = oldest.and_then(|key| self.entries.remove(&key)) {
+            self.bytes -= entry.bytes;
+            self.evictions += 1;
+        }
+    }
+
+    /// Drop every entry when the index has changed since they were stored.
+    fn sync_version(&mut self, version: DataVersion) {
+        if self.version != Some(version) {
+            self.entries.clear();
+            self.bytes = 0;
+            self.version = Some(version);
+        }
+    }
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
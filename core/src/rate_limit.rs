@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "server")]
+use std::sync::Arc;
+
+#[cfg(feature = "server")]
+use axum::extract::{Request, State};
+#[cfg(feature = "server")]
+use axum::http::{header, HeaderValue, StatusCode};
+#[cfg(feature = "server")]
+use axum::middleware::Next;
+#[cfg(feature = "server")]
+use axum::response::{IntoResponse, Response};
+
+// ---------------------------------------------------------------------------
+// HTTP rate limiting
+// ---------------------------------------------------------------------------
+//
+// Several agents can share one `focal serve --http` host. Each MCP session
+// (the `Mcp-Session-Id` header) gets a token bucket of requests, enforced by
+// `enforce` as an axum middleware layer in front of `/mcp`, so a runaway
+// agent gets 429s instead of crowding out the others. Concurrent tool calls
+// are capped separately, per session and across the server, in
+// `FocalServer::call_tool`.
+
+/// Default sustained requests per minute per session.
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 600;
+/// Default requests a session may send at once before the rate applies.
+pub const DEFAULT_BURST: u32 = 60;
+/// Default tool calls running at once across all sessions.
+pub const DEFAULT_MAX_CONCURRENT_TOOLS: usize = 16;
+/// Default tool calls running at once within one session.
+pub const DEFAULT_MAX_CONCURRENT_TOOLS_PER_SESSION: usize = 4;
+
+/// Header carrying the MCP session id on every request after `initialize`.
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Buckets idle this long are dropped; a returning session starts full.
+const IDLE_EVICTION: Duration = Duration::from_secs(600);
+
+/// Default cap on tracked sessions. Session ids come from the client, so a
+/// client minting a new id per request would otherwise grow the map until
+/// idle eviction catches up.
+pub const DEFAULT_MAX_TRACKED_SESSIONS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Per-key token buckets: each key holds up to `burst` requests and refills
+/// at `per_minute`. Requests without a session id share one bucket.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_minute: u32,
+    burst: u32,
+    max_sessions: usize,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// `per_minute = 0` disables limiting. `burst` is at least 1.
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        Self {
+            per_minute,
+            burst: burst.max(1),
+            max_sessions: DEFAULT_MAX_TRACKED_SESSIONS,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cap on tracked sessions (at least 1). When a new session arrives at the
+    /// cap, idle buckets are dropped first, then the least recently used one.
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.per_minute > 0
+    }
+
+    /// Take one request from `key`'s bucket at `now`. When it is empty,
+    /// returns how long until a request would be allowed.
+    pub fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let rate = f64::from(self.per_minute) / 60.0;
+        let burst = f64::from(self.burst);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if !buckets.contains_key(key) {
+            buckets.retain(|_, b| now.saturating_duration_since(b.updated) < IDLE_EVICTION);
+            while buckets.len() >= self.max_sessions {
+                let Some(oldest) = buckets.iter().min_by_key(|(_, b)| b.updated).map(|(k, _)| k.clone()) else {
+                    break;
+                };
+                buckets.remove(&oldest);
+            }
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket { tokens: burst, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+    }
+
+    /// Sessions currently tracked.
+    pub fn tracked_sessions(&self) -> usize {
+        self.buckets.lock().map(|b| b.len()).unwrap_or(0)
+    }
+}
+
+/// Middleware: reject requests over their session's rate with
+/// `429 Too Many Requests` and a `Retry-After` header (whole seconds).
+#[cfg(feature = "server")]
+pub async fn enforce(State(limiter): State<Arc<RateLimiter>>, request: Request, next: Next) -> Response {
+    let session = request
+        .headers()
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    match limiter.check(&session, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            tracing::warn!(session = %session, retry_after, "rate limit exceeded");
+            let body = serde_json::json!({ "error": "rate limit exceeded", "retry_after_secs": retry_after });
+            let mut response = (StatusCode::TOO_MANY_REQUESTS, body.to_string()).into_response();
+            let headers = response.headers_mut();
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            response
+        }
+    }
+}
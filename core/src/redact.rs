@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::grammar::ExtractedSymbol;
+
+// ---------------------------------------------------------------------------
+// Secret scrubbing
+// ---------------------------------------------------------------------------
+//
+// Symbol bodies and signatures are served verbatim to LLM clients, so a key
+// hard-coded in a function would be echoed into every capsule containing it.
+// The indexer masks recognisable credentials before anything is stored: the
+// symbols table, FTS and snapshots only ever hold the masked text.
+
+/// Secret shapes, as (kind, pattern). When the pattern has a capture group
+/// only the group is masked, so `api_key = "..."` keeps its name.
+const PATTERNS: &[(&str, &str)] = &[
+    (
+        "private_key",
+        r"-----BEGIN [A-Z0-9 ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z0-9 ]*PRIVATE KEY-----",
+    ),
+    ("aws_access_key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    (
+        "aws_secret_key",
+        r#"(?i)aws_?secret_?(?:access_?)?key["']?\s*(?::=|[:=])\s*["']?([A-Za-z0-9/+=]{40})\b"#,
+    ),
+    ("github_token", r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{40,})\b"),
+    ("slack_token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}"),
+    ("stripe_key", r"\b[rs]k_(?:live|test)_[A-Za-z0-9]{16,}\b"),
+    ("google_api_key", r"\bAIza[0-9A-Za-z_-]{35}\b"),
+    ("api_key", r"\bsk-(?:proj-|ant-)?[A-Za-z0-9_-]{32,}"),
+    (
+        "secret",
+        r#"(?i)(?:api_?key|secret|token|passw(?:or)?d)["']?\s*(?::=|[:=])\s*["']([^"'\s]{8,})["']"#,
+    ),
+];
+
+static COMPILED: LazyLock<Vec<(&'static str, Regex)>> = LazyLock::new(|| {
+    PATTERNS
+        .iter()
+        .map(|(kind, pattern)| (*kind, Regex::new(pattern).expect("invalid redaction pattern")))
+        .collect()
+});
+
+/// Replace every secret in `text` with `[REDACTED:<kind>]`. Returns the text
+/// (borrowed when nothing matched) and the number of secrets masked.
+pub fn redact(text: &str) -> (Cow<'_, str>, usize) {
+    let mut out = Cow::Borrowed(text);
+    let mut count = 0;
+    for (kind, re) in COMPILED.iter() {
+        if !re.is_match(&out) {
+            continue;
+        }
+        let mut masked = String::with_capacity(out.len());
+        let mut last = 0;
+        for caps in re.captures_iter(&out) {
+            let m = caps.get(1).or_else(|| caps.get(0)).expect("match has group 0");
+            masked.push_str(&out[last..m.start()]);
+            masked.push_str(&format!("[REDACTED:{kind}]"));
+            last = m.end();
+            count += 1;
+        }
+        masked.push_str(&out[last..]);
+        out = Cow::Owned(masked);
+    }
+    (out, count)
+}
+
+/// Mask secrets in the bodies and signatures of `symbols` and their
+/// children, in place. A parent's body contains its children's, so counts
+/// per file come from `count_secrets` on the source instead.
+pub fn redact_symbols(symbols: &mut [ExtractedSymbol]) {
+    for sym in symbols {
+        for text in [&mut sym.body, &mut sym.signature] {
+            let masked = match redact(text) {
+                (Cow::Owned(masked), _) => masked,
+                (Cow::Borrowed(_), _) => continue,
+            };
+            *text = masked;
+        }
+        redact_symbols(&mut sym.children);
+    }
+}
+
+/// Number of secrets `redact` would mask in `text`.
+pub fn count_secrets(text: &str) -> usize {
+    redact(text).1
+}
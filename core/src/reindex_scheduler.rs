@@ -0,0 +1,588 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::builtins::BuiltinFilter;
+use crate::db::Database;
+use crate::deny::DenyList;
+use crate::grammar::GrammarRegistry;
+use crate::guardrails::Guardrails;
+use crate::indexer::{
+    FileParser, IndexStats, Indexer, ParsedFile, RescanPlan, DEFAULT_MAX_FILE_SIZE, DEFAULT_MAX_PARTIAL_FILE_SIZE,
+};
+
+// ---------------------------------------------------------------------------
+// Reindex scheduling
+// ---------------------------------------------------------------------------
+//
+// The file watcher hands over batches of changed paths; the scheduler decides
+// what to do with them and in which order. Paths are queued once no matter
+// how often they change, files an agent has recently read go first, and the
+// queue is bounded: when a burst (a checkout, a code generator) overflows it,
+// the affected root is rescanned as a whole instead of file by file. Files
+// are processed in batches, with the hot set re-read between them. The work
+// itself goes through a `ReindexTarget`, so the policy can be tested without
+// a database or a real filesystem.
+
+/// Changed paths held before a root falls back to a full rescan.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+
+/// Default files per batch. The hot set is re-read between batches so files
+/// an agent reads mid-run still jump the queue.
+pub const BATCH_SIZE: usize = 64;
+
+/// Recently read files remembered for prioritisation.
+const MAX_HOT_FILES: usize = 512;
+
+/// Files referenced by recent queries, shared between MCP sessions and the
+/// scheduler. Cloning shares the same set.
+#[derive(Debug, Clone, Default)]
+pub struct HotFiles {
+    inner: Arc<Mutex<HotSet>>,
+}
+
+#[derive(Debug, Default)]
+struct HotSet {
+    /// Absolute path → tick of the last reference.
+    files: HashMap<PathBuf, u64>,
+    tick: u64,
+}
+
+impl HotFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that queries returned `paths`.
+    pub fn touch(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        let Ok(mut hot) = self.inner.lock() else { return };
+        for path in paths {
+            hot.tick += 1;
+            let tick = hot.tick;
+            hot.files.insert(path, tick);
+        }
+        if hot.files.len() > MAX_HOT_FILES {
+            let mut ticks: Vec<u64> = hot.files.values().copied().collect();
+            ticks.sort_unstable();
+            let cutoff = ticks[ticks.len() - MAX_HOT_FILES];
+            hot.files.retain(|_, tick| *tick >= cutoff);
+        }
+    }
+
+    /// When `path` was last referenced; larger is more recent.
+    pub fn rank(&self, path: &Path) -> Option<u64> {
+        self.inner.lock().ok()?.files.get(path).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().map(|hot| hot.files.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The operations a reindex run performs. `IndexerTarget` is the real one.
+pub trait ReindexTarget {
+    /// Called for a root with pending work, before any of its files, unless
+    /// its `.git/HEAD` is unchanged since the last successful call.
+    fn sync_root(&mut self, root: &Path) -> Result<()>;
+    /// Reindex everything under `root` (after a queue overflow). Returns the
+    /// files that failed; an error means the rescan could not start.
+    fn rescan_root(&mut self, root: &Path) -> Result<Vec<String>>;
+    fn exists(&self, path: &Path) -> bool;
+    /// Reindex one file; true when its contents had changed.
+    fn reindex_file(&mut self, path: &Path, root: &Path) -> Result<bool>;
+    /// Drop a deleted file from the index; true when it was indexed.
+    fn remove_file(&mut self, path: &Path, root: &Path) -> Result<bool>;
+    /// Called before each batch of files.
+    fn begin_batch(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Called after each batch with what it changed. An error is reported and
+    /// the batch is not counted.
+    fn end_batch(&mut self, _batch: &BatchReport) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Files one batch reindexed or removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BatchReport {
+    pub reindexed: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub errors: usize,
+}
+
+impl BatchReport {
+    pub fn is_empty(&self) -> bool {
+        self.reindexed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// What one `ReindexScheduler::run` did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunStats {
+    pub reindexed: usize,
+    pub removed: usize,
+    pub rescanned: usize,
+    /// Batches applied.
+    pub batches: usize,
+    pub errors: Vec<String>,
+}
+
+/// Bounded, coalescing queue of changed paths under a set of roots.
+pub struct ReindexScheduler {
+    roots: Vec<PathBuf>,
+    capacity: usize,
+    batch_size: usize,
+    queue: VecDeque<PathBuf>,
+    queued: HashSet<PathBuf>,
+    /// Roots whose changes overflowed the queue.
+    rescan: BTreeSet<PathBuf>,
+    hot: HotFiles,
+    dropped: usize,
+    /// Modification time of each root's `.git/HEAD` when it was last
+    /// synced; a checkout rewrites it, ordinary edits don't.
+    heads: HashMap<PathBuf, SystemTime>,
+}
+
+impl ReindexScheduler {
+    pub fn new(roots: Vec<PathBuf>, capacity: usize) -> Self {
+        Self {
+            roots,
+            capacity,
+            batch_size: BATCH_SIZE,
+            queue: VecDeque::new(),
+            queued: HashSet::new(),
+            rescan: BTreeSet::new(),
+            hot: HotFiles::new(),
+            dropped: 0,
+            heads: HashMap::new(),
+        }
+    }
+
+    /// Prioritise files recorded in `hot`.
+    pub fn with_hot_files(mut self, hot: HotFiles) -> Self {
+        self.hot = hot;
+        self
+    }
+
+    /// Files per batch (and per transaction); at least one.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn hot_files(&self) -> HotFiles {
+        self.hot.clone()
+    }
+
+    /// Queue changed paths. Paths outside every root are ignored and paths
+    /// already queued are coalesced; returns how many were newly queued.
+    pub fn enqueue(&mut self, paths: impl IntoIterator<Item = PathBuf>) -> usize {
+        let mut added = 0;
+        for path in paths {
+            let Some(root) = self.root_of(&path).map(Path::to_path_buf) else {
+                continue;
+            };
+            if self.rescan.contains(&root) || self.queued.contains(&path) {
+                continue;
+            }
+            if self.queue.len() >= self.capacity {
+                // The rescan covers this root's queued files too.
+                self.dropped += 1;
+                self.queue.retain(|p| !crate::paths::is_under(p, &root));
+                self.queued.retain(|p| !crate::paths::is_under(p, &root));
+                self.rescan.insert(root);
+                continue;
+            }
+            self.queued.insert(path.clone());
+            self.queue.push_back(path);
+            added += 1;
+        }
+        added
+    }
+
+    /// Paths waiting to be processed.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty() && self.rescan.is_empty()
+    }
+
+    /// Roots waiting for a full rescan.
+    pub fn pending_rescans(&self) -> Vec<PathBuf> {
+        self.rescan.iter().cloned().collect()
+    }
+
+    /// Changed paths that overflowed the queue since the scheduler started.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Take up to `max` paths: hot files first, most recently referenced
+    /// first, then the rest in arrival order.
+    pub fn next_batch(&mut self, max: usize) -> Vec<PathBuf> {
+        let mut ordered: Vec<(Option<u64>, PathBuf)> =
+            self.queue.drain(..).map(|path| (self.hot.rank(&path), path)).collect();
+        // Stable: equal ranks (including all cold files) keep arrival order.
+        ordered.sort_by_key(|(rank, _)| std::cmp::Reverse(*rank));
+        let rest = ordered.split_off(max.min(ordered.len()));
+        self.queue = rest.into_iter().map(|(_, path)| path).collect();
+        let batch: Vec<PathBuf> = ordered.into_iter().map(|(_, path)| path).collect();
+        for path in &batch {
+            self.queued.remove(path);
+        }
+        batch
+    }
+
+    /// Process everything queued: sync each affected root, rescan overflowed
+    /// roots, then reindex or remove files in priority order, one batch per
+    /// `begin_batch` / `end_batch` pair.
+    pub fn run<T: ReindexTarget>(&mut self, target: &mut T) -> RunStats {
+        let mut stats = RunStats::default();
+        let touched: Vec<PathBuf> = self
+            .roots
+            .iter()
+            .filter(|root| self.rescan.contains(*root) || self.queue.iter().any(|p| crate::paths::is_under(p, root)))
+            .cloned()
+            .collect();
+        for root in &touched {
+            // Roots without a plain `.git` directory (linked worktrees, no
+            // checkout) have no stamp and are synced every run.
+            let head = std::fs::metadata(root.join(".git").join("HEAD")).and_then(|m| m.modified()).ok();
+            if head.is_some() && self.heads.get(root) == head.as_ref() {
+                continue;
+            }
+            match target.sync_root(root) {
+                Ok(()) => {
+                    if let Some(head) = head {
+                        self.heads.insert(root.clone(), head);
+                    }
+                }
+                Err(e) => stats.errors.push(format!("{}: branch sync failed: {e}", root.display())),
+            }
+        }
+
+        for root in std::mem::take(&mut self.rescan) {
+            match target.rescan_root(&root) {
+                Ok(errors) => {
+                    stats.rescanned += 1;
+                    stats.errors.extend(errors);
+                }
+                Err(e) => stats.errors.push(format!("{}: rescan failed: {e}", root.display())),
+            }
+        }
+
+        loop {
+            let batch = self.next_batch(self.batch_size);
+            if batch.is_empty() {
+                break;
+            }
+            if let Err(e) = target.begin_batch() {
+                stats.errors.push(format!("batch start failed: {e}"));
+            }
+            let mut report = BatchReport::default();
+            for path in batch {
+                let Some(root) = self.root_of(&path).map(Path::to_path_buf) else {
+                    continue;
+                };
+                if target.exists(&path) {
+                    match target.reindex_file(&path, &root) {
+                        Ok(true) => report.reindexed.push(path),
+                        Ok(false) => {}
+                        Err(e) => {
+                            report.errors += 1;
+                            stats.errors.push(format!("{}: re-index failed: {e}", path.display()));
+                        }
+                    }
+                } else {
+                    match target.remove_file(&path, &root) {
+                        Ok(true) => report.removed.push(path),
+                        Ok(false) => {}
+                        Err(e) => {
+                            report.errors += 1;
+                            stats.errors.push(format!("{}: remove failed: {e}", path.display()));
+                        }
+                    }
+                }
+            }
+            match target.end_batch(&report) {
+                Ok(()) => {
+                    stats.batches += 1;
+                    stats.reindexed += report.reindexed.len();
+                    stats.removed += report.removed.len();
+                }
+                Err(e) => stats.errors.push(format!("batch commit failed: {e}")),
+            }
+        }
+        stats
+    }
+
+    fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .find(|root| crate::paths::is_under(path, root))
+            .map(PathBuf::as_path)
+    }
+}
+
+/// `ReindexTarget` backed by the index. Files are read and parsed with the
+/// database unlocked, then written under a lock of their own in one
+/// transaction each, so MCP handlers wait for at most one file's writes.
+pub struct IndexerTarget<'a> {
+    db: &'a Mutex<Database>,
+    registry: &'a GrammarRegistry,
+    builtins: BuiltinFilter,
+    redact_secrets: bool,
+    deny: DenyList,
+    guardrails: Guardrails,
+    session_id: String,
+    /// Roots whose files changed in the current batch; their repo-wide passes
+    /// (`Indexer::finish_file_updates`) run once the batch ends.
+    touched: BTreeSet<PathBuf>,
+}
+
+impl<'a> IndexerTarget<'a> {
+    pub fn new(db: &'a Mutex<Database>, registry: &'a GrammarRegistry, builtins: BuiltinFilter) -> Self {
+        Self {
+            db,
+            registry,
+            builtins,
+            redact_secrets: true,
+            deny: DenyList::default(),
+            guardrails: Guardrails::default(),
+            touched: BTreeSet::new(),
+            session_id: "watcher".to_string(),
+        }
+    }
+
+    /// See `Indexer::with_redaction`.
+    pub fn with_redaction(mut self, enabled: bool) -> Self {
+        self.redact_secrets = enabled;
+        self
+    }
+
+    /// See `Indexer::with_deny_list`.
+    pub fn with_deny_list(mut self, deny: DenyList) -> Self {
+        self.deny = deny;
+        self
+    }
+
+    /// See `Indexer::with_guardrails`.
+    pub fn with_guardrails(mut self, guardrails: Guardrails) -> Self {
+        self.guardrails = guardrails;
+        self
+    }
+
+    /// Session the per-batch auto-observation is recorded under.
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = session_id.into();
+        self
+    }
+
+    fn with_indexer<R>(&self, f: impl FnOnce(&Indexer) -> Result<R>) -> Result<R> {
+        let indexer = |db| {
+            Indexer::new(db, self.registry)
+                .with_builtin_filter(self.builtins.clone())
+                .with_redaction(self.redact_secrets)
+                .with_deny_list(self.deny.clone())
+                .with_guardrails(self.guardrails.clone())
+        };
+        let db = self.db.lock().map_err(|e| anyhow!("failed to lock DB: {e}"))?;
+        f(&indexer(&db))
+    }
+
+    /// `Indexer::reindex_paths` with the walk under one lock and each file
+    /// re-indexed under its own, so queries interleave with a large run.
+    pub fn reindex_paths(&mut self, root: &Path, globs: &[String]) -> Result<IndexStats> {
+        let root = crate::paths::canonicalize(root)?;
+        let plan = self.with_indexer(|indexer| {
+            indexer.sync_branch(&root)?;
+            indexer.plan_paths(&root, globs)
+        })?;
+        Ok(self.apply_plan(&plan, &root))
+    }
+
+    fn parser(&self) -> FileParser<'_> {
+        FileParser {
+            registry: self.registry,
+            redact_secrets: self.redact_secrets,
+            deny: &self.deny,
+            guardrails: &self.guardrails,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_partial_file_size: DEFAULT_MAX_PARTIAL_FILE_SIZE,
+        }
+    }
+
+    /// Read and parse `path` with the database unlocked, locking only to skip
+    /// a file whose contents are already indexed. None when there is nothing
+    /// to write.
+    fn parse(&self, path: &Path, root: &Path) -> Result<Option<ParsedFile>> {
+        let parser = self.parser();
+        let Some(file) = parser.read(path, root)? else {
+            return Ok(None);
+        };
+        if self.with_indexer(|indexer| indexer.is_unchanged(&file))? {
+            return Ok(None);
+        }
+        parser.parse(file).map(Some)
+    }
+
+    /// Write a file `parse` returned, in its own transaction.
+    fn write(&self, parsed: Option<ParsedFile>) -> Result<bool> {
+        match parsed {
+            Some(parsed) => self.with_indexer(|indexer| indexer.write_parsed(parsed)),
+            None => Ok(false),
+        }
+    }
+
+    /// Apply `plan` one file per lock, collecting errors.
+    fn apply_plan(&self, plan: &RescanPlan, root: &Path) -> IndexStats {
+        let mut stats = IndexStats { files_skipped: plan.unchanged, ..IndexStats::default() };
+        let mut go_changed = false;
+        for (rel_path, path) in &plan.changed {
+            let parsed = self.parse(path, root);
+            let recorded = self.with_indexer(|indexer| {
+                let indexed = parsed.and_then(|parsed| match parsed {
+                    Some(parsed) => indexer.write_parsed(parsed),
+                    None => Ok(false),
+                });
+                Ok(indexer.record_planned(plan.repo_id, rel_path, path, indexed, &mut stats))
+            });
+            match recorded {
+                Ok(go) => go_changed |= go,
+                Err(e) => stats.errors.push(format!("{rel_path}: {e:#}")),
+            }
+        }
+        for rel_path in &plan.deleted {
+            match self.with_indexer(|indexer| Ok(indexer.remove_planned(rel_path, root, &mut stats))) {
+                Ok(go) => go_changed |= go,
+                Err(e) => stats.errors.push(format!("{rel_path}: {e:#}")),
+            }
+        }
+        if go_changed {
+            if let Err(e) = self.with_indexer(|indexer| indexer.refresh_go_implements(root)) {
+                stats.errors.push(format!("{}: Go implements refresh failed: {e:#}", root.display()));
+            }
+        }
+        if stats.files_indexed + stats.files_removed > 0 {
+            match self.with_indexer(|indexer| indexer.finish_file_updates(root)) {
+                Ok(links) => stats.test_links = links,
+                Err(e) => stats.errors.push(format!("{}: finishing updates failed: {e:#}", root.display())),
+            }
+        }
+        stats
+    }
+}
+
+impl ReindexTarget for IndexerTarget<'_> {
+    fn sync_root(&mut self, root: &Path) -> Result<()> {
+        // A checkout shows up as a burst of file changes. Activating the new
+        // branch's namespace first restores its files from their snapshots
+        // instead of re-parsing them one by one.
+        if let Some(switch) = self.with_indexer(|indexer| indexer.sync_branch(root))? {
+            tracing::info!(
+                from = %switch.from,
+                to = %switch.to,
+                parked = switch.parked,
+                restored = switch.restored,
+                reindexed = switch.reindexed,
+                "switched branch namespace"
+            );
+        }
+        Ok(())
+    }
+
+    fn rescan_root(&mut self, root: &Path) -> Result<Vec<String>> {
+        // `sync_root` already ran. The tree is hashed under one lock, then
+        // each file is re-indexed under its own, so handlers interleave with
+        // a large rescan instead of waiting it out.
+        let root = crate::paths::canonicalize(root)?;
+        let Some(plan) = self.with_indexer(|indexer| indexer.plan_rescan(&root))? else {
+            let stats = self.with_indexer(|indexer| indexer.index_directory(&root))?;
+            return Ok(stats.errors);
+        };
+        let stats = self.apply_plan(&plan, &root);
+        tracing::info!(
+            root = %root.display(),
+            files_indexed = stats.files_indexed,
+            files_removed = stats.files_removed,
+            errors = stats.errors.len(),
+            "rescanned root after watcher overflow"
+        );
+        Ok(stats.errors)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn reindex_file(&mut self, path: &Path, root: &Path) -> Result<bool> {
+        let parsed = self.parse(path, root)?;
+        let indexed = self.write(parsed)?;
+        if indexed && crate::indexer::is_go_file(path) {
+            self.with_indexer(|indexer| indexer.refresh_go_implements(root))?;
+        }
+        if indexed {
+            self.touched.insert(root.to_path_buf());
+        }
+        Ok(indexed)
+    }
+
+    fn remove_file(&mut self, path: &Path, root: &Path) -> Result<bool> {
+        let removed = self.with_indexer(|indexer| indexer.remove_one_file(path, root))?;
+        if removed {
+            self.touched.insert(root.to_path_buf());
+        }
+        Ok(removed)
+    }
+
+    fn end_batch(&mut self, batch: &BatchReport) -> Result<()> {
+        for root in std::mem::take(&mut self.touched) {
+            self.with_indexer(|indexer| indexer.finish_file_updates(&root))
+                .with_context(|| format!("{}: finishing updates failed", root.display()))?;
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let db = self.db.lock().map_err(|e| anyhow!("failed to lock DB: {e}"))?;
+        db.save_auto_observation(&describe_batch(batch), "auto:watcher", &self.session_id, &[])?;
+        Ok(())
+    }
+}
+
+/// Files named in a batch's auto-observation before the rest are counted.
+const OBSERVATION_PATHS: usize = 5;
+
+/// One-line summary of a batch for its auto-observation.
+pub fn describe_batch(batch: &BatchReport) -> String {
+    let mut parts = Vec::new();
+    for (verb, paths) in [("Re-indexed", &batch.reindexed), ("Removed", &batch.removed)] {
+        if paths.is_empty() {
+            continue;
+        }
+        let mut names: Vec<String> = paths
+            .iter()
+            .take(OBSERVATION_PATHS)
+            .map(|p| p.file_name().map_or_else(|| p.display().to_string(), |n| n.to_string_lossy().to_string()))
+            .collect();
+        if paths.len() > OBSERVATION_PATHS {
+            names.push(format!("+{} more", paths.len() - OBSERVATION_PATHS));
+        }
+        let noun = if paths.len() == 1 { "file" } else { "files" };
+        parts.push(format!("{verb} {} {noun} ({})", paths.len(), names.join(", ")));
+    }
+    let mut text = parts.join("; ");
+    if batch.errors > 0 {
+        text.push_str(&format!("; {} failed", batch.errors));
+    }
+    text
+}
@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::db::{Database, Repository, Symbol};
+use crate::encoding::decode_source;
+
+// ---------------------------------------------------------------------------
+// Rename planning
+// ---------------------------------------------------------------------------
+//
+// The index knows which symbols reference which (edges) and the line range
+// of every symbol, but not the column of each reference. A plan recovers the
+// positions by scanning the definition and each referencing symbol's line
+// range for the old name as a whole word, so a homonym inside a confirmed
+// referrer (a local of the same name) is listed too, but nothing outside
+// one. Symbols that mention the name without an edge (calls through an
+// unresolved qualifier, strings, comments) are listed separately as
+// `unconfirmed`, to be checked by hand.
+//
+// Methods are stored under their type (`Server.Start`, `Wrapper::get`), but
+// source spells only the last segment at the definition and call sites, so
+// that segment is what is scanned for and what `new_name` replaces.
+//
+// Lines are read from the file on disk, so columns match the tree the
+// rename will edit; the stored body stands in when the file is unreadable.
+// Returned line text has secrets masked, as stored bodies do.
+
+/// Symbols without an edge scanned for unconfirmed mentions.
+const MAX_UNCONFIRMED_SYMBOLS: i64 = 50;
+
+/// Memories searched for mentions of the old name.
+const MAX_MEMORY_MATCHES: i64 = 50;
+
+/// One place the name appears.
+#[derive(Debug, Clone, Serialize)]
+pub struct Location {
+    /// Set when the file is in another repository than the symbol.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    pub file_path: String,
+    pub line: i64,
+    /// 1-based, in characters.
+    pub column: usize,
+    /// The whole line, trimmed.
+    pub text: String,
+    /// Symbol whose range holds the occurrence.
+    pub in_symbol: String,
+    /// `definition`, `self` (inside the definition), the referencing edge
+    /// kind (`calls`, `type_ref`, ...), or `mention`.
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AffectedFile {
+    pub repo: String,
+    pub file_path: String,
+    pub references: usize,
+}
+
+/// An existing symbol already called the new name.
+#[derive(Debug, Clone, Serialize)]
+pub struct Collision {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub line: i64,
+    /// `same_file` (the definition's file), `referencing_file` (a file the
+    /// rename edits) or `repo`.
+    pub scope: &'static str,
+}
+
+/// A memory to revise along with the rename.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryUpdate {
+    pub id: i64,
+    pub content: String,
+    /// Linked to the symbol. Links are re-made by name on re-index, so this
+    /// one goes stale unless relinked.
+    pub linked: bool,
+    /// The content names the symbol.
+    pub mentions: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenamePlan {
+    pub symbol: String,
+    pub new_name: String,
+    pub kind: String,
+    pub repo: String,
+    pub file_path: String,
+    pub line: i64,
+    /// Every edit site, the definition first, then by file and position.
+    pub references: Vec<Location>,
+    /// References left out past `max_references`.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub references_omitted: usize,
+    pub files: Vec<AffectedFile>,
+    /// Occurrences in symbols with no edge to this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unconfirmed: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub collisions: Vec<Collision>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub memories: Vec<MemoryUpdate>,
+    /// Referencing symbols whose range never names the symbol, e.g. through
+    /// an import alias, as `name (file:line)`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unlocated: Vec<String>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Plan renaming `sym` to `new_name`: where to edit, which existing names
+/// would clash, and which memories to revise. Nothing is changed. For a
+/// method, `new_name` is the method's new name alone, or with the same
+/// type qualifier (`Run` or `Server.Run` for `Server.Start`).
+pub fn plan_rename(db: &Database, sym: &Symbol, new_name: &str, max_references: usize) -> Result<RenamePlan> {
+    let old = last_segment(&sym.name);
+    let qualifier = &sym.name[..sym.name.len() - old.len()];
+    let new = new_name.strip_prefix(qualifier).filter(|_| !qualifier.is_empty()).unwrap_or(new_name);
+    if !is_identifier(new) {
+        if !qualifier.is_empty() && last_segment(new_name) != new_name {
+            bail!("'{new_name}' changes the qualifier '{qualifier}'; pass the new name alone");
+        }
+        bail!("'{new_name}' is not a valid identifier");
+    }
+    if new == old {
+        bail!("'{new_name}' is already the symbol's name");
+    }
+    let new_name = format!("{qualifier}{new}");
+    let repos: HashMap<i64, Repository> = db.list_repositories()?.into_iter().map(|r| (r.id, r)).collect();
+    let mut files = SourceFiles { db, repos: &repos, home_repo: 0, cache: HashMap::new() };
+    let home = files.file(sym.file_id)?;
+    let repo_id = home.repo_id;
+    files.home_repo = repo_id;
+
+    let mut seen = HashSet::new();
+    let mut references = Vec::new();
+    let mut in_definition = files.occurrences(sym, old)?;
+    if let Some(first) = in_definition.first_mut() {
+        first.kind = "definition".to_string();
+    }
+    for loc in in_definition.iter_mut().skip(1) {
+        loc.kind = "self".to_string();
+    }
+    references.extend(in_definition.into_iter().filter(|l| seen.insert(l.key())));
+
+    let mut referrers = HashSet::from([sym.id]);
+    let mut unlocated = Vec::new();
+    let mut rest = Vec::new();
+    for (edge, source) in db.get_dependents(sym.id)? {
+        if !referrers.insert(source.id) {
+            continue;
+        }
+        let mut found = files.occurrences(&source, old)?;
+        if found.is_empty() {
+            let file = files.file(source.file_id)?;
+            unlocated.push(format!("{} ({}:{})", source.name, file.path, source.start_line));
+        }
+        for loc in &mut found {
+            loc.kind = edge.kind.clone();
+        }
+        rest.extend(found);
+    }
+    rest.sort_by(|a, b| (&a.repo, &a.file_path, a.line, a.column).cmp(&(&b.repo, &b.file_path, b.line, b.column)));
+    references.extend(rest.into_iter().filter(|l| seen.insert(l.key())));
+
+    let mut unconfirmed = Vec::new();
+    for other in db.find_symbols_containing(old, Some(repo_id), MAX_UNCONFIRMED_SYMBOLS)? {
+        if referrers.contains(&other.id) {
+            continue;
+        }
+        for mut loc in files.occurrences(&other, old)? {
+            if seen.insert(loc.key()) {
+                loc.kind = "mention".to_string();
+                unconfirmed.push(loc);
+            }
+        }
+    }
+
+    let mut per_file: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for loc in &references {
+        let repo = loc.repo.clone().unwrap_or_else(|| files.repo_name(repo_id));
+        *per_file.entry((repo, loc.file_path.clone())).or_default() += 1;
+    }
+    let edited: HashSet<&str> = references.iter().filter(|l| l.repo.is_none()).map(|l| l.file_path.as_str()).collect();
+    let mut collisions = Vec::new();
+    for other in db.symbols_named(Some(repo_id), new)? {
+        // Only names the rename could clash with: the same qualifier, or a
+        // sibling under the same parent (a method stored bare in its class).
+        let sibling = other.parent_id.is_some() && other.parent_id == sym.parent_id;
+        if other.name != new_name && !sibling {
+            continue;
+        }
+        let file = files.file(other.file_id)?;
+        let scope = if other.file_id == sym.file_id {
+            "same_file"
+        } else if edited.contains(file.path.as_str()) {
+            "referencing_file"
+        } else {
+            "repo"
+        };
+        collisions.push(Collision {
+            name: other.name,
+            kind: other.kind,
+            file_path: file.path.clone(),
+            line: other.start_line,
+            scope,
+        });
+    }
+    collisions.sort_by_key(|c| match c.scope {
+        "same_file" => 0,
+        "referencing_file" => 1,
+        _ => 2,
+    });
+
+    let mut memories: BTreeMap<i64, MemoryUpdate> = BTreeMap::new();
+    for memory in db.get_memories_for_symbol(sym.id, true)? {
+        let mentions = !word_columns(&memory.content, old).is_empty();
+        memories.insert(memory.id, MemoryUpdate { id: memory.id, content: memory.content, linked: true, mentions });
+    }
+    for memory in db.search_memories(old, MAX_MEMORY_MATCHES)? {
+        if !memories.contains_key(&memory.id) && !word_columns(&memory.content, old).is_empty() {
+            memories.insert(memory.id, MemoryUpdate { id: memory.id, content: memory.content, linked: false, mentions: true });
+        }
+    }
+
+    let references_omitted = references.len().saturating_sub(max_references);
+    references.truncate(max_references);
+    Ok(RenamePlan {
+        symbol: sym.name.clone(),
+        new_name,
+        kind: sym.kind.clone(),
+        repo: files.repo_name(repo_id),
+        file_path: home.path.clone(),
+        line: sym.start_line,
+        references,
+        references_omitted,
+        files: per_file
+            .into_iter()
+            .map(|((repo, file_path), references)| AffectedFile { repo, file_path, references })
+            .collect(),
+        unconfirmed,
+        collisions,
+        memories: memories.into_values().collect(),
+        unlocated,
+    })
+}
+
+impl Location {
+    fn key(&self) -> (Option<String>, String, i64, usize) {
+        (self.repo.clone(), self.file_path.clone(), self.line, self.column)
+    }
+}
+
+/// Indexed files and their current lines, loaded once per plan.
+struct SourceFiles<'a> {
+    db: &'a Database,
+    repos: &'a HashMap<i64, Repository>,
+    /// The definition's repository; locations elsewhere name theirs.
+    home_repo: i64,
+    cache: HashMap<i64, Rc<SourceFile>>,
+}
+
+struct SourceFile {
+    repo_id: i64,
+    path: String,
+    /// None when the file can't be read from disk.
+    lines: Option<Vec<String>>,
+}
+
+impl SourceFiles<'_> {
+    fn file(&mut self, file_id: i64) -> Result<Rc<SourceFile>> {
+        if let Some(file) = self.cache.get(&file_id) {
+            return Ok(file.clone());
+        }
+        let record = self.db.get_file_by_id(file_id)?.with_context(|| format!("file {file_id} not found"))?;
+        let lines = self.repos.get(&record.repo_id).and_then(|repo| {
+            let raw = std::fs::read(Path::new(&repo.root_path).join(&record.path)).ok()?;
+            let text = String::from_utf8_lossy(&decode_source(&raw).text).into_owned();
+            Some(text.lines().map(String::from).collect())
+        });
+        let file = Rc::new(SourceFile { repo_id: record.repo_id, path: record.path, lines });
+        self.cache.insert(file_id, file.clone());
+        Ok(file)
+    }
+
+    fn repo_name(&self, repo_id: i64) -> String {
+        self.repos.get(&repo_id).map(|r| r.name.clone()).unwrap_or_default()
+    }
+
+    /// Whole-word occurrences of `name` within `sym`'s line range.
+    fn occurrences(&mut self, sym: &Symbol, name: &str) -> Result<Vec<Location>> {
+        let file = self.file(sym.file_id)?;
+        let first = sym.start_line.max(1);
+        let lines: Vec<(i64, &str)> = match &file.lines {
+            Some(lines) => (first..=sym.end_line.max(first))
+                .filter_map(|n| lines.get(n as usize - 1).map(|l| (n, l.as_str())))
+                .collect(),
+            None => (first..).zip(sym.body.lines()).collect(),
+        };
+        let repo = (file.repo_id != self.home_repo).then(|| self.repo_name(file.repo_id));
+        Ok(lines
+            .into_iter()
+            .flat_map(|(line, text)| {
+                word_columns(text, name).into_iter().map(move |column| (line, text, column))
+            })
+            .map(|(line, text, column)| Location {
+                repo: repo.clone(),
+                file_path: file.path.clone(),
+                line,
+                column,
+                text: crate::redact::redact(text.trim()).0.into_owned(),
+                in_symbol: sym.name.clone(),
+                kind: String::new(),
+            })
+            .collect())
+    }
+}
+
+/// 1-based character columns where `name` occurs in `text` as a whole word.
+fn word_columns(text: &str, name: &str) -> Vec<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    text.match_indices(name)
+        .filter(|(start, _)| {
+            let before = text[..*start].chars().next_back();
+            let after = text[start + name.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .map(|(start, _)| text[..start].chars().count() + 1)
+        .collect()
+}
+
+/// The part of a stored name that source spells: `Start` for a Go method
+/// stored as `Server.Start`, `get` for `Wrapper::get`.
+pub(crate) fn last_segment(name: &str) -> &str {
+    let dotted = name.rsplit('.').next().unwrap_or(name);
+    dotted.rsplit("::").next().unwrap_or(dotted)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::indexer::IndexStats;
+
+// ---------------------------------------------------------------------------
+// Last-run report
+// ---------------------------------------------------------------------------
+//
+// Every `focal index` and every startup indexing pass of `focal serve`
+// overwrites `last_index.json` beside the database with a summary of the run, so CI can
+// assert on indexing results without scraping stderr. The
+// `get_last_index_report` tool returns the same file.
+
+/// Totals of one indexing run over one or more workspace roots.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexReport {
+    /// Unix time (seconds) the run finished.
+    pub finished_at: u64,
+    pub duration_ms: u64,
+    pub roots: Vec<String>,
+    pub files_indexed: usize,
+    pub files_skipped: usize,
+    pub files_partial: usize,
+    #[serde(default)]
+    pub files_minified: usize,
+    #[serde(default)]
+    pub files_binary: usize,
+    pub symbols: usize,
+    pub edges: usize,
+    pub test_links: usize,
+    /// Secrets masked in stored symbol bodies.
+    #[serde(default)]
+    pub redactions: usize,
+    /// Directories skipped as generated output.
+    #[serde(default)]
+    pub generated_dirs: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl IndexReport {
+    /// Add the outcome of indexing `root`.
+    pub fn add(&mut self, root: &Path, stats: &IndexStats) {
+        self.roots.push(root.display().to_string());
+        self.files_indexed += stats.files_indexed;
+        self.files_skipped += stats.files_skipped;
+        self.files_partial += stats.files_partial;
+        self.files_minified += stats.files_minified;
+        self.files_binary += stats.files_binary;
+        self.symbols += stats.symbols_extracted;
+        self.edges += stats.edges_created;
+        self.test_links += stats.test_links;
+        self.redactions += stats.redactions;
+        self.generated_dirs.extend(stats.generated_dirs.iter().cloned());
+        self.errors.extend(stats.errors.iter().cloned());
+    }
+
+    /// Record a root whose indexing failed outright.
+    pub fn add_failure(&mut self, root: &Path, error: &anyhow::Error) {
+        self.roots.push(root.display().to_string());
+        self.errors.push(format!("{}: {error:#}", root.display()));
+    }
+
+    /// Stamp the run's duration and finish time.
+    pub fn finish(&mut self, elapsed: Duration) {
+        self.duration_ms = elapsed.as_millis() as u64;
+        self.finished_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+    }
+}
+
+/// `last_index.json` in the directory of `config::db_path`, so the report
+/// follows `FOCAL_DB_PATH`.
+pub fn default_report_path() -> PathBuf {
+    crate::config::db_path().with_file_name("last_index.json")
+}
+
+/// Overwrite the report at `path`. The JSON goes to a temporary file that is
+/// then renamed over `path`, so a concurrent `read_report` never sees a
+/// partial write.
+pub fn write_report(path: &Path, report: &IndexReport) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)?;
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("last_index.json");
+    let tmp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+    std::fs::write(&tmp, json).with_context(|| format!("failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        anyhow::Error::new(e).context(format!("failed to replace {}", path.display()))
+    })
+}
+
+/// The report at `path`, or None when no run has written one yet.
+pub fn read_report(path: &Path) -> Result<Option<IndexReport>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let report = serde_json::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(report))
+}
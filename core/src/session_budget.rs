@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+// ---------------------------------------------------------------------------
+// Session token accounting
+// ---------------------------------------------------------------------------
+//
+// Every tool response is sized (~4 chars per token) and added to a running
+// total for the MCP session. With a soft cap configured, get_context budgets
+// shrink in proportion to how much of the cap is already spent, so a long
+// session degrades to smaller capsules instead of flooding the agent.
+
+/// get_context budgets are never shrunk below this.
+pub const MIN_SHRUNK_BUDGET: usize = 1_000;
+
+/// Calls and tokens served by one tool.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ToolUsage {
+    pub calls: usize,
+    pub tokens: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionBudgetReport {
+    pub session_id: String,
+    pub total_tokens: usize,
+    pub calls: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soft_cap: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<usize>,
+    /// Share of a requested get_context budget currently granted, 0-1.
+    pub context_budget_factor: f64,
+    /// Per-tool usage, heaviest first.
+    pub by_tool: Vec<(String, ToolUsage)>,
+}
+
+/// Running token totals for one session.
+#[derive(Debug, Default)]
+pub struct SessionBudget {
+    tools: BTreeMap<String, ToolUsage>,
+    total_tokens: usize,
+    soft_cap: Option<usize>,
+}
+
+impl SessionBudget {
+    pub fn new(soft_cap: Option<usize>) -> Self {
+        Self { soft_cap: soft_cap.filter(|&cap| cap > 0), ..Self::default() }
+    }
+
+    pub fn record(&mut self, tool: &str, tokens: usize) {
+        let usage = self.tools.entry(tool.to_string()).or_default();
+        usage.calls += 1;
+        usage.tokens += tokens;
+        self.total_tokens += tokens;
+    }
+
+    pub fn total_tokens(&self) -> usize {
+        self.total_tokens
+    }
+
+    /// Unspent share of the soft cap (1.0 without a cap).
+    pub fn factor(&self) -> f64 {
+        match self.soft_cap {
+            Some(cap) => (1.0 - self.total_tokens as f64 / cap as f64).clamp(0.0, 1.0),
+            None => 1.0,
+        }
+    }
+
+    /// `requested` scaled by `factor`, but at least `MIN_SHRUNK_BUDGET` (or
+    /// `requested`, if smaller).
+    pub fn shrink(&self, requested: usize) -> usize {
+        let scaled = (requested as f64 * self.factor()) as usize;
+        scaled.max(MIN_SHRUNK_BUDGET).min(requested)
+    }
+
+    pub fn report(&self, session_id: &str) -> SessionBudgetReport {
+        let mut by_tool: Vec<(String, ToolUsage)> =
+            self.tools.iter().map(|(tool, usage)| (tool.clone(), usage.clone())).collect();
+        by_tool.sort_by(|a, b| b.1.tokens.cmp(&a.1.tokens).then_with(|| a.0.cmp(&b.0)));
+        SessionBudgetReport {
+            session_id: session_id.to_string(),
+            total_tokens: self.total_tokens,
+            calls: self.tools.values().map(|u| u.calls).sum(),
+            soft_cap: self.soft_cap,
+            remaining: self.soft_cap.map(|cap| cap.saturating_sub(self.total_tokens)),
+            context_budget_factor: (self.factor() * 100.0).round() / 100.0,
+            by_tool,
+        }
+    }
+}
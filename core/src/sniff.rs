@@ -0,0 +1,71 @@
+// ---------------------------------------------------------------------------
+// Binary and minified content
+// ---------------------------------------------------------------------------
+//
+// A file whose extension matches a grammar is not necessarily source: a
+// `.ts` can be an MPEG transport stream, and a bundler's `app.js` packs the
+// whole program onto a handful of lines. Parsed as-is, either produces
+// "symbols" whose bodies run to megabytes and eat a capsule's token budget.
+//
+// - Binary: a NUL byte, or more than 10% control bytes, in the first
+//   `SNIFF_BYTES` bytes. Text with a byte-order mark (UTF-16 holds NULs) is
+//   never binary. Binary files are skipped.
+// - Minified: dense lines (longer than `DENSE_LINE_BYTES`, with less than 10%
+//   whitespace) hold at least half of the file. Whitespace density is a cheap
+//   stand-in for entropy: a long SQL string or a table of constants is still
+//   spaced out, a bundle or an inlined base64 blob is not. Minified files are
+//   indexed partially, like oversized ones: names, signatures and line
+//   ranges, no bodies.
+
+/// Leading bytes inspected for binary content.
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Lines at least this long count towards minification when dense.
+const DENSE_LINE_BYTES: usize = 1000;
+
+/// Files smaller than this are never considered minified.
+const MIN_MINIFIED_BYTES: usize = 2 * 1024;
+
+/// What a file's bytes look like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content {
+    Text,
+    Minified,
+    Binary,
+}
+
+/// Classify `source`, the raw bytes of a file.
+pub fn sniff(source: &[u8]) -> Content {
+    if is_binary(source) {
+        Content::Binary
+    } else if is_minified(source) {
+        Content::Minified
+    } else {
+        Content::Text
+    }
+}
+
+fn is_binary(source: &[u8]) -> bool {
+    if encoding_rs::Encoding::for_bom(source).is_some() {
+        return false;
+    }
+    let head = &source[..source.len().min(SNIFF_BYTES)];
+    if head.contains(&0) {
+        return true;
+    }
+    let control = head.iter().filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c)).count();
+    control * 10 > head.len()
+}
+
+fn is_minified(source: &[u8]) -> bool {
+    if source.len() < MIN_MINIFIED_BYTES {
+        return false;
+    }
+    let dense: usize = source
+        .split(|&b| b == b'\n')
+        .filter(|line| line.len() >= DENSE_LINE_BYTES)
+        .filter(|line| line.iter().filter(|b| b.is_ascii_whitespace()).count() * 10 < line.len())
+        .map(<[u8]>::len)
+        .sum();
+    dense * 2 >= source.len()
+}
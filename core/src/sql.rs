@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Tree};
+
+// ---------------------------------------------------------------------------
+// Embedded SQL
+// ---------------------------------------------------------------------------
+
+/// String-literal node kinds across the supported grammars (Rust, Go,
+/// TypeScript/JavaScript, Python).
+const STRING_KINDS: &[&str] = &[
+    "string_literal",
+    "raw_string_literal",
+    "interpreted_string_literal",
+    "string",
+    "template_string",
+];
+
+/// Statement keywords that open a query, with a keyword that must follow for
+/// the literal to count as SQL rather than prose ("Select a file").
+const STATEMENTS: &[(&str, &str)] = &[
+    ("SELECT", "FROM"),
+    ("INSERT", "INTO"),
+    ("UPDATE", "SET"),
+    ("DELETE", "FROM"),
+    ("REPLACE", "INTO"),
+    ("CREATE", "TABLE"),
+    ("ALTER", "TABLE"),
+    ("DROP", "TABLE"),
+    ("WITH", "AS"),
+];
+
+/// Keywords after which a table name appears.
+const TABLE_KEYWORDS: &[&str] = &["FROM", "JOIN", "INTO", "UPDATE", "TABLE"];
+
+/// A SQL statement found in a string literal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SqlQuery {
+    /// Statement keyword, upper-case: SELECT, INSERT, UPDATE, DELETE, ...
+    pub operation: String,
+    /// Tables the statement reads or writes, lower-case, in order of appearance.
+    pub tables: Vec<String>,
+    /// Literal contents without quotes.
+    pub text: String,
+    /// 1-based line where the literal starts.
+    pub line: usize,
+}
+
+/// Find string literals that hold SQL. A literal qualifies when it starts
+/// with a statement keyword (all upper- or all lower-case) followed later by
+/// that statement's companion keyword, and names at least one table.
+pub fn extract_sql_queries(source: &[u8], tree: &Tree) -> Vec<SqlQuery> {
+    let mut queries = Vec::new();
+    let mut stack: Vec<Node> = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if STRING_KINDS.contains(&node.kind()) {
+            let text = node.utf8_text(source).unwrap_or_default();
+            if let Some(query) = parse_sql(literal_contents(text), node.start_position().row + 1) {
+                queries.push(query);
+            }
+            continue;
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+    queries.sort_by_key(|q| q.line);
+    queries
+}
+
+/// Strip prefixes (`r#`, `f`, `b`), quotes (single, triple, backtick) and raw
+/// string hashes from a literal's source text.
+fn literal_contents(text: &str) -> &str {
+    let Some(start) = text.find(['"', '\'', '`']) else {
+        return text;
+    };
+    let quote = &text[start..start + 1];
+    let run = if text[start..].starts_with(&quote.repeat(3)) { 3 } else { 1 };
+    let body = &text[start + run..];
+    let body = body.trim_end_matches('#');
+    body.strip_suffix(quote.repeat(run).as_str()).unwrap_or(body)
+}
+
+fn parse_sql(text: &str, line: usize) -> Option<SqlQuery> {
+    // Escapes (`\n`, line continuations) separate words like whitespace.
+    let text = text
+        .replace("\\n", " ")
+        .replace("\\t", " ")
+        .replace("\\r", " ")
+        .replace('\\', " ");
+    let text = text.trim();
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == ',' || c == ';')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let first = *words.first()?;
+    if first != first.to_uppercase() && first != first.to_lowercase() {
+        return None;
+    }
+    let (keyword, companion) = STATEMENTS
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(first))?;
+    if !words[1..].iter().any(|w| w.eq_ignore_ascii_case(companion)) {
+        return None;
+    }
+
+    // `WITH` statements take their operation from the main statement, and
+    // the CTE names they define are not tables.
+    let mut operation = keyword.to_string();
+    let mut ctes: Vec<String> = Vec::new();
+    if *keyword == "WITH" {
+        for (i, w) in words.iter().enumerate().skip(1) {
+            if w.eq_ignore_ascii_case("AS") {
+                ctes.push(normalize_table(words[i - 1]));
+            }
+        }
+        operation = words
+            .iter()
+            .skip(1)
+            .find_map(|w| {
+                ["SELECT", "INSERT", "UPDATE", "DELETE"]
+                    .into_iter()
+                    .find(|k| k.eq_ignore_ascii_case(w))
+            })?
+            .to_string();
+    }
+
+    let mut tables: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        if TABLE_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(words[i])) {
+            // CREATE TABLE IF NOT EXISTS t / DROP TABLE IF EXISTS t
+            let mut j = i + 1;
+            while j < words.len()
+                && ["IF", "NOT", "EXISTS", "ONLY", "OR", "IGNORE"]
+                    .iter()
+                    .any(|k| k.eq_ignore_ascii_case(words[j]))
+            {
+                j += 1;
+            }
+            if let Some(word) = words.get(j) {
+                let name = normalize_table(word);
+                if is_table_name(&name) && !ctes.contains(&name) && !tables.contains(&name) {
+                    tables.push(name);
+                }
+            }
+            i = j;
+        }
+        i += 1;
+    }
+    if tables.is_empty() {
+        return None;
+    }
+    Some(SqlQuery { operation, tables, text: text.to_string(), line })
+}
+
+fn normalize_table(word: &str) -> String {
+    word.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']' | '\''))
+        .to_lowercase()
+}
+
+/// Identifiers, optionally schema-qualified; rejects placeholders such as
+/// `${table}`, `{}`, `?` and `$1`, and keywords after a bare FROM.
+fn is_table_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && !["select", "where", "values", "set", "lateral"].contains(&name)
+}
@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::db::{Database, ResolvedFile};
+
+// ---------------------------------------------------------------------------
+// Stack-trace parsing
+// ---------------------------------------------------------------------------
+//
+// Recognized frame formats:
+//   Rust    `  3: app::server::handle`  +  `             at ./src/server.rs:42:9`
+//           `thread 'main' panicked at src/main.rs:10:5:`
+//   Go      `main.(*Server).handle(0xc000010000)`  +  `\t/app/server.go:42 +0x1d`
+//   Python  `  File "/app/server.py", line 42, in handle`
+//   JS      `    at Server.handle (/app/server.js:42:9)` / `    at /app/server.js:42:9`
+//           `handle@/app/server.js:42:9` (Firefox)
+
+/// One frame of a stack trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Frame {
+    /// Function as printed in the trace, when the format includes one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    pub file: String,
+    pub line: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<i64>,
+}
+
+/// Parse every frame in `text`, innermost (the failing call) first. Python
+/// tracebacks print the innermost frame last and are reversed.
+pub fn parse_stacktrace(text: &str) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    let mut python = false;
+    // Rust backtraces and Go panics name the function on the line before
+    // the location.
+    let mut pending_function: Option<String> = None;
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if let Some(frame) = python_frame(line) {
+            python = true;
+            frames.push(frame);
+            pending_function = None;
+        } else if let Some(loc) = line.strip_prefix("at ").filter(|_| pending_function.is_some() || !is_js(line)) {
+            // Rust `at path:line:col` following a numbered frame
+            if let Some((file, line, column)) = split_location(loc) {
+                frames.push(Frame { function: pending_function.take(), file, line, column });
+            }
+        } else if let Some(frame) = js_frame(line) {
+            frames.push(frame);
+            pending_function = None;
+        } else if let Some(frame) = rust_panic_frame(line) {
+            frames.push(frame);
+        } else if let Some(frame) = go_location(raw, &mut pending_function) {
+            frames.push(frame);
+        } else if let Some(function) = rust_numbered_frame(line).or_else(|| go_function(line)) {
+            pending_function = Some(function);
+        }
+    }
+
+    if python {
+        frames.reverse();
+    }
+    frames
+}
+
+/// Bare function name for matching against symbols: module paths, receivers,
+/// Rust hash suffixes and closure markers are dropped.
+/// `app::Server::handle::h0123456789abcdef` → `handle`,
+/// `main.(*Server).handle` → `handle`, `Server.handle` → `handle`.
+pub fn short_name(function: &str) -> &str {
+    let segments: Vec<&str> = function
+        .split("::")
+        .filter(|s| !s.is_empty() && !s.starts_with("{{") && !is_rust_hash(s))
+        .collect();
+    let last = segments.last().copied().unwrap_or(function);
+    let last = last.rsplit('.').next().unwrap_or(last);
+    last.trim_start_matches("new ").trim_start_matches("async ")
+}
+
+fn is_rust_hash(segment: &str) -> bool {
+    segment.len() == 17 && segment.starts_with('h') && segment[1..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn is_js(line: &str) -> bool {
+    line.ends_with(')') || line.contains("file://") || [".js:", ".ts:", ".mjs:", ".cjs:", ".tsx:", ".jsx:"].iter().any(|e| line.contains(e))
+}
+
+/// `path:line:col` or `path:line` (Windows drive letters allowed). Node.js
+/// internals (`node:internal/...`) have no source to map and yield None.
+fn split_location(loc: &str) -> Option<(String, i64, Option<i64>)> {
+    let loc = loc.trim().trim_end_matches(':');
+    if loc.starts_with("node:") {
+        return None;
+    }
+    let loc = loc.strip_prefix("file://").unwrap_or(loc);
+    let (rest, last) = loc.rsplit_once(':')?;
+    let last: i64 = last.parse().ok()?;
+    let (file, line, column) = match rest.rsplit_once(':') {
+        Some((file, line)) if line.parse::<i64>().is_ok() => (file, line.parse().ok()?, Some(last)),
+        _ => (rest, last, None),
+    };
+    (!file.is_empty()).then(|| (file.to_string(), line, column))
+}
+
+/// `File "/app/server.py", line 42, in handle`
+fn python_frame(line: &str) -> Option<Frame> {
+    let rest = line.strip_prefix("File \"")?;
+    let (file, rest) = rest.split_once('"')?;
+    let rest = rest.trim_start_matches(',').trim().strip_prefix("line ")?;
+    let (line_no, rest) = rest.split_once(',').unwrap_or((rest, ""));
+    let function = rest.trim().strip_prefix("in ").map(|f| f.trim().to_string());
+    Some(Frame { function, file: file.to_string(), line: line_no.trim().parse().ok()?, column: None })
+}
+
+/// `at Server.handle (/app/server.js:42:9)`, `at /app/server.js:42:9`,
+/// `handle@/app/server.js:42:9`
+fn js_frame(line: &str) -> Option<Frame> {
+    if let Some(rest) = line.strip_prefix("at ") {
+        let (function, loc) = match rest.strip_suffix(')').and_then(|r| r.rsplit_once(" (")) {
+            Some((function, loc)) => (Some(function.trim_start_matches("async ").to_string()), loc),
+            None => (None, rest.trim_start_matches("async ")),
+        };
+        let (file, line, column) = split_location(loc)?;
+        return Some(Frame { function, file, line, column });
+    }
+    let (function, loc) = line.split_once('@')?;
+    if function.contains(char::is_whitespace) {
+        return None;
+    }
+    let (file, line, column) = split_location(loc)?;
+    Some(Frame { function: (!function.is_empty()).then(|| function.to_string()), file, line, column })
+}
+
+/// `thread 'main' panicked at src/main.rs:10:5:` (Rust ≥ 1.73) or
+/// `thread 'main' panicked at 'boom', src/main.rs:10:5`.
+fn rust_panic_frame(line: &str) -> Option<Frame> {
+    let (_, rest) = line.split_once("panicked at ")?;
+    let loc = match rest.rsplit_once("', ") {
+        Some((_, loc)) => loc,
+        None => rest,
+    };
+    let (file, line, column) = split_location(loc)?;
+    Some(Frame { function: None, file, line, column })
+}
+
+/// `  3: app::server::handle` → `app::server::handle`
+fn rust_numbered_frame(line: &str) -> Option<String> {
+    let (index, function) = line.split_once(": ")?;
+    if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(function.trim().to_string())
+}
+
+/// `main.(*Server).handle(0xc000010000, 0x1)` → `main.(*Server).handle`
+fn go_function(line: &str) -> Option<String> {
+    if line.starts_with("goroutine ") || !line.ends_with(')') {
+        return None;
+    }
+    let open = line.rfind('(')?;
+    let name = &line[..open];
+    (name.contains('.') && !name.contains(' ')).then(|| name.to_string())
+}
+
+/// `\t/app/server.go:42 +0x1d`, paired with the function line above it.
+fn go_location(raw: &str, pending_function: &mut Option<String>) -> Option<Frame> {
+    if !raw.starts_with('\t') {
+        return None;
+    }
+    let loc = raw.trim().split(" +0x").next()?;
+    if !loc.ends_with(|c: char| c.is_ascii_digit()) || !loc.contains(".go:") {
+        return None;
+    }
+    let (file, line, column) = split_location(loc)?;
+    Some(Frame { function: pending_function.take(), file, line, column })
+}
+
+// ---------------------------------------------------------------------------
+// Resolution against the index
+// ---------------------------------------------------------------------------
+
+/// The indexed symbol a frame points into.
+#[derive(Debug, Serialize)]
+pub struct FrameSymbol {
+    #[serde(skip)]
+    pub symbol_id: i64,
+    pub repo: String,
+    pub file_path: String,
+    pub name: String,
+    pub kind: String,
+    pub signature: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    /// Full body, while the token budget lasts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Why `body` is absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolvedFrame {
+    #[serde(flatten)]
+    pub frame: Frame,
+    /// None for frames outside the index (standard library, dependencies).
+    pub symbol: Option<FrameSymbol>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StackResolution {
+    /// Innermost first.
+    pub frames: Vec<ResolvedFrame>,
+    pub resolved: usize,
+    pub total_tokens: usize,
+}
+
+/// Parse `trace` and map each frame to the innermost indexed symbol at its
+/// file and line. Bodies are attached innermost-first until `max_tokens` is
+/// spent; symbols in `sent` (already delivered this session) and repeats
+/// (recursion) get a note instead.
+pub fn resolve_stacktrace(
+    db: &Database,
+    trace: &str,
+    max_tokens: usize,
+    sent: &HashSet<i64>,
+) -> Result<StackResolution> {
+    let mut frames = Vec::new();
+    let mut total_tokens = 0;
+    let mut first_frame_of: HashMap<i64, usize> = HashMap::new();
+
+    for (index, frame) in parse_stacktrace(trace).into_iter().enumerate() {
+        let mut symbol = resolve_frame(db, &frame)?;
+        if let Some(sym) = symbol.as_mut() {
+            total_tokens += estimate_tokens(&sym.signature) + estimate_tokens(&sym.file_path) + 16;
+            let body = sym.body.take().unwrap_or_default();
+            if let Some(first) = first_frame_of.get(&sym.symbol_id) {
+                sym.note = Some(format!("same symbol as frame {first}"));
+            } else if sent.contains(&sym.symbol_id) {
+                sym.note = Some("body sent earlier in this session".to_string());
+            } else if total_tokens + estimate_tokens(&body) > max_tokens {
+                sym.note = Some("body omitted: token budget exhausted".to_string());
+            } else {
+                total_tokens += estimate_tokens(&body);
+                sym.body = Some(body);
+            }
+            first_frame_of.entry(sym.symbol_id).or_insert(index);
+        }
+        frames.push(ResolvedFrame { frame, symbol });
+    }
+
+    let resolved = frames.iter().filter(|f| f.symbol.is_some()).count();
+    Ok(StackResolution { frames, resolved, total_tokens })
+}
+
+/// Resolve the frame's file by its longest indexed path suffix, then take
+/// the innermost symbol containing the line. A match on the bare file name
+/// alone is accepted only when the symbol name agrees with the frame's
+/// function, so `main.rs` from a dependency doesn't land in the wrong file.
+fn resolve_frame(db: &Database, frame: &Frame) -> Result<Option<FrameSymbol>> {
+    let Some((file, segments)) = resolve_path(db, &frame.file)? else {
+        return Ok(None);
+    };
+    let Some(sym) = db.symbols_at_line(file.file_id, frame.line)?.pop() else {
+        return Ok(None);
+    };
+    if segments == 1 && frame.file.contains(['/', '\\']) {
+        let matches_function = frame.function.as_deref().is_some_and(|f| short_name(f) == sym.name);
+        if !matches_function {
+            return Ok(None);
+        }
+    }
+    Ok(Some(FrameSymbol {
+        symbol_id: sym.id,
+        repo: file.repo,
+        file_path: file.file_path,
+        name: sym.name,
+        kind: sym.kind,
+        signature: sym.signature,
+        start_line: sym.start_line,
+        end_line: sym.end_line,
+        body: Some(sym.body),
+        note: None,
+    }))
+}
+
+/// The indexed file for `path` and how many trailing path segments matched.
+/// Only relative paths fall back to a suffix match: an absolute path outside
+/// every indexed root (the cargo registry, `/rustc/...`, site-packages)
+/// belongs to code that isn't indexed, whatever its file name.
+fn resolve_path(db: &Database, path: &str) -> Result<Option<(ResolvedFile, usize)>> {
+    if let Some(file) = db.resolve_file_path(path, None)? {
+        let segments = file.file_path.split('/').count();
+        return Ok(Some((file, segments)));
+    }
+    let normalized = crate::paths::normalize_path(path);
+    if crate::paths::is_absolute(&normalized) {
+        return Ok(None);
+    }
+    let parts: Vec<&str> = normalized.split('/').filter(|s| !s.is_empty()).collect();
+    for start in 1..parts.len() {
+        if let Some(file) = db.resolve_file_path(&parts[start..].join("/"), None)? {
+            return Ok(Some((file, parts.len() - start)));
+        }
+    }
+    Ok(None)
+}
+
+/// ~4 chars per token, as in the context engine.
+fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
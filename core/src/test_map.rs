@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+
+use crate::db::{Database, Symbol};
+
+/// Symbol kinds a test can be linked to.
+const TARGET_KINDS: &[&str] = &[
+    "function", "method", "struct", "class", "interface", "trait", "enum", "type",
+];
+
+/// A name match is trusted only when it is this unambiguous, unless the
+/// candidates sit next to the test.
+const MAX_NAME_MATCHES: usize = 3;
+
+// ---------------------------------------------------------------------------
+// Test detection
+// ---------------------------------------------------------------------------
+
+/// Files that hold only tests and their helpers: `*_test.go`, `test_*.py` /
+/// `*_test.py`, `*.test.ts` / `*.spec.ts`, Rust and Python files under a
+/// `tests/` directory, and anything under `__tests__/`.
+pub fn is_test_file(path: &str) -> bool {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let stem = file.split('.').next().unwrap_or(file);
+    let under = |dir: &str| path.starts_with(&format!("{dir}/")) || path.contains(&format!("/{dir}/"));
+    file.ends_with("_test.go")
+        || (file.ends_with(".py") && (stem.starts_with("test_") || stem.ends_with("_test")))
+        || ((file.ends_with(".rs") || file.ends_with(".py")) && under("tests"))
+        || file.contains(".test.")
+        || file.contains(".spec.")
+        || under("__tests__")
+}
+
+/// Whether `sym` is a test function: `#[test]`-style attributes in Rust
+/// (top-level functions only; items inside `mod tests` are not indexed),
+/// `Test*`/`Benchmark*`/`Fuzz*` in Go test files, `test*` in Python and
+/// TypeScript test files.
+pub fn is_test_symbol(sym: &Symbol, file_path: &str, language: &str) -> bool {
+    if !matches!(sym.kind.as_str(), "function" | "method") {
+        return false;
+    }
+    match language {
+        "rs" => sym.attributes.lines().any(|a| {
+            let inner = a.trim_start_matches("#[").trim_end_matches(']');
+            inner == "test" || inner.ends_with("::test") || inner.starts_with("tokio::test")
+        }),
+        "go" => {
+            is_test_file(file_path)
+                && ["Test", "Benchmark", "Fuzz"].iter().any(|p| sym.name.starts_with(p))
+        }
+        _ => is_test_file(file_path) && sym.name.to_lowercase().starts_with("test"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Naming heuristics
+// ---------------------------------------------------------------------------
+
+/// Candidate target names for a test, longest first, lower-cased with
+/// separators removed: `test_parse_header_rejects_empty` yields
+/// "parseheaderrejectsempty", "parseheaderrejects", "parseheader", "parse";
+/// `TestParseHeader` yields "parseheader", "parse".
+pub fn name_candidates(test_name: &str) -> Vec<String> {
+    let mut name = test_name;
+    for prefix in ["test_", "Test", "test", "Benchmark", "Fuzz"] {
+        if let Some(rest) = name.strip_prefix(prefix) {
+            name = rest;
+            break;
+        }
+    }
+    for suffix in ["_tests", "_test", "Test"] {
+        if let Some(rest) = name.strip_suffix(suffix) {
+            name = rest;
+            break;
+        }
+    }
+    let words = split_words(name);
+    (1..=words.len())
+        .rev()
+        .map(|n| words[..n].concat().to_lowercase())
+        .filter(|c| c.len() >= 3)
+        .collect()
+}
+
+fn split_words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '_' {
+            prev_lower = false;
+            words.push(String::new());
+            continue;
+        }
+        if words.is_empty() || (c.is_uppercase() && prev_lower) {
+            words.push(String::new());
+        }
+        words.last_mut().unwrap().push(c);
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+    }
+    words.retain(|w| !w.is_empty());
+    words
+}
+
+fn normalize(name: &str) -> String {
+    name.replace(['_', '.'], "").to_lowercase()
+}
+
+fn dir_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+// ---------------------------------------------------------------------------
+// Mapping
+// ---------------------------------------------------------------------------
+
+/// Link every test function in `repo_id` to the production symbols it
+/// exercises and store the result in `test_links`, replacing earlier links.
+/// A link is made for each symbol the test calls directly ("call") and for
+/// the symbols whose name matches the test's name ("name"); both yields
+/// "call+name". Name matches in the test's own file or directory win over
+/// matches elsewhere; ambiguous names are not linked. Returns the number of
+/// links stored.
+pub fn map_tests(db: &Database, repo_id: i64) -> Result<usize> {
+    let files = db.get_files_for_repo(repo_id)?;
+    // (test, its file, enclosing class or module name)
+    let mut tests: Vec<(Symbol, String, Option<String>)> = Vec::new();
+    let mut targets: HashSet<i64> = HashSet::new();
+    let mut by_name: HashMap<String, Vec<(i64, String)>> = HashMap::new();
+
+    for file in &files {
+        let symbols = db.get_symbols_by_file(file.id)?;
+        let by_id: HashMap<i64, &Symbol> = symbols.iter().map(|s| (s.id, s)).collect();
+        let test_file = is_test_file(&file.path);
+        for sym in &symbols {
+            if is_test_symbol(sym, &file.path, &file.language) {
+                let parent = sym.parent_id.and_then(|id| by_id.get(&id)).map(|p| p.name.clone());
+                tests.push((sym.clone(), file.path.clone(), parent));
+            } else if !test_file && TARGET_KINDS.contains(&sym.kind.as_str()) {
+                targets.insert(sym.id);
+                by_name
+                    .entry(normalize(&sym.name))
+                    .or_default()
+                    .push((sym.id, file.path.clone()));
+                // Go methods (`Server.Start`) also match by the bare method name.
+                if let Some((_, method)) = sym.name.split_once('.') {
+                    by_name.entry(normalize(method)).or_default().push((sym.id, file.path.clone()));
+                }
+            }
+        }
+    }
+
+    let mut links: Vec<(i64, i64, &str)> = Vec::new();
+    for (test, test_path, parent) in &tests {
+        let mut via: HashMap<i64, &str> = HashMap::new();
+        for (_, dep) in db.get_dependencies(test.id)? {
+            if targets.contains(&dep.id) {
+                via.insert(dep.id, "call");
+            }
+        }
+
+        let mut candidates = name_candidates(&test.name);
+        // Python/TS test classes name their subject: `class TestParser`.
+        if let Some(parent) = parent.as_deref().filter(|p| p.starts_with("Test")) {
+            candidates.extend(name_candidates(parent));
+        }
+        for candidate in candidates {
+            let Some(matches) = by_name.get(&candidate) else {
+                continue;
+            };
+            let near: Vec<i64> = matches
+                .iter()
+                .filter(|(_, path)| dir_of(path) == dir_of(test_path))
+                .map(|(id, _)| *id)
+                .collect();
+            let chosen = if !near.is_empty() {
+                near
+            } else if matches.len() <= MAX_NAME_MATCHES {
+                matches.iter().map(|(id, _)| *id).collect()
+            } else {
+                continue;
+            };
+            for id in chosen {
+                via.entry(id)
+                    .and_modify(|v| *v = "call+name")
+                    .or_insert("name");
+            }
+            break;
+        }
+
+        links.extend(via.into_iter().map(|(target, v)| (test.id, target, v)));
+    }
+
+    db.replace_test_links(repo_id, &links)?;
+    Ok(links.len())
+}
@@ -0,0 +1,229 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use tree_sitter::{Query, QueryCursor, StreamingIterator};
+
+use crate::db::{Database, Repository};
+use crate::encoding::decode_source;
+use crate::grammar::Grammar;
+
+/// Captured text longer than this is cut, so a `(function_item) @f` query
+/// does not return whole bodies.
+const MAX_CAPTURE_CHARS: usize = 300;
+
+// ---------------------------------------------------------------------------
+// Report types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeCapture {
+    pub name: String,
+    pub text: String,
+    /// 1-based.
+    pub start_line: usize,
+    /// 0-based byte column.
+    pub start_column: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeMatch {
+    pub repo: String,
+    pub file_path: String,
+    /// Index of the matching pattern within the query.
+    pub pattern: usize,
+    pub captures: Vec<TreeCapture>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeQueryReport {
+    pub files_scanned: usize,
+    pub total: usize,
+    /// True when `total` exceeded the requested limit and matches were dropped.
+    pub truncated: bool,
+    /// Set when a budget in `TreeQueryLimits` stopped the scan early
+    /// (`max_files`, `max_matches` or `timeout`). Files not scanned are not
+    /// counted in `total`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped: Option<&'static str>,
+    pub matches: Vec<TreeMatch>,
+}
+
+/// Bounds on one query. Files are read and parsed per request, so an
+/// unscoped query over a large index is capped rather than left to run.
+#[derive(Debug, Clone, Copy)]
+pub struct TreeQueryLimits {
+    /// Matches returned; more are counted in `total` only.
+    pub max_results: usize,
+    pub max_files: usize,
+    /// Matches counted before the scan stops.
+    pub max_matches: usize,
+    pub timeout: Duration,
+}
+
+impl TreeQueryLimits {
+    pub const DEFAULT_MAX_RESULTS: usize = 100;
+    pub const DEFAULT_MAX_FILES: usize = 20_000;
+    pub const DEFAULT_MAX_MATCHES: usize = 10_000;
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+}
+
+impl Default for TreeQueryLimits {
+    fn default() -> Self {
+        Self {
+            max_results: Self::DEFAULT_MAX_RESULTS,
+            max_files: Self::DEFAULT_MAX_FILES,
+            max_matches: Self::DEFAULT_MAX_MATCHES,
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// An indexed file a query reads, from `query_targets`.
+#[derive(Debug, Clone)]
+pub struct QueryTarget {
+    pub repo: String,
+    pub root: PathBuf,
+    pub file_path: String,
+}
+
+// ---------------------------------------------------------------------------
+// Execution
+// ---------------------------------------------------------------------------
+
+/// Run a tree-sitter query against the indexed files of `repos` written in
+/// `grammar`'s language, optionally restricted to files under `path_prefix`.
+/// See `query_targets` and `query_files`.
+pub fn run_tree_query(
+    db: &Database,
+    repos: &[Repository],
+    grammar: &dyn Grammar,
+    query_source: &str,
+    path_prefix: Option<&str>,
+    limits: &TreeQueryLimits,
+) -> Result<TreeQueryReport> {
+    let targets = query_targets(db, repos, grammar, path_prefix)?;
+    query_files(&targets, grammar, query_source, limits)
+}
+
+/// The indexed files of `repos` in `grammar`'s language, optionally under
+/// `path_prefix`. The only step that needs the database, so callers can
+/// release it before `query_files` reads and parses.
+pub fn query_targets(
+    db: &Database,
+    repos: &[Repository],
+    grammar: &dyn Grammar,
+    path_prefix: Option<&str>,
+) -> Result<Vec<QueryTarget>> {
+    let canonical = grammar.file_extensions()[0];
+    let prefix = path_prefix.map(crate::paths::normalize_path);
+    let mut targets = Vec::new();
+    for repo in repos {
+        for file in db.get_files_for_repo(repo.id)? {
+            if file.language != canonical {
+                continue;
+            }
+            if let Some(prefix) = &prefix {
+                if file.path != *prefix && !file.path.starts_with(&format!("{prefix}/")) {
+                    continue;
+                }
+            }
+            targets.push(QueryTarget {
+                repo: repo.name.clone(),
+                root: PathBuf::from(&repo.root_path),
+                file_path: file.path,
+            });
+        }
+    }
+    Ok(targets)
+}
+
+/// Run a tree-sitter query against `targets`, read from disk at request
+/// time, within `limits`. Captures whose name starts with `_` are used for
+/// matching only and left out of the report. Captured text has secrets
+/// masked, as stored symbol bodies do, and is truncated.
+pub fn query_files(
+    targets: &[QueryTarget],
+    grammar: &dyn Grammar,
+    query_source: &str,
+    limits: &TreeQueryLimits,
+) -> Result<TreeQueryReport> {
+    let started = Instant::now();
+    let language = grammar.language();
+    let query = Query::new(&language, query_source).map_err(|e| anyhow!("{e}"))?;
+    let capture_names = query.capture_names();
+
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&language)?;
+    let mut report = TreeQueryReport {
+        files_scanned: 0,
+        total: 0,
+        truncated: false,
+        stopped: None,
+        matches: Vec::new(),
+    };
+
+    'files: for target in targets {
+        if report.files_scanned >= limits.max_files {
+            report.stopped = Some("max_files");
+            break;
+        }
+        if started.elapsed() >= limits.timeout {
+            report.stopped = Some("timeout");
+            break;
+        }
+        let Ok(raw) = std::fs::read(target.root.join(&target.file_path)) else {
+            continue;
+        };
+        let source = decode_source(&raw).text;
+        let Some(tree) = parser.parse(&source, None) else {
+            continue;
+        };
+        report.files_scanned += 1;
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&query, tree.root_node(), &source[..]);
+        while let Some(m) = matches.next() {
+            if report.total >= limits.max_matches {
+                report.stopped = Some("max_matches");
+                break 'files;
+            }
+            report.total += 1;
+            if report.matches.len() >= limits.max_results {
+                continue;
+            }
+            let captures = m
+                .captures
+                .iter()
+                .filter(|c| !capture_names[c.index as usize].starts_with('_'))
+                .map(|c| TreeCapture {
+                    name: capture_names[c.index as usize].to_string(),
+                    text: capture_text(c.node.utf8_text(&source).unwrap_or_default()),
+                    start_line: c.node.start_position().row + 1,
+                    start_column: c.node.start_position().column,
+                    end_line: c.node.end_position().row + 1,
+                })
+                .collect();
+            report.matches.push(TreeMatch {
+                repo: target.repo.clone(),
+                file_path: target.file_path.clone(),
+                pattern: m.pattern_index,
+                captures,
+            });
+        }
+    }
+    report.truncated = report.total > report.matches.len();
+    Ok(report)
+}
+
+/// Mask secrets, then truncate; cutting first could leave the start of a
+/// secret that no longer matches its pattern.
+fn capture_text(text: &str) -> String {
+    let masked = crate::redact::redact(text).0;
+    match masked.char_indices().nth(MAX_CAPTURE_CHARS) {
+        Some((end, _)) => format!("{}…", &masked[..end]),
+        None => masked.into_owned(),
+    }
+}
@@ -109,7 +109,7 @@ fn collect_file_paths(event: &Event, out: &mut HashSet<PathBuf>) {
         match path.symlink_metadata() {
             Ok(meta) if meta.is_dir() => continue,
             _ => {
-                out.insert(path.clone());
+                out.insert(crate::paths::simplify_verbatim(path.clone()));
             }
         }
     }
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::db::{DataVersion, Database};
+use crate::proximity::Focus;
+
+// ---------------------------------------------------------------------------
+// Session working set
+// ---------------------------------------------------------------------------
+//
+// The symbols and files an MCP session has looked at: every symbol whose body
+// was sent (which covers the symbols linked to the session's auto
+// observations) and every file opened as a skeleton or symbol listing.
+// get_context biases its pivots toward this set, on the assumption that the
+// agent's next question is about the code it has been reading. The focus is
+// rebuilt only when its symbols or files change or the index is written.
+
+/// Symbols used as graph roots when the working set becomes a focus.
+pub const MAX_FOCUS_SYMBOLS: usize = 50;
+/// Files used as focus files.
+pub const MAX_FOCUS_FILES: usize = 50;
+/// Symbols, and separately files, remembered per session; touching one more
+/// forgets the least recently touched.
+pub const MAX_TRACKED: usize = 1_000;
+
+/// Symbols and files touched this session, with when they were last touched.
+#[derive(Debug, Default)]
+pub struct WorkingSet {
+    /// Symbol id → tick of the last touch.
+    symbols: HashMap<i64, u64>,
+    /// File id → tick of the last touch.
+    files: HashMap<i64, u64>,
+    tick: u64,
+    /// The last focus built, with the ids and index version it was built for.
+    focus: Option<CachedFocus>,
+}
+
+#[derive(Debug)]
+struct CachedFocus {
+    symbols: Vec<i64>,
+    files: Vec<i64>,
+    version: DataVersion,
+    focus: Option<Focus>,
+}
+
+impl WorkingSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn touch_symbols(&mut self, ids: impl IntoIterator<Item = i64>) {
+        for id in ids {
+            self.tick += 1;
+            self.symbols.insert(id, self.tick);
+            forget_oldest(&mut self.symbols);
+        }
+    }
+
+    pub fn touch_file(&mut self, file_id: i64) {
+        self.tick += 1;
+        self.files.insert(file_id, self.tick);
+        forget_oldest(&mut self.files);
+    }
+
+    /// Up to `limit` symbol ids, most recently touched first.
+    pub fn recent_symbols(&self, limit: usize) -> Vec<i64> {
+        recent(&self.symbols, limit)
+    }
+
+    /// File ids, most recently touched first.
+    pub fn files(&self) -> Vec<i64> {
+        recent(&self.files, usize::MAX)
+    }
+
+    pub fn symbol_count(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty() && self.files.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+        self.files.clear();
+        self.focus = None;
+    }
+
+    /// The `MAX_FOCUS_SYMBOLS` most recent symbols and `MAX_FOCUS_FILES` most
+    /// recent files as a `Focus`, reused while neither they nor the index
+    /// change. None when the working set is empty.
+    pub fn focus(&mut self, db: &Database) -> Result<Option<Focus>> {
+        let mut symbols = self.recent_symbols(MAX_FOCUS_SYMBOLS);
+        let mut files = recent(&self.files, MAX_FOCUS_FILES);
+        symbols.sort_unstable();
+        files.sort_unstable();
+        let version = db.data_version()?;
+        if let Some(cached) = &self.focus {
+            if cached.version == version && cached.symbols == symbols && cached.files == files {
+                return Ok(cached.focus.clone());
+            }
+        }
+        let focus = Focus::from_ids(db, &symbols, &files)?;
+        self.focus = Some(CachedFocus { symbols, files, version, focus: focus.clone() });
+        Ok(focus)
+    }
+}
+
+/// Drop the least recently touched entry once `touched` is over `MAX_TRACKED`.
+fn forget_oldest(touched: &mut HashMap<i64, u64>) {
+    if touched.len() <= MAX_TRACKED {
+        return;
+    }
+    if let Some(oldest) = touched.iter().min_by_key(|&(_, &tick)| tick).map(|(&id, _)| id) {
+        touched.remove(&oldest);
+    }
+}
+
+fn recent(touched: &HashMap<i64, u64>, limit: usize) -> Vec<i64> {
+    let mut ids: Vec<(i64, u64)> = touched.iter().map(|(&id, &tick)| (id, tick)).collect();
+    ids.sort_by_key(|&(_, tick)| std::cmp::Reverse(tick));
+    ids.into_iter().take(limit).map(|(id, _)| id).collect()
+}
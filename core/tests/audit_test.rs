@@ -0,0 +1,131 @@
+use focal_core::audit::{
+    admin_response, bearer_token, token_fingerprint, token_matches, AuditEntry, AuditFilter, MAX_AUDIT_LIMIT,
+};
+use focal_core::db::Database;
+
+fn entry(session: &str, token: Option<&str>, tool: &str) -> AuditEntry {
+    AuditEntry {
+        session_id: session.to_string(),
+        token: token.map(token_fingerprint),
+        tool: tool.to_string(),
+        params: r#"{"query":"Main"}"#.to_string(),
+        outcome: "ok".to_string(),
+        response_tokens: 42,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 1. Entries round-trip and filter by session, token and tool, newest first
+// ---------------------------------------------------------------------------
+#[test]
+fn test_record_and_query_audit_log() {
+    let db = Database::open_in_memory().unwrap();
+    db.record_audit(&entry("s1", Some("alice"), "search_code")).unwrap();
+    db.record_audit(&entry("s1", Some("alice"), "get_context")).unwrap();
+    db.record_audit(&entry("s2", Some("bob"), "search_code")).unwrap();
+
+    let all = db.query_audit_log(&AuditFilter::default()).unwrap();
+    assert_eq!(all.len(), 3);
+    assert!(all[0].id > all[2].id, "newest first");
+    assert_eq!(all[2].params, r#"{"query":"Main"}"#);
+    assert_eq!(all[2].response_tokens, 42);
+    assert!(!all[2].at.is_empty());
+
+    let by_session = AuditFilter { session_id: Some("s1".into()), ..Default::default() };
+    assert_eq!(db.query_audit_log(&by_session).unwrap().len(), 2);
+    let by_token = AuditFilter { token: Some(token_fingerprint("bob")), ..Default::default() };
+    assert_eq!(db.query_audit_log(&by_token).unwrap()[0].session_id, "s2");
+    let by_tool = AuditFilter { tool: Some("search_code".into()), limit: Some(1), ..Default::default() };
+    assert_eq!(db.query_audit_log(&by_tool).unwrap().len(), 1);
+    let future = AuditFilter { since: Some("2999-01-01".into()), ..Default::default() };
+    assert!(db.query_audit_log(&future).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 2. The table is append-only and audit writes don't invalidate the cache
+// ---------------------------------------------------------------------------
+#[test]
+fn test_audit_log_is_append_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.db");
+    let db = Database::open(path.to_str().unwrap()).unwrap();
+    let before = db.data_version().unwrap();
+    db.record_audit(&entry("s1", None, "search_code")).unwrap();
+    assert_eq!(db.data_version().unwrap(), before);
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    assert!(conn.execute("UPDATE audit_log SET tool = 'x'", []).is_err());
+    assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+    let tool: String = conn.query_row("SELECT tool FROM audit_log", [], |row| row.get(0)).unwrap();
+    assert_eq!(tool, "search_code");
+}
+
+// ---------------------------------------------------------------------------
+// 3. Tokens are fingerprinted; bearer headers parse case-insensitively
+// ---------------------------------------------------------------------------
+#[test]
+fn test_token_helpers() {
+    let fp = token_fingerprint("secret-token");
+    assert!(fp.starts_with("sha256:"));
+    assert_eq!(fp.len(), "sha256:".len() + 12);
+    assert!(!fp.contains("secret"));
+    assert_eq!(fp, token_fingerprint("secret-token"));
+
+    assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+    assert_eq!(bearer_token("bearer  abc "), Some("abc"));
+    assert_eq!(bearer_token("Basic abc"), None);
+    assert_eq!(bearer_token("Bearer "), None);
+
+    assert!(token_matches("abc", "abc"));
+    assert!(!token_matches("abd", "abc"));
+    assert!(!token_matches("ab", "abc"));
+}
+
+// ---------------------------------------------------------------------------
+// 4. The admin endpoint requires the configured token and parses filters
+// ---------------------------------------------------------------------------
+#[test]
+fn test_admin_response() {
+    let db = Database::open_in_memory().unwrap();
+    db.record_audit(&entry("s1", Some("alice"), "search_code")).unwrap();
+    db.record_audit(&entry("s2", Some("bob"), "get_context")).unwrap();
+
+    assert_eq!(admin_response(&db, None, Some("Bearer x"), "").0, 403);
+    assert_eq!(admin_response(&db, Some("admin"), None, "").0, 401);
+    assert_eq!(admin_response(&db, Some("admin"), Some("Bearer nope"), "").0, 401);
+    assert_eq!(admin_response(&db, Some("admin"), Some("Bearer admin"), "limit=x").0, 400);
+
+    let (status, body) = admin_response(&db, Some("admin"), Some("Bearer admin"), "tool=get_context");
+    assert_eq!(status, 200);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let entries = json["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["session_id"], "s2");
+
+    let filter = AuditFilter::from_query("session=a%20b&limit=5000&other=1").unwrap();
+    assert_eq!(filter.session_id.as_deref(), Some("a b"));
+    assert_eq!(filter.effective_limit(), MAX_AUDIT_LIMIT);
+}
+
+// ---------------------------------------------------------------------------
+// 5. Rows past the retention period are purged; the table stays append-only
+// ---------------------------------------------------------------------------
+#[test]
+fn test_purge_audit_log() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.db");
+    let db = Database::open(path.to_str().unwrap()).unwrap();
+    db.record_audit(&entry("s1", None, "search_code")).unwrap();
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute(
+        "INSERT INTO audit_log (at, session_id, tool, params, outcome)
+         VALUES (datetime('now', '-100 days'), 'old', 'get_context', '{}', 'ok')",
+        [],
+    )
+    .unwrap();
+
+    assert_eq!(db.purge_audit_log(90).unwrap(), 1);
+    let left = db.query_audit_log(&AuditFilter::default()).unwrap();
+    assert_eq!(left.iter().map(|r| r.session_id.as_str()).collect::<Vec<_>>(), vec!["s1"]);
+    assert!(conn.execute("DELETE FROM audit_log", []).is_err(), "no-delete trigger restored");
+}
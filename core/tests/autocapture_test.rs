@@ -44,15 +44,15 @@ fn test_auto_capture_multiple_sources() {
         .unwrap();
 
     // All four memories exist
-    let all = db.list_memories("", false, "").unwrap();
+    let all = db.list_memories("", false, false, "").unwrap();
     assert_eq!(all.len(), 4);
 
     // Three are observations
-    let obs = db.list_memories("observation", false, "").unwrap();
+    let obs = db.list_memories("observation", false, false, "").unwrap();
     assert_eq!(obs.len(), 3);
 
     // One is manual
-    let notes = db.list_memories("note", false, "").unwrap();
+    let notes = db.list_memories("note", false, false, "").unwrap();
     assert_eq!(notes.len(), 1);
     assert_eq!(notes[0].content, "This function handles retries");
 
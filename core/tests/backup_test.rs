@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use focal_core::backup::{create, due, latest, list, restore, restore_index, IndexRestore};
+use focal_core::config::FocalConfig;
+use focal_core::db::{Database, SearchFilter};
+use tempfile::TempDir;
+
+fn open(dir: &TempDir) -> Database {
+    Database::open(dir.path().join("index.db").to_str().unwrap()).unwrap()
+}
+
+fn memories(path: &Path) -> Vec<String> {
+    memories_of(&Database::open_read_only(path.to_str().unwrap()).unwrap())
+}
+
+fn memories_of(db: &Database) -> Vec<String> {
+    db.list_memories("", true, false, "").unwrap().into_iter().map(|m| m.content).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Snapshots hold the memories and rotate down to `keep`
+// ---------------------------------------------------------------------------
+#[test]
+fn test_backup_and_rotate() {
+    let home = TempDir::new().unwrap();
+    let backups = home.path().join("backups");
+    let db = open(&home);
+    db.save_memory("orders are soft-deleted", "decision", &[]).unwrap();
+    assert!(list(&backups).unwrap().is_empty(), "a missing directory has no backups");
+
+    let first = create(&db, &backups, 2, None).unwrap();
+    assert_eq!(first.memories, 1);
+    assert!(first.bytes > 0);
+    let name = first.path.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("index-") && name.ends_with("Z.db") && name.len() == "index-20261017T093005.123Z.db".len(), "{name}");
+    assert_eq!(memories(&first.path), ["orders are soft-deleted"]);
+    assert!(!first.path.with_extension("db-wal").exists(), "snapshots are a single file");
+
+    db.save_memory("refunds go through the ledger", "decision", &[]).unwrap();
+    let second = create(&db, &backups, 2, None).unwrap();
+    let third = create(&db, &backups, 2, None).unwrap();
+    assert_eq!(third.removed, [first.path]);
+    assert_eq!(list(&backups).unwrap(), [second.path.clone(), third.path.clone()]);
+    assert_eq!(latest(&backups).unwrap(), Some(third.path));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Restore brings a snapshot back and keeps the replaced index
+// ---------------------------------------------------------------------------
+#[test]
+fn test_restore() {
+    let home = TempDir::new().unwrap();
+    let backups = home.path().join("backups");
+    let mut db = open(&home);
+    let repo_id = db.upsert_repository("shop", "/tmp/shop").unwrap();
+    let file_id = db.upsert_file(repo_id, "orders.go", "go", "h").unwrap();
+    db.insert_symbol(file_id, "CreateOrder", "", "function", "func CreateOrder()", "", "", 1, 3, None).unwrap();
+    let kept = db.save_memory("orders are soft-deleted", "decision", &[]).unwrap();
+    let snapshot = create(&db, &backups, 5, None).unwrap();
+
+    db.delete_memory(kept).unwrap();
+    db.save_memory("written after the backup", "note", &[]).unwrap();
+    let restored = restore(&mut db, &snapshot.path, &backups, 5, None).unwrap();
+    assert_eq!(restored.memories, 1);
+
+    let now: Vec<String> = db.list_memories("", true, false, "").unwrap().into_iter().map(|m| m.content).collect();
+    assert_eq!(now, ["orders are soft-deleted"]);
+    assert_eq!(db.search_code("CreateOrder", &SearchFilter::default(), 10).unwrap().len(), 1);
+    assert!(db.get_health().unwrap().fts_ok);
+    assert_eq!(memories(&restored.previous.path), ["written after the backup"]);
+    assert_eq!(list(&backups).unwrap(), [snapshot.path, restored.previous.path]);
+
+    // A file that isn't a database is refused before anything is touched.
+    let junk = home.path().join("junk.db");
+    std::fs::write(&junk, "not a database").unwrap();
+    assert!(restore(&mut db, &junk, &backups, 5, None).is_err());
+    assert_eq!(list(&backups).unwrap().len(), 2);
+    assert_eq!(db.count_memories().unwrap(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Scheduled backups run when none is recent enough
+// ---------------------------------------------------------------------------
+#[test]
+fn test_due() {
+    let home = TempDir::new().unwrap();
+    let backups = home.path().join("backups");
+    assert!(due(&backups, 24).unwrap());
+    assert!(!due(&backups, 0).unwrap(), "0 disables scheduled backups");
+    create(&open(&home), &backups, 5, None).unwrap();
+    assert!(!due(&backups, 24).unwrap());
+}
+
+// ---------------------------------------------------------------------------
+// 4. [backup] settings
+// ---------------------------------------------------------------------------
+#[test]
+fn test_config() {
+    let defaults = FocalConfig::default().backup;
+    assert_eq!((defaults.keep(), defaults.interval_hours()), (7, 24));
+    assert!(defaults.dir().ends_with("backups"));
+    let config: FocalConfig = toml::from_str("[backup]\ndir = \"/srv/focal\"\nkeep = 0\ninterval_hours = 6\n").unwrap();
+    assert_eq!(config.backup.dir(), Path::new("/srv/focal"));
+    assert_eq!((config.backup.keep(), config.backup.interval_hours()), (1, 6), "at least one backup is kept");
+}
+
+// ---------------------------------------------------------------------------
+// 5. A corrupt index is set aside and the snapshot copied into place
+// ---------------------------------------------------------------------------
+#[test]
+fn test_restore_corrupt_index() {
+    let home = TempDir::new().unwrap();
+    let backups = home.path().join("backups");
+    let db = open(&home);
+    db.save_memory("orders are soft-deleted", "decision", &[]).unwrap();
+    let snapshot = create(&db, &backups, 5, None).unwrap();
+    drop(db);
+
+    let db_path = home.path().join("index.db");
+    std::fs::write(&db_path, "garbage, not a database").unwrap();
+    std::fs::write(home.path().join("index.db-wal"), "stale wal").unwrap();
+    let IndexRestore::SetAside { memories, corrupt, .. } =
+        restore_index(&db_path, &snapshot.path, &backups, 5, None).unwrap()
+    else {
+        panic!("a garbage index can't be backed up");
+    };
+    assert_eq!(memories, 1);
+    assert!(corrupt.file_name().unwrap().to_str().unwrap().starts_with("index.db.corrupt-"));
+    assert_eq!(std::fs::read_to_string(&corrupt).unwrap(), "garbage, not a database");
+    let wal = corrupt.with_file_name(format!("{}-wal", corrupt.file_name().unwrap().to_str().unwrap()));
+    assert_eq!(std::fs::read_to_string(wal).unwrap(), "stale wal");
+    assert!(!home.path().join("index.db-wal").exists());
+    assert_eq!(memories_of(&open(&home)), ["orders are soft-deleted"]);
+
+    // A healthy index is backed up and replaced as with `restore`.
+    let restored = restore_index(&db_path, &snapshot.path, &backups, 5, None).unwrap();
+    assert!(matches!(restored, IndexRestore::Replaced(_)));
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs;
+
+use focal_core::barrels::{resolve_module, ModuleLinks, NamedExport};
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn write(dir: &TempDir, rel_path: &str, content: &str) {
+    let full = dir.path().join(rel_path);
+    fs::create_dir_all(full.parent().unwrap()).unwrap();
+    fs::write(full, content).unwrap();
+}
+
+/// `app.ts` imports through `utils/index.ts`, which re-exports from two
+/// modules; `legacy.ts` declares another `parse` that must not be picked.
+fn write_repo(dir: &TempDir) {
+    write(dir, "legacy.ts", "export function parse(s: string) {}\n");
+    write(dir, "utils/parse.ts", "export function parse(s: string) {}\nexport default function tokenize() {}\n");
+    write(dir, "utils/format.ts", "export function render() {}\nfunction pad() {}\nexport { pad as padLeft };\n");
+    write(
+        dir,
+        "utils/index.ts",
+        "export * from './parse';\n\
+         export { render as display } from './format';\n\
+         export { padLeft } from './format.js';\n\
+         import tokenize from './parse';\n\
+         export { tokenize };\n",
+    );
+    write(
+        dir,
+        "app.ts",
+        "import { parse as parseInput, display, padLeft, tokenize } from './utils';\n\n\
+         export function main() {\n    parseInput('x');\n    display();\n    padLeft();\n    tokenize();\n}\n",
+    );
+}
+
+fn callees(db: &Database, name: &str) -> Vec<(String, String)> {
+    let sym = db.find_symbol_by_name_any(name).unwrap().unwrap();
+    let mut found: Vec<(String, String)> = db
+        .get_dependencies(sym.id)
+        .unwrap()
+        .into_iter()
+        .filter(|(edge, _)| edge.kind == "calls")
+        .map(|(_, s)| (s.name, s.qualified_name))
+        .collect();
+    found.sort();
+    found
+}
+
+// ---------------------------------------------------------------------------
+// 1. Import and re-export statements
+// ---------------------------------------------------------------------------
+#[test]
+fn test_module_links() {
+    let links = ModuleLinks::parse([
+        ("imports", "import React, { useState as useS, type FC } from 'react';"),
+        ("imports", "import * as fs from 'fs'"),
+        ("imports", "export * from './a'"),
+        ("imports", "export * as b from './b'"),
+        ("imports", "export { x, y as z, default as D } from \"./c\""),
+        ("exports", "export { local as renamed };"),
+        ("calls", "ignored"),
+    ]);
+    let imports: HashMap<&str, (&str, &str)> = links
+        .imports
+        .iter()
+        .map(|(local, (spec, name))| (local.as_str(), (spec.as_str(), name.as_str())))
+        .collect();
+    assert_eq!(
+        imports,
+        HashMap::from([
+            ("React", ("react", "default")),
+            ("useS", ("react", "useState")),
+            ("FC", ("react", "FC")),
+        ])
+    );
+    assert_eq!(links.star, ["./a"]);
+    let export = |exported: &str, imported: &str, spec: Option<&str>| NamedExport {
+        exported: exported.into(),
+        imported: imported.into(),
+        spec: spec.map(String::from),
+    };
+    assert_eq!(
+        links.named,
+        [
+            export("x", "x", Some("./c")),
+            export("z", "y", Some("./c")),
+            export("D", "default", Some("./c")),
+            export("renamed", "local", None),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 2. Relative specifiers resolve to repository files
+// ---------------------------------------------------------------------------
+#[test]
+fn test_resolve_module() {
+    let files = ["src/app.ts", "src/utils/index.ts", "src/lib.tsx", "shared/api.ts"];
+    let exists = |p: &str| files.contains(&p);
+    assert_eq!(resolve_module("src/app.ts", "./utils", &exists).as_deref(), Some("src/utils/index.ts"));
+    assert_eq!(resolve_module("src/app.ts", "./lib", &exists).as_deref(), Some("src/lib.tsx"));
+    assert_eq!(resolve_module("src/app.ts", "./lib.js", &exists).as_deref(), Some("src/lib.tsx"));
+    assert_eq!(resolve_module("src/utils/index.ts", "../../shared/api", &exists).as_deref(), Some("shared/api.ts"));
+    assert_eq!(resolve_module("src/app.ts", "./missing", &exists), None);
+    assert_eq!(resolve_module("src/app.ts", "react", &exists), None);
+    assert_eq!(resolve_module("app.ts", "../outside", &exists), None);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Calls through a barrel link to the declaring module's symbol
+// ---------------------------------------------------------------------------
+#[test]
+fn test_calls_follow_reexports() {
+    let dir = TempDir::new().unwrap();
+    write_repo(&dir);
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let expected = vec![
+        ("pad".to_string(), "format.pad".to_string()),
+        ("parse".to_string(), "parse.parse".to_string()),
+        ("render".to_string(), "format.render".to_string()),
+        ("tokenize".to_string(), "parse.tokenize".to_string()),
+    ];
+    assert_eq!(callees(&db, "main"), expected);
+
+    // get_dependents on the original declaration finds the consumer.
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+    let names = db.get_all_symbol_names_for_repo(repo_id).unwrap();
+    let dependents: Vec<String> = db
+        .get_dependents(names["parse.parse"])
+        .unwrap()
+        .into_iter()
+        .map(|(_, s)| s.name)
+        .collect();
+    assert_eq!(dependents, ["main"]);
+
+    // Re-indexing the consumer alone resolves the same way.
+    write(
+        &dir,
+        "app.ts",
+        "import { parse as parseInput, display, padLeft, tokenize } from './utils';\n\n\
+         export function main() {\n    parseInput('y');\n    display();\n    padLeft();\n    tokenize();\n}\n",
+    );
+    Indexer::new(&db, &registry).index_file(&root.join("app.ts"), &root).unwrap();
+    assert_eq!(callees(&db, "main"), expected);
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use focal_core::bench::run_bench;
+use focal_core::db::{Database, SearchFilter};
+use focal_core::fixture::{function_name, generate_repo, FixtureSpec};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+// ---------------------------------------------------------------------------
+// 1. Generated repositories index to the expected shape
+// ---------------------------------------------------------------------------
+#[test]
+fn test_fixture_indexes_every_function_and_call() {
+    let dir = TempDir::new().unwrap();
+    let spec = FixtureSpec { files: 8, functions_per_file: 5 };
+    let summary = generate_repo(dir.path(), &spec).unwrap();
+    assert_eq!((summary.files, summary.functions, summary.calls), (8, 40, 39));
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let stats = Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert!(stats.errors.is_empty(), "{:?}", stats.errors);
+    assert_eq!(stats.files_indexed, 8);
+    assert_eq!(stats.symbols_extracted, 40);
+    assert_eq!(stats.edges_created, 39);
+
+    // The first function of each file calls into the previous file.
+    let repo = &db.list_repositories().unwrap()[0];
+    let head = db.find_symbol_by_name(repo.id, &function_name(3, 0)).unwrap().unwrap();
+    let deps = db.get_dependencies(head.id).unwrap();
+    assert_eq!(deps.len(), 1);
+    assert_eq!(deps[0].1.name, function_name(2, 0));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Bench report covers indexing, search and capsules
+// ---------------------------------------------------------------------------
+#[test]
+fn test_run_bench_report() {
+    let dir = TempDir::new().unwrap();
+    let report = run_bench(dir.path(), &FixtureSpec { files: 12, functions_per_file: 4 }, 10).unwrap();
+    assert_eq!(report.files, 12);
+    assert_eq!(report.symbols, 48);
+    assert_eq!(report.search.samples, 10);
+    assert_eq!(report.capsule.samples, 10);
+    assert!(report.search.p50_us <= report.search.p95_us);
+    assert!(report.search.p95_us <= report.search.max_us);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Performance regression guard
+// ---------------------------------------------------------------------------
+/// A coarse ceiling that catches accidental quadratic behavior, not small
+/// slowdowns. Wall-clock bounds are at the mercy of the machine, so it only
+/// runs on request (`--ignored`). Use `cargo bench` for real numbers.
+#[test]
+#[ignore = "timing-sensitive; run with --ignored"]
+fn test_indexing_stays_within_budget() {
+    let dir = TempDir::new().unwrap();
+    generate_repo(dir.path(), &FixtureSpec { files: 200, functions_per_file: 10 }).unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+
+    let start = Instant::now();
+    let stats = Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(stats.symbols_extracted, 2_000);
+    assert!(elapsed < Duration::from_secs(60), "indexing 200 files took {elapsed:?}");
+
+    let start = Instant::now();
+    for _ in 0..100 {
+        db.search_code("validate invoice", &SearchFilter::default(), 20).unwrap();
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed < Duration::from_secs(10), "100 searches took {elapsed:?}");
+}
@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use focal_core::config::{db_path, focal_dir, workspaces_from_env, DB_PATH_ENV, WORKSPACES_ENV};
+use focal_core::report::default_report_path;
+
+// ---------------------------------------------------------------------------
+// 1. Environment overrides for container deployments. One test, since the
+//    process environment is shared between test threads.
+// ---------------------------------------------------------------------------
+#[test]
+fn test_env_overrides() {
+    std::env::remove_var(DB_PATH_ENV);
+    std::env::remove_var(WORKSPACES_ENV);
+    assert_eq!(db_path(), focal_dir().join("index.db"));
+    assert!(workspaces_from_env().is_empty());
+
+    std::env::set_var(DB_PATH_ENV, "/data/focal/index.db");
+    assert_eq!(db_path(), PathBuf::from("/data/focal/index.db"));
+    assert_eq!(focal_dir(), PathBuf::from("/data/focal"), "config and reports follow the database");
+    assert_eq!(default_report_path(), PathBuf::from("/data/focal/last_index.json"));
+    std::env::set_var(DB_PATH_ENV, "");
+    assert_eq!(db_path(), focal_dir().join("index.db"), "empty means unset");
+
+    let joined = std::env::join_paths(["/repos/api", "/repos/web"]).unwrap();
+    std::env::set_var(WORKSPACES_ENV, &joined);
+    assert_eq!(workspaces_from_env(), vec![PathBuf::from("/repos/api"), PathBuf::from("/repos/web")]);
+    std::env::set_var(WORKSPACES_ENV, "");
+    assert!(workspaces_from_env().is_empty());
+
+    std::env::remove_var(DB_PATH_ENV);
+    std::env::remove_var(WORKSPACES_ENV);
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use focal_core::context::{ContextEngine, Intent};
+use focal_core::context::{CapsuleTemplate, ContextEngine, Expansion, Intent};
 use focal_core::db::Database;
 
 /// Seed a test database with symbols and edges for context engine tests.
@@ -366,3 +366,420 @@ fn test_intent_detect_debug_keyword() {
     // "debug" is now a keyword
     assert_eq!(Intent::detect("debug the handler"), Intent::Debug);
 }
+
+// ---------------------------------------------------------------------------
+// 8. Memories are ranked by relevance, not just linkage order
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_capsule_memories_ranked_by_relevance() {
+    let (db, repo_id) = seed_db();
+    let hr = db.find_symbol_by_name_any("handle_request").unwrap().unwrap();
+
+    // Auto-observation linked to the pivot: lowest category weight.
+    db.save_memory("Explored 'handle_request' (3 results)", "observation", &[hr.id])
+        .unwrap();
+    // Decision linked to the pivot: outranks the architecture note.
+    db.save_memory("Requests are processed synchronously", "decision", &[hr.id])
+        .unwrap();
+    // Unlinked but pinned: always first.
+    let pinned = db
+        .save_memory("Never hold the DB lock across await points", "convention", &[])
+        .unwrap();
+    db.set_memory_pinned(pinned, true).unwrap();
+    // Unlinked but matches the query text via FTS.
+    db.save_memory("handle_request retries are owned by the caller", "pattern", &[])
+        .unwrap();
+
+    let engine = ContextEngine::new(&db);
+    let capsule = engine
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+
+    let contents: Vec<&str> = capsule.memories.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents.len(), 5, "all candidates fit the budget: {contents:?}");
+    assert!(contents[0].contains("DB lock"), "pinned memory first: {contents:?}");
+    assert!(contents[1].contains("synchronously"), "decision second: {contents:?}");
+    assert!(contents[3].contains("retries"), "FTS-only match after linked notes: {contents:?}");
+    assert!(contents[4].starts_with("Explored"), "observation ranks last: {contents:?}");
+}
+
+// ---------------------------------------------------------------------------
+// 9. Memory budget is configurable; overflow becomes compact references
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_capsule_memory_budget_overflow_refs() {
+    let (db, repo_id) = seed_db();
+    let hr = db.find_symbol_by_name_any("handle_request").unwrap().unwrap();
+    let long_note = format!("Retry policy for handle_request: {}", "backoff ".repeat(40));
+    db.save_memory(&long_note, "decision", &[hr.id]).unwrap();
+
+    // Zero memory budget: references count against it too, so nothing.
+    let capsule = ContextEngine::new(&db)
+        .with_memory_budget_fraction(0.0)
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert!(capsule.memories.is_empty());
+    assert!(capsule.memory_refs.is_empty());
+
+    // Room for one reference (30 tokens): the best memory is referenced,
+    // the second reference would overflow the budget and is dropped.
+    let capsule = ContextEngine::new(&db)
+        .with_memory_budget_fraction(0.003)
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert!(capsule.memories.is_empty());
+    assert_eq!(capsule.memory_refs.len(), 1);
+    let long_ref = &capsule.memory_refs[0];
+    assert!(long_ref.preview.starts_with("Retry policy"));
+    assert_eq!(long_ref.preview.chars().count(), 81, "80 chars plus ellipsis");
+    assert!(long_ref.preview.ends_with('…'));
+    let trace = ContextEngine::new(&db)
+        .with_memory_budget_fraction(0.003)
+        .explain("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert!(trace.budget.memories <= trace.budget.memory_budget, "{:?}", trace.budget);
+
+    // Generous budget: both memories attached, no overflow.
+    let capsule = ContextEngine::new(&db)
+        .with_memory_budget_fraction(0.5)
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert_eq!(capsule.memories.len(), 2);
+    assert!(capsule.memory_refs.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 10. Directory memories surface when a pivot lives under the noted path
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_capsule_includes_directory_memories() {
+    let (db, repo_id) = seed_db();
+    let mem = db
+        .save_memory("Everything under src/ is reviewed by the core team", "convention", &[])
+        .unwrap();
+    db.link_memory_files(mem, &["src".to_string()], None).unwrap();
+    let other = db
+        .save_memory("Docs are generated, never edit by hand", "convention", &[])
+        .unwrap();
+    db.link_memory_files(other, &["docs/".to_string()], None).unwrap();
+
+    let capsule = ContextEngine::new(&db)
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+
+    let ids: Vec<i64> = capsule.memories.iter().map(|m| m.id).collect();
+    assert!(ids.contains(&mem), "src/ memory should attach to src/lib.rs pivot");
+    assert!(!ids.contains(&other), "docs/ memory is unrelated to the pivots");
+}
+
+// ---------------------------------------------------------------------------
+// 11. Superseded memories are replaced by the newest in their chain
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_capsule_prefers_superseding_memory() {
+    let (db, repo_id) = seed_db();
+    let hr = db.find_symbol_by_name_any("handle_request").unwrap().unwrap();
+    let old = db
+        .save_memory("Requests are retried three times", "decision", &[hr.id])
+        .unwrap();
+    // The replacement isn't linked to the symbol; supersession carries it in.
+    let new = db
+        .save_memory("Requests are never retried; callers own retries", "decision", &[])
+        .unwrap();
+    db.link_memories(new, old, "supersedes").unwrap();
+
+    let capsule = ContextEngine::new(&db)
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    let ids: Vec<i64> = capsule.memories.iter().map(|m| m.id).collect();
+    assert!(ids.contains(&new), "superseding memory attached: {ids:?}");
+    assert!(!ids.contains(&old), "superseded memory hidden: {ids:?}");
+}
+
+// ---------------------------------------------------------------------------
+// 12. Symbols marked irrelevant in a session stay out of its capsules
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_capsule_skips_irrelevant_symbols() {
+    let (db, repo_id) = seed_db();
+    let pi = db.find_symbol_by_name_any("parse_input").unwrap().unwrap();
+    assert_eq!(db.mark_irrelevant("s1", &[pi.id], "not about parsing").unwrap(), 1);
+    assert_eq!(db.mark_irrelevant("s1", &[pi.id], "").unwrap(), 0, "already marked");
+    assert!(db.irrelevant_symbols("s2").unwrap().is_empty(), "marks are per session");
+
+    let capsule = ContextEngine::new(&db)
+        .with_excluded(db.irrelevant_symbols("s1").unwrap())
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    let names: Vec<&str> = capsule.items.iter().map(|i| i.name.as_str()).collect();
+    assert!(names.contains(&"handle_request"), "{names:?}");
+    assert!(!names.contains(&"parse_input"), "{names:?}");
+    assert_eq!(capsule.skipped_irrelevant, 1);
+
+    assert_eq!(db.unmark_irrelevant("s1", &[]).unwrap(), 1);
+    assert!(db.irrelevant_symbols("s1").unwrap().is_empty());
+}
+
+#[test]
+fn test_irrelevant_mark_survives_reindex() {
+    let (db, _) = seed_db();
+    let pi = db.find_symbol_by_name_any("parse_input").unwrap().unwrap();
+    db.mark_irrelevant("s1", &[pi.id], "").unwrap();
+
+    // Re-indexing the file replaces its rows with fresh ids.
+    db.delete_symbols_by_file(pi.file_id).unwrap();
+    let new_id = db
+        .insert_symbol(pi.file_id, "parse_input", "", "function", "fn parse_input()", "fn parse_input() {}", "", 12, 14, None)
+        .unwrap();
+    assert_ne!(new_id, pi.id);
+    assert_eq!(db.irrelevant_symbols("s1").unwrap(), HashSet::from([new_id]));
+
+    assert_eq!(db.unmark_irrelevant("s1", &[new_id]).unwrap(), 1);
+    assert!(db.irrelevant_symbols("s1").unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 13. File skeletons of pivots are opt-in and budgeted
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_capsule_file_skeletons() {
+    let (db, repo_id) = seed_db();
+    let plain = ContextEngine::new(&db)
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert!(plain.file_skeletons.is_empty());
+
+    let engine = ContextEngine::new(&db).with_file_skeletons(true);
+    let capsule = engine
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert_eq!(capsule.file_skeletons.len(), 1);
+    let skeleton = &capsule.file_skeletons[0];
+    assert_eq!(skeleton.file_path, "src/lib.rs");
+    let names: Vec<&str> = skeleton.symbols.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["handle_request", "parse_input", "validate", "log_error"]);
+    assert_eq!(capsule.total_tokens, plain.total_tokens + skeleton.token_estimate);
+
+    // No room left once the memory share is set aside: the skeleton is dropped.
+    let tight = plain.total_tokens + skeleton.token_estimate;
+    let capsule = engine
+        .get_capsule("handle_request", tight, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert!(capsule.file_skeletons.is_empty());
+    assert!(capsule.total_tokens <= tight);
+}
+
+// ---------------------------------------------------------------------------
+// 14. Sibling expansion pulls in the pivot's neighbours under the same parent
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_capsule_sibling_expansion() {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("shapes", "/tmp/shapes").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/shapes.rs", "rust", "h").unwrap();
+    let circle = db
+        .insert_symbol(file_id, "Circle", "", "struct", "struct Circle", "struct Circle { r: f64 }", "", 1, 20, None)
+        .unwrap();
+    for (name, line) in [("area", 3), ("perimeter", 8), ("diameter", 13)] {
+        let sig = format!("fn {name}(&self) -> f64");
+        db.insert_symbol(file_id, name, "", "method", &sig, &format!("{sig} {{ 0.0 }}"), "", line, line + 3, Some(circle))
+            .unwrap();
+    }
+    db.insert_symbol(file_id, "draw", "", "function", "fn draw()", "fn draw() {}", "", 22, 24, None).unwrap();
+    db.rebuild_fts().unwrap();
+
+    let names = |expansion: Expansion| -> Vec<String> {
+        ContextEngine::new(&db)
+            .with_expansion(expansion)
+            .get_capsule("area", 10000, Some(repo_id), &HashSet::new())
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|i| i.name)
+            .collect()
+    };
+    assert_eq!(names(Expansion::Graph), ["area"]);
+    assert_eq!(names(Expansion::Siblings), ["area", "perimeter", "diameter"], "nearest first, parent excluded");
+
+    assert_eq!(Expansion::parse(None).unwrap(), Expansion::Graph);
+    assert_eq!(Expansion::parse(Some("both")).unwrap(), Expansion::Both);
+    assert!(Expansion::parse(Some("cousins")).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 15. Debug template: caller chains and recently changed files
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_debug_template_extras() {
+    let (db, repo_id) = seed_db();
+    let le = db.find_symbol_by_name_any("log_error").unwrap().unwrap();
+    let file_id = db.upsert_file(repo_id, "src/main.rs", "rust", "m").unwrap();
+    let main_id = db
+        .insert_symbol(file_id, "main", "", "function", "fn main()", "fn main() { log_error(\"x\") }", "", 1, 3, None)
+        .unwrap();
+    db.insert_edge(main_id, le.id, "calls").unwrap();
+    assert_eq!(CapsuleTemplate::for_intent(Intent::Debug), CapsuleTemplate::DEBUG);
+
+    let engine = ContextEngine::new(&db);
+    let capsule = engine
+        .get_capsule("fix handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert_eq!(capsule.call_chains, ["main → log_error → handle_request"]);
+    assert_eq!(capsule.recent_changes.len(), 1);
+    assert_eq!(capsule.recent_changes[0].file_path, "src/lib.rs");
+    let roles: Vec<(&str, &str)> = capsule.items.iter().map(|i| (i.name.as_str(), i.role.as_str())).collect();
+    assert_eq!(roles[0], ("handle_request", "pivot"));
+    assert!(roles.contains(&("log_error", "dependent")));
+    assert!(roles.contains(&("parse_input", "dependency")));
+
+    let explore = engine
+        .get_capsule("handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert!(explore.call_chains.is_empty() && explore.recent_changes.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 16. Refactor template: dependents, linked tests and the public API
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_refactor_template_tests_and_public_api() {
+    let (db, repo_id) = seed_db();
+    let hr = db.find_symbol_by_name_any("handle_request").unwrap().unwrap();
+    let v = db.find_symbol_by_name_any("validate").unwrap().unwrap();
+    db.set_symbol_details(v.id, "public", "", None, "").unwrap();
+    let file_id = db.upsert_file(repo_id, "tests/lib_test.rs", "rust", "t").unwrap();
+    let test_id = db
+        .insert_symbol(file_id, "test_request_roundtrip", "", "function", "fn test_request_roundtrip()", "fn test_request_roundtrip() {}", "", 1, 3, None)
+        .unwrap();
+    db.replace_test_links(repo_id, &[(test_id, hr.id, "calls")]).unwrap();
+
+    let capsule = ContextEngine::new(&db)
+        .get_capsule("refactor handle_request", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    let roles: Vec<(&str, &str)> = capsule.items.iter().map(|i| (i.name.as_str(), i.role.as_str())).collect();
+    assert_eq!(
+        roles,
+        [
+            ("handle_request", "pivot"),
+            ("log_error", "dependent"),
+            ("test_request_roundtrip", "test"),
+            ("validate", "public_api"),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 17. explain() traces the capsule's decisions without bodies
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_explain_traces_decisions() {
+    let (db, repo_id) = seed_db();
+    let pi = db.find_symbol_by_name_any("parse_input").unwrap().unwrap();
+    let engine = ContextEngine::new(&db).with_excluded(HashSet::from([pi.id]));
+
+    let trace = engine
+        .explain("fix handle_request crash", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    assert_eq!(trace.intent, "debug");
+    assert_eq!(trace.fts_query, "handle_request");
+    assert_eq!(trace.template, CapsuleTemplate::DEBUG);
+    let pivot = &trace.candidates[0];
+    assert_eq!((pivot.name.as_str(), pivot.file_path.as_str()), ("handle_request", "src/lib.rs"));
+    assert_eq!((pivot.source, pivot.decision), ("fts", "pivot"));
+    assert!(pivot.score.is_some());
+
+    let decisions: Vec<(&str, &str, &str)> =
+        trace.expansions.iter().map(|e| (e.name.as_str(), e.role, e.decision)).collect();
+    assert!(decisions.contains(&("log_error", "dependent", "added")), "{decisions:?}");
+    assert!(decisions.contains(&("parse_input", "dependency", "irrelevant")), "{decisions:?}");
+    assert!(trace.expansions.iter().all(|e| e.from == "handle_request"));
+
+    let capsule = engine
+        .get_capsule("fix handle_request crash", 10000, Some(repo_id), &HashSet::new())
+        .unwrap();
+    let b = &trace.budget;
+    assert_eq!(b.total, capsule.total_tokens);
+    assert_eq!(b.pivots + b.neighbours + b.extras + b.file_skeletons + b.memories, b.total);
+    let json = serde_json::to_string(&trace).unwrap();
+    assert!(!json.contains("Response::ok"), "no bodies in the trace");
+
+    // A budget too small for the pivot marks it rather than dropping it.
+    let tight = engine.explain("handle_request", 50, Some(repo_id), &HashSet::new()).unwrap();
+    assert_eq!(tight.candidates[0].decision, "over_budget");
+    assert!(tight.expansions.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 18. A repository's .focal.toml tunes its capsules
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_repo_config_tunes_capsule() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("jobs", dir.path().to_str().unwrap()).unwrap();
+    let file_id = db.upsert_file(repo_id, "src/jobs.rs", "rust", "h").unwrap();
+    let add = |name: &str, line: i64| {
+        let signature = format!("fn {name}()");
+        db.insert_symbol(file_id, name, "", "function", &signature, &signature, "", line, line, None)
+            .unwrap()
+    };
+    let (main, run, load, read, log) = (add("main", 1), add("run_job", 2), add("load_job", 3), add("read_row", 4), add("log_event", 5));
+    db.insert_edge(main, run, "calls").unwrap();
+    db.insert_edge(run, load, "calls").unwrap();
+    db.insert_edge(run, log, "calls").unwrap();
+    db.insert_edge(load, read, "calls").unwrap();
+    db.rebuild_fts().unwrap();
+
+    let explain = |engine: ContextEngine, query: &str| engine.explain(query, 10000, None, &HashSet::new()).unwrap();
+    let added = |trace: &focal_core::context::CapsuleTrace| -> Vec<String> {
+        trace.expansions.iter().filter(|e| e.decision == "added").map(|e| e.name.clone()).collect()
+    };
+
+    let defaults = explain(ContextEngine::new(&db), "run_job");
+    assert!(defaults.config.is_none());
+    assert_eq!(added(&defaults), ["load_job", "log_event"]);
+    assert_eq!(defaults.budget.memory_budget, 1000);
+
+    std::fs::write(
+        dir.path().join(".focal.toml"),
+        "[context]\npivots = 1\nexpansion_depth = 2\nmemory_budget = 0.25\nbuiltins = [\"log_event\"]\n\n\
+         [context.templates.explore]\ndependents = true\n",
+    )
+    .unwrap();
+    let tuned = explain(ContextEngine::new(&db), "run_job");
+    assert!(tuned.config.as_deref().unwrap().ends_with(".focal.toml"));
+    assert!(tuned.template.dependents);
+    assert_eq!(added(&tuned), ["main", "load_job", "read_row"]);
+    assert_eq!(tuned.budget.memory_budget, 2500);
+    assert_eq!(tuned.candidates.iter().filter(|c| c.decision == "pivot").count(), 1);
+
+    // The request's memory budget wins over the file's.
+    let explicit = explain(ContextEngine::new(&db).with_memory_budget_fraction(0.5), "run_job");
+    assert_eq!(explicit.budget.memory_budget, 5000);
+
+    // Unknown keys and out-of-range values are rejected like parse errors.
+    for invalid in [
+        "[context]\npivots = \"many\"\n",
+        "[context]\npivot = 1\n",
+        "[context]\nexpansion_depth = 4\n",
+        "[context]\nmemory_budget = 1.5\n",
+        "[context.templates.explore]\ndependants = true\n",
+        "[context.templates.review]\ntests = true\n",
+    ] {
+        std::fs::write(dir.path().join(".focal.toml"), invalid).unwrap();
+        assert!(focal_core::config::RepoConfig::try_load(dir.path()).is_err(), "{invalid}");
+    }
+    // The cached tuning is dropped once the file changes.
+    assert_eq!(added(&explain(ContextEngine::new(&db), "run_job")), ["load_job", "log_event"], "invalid file: defaults");
+}
@@ -0,0 +1,91 @@
+use std::fs;
+
+use focal_core::coupling::{compute, module_of, sort_metrics, ModuleMetrics};
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+/// `api` calls into `store` twice and `util` once; `store` calls `util`.
+fn indexed_repo(db: &Database, dir: &TempDir) -> i64 {
+    let write = |rel: &str, content: &str| {
+        let path = dir.path().join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    write(
+        "api/handlers.go",
+        "package api\n\nfunc GetUser() {\n    LoadUser()\n    Clean()\n    respond()\n}\n\nfunc ListUsers() {\n    LoadUser()\n}\n\nfunc respond() {}\n",
+    );
+    write("store/users.go", "package store\n\nfunc LoadUser() {\n    Clean()\n}\n");
+    write("util/strings.go", "package util\n\nfunc Clean() {}\n");
+    let registry = GrammarRegistry::new();
+    Indexer::new(db, &registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id
+}
+
+fn module<'a>(metrics: &'a [ModuleMetrics], name: &str) -> &'a ModuleMetrics {
+    metrics.iter().find(|m| m.module == name).unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Afferent/efferent coupling, instability and cohesion per directory
+// ---------------------------------------------------------------------------
+#[test]
+fn test_coupling_metrics() {
+    let db = Database::open_in_memory().unwrap();
+    let dir = TempDir::new().unwrap();
+    let repo_id = indexed_repo(&db, &dir);
+
+    let metrics = compute(&db, repo_id).unwrap();
+    let api = module(&metrics, "api");
+    assert_eq!((api.files, api.symbols), (1, 3));
+    assert_eq!((api.afferent, api.efferent), (0, 2), "LoadUser and Clean");
+    assert_eq!(api.instability, Some(1.0));
+    assert_eq!(api.internal_edges, 1);
+    assert_eq!(api.cohesion, Some(0.25));
+
+    let store = module(&metrics, "store");
+    assert_eq!((store.afferent, store.efferent), (2, 1), "GetUser and ListUsers call in");
+    assert_eq!(store.instability, Some(0.33));
+
+    let util = module(&metrics, "util");
+    assert_eq!((util.afferent, util.efferent), (2, 0));
+    assert_eq!(util.instability, Some(0.0));
+    assert_eq!(util.cohesion, None, "depends on nothing");
+}
+
+// ---------------------------------------------------------------------------
+// 2. Index runs store the metrics
+// ---------------------------------------------------------------------------
+#[test]
+fn test_metrics_stored_by_index_run() {
+    let db = Database::open_in_memory().unwrap();
+    let dir = TempDir::new().unwrap();
+    let repo_id = indexed_repo(&db, &dir);
+
+    assert_eq!(db.get_module_metrics(repo_id).unwrap(), compute(&db, repo_id).unwrap());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Sorting and module paths
+// ---------------------------------------------------------------------------
+#[test]
+fn test_sort_and_module_of() {
+    let db = Database::open_in_memory().unwrap();
+    let dir = TempDir::new().unwrap();
+    let repo_id = indexed_repo(&db, &dir);
+    let mut metrics = compute(&db, repo_id).unwrap();
+
+    let order = |metrics: &[ModuleMetrics]| metrics.iter().map(|m| m.module.clone()).collect::<Vec<_>>();
+    sort_metrics(&mut metrics, "instability");
+    assert_eq!(order(&metrics), ["api", "store", "util"]);
+    sort_metrics(&mut metrics, "afferent");
+    assert_eq!(order(&metrics), ["store", "util", "api"]);
+    sort_metrics(&mut metrics, "cohesion");
+    assert_eq!(order(&metrics), ["store", "api", "util"]);
+
+    assert_eq!(module_of("src/db/mod.rs"), "src/db");
+    assert_eq!(module_of("main.go"), ".");
+}
@@ -0,0 +1,133 @@
+use std::fs;
+
+use focal_core::coverage::{import_coverage, parse_report};
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::graph::GraphEngine;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+const SOURCE: &str = "pub fn tested() -> i32 {
+    helper() + 1
+}
+
+pub fn untested() -> i32 {
+    helper() * 2
+}
+
+fn helper() -> i32 {
+    41
+}
+";
+
+/// Index `SOURCE` as `src/lib.rs` in a fresh database.
+fn indexed_repo(dir: &TempDir) -> Database {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), SOURCE).unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    db
+}
+
+fn symbol_id(db: &Database, name: &str) -> i64 {
+    let repo = &db.list_repositories().unwrap()[0];
+    db.get_files_for_repo(repo.id)
+        .unwrap()
+        .iter()
+        .flat_map(|f| db.get_symbols_by_file(f.id).unwrap())
+        .find(|s| s.name == name)
+        .unwrap()
+        .id
+}
+
+// ---------------------------------------------------------------------------
+// 1. lcov and Cobertura parsing
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_reports() {
+    let lcov = "TN:\nSF:/ci/build/src/lib.rs\nDA:2,3\nDA:6,0\nend_of_record\n";
+    let hits = parse_report(lcov, "").unwrap();
+    let lines = &hits["/ci/build/src/lib.rs"];
+    assert_eq!(lines[&2], 3);
+    assert_eq!(lines[&6], 0);
+
+    let cobertura = r#"<?xml version="1.0" ?>
+<coverage line-rate="0.5">
+  <packages><package name="app"><classes>
+    <class name="lib" filename="src/lib.rs" line-rate="0.5">
+      <methods><method name="tested"><lines><line number="2" hits="1"/></lines></method></methods>
+      <lines>
+        <line number="2" hits="4"/>
+        <line number="6" hits="0"/>
+      </lines>
+    </class>
+  </classes></package></packages>
+</coverage>"#;
+    let hits = parse_report(cobertura, "").unwrap();
+    let lines = &hits["src/lib.rs"];
+    assert_eq!(lines[&2], 4, "highest count across method and class lines wins");
+    assert_eq!(lines[&6], 0);
+
+    assert!(parse_report(lcov, "jacoco").is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Line hits are mapped onto symbols by suffix path match
+// ---------------------------------------------------------------------------
+#[test]
+fn test_import_maps_lines_to_symbols() {
+    let dir = TempDir::new().unwrap();
+    let db = indexed_repo(&dir);
+    let repo = db.list_repositories().unwrap()[0].clone();
+
+    let lcov = "SF:/ci/build/src/lib.rs\nDA:2,3\nDA:6,0\nDA:10,3\nend_of_record\nSF:/ci/build/src/gone.rs\nDA:1,1\nend_of_record\n";
+    let import = import_coverage(&db, &repo, &parse_report(lcov, "lcov").unwrap()).unwrap();
+    assert_eq!(import.files_matched, 1);
+    assert_eq!(import.files_unmatched, 1);
+    assert_eq!(import.unmatched_files, vec!["/ci/build/src/gone.rs"]);
+    assert_eq!((import.symbols_covered, import.symbols_uncovered), (2, 1));
+
+    let ids = [symbol_id(&db, "tested"), symbol_id(&db, "untested")];
+    let coverage = db.get_coverage_for_symbols(&ids).unwrap();
+    assert!(coverage[&ids[0]].covered);
+    assert_eq!(coverage[&ids[0]].percent, 100.0);
+    assert!(!coverage[&ids[1]].covered);
+    assert_eq!(coverage[&ids[1]].lines_total, 1);
+
+    // A report matching nothing keeps the earlier import.
+    let other = parse_report("SF:elsewhere/main.go\nDA:1,1\nend_of_record\n", "").unwrap();
+    let import = import_coverage(&db, &repo, &other).unwrap();
+    assert_eq!(import.files_matched, 0);
+    assert_eq!(db.get_coverage_for_symbols(&ids).unwrap().len(), 2);
+
+    // A new import replaces the previous one.
+    let cobertura = r#"<coverage><class filename="src/lib.rs"><lines><line number="6" hits="2"/></lines></class></coverage>"#;
+    import_coverage(&db, &repo, &parse_report(cobertura, "").unwrap()).unwrap();
+    let coverage = db.get_coverage_for_symbols(&ids).unwrap();
+    assert!(!coverage.contains_key(&ids[0]));
+    assert!(coverage[&ids[1]].covered);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Impact graph nodes carry coverage
+// ---------------------------------------------------------------------------
+#[test]
+fn test_impact_graph_coverage() {
+    let dir = TempDir::new().unwrap();
+    let db = indexed_repo(&dir);
+    let repo = db.list_repositories().unwrap()[0].clone();
+    let lcov = format!(
+        "SF:{}/src/lib.rs\nDA:2,1\nDA:6,0\nend_of_record\n",
+        repo.root_path
+    );
+    import_coverage(&db, &repo, &parse_report(&lcov, "").unwrap()).unwrap();
+
+    let nodes = GraphEngine::new(&db).impact_graph("helper", 2, Some(repo.id)).unwrap();
+    let covered: Vec<_> = nodes
+        .iter()
+        .map(|n| (n.name.as_str(), n.coverage.as_ref().map(|c| c.covered)))
+        .collect();
+    assert!(covered.contains(&("tested", Some(true))));
+    assert!(covered.contains(&("untested", Some(false))));
+}
@@ -1,4 +1,4 @@
-use focal_core::db::Database;
+use focal_core::db::{Database, SearchFilter};
 
 // ---------------------------------------------------------------------------
 // 1. Schema migration — all tables exist after open
@@ -9,10 +9,13 @@ fn test_create_database_and_migrate() {
     let tables = db.table_names().unwrap();
 
     let expected = [
+        "branch_files",
         "edges",
         "files",
         "memories",
         "memories_fts",
+        "memory_files",
+        "memory_links",
         "memory_symbols",
         "repositories",
         "symbols",
@@ -191,28 +194,28 @@ fn test_memory_crud() {
     assert!(mem_id > 0);
 
     // list_memories — non-stale, no filter
-    let mems = db.list_memories("", false, "").unwrap();
+    let mems = db.list_memories("", false, false, "").unwrap();
     assert_eq!(mems.len(), 1);
     assert_eq!(mems[0].content, "this function is performance-critical");
     assert!(!mems[0].stale);
 
     // list by category
-    let mems = db.list_memories("note", false, "").unwrap();
+    let mems = db.list_memories("note", false, false, "").unwrap();
     assert_eq!(mems.len(), 1);
-    let mems = db.list_memories("bug", false, "").unwrap();
+    let mems = db.list_memories("bug", false, false, "").unwrap();
     assert!(mems.is_empty());
 
     // update_memory
     db.update_memory(mem_id, "updated content", "bug", &[sym_id])
         .unwrap();
-    let mems = db.list_memories("bug", false, "").unwrap();
+    let mems = db.list_memories("bug", false, false, "").unwrap();
     assert_eq!(mems.len(), 1);
     assert_eq!(mems[0].content, "updated content");
 
     // delete_memory
     let deleted = db.delete_memory(mem_id).unwrap();
     assert!(deleted);
-    let mems = db.list_memories("", false, "").unwrap();
+    let mems = db.list_memories("", false, false, "").unwrap();
     assert!(mems.is_empty());
 
     // delete non-existent
@@ -253,11 +256,13 @@ fn test_memory_staleness() {
     let mems = db.get_memories_for_symbol(sym_id, true).unwrap();
     assert_eq!(mems.len(), 1);
     assert!(mems[0].stale);
+    assert_eq!(mems[0].stale_reason.as_deref(), Some("file_reindexed"));
+    assert!(mems[0].stale_at.is_some());
 
     // list_memories include_stale=false hides it, include_stale=true shows it
-    let mems = db.list_memories("", false, "").unwrap();
+    let mems = db.list_memories("", false, false, "").unwrap();
     assert!(mems.is_empty());
-    let mems = db.list_memories("", true, "").unwrap();
+    let mems = db.list_memories("", true, false, "").unwrap();
     assert_eq!(mems.len(), 1);
     assert_eq!(mems[0].id, mem_id);
 }
@@ -302,34 +307,34 @@ fn test_fts_search() {
     db.rebuild_fts().unwrap();
 
     // Search by name
-    let results = db.search_code("calculate_total", "", None, 10).unwrap();
+    let results = db.search_code("calculate_total", &SearchFilter::default(), 10).unwrap();
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].name, "calculate_total");
 
     // Search by body content
-    let results = db.search_code("price", "", None, 10).unwrap();
+    let results = db.search_code("price", &SearchFilter::default(), 10).unwrap();
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].name, "calculate_total");
 
     // Search by signature content
-    let results = db.search_code("Config", "", None, 10).unwrap();
+    let results = db.search_code("Config", &SearchFilter::default(), 10).unwrap();
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].name, "parse_config");
 
     // Search with kind filter
     let results = db
-        .search_code("calculate_total", "function", None, 10)
+        .search_code("calculate_total", &SearchFilter { kind: "function", ..SearchFilter::default() }, 10)
         .unwrap();
     assert_eq!(results.len(), 1);
 
     // Search with repo_id filter
     let results = db
-        .search_code("calculate_total", "", Some(repo_id), 10)
+        .search_code("calculate_total", &SearchFilter::repo(Some(&repo_id)), 10)
         .unwrap();
     assert_eq!(results.len(), 1);
 
     // No match
-    let results = db.search_code("nonexistent_xyz", "", None, 10).unwrap();
+    let results = db.search_code("nonexistent_xyz", &SearchFilter::default(), 10).unwrap();
     assert!(results.is_empty());
 }
 
@@ -356,7 +361,7 @@ fn test_auto_observation() {
     assert!(mem_id > 0);
 
     // Category is always "observation" for auto observations
-    let mems = db.list_memories("observation", false, "").unwrap();
+    let mems = db.list_memories("observation", false, false, "").unwrap();
     assert_eq!(mems.len(), 1);
     assert_eq!(mems[0].content, "user refactored this function for clarity");
 }
@@ -380,6 +385,24 @@ fn test_transaction_rollback_on_error() {
     assert!(files.is_empty(), "rolled-back file should not persist");
 }
 
+#[test]
+fn test_nested_transaction_rolls_back_only_itself() {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("test", "/test").unwrap();
+
+    db.begin_transaction().unwrap();
+    db.with_transaction(|| db.upsert_file(repo_id, "a.go", "go", "hash_a")).unwrap();
+    let failed = db.with_transaction(|| -> anyhow::Result<()> {
+        db.upsert_file(repo_id, "b.go", "go", "hash_b")?;
+        anyhow::bail!("intentional failure");
+    });
+    assert!(failed.is_err());
+    db.commit_transaction().unwrap();
+
+    let files: Vec<String> = db.get_files_for_repo(repo_id).unwrap().into_iter().map(|f| f.path).collect();
+    assert_eq!(files, vec!["a.go".to_string()]);
+}
+
 // ---------------------------------------------------------------------------
 // 10. Cleanup old auto-observations, keep manual
 // ---------------------------------------------------------------------------
@@ -461,7 +484,7 @@ fn test_cleanup_old_observations() {
     assert_eq!(cleaned, 1);
 
     // Manual memory survives
-    let mems = db.list_memories("", false, "").unwrap();
+    let mems = db.list_memories("", false, false, "").unwrap();
     assert_eq!(mems.len(), 1);
     assert_eq!(mems[0].content, "manual note");
 }
@@ -491,12 +514,12 @@ fn test_incremental_fts() {
         )
         .unwrap();
 
-    let results = db.search_code("HandleRequest", "", None, 10).unwrap();
+    let results = db.search_code("HandleRequest", &SearchFilter::default(), 10).unwrap();
     assert_eq!(results.len(), 1, "symbol should be FTS-searchable after insert");
 
     // Delete symbols — should vanish from FTS
     db.delete_symbols_by_file(file_id).unwrap();
-    let results = db.search_code("HandleRequest", "", None, 10).unwrap();
+    let results = db.search_code("HandleRequest", &SearchFilter::default(), 10).unwrap();
     assert!(results.is_empty(), "symbol should vanish from FTS after delete");
 }
 
@@ -724,3 +747,306 @@ fn test_session_recovery_file_dedup() {
     assert!(data.symbol_names_accessed.contains(&"bar".to_string()));
     assert!(data.symbol_names_accessed.contains(&"foo".to_string()));
 }
+
+// ---------------------------------------------------------------------------
+// 19. Name suggestions — prefix, substring, and typo matches
+// ---------------------------------------------------------------------------
+#[test]
+fn test_suggest_symbol_names() {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("r", "/tmp/r").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/lib.rs", "rust", "h").unwrap();
+    for (i, name) in ["handle_request", "HandleRequest", "parse_input", "validate", "p", "parse"]
+        .iter()
+        .enumerate()
+    {
+        let line = (i as i64) * 10 + 1;
+        db.insert_symbol(file_id, name, "", "function", "", "", "", line, line + 5, None)
+            .unwrap();
+    }
+
+    // Typo within edit distance
+    let typo = db.suggest_symbol_names("validat", None, 5).unwrap();
+    assert_eq!(typo, vec!["validate".to_string()]);
+
+    // Case-insensitive prefix match ranks both spellings
+    let prefix = db.suggest_symbol_names("handle", Some(repo_id), 5).unwrap();
+    assert_eq!(prefix.len(), 2);
+    assert!(prefix.contains(&"handle_request".to_string()));
+    assert!(prefix.contains(&"HandleRequest".to_string()));
+
+    // Nothing close
+    assert!(db.suggest_symbol_names("zzzzzzzzzz", None, 5).unwrap().is_empty());
+
+    // Names shorter than half the query don't count as its prefix, so
+    // "p" and "parse" don't outrank the typo match.
+    let typo = db.suggest_symbol_names("parse_inptu", None, 5).unwrap();
+    assert_eq!(typo, vec!["parse_input".to_string()]);
+    let short = db.suggest_symbol_names("parse_in", None, 5).unwrap();
+    assert_eq!(short, vec!["parse".to_string(), "parse_input".to_string()]);
+
+    let msg = db.symbol_not_found_message("parse_inptu", None);
+    assert!(msg.contains("not found"));
+    assert!(msg.contains("Did you mean: parse_input?"), "got: {msg}");
+}
+
+// ---------------------------------------------------------------------------
+// 20. File/directory memories — prefix matching and orphan staleness
+// ---------------------------------------------------------------------------
+#[test]
+fn test_memories_for_path() {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("r", "/tmp/r").unwrap();
+    db.upsert_file(repo_id, "payments/charge.rs", "rust", "h1").unwrap();
+    db.upsert_file(repo_id, "payments_v2/refund.rs", "rust", "h2").unwrap();
+
+    let dir_mem = db.save_memory("Everything here needs PCI review", "convention", &[]).unwrap();
+    db.link_memory_files(dir_mem, &["./payments/".to_string()], None).unwrap();
+    let file_mem = db.save_memory("Refunds are idempotent", "invariant", &[]).unwrap();
+    db.link_memory_files(file_mem, &["payments_v2/refund.rs".to_string()], None).unwrap();
+
+    assert_eq!(db.get_file_paths_for_memory(dir_mem).unwrap(), vec!["payments".to_string()]);
+
+    let under_dir = db.get_memories_for_path(Some(repo_id), "payments/charge.rs", false).unwrap();
+    assert_eq!(under_dir.len(), 1);
+    assert_eq!(under_dir[0].id, dir_mem);
+
+    // "payments" must not match the sibling directory "payments_v2".
+    let sibling = db.get_memories_for_path(Some(repo_id), "payments_v2/refund.rs", false).unwrap();
+    assert_eq!(sibling.len(), 1);
+    assert_eq!(sibling[0].id, file_mem);
+
+    // Deleting the only file under a linked path marks the memory stale.
+    assert_eq!(db.mark_orphaned_path_memories_stale().unwrap(), 0);
+    db.remove_file(repo_id, "payments_v2/refund.rs").unwrap();
+    assert_eq!(db.mark_orphaned_path_memories_stale().unwrap(), 1);
+    let orphaned = db.get_memory_by_id(file_mem).unwrap().unwrap();
+    assert!(orphaned.stale);
+    assert_eq!(orphaned.stale_reason.as_deref(), Some("file_deleted"));
+    assert!(!db.get_memory_by_id(dir_mem).unwrap().unwrap().stale);
+
+    // A link scoped to one repository doesn't attach to the same path in
+    // another, and only that repository's files keep it fresh.
+    let other_id = db.upsert_repository("o", "/tmp/o").unwrap();
+    db.upsert_file(other_id, "billing/invoice.rs", "rust", "h3").unwrap();
+    let scoped = db.save_memory("Invoices are immutable", "invariant", &[]).unwrap();
+    db.link_memory_files(scoped, &["billing".to_string()], Some(repo_id)).unwrap();
+    assert!(db.get_memories_for_path(Some(other_id), "billing/invoice.rs", false).unwrap().is_empty());
+    assert_eq!(db.mark_orphaned_path_memories_stale().unwrap(), 1);
+    assert!(db.get_memory_by_id(scoped).unwrap().unwrap().stale);
+
+    // An absolute path is scoped to the repository whose root contains it.
+    let absolute = db.save_memory("Invoice numbers are sequential", "invariant", &[]).unwrap();
+    db.link_memory_files(absolute, &["/tmp/o/billing".to_string()], None).unwrap();
+    let in_other = db.get_memories_for_path(Some(other_id), "billing/invoice.rs", false).unwrap();
+    assert_eq!(in_other.iter().map(|m| m.id).collect::<Vec<_>>(), vec![absolute]);
+    assert!(db.get_memories_for_path(Some(repo_id), "billing/invoice.rs", false).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 21. Memory links — supersession hides old memories and resolves chains
+// ---------------------------------------------------------------------------
+#[test]
+fn test_memory_supersession() {
+    let db = Database::open_in_memory().unwrap();
+    let v1 = db.save_memory("Use a mutex around the cache", "decision", &[]).unwrap();
+    let v2 = db.save_memory("Use an RwLock around the cache", "decision", &[]).unwrap();
+    let v3 = db.save_memory("Use a sharded cache, no global lock", "decision", &[]).unwrap();
+    let note = db.save_memory("Cache hit rate is ~90%", "note", &[]).unwrap();
+
+    db.link_memories(v2, v1, "supersedes").unwrap();
+    db.link_memories(v3, v2, "supersedes").unwrap();
+    db.link_memories(note, v3, "relates_to").unwrap();
+
+    assert_eq!(db.latest_in_supersession_chain(v1).unwrap(), v3);
+    assert_eq!(db.latest_in_supersession_chain(v3).unwrap(), v3);
+
+    let visible: Vec<i64> = db
+        .list_memories("", false, false, "")
+        .unwrap()
+        .iter()
+        .map(|m| m.id)
+        .collect();
+    assert!(visible.contains(&v3));
+    assert!(visible.contains(&note), "relates_to does not hide anything");
+    assert!(!visible.contains(&v1));
+    assert!(!visible.contains(&v2));
+
+    let all = db.list_memories("", false, true, "").unwrap();
+    assert_eq!(all.len(), 4);
+}
+
+// ---------------------------------------------------------------------------
+// 22. Migration strips the Windows verbatim prefix from repository roots
+// ---------------------------------------------------------------------------
+#[test]
+fn test_migration_simplifies_verbatim_repo_roots() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let path = path.to_string_lossy();
+    {
+        let db = Database::open(&path).unwrap();
+        db.upsert_repository("repo", r"\\?\C:\work\repo").unwrap();
+        db.upsert_repository("share", r"\\?\UNC\server\share").unwrap();
+    }
+    let db = Database::open(&path).unwrap();
+    assert!(db.get_repository_by_path(r"C:\work\repo").unwrap().is_some());
+    assert!(db.get_repository_by_path(r"\\?\UNC\server\share").unwrap().is_some());
+}
+
+// ---------------------------------------------------------------------------
+// 23. Read-only open — queries work, writes are rejected
+// ---------------------------------------------------------------------------
+#[test]
+fn test_open_read_only() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let path = path.to_string_lossy();
+    {
+        let db = Database::open(&path).unwrap();
+        db.upsert_repository("repo", "/work/repo").unwrap();
+    }
+    let db = Database::open_read_only(&path).unwrap();
+    assert!(db.get_repository_by_path("/work/repo").unwrap().is_some());
+    assert!(db.upsert_repository("other", "/work/other").is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 24. Equal-rank results are ordered by name, path, then line
+// ---------------------------------------------------------------------------
+#[test]
+fn test_search_ties_are_deterministic() {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("repo", "/work/repo").unwrap();
+    // Inserted in reverse of the expected order.
+    let later = db.upsert_file(repo_id, "src/zeta.rs", "rust", "h1").unwrap();
+    let earlier = db.upsert_file(repo_id, "src/alpha.rs", "rust", "h2").unwrap();
+    let body = "fn handle() { dispatch_request() }";
+    db.insert_symbol(later, "handle", "", "function", "fn handle()", body, "b", 9, 9, None).unwrap();
+    db.insert_symbol(earlier, "handle", "", "function", "fn handle()", body, "b", 20, 20, None).unwrap();
+    db.insert_symbol(earlier, "handle", "", "function", "fn handle()", body, "b", 3, 3, None).unwrap();
+
+    let found = db.search_code("dispatch_request", &SearchFilter::default(), 10).unwrap();
+    let order: Vec<(i64, i64)> = found.iter().map(|s| (s.file_id, s.start_line)).collect();
+    assert_eq!(order, vec![(earlier, 3), (earlier, 20), (later, 9)]);
+
+    let queried = db.query_symbols_full("handle", "", "", "", "").unwrap();
+    let order: Vec<(&str, i64)> = queried.iter().map(|s| (s.file_path.as_str(), s.start_line)).collect();
+    assert_eq!(order, vec![("src/alpha.rs", 3), ("src/alpha.rs", 20), ("src/zeta.rs", 9)]);
+}
+
+// ---------------------------------------------------------------------------
+// 25. WAL checkpoint truncates the log; health reports its size
+// ---------------------------------------------------------------------------
+#[test]
+fn test_wal_checkpoint_truncates() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let db = Database::open(path.to_str().unwrap()).unwrap();
+    let repo_id = db.upsert_repository("r", "/tmp/r").unwrap();
+    for i in 0..20 {
+        db.upsert_file(repo_id, &format!("f{i}.rs"), "rust", "h").unwrap();
+    }
+    assert!(db.get_health().unwrap().wal_size_bytes.unwrap() > 0);
+
+    let checkpoint = db.checkpoint_wal().unwrap();
+    assert!(!checkpoint.busy);
+    assert!(checkpoint.bytes_before > 0);
+    assert_eq!(checkpoint.bytes_after, 0);
+    assert_eq!(db.get_health().unwrap().wal_size_bytes, Some(0));
+
+    let memory = Database::open_in_memory().unwrap();
+    assert_eq!(memory.get_health().unwrap().wal_size_bytes, None);
+}
+
+// ---------------------------------------------------------------------------
+// 26. FTS drift is repaired: missing rows indexed, orphans dropped
+// ---------------------------------------------------------------------------
+#[test]
+fn test_check_fts_consistency() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let db = Database::open(path.to_str().unwrap()).unwrap();
+    let repo_id = db.upsert_repository("r", "/tmp/r").unwrap();
+    let file_id = db.upsert_file(repo_id, "f.rs", "rust", "h").unwrap();
+    let kept = db.insert_symbol(file_id, "parse_config", "", "function", "fn parse_config()", "", "", 1, 5, None).unwrap();
+    let gone = db.insert_symbol(file_id, "load_config", "", "function", "fn load_config()", "", "", 6, 9, None).unwrap();
+    db.save_memory("config is parsed lazily", "decision", &[kept]).unwrap();
+    assert!(db.get_health().unwrap().fts_ok);
+    let clean = db.check_fts_consistency().unwrap();
+    assert!(clean.iter().all(|r| r.inserted == 0 && r.removed == 0 && !r.rebuilt), "{clean:?}");
+
+    // Writes that bypassed the FTS tables: a symbol and a memory never
+    // indexed, and a symbol deleted without unindexing it.
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute(
+            "INSERT INTO symbols (file_id, name, qualified_name, kind, signature, body, body_hash, start_line, end_line)
+             VALUES (?1, 'write_config', '', 'function', 'fn write_config()', '', '', 10, 12)",
+            rusqlite::params![file_id],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO memories (content, category) VALUES ('write_config is atomic', 'note')", [])
+            .unwrap();
+        conn.execute("DELETE FROM symbols WHERE id = ?1", rusqlite::params![gone]).unwrap();
+    }
+    assert!(!db.get_health().unwrap().fts_ok);
+    assert!(db.search_code("write_config", &SearchFilter::default(), 10).unwrap().is_empty());
+
+    let repairs = db.check_fts_consistency().unwrap();
+    let summary: Vec<(&str, usize, usize)> = repairs.iter().map(|r| (r.table, r.inserted, r.removed)).collect();
+    assert_eq!(summary, [("symbols_fts", 1, 1), ("memories_fts", 1, 0)]);
+    assert!(repairs[0].rebuilt, "an orphan's tokens only go away with a rebuild");
+    assert!(!repairs[1].rebuilt);
+    assert!(db.get_health().unwrap().fts_ok);
+    assert_eq!(db.search_code("write_config", &SearchFilter::default(), 10).unwrap().len(), 1);
+    assert_eq!(db.search_memories("atomic", 10).unwrap().len(), 1);
+    let (symbols, fts_docs) = db.fts_symbol_counts().unwrap();
+    assert_eq!(symbols, fts_docs);
+}
+
+// ---------------------------------------------------------------------------
+// 27. Updating a memory leaves no stale tokens in memories_fts
+// ---------------------------------------------------------------------------
+#[test]
+fn test_memory_update_keeps_fts_consistent() {
+    let db = Database::open_in_memory().unwrap();
+    let id = db.save_memory("retries use exponential backoff", "decision", &[]).unwrap();
+    db.update_memory(id, "retries use a fixed delay", "decision", &[]).unwrap();
+    db.save_auto_observation("viewed parse_config", "query_symbol", "s1", &[]).unwrap();
+    db.save_auto_observation("viewed parse_config again", "query_symbol", "s1", &[]).unwrap();
+
+    assert!(db.get_health().unwrap().fts_ok);
+    assert!(db.search_memories("exponential", 10).unwrap().is_empty());
+    assert_eq!(db.search_memories("fixed", 10).unwrap().len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// 28. Symbol mentions resolve qualified names within one repository
+// ---------------------------------------------------------------------------
+#[test]
+fn test_find_symbol_by_mention() {
+    let db = Database::open_in_memory().unwrap();
+    let a = db.upsert_repository("a", "/tmp/a").unwrap();
+    let b = db.upsert_repository("b", "/tmp/b").unwrap();
+    let a_file = db.upsert_file(a, "src/db.rs", "rust", "h1").unwrap();
+    let b_file = db.upsert_file(b, "billing/utils.py", "python", "h2").unwrap();
+    let new = db
+        .insert_symbol(a_file, "Config::new", "db::Config::new", "method", "", "", "", 1, 3, None)
+        .unwrap();
+    let parse = db
+        .insert_symbol(b_file, "parse", "billing.utils.parse", "function", "", "", "", 1, 2, None)
+        .unwrap();
+    let b_new = db
+        .insert_symbol(b_file, "Config::new", "", "method", "", "", "", 4, 6, None)
+        .unwrap();
+
+    let id = |repo, mention| db.find_symbol_by_mention(repo, mention).unwrap().map(|s| s.id);
+    assert_eq!(id(Some(a), "Config::new"), Some(new));
+    assert_eq!(id(Some(b), "Config::new"), Some(b_new), "scoped to the repository");
+    assert_eq!(id(Some(a), "db::Config::new"), Some(new), "full qualified name");
+    assert_eq!(id(Some(a), "Config.new"), Some(new), "separators are interchangeable");
+    assert_eq!(id(None, "utils.parse"), Some(parse), "qualified suffix");
+    assert_eq!(id(Some(a), "utils.parse"), None);
+    assert_eq!(id(None, "ls.parse"), None, "suffixes stop at a separator");
+}
@@ -0,0 +1,133 @@
+use std::fs;
+
+use focal_core::config::FocalConfig;
+use focal_core::db::{Database, SearchFilter};
+use focal_core::deny::DenyList;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::Focal;
+use tempfile::TempDir;
+
+fn write_repo(dir: &TempDir) {
+    fs::create_dir_all(dir.path().join("internal/secrets")).unwrap();
+    fs::write(dir.path().join("main.go"), "package main\n\nfunc Main() {}\n").unwrap();
+    fs::write(
+        dir.path().join("internal/secrets/vault.go"),
+        "package secrets\n\nfunc VaultToken() string { return \"\" }\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join(".envrc.py"), "def load_env():\n    pass\n").unwrap();
+}
+
+fn deny() -> DenyList {
+    DenyList::new(["**/secrets/**", ".env*"])
+}
+
+// ---------------------------------------------------------------------------
+// 1. Patterns match gitignore-style against repo-relative paths
+// ---------------------------------------------------------------------------
+#[test]
+fn test_deny_list_matching() {
+    let deny = deny();
+    assert!(deny.is_denied("internal/secrets/vault.go"));
+    assert!(deny.is_denied("secrets/a/b.go"));
+    assert!(deny.is_denied(".env"));
+    assert!(deny.is_denied("config/.env.local"));
+    assert!(!deny.is_denied("internal/secretsmanager.go"));
+    assert!(!deny.is_denied("main.go"));
+
+    assert!(DenyList::new([" ", ""]).is_empty());
+    assert!(DenyList::new(["build\\out/"]).is_denied("build/out/gen.go"));
+}
+
+// ---------------------------------------------------------------------------
+// 2. The indexer never stores denied files
+// ---------------------------------------------------------------------------
+#[test]
+fn test_indexer_skips_denied_paths() {
+    let dir = TempDir::new().unwrap();
+    write_repo(&dir);
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry).with_deny_list(deny());
+    indexer.index_directory(dir.path()).unwrap();
+
+    assert!(db.find_symbol_by_name_any("Main").unwrap().is_some());
+    assert!(db.find_symbol_by_name_any("VaultToken").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("load_env").unwrap().is_none());
+
+    // Watcher-style single-file reindexing is refused too.
+    let vault = dir.path().join("internal/secrets/vault.go");
+    assert!(!indexer.index_file(&vault, dir.path()).unwrap());
+    assert!(db.search_code("VaultToken", &SearchFilter::default(), 10).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Files indexed before a pattern was added are purged, tombstones included
+// ---------------------------------------------------------------------------
+#[test]
+fn test_purge_previously_indexed_files() {
+    let dir = TempDir::new().unwrap();
+    write_repo(&dir);
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert!(db.find_symbol_by_name_any("VaultToken").unwrap().is_some());
+
+    // A deleted denied file sits in a tombstone until purged.
+    fs::remove_file(dir.path().join(".envrc.py")).unwrap();
+    Indexer::new(&db, &registry).reindex_changed(dir.path()).unwrap();
+    let repo_id = db.list_repositories().unwrap()[0].id;
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 1);
+
+    assert_eq!(db.purge_denied_files(&DenyList::default()).unwrap(), 0);
+    assert_eq!(db.purge_denied_files(&deny()).unwrap(), 1);
+    assert!(db.find_symbol_by_name_any("VaultToken").unwrap().is_none());
+    assert!(db.search_code("VaultToken", &SearchFilter::default(), 10).unwrap().is_empty());
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 0);
+    assert!(db.find_symbol_by_name_any("Main").unwrap().is_some());
+
+    // Nothing left to purge; a second pass is a no-op.
+    assert_eq!(db.purge_denied_files(&deny()).unwrap(), 0);
+}
+
+// ---------------------------------------------------------------------------
+// 4. Re-indexing with a deny list purges without a full rebuild
+// ---------------------------------------------------------------------------
+#[test]
+fn test_reindex_purges_denied_files() {
+    let dir = TempDir::new().unwrap();
+    write_repo(&dir);
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let indexer = Indexer::new(&db, &registry).with_deny_list(deny());
+    indexer.reindex_changed(dir.path()).unwrap();
+    assert!(db.find_symbol_by_name_any("VaultToken").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("load_env").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("Main").unwrap().is_some());
+
+    let repo_id = db.list_repositories().unwrap()[0].id;
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 0);
+    assert!(indexer.find_unindexed_files(dir.path(), repo_id).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 5. Building a handle with a deny list purges files indexed before it
+// ---------------------------------------------------------------------------
+#[test]
+fn test_focal_build_purges_denied_files() {
+    let dir = TempDir::new().unwrap();
+    write_repo(&dir);
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let mut config = FocalConfig::default();
+    config.index.deny_paths = vec!["**/secrets/**".to_string()];
+    let focal = Focal::builder().database(db).config(config).build().unwrap();
+    let db = focal.database().lock().unwrap();
+    assert!(db.find_symbol_by_name_any("VaultToken").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("load_env").unwrap().is_some());
+}
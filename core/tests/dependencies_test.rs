@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::Path;
+
+use focal_core::db::{Database, SearchFilter};
+use focal_core::dependencies::{imported_modules, imported_names, module_matches, parse_dependencies};
+use focal_core::graph::GraphEngine;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn summary(deps: &[focal_core::dependencies::Dependency]) -> Vec<(&str, &str, &str)> {
+    deps.iter().map(|d| (d.name.as_str(), d.version.as_str(), d.kind.as_str())).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Manifest parsing
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_cargo_dependencies() {
+    let deps = parse_dependencies(
+        "server/Cargo.toml",
+        r#"
+[package]
+name = "server"
+
+[dependencies]
+axum = "0.8"
+serde = { version = "1", features = ["derive"] }
+json = { package = "serde_json", version = "1" }
+shared = { path = "../shared" }
+tokio.workspace = true
+
+[dev-dependencies]
+tempfile = "3"
+
+[target.'cfg(unix)'.dependencies]
+nix = "0.29"
+"#,
+    );
+    assert_eq!(
+        summary(&deps),
+        [
+            ("axum", "0.8", "prod"),
+            ("serde_json", "1", "prod"),
+            ("serde", "1", "prod"),
+            ("shared", "path:../shared", "prod"),
+            ("tokio", "workspace", "prod"),
+            ("nix", "0.29", "prod"),
+            ("tempfile", "3", "dev"),
+        ]
+    );
+    assert!(deps.iter().all(|d| d.manifest == "server/Cargo.toml"));
+    let json = deps.iter().find(|d| d.name == "serde_json").unwrap();
+    assert_eq!(json.module, "json", "renamed crates import by their key");
+}
+
+#[test]
+fn test_parse_npm_go_and_python_dependencies() {
+    let npm = parse_dependencies(
+        "package.json",
+        r#"{"dependencies": {"express": "^4.18.2"}, "devDependencies": {"@types/node": "20"}}"#,
+    );
+    assert_eq!(summary(&npm), [("express", "^4.18.2", "prod"), ("@types/node", "20", "dev")]);
+
+    let go = parse_dependencies(
+        "go.mod",
+        "module example.com/app\n\nrequire github.com/go-chi/chi/v5 v5.0.12\n\nrequire (\n\tgolang.org/x/sync v0.7.0 // indirect\n\tgithub.com/lib/pq v1.10.9\n)\n",
+    );
+    assert_eq!(
+        summary(&go),
+        [
+            ("github.com/go-chi/chi/v5", "v5.0.12", "prod"),
+            ("golang.org/x/sync", "v0.7.0", "indirect"),
+            ("github.com/lib/pq", "v1.10.9", "prod"),
+        ]
+    );
+
+    let py = parse_dependencies(
+        "pyproject.toml",
+        "[project]\nname = \"app\"\ndependencies = [\"requests[socks]>=2.31; python_version<'4'\", \"PyYAML\"]\n[project.optional-dependencies]\ntest = [\"pytest==8.0\"]\n",
+    );
+    assert_eq!(
+        summary(&py),
+        [("requests", ">=2.31", "prod"), ("PyYAML", "", "prod"), ("pytest", "==8.0", "optional")]
+    );
+    let modules: Vec<&str> = py.iter().map(|d| d.module.as_str()).collect();
+    assert_eq!(modules, ["requests", "yaml", "pytest"]);
+    assert!(parse_dependencies("Cargo.toml", "[dependencies\n").is_empty());
+}
+
+#[test]
+fn test_imported_modules() {
+    assert_eq!(imported_modules("rs", "use axum::{routing::get, Router};"), ["axum"]);
+    assert!(imported_modules("rs", "use crate::db::Database;").is_empty());
+    assert!(imported_modules("rs", "use std::fmt;").is_empty());
+    assert_eq!(imported_modules("py", "import os.path, requests as r"), ["os", "requests"]);
+    assert_eq!(imported_modules("py", "from yaml import safe_load"), ["yaml"]);
+    assert!(imported_modules("py", "from .models import User").is_empty());
+    assert_eq!(imported_modules("ts", "import { Router } from 'express';"), ["express"]);
+    assert_eq!(imported_modules("ts", "import x from \"@tanstack/react-query/build\";"), ["@tanstack/react-query"]);
+    assert!(imported_modules("ts", "import { a } from './a';").is_empty());
+    assert_eq!(imported_modules("go", "chi \"github.com/go-chi/chi/v5/middleware\""), ["github.com/go-chi/chi/v5/middleware"]);
+
+    assert!(module_matches("rs", "serde_json", "serde-json"));
+    assert!(module_matches("go", "github.com/go-chi/chi/v5/middleware", "github.com/go-chi/chi/v5"));
+    assert!(!module_matches("go", "github.com/go-chi/chi/v50", "github.com/go-chi/chi/v5"));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Indexing links imports to declared dependencies
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_links_imports_to_dependencies() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(root, "Cargo.toml", "[package]\nname = \"app\"\n\n[dependencies]\naxum = \"0.8\"\nserde-json = \"1\"\nhttp = { package = \"hyper\", version = \"1\" }\n\n[dev-dependencies]\ntempfile = \"3\"\n");
+    write(root, "src/lib.rs", "use axum::Router;\nuse crate::util::helper;\nuse http::Request;\n\npub fn app() {}\n");
+    write(root, "tools/pyproject.toml", "[project]\nname = \"tools\"\ndependencies = [\"PyYAML>=6\"]\n");
+    write(root, "tools/load.py", "import yaml\n\ndef load(path):\n    return yaml.safe_load(path)\n");
+    write(root, "src/json.rs", "use serde_json::Value;\nuse axum::Json;\n\npub fn parse() {}\n");
+    write(root, "web/package.json", r#"{"name": "web", "dependencies": {"express": "^4.18.2"}}"#);
+    write(root, "web/server.ts", "import express from 'express';\nimport { x } from './x';\nexport function start() {}\n");
+    write(root, "api/go.mod", "module example.com/api\n\nrequire github.com/lib/pq v1.10.9\n");
+    write(root, "api/db.go", "package api\n\nimport (\n\t\"database/sql\"\n\t_ \"github.com/lib/pq\"\n)\n\nfunc Open() *sql.DB { return nil }\n");
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(root).unwrap();
+
+    let deps = db.list_dependencies(None, "", "").unwrap();
+    let usage: Vec<(&str, &str, Vec<&str>)> = deps
+        .iter()
+        .map(|d| (d.name.as_str(), d.version.as_str(), d.imported_by.iter().map(String::as_str).collect()))
+        .collect();
+    assert_eq!(
+        usage,
+        [
+            ("PyYAML", ">=6", vec!["tools/load.py"]),
+            ("axum", "0.8", vec!["src/json.rs", "src/lib.rs"]),
+            ("express", "^4.18.2", vec!["web/server.ts"]),
+            ("github.com/lib/pq", "v1.10.9", vec!["api/db.go"]),
+            ("hyper", "1", vec!["src/lib.rs"]),
+            ("serde-json", "1", vec!["src/json.rs"]),
+            ("tempfile", "3", vec![]),
+        ]
+    );
+    assert_eq!(db.list_dependencies(None, "", "dev").unwrap().len(), 1);
+    // `_` in the filter is literal: `serde_json` doesn't match `serde-json`.
+    assert!(db.list_dependencies(None, "serde_json", "").unwrap().is_empty());
+
+    // A watcher update re-links just that file.
+    write(root, "src/lib.rs", "pub fn app() {}\n");
+    indexer.index_file(&root.join("src/lib.rs"), root).unwrap();
+    let axum = &db.list_dependencies(None, "axum", "").unwrap()[0];
+    assert_eq!(axum.imported_by, ["src/json.rs"]);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Calls into dependencies resolve to external stubs
+// ---------------------------------------------------------------------------
+#[test]
+fn test_imported_names() {
+    let pairs = |language, import| -> Vec<(String, String)> { imported_names(language, import) };
+    let owned = |items: &[(&str, &str)]| -> Vec<(String, String)> {
+        items.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect()
+    };
+    assert_eq!(
+        pairs("rs", "use axum::{routing::{get, post as p}, Router, extract::*};"),
+        owned(&[("get", "get"), ("post", "p"), ("Router", "Router")])
+    );
+    assert!(pairs("rs", "use serde_json;").is_empty());
+    assert_eq!(pairs("py", "from requests import (get as fetch, Session)"), owned(&[("get", "fetch"), ("Session", "Session")]));
+    assert!(pairs("py", "import requests").is_empty());
+    assert_eq!(
+        pairs("ts", "import React, { useState, type FC as Component } from \"react\";"),
+        owned(&[("React", "React"), ("useState", "useState"), ("FC", "Component")])
+    );
+    assert!(pairs("ts", "import * as path from 'path';").is_empty());
+    assert!(pairs("go", "chi \"github.com/go-chi/chi/v5\"").is_empty());
+}
+
+#[test]
+fn test_calls_into_dependencies_resolve_to_external_stubs() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(root, "Cargo.toml", "[package]\nname = \"app\"\n\n[dependencies]\naxum = \"0.8\"\nserde_json = \"1\"\n");
+    write(
+        root,
+        "src/lib.rs",
+        "use axum::routing::get;\nuse serde_json::from_str as parse;\n\npub fn app() { get(helper); parse(\"{}\"); helper(); unknown(); }\npub fn helper() {}\n",
+    );
+    write(root, "src/other.rs", "use axum::routing::get;\n\npub fn routes() { get(routes); }\n");
+    write(root, "web/package.json", r#"{"name": "web", "dependencies": {"react": "18"}}"#);
+    write(root, "web/card.ts", "import { useState } from \"react\";\nexport function card() { useState(0); }\n");
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(root).unwrap();
+
+    let repo = db.get_repo_id_by_name(&root.file_name().unwrap().to_string_lossy()).unwrap().unwrap();
+    let app = db.find_symbol_by_name(repo, "app").unwrap().unwrap();
+    let mut targets: Vec<(String, String, String)> = db
+        .get_dependencies(app.id)
+        .unwrap()
+        .into_iter()
+        .map(|(_, s)| (s.qualified_name, s.kind, s.source))
+        .collect();
+    targets.sort();
+    assert_eq!(targets.len(), 3, "{targets:?}");
+    assert_eq!(targets[0], ("axum::get".into(), "external".into(), "external".into()));
+    assert_eq!(targets[1].1, "function");
+    assert_eq!(targets[2], ("serde_json::from_str".into(), "external".into(), "external".into()));
+
+    // One stub per package and name, shared by every caller.
+    let external = db.get_repo_id_by_name("external").unwrap().unwrap();
+    let impact = GraphEngine::new(&db).impact_graph("get", 1, Some(external)).unwrap();
+    let mut callers: Vec<&str> = impact.iter().map(|n| n.name.as_str()).collect();
+    callers.sort();
+    assert_eq!(callers, ["app", "routes"]);
+    let card = db.find_symbol_by_name(repo, "card").unwrap().unwrap();
+    let deps = db.get_dependencies(card.id).unwrap();
+    assert_eq!(deps[0].1.qualified_name, "react.useState");
+    let hints = db.get_dependency_hint_names(card.id, &Default::default()).unwrap();
+    assert_eq!(hints, [("react.useState".to_string(), "external".to_string(), "calls".to_string())]);
+
+    // Stubs are graph targets only: search and the overview leave them out.
+    let found = db.search_code("useState", &SearchFilter { include_generated: true, ..SearchFilter::default() }, 10).unwrap();
+    assert!(!found.is_empty() && found.iter().all(|s| s.source != "external"), "{found:?}");
+    let overviews = db.get_repo_overview("").unwrap();
+    assert!(overviews.iter().all(|o| o.name != "external"));
+
+    // Stubs nothing calls any more are pruned; the index stays consistent.
+    write(root, "web/card.ts", "export function card() {}\n");
+    indexer.index_file(&root.join("web/card.ts"), root).unwrap();
+    assert!(db.find_symbol_by_name(external, "useState").unwrap().is_none());
+    assert_eq!(db.get_files_for_repo(external).unwrap().len(), 2);
+    indexer.index_directory(root).unwrap();
+    assert!(db.find_symbol_by_name(external, "get").unwrap().is_some());
+    let (symbols, fts_docs) = db.fts_symbol_counts().unwrap();
+    assert_eq!(symbols, fts_docs);
+}
@@ -0,0 +1,129 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::diagnostics::{apply_fixes, diagnose_index};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+const GO_FILE: &str = r#"package main
+
+func Alpha() {
+    println("alpha")
+}
+"#;
+
+fn checks(report: &focal_core::diagnostics::DiagnosticReport) -> Vec<&'static str> {
+    report.diagnostics.iter().map(|d| d.check).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Freshly indexed repo is healthy
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diagnose_clean_index() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.go"), GO_FILE).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let report = diagnose_index(&db, &registry).unwrap();
+    assert!(report.ok, "unexpected diagnostics: {:?}", report.diagnostics);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Files added after indexing are reported as unindexed
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diagnose_unindexed_files() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.go"), GO_FILE).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    fs::create_dir_all(dir.path().join("pkg")).unwrap();
+    fs::write(dir.path().join("pkg/extra.go"), GO_FILE).unwrap();
+    fs::write(dir.path().join("README.md"), "not source").unwrap();
+
+    let report = diagnose_index(&db, &registry).unwrap();
+    let diag = report
+        .diagnostics
+        .iter()
+        .find(|d| d.check == "unindexed_files")
+        .expect("unindexed_files diagnostic");
+    assert_eq!(diag.count, 1);
+    assert_eq!(diag.examples, vec!["pkg/extra.go".to_string()]);
+    assert!(diag.fix.starts_with("focal serve"));
+}
+
+// ---------------------------------------------------------------------------
+// 3. Missing repo root is reported and repaired by apply_fixes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diagnose_missing_root_and_fix() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.go"), GO_FILE).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    drop(dir);
+
+    let report = diagnose_index(&db, &registry).unwrap();
+    assert_eq!(checks(&report), vec!["missing_repo_root"]);
+
+    let actions = apply_fixes(&db, &report).unwrap();
+    assert_eq!(actions.len(), 1);
+    assert!(db.list_repositories().unwrap().is_empty());
+    assert!(diagnose_index(&db, &registry).unwrap().ok);
+}
+
+// ---------------------------------------------------------------------------
+// 4. Empty symbol bodies are flagged with examples
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diagnose_empty_bodies() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let repo_id = db.upsert_repository("r", "manifest://r").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/lib.rs", "rust", "h").unwrap();
+    db.insert_symbol(file_id, "hollow", "", "function", "fn hollow()", "", "", 1, 1, None)
+        .unwrap();
+
+    let report = diagnose_index(&db, &registry).unwrap();
+    let diag = report
+        .diagnostics
+        .iter()
+        .find(|d| d.check == "empty_bodies")
+        .expect("empty_bodies diagnostic");
+    assert_eq!(diag.examples, vec!["src/lib.rs:hollow".to_string()]);
+}
+
+// ---------------------------------------------------------------------------
+// 5. FTS text drift is only found by the full check, and fixed by a rebuild
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diagnose_fts_text_drift() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let db = Database::open(path.to_str().unwrap()).unwrap();
+    let registry = GrammarRegistry::new();
+    let repo_id = db.upsert_repository("r", "manifest://r").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/lib.rs", "rust", "h").unwrap();
+    db.insert_symbol(file_id, "parse", "", "function", "fn parse()", "fn parse() {}", "", 1, 1, None).unwrap();
+
+    // Renamed without reindexing: same rowids, stale tokens.
+    rusqlite::Connection::open(&path).unwrap().execute("UPDATE symbols SET name = 'load'", []).unwrap();
+    assert!(db.get_health().unwrap().fts_ok, "get_health only compares rowids");
+    assert!(!db.fts_content_ok());
+
+    let report = diagnose_index(&db, &registry).unwrap();
+    assert!(checks(&report).contains(&"fts_mismatch"), "{:?}", report.diagnostics);
+    let actions = apply_fixes(&db, &report).unwrap();
+    assert!(actions.contains(&"rebuilt symbols_fts".to_string()), "{actions:?}");
+    assert!(db.fts_content_ok());
+}
@@ -0,0 +1,89 @@
+use std::fs;
+
+use focal_core::diff::unified_diff;
+use focal_core::grammar::GrammarRegistry;
+use tempfile::TempDir;
+
+// ---------------------------------------------------------------------------
+// 1. Identical inputs — empty diff
+// ---------------------------------------------------------------------------
+#[test]
+fn test_identical_bodies() {
+    let body = "fn parse() {\n    todo!()\n}";
+    let diff = unified_diff(body, body, "a", "b", 3);
+    assert!(diff.text.is_empty());
+    assert_eq!((diff.added, diff.removed), (0, 0));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Single change — headers, hunk range, and context lines
+// ---------------------------------------------------------------------------
+#[test]
+fn test_single_hunk() {
+    let old = "fn parse_v1(input: &str) -> u32 {\n    let s = input.trim();\n    s.parse().unwrap()\n}";
+    let new = "fn parse_v2(input: &str) -> u32 {\n    let s = input.trim();\n    s.parse().unwrap_or(0)\n}";
+    let diff = unified_diff(old, new, "parse_v1", "parse_v2", 3);
+
+    assert_eq!((diff.added, diff.removed), (2, 2));
+    assert_eq!(
+        diff.text,
+        "--- parse_v1\n+++ parse_v2\n@@ -1,4 +1,4 @@\n\
+         -fn parse_v1(input: &str) -> u32 {\n\
+         +fn parse_v2(input: &str) -> u32 {\n\
+         \x20    let s = input.trim();\n\
+         -    s.parse().unwrap()\n\
+         +    s.parse().unwrap_or(0)\n\
+         \x20}\n"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 3. Distant changes split into separate hunks; pure insertion ranges
+// ---------------------------------------------------------------------------
+#[test]
+fn test_multiple_hunks_and_insertion() {
+    let old: Vec<String> = (1..=20).map(|i| format!("line {i}")).collect();
+    let mut new = old.clone();
+    new[1] = "changed 2".to_string();
+    new[17] = "changed 18".to_string();
+    let diff = unified_diff(&old.join("\n"), &new.join("\n"), "a", "b", 2);
+    let headers: Vec<&str> = diff.text.lines().filter(|l| l.starts_with("@@")).collect();
+    assert_eq!(headers, vec!["@@ -1,4 +1,4 @@", "@@ -16,5 +16,5 @@"]);
+
+    let diff = unified_diff("", "fn added() {}", "a", "b", 3);
+    assert_eq!(diff.text, "--- a\n+++ b\n@@ -0,0 +1 @@\n+fn added() {}\n");
+}
+
+// ---------------------------------------------------------------------------
+// 4. Symbol body at a git revision
+// ---------------------------------------------------------------------------
+#[test]
+fn test_symbol_at_revision() {
+    let dir = TempDir::new().unwrap();
+    let git = |args: &[&str]| {
+        let out = std::process::Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "git {args:?} failed: {out:?}");
+    };
+
+    fs::write(dir.path().join("main.go"), "package main\n\nfunc Parse() int {\n\treturn 1\n}\n").unwrap();
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "v1"]);
+    fs::write(dir.path().join("main.go"), "package main\n\nfunc Parse() int {\n\treturn 2\n}\n").unwrap();
+
+    let registry = GrammarRegistry::new();
+    let sym = focal_core::git::symbol_at_revision(dir.path(), "HEAD", "main.go", "Parse", &registry)
+        .unwrap()
+        .unwrap();
+    assert!(sym.body.contains("return 1"), "got body: {}", sym.body);
+
+    let missing =
+        focal_core::git::symbol_at_revision(dir.path(), "HEAD", "main.go", "Nope", &registry).unwrap();
+    assert!(missing.is_none());
+    assert!(focal_core::git::symbol_at_revision(dir.path(), "HEAD", "gone.go", "Parse", &registry).is_err());
+}
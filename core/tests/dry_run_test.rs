@@ -0,0 +1,75 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::deny::DenyList;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::guardrails::Guardrails;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn tree() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    for sub in ["api", "node_modules/left-pad", "secrets"] {
+        fs::create_dir_all(root.join(sub)).unwrap();
+    }
+    fs::write(root.join("api/orders.go"), "package api\n\nfunc CreateOrder() {}\n").unwrap();
+    fs::write(root.join("api/orders.pb.go"), "package api\n\nfunc Marshal() {}\n").unwrap();
+    fs::write(root.join("app.py"), "def main():\n    pass\n").unwrap();
+    let mut bundle = String::from("\"use strict\";");
+    for i in 0..100 {
+        bundle.push_str(&format!("function f{i}(a,b){{return a*{i}+b}}"));
+    }
+    fs::write(root.join("bundle.js"), bundle).unwrap();
+    fs::write(root.join("clip.ts"), [0x47, 0x40, 0x00, 0x10, 0x00, 0x00, 0xb0, 0x0d]).unwrap();
+    fs::write(root.join("secrets/keys.go"), "package secrets\n").unwrap();
+    fs::write(root.join("notes.txt"), "todo\n").unwrap();
+    fs::write(root.join("node_modules/left-pad/index.js"), "module.exports = 1;\n").unwrap();
+    fs::write(root.join("package.json"), "{\"name\": \"shop\"}\n").unwrap();
+    dir
+}
+
+// ---------------------------------------------------------------------------
+// 1. The report covers languages, skip reasons and excluded directories
+// ---------------------------------------------------------------------------
+#[test]
+fn test_dry_run() {
+    let dir = tree();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let run = Indexer::new(&db, &registry)
+        .with_deny_list(DenyList::new(["secrets/"]))
+        .dry_run(dir.path())
+        .unwrap();
+
+    let languages: Vec<(&str, usize)> = run.languages.iter().map(|(l, c)| (l.as_str(), c.files)).collect();
+    assert_eq!(languages, [("go", 2), ("py", 1), ("ts", 1)]);
+    assert_eq!((run.files(), run.partial, run.generated), (4, 1, 1));
+
+    let skipped: Vec<(&str, usize)> = run.skipped.iter().map(|(r, s)| (*r, s.files)).collect();
+    assert_eq!(skipped, [("binary", 1), ("denied", 1), ("unsupported", 1)], "manifests are not reported");
+    assert_eq!(run.skipped["denied"].examples, ["secrets/keys.go"]);
+    assert_eq!(run.excluded_dirs, ["node_modules"]);
+    assert!(run.estimated_db_bytes > 0);
+    assert!(run.file_cap.is_none());
+
+    assert!(db.list_repositories().unwrap().is_empty(), "nothing is written");
+}
+
+// ---------------------------------------------------------------------------
+// 2. Oversized files and the file cap
+// ---------------------------------------------------------------------------
+#[test]
+fn test_dry_run_limits() {
+    let dir = tree();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let mut guardrails = Guardrails::default();
+    guardrails.max_files = 2;
+    let indexer = || Indexer::new(&db, &registry).with_guardrails(guardrails.clone()).with_max_partial_file_size(100);
+
+    let run = indexer().dry_run(dir.path()).unwrap();
+    assert_eq!(run.skipped["oversized"].examples, ["bundle.js"]);
+    assert_eq!(run.file_cap, Some(2));
+    assert!(indexer().with_force(true).dry_run(dir.path()).unwrap().file_cap.is_none());
+}
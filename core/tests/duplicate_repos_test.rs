@@ -0,0 +1,115 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use focal_core::db::Database;
+use focal_core::diagnostics::{apply_fixes, diagnose_index};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn index(db: &Database, root: &Path) {
+    let registry = GrammarRegistry::new();
+    Indexer::new(db, &registry).index_directory(root).unwrap();
+}
+
+fn canonical(root: &Path) -> String {
+    focal_core::paths::canonicalize(root).unwrap().to_string_lossy().to_string()
+}
+
+/// Index `checkout/` with an extra `legacy.go`, move it to `real/`, leave a
+/// symlink behind, and index `real/`: the old row's root now resolves to the
+/// new row's tree.
+fn write_duplicate(dir: &TempDir, db: &Database) -> (PathBuf, PathBuf) {
+    let old = dir.path().join("checkout");
+    let real = dir.path().join("real");
+    fs::create_dir_all(&old).unwrap();
+    fs::write(old.join("billing.go"), "package api\n\nfunc ChargeCard() {}\n").unwrap();
+    fs::write(old.join("legacy.go"), "package api\n\nfunc OldCharge() {\n\tChargeCard()\n}\n").unwrap();
+    index(db, &old);
+
+    fs::rename(&old, &real).unwrap();
+    fs::remove_file(real.join("legacy.go")).unwrap();
+    std::os::unix::fs::symlink(&real, &old).unwrap();
+    index(db, &real);
+    (old, real)
+}
+
+// ---------------------------------------------------------------------------
+// 1. Symlinked and trailing-slash spellings of a root share one row
+// ---------------------------------------------------------------------------
+#[test]
+fn test_upsert_normalizes_root() {
+    let dir = TempDir::new().unwrap();
+    let real = dir.path().join("real");
+    fs::create_dir_all(&real).unwrap();
+    let link = dir.path().join("link");
+    std::os::unix::fs::symlink(&real, &link).unwrap();
+    let db = Database::open_in_memory().unwrap();
+
+    let id = db.upsert_repository("real", real.to_str().unwrap()).unwrap();
+    assert_eq!(db.upsert_repository("real", link.to_str().unwrap()).unwrap(), id);
+    assert_eq!(db.upsert_repository("real", &format!("{}/", real.display())).unwrap(), id);
+    assert_eq!(db.list_repositories().unwrap().len(), 1);
+    assert_eq!(db.get_repository_by_path(link.to_str().unwrap()).unwrap().unwrap().root_path, canonical(&real));
+    assert!(db.duplicate_repositories().unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Merging keeps one row, its files, and memories on clashing files
+// ---------------------------------------------------------------------------
+#[test]
+fn test_merge_duplicates() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let (_, real) = write_duplicate(&dir, &db);
+
+    let groups = db.duplicate_repositories().unwrap();
+    assert_eq!(groups.len(), 1);
+    let (root, repos) = &groups[0];
+    assert_eq!(root, &canonical(&real));
+    assert_eq!(repos[0].name, "real", "the row stored under the real root is kept");
+
+    let old_charge = db
+        .query_symbols_full("ChargeCard", "", "checkout", "", "")
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap();
+    db.save_memory("Charges go through the card vault", "decision", &[old_charge.id]).unwrap();
+
+    assert_eq!(db.merge_duplicate_repositories().unwrap(), 1);
+    let repos = db.list_repositories().unwrap();
+    assert_eq!(repos.len(), 1);
+    let repo_id = repos[0].id;
+    assert_eq!(db.get_repo_id_by_name("checkout").unwrap(), Some(repo_id), "old name is an alias");
+    assert!(db.duplicate_repositories().unwrap().is_empty());
+
+    let charges = db.query_symbols_full("ChargeCard", "", "real", "", "").unwrap();
+    assert_eq!(charges.len(), 1, "the clashing file is kept once");
+    assert_eq!(db.get_memories_for_symbol(charges[0].id, false).unwrap().len(), 1);
+    let callers = db.get_dependents(charges[0].id).unwrap();
+    assert!(callers.iter().any(|(_, s)| s.name == "OldCharge"), "edges from reparented files survive");
+    assert_eq!(db.recent_graph_snapshots(repo_id, 10).unwrap().len(), 2, "both rows' graph history is kept");
+}
+
+// ---------------------------------------------------------------------------
+// 3. diagnose_index reports duplicates and apply_fixes merges them
+// ---------------------------------------------------------------------------
+#[test]
+fn test_doctor_merges_duplicates() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open_in_memory().unwrap();
+    write_duplicate(&dir, &db);
+
+    let registry = GrammarRegistry::new();
+    let report = diagnose_index(&db, &registry).unwrap();
+    let diag = report.diagnostics.iter().find(|d| d.check == "duplicate_repos").unwrap();
+    assert_eq!(diag.count, 2);
+
+    let actions = apply_fixes(&db, &report).unwrap();
+    assert!(actions.iter().any(|a| a.contains("merged 1 duplicate")));
+    let report = diagnose_index(&db, &registry).unwrap();
+    assert!(report.diagnostics.iter().all(|d| d.check != "duplicate_repos"));
+}
@@ -0,0 +1,83 @@
+use std::fs;
+
+use focal_core::db::{Database, SearchFilter};
+use focal_core::encoding::decode_source;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn index(dir: &TempDir) -> Database {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let stats = Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert!(stats.errors.is_empty(), "{:?}", stats.errors);
+    db
+}
+
+// ---------------------------------------------------------------------------
+// 1. Detection — BOMs, plain UTF-8 and legacy single-byte encodings
+// ---------------------------------------------------------------------------
+#[test]
+fn test_decode_source_detects_encoding() {
+    let plain = decode_source("fn main() {}\n".as_bytes());
+    assert_eq!(plain.encoding, "UTF-8");
+    assert_eq!(&plain.text[..], b"fn main() {}\n");
+
+    let bom = decode_source(b"\xEF\xBB\xBFfn main() {}\n");
+    assert_eq!(bom.encoding, "UTF-8 BOM");
+    assert_eq!(&bom.text[..], b"fn main() {}\n");
+
+    let utf16: Vec<u8> = [0xFF, 0xFE]
+        .into_iter()
+        .chain("def f(): pass\n".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    let decoded = decode_source(&utf16);
+    assert_eq!(decoded.encoding, "UTF-16LE BOM");
+    assert_eq!(&decoded.text[..], b"def f(): pass\n");
+
+    let latin1 = decode_source(b"# Fran\xE7ois a \xE9crit \xE7a\ndef f(): pass\n");
+    assert_eq!(latin1.encoding, "windows-1252");
+    assert_eq!(std::str::from_utf8(&latin1.text).unwrap(), "# François a écrit ça\ndef f(): pass\n");
+}
+
+// ---------------------------------------------------------------------------
+// 2. Indexing — bodies transcoded, original encoding recorded on the file
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_transcodes_and_records_encoding() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("legacy.py"),
+        b"def greet():\n    # d\xE9j\xE0 vu, r\xE9sum\xE9\n    return 'caf\xE9'\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("bom.go"), b"\xEF\xBB\xBFpackage main\n\nfunc Start() {}\n").unwrap();
+    fs::write(dir.path().join("plain.rs"), "pub fn plain() {}\n").unwrap();
+    let db = index(&dir);
+
+    let greet = &db.query_symbols_full("greet", "", "", "", "").unwrap()[0];
+    assert!(greet.body.contains("déjà vu, résumé"), "{}", greet.body);
+    assert!(greet.body.contains("'café'"));
+    assert_eq!((greet.start_line, greet.end_line), (1, 3));
+    assert_eq!(db.search_code("résumé", &SearchFilter::default(), 10).unwrap().len(), 1);
+
+    let start = &db.query_symbols_full("Start", "", "", "", "").unwrap()[0];
+    assert_eq!(start.signature, "func Start()");
+    assert_eq!(start.start_line, 3);
+
+    let repo = &db.list_repositories().unwrap()[0];
+    let encodings: Vec<(String, String)> = db
+        .get_files_for_repo(repo.id)
+        .unwrap()
+        .into_iter()
+        .map(|f| (f.path, f.encoding))
+        .collect();
+    assert_eq!(
+        encodings,
+        [
+            ("bom.go".to_string(), "UTF-8 BOM".to_string()),
+            ("legacy.py".to_string(), "windows-1252".to_string()),
+            ("plain.rs".to_string(), "UTF-8".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,67 @@
+use focal_core::db::Database;
+use focal_core::encryption::{is_encrypted, open, open_read_only};
+use tempfile::TempDir;
+
+// ---------------------------------------------------------------------------
+// 1. Plaintext databases are recognised by their header
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_plaintext_database_not_encrypted() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    assert!(!is_encrypted(&path), "missing file");
+
+    let db = open(&path, false).unwrap();
+    db.upsert_repository("repo", "/work/repo").unwrap();
+    drop(db);
+    assert!(!is_encrypted(&path));
+    assert!(open_read_only(&path).unwrap().get_repository_by_path("/work/repo").unwrap().is_some());
+
+    std::fs::write(dir.path().join("noise.db"), b"\x8a\x01 random salt bytes").unwrap();
+    assert!(is_encrypted(&dir.path().join("noise.db")));
+}
+
+// ---------------------------------------------------------------------------
+// 2. --encrypt never converts an existing plaintext database
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_encrypt_rejects_existing_plaintext() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    open(&path, false).unwrap();
+
+    let err = open(&path, true).err().unwrap().to_string();
+    assert!(err.contains("already exists unencrypted"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// 3. Encrypted round trip (with the feature) or a clear refusal (without)
+// ---------------------------------------------------------------------------
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_encrypted_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let path_str = path.to_string_lossy();
+    {
+        let db = Database::open_encrypted(&path_str, "secret").unwrap();
+        db.upsert_repository("repo", "/work/repo").unwrap();
+    }
+    assert!(is_encrypted(&path));
+    let db = Database::open_encrypted(&path_str, "secret").unwrap();
+    assert!(db.get_repository_by_path("/work/repo").unwrap().is_some());
+    assert!(Database::open_encrypted(&path_str, "wrong").is_err());
+}
+
+#[cfg(not(feature = "encryption"))]
+#[test]
+fn test_encryption_requires_feature() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let err = Database::open_encrypted(&path.to_string_lossy(), "secret").err().unwrap().to_string();
+    assert!(err.contains("`encryption` feature"), "{err}");
+    assert!(!path.exists() || !is_encrypted(&path));
+}
@@ -0,0 +1,96 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::env::scan_env_usages;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn reads(source: &str, language: &str) -> Vec<(String, usize)> {
+    scan_env_usages(source, language)
+        .into_iter()
+        .map(|u| (u.name, u.line))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Per-language read patterns
+// ---------------------------------------------------------------------------
+#[test]
+fn test_scan_patterns() {
+    let rust = "let url = std::env::var(\"DATABASE_URL\")?;\nlet v = env!(\"CARGO_PKG_VERSION\");\nlet d = dotenv::var(\"IGNORED\");\n";
+    assert_eq!(
+        reads(rust, "rs"),
+        vec![("DATABASE_URL".into(), 1), ("CARGO_PKG_VERSION".into(), 2)]
+    );
+
+    let go = "port := os.Getenv(\"PORT\")\nif v, ok := os.LookupEnv(`DEBUG`); ok {}\n";
+    assert_eq!(reads(go, "go"), vec![("PORT".into(), 1), ("DEBUG".into(), 2)]);
+
+    let ts = "const a = process.env.API_KEY;\nconst b = process.env[\"REGION\"] ?? process.env.FALLBACK;\n";
+    assert_eq!(
+        reads(ts, "ts"),
+        vec![("API_KEY".into(), 1), ("REGION".into(), 2), ("FALLBACK".into(), 2)]
+    );
+
+    let py = "a = os.getenv('HOME')\nb = os.environ[\"USER\"]\nc = os.environ.get(\"SHELL\", \"sh\")\n";
+    assert_eq!(
+        reads(py, "py"),
+        vec![("HOME".into(), 1), ("USER".into(), 2), ("SHELL".into(), 3)]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 2. Dynamic names and other languages are ignored
+// ---------------------------------------------------------------------------
+#[test]
+fn test_scan_ignores_dynamic_names() {
+    assert!(reads("let v = std::env::var(key)?;\nlet w = env::var(format!(\"{p}_URL\"));\n", "rs").is_empty());
+    assert!(reads("x := os.Getenv(prefix + \"_URL\")\n", "go").is_empty());
+    assert!(reads("process.env[name]\n", "ts").is_empty());
+    assert!(reads("os.Getenv(\"PORT\")\n", "py").is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Indexed reads are linked to the innermost enclosing symbol
+// ---------------------------------------------------------------------------
+#[test]
+fn test_indexed_env_usages() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.rs"),
+        "impl Config {\n    pub fn from_env() -> Self {\n        let url = std::env::var(\"DATABASE_URL\").unwrap();\n        Config\n    }\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("settings.py"),
+        "import os\n\nDEBUG = os.getenv(\"DEBUG\")\n\ndef db_url():\n    return os.environ[\"DATABASE_URL\"]\n",
+    )
+    .unwrap();
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+
+    let url = db.list_env_usages(None, "database_url").unwrap();
+    let sites: Vec<_> = url
+        .iter()
+        .map(|u| (u.file_path.as_str(), u.line, u.symbol.as_deref()))
+        .collect();
+    assert_eq!(
+        sites,
+        vec![("config.rs", 3, Some("Config::from_env")), ("settings.py", 6, Some("db_url"))]
+    );
+    // Module-level read: no enclosing symbol.
+    let debug = db.list_env_usages(None, "DEBUG").unwrap();
+    assert_eq!(debug.len(), 1);
+    assert_eq!(debug[0].symbol, None);
+    // `_` in the filter is literal, not a one-character wildcard.
+    assert!(db.list_env_usages(None, "DATABASE_UR_").unwrap().is_empty());
+    assert_eq!(db.list_env_usages(None, "BASE_").unwrap().len(), 2);
+
+    // Re-indexing replaces the file's reads.
+    fs::write(dir.path().join("settings.py"), "def db_url():\n    return None\n").unwrap();
+    indexer.index_file(&dir.path().join("settings.py"), dir.path()).unwrap();
+    assert_eq!(db.list_env_usages(None, "").unwrap().len(), 1);
+}
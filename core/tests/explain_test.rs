@@ -0,0 +1,81 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::explain::explain_symbol;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"/// Pick the backoff for `attempt`.
+pub fn backoff(attempt: u32, jitter: bool) -> u64 {
+    // if this were a branch it would count
+    let base = match attempt {
+        0 => 0,
+        1 | 2 => 100,
+        _ => 1000,
+    };
+    if jitter && attempt > 0 {
+        for _ in 0..attempt {
+            log("if while for");
+        }
+    }
+    base
+}
+
+pub fn retry() -> u64 {
+    backoff(1, false)
+}
+
+fn log(_msg: &str) {}
+"#;
+
+fn setup() -> (TempDir, Database) {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("retry.rs"), SOURCE).unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    (dir, db)
+}
+
+// ---------------------------------------------------------------------------
+// 1. Doc, body, complexity and neighbours in one report
+// ---------------------------------------------------------------------------
+#[test]
+fn test_explain_symbol() {
+    let (_dir, db) = setup();
+    let sym = db.find_symbol_by_name_any("backoff").unwrap().unwrap();
+
+    let report = explain_symbol(&db, &sym, Vec::new(), false, 4_000).unwrap();
+    assert_eq!(report.file_path, "retry.rs");
+    assert_eq!(report.doc.as_deref(), Some("Pick the backoff for `attempt`."));
+    assert!(report.body.is_some());
+    // match (3 arms → 2), if, &&, for; comments and strings ignored
+    assert_eq!(report.complexity.branches, 5);
+    assert_eq!(report.complexity.cyclomatic, 6);
+    assert_eq!(report.complexity.max_nesting, 2);
+    let callers: Vec<&str> = report.callers.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(callers, ["retry"]);
+    let callees: Vec<&str> = report.callees.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(callees, ["log"]);
+    assert!(report.omitted.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Sent bodies and tight budgets degrade to notes and omitted counts
+// ---------------------------------------------------------------------------
+#[test]
+fn test_explain_symbol_budget() {
+    let (_dir, db) = setup();
+    let sym = db.find_symbol_by_name_any("backoff").unwrap().unwrap();
+
+    let sent = explain_symbol(&db, &sym, Vec::new(), true, 4_000).unwrap();
+    assert!(sent.body.is_none());
+    assert_eq!(sent.body_note.as_deref(), Some("body sent earlier in this session"));
+
+    let tight = explain_symbol(&db, &sym, Vec::new(), false, 10).unwrap();
+    assert!(tight.body.is_none());
+    assert!(tight.callers.is_empty());
+    assert_eq!(tight.omitted.get("callers"), Some(&1));
+    assert_eq!(tight.omitted.get("callees"), Some(&1));
+}
@@ -0,0 +1,136 @@
+use std::fs;
+
+use focal_core::config::FocalConfig;
+use focal_core::Focal;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("store.go"), "package main\n\n// save persists a session.\nfunc save() {}\n").unwrap();
+    fs::write(dir.path().join("api.go"), "package main\n\nfunc create() {\n    save()\n}\n").unwrap();
+    dir
+}
+
+fn in_memory(dir: &TempDir) -> Focal {
+    Focal::builder()
+        .workspace(dir.path())
+        .in_memory()
+        .config(FocalConfig::default())
+        .build()
+        .unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Index the workspaces, then query symbols, impact and context
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_and_query() {
+    let dir = workspace();
+    let focal = in_memory(&dir);
+
+    let stats = focal.index().unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].files_indexed, 2);
+
+    let found = focal.find_symbols("save", "").unwrap();
+    assert_eq!(found.len(), 1);
+    assert!(focal.find_symbols("save", "struct").unwrap().is_empty());
+    assert!(focal.search("save", 10).unwrap().iter().any(|s| s.name == "save"));
+
+    let impact = focal.impact("save", 2).unwrap();
+    assert_eq!(impact.nodes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), ["create"]);
+    assert!(impact.truncated.is_none());
+
+    let capsule = focal.context("save", 2000).unwrap();
+    assert!(capsule.items.iter().any(|i| i.name == "save"));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Memories: save, list, search, delete
+// ---------------------------------------------------------------------------
+#[test]
+fn test_memories() {
+    let dir = workspace();
+    let focal = in_memory(&dir);
+    focal.index().unwrap();
+
+    let id = focal
+        .save_memory("Sessions are saved synchronously", "decision", &["save", "missing"])
+        .unwrap();
+    let listed = focal.list_memories("decision").unwrap();
+    assert_eq!(listed.iter().map(|m| m.id).collect::<Vec<_>>(), [id]);
+    assert!(focal.list_memories("bug_fix").unwrap().is_empty());
+    assert_eq!(focal.search_memories("synchronously", 10).unwrap()[0].id, id);
+
+    let linked = focal.database().lock().unwrap().list_memories("", false, false, "save").unwrap();
+    assert_eq!(linked.len(), 1, "linked to the indexed symbol, unknown name skipped");
+
+    assert!(focal.delete_memory(id).unwrap());
+    assert!(!focal.delete_memory(id).unwrap());
+    assert!(focal.list_memories("").unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Memory symbol names resolve in the handle's workspaces first
+// ---------------------------------------------------------------------------
+#[test]
+fn test_save_memory_resolves_in_workspaces() {
+    let (other, dir) = (workspace(), workspace());
+    let focal = in_memory(&dir);
+    focal.index_workspace(other.path()).unwrap();
+    focal.index().unwrap();
+
+    let id = focal.save_memory("Saves are synchronous", "decision", &["save"]).unwrap();
+    let db = focal.database().lock().unwrap();
+    let repo_of = |root: &std::path::Path| {
+        let root = root.canonicalize().unwrap();
+        db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id
+    };
+    let (mine, theirs) = (repo_of(dir.path()), repo_of(other.path()));
+    let save_in = |repo_id| db.find_symbol_by_name(repo_id, "save").unwrap().unwrap().id;
+    assert_eq!(db.get_memories_for_symbol(save_in(mine), false).unwrap()[0].id, id);
+    assert!(db.get_memories_for_symbol(save_in(theirs), false).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 4. Re-index one file of a workspace
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_file() {
+    let dir = workspace();
+    let focal = in_memory(&dir);
+    focal.index().unwrap();
+
+    fs::write(dir.path().join("api.go"), "package main\n\nfunc update() {\n    save()\n}\n").unwrap();
+    assert!(focal.index_file(&dir.path().join("api.go")).unwrap());
+    assert!(focal.find_symbols("create", "").unwrap().is_empty());
+    assert_eq!(focal.find_symbols("update", "").unwrap().len(), 1);
+
+    let outside = TempDir::new().unwrap();
+    fs::write(outside.path().join("other.go"), "package main\n").unwrap();
+    assert!(focal.index_file(&outside.path().join("other.go")).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 5. `db_path` creates the index file and its directory
+// ---------------------------------------------------------------------------
+#[test]
+fn test_db_path_persists() {
+    let dir = workspace();
+    let data = TempDir::new().unwrap();
+    let db_path = data.path().join("nested").join("focal.db");
+    let open = || {
+        Focal::builder()
+            .workspace(dir.path())
+            .db_path(&db_path)
+            .config(FocalConfig::default())
+            .build()
+            .unwrap()
+    };
+
+    open().index().unwrap();
+    assert!(db_path.exists());
+    let reopened = open();
+    assert_eq!(reopened.find_symbols("create", "").unwrap().len(), 1);
+    assert_eq!(reopened.workspaces(), [dir.path().to_path_buf()]);
+}
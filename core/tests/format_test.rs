@@ -0,0 +1,136 @@
+use focal_core::db::SymbolSummary;
+use focal_core::format::{render, render_skeleton, FieldMask, OutputFormat};
+use serde_json::json;
+
+fn summary(name: &str, kind: &str, signature: &str, start_line: i64, end_line: i64) -> SymbolSummary {
+    SymbolSummary {
+        name: name.to_string(),
+        kind: kind.to_string(),
+        signature: signature.to_string(),
+        start_line,
+        end_line,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 1. Format names parse; unknown ones are rejected
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_parse_format() {
+    assert_eq!(OutputFormat::parse(None).unwrap(), OutputFormat::Json);
+    assert_eq!(OutputFormat::parse(Some("compact-json")).unwrap(), OutputFormat::CompactJson);
+    assert_eq!(OutputFormat::parse(Some("text")).unwrap(), OutputFormat::Text);
+    assert!(OutputFormat::parse(Some("yaml")).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Text puts one line per symbol, bodies indented below, and is smallest
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_text_one_line_per_symbol() {
+    let results = json!([
+        {
+            "name": "parse",
+            "kind": "function",
+            "signature": "pub fn parse(raw: &str)\n    -> u32",
+            "file_path": "src/lib.rs",
+            "start_line": 3,
+            "end_line": 5,
+            "body": "pub fn parse(raw: &str) -> u32 {\n    raw.len() as u32\n}",
+            "memories": [],
+            "repo_name": "demo",
+        },
+        { "name": "Config", "kind": "struct", "signature": "", "file_path": "src/config.rs", "start_line": 1, "end_line": 1 },
+    ]);
+
+    let text = render(&results, OutputFormat::Text).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "pub fn parse(raw: &str) -> u32  src/lib.rs:3-5 repo_name=demo");
+    assert_eq!(lines[1], "    body:");
+    assert_eq!(lines[3], "          raw.len() as u32");
+    assert_eq!(lines[5], "struct Config  src/config.rs:1");
+    assert!(!text.contains("memories"), "empty fields are dropped");
+
+    let compact = render(&results, OutputFormat::CompactJson).unwrap();
+    let pretty = render(&results, OutputFormat::Json).unwrap();
+    assert!(text.len() < compact.len() && compact.len() < pretty.len());
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&compact).unwrap(), results);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Skeletons indent symbols by nesting
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_skeleton_text_indents_nested_symbols() {
+    let symbols = vec![
+        summary("Server", "impl", "impl Server", 1, 10),
+        summary("new", "method", "pub fn new() -> Self", 2, 4),
+        summary("run", "method", "pub fn run(&self)", 6, 9),
+        summary("main", "function", "fn main()", 12, 14),
+    ];
+    let text = render_skeleton("demo", "src/server.rs", &symbols, OutputFormat::Text).unwrap();
+    assert_eq!(
+        text,
+        "demo:src/server.rs\n  impl Server  1-10\n    pub fn new() -> Self  2-4\n    pub fn run(&self)  6-9\n  fn main()  12-14"
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&render_skeleton("demo", "src/server.rs", &symbols, OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["symbols"][1]["name"], "new");
+}
+
+// ---------------------------------------------------------------------------
+// 4. Field masks trim each result; id and name always survive
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_field_mask_include_and_exclude() {
+    let known = focal_core::db::SYMBOL_RESULT_FIELDS;
+    let results = json!([
+        { "id": 1, "name": "parse", "kind": "function", "body": "fn parse() {}", "memories": [], "file_path": "src/lib.rs" },
+        { "id": 2, "name": "run", "kind": "function", "body": "fn run() {}", "memories": [], "file_path": "src/main.rs" },
+    ]);
+
+    let exclude = vec!["body".to_string(), "memories".to_string()];
+    let mask = FieldMask::new(None, Some(&exclude), known).unwrap();
+    assert!(!mask.keeps("body") && mask.keeps("kind"));
+    let trimmed = mask.apply(&results).unwrap();
+    assert_eq!(trimmed[1], json!({ "id": 2, "name": "run", "kind": "function", "file_path": "src/main.rs" }));
+
+    let include = vec!["file_path".to_string()];
+    let trimmed = FieldMask::new(Some(&include), None, known).unwrap().apply(&results).unwrap();
+    assert_eq!(trimmed[0], json!({ "id": 1, "name": "parse", "file_path": "src/lib.rs" }));
+
+    let typo = vec!["bodyy".to_string()];
+    let err = FieldMask::new(None, Some(&typo), known).unwrap_err().to_string();
+    assert!(err.contains("unknown field 'bodyy'"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// 5. Flat records (routes, env vars, owners, ...) take one line each
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_text_one_line_per_record() {
+    let response = json!({
+        "repo": "shop",
+        "routes": [
+            { "method": "GET", "path": "/users", "handler": "listUsers", "file_path": "api/users.go", "line": 12 },
+            { "method": "POST", "path": "/users", "handler": "", "file_path": "api/users.go", "start_line": 30, "end_line": 41 },
+            { "owner": "@web", "lines": 120 },
+        ],
+        "errors": [{ "path": "bad.py", "frames": ["a", "b"] }],
+    });
+
+    let text = render(&response, OutputFormat::Text).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[..4], ["errors:", "  - frames:", "      - a", "      - b"], "nested lists keep one field per line");
+    assert_eq!(lines[4..7], ["    path: bad.py", "repo: shop", "routes:"]);
+    assert_eq!(lines[7], "  - handler=listUsers method=GET path=/users  api/users.go:12");
+    assert_eq!(lines[8], "  - method=POST path=/users  api/users.go:30-41", "empty fields are dropped");
+    assert_eq!(lines[9], "  - lines=120 owner=@web");
+    assert!(text.len() < render(&response, OutputFormat::CompactJson).unwrap().len());
+}
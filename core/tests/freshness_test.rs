@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use focal_core::db::Database;
+use focal_core::freshness::{refresh, stale_files, StaleFile, DEFAULT_REFRESH_BUDGET};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+/// Index a repo with `a.go`, `b.go` and `c.go`; returns the canonical root.
+fn indexed_repo(db: &Database, registry: &GrammarRegistry, dir: &TempDir) -> std::path::PathBuf {
+    for name in ["a", "b", "c"] {
+        let body = format!("package main\n\nfunc Fn{}() {{\n    println(\"old\")\n}}\n", name.to_uppercase());
+        fs::write(dir.path().join(format!("{name}.go")), body).unwrap();
+    }
+    Indexer::new(db, registry).index_directory(dir.path()).unwrap();
+    dir.path().canonicalize().unwrap()
+}
+
+/// Rewrite `path` with a modification time safely after its index stamp.
+fn edit(path: &Path, content: &str) {
+    fs::write(path, content).unwrap();
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+}
+
+fn all_file_ids(db: &Database) -> Vec<i64> {
+    ["FnA", "FnB", "FnC"]
+        .iter()
+        .filter_map(|name| db.find_symbol_by_name_any(name).unwrap())
+        .map(|sym| sym.file_id)
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Edited and deleted files are stale; untouched files are not
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_stale_files_detects_edits_and_deletions() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let root = indexed_repo(&db, &registry, &dir);
+    let file_ids = all_file_ids(&db);
+    assert!(stale_files(&db, &file_ids).unwrap().is_empty());
+
+    edit(&root.join("a.go"), "package main\n\nfunc FnA() {}\n");
+    fs::remove_file(root.join("b.go")).unwrap();
+
+    let stale: Vec<String> = stale_files(&db, &file_ids)
+        .unwrap()
+        .iter()
+        .map(|f| f.path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(stale, vec!["a.go", "b.go"]);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Refresh re-parses edited files and drops deleted ones
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_refresh_serves_fresh_bodies() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let root = indexed_repo(&db, &registry, &dir);
+    let file_ids = all_file_ids(&db);
+
+    edit(&root.join("a.go"), "package main\n\nfunc FnA() {\n    println(\"new\")\n}\n");
+    fs::remove_file(root.join("b.go")).unwrap();
+
+    let stale = stale_files(&db, &file_ids).unwrap();
+    let stats = refresh(&Indexer::new(&db, &registry), &db, &stale, DEFAULT_REFRESH_BUDGET);
+    assert_eq!((stats.reindexed, stats.deferred), (2, 0));
+    assert!(stats.errors.is_empty(), "{:?}", stats.errors);
+
+    let fn_a = db.find_symbol_by_name_any("FnA").unwrap().unwrap();
+    assert!(fn_a.body.contains("new"), "{}", fn_a.body);
+    assert!(db.find_symbol_by_name_any("FnB").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("FnC").unwrap().unwrap().body.contains("old"));
+}
+
+// ---------------------------------------------------------------------------
+// 3. Files past the time budget are left for the watcher
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_refresh_respects_budget() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let root = indexed_repo(&db, &registry, &dir);
+    let file_ids = all_file_ids(&db);
+
+    edit(&root.join("a.go"), "package main\n\nfunc FnA() {}\n");
+    let stale = stale_files(&db, &file_ids).unwrap();
+    let stats = refresh(&Indexer::new(&db, &registry), &db, &stale, Duration::ZERO);
+    assert_eq!((stats.reindexed, stats.deferred), (0, 1));
+    assert!(db.find_symbol_by_name_any("FnA").unwrap().unwrap().body.contains("old"));
+}
+
+// ---------------------------------------------------------------------------
+// 4. A file that fails to refresh doesn't stop the others
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_refresh_continues_past_errors() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let root = indexed_repo(&db, &registry, &dir);
+    let file_ids = all_file_ids(&db);
+
+    edit(&root.join("a.go"), "package main\n\nfunc FnA() {\n    println(\"new\")\n}\n");
+    let missing_root = root.join("missing");
+    let mut stale = vec![StaleFile { file_id: -1, root: missing_root.clone(), path: missing_root.join("gone.go") }];
+    stale.extend(stale_files(&db, &file_ids).unwrap());
+
+    let stats = refresh(&Indexer::new(&db, &registry), &db, &stale, DEFAULT_REFRESH_BUDGET);
+    assert_eq!(stats.reindexed, 1);
+    assert_eq!(stats.errors.len(), 1, "{:?}", stats.errors);
+    assert!(stats.errors[0].contains("gone.go"), "{}", stats.errors[0]);
+    assert!(db.find_symbol_by_name_any("FnA").unwrap().unwrap().body.contains("new"));
+}
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use std::fs;
+
+use focal_core::context::ContextEngine;
+use focal_core::db::{Database, SearchFilter};
+use focal_core::generated::is_generated;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn index_repo() -> (TempDir, Database) {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("orders.pb.go"),
+        "package orders\n\nfunc CreateOrderRequest() { submit() }\n\nfunc CreateOrderResponse() { submit() }\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("client.go"),
+        "// Code generated by openapi-generator. DO NOT EDIT.\n\npackage orders\n\nfunc CreateOrderClient() { submit() }\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("handler.go"), "package orders\n\nfunc CreateOrder() { submit() }\n")
+        .unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    (dir, db)
+}
+
+fn names(symbols: &[focal_core::db::Symbol]) -> HashSet<&str> {
+    symbols.iter().map(|s| s.name.as_str()).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Suffixes, header markers and source maps
+// ---------------------------------------------------------------------------
+#[test]
+fn test_is_generated() {
+    assert!(is_generated("api/orders.pb.go", b"package api\n"));
+    assert!(is_generated("gen/orders_pb2.py", b""));
+    assert!(is_generated("web/types_generated.ts", b""));
+    assert!(is_generated("a.go", b"// Code generated by protoc-gen-go. DO NOT EDIT.\n\npackage a\n"));
+    assert!(is_generated("schema.rs", b"// @generated by diesel\n\npub mod schema {}\n"));
+    assert!(is_generated("m.py", b"# -*- coding: utf-8 -*-\n# Generated by the protocol buffer compiler.  DO NOT EDIT!\n"));
+    assert!(is_generated("Api.ts", b"/* <auto-generated> */\nexport class Api {}\n"));
+    assert!(is_generated("dist/app.js", b"function a(){}\n//# sourceMappingURL=app.js.map\n\n"));
+    assert!(is_generated("client.ts", b"// Auto-generated by orval. Do not edit.\nexport {};\n"));
+    assert!(is_generated("models.py", b"# This file was automatically generated by datamodel-codegen.\n"));
+
+    assert!(!is_generated("handler.go", b"package api\n\n// Generate builds a token.\nfunc Generate() {}\n"));
+    assert!(!is_generated("docs.go", b"package api\n\n// Files with \"Code generated\" headers must not be edited.\n"));
+    assert!(!is_generated("ids.rs", b"/// The primary key, auto-generated by the database.\npub struct Id(i64);\n"));
+    assert!(!is_generated("slug.py", b"# Slugs are automatically generated by slugify() on save.\n"));
+    let late_marker = format!("package a\n{}// Code generated by x. DO NOT EDIT.\n", "\n".repeat(20));
+    assert!(!is_generated("late.go", late_marker.as_bytes()), "markers only count in the header");
+}
+
+// ---------------------------------------------------------------------------
+// 2. Generated files are flagged and left out of search by default
+// ---------------------------------------------------------------------------
+#[test]
+fn test_search_skips_generated() {
+    let (dir, db) = index_repo();
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+    let flag = |path: &str| db.is_file_generated(db.get_file_by_path(repo_id, path).unwrap().unwrap().id).unwrap();
+    assert!(flag("orders.pb.go"));
+    assert!(flag("client.go"));
+    assert!(!flag("handler.go"));
+
+    let found = db.search_code("submit", &SearchFilter::default(), 20).unwrap();
+    assert_eq!(names(&found), HashSet::from(["CreateOrder"]));
+
+    let all = db.search_code("submit", &SearchFilter { include_generated: true, ..SearchFilter::default() }, 20).unwrap();
+    assert_eq!(
+        names(&all),
+        HashSet::from(["CreateOrder", "CreateOrderRequest", "CreateOrderResponse", "CreateOrderClient"])
+    );
+    let ids: Vec<i64> = all.iter().map(|s| s.id).collect();
+    let (partial, generated) = db.symbol_file_flags(&ids).unwrap();
+    assert!(partial.is_empty());
+    assert_eq!(generated.len(), 3);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Context pivots skip generated files unless asked
+// ---------------------------------------------------------------------------
+#[test]
+fn test_pivots_skip_generated() {
+    let (_dir, db) = index_repo();
+    let pivots = |include: bool| -> HashSet<String> {
+        ContextEngine::new(&db)
+            .with_generated(include)
+            .get_capsule("CreateOrderRequest CreateOrderClient", 8000, None, &HashSet::new())
+            .unwrap()
+            .items
+            .into_iter()
+            .filter(|i| i.is_pivot)
+            .map(|i| i.name)
+            .collect()
+    };
+    assert!(!pivots(false).iter().any(|n| n == "CreateOrderRequest" || n == "CreateOrderClient"));
+    assert!(pivots(true).contains("CreateOrderRequest"));
+}
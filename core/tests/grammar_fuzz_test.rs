@@ -0,0 +1,309 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::env::scan_env_usages;
+use focal_core::grammar::{
+    ExtractedReference, ExtractedRoute, ExtractedSymbol, Grammar, GrammarRegistry,
+};
+use focal_core::indexer::Indexer;
+use focal_core::sql::extract_sql_queries;
+use proptest::prelude::*;
+use tempfile::TempDir;
+
+/// Valid sources exercising every extractor (symbols, references, routes,
+/// error sites, SQL, env reads), keyed by file extension.
+const SEEDS: &[(&str, &str)] = &[
+    (
+        "rs",
+        r#"use axum::{routing::get, Router};
+
+/// Loads a user.
+#[derive(Debug, Clone)]
+pub struct User<T: Clone> { id: u64, extra: T }
+
+impl<T: Clone> User<T> {
+    pub async fn load(id: u64) -> Result<Self, Error> {
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let q = "SELECT id, name FROM users WHERE id = ?";
+        query(q).fetch_one().await.expect("user")
+    }
+}
+
+pub fn app() -> Router {
+    Router::new().route("/users/:id", get(show_user)).route("/health", get(|| async { "ok" }))
+}
+
+#[cfg(test)]
+mod tests { #[test] fn loads() { assert!(true); } }
+"#,
+    ),
+    (
+        "go",
+        r#"package server
+
+import ("net/http"; "os")
+
+// Handler serves users.
+type Handler struct{ db *DB }
+
+func (h *Handler) ShowUser(w http.ResponseWriter, r *http.Request) {
+	rows, _ := h.db.Query(`SELECT * FROM users WHERE id = $1`, r.URL.Query().Get("id"))
+	_ = rows.Close()
+}
+
+func Routes(mux *http.ServeMux, h *Handler) {
+	mux.HandleFunc("GET /users/{id}", h.ShowUser)
+	port := os.Getenv("PORT")
+	_ = port
+}
+"#,
+    ),
+    (
+        "py",
+        r#"import os
+from fastapi import FastAPI
+
+app = FastAPI()
+
+class UserService:
+    """Loads users."""
+    def load(self, user_id: int) -> "User":
+        try:
+            return self.db.execute("SELECT id FROM users WHERE id = %s", (user_id,))
+        except:
+            pass
+
+@app.get("/users/{user_id}")
+async def show_user(user_id: int):
+    key = os.environ["API_KEY"]
+    return UserService().load(user_id)
+"#,
+    ),
+    (
+        "ts",
+        r#"import express from "express";
+
+export interface User { id: number; name?: string }
+
+export class UserService<T> {
+  constructor(private readonly db: Db) {}
+  async load(id: number): Promise<User> {
+    try { return await this.db.query(`SELECT * FROM users WHERE id = ${id}`); } catch (e) {}
+  }
+}
+
+const router = express.Router();
+router.get("/users/:id", showUser);
+export const handler = async (req: Request) => process.env.REGION ?? "us";
+"#,
+    ),
+    (
+        "tsx",
+        r#"import React, { useState } from "react";
+
+export function UserCard({ user }: { user: User }) {
+  const [open, setOpen] = useState(false);
+  return (
+    <div className="card" onClick={() => setOpen(!open)}>
+      <h2>{user.name}</h2>
+      {open && <Details user={user} />}
+    </div>
+  );
+}
+
+export default class Panel extends React.Component<Props> {
+  render() { return <section>{this.props.children}</section>; }
+}
+"#,
+    ),
+];
+
+/// Fragments that unbalance or re-open constructs when spliced in.
+const FRAGMENTS: &[&str] = &[
+    "{", "}", "(", ")", "<", ">", "</", "/>", "<div", "\"", "'", "`", "${", "/*", "*/", "//", "#[",
+    "r#\"", "\"\"\"", "\n", "\t", ":", "=>", "fn ", "def ", "func ", "class ", "@", "\\", "\u{0}",
+    "é", "\u{200b}",
+];
+
+#[derive(Debug, Clone)]
+enum Mutation {
+    Truncate(usize),
+    Delete(usize, usize),
+    Insert(usize, usize),
+    Byte(usize, u8),
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        any::<usize>().prop_map(Mutation::Truncate),
+        (any::<usize>(), 1usize..40).prop_map(|(at, len)| Mutation::Delete(at, len)),
+        (any::<usize>(), 0..FRAGMENTS.len()).prop_map(|(at, f)| Mutation::Insert(at, f)),
+        (any::<usize>(), any::<u8>()).prop_map(|(at, b)| Mutation::Byte(at, b)),
+    ]
+}
+
+fn apply(source: &mut Vec<u8>, m: &Mutation) {
+    if source.is_empty() {
+        return;
+    }
+    let len = source.len();
+    match *m {
+        Mutation::Truncate(at) => source.truncate(at % len),
+        Mutation::Delete(at, n) => {
+            let at = at % len;
+            source.drain(at..(at + n).min(len));
+        }
+        Mutation::Insert(at, f) => {
+            let at = at % (len + 1);
+            source.splice(at..at, FRAGMENTS[f].bytes());
+        }
+        Mutation::Byte(at, b) => source[at % len] = b,
+    }
+}
+
+/// Run every extractor the indexer runs over `source`.
+fn extract_all(grammar: &dyn Grammar, source: &[u8]) {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&grammar.language()).unwrap();
+    let Some(tree) = parser.parse(source, None) else {
+        return;
+    };
+    let symbols = grammar.extract_symbols(source, &tree);
+    for sym in &symbols {
+        assert!(sym.start_line <= sym.end_line, "{} spans backwards", sym.name);
+    }
+    grammar.extract_references(source, &tree);
+    grammar.extract_routes(source, &tree);
+    grammar.extract_error_sites(source, &tree);
+    extract_sql_queries(source, &tree);
+    scan_env_usages(&String::from_utf8_lossy(source), grammar.file_extensions()[0]);
+}
+
+// ---------------------------------------------------------------------------
+// 1. Mutated valid sources never panic an extractor
+// ---------------------------------------------------------------------------
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn test_mutated_sources_do_not_panic(
+        seed in 0..SEEDS.len(),
+        mutations in prop::collection::vec(mutation(), 1..8),
+    ) {
+        let registry = GrammarRegistry::new();
+        let (ext, text) = SEEDS[seed];
+        let grammar = registry.for_extension(ext).unwrap();
+        let mut source = text.as_bytes().to_vec();
+        for m in &mutations {
+            apply(&mut source, m);
+        }
+        extract_all(grammar, &source);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 2. Random bytes and fragment soup never panic an extractor
+// ---------------------------------------------------------------------------
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn test_random_sources_do_not_panic(
+        ext in prop::sample::select(vec!["rs", "go", "py", "ts", "tsx"]),
+        bytes in prop::collection::vec(any::<u8>(), 0..512),
+        fragments in prop::collection::vec(0..FRAGMENTS.len(), 0..64),
+    ) {
+        let registry = GrammarRegistry::new();
+        let grammar = registry.for_extension(ext).unwrap();
+        extract_all(grammar, &bytes);
+        let soup: String = fragments.iter().map(|&f| FRAGMENTS[f]).collect();
+        extract_all(grammar, soup.as_bytes());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 3. Every prefix of each seed (a file being typed) extracts cleanly
+// ---------------------------------------------------------------------------
+#[test]
+fn test_every_prefix_extracts() {
+    let registry = GrammarRegistry::new();
+    for (ext, text) in SEEDS {
+        let grammar = registry.for_extension(ext).unwrap();
+        for end in 0..=text.len() {
+            extract_all(grammar, &text.as_bytes()[..end]);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 4. Deeply nested and unclosed constructs
+// ---------------------------------------------------------------------------
+#[test]
+fn test_deep_nesting_extracts() {
+    let registry = GrammarRegistry::new();
+    let cases = [
+        ("tsx", format!("export function App() {{ return ({}; }}", "<div>".repeat(5_000))),
+        ("tsx", format!("const x = <A>{}", "{<B>".repeat(2_000))),
+        ("ts", format!("function f() {{ return {}", "g(".repeat(5_000))),
+        ("rs", format!("fn f() {{ {}", "{ let x = (".repeat(3_000))),
+        ("go", format!("package p\nfunc f() {{ {}", "if x { ".repeat(3_000))),
+        ("py", format!("def f():\n    return {}", "[(".repeat(3_000))),
+    ];
+    for (ext, source) in &cases {
+        extract_all(registry.for_extension(ext).unwrap(), source.as_bytes());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 5. A panicking extractor costs one step, not the file or the run
+// ---------------------------------------------------------------------------
+
+/// Rust parser whose symbol extraction always panics but whose route
+/// extraction works.
+struct PanickingGrammar;
+
+impl Grammar for PanickingGrammar {
+    fn language(&self) -> tree_sitter::Language {
+        tree_sitter_rust::LANGUAGE.into()
+    }
+    fn file_extensions(&self) -> &[&str] {
+        &["boom"]
+    }
+    fn extract_symbols(&self, _source: &[u8], _tree: &tree_sitter::Tree) -> Vec<ExtractedSymbol> {
+        panic!("unclosed tag")
+    }
+    fn extract_references(&self, _source: &[u8], _tree: &tree_sitter::Tree) -> Vec<ExtractedReference> {
+        Vec::new()
+    }
+    fn extract_routes(&self, _source: &[u8], _tree: &tree_sitter::Tree) -> Vec<ExtractedRoute> {
+        vec![ExtractedRoute {
+            method: "GET".into(),
+            path: "/still-here".into(),
+            handler: String::new(),
+            line: 1,
+        }]
+    }
+}
+
+#[test]
+fn test_extraction_panic_is_reported() {
+    let db = Database::open_in_memory().unwrap();
+    let mut registry = GrammarRegistry::new();
+    registry.register(Box::new(PanickingGrammar));
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("broken.boom"), "fn f() {}\n").unwrap();
+    fs::write(dir.path().join("fine.rs"), "pub fn fine() {}\n").unwrap();
+
+    let stats = Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert_eq!(stats.files_indexed, 2);
+    assert_eq!(stats.symbols_extracted, 1);
+    assert_eq!(stats.errors.len(), 1, "{:?}", stats.errors);
+    assert!(stats.errors[0].contains("broken.boom: symbol extraction panicked (unclosed tag)"));
+    let recorded = db.list_index_errors(None, "panic").unwrap();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].path, "broken.boom");
+
+    let routes = db.list_routes(None, "", "").unwrap();
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes[0].path, "/still-here");
+}
@@ -1,5 +1,5 @@
 use focal_core::grammar::python::PythonGrammar;
-use focal_core::grammar::{Grammar, SymbolKind};
+use focal_core::grammar::{ExtractedRoute, Grammar, SymbolKind, Visibility};
 
 const PY_SOURCE: &str = r#"import os
 from pathlib import Path
@@ -196,3 +196,214 @@ fn test_python_registry() {
         "expected for_extension(\"pyi\") to return Some"
     );
 }
+
+// ---------------------------------------------------------------------------
+// 5. Visibility and modifiers
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_visibility_and_modifiers() {
+    let source = "async def fetch():\n    pass\n\n\
+                  def _cache():\n    pass\n\n\
+                  class Client:\n    def __init__(self):\n        pass\n\n    def _retry(self):\n        pass\n";
+    let tree = parse_python(source);
+    let symbols = PythonGrammar.extract_symbols(source.as_bytes(), &tree);
+    let get = |name: &str| symbols.iter().find(|s| s.name == name).unwrap();
+
+    assert_eq!(get("fetch").visibility, Visibility::Public);
+    assert_eq!(get("fetch").modifiers, vec!["async"]);
+    assert_eq!(get("_cache").visibility, Visibility::Private);
+    let client = get("Client");
+    assert_eq!(client.children[0].visibility, Visibility::Public);
+    assert_eq!(client.children[1].visibility, Visibility::Private);
+}
+
+// ---------------------------------------------------------------------------
+// 6. Parameter and return types
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_type_signature() {
+    let source = "def load(path, mode: str, retries=3, timeout: float = 1.0, *args, **kwargs) -> list[int]:\n    pass\n";
+    let tree = parse_python(source);
+    let symbols = PythonGrammar.extract_symbols(source.as_bytes(), &tree);
+    let types = symbols[0].types.as_ref().unwrap();
+
+    let names: Vec<(&str, Option<&str>)> = types
+        .params
+        .iter()
+        .map(|p| (p.name.as_str(), p.type_name.as_deref()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            ("path", None),
+            ("mode", Some("str")),
+            ("retries", None),
+            ("timeout", Some("float")),
+            ("*args", None),
+            ("**kwargs", None),
+        ]
+    );
+    assert_eq!(types.returns.as_deref(), Some("list[int]"));
+}
+
+// ---------------------------------------------------------------------------
+// 7. Decorators
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_decorators() {
+    let source = "@app.route(\"/users\", methods=[\"GET\"])\n@login_required\ndef list_users():\n    pass\n\n\
+                  @dataclass\nclass User:\n    @property\n    def display(self):\n        pass\n";
+    let tree = parse_python(source);
+    let symbols = PythonGrammar.extract_symbols(source.as_bytes(), &tree);
+
+    assert_eq!(symbols[0].name, "list_users");
+    assert_eq!(
+        symbols[0].attributes,
+        vec!["@app.route(\"/users\", methods=[\"GET\"])", "@login_required"]
+    );
+    assert_eq!(symbols[1].name, "User");
+    assert_eq!(symbols[1].attributes, vec!["@dataclass"]);
+    assert_eq!(symbols[1].children[0].qualified_name, "User.display");
+    assert_eq!(symbols[1].children[0].attributes, vec!["@property"]);
+}
+
+// ---------------------------------------------------------------------------
+// 8. HTTP routes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_routes() {
+    let source = "@app.route(\"/users\", methods=[\"GET\", \"POST\"])\ndef users():\n    pass\n\n\
+                  @app.route(\"/health\")\ndef health():\n    pass\n\n\
+                  @router.get(\"/items/{item_id}\")\nasync def read_item(item_id: int):\n    pass\n\n\
+                  @functools.lru_cache(\"key\")\ndef cached():\n    pass\n";
+    let tree = parse_python(source);
+    let routes = PythonGrammar.extract_routes(source.as_bytes(), &tree);
+    let route = |method: &str, path: &str, handler: &str, line: usize| ExtractedRoute {
+        method: method.into(),
+        path: path.into(),
+        handler: handler.into(),
+        line,
+    };
+    assert_eq!(
+        routes,
+        vec![
+            route("GET", "/users", "users", 1),
+            route("POST", "/users", "users", 1),
+            route("GET", "/health", "health", 5),
+            route("GET", "/items/{item_id}", "read_item", 9),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 9. Error sites — bare and swallowed exceptions
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_error_sites() {
+    let source = "try:\n    run()\nexcept:\n    log()\n\ntry:\n    run()\nexcept ValueError:\n    # ignore\n    pass\n\n\
+                  try:\n    run()\nexcept (KeyError, OSError) as e:\n    raise Wrapped(e)\n";
+    let tree = parse_python(source);
+    let sites = PythonGrammar.extract_error_sites(source.as_bytes(), &tree);
+    let found: Vec<_> = sites.iter().map(|s| (s.kind.as_str(), s.line)).collect();
+    assert_eq!(found, vec![("bare_except", 3), ("swallowed_exception", 8)]);
+    assert_eq!(sites[1].snippet, "except ValueError:");
+}
+
+// ---------------------------------------------------------------------------
+// 10. Generics — PEP 695 type parameters and subscripted calls
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_generics() {
+    let source = r#"type Pairs[T] = list[tuple[T, T]]
+type UserId = int
+
+class Stack[T]:
+    def push(self, item: T) -> None:
+        pass
+
+def first[T](xs: list[T]) -> T:
+    return xs[0]
+
+def main():
+    s = Stack[int]()
+    first[int]([1])
+    handlers["a"]()
+    routes[key]()
+"#;
+    let tree = parse_python(source);
+    let symbols = PythonGrammar.extract_symbols(source.as_bytes(), &tree);
+    let summary: Vec<(&str, SymbolKind, &str)> = symbols
+        .iter()
+        .map(|s| (s.name.as_str(), s.kind.clone(), s.signature.as_str()))
+        .collect();
+    assert_eq!(summary[0], ("Pairs", SymbolKind::TypeAlias, "type Pairs[T] = list[tuple[T, T]]"));
+    assert_eq!(summary[1], ("UserId", SymbolKind::TypeAlias, "type UserId = int"));
+    assert_eq!(summary[2].0, "Stack");
+    assert_eq!(summary[3], ("first", SymbolKind::Function, "def first[T](xs: list[T]) -> T:"));
+
+    let refs = PythonGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<&str> = refs
+        .iter()
+        .filter(|r| r.from_symbol == "main")
+        .map(|r| r.to_name.as_str())
+        .collect();
+    assert_eq!(calls, ["Stack", "first"], "calls through a lookup name nothing");
+}
+
+// ---------------------------------------------------------------------------
+// 11. Lambdas — module-level bindings are functions, tables of them constants
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_lambdas() {
+    let source = r#"normalize = lambda text, lower=True: clean(text)
+handlers = {"a": lambda: run()}
+
+def main():
+    items.sort(key=lambda i: rank(i))
+
+atexit.register(lambda: cleanup())
+"#;
+    let tree = parse_python(source);
+    let symbols = PythonGrammar.extract_symbols(source.as_bytes(), &tree);
+    let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["normalize", "handlers", "main"]);
+    assert_eq!(symbols[1].kind, SymbolKind::Const);
+    assert_eq!(symbols[1].signature, "handlers = {\"a\": lambda: run()}");
+    assert_eq!(symbols[0].kind, SymbolKind::Function);
+    assert_eq!(symbols[0].signature, "normalize = lambda text, lower=True:");
+    let params: Vec<&str> = symbols[0].types.as_ref().unwrap().params.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(params, ["text", "lower"]);
+
+    let refs = PythonGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<(&str, &str)> = refs.iter().map(|r| (r.from_symbol.as_str(), r.to_name.as_str())).collect();
+    assert_eq!(
+        calls,
+        [
+            ("normalize", "clean"),
+            ("handlers", "run"),
+            ("main", "sort"),
+            ("main", "rank"),
+            ("", "register"),
+            ("", "cleanup"),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 12. Call arguments — count, literal kind and keywords per call site
+// ---------------------------------------------------------------------------
+#[test]
+fn test_python_call_arguments() {
+    let source = r#"
+def run(items):
+    connect("db", -5432, True, None, timeout=3.5)
+    apply([1], {"a": 1}, lambda x: x, *items, **opts)
+    total(x for x in items)
+"#;
+    let tree = parse_python(source);
+    let refs = PythonGrammar.extract_references(source.as_bytes(), &tree);
+    let args = |name: &str| refs.iter().find(|r| r.to_name == name).unwrap().args.clone().unwrap();
+    assert_eq!(args("connect"), ["string", "number", "bool", "null", "timeout=number"]);
+    assert_eq!(args("apply"), ["array", "object", "function", "spread", "spread"]);
+    assert_eq!(args("total"), ["expr"]);
+}
@@ -1,5 +1,5 @@
 use focal_core::grammar::rust_lang::RustGrammar;
-use focal_core::grammar::{Grammar, SymbolKind};
+use focal_core::grammar::{ExtractedRoute, Grammar, Param, SymbolKind, Visibility};
 
 const RUST_SOURCE: &str = r#"use std::io;
 
@@ -135,11 +135,11 @@ fn test_rust_extract_references() {
     let grammar = RustGrammar;
     let refs = grammar.extract_references(RUST_SOURCE.as_bytes(), &tree);
 
-    // new calls String::from => callee = "from"
+    // Config::new calls String::from => callee = "from"
     assert!(
         refs.iter()
-            .any(|r| r.from_symbol == "new" && r.to_name == "from" && r.kind == "calls"),
-        "expected new -> from call, got: {refs:?}"
+            .any(|r| r.from_symbol == "Config::new" && r.to_name == "from" && r.kind == "calls"),
+        "expected Config::new -> from call, got: {refs:?}"
     );
 
     // start_server calls println! macro
@@ -214,3 +214,240 @@ fn test_rust_registry() {
         "expected for_extension(\"rs\") to return Some"
     );
 }
+
+// ---------------------------------------------------------------------------
+// 5. Visibility and modifiers
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_visibility_and_modifiers() {
+    let source = "pub async unsafe fn open() {}\n\
+                  pub(crate) const fn limit() -> usize { 1 }\n\
+                  fn helper() {}\n\
+                  pub static mut COUNTER: u32 = 0;\n\
+                  pub struct Db;\n\
+                  impl Db { pub fn get(&self) {} }\n\
+                  impl Clone for Db { fn clone(&self) -> Self { Db } }\n\
+                  pub(crate) struct Row;\n\
+                  impl Default for Row { fn default() -> Self { Row } }\n\
+                  impl Drop for Remote { fn drop(&mut self) {} }\n";
+    let tree = parse_rust(source);
+    let symbols = RustGrammar.extract_symbols(source.as_bytes(), &tree);
+    let get = |name: &str| symbols.iter().find(|s| s.name == name).unwrap();
+
+    assert_eq!(get("open").visibility, Visibility::Public);
+    assert_eq!(get("open").modifiers, vec!["async", "unsafe"]);
+    assert_eq!(get("limit").visibility, Visibility::Internal);
+    assert_eq!(get("limit").modifiers, vec!["const"]);
+    assert_eq!(get("helper").visibility, Visibility::Private);
+    assert!(get("helper").modifiers.is_empty());
+    assert_eq!(get("COUNTER").modifiers, vec!["static", "mut"]);
+    assert_eq!(get("Db").visibility, Visibility::Public);
+    assert_eq!(get("Db::get").visibility, Visibility::Public);
+    assert_eq!(get("Db::clone").visibility, Visibility::Public, "trait impls follow the type");
+    assert_eq!(get("Row::default").visibility, Visibility::Internal);
+    assert_eq!(get("Remote::drop").visibility, Visibility::Public);
+}
+
+// ---------------------------------------------------------------------------
+// 6. Parameter and return types
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_type_signature() {
+    let source = "impl Db { pub fn get(&self, key: &str, (a, b): (u8, u8)) -> Option<Vec<u8>> { None } }\n\
+                  pub struct Db;\n";
+    let tree = parse_rust(source);
+    let symbols = RustGrammar.extract_symbols(source.as_bytes(), &tree);
+    let get = symbols.iter().find(|s| s.name == "Db::get").unwrap();
+    let types = get.types.as_ref().unwrap();
+
+    let param = |name: &str, ty: &str| Param { name: name.into(), type_name: Some(ty.into()) };
+    assert_eq!(
+        types.params,
+        vec![param("self", "&self"), param("key", "&str"), param("(a, b)", "(u8, u8)")]
+    );
+    assert_eq!(types.returns.as_deref(), Some("Option<Vec<u8>>"));
+    assert!(symbols.iter().find(|s| s.name == "Db").unwrap().types.is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 7. Attributes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_attributes() {
+    let source = "/// A user.\n#[derive(Debug, Clone)]\n// serialized as camelCase\n#[serde(rename_all = \"camelCase\")]\npub struct User;\n\n\
+                  impl User {\n    #[inline]\n    pub fn id(&self) -> u64 { 0 }\n}\n\nfn plain() {}\n";
+    let tree = parse_rust(source);
+    let symbols = RustGrammar.extract_symbols(source.as_bytes(), &tree);
+    let get = |name: &str| symbols.iter().find(|s| s.name == name).unwrap();
+
+    assert_eq!(
+        get("User").attributes,
+        vec!["#[derive(Debug, Clone)]", "#[serde(rename_all = \"camelCase\")]"]
+    );
+    assert_eq!(get("User::id").attributes, vec!["#[inline]"]);
+    assert!(get("plain").attributes.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 8. HTTP routes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_routes() {
+    let source = r#"fn app() -> Router {
+    Router::new()
+        .route("/users", get(list_users).post(handlers::create_user))
+        .route("/health", routing::get(health))
+}
+
+#[get("/items/{id}")]
+async fn get_item() -> String { String::new() }
+
+fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/items", web::post().to(create_item));
+}
+"#;
+    let tree = parse_rust(source);
+    let routes = RustGrammar.extract_routes(source.as_bytes(), &tree);
+    let route = |method: &str, path: &str, handler: &str, line: usize| ExtractedRoute {
+        method: method.into(),
+        path: path.into(),
+        handler: handler.into(),
+        line,
+    };
+    assert_eq!(
+        routes,
+        vec![
+            route("GET", "/users", "list_users", 3),
+            route("POST", "/users", "create_user", 3),
+            route("GET", "/health", "health", 4),
+            route("GET", "/items/{id}", "get_item", 7),
+            route("POST", "/items", "create_item", 11),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 9. Error sites — unwrap/expect outside test code
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_error_sites() {
+    let source = r#"fn load(path: &str) -> Config {
+    let text = std::fs::read_to_string(path).unwrap();
+    toml::from_str(&text).expect("valid config")
+}
+
+fn safe(v: Option<u8>) -> u8 { v.unwrap_or(0) }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn loads() { load("x").port.unwrap(); }
+}
+
+#[test]
+fn top_level() { Some(1).unwrap(); }
+"#;
+    let tree = parse_rust(source);
+    let sites = RustGrammar.extract_error_sites(source.as_bytes(), &tree);
+    let found: Vec<_> = sites.iter().map(|s| (s.kind.as_str(), s.line)).collect();
+    assert_eq!(found, vec![("unwrap", 2), ("expect", 3)]);
+    assert_eq!(sites[0].snippet, "let text = std::fs::read_to_string(path).unwrap();");
+}
+
+// ---------------------------------------------------------------------------
+// 10. Generics — impl targets, type parameters and turbofish calls
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_generics() {
+    let source = r#"pub struct Wrapper<T, const N: usize> { items: [T; N] }
+
+impl<T: Clone, const N: usize> Wrapper<T, N> {
+    pub fn get(&self, i: usize) -> Option<&T> { self.items.get(i) }
+}
+
+impl<T> std::fmt::Display for Wrapper<T, 1> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { Ok(()) }
+}
+
+impl<K, V> crate::store::Cache<K, V> where K: Eq {
+    fn hit(&self) -> bool { true }
+}
+
+static CONFIG: Config = Config::new();
+
+pub fn parse_all<I: IntoIterator<Item = String>>(input: I) -> Vec<i32> {
+    input.into_iter().map(|s| s.parse::<i32>().unwrap()).collect::<Vec<_>>()
+}
+"#;
+    let tree = parse_rust(source);
+    let symbols = RustGrammar.extract_symbols(source.as_bytes(), &tree);
+    let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Wrapper", "Wrapper::get", "Wrapper::fmt", "Cache::hit", "CONFIG", "parse_all"]);
+    assert_eq!(symbols[0].signature, "pub struct Wrapper<T, const N: usize>");
+    assert_eq!(
+        symbols[5].signature,
+        "pub fn parse_all<I: IntoIterator<Item = String>>(input: I) -> Vec<i32>"
+    );
+
+    let refs = RustGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<&str> = refs
+        .iter()
+        .filter(|r| r.from_symbol == "parse_all")
+        .map(|r| r.to_name.as_str())
+        .collect();
+    assert_eq!(calls, ["collect", "map", "into_iter", "unwrap", "parse"]);
+}
+
+// ---------------------------------------------------------------------------
+// 11. Closures — calls belong to the enclosing method or static
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_closure_references() {
+    let source = r#"static CLIENT: LazyLock<Client> = LazyLock::new(|| Client::new());
+
+impl Config {
+    fn ports(&self) -> Vec<u16> {
+        self.hosts.iter().map(|h| parse_port(h)).collect()
+    }
+}
+
+fn run() {
+    let f = |x| helper(x);
+}
+"#;
+    let tree = parse_rust(source);
+    let refs = RustGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<(&str, &str)> = refs.iter().map(|r| (r.from_symbol.as_str(), r.to_name.as_str())).collect();
+    assert_eq!(
+        calls,
+        [
+            ("CLIENT", "new"),
+            ("CLIENT", "new"),
+            ("Config::ports", "collect"),
+            ("Config::ports", "map"),
+            ("Config::ports", "iter"),
+            ("Config::ports", "parse_port"),
+            ("run", "helper"),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 12. Call arguments — count and literal kind per call site
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_call_arguments() {
+    let source = r#"
+fn run(config: &Config) {
+    connect("db", -5432, true, &[1, 2]);
+    config.apply(Options { retries: 3 }, |x| x + 1, config);
+    println!("{}", 1);
+}
+"#;
+    let tree = parse_rust(source);
+    let refs = RustGrammar.extract_references(source.as_bytes(), &tree);
+    let args = |name: &str| refs.iter().find(|r| r.to_name == name).unwrap().args.clone();
+    assert_eq!(args("connect").unwrap(), ["string", "number", "bool", "array"]);
+    assert_eq!(args("apply").unwrap(), ["object", "function", "expr"]);
+    assert_eq!(args("println"), None, "macro arguments are token trees");
+}
@@ -1,7 +1,7 @@
 use std::path::Path;
 
-use focal_core::grammar::go::GoGrammar;
-use focal_core::grammar::{Grammar, GrammarRegistry, SymbolKind};
+use focal_core::grammar::go::{interface_members, GoGrammar, InterfaceMembers};
+use focal_core::grammar::{ExtractedRoute, Grammar, GrammarRegistry, Param, SymbolKind};
 
 const GO_SOURCE: &str = r#"
 package main
@@ -52,10 +52,10 @@ fn test_go_extract_symbols() {
         "expected Server (Struct), got: {names:?}"
     );
 
-    // Start method
+    // Start method, named after its receiver
     assert!(
-        names.iter().any(|(n, k)| *n == "Start" && **k == SymbolKind::Method),
-        "expected Start (Method), got: {names:?}"
+        names.iter().any(|(n, k)| *n == "Server.Start" && **k == SymbolKind::Method),
+        "expected Server.Start (Method), got: {names:?}"
     );
 
     // HandleRequest function
@@ -82,8 +82,8 @@ fn test_go_extract_references() {
 
     // Start calls Println (via fmt.Println)
     assert!(
-        refs.iter().any(|r| r.from_symbol == "Start" && r.to_name == "Println"),
-        "expected Start -> Println call, got: {refs:?}",
+        refs.iter().any(|r| r.from_symbol == "Server.Start" && r.to_name == "Println"),
+        "expected Server.Start -> Println call, got: {refs:?}",
     );
 
     // HandleRequest calls Write (via w.Write)
@@ -154,3 +154,275 @@ fn test_go_signature_extraction() {
         handle_req.signature
     );
 }
+
+// ---------------------------------------------------------------------------
+// 5. Parameter and result types
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_type_signature() {
+    let source = "package main\n\n\
+                  func (s *Server) Serve(addr, host string, opts ...Option) (int, error) { return 0, nil }\n\n\
+                  func Stop(int) {}\n";
+    let tree = parse_go(source);
+    let symbols = GoGrammar.extract_symbols(source.as_bytes(), &tree);
+
+    let serve = symbols[0].types.as_ref().unwrap();
+    let param = |name: &str, ty: &str| Param { name: name.into(), type_name: Some(ty.into()) };
+    assert_eq!(
+        serve.params,
+        vec![param("addr", "string"), param("host", "string"), param("opts", "...Option")]
+    );
+    assert_eq!(serve.returns.as_deref(), Some("(int, error)"));
+
+    let stop = symbols[1].types.as_ref().unwrap();
+    assert_eq!(stop.params, vec![param("", "int")]);
+    assert_eq!(stop.returns, None);
+}
+
+// ---------------------------------------------------------------------------
+// 6. Build constraints and directives
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_directives() {
+    let source = "//go:build linux && amd64\n\npackage sys\n\n\
+                  // Fast path.\n//go:noinline\nfunc Fast() {}\n\n\
+                  //go:generate stringer -type=Mode\n\ntype Mode int\n";
+    let tree = parse_go(source);
+    let symbols = GoGrammar.extract_symbols(source.as_bytes(), &tree);
+
+    assert_eq!(symbols[0].attributes, vec!["//go:build linux && amd64", "//go:noinline"]);
+    // A blank line separates the directive from the declaration.
+    assert_eq!(symbols[1].attributes, vec!["//go:build linux && amd64"]);
+}
+
+// ---------------------------------------------------------------------------
+// 7. HTTP routes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_routes() {
+    let source = r#"package api
+
+func Routes(r *gin.Engine, mux *http.ServeMux, m *mux.Router) {
+	r.GET("/users/:id", h.GetUser)
+	r.POST("/users", createUser)
+	mux.HandleFunc("DELETE /items/{id}", deleteItem)
+	mux.Handle("/static/", fileServer)
+	m.HandleFunc("/orders", orders).Methods("GET", "POST")
+	cache.Get("/not-a-route")
+}
+"#;
+    let tree = parse_go(source);
+    let routes = GoGrammar.extract_routes(source.as_bytes(), &tree);
+    let route = |method: &str, path: &str, handler: &str, line: usize| ExtractedRoute {
+        method: method.into(),
+        path: path.into(),
+        handler: handler.into(),
+        line,
+    };
+    assert_eq!(
+        routes,
+        vec![
+            route("GET", "/users/:id", "GetUser", 4),
+            route("POST", "/users", "createUser", 5),
+            route("DELETE", "/items/{id}", "deleteItem", 6),
+            route("ANY", "/static/", "fileServer", 7),
+            route("GET", "/orders", "orders", 8),
+            route("POST", "/orders", "orders", 8),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 8. Error sites — discarded error returns
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_error_sites() {
+    let source = r#"package main
+
+func run() {
+	n, _ := strconv.Atoi(s)
+	_ = os.Remove(path)
+	_ = err
+	v, _ := cache[key]
+	for _, x := range xs {}
+	n, err := strconv.Atoi(s)
+}
+"#;
+    let tree = parse_go(source);
+    let sites = GoGrammar.extract_error_sites(source.as_bytes(), &tree);
+    let found: Vec<_> = sites.iter().map(|s| (s.kind.as_str(), s.line)).collect();
+    assert_eq!(found, vec![("ignored_error", 4), ("ignored_error", 5), ("ignored_error", 6)]);
+    assert_eq!(sites[1].snippet, "_ = os.Remove(path)");
+}
+
+// ---------------------------------------------------------------------------
+// 9. Methods are named after their receiver type
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_receiver_qualified_methods() {
+    let source = r#"package net
+
+type Server struct{}
+type Client struct{}
+type List[T any] struct{}
+
+func (s *Server) Start() { s.listen() }
+func (s *Server) listen() {}
+func (c Client) Start() { c.dial(); other.listen() }
+func (l *List[T]) Push(v T) {}
+"#;
+    let tree = parse_go(source);
+    let symbols = GoGrammar.extract_symbols(source.as_bytes(), &tree);
+    let methods: Vec<(&str, &str)> = symbols
+        .iter()
+        .filter(|s| s.kind == SymbolKind::Method)
+        .map(|s| (s.name.as_str(), s.qualified_name.as_str()))
+        .collect();
+    assert_eq!(
+        methods,
+        [
+            ("Server.Start", "net.Server.Start"),
+            ("Server.listen", "net.Server.listen"),
+            ("Client.Start", "net.Client.Start"),
+            ("List.Push", "net.List.Push"),
+        ]
+    );
+
+    let refs = GoGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<(&str, &str)> = refs.iter().map(|r| (r.from_symbol.as_str(), r.to_name.as_str())).collect();
+    assert_eq!(
+        calls,
+        [("Server.Start", "Server.listen"), ("Client.Start", "Client.dial"), ("Client.Start", "listen")],
+        "calls through the receiver name its type"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 10. Interface method sets
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_interface_members() {
+    let decl = "type ReadCloser interface {\n\tReader\n\tio.Closer\n\tClose() error\n}";
+    assert_eq!(
+        interface_members(decl, "ReadCloser"),
+        Some(InterfaceMembers {
+            methods: vec!["Close".to_string()],
+            embedded: vec!["Reader".to_string(), "io.Closer".to_string()],
+        })
+    );
+    assert_eq!(interface_members(decl, "Other"), None);
+    assert_eq!(interface_members("type Number interface { ~int | ~float64 }", "Number"), None);
+    assert_eq!(interface_members("type Server struct{}", "Server"), None);
+}
+
+// ---------------------------------------------------------------------------
+// 11. Generics — type parameters, aliases and instantiated calls
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_generics() {
+    let source = r#"package coll
+
+type List[T any] struct {
+    items []T
+}
+
+type Set[T comparable] = map[T]struct{}
+
+type ID = string
+
+func (l *List[T]) Push(v T) { l.items = append(l.items, v) }
+
+func Sum[N int | float64](xs []N) N { return 0 }
+
+func New[T any]() *List[T] { return &List[T]{} }
+
+func Use() {
+    Sum[float64](nil)
+    New[int]()
+    slices.Index[[]int](nil, 1)
+    Map[int, string](nil, nil)
+    handlers[i]()
+}
+"#;
+    let tree = parse_go(source);
+    let symbols = GoGrammar.extract_symbols(source.as_bytes(), &tree);
+    let summary: Vec<(&str, SymbolKind, &str)> = symbols
+        .iter()
+        .filter(|s| s.kind != SymbolKind::Function && s.kind != SymbolKind::Method)
+        .map(|s| (s.name.as_str(), s.kind.clone(), s.signature.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("List", SymbolKind::Struct, "type List[T any] struct type"),
+            ("Set", SymbolKind::TypeAlias, "type Set[T comparable] = map type"),
+            ("ID", SymbolKind::TypeAlias, "type ID = type identifier"),
+        ]
+    );
+    let sum = symbols.iter().find(|s| s.name == "Sum").unwrap();
+    assert_eq!(sum.signature, "func Sum[N int | float64](xs []N) N");
+    assert!(symbols.iter().any(|s| s.name == "List.Push"));
+
+    let refs = GoGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<&str> = refs
+        .iter()
+        .filter(|r| r.from_symbol == "Use")
+        .map(|r| r.to_name.as_str())
+        .collect();
+    assert_eq!(calls, ["Sum", "New", "Index", "Map"], "an indexed call names nothing");
+}
+
+// ---------------------------------------------------------------------------
+// 12. Function literals — package-level vars are functions
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_func_literals() {
+    let source = r#"package api
+
+var Handler = func(w http.ResponseWriter, r *http.Request) {
+    render(w)
+}
+
+var defaultClient = newClient()
+
+func Serve() {
+    go func() { listen() }()
+}
+"#;
+    let tree = parse_go(source);
+    let symbols = GoGrammar.extract_symbols(source.as_bytes(), &tree);
+    let handler = symbols.iter().find(|s| s.name == "Handler").unwrap();
+    assert_eq!(handler.kind, SymbolKind::Function);
+    assert_eq!(handler.signature, "var Handler = func(w http.ResponseWriter, r *http.Request)");
+    assert_eq!(handler.types.as_ref().unwrap().params.len(), 2);
+    let client = symbols.iter().find(|s| s.name == "defaultClient").unwrap();
+    assert_eq!(client.kind, SymbolKind::Const);
+
+    let refs = GoGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<(&str, &str)> = refs.iter().map(|r| (r.from_symbol.as_str(), r.to_name.as_str())).collect();
+    assert_eq!(
+        calls,
+        [("Handler", "render"), ("defaultClient", "newClient"), ("Serve", "listen")]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 13. Call arguments — count and literal kind per call site
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_call_arguments() {
+    let source = r#"package api
+
+func Run(xs []int) {
+    Connect("db", -5432, true, nil)
+    Apply(&Config{Port: 1}, []int{1}, func() {}, xs...)
+    Close()
+}
+"#;
+    let tree = parse_go(source);
+    let refs = GoGrammar.extract_references(source.as_bytes(), &tree);
+    let args = |name: &str| refs.iter().find(|r| r.to_name == name).unwrap().args.clone().unwrap();
+    assert_eq!(args("Connect"), ["string", "number", "bool", "null"]);
+    assert_eq!(args("Apply"), ["object", "array", "function", "spread"]);
+    assert!(args("Close").is_empty());
+}
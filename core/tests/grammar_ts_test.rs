@@ -1,5 +1,5 @@
 use focal_core::grammar::typescript::TypeScriptGrammar;
-use focal_core::grammar::{Grammar, SymbolKind};
+use focal_core::grammar::{ExtractedRoute, ExtractedSymbol, Grammar, Param, SymbolKind, Visibility};
 
 const TS_SOURCE: &str = r#"import { Request, Response } from 'express';
 
@@ -150,7 +150,7 @@ fn test_ts_extract_references() {
     // constructor calls new Map() => callee = "Map"
     assert!(
         refs.iter()
-            .any(|r| r.from_symbol == "constructor" && r.to_name == "Map"),
+            .any(|r| r.from_symbol == "UserService.constructor" && r.to_name == "Map"),
         "expected constructor -> Map new call, got: {refs:?}"
     );
 
@@ -215,3 +215,316 @@ fn test_ts_registry() {
         "expected for_extension(\"js\") to return Some"
     );
 }
+
+// ---------------------------------------------------------------------------
+// 5. Visibility and modifiers
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_visibility_and_modifiers() {
+    let source = "export default async function load() {}\n\
+                  function helper() {}\n\
+                  export abstract class Store {\n\
+                    static async create() {}\n\
+                    get size() { return 0; }\n\
+                    protected flush() {}\n\
+                    private reset() {}\n\
+                    #evict() {}\n\
+                  }\n";
+    let tree = parse_ts(source);
+    let symbols = TypeScriptGrammar.extract_symbols(source.as_bytes(), &tree);
+    let get = |name: &str| symbols.iter().find(|s| s.name == name).unwrap();
+
+    assert_eq!(get("load").visibility, Visibility::Public);
+    assert_eq!(get("load").modifiers, vec!["default", "async"]);
+    assert_eq!(get("helper").visibility, Visibility::Private);
+
+    let store = get("Store");
+    assert_eq!(store.visibility, Visibility::Public);
+    assert_eq!(store.modifiers, vec!["abstract"]);
+    let member = |name: &str| store.children.iter().find(|c| c.name == name).unwrap();
+    assert_eq!(member("create").visibility, Visibility::Public);
+    assert_eq!(member("create").modifiers, vec!["static", "async"]);
+    assert_eq!(member("size").modifiers, vec!["get"]);
+    assert_eq!(member("flush").visibility, Visibility::Protected);
+    assert_eq!(member("reset").visibility, Visibility::Private);
+    assert_eq!(member("#evict").visibility, Visibility::Private);
+}
+
+// ---------------------------------------------------------------------------
+// 6. Parameter and return types
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_type_signature() {
+    let source = "function fetch(url: string, retries?: number, ...rest: any[]): Promise<Response> {}\n\
+                  class Cache { put(key, value: Entry) {} }\n";
+    let tree = parse_ts(source);
+    let symbols = TypeScriptGrammar.extract_symbols(source.as_bytes(), &tree);
+
+    let types = symbols[0].types.as_ref().unwrap();
+    let names: Vec<(&str, Option<&str>)> = types
+        .params
+        .iter()
+        .map(|p| (p.name.as_str(), p.type_name.as_deref()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![("url", Some("string")), ("retries", Some("number")), ("...rest", Some("any[]"))]
+    );
+    assert_eq!(types.returns.as_deref(), Some("Promise<Response>"));
+
+    let put = symbols[1].children[0].types.as_ref().unwrap();
+    assert_eq!(
+        put.params,
+        vec![
+            Param { name: "key".into(), type_name: None },
+            Param { name: "value".into(), type_name: Some("Entry".into()) },
+        ]
+    );
+    assert_eq!(put.returns, None);
+}
+
+// ---------------------------------------------------------------------------
+// 7. Decorators
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_decorators() {
+    let source = "@Component({ selector: 'app' })\nexport class AppComponent {\n  @Input() name: string;\n  @HostListener('click')\n  onClick() {}\n  render() {}\n}\n\n@Injectable()\nclass Service {}\n";
+    let tree = parse_ts(source);
+    let symbols = TypeScriptGrammar.extract_symbols(source.as_bytes(), &tree);
+
+    assert_eq!(symbols[0].attributes, vec!["@Component({ selector: 'app' })"]);
+    let methods = &symbols[0].children;
+    assert_eq!(methods[0].name, "onClick");
+    assert_eq!(methods[0].attributes, vec!["@HostListener('click')"]);
+    assert!(methods[1].attributes.is_empty());
+    assert_eq!(symbols[1].attributes, vec!["@Injectable()"]);
+}
+
+// ---------------------------------------------------------------------------
+// 8. HTTP routes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_routes() {
+    let source = r#"app.get("/users", auth, listUsers);
+router.post(`/users`, handlers.createUser);
+cache.get("/key");
+
+@Controller("users")
+export class UsersController {
+  @Get(":id")
+  findOne() {}
+
+  @Post()
+  create() {}
+}
+"#;
+    let tree = parse_ts(source);
+    let routes = TypeScriptGrammar.extract_routes(source.as_bytes(), &tree);
+    let route = |method: &str, path: &str, handler: &str, line: usize| ExtractedRoute {
+        method: method.into(),
+        path: path.into(),
+        handler: handler.into(),
+        line,
+    };
+    assert_eq!(
+        routes,
+        vec![
+            route("GET", "/users", "listUsers", 1),
+            route("POST", "/users", "createUser", 2),
+            route("GET", "/users/:id", "findOne", 7),
+            route("POST", "/users", "create", 10),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 9. Error sites — empty catch blocks
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_error_sites() {
+    let source = "try { run(); } catch (e) {}\ntry {\n  run();\n} catch {\n  // best effort\n}\ntry { run(); } catch (e) { log(e); }\n";
+    let tree = parse_ts(source);
+    let sites = TypeScriptGrammar.extract_error_sites(source.as_bytes(), &tree);
+    let found: Vec<_> = sites.iter().map(|s| (s.kind.as_str(), s.line)).collect();
+    assert_eq!(found, vec![("empty_catch", 1), ("empty_catch", 4)]);
+}
+
+// ---------------------------------------------------------------------------
+// 10. Generics — type parameters stay in signatures, not names
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_generics() {
+    let source = r#"export class Box<T extends object> {
+    map<U>(f: (v: T) => U): Box<U> { return new Box<U>(); }
+}
+
+export interface Repo<T, K = string> {
+    find(id: K): T;
+}
+
+export type Pair<A, B> = [A, B];
+
+export function identity<T>(value: T): T {
+    return value;
+}
+
+export const wrap = <T,>(value: T): T[] => [value];
+
+function run() {
+    identity<string>("x");
+    new Map<string, number>();
+    repo.find<User>("1");
+}
+"#;
+    let tree = parse_ts(source);
+    let symbols = TypeScriptGrammar.extract_symbols(source.as_bytes(), &tree);
+    let names: Vec<(&str, SymbolKind)> = symbols.iter().map(|s| (s.name.as_str(), s.kind.clone())).collect();
+    assert_eq!(
+        names,
+        [
+            ("Box", SymbolKind::Class),
+            ("Repo", SymbolKind::Interface),
+            ("Pair", SymbolKind::TypeAlias),
+            ("identity", SymbolKind::Function),
+            ("wrap", SymbolKind::Function),
+            ("run", SymbolKind::Function),
+        ]
+    );
+    assert_eq!(symbols[0].children[0].name, "map");
+    assert_eq!(symbols[0].children[0].signature, "map<U>(f: (v: T) => U): Box<U>");
+    let identity = symbols.iter().find(|s| s.name == "identity").unwrap();
+    assert_eq!(identity.signature, "function identity<T>(value: T): T");
+
+    let refs = TypeScriptGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<&str> = refs
+        .iter()
+        .filter(|r| r.from_symbol == "run")
+        .map(|r| r.to_name.as_str())
+        .collect();
+    assert_eq!(calls, ["identity", "Map", "find"]);
+}
+
+// ---------------------------------------------------------------------------
+// 11. Arrow functions — named consts and class fields are functions
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_arrow_functions() {
+    let source = r#"export const fetchUser = async (id: string): Promise<User> => {
+    return api.get(id);
+};
+
+const routes = [{ path: "/", load: () => loadHome() }];
+
+class Widget {
+    private onClick = (e: Event) => { this.render(); };
+}
+
+function main() {
+    const inner = () => helper();
+    items.forEach(function (x) { track(x); });
+}
+"#;
+    let tree = parse_ts(source);
+    let symbols = TypeScriptGrammar.extract_symbols(source.as_bytes(), &tree);
+    let fetch = &symbols[0];
+    assert_eq!((fetch.name.as_str(), &fetch.kind), ("fetchUser", &SymbolKind::Function));
+    assert_eq!(fetch.signature, "const fetchUser = async (id: string): Promise<User> =>");
+    assert_eq!(fetch.modifiers, vec!["async"]);
+    assert_eq!(fetch.types.as_ref().unwrap().returns.as_deref(), Some("Promise<User>"));
+    assert_eq!(symbols[1].kind, SymbolKind::Const);
+    let on_click = &symbols[2].children[0];
+    assert_eq!((on_click.name.as_str(), &on_click.kind), ("onClick", &SymbolKind::Method));
+    assert_eq!(on_click.visibility, Visibility::Private);
+
+    let refs = TypeScriptGrammar.extract_references(source.as_bytes(), &tree);
+    let calls: Vec<(&str, &str)> = refs.iter().map(|r| (r.from_symbol.as_str(), r.to_name.as_str())).collect();
+    assert_eq!(
+        calls,
+        [
+            ("fetchUser", "get"),
+            ("routes", "loadHome"),
+            ("Widget.onClick", "render"),
+            ("main", "helper"),
+            ("main", "forEach"),
+            ("main", "track"),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 12. Namespaces — module symbols parent their qualified members
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_namespaces() {
+    let source = r#"namespace Validation {
+    export function isValid(s: string): boolean { return check(s); }
+    export namespace Rules {
+        export const required = (v: string) => v.length > 0;
+    }
+    class Checker {
+        run() {}
+    }
+}
+
+export namespace A.B {
+    function f() {}
+}
+
+declare module "express" {
+    export function extra(): void;
+}
+"#;
+    let tree = parse_ts(source);
+    let symbols = TypeScriptGrammar.extract_symbols(source.as_bytes(), &tree);
+    type Entry = (String, String, SymbolKind, Visibility);
+    fn flatten(syms: &[ExtractedSymbol], out: &mut Vec<Entry>) {
+        for s in syms {
+            out.push((s.name.clone(), s.qualified_name.clone(), s.kind.clone(), s.visibility));
+            flatten(&s.children, out);
+        }
+    }
+    let mut flat = Vec::new();
+    flatten(&symbols, &mut flat);
+    let entry = |name: &str, qualified: &str, kind: SymbolKind, vis: Visibility| {
+        (name.to_string(), qualified.to_string(), kind, vis)
+    };
+    assert_eq!(
+        flat,
+        [
+            entry("Validation", "Validation", SymbolKind::Module, Visibility::Private),
+            entry("isValid", "Validation.isValid", SymbolKind::Function, Visibility::Public),
+            entry("Rules", "Validation.Rules", SymbolKind::Module, Visibility::Public),
+            entry("required", "Validation.Rules.required", SymbolKind::Function, Visibility::Public),
+            entry("Checker", "Validation.Checker", SymbolKind::Class, Visibility::Private),
+            entry("run", "Validation.Checker.run", SymbolKind::Method, Visibility::Public),
+            entry("A.B", "A.B", SymbolKind::Module, Visibility::Public),
+            entry("f", "A.B.f", SymbolKind::Function, Visibility::Private),
+        ]
+    );
+    assert_eq!(symbols[0].signature, "namespace Validation");
+
+    let refs = TypeScriptGrammar.extract_references(source.as_bytes(), &tree);
+    assert!(refs.iter().any(|r| r.from_symbol == "Validation.isValid" && r.to_name == "check"));
+}
+
+// ---------------------------------------------------------------------------
+// 13. Call arguments — count and literal kind per call site
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ts_call_arguments() {
+    let source = r#"
+function run(items: string[]) {
+    connect("db", -5432, false, null, `x${1}`);
+    apply([1], { a: 1 }, (x) => x, ...items);
+    const m = new Map;
+    sql`SELECT 1`;
+}
+"#;
+    let tree = parse_ts(source);
+    let refs = TypeScriptGrammar.extract_references(source.as_bytes(), &tree);
+    let args = |name: &str| refs.iter().find(|r| r.to_name == name).unwrap().args.clone();
+    assert_eq!(args("connect").unwrap(), ["string", "number", "bool", "null", "string"]);
+    assert_eq!(args("apply").unwrap(), ["array", "object", "function", "spread"]);
+    assert_eq!(args("Map").unwrap(), Vec::<String>::new());
+    assert_eq!(args("sql"), None, "tagged templates have no argument list");
+}
@@ -0,0 +1,116 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::graph::GraphEngine;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn dependent_names(db: &Database, symbol_id: i64) -> Vec<String> {
+    let graph = db.graph().unwrap();
+    graph.incoming(symbol_id).map(|e| graph.symbol(e.source_id).unwrap().name.clone()).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Re-indexing a file patches the cache instead of rebuilding it
+// ---------------------------------------------------------------------------
+#[test]
+fn test_incremental_update_on_reindex() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("store.go"), "package main\n\nfunc save() {}\n").unwrap();
+    fs::write(dir.path().join("api.go"), "package main\n\nfunc create() {\n    save()\n}\n").unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+
+    let nodes = GraphEngine::new(&db).impact_graph("save", 2, Some(repo_id)).unwrap();
+    assert_eq!(nodes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), ["create"]);
+    assert_eq!(db.graph().unwrap().stats().builds, 1);
+
+    fs::write(
+        dir.path().join("api.go"),
+        "package main\n\nfunc update() {\n    save()\n}\n\nfunc archive() {\n    save()\n}\n",
+    )
+    .unwrap();
+    Indexer::new(&db, &registry).index_file(&root.join("api.go"), &root).unwrap();
+
+    let nodes = GraphEngine::new(&db).impact_graph("save", 2, Some(repo_id)).unwrap();
+    assert_eq!(nodes.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), ["archive", "update"]);
+    assert_eq!(db.graph().unwrap().stats().builds, 1, "patched, not rebuilt");
+
+    let save = db.find_symbol_by_name(repo_id, "save").unwrap().unwrap();
+    let expected: Vec<String> = db.get_dependents(save.id).unwrap().into_iter().map(|(_, s)| s.name).collect();
+    assert_eq!(dependent_names(&db, save.id), expected, "same order as get_dependents");
+}
+
+// ---------------------------------------------------------------------------
+// 2. Rows read inside a transaction that rolls back don't linger
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rollback_after_sync_in_transaction() {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("test-repo", "/tmp/test-repo").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/lib.rs", "rust", "abc123").unwrap();
+    let a = db.insert_symbol(file_id, "a", "", "function", "fn a()", "", "", 1, 2, None).unwrap();
+    let b = db.insert_symbol(file_id, "b", "", "function", "fn b()", "", "", 3, 4, None).unwrap();
+    assert!(dependent_names(&db, a).is_empty());
+
+    db.begin_transaction().unwrap();
+    db.insert_edge(b, a, "calls").unwrap();
+    assert_eq!(dependent_names(&db, a), ["b"]);
+    db.rollback_transaction();
+
+    assert!(dependent_names(&db, a).is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Commits by another connection rebuild the cache
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rebuild_after_outside_commit() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("focal.db");
+    let db = Database::open(&path.to_string_lossy()).unwrap();
+    let repo_id = db.upsert_repository("test-repo", "/tmp/test-repo").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/lib.rs", "rust", "abc123").unwrap();
+    let a = db.insert_symbol(file_id, "a", "", "function", "fn a()", "", "", 1, 2, None).unwrap();
+    let b = db.insert_symbol(file_id, "b", "", "function", "fn b()", "", "", 3, 4, None).unwrap();
+    assert!(dependent_names(&db, a).is_empty());
+
+    let other = Database::open(&path.to_string_lossy()).unwrap();
+    other.insert_edge(b, a, "calls").unwrap();
+
+    assert_eq!(dependent_names(&db, a), ["b"]);
+    assert_eq!(db.graph().unwrap().stats().builds, 2);
+}
+
+// ---------------------------------------------------------------------------
+// 4. A graph over its budget serves the traversal, then is released
+// ---------------------------------------------------------------------------
+#[test]
+fn test_graph_over_budget_is_released() {
+    let db = Database::open_in_memory().unwrap().with_graph_cache_budget(64);
+    let repo_id = db.upsert_repository("test-repo", "/tmp/test-repo").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/lib.rs", "rust", "abc123").unwrap();
+    let a = db.insert_symbol(file_id, "a", "", "function", "fn a()", "", "", 1, 2, None).unwrap();
+    let b = db.insert_symbol(file_id, "b", "", "function", "fn b()", "", "", 3, 4, None).unwrap();
+    db.insert_edge(b, a, "calls").unwrap();
+
+    let nodes = GraphEngine::new(&db).impact_graph("a", 1, Some(repo_id)).unwrap();
+    assert_eq!(nodes.len(), 1);
+    let stats = db.graph_cache_stats().unwrap();
+    assert!(!stats.resident);
+    assert_eq!((stats.builds, stats.evictions, stats.bytes), (1, 1, 0));
+
+    assert_eq!(dependent_names(&db, a), ["b"], "rebuilt for the next traversal");
+    assert_eq!(db.graph_cache_stats().unwrap().builds, 2);
+
+    let roomy = Database::open_in_memory().unwrap();
+    let repo_id = roomy.upsert_repository("test-repo", "/tmp/test-repo").unwrap();
+    roomy.upsert_file(repo_id, "src/lib.rs", "rust", "abc123").unwrap();
+    drop(roomy.graph().unwrap());
+    let stats = roomy.graph_cache_stats().unwrap();
+    assert!(stats.resident && stats.bytes > 0 && stats.bytes <= stats.budget_bytes);
+}
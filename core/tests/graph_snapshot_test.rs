@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::PathBuf;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::graph_snapshot::{graph_diff, GraphSnapshot, SnapshotEdge, SNAPSHOTS_WITH_GRAPH, SNAPSHOT_HISTORY};
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+const MAIN: &str = "package main\n\nfunc main() {\n    serve()\n}\n";
+const SERVER: &str = "package main\n\nfunc serve() {\n    listen()\n}\n\nfunc listen() {}\n";
+
+/// Index `main.go` and `server.go`; returns the root and the repo id.
+fn indexed_repo(db: &Database, registry: &GrammarRegistry, dir: &TempDir) -> (PathBuf, i64) {
+    fs::write(dir.path().join("main.go"), MAIN).unwrap();
+    fs::write(dir.path().join("server.go"), SERVER).unwrap();
+    Indexer::new(db, registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let repo = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap();
+    (root, repo.id)
+}
+
+fn edge(source: &str, target: &str) -> SnapshotEdge {
+    SnapshotEdge { source: source.to_string(), target: target.to_string(), kind: "calls".to_string() }
+}
+
+// ---------------------------------------------------------------------------
+// 1. An index run records counts and a hash; an unchanged run records nothing
+// ---------------------------------------------------------------------------
+#[test]
+fn test_unchanged_runs() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (_, repo_id) = indexed_repo(&db, &registry, &dir);
+
+    assert!(graph_diff(&db, repo_id, false, 100).unwrap().is_none(), "one run only");
+    let live = graph_diff(&db, repo_id, true, 100).unwrap().unwrap();
+    assert!(!live.changed);
+    assert_eq!((live.from.symbols, live.from.edges), (3, 2));
+
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert!(graph_diff(&db, repo_id, false, 100).unwrap().is_none(), "the same graph is not stored twice");
+    assert_eq!(db.recent_graph_snapshots(repo_id, 100).unwrap().len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// 2. A refactor between runs shows up as added and removed symbols/edges
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diff_between_runs() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (_, repo_id) = indexed_repo(&db, &registry, &dir);
+
+    // Inline `listen` into `serve` and add a `shutdown` step.
+    fs::write(
+        dir.path().join("server.go"),
+        "package main\n\nfunc serve() {\n    shutdown()\n}\n\nfunc shutdown() {}\n",
+    )
+    .unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let diff = graph_diff(&db, repo_id, false, 100).unwrap().unwrap();
+    assert!(diff.changed);
+    assert_eq!(diff.symbols_added, ["server.go::main.shutdown"]);
+    assert_eq!(diff.symbols_removed, ["server.go::main.listen"]);
+    assert_eq!(diff.edges_added, [edge("server.go::main.serve", "server.go::main.shutdown")]);
+    assert_eq!(diff.edges_removed, [edge("server.go::main.serve", "server.go::main.listen")]);
+    assert_eq!(diff.history.len(), 2);
+    assert_eq!(diff.omitted, 0);
+
+    let capped = graph_diff(&db, repo_id, false, 0).unwrap().unwrap();
+    assert!(capped.symbols_added.is_empty());
+    assert_eq!(capped.omitted, 4);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Live diffs include incremental re-indexes since the last run
+// ---------------------------------------------------------------------------
+#[test]
+fn test_live_diff() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (root, repo_id) = indexed_repo(&db, &registry, &dir);
+
+    fs::write(dir.path().join("main.go"), "package main\n\nfunc main() {\n    serve()\n    listen()\n}\n").unwrap();
+    Indexer::new(&db, &registry).index_file(&root.join("main.go"), &root).unwrap();
+
+    let diff = graph_diff(&db, repo_id, true, 100).unwrap().unwrap();
+    assert!(diff.changed);
+    assert!(diff.to.taken_at.is_none());
+    assert_eq!(diff.edges_added, [edge("main.go::main.main", "server.go::main.listen")]);
+    assert!(diff.symbols_added.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 4. Old runs keep counts only; history is bounded
+// ---------------------------------------------------------------------------
+#[test]
+fn test_snapshot_pruning() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (_, repo_id) = indexed_repo(&db, &registry, &dir);
+    for i in 0..SNAPSHOT_HISTORY {
+        fs::write(dir.path().join("extra.go"), format!("package main\n\nfunc step{i}() {{}}\n")).unwrap();
+        Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    }
+
+    let stored = db.recent_graph_snapshots(repo_id, 100).unwrap();
+    assert_eq!(stored.len(), SNAPSHOT_HISTORY);
+    let with_graph = stored.iter().filter(|s| s.graph.is_some()).count();
+    assert_eq!(with_graph, SNAPSHOTS_WITH_GRAPH);
+    assert!(stored[0].graph.is_some() && stored[1].graph.is_some());
+}
+
+// ---------------------------------------------------------------------------
+// 5. Symbols sharing a qualified name in one file are told apart by line
+// ---------------------------------------------------------------------------
+#[test]
+fn test_repeated_names_keyed_by_line() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("box.py"),
+        "class Box:\n    @property\n    def size(self):\n        return measure()\n\n\
+         \x20   @size.setter\n    def size(self, value):\n        store(value)\n\n\
+         def measure():\n    pass\n\ndef store(value):\n    pass\n",
+    )
+    .unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+
+    let live = graph_diff(&db, repo_id, true, 100).unwrap().unwrap();
+    assert_eq!((live.to.symbols, live.to.edges), (5, 2), "getter and setter keep their own edges");
+    let snapshot = GraphSnapshot::capture(&db, repo_id).unwrap();
+    let keys: Vec<&str> = snapshot.symbols.iter().map(String::as_str).collect();
+    assert_eq!(
+        keys,
+        ["box.py::box.Box", "box.py::box.Box.size@3", "box.py::box.Box.size@7", "box.py::box.measure", "box.py::box.store"]
+    );
+}
@@ -1,5 +1,7 @@
+use std::time::Duration;
+
 use focal_core::db::Database;
-use focal_core::graph::GraphEngine;
+use focal_core::graph::{GraphEngine, TraversalLimits};
 
 /// Helper: create an in-memory DB with a repo, a file, and return (db, repo_id, file_id).
 fn setup_db() -> (Database, i64, i64) {
@@ -108,6 +110,23 @@ fn test_impact_graph_unknown_symbol() {
     assert!(result.unwrap_err().to_string().contains("not found"));
 }
 
+// ---------------------------------------------------------------------------
+// 3b. Unknown symbol error carries "did you mean" suggestions
+// ---------------------------------------------------------------------------
+#[test]
+fn test_impact_graph_unknown_symbol_suggestions() {
+    let (db, repo_id, file_id) = setup_db();
+    db.insert_symbol(file_id, "HandleRequest", "", "function", "", "", "", 1, 5, None)
+        .unwrap();
+    let engine = GraphEngine::new(&db);
+    let err = engine
+        .impact_graph("HandleReqest", 2, Some(repo_id))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("not found"));
+    assert!(err.contains("Did you mean: HandleRequest?"), "got: {err}");
+}
+
 // ---------------------------------------------------------------------------
 // 4. Logic flow: main -> HandleRequest -> Process -> SaveToDB
 // ---------------------------------------------------------------------------
@@ -229,3 +248,133 @@ fn test_logic_flow_same_symbol() {
     assert_eq!(paths[0].len(), 1);
     assert_eq!(paths[0][0].name, "SelfRef");
 }
+
+// ---------------------------------------------------------------------------
+// 8. Impact graph and edge lists are ordered by distance, then name
+// ---------------------------------------------------------------------------
+#[test]
+fn test_graph_order_is_deterministic() {
+    let (db, repo_id, file_id) = setup_db();
+
+    let a = db.insert_symbol(file_id, "A", "", "function", "fn A()", "", "", 1, 1, None).unwrap();
+    let mut ids = Vec::new();
+    for (i, name) in ["Zeta", "Alpha", "Mid"].iter().enumerate() {
+        let line = 10 + i as i64;
+        let id = db.insert_symbol(file_id, name, "", "function", "", "", "", line, line, None).unwrap();
+        db.insert_edge(id, a, "calls").unwrap();
+        ids.push(id);
+    }
+    let beta = db.insert_symbol(file_id, "Beta", "", "function", "", "", "", 20, 20, None).unwrap();
+    db.insert_edge(beta, ids[0], "calls").unwrap();
+
+    let nodes = GraphEngine::new(&db).impact_graph("A", 2, Some(repo_id)).unwrap();
+    let names: Vec<&str> = nodes.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["Alpha", "Mid", "Zeta", "Beta"]);
+
+    let dependents: Vec<String> = db.get_dependents(a).unwrap().into_iter().map(|(_, s)| s.name).collect();
+    assert_eq!(dependents, vec!["Alpha", "Mid", "Zeta"]);
+}
+
+// ---------------------------------------------------------------------------
+// 9. File path: shortest chain of files, each hop naming a crossing edge
+// ---------------------------------------------------------------------------
+#[test]
+fn test_find_file_path() {
+    let (db, repo_id, cli) = setup_db();
+    let orders = db.upsert_file(repo_id, "src/orders.rs", "rust", "h1").unwrap();
+    let billing = db.upsert_file(repo_id, "src/billing.rs", "rust", "h2").unwrap();
+    let audit = db.upsert_file(repo_id, "src/audit.rs", "rust", "h3").unwrap();
+
+    let run = db.insert_symbol(cli, "run", "", "function", "fn run()", "", "", 1, 5, None).unwrap();
+    let place = db.insert_symbol(orders, "place", "", "function", "fn place()", "", "", 1, 5, None).unwrap();
+    let charge = db.insert_symbol(billing, "charge", "", "function", "fn charge()", "", "", 1, 5, None).unwrap();
+    let log = db.insert_symbol(audit, "log", "", "function", "fn log()", "", "", 1, 5, None).unwrap();
+
+    // cli -> orders -> billing, and a longer detour cli -> audit -> orders.
+    db.insert_edge(run, place, "calls").unwrap();
+    db.insert_edge(run, log, "calls").unwrap();
+    db.insert_edge(log, place, "calls").unwrap();
+    db.insert_edge(place, charge, "calls").unwrap();
+
+    let engine = GraphEngine::new(&db);
+    let chain = engine.find_file_path(cli, billing).unwrap().unwrap();
+    let files: Vec<&str> = chain.iter().map(|h| h.file_path.as_str()).collect();
+    assert_eq!(files, ["src/lib.rs", "src/orders.rs", "src/billing.rs"]);
+    assert!(chain[0].via.is_none());
+    let via = chain[2].via.as_ref().unwrap();
+    assert_eq!((via.from_symbol.as_str(), via.to_symbol.as_str()), ("place", "charge"));
+
+    assert!(engine.find_file_path(billing, cli).unwrap().is_none(), "edges are directed");
+    assert_eq!(engine.find_file_path(cli, cli).unwrap().unwrap().len(), 1);
+
+    // Stopping at a limit is an error, not "unreachable".
+    let limited = GraphEngine::new(&db).with_limits(TraversalLimits { max_nodes: 2, ..TraversalLimits::default() });
+    let err = limited.find_file_path(cli, billing).unwrap_err();
+    assert!(err.to_string().contains("max_nodes"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// 10. Impact graph: traversal limits return the nearest dependents, marked
+//     truncated
+// ---------------------------------------------------------------------------
+#[test]
+fn test_impact_graph_limits() {
+    let (db, repo_id, file_id) = setup_db();
+
+    // Root <- caller_0..caller_4 (distance 1) <- outer (distance 2).
+    let root = db
+        .insert_symbol(file_id, "Root", "", "function", "fn Root()", "", "", 1, 2, None)
+        .unwrap();
+    let outer = db
+        .insert_symbol(file_id, "outer", "", "function", "fn outer()", "", "", 100, 101, None)
+        .unwrap();
+    for i in 0..5 {
+        let line = 10 + i * 2;
+        let caller = db
+            .insert_symbol(file_id, &format!("caller_{i}"), "", "function", "fn caller()", "", "", line, line + 1, None)
+            .unwrap();
+        db.insert_edge(caller, root, "calls").unwrap();
+        db.insert_edge(outer, caller, "calls").unwrap();
+    }
+
+    let full = GraphEngine::new(&db).impact_graph_report("Root", 3, Some(repo_id)).unwrap();
+    assert_eq!(full.nodes.len(), 6);
+    assert!(full.truncated.is_none());
+
+    let limits = TraversalLimits { max_nodes: 5, ..TraversalLimits::default() };
+    let capped = GraphEngine::new(&db).with_limits(limits).impact_graph_report("Root", 3, Some(repo_id)).unwrap();
+    assert_eq!(capped.truncated.as_deref(), Some("max_nodes"));
+    let names: Vec<&str> = capped.nodes.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, ["caller_0", "caller_1", "caller_2", "caller_3", "caller_4"]);
+
+    let limits = TraversalLimits { timeout: Duration::ZERO, ..TraversalLimits::default() };
+    let timed_out = GraphEngine::new(&db).with_limits(limits).impact_graph_report("Root", 3, Some(repo_id)).unwrap();
+    assert_eq!(timed_out.truncated.as_deref(), Some("timeout"));
+}
+
+// ---------------------------------------------------------------------------
+// 11. Impact graph: a wide level expanded on several threads is complete
+//     and merged in order
+// ---------------------------------------------------------------------------
+#[test]
+fn test_impact_graph_wide_frontier() {
+    let (db, repo_id, file_id) = setup_db();
+    db.with_transaction(|| {
+        let root = db.insert_symbol(file_id, "Root", "", "function", "fn Root()", "", "", 1, 2, None)?;
+        for i in 0..1500 {
+            let caller = db.insert_symbol(file_id, &format!("caller_{i:04}"), "", "function", "", "", "", 10, 11, None)?;
+            let outer = db.insert_symbol(file_id, &format!("outer_{i:04}"), "", "function", "", "", "", 20, 21, None)?;
+            db.insert_edge(caller, root, "calls")?;
+            db.insert_edge(outer, caller, "calls")?;
+        }
+        Ok(())
+    })
+    .unwrap();
+
+    let report = GraphEngine::new(&db).impact_graph_report("Root", 3, Some(repo_id)).unwrap();
+    assert!(report.truncated.is_none());
+    assert_eq!(report.nodes.len(), 3000);
+    assert!(report.nodes[..1500].iter().all(|n| n.distance == 1 && n.name.starts_with("caller_")));
+    assert!(report.nodes[1500..].iter().all(|n| n.distance == 2 && n.name.starts_with("outer_")));
+    assert_eq!(report.nodes[1500].name, "outer_0000");
+}
@@ -0,0 +1,222 @@
+use std::fs;
+
+use focal_core::config::FocalConfig;
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::guardrails::{generated_pattern, Guardrails, LimitExceeded};
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn guardrails(max_files: usize, max_symbols: usize, generated_dir_files: usize) -> Guardrails {
+    Guardrails::new(max_files, max_symbols, generated_dir_files)
+}
+
+fn repo_id(db: &Database, dir: &TempDir) -> i64 {
+    let root = dir.path().canonicalize().unwrap();
+    db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id
+}
+
+fn indexed_paths(db: &Database, dir: &TempDir) -> Vec<String> {
+    let mut paths: Vec<String> = db.get_files_for_repo(repo_id(db, dir)).unwrap().into_iter().map(|f| f.path).collect();
+    paths.sort();
+    paths
+}
+
+// ---------------------------------------------------------------------------
+// 1. Generated name patterns
+// ---------------------------------------------------------------------------
+#[test]
+fn test_generated_pattern() {
+    assert_eq!(generated_pattern("user.pb.go").as_deref(), Some("*.pb.go"));
+    assert_eq!(generated_pattern("user_grpc.pb.go").as_deref(), Some("*_grpc.pb.go"));
+    assert_eq!(generated_pattern("api_pb2.py").as_deref(), Some("*_pb2.py"));
+    assert_eq!(generated_pattern("case_0012.ts").as_deref(), Some("case_#.ts"));
+    assert_eq!(generated_pattern("v1_20.go").as_deref(), Some("v#_#.go"));
+    assert_eq!(generated_pattern("handler.go"), None);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Directories dominated by one generated pattern are skipped
+// ---------------------------------------------------------------------------
+#[test]
+fn test_generated_dirs_skipped() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("api/gen")).unwrap();
+    fs::create_dir_all(dir.path().join("mixed")).unwrap();
+    fs::write(dir.path().join("main.go"), "package main\n\nfunc main() {}\n").unwrap();
+    for i in 0..5 {
+        fs::write(dir.path().join(format!("api/gen/m{i}.pb.go")), format!("package gen\n\nfunc M{i}() {{}}\n")).unwrap();
+    }
+    for i in 0..4 {
+        fs::write(dir.path().join(format!("mixed/m{i}.pb.go")), "package mixed\n").unwrap();
+    }
+    for name in ["a", "b", "c", "d", "e"] {
+        fs::write(dir.path().join(format!("mixed/{name}.go")), "package mixed\n").unwrap();
+    }
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry).with_guardrails(guardrails(100, 1000, 3));
+    let stats = indexer.index_directory(dir.path()).unwrap();
+    assert_eq!(stats.generated_dirs, ["api/gen (5 files like *.pb.go)"]);
+    let paths = indexed_paths(&db, &dir);
+    assert!(paths.iter().all(|p| !p.starts_with("api/gen/")), "{paths:?}");
+    assert_eq!(paths.iter().filter(|p| p.starts_with("mixed/")).count(), 9, "generated files are a minority");
+
+    // The watcher and changed-only walks skip the directory too.
+    assert!(!indexer.index_file(&dir.path().join("api/gen/m0.pb.go"), dir.path()).unwrap());
+    indexer.reindex_changed(dir.path()).unwrap();
+    assert_eq!(indexed_paths(&db, &dir), paths);
+
+    // 0 turns the heuristic off.
+    Indexer::new(&db, &registry).with_guardrails(guardrails(100, 1000, 0)).index_directory(dir.path()).unwrap();
+    assert_eq!(indexed_paths(&db, &dir).len(), paths.len() + 5);
+}
+
+// ---------------------------------------------------------------------------
+// 3. The file cap aborts the walk; --force indexes and waives it
+// ---------------------------------------------------------------------------
+#[test]
+fn test_file_cap() {
+    let dir = TempDir::new().unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(dir.path().join(format!("{name}.go")), format!("package main\n\nfunc {name}() {{}}\n")).unwrap();
+    }
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let capped = || Indexer::new(&db, &registry).with_guardrails(guardrails(2, 1000, 0));
+
+    let err = capped().index_directory(dir.path()).unwrap_err();
+    let limit = err.downcast_ref::<LimitExceeded>().expect("a LimitExceeded error");
+    assert_eq!((limit.limit, limit.count, limit.max), ("files", 3, 2));
+    assert!(err.to_string().contains("--force"));
+    assert!(indexed_paths(&db, &dir).is_empty(), "nothing written");
+
+    let stats = capped().with_force(true).index_directory(dir.path()).unwrap();
+    assert_eq!(stats.files_indexed, 3);
+    assert!(db.limits_waived(repo_id(&db, &dir)).unwrap());
+
+    fs::write(dir.path().join("d.go"), "package main\n\nfunc d() {}\n").unwrap();
+    assert_eq!(capped().index_directory(dir.path()).unwrap().files_indexed, 1, "waiver persists");
+}
+
+// ---------------------------------------------------------------------------
+// 4. The symbol cap counts the repository's symbols
+// ---------------------------------------------------------------------------
+#[test]
+fn test_symbol_cap() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.go"), "package main\n\nfunc a() {}\n\nfunc b() {}\n\nfunc c() {}\n").unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+
+    let err = Indexer::new(&db, &registry)
+        .with_guardrails(guardrails(10, 2, 0))
+        .index_directory(dir.path())
+        .unwrap_err();
+    let limit = err.downcast_ref::<LimitExceeded>().unwrap();
+    assert_eq!((limit.limit, limit.count, limit.max), ("symbols", 3, 2));
+    assert!(Indexer::new(&db, &registry).with_guardrails(guardrails(10, 3, 0)).index_directory(dir.path()).is_ok());
+}
+
+// ---------------------------------------------------------------------------
+// 5. [index] settings
+// ---------------------------------------------------------------------------
+#[test]
+fn test_config() {
+    assert_eq!(FocalConfig::default().index.guardrails(), Guardrails::default());
+    let config: FocalConfig =
+        toml::from_str("[index]\nmax_files_per_repo = 10\nmax_symbols_per_repo = 20\ngenerated_dir_files = 0\n").unwrap();
+    assert_eq!(config.index.guardrails(), guardrails(10, 20, 0));
+}
+
+// ---------------------------------------------------------------------------
+// 6. Only source files count toward a generated pattern
+// ---------------------------------------------------------------------------
+#[test]
+fn test_generated_dirs_count_source_files() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("assets")).unwrap();
+    for i in 0..10 {
+        fs::write(dir.path().join(format!("assets/frame_{i}.png")), "png").unwrap();
+    }
+    fs::write(dir.path().join("assets/loader.go"), "package assets\n\nfunc Load() {}\n").unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry).with_guardrails(guardrails(100, 1000, 3));
+    let stats = indexer.index_directory(dir.path()).unwrap();
+    assert!(stats.generated_dirs.is_empty(), "{:?}", stats.generated_dirs);
+    assert_eq!(indexed_paths(&db, &dir), ["assets/loader.go"]);
+
+    // Verdicts are cached per directory, and recomputed once it changes.
+    for i in 0..5 {
+        fs::write(dir.path().join(format!("assets/frame_{i}.go")), "package assets\n").unwrap();
+    }
+    assert!(!indexer.index_file(&dir.path().join("assets/frame_0.go"), dir.path()).unwrap());
+}
+
+/// Run a git command in `dir`, panicking on failure.
+fn git(dir: &TempDir, args: &[&str]) {
+    let out = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "git {args:?} failed: {out:?}");
+}
+
+// ---------------------------------------------------------------------------
+// 7. Changed-only runs apply the caps, and --force waives them
+// ---------------------------------------------------------------------------
+#[test]
+fn test_reindex_changed_caps() {
+    let dir = TempDir::new().unwrap();
+    for name in ["a", "b"] {
+        fs::write(dir.path().join(format!("{name}.go")), format!("package main\n\nfunc {name}() {{}}\n")).unwrap();
+    }
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let capped = |max_files, max_symbols| {
+        Indexer::new(&db, &registry).with_guardrails(guardrails(max_files, max_symbols, 0))
+    };
+    capped(2, 1000).index_directory(dir.path()).unwrap();
+
+    fs::write(dir.path().join("c.go"), "package main\n\nfunc c() {}\n").unwrap();
+    let err = capped(2, 1000).reindex_changed(dir.path()).unwrap_err();
+    let limit = err.downcast_ref::<LimitExceeded>().unwrap();
+    assert_eq!((limit.limit, limit.count, limit.max), ("files", 3, 2));
+    assert_eq!(indexed_paths(&db, &dir), ["a.go", "b.go"]);
+
+    let err = capped(10, 2).reindex_changed(dir.path()).unwrap_err();
+    assert_eq!(err.downcast_ref::<LimitExceeded>().unwrap().limit, "symbols");
+    assert_eq!(indexed_paths(&db, &dir), ["a.go", "b.go"], "rolled back");
+
+    let stats = capped(2, 2).with_force(true).reindex_changed(dir.path()).unwrap();
+    assert_eq!(stats.files_indexed, 1);
+    assert!(db.limits_waived(repo_id(&db, &dir)).unwrap());
+}
+
+// ---------------------------------------------------------------------------
+// 8. Revision runs apply the caps
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_revision_caps() {
+    let dir = TempDir::new().unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(dir.path().join(format!("{name}.go")), format!("package main\n\nfunc {name}() {{}}\n")).unwrap();
+    }
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "initial"]);
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let capped = || Indexer::new(&db, &registry).with_guardrails(guardrails(2, 1000, 0));
+    let err = capped().index_revision(dir.path(), "HEAD").unwrap_err();
+    assert_eq!(err.downcast_ref::<LimitExceeded>().unwrap().limit, "files");
+    assert!(indexed_paths(&db, &dir).is_empty());
+
+    assert_eq!(capped().with_force(true).index_revision(dir.path(), "HEAD").unwrap().files_indexed, 3);
+}
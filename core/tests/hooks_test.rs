@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::hooks::{hook_block, install_hooks, HookChange};
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn git_init(root: &Path) -> bool {
+    Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(root)
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+// ---------------------------------------------------------------------------
+// 1. Hooks are created, existing hooks are extended once
+// ---------------------------------------------------------------------------
+#[test]
+fn test_install_hooks() {
+    let repo = TempDir::new().unwrap();
+    if !git_init(repo.path()) {
+        eprintln!("git not available, skipping");
+        return;
+    }
+    let hooks = repo.path().join(".git/hooks");
+    fs::create_dir_all(&hooks).unwrap();
+    fs::write(hooks.join("post-checkout"), "#!/bin/sh\necho checked out\n").unwrap();
+    let binary = Path::new("/usr/local/bin/focal");
+
+    let changes = install_hooks(repo.path(), binary).unwrap();
+    assert!(matches!(&changes[0], HookChange::Created(p) if p.ends_with("post-commit")), "{changes:?}");
+    assert!(matches!(&changes[1], HookChange::Appended(p) if p.ends_with("post-checkout")), "{changes:?}");
+
+    let commit = fs::read_to_string(hooks.join("post-commit")).unwrap();
+    assert!(commit.starts_with("#!/bin/sh\n"));
+    assert!(commit.contains("reindex --changed-only"), "{commit}");
+    let checkout = fs::read_to_string(hooks.join("post-checkout")).unwrap();
+    assert!(checkout.starts_with("#!/bin/sh\necho checked out\n"), "{checkout}");
+    assert!(checkout.ends_with(&hook_block(binary, repo.path())), "{checkout}");
+
+    // Paths are single-quoted, embedded quotes included.
+    let block = hook_block(Path::new("/opt/it's/focal"), Path::new("/src/a \"b\" $c"));
+    assert!(block.contains("'/opt/it'\\''s/focal' reindex --changed-only '/src/a \"b\" $c' "), "{block}");
+
+    // Re-running is a no-op
+    let again = install_hooks(repo.path(), binary).unwrap();
+    assert!(again.iter().all(|c| matches!(c, HookChange::AlreadyInstalled(_))), "{again:?}");
+    assert_eq!(fs::read_to_string(hooks.join("post-checkout")).unwrap(), checkout);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(hooks.join("post-commit")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0o111);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 2. reindex_changed picks up edits, additions and deletions only
+// ---------------------------------------------------------------------------
+#[test]
+fn test_reindex_changed_only() {
+    let repo = TempDir::new().unwrap();
+    fs::write(repo.path().join("a.go"), "package main\n\nfunc A() {}\n").unwrap();
+    fs::write(repo.path().join("b.go"), "package main\n\nfunc B() {}\n").unwrap();
+    fs::write(repo.path().join("c.go"), "package main\n\nfunc C() {}\n").unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(repo.path()).unwrap();
+
+    fs::write(repo.path().join("a.go"), "package main\n\nfunc A2() { D() }\n").unwrap();
+    fs::write(repo.path().join("d.go"), "package main\n\nfunc D() {}\n").unwrap();
+    fs::remove_file(repo.path().join("c.go")).unwrap();
+
+    let stats = indexer.reindex_changed(repo.path()).unwrap();
+    assert_eq!((stats.files_indexed, stats.files_skipped), (2, 1), "{stats:?}");
+    assert!(stats.errors.is_empty(), "{:?}", stats.errors);
+
+    let repo_id = db.list_repositories().unwrap()[0].id;
+    let paths: Vec<String> = db.get_files_for_repo(repo_id).unwrap().into_iter().map(|f| f.path).collect();
+    assert_eq!(paths, ["a.go", "b.go", "d.go"]);
+    assert!(db.find_symbol_by_name(repo_id, "A2").unwrap().is_some());
+    assert!(db.find_symbol_by_name(repo_id, "A").unwrap().is_none());
+    assert!(db.find_symbol_by_name(repo_id, "C").unwrap().is_none());
+}
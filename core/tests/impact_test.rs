@@ -0,0 +1,126 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::impact::{diff_impact, parse_unified_diff, DiffSide, FileChange};
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+// ---------------------------------------------------------------------------
+// 1. Hunks become changed lines on both sides
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_unified_diff() {
+    let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3,3 +3,4 @@ fn a() {
+ fn a() {
+-    old();
++    new();
++    more();
+ }
+@@ -20 +21,0 @@
+--- removed sql comment
+diff --git a/gone.py b/gone.py
+--- a/gone.py
++++ /dev/null
+@@ -1,2 +0,0 @@
+-def f():
+-    pass
+--- a/notes.txt\t2026-01-01 00:00:00
++++ b/notes.txt\t2026-01-02 00:00:00
+@@ -1 +1 @@
+-a
+\\ No newline at end of file
++b
+";
+    assert_eq!(
+        parse_unified_diff(diff),
+        vec![
+            FileChange { path: "src/lib.rs".into(), old_lines: vec![4, 5, 20], new_lines: vec![4, 5, 21] },
+            FileChange { path: "gone.py".into(), old_lines: vec![1, 2], new_lines: vec![1, 2] },
+            FileChange { path: "notes.txt".into(), old_lines: vec![1, 2], new_lines: vec![1] },
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 2. Changed symbols, their dependents and linked tests
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diff_impact() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("calc.go"),
+        "package calc\n\nfunc Add(a, b int) int {\n\treturn a + b\n}\n\nfunc Sum(xs []int) int {\n\tt := 0\n\tfor _, x := range xs {\n\t\tt = Add(t, x)\n\t}\n\treturn t\n}\n\nfunc Other() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("calc_test.go"),
+        "package calc\n\nimport \"testing\"\n\nfunc TestSum(t *testing.T) {\n\tSum(nil)\n}\n",
+    )
+    .unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let repo_id = db.list_repositories().unwrap()[0].id;
+
+    let diff = "\
+--- a/calc.go
++++ b/calc.go
+@@ -4 +4 @@ func Add(a, b int) int {
+-\treturn a + b
++\treturn b + a
+--- a/README.md
++++ b/README.md
+@@ -1 +1 @@
+-x
++y
+";
+    let impact = diff_impact(&db, repo_id, diff, DiffSide::Old, 2).unwrap();
+    let changed: Vec<&str> = impact.changed.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(changed, ["Add"]);
+    let impacted: Vec<(&str, usize)> = impact.impacted.iter().map(|n| (n.name.as_str(), n.distance)).collect();
+    assert_eq!(impacted, [("Sum", 1), ("TestSum", 2)]);
+    let tests: Vec<&str> = impact.tests.iter().map(|t| t.test.as_str()).collect();
+    assert_eq!(tests, ["TestSum"]);
+    assert_eq!(impact.unindexed_files, ["README.md"]);
+}
+
+// ---------------------------------------------------------------------------
+// 3. A patch not yet applied is matched on its old side
+// ---------------------------------------------------------------------------
+#[test]
+fn test_diff_impact_old_side() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("calc.go"),
+        "package calc\n\nfunc Add(a, b int) int {\n\treturn a + b\n}\n\nfunc Sub(a, b int) int {\n\treturn a - b\n}\n",
+    )
+    .unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let repo_id = db.list_repositories().unwrap()[0].id;
+
+    // Three lines land above Add, so Sub's body is line 8 before and 11 after.
+    let diff = "\
+--- a/calc.go
++++ b/calc.go
+@@ -2,0 +3,3 @@
++// Arithmetic helpers.
++
++
+@@ -8 +11 @@ func Sub(a, b int) int {
+-\treturn a - b
++\treturn -(b - a)
+";
+    let changed = |side| -> Vec<String> {
+        diff_impact(&db, repo_id, diff, side, 1).unwrap().changed.into_iter().map(|s| s.name).collect()
+    };
+    assert_eq!(changed(DiffSide::Old), ["Sub"]);
+    assert_ne!(changed(DiffSide::New), ["Sub"], "new-side numbers miss against the old index");
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::Path;
+
+use focal_core::db::{Database, SearchFilter};
+use focal_core::deny::DenyList;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::index_import::{import, load, parse_ctags, parse_lsif, DumpFormat, ImportedIndex, IMPORTED_HASH};
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+const ML_SOURCE: &str = "let save db =\n  Db.write db\n\nlet create db =\n  save db\n";
+
+const LSIF: &str = r#"{"id":1,"type":"vertex","label":"metaData","version":"0.4.3","projectRoot":"file:///proj"}
+{"id":2,"type":"vertex","label":"document","uri":"file:///proj/src/app.ml","languageId":"ocaml"}
+{"id":3,"type":"vertex","label":"resultSet"}
+{"id":4,"type":"vertex","label":"range","start":{"line":0,"character":4},"end":{"line":0,"character":8},"tag":{"type":"definition","text":"save","kind":12,"fullRange":{"start":{"line":0,"character":0},"end":{"line":1,"character":13}}}}
+{"id":5,"type":"edge","label":"next","outV":4,"inV":3}
+{"id":6,"type":"vertex","label":"resultSet"}
+{"id":7,"type":"vertex","label":"range","start":{"line":3,"character":4},"end":{"line":3,"character":10},"tag":{"type":"definition","text":"create","kind":12,"fullRange":{"start":{"line":3,"character":0},"end":{"line":4,"character":9}}}}
+{"id":8,"type":"edge","label":"next","outV":7,"inV":6}
+{"id":9,"type":"vertex","label":"range","start":{"line":4,"character":2},"end":{"line":4,"character":6}}
+{"id":10,"type":"edge","label":"next","outV":9,"inV":3}
+{"id":11,"type":"edge","label":"contains","outV":2,"inVs":[4,7,9]}
+{"id":12,"type":"vertex","label":"document","uri":"file:///proj/src/cli.ml","languageId":"ocaml"}
+{"id":13,"type":"vertex","label":"range","start":{"line":0,"character":4},"end":{"line":0,"character":8},"tag":{"type":"definition","text":"main","kind":12,"fullRange":{"start":{"line":0,"character":0},"end":{"line":2,"character":0}}}}
+{"id":14,"type":"vertex","label":"range","start":{"line":1,"character":2},"end":{"line":1,"character":8}}
+{"id":15,"type":"edge","label":"contains","outV":12,"inVs":[13,14]}
+{"id":16,"type":"vertex","label":"referenceResult"}
+{"id":17,"type":"edge","label":"textDocument/references","outV":6,"inV":16}
+{"id":18,"type":"edge","label":"item","outV":16,"inVs":[14],"document":12,"property":"references"}
+"#;
+
+fn names(index: &ImportedIndex) -> Vec<(&str, &str, &str, i64, i64)> {
+    index
+        .symbols
+        .iter()
+        .map(|s| (s.path.as_str(), s.name.as_str(), s.kind.as_str(), s.start_line, s.end_line))
+        .collect()
+}
+
+fn edge_names(index: &ImportedIndex) -> Vec<(&str, &str, &str)> {
+    index
+        .edges
+        .iter()
+        .map(|e| (index.symbols[e.source].name.as_str(), index.symbols[e.target].name.as_str(), e.kind.as_str()))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Classic tags files: line fields, search patterns, scopes and kinds
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_classic_tags() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/app.ml"), ML_SOURCE).unwrap();
+    let tags = "!_TAG_FILE_FORMAT\t2\t/extended format/\n\
+                save\tsrc/app.ml\t/^let save db =$/;\"\tf\n\
+                create\tsrc/app.ml\t/^let create db =$/;\"\tkind:function\tline:4\tend:5\tsignature:(db)\n\
+                write\tsrc/db.ml\t12;\"\tf\tmodule:Db\n\
+                tmp\tsrc/app.ml\t/^  let tmp$/;\"\tlocal\n\
+                gone\tsrc/app.ml\t/^let gone$/;\"\tf\n";
+
+    let index = parse_ctags(tags, dir.path()).unwrap();
+    assert_eq!(
+        names(&index),
+        [
+            ("src/app.ml", "save", "function", 1, 1),
+            ("src/app.ml", "create", "function", 4, 5),
+            ("src/db.ml", "write", "function", 12, 12),
+        ],
+        "pattern located, locals and unmatched patterns dropped"
+    );
+    assert_eq!(index.symbols[1].signature, "create(db)");
+    assert_eq!(index.symbols[2].qualified_name, "Db.write");
+    assert_eq!(index.symbols[0].language, "ml");
+    assert!(index.edges.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Universal Ctags JSON output
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_ctags_json() {
+    let json = r#"{"_type": "ptag", "name": "JSON_OUTPUT_VERSION", "path": "0.0"}
+{"_type": "tag", "name": "Account", "path": "/proj/lib/account.ex", "pattern": "/^defmodule Account do$/", "language": "Elixir", "line": 1, "kind": "module", "end": 20}
+{"_type": "tag", "name": "open", "path": "/proj/lib/account.ex", "language": "Elixir", "line": 3, "kind": "function", "scope": "Account", "scopeKind": "module", "signature": "(owner)", "end": 5}
+"#;
+    let index = parse_ctags(json, Path::new("/proj")).unwrap();
+    assert_eq!(
+        names(&index),
+        [("lib/account.ex", "Account", "module", 1, 20), ("lib/account.ex", "open", "function", 3, 5)]
+    );
+    assert_eq!(index.symbols[1].qualified_name, "Account.open");
+    assert_eq!(index.symbols[1].language, "elixir");
+}
+
+// ---------------------------------------------------------------------------
+// 3. LSIF: tagged definitions, references through `next` and `item` edges
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_lsif() {
+    assert_eq!(DumpFormat::detect(LSIF), DumpFormat::Lsif);
+    assert_eq!(DumpFormat::detect("save\tsrc/app.ml\t1;\"\tf\n"), DumpFormat::Ctags);
+
+    let index = parse_lsif(LSIF, Path::new("/elsewhere")).unwrap();
+    assert_eq!(
+        names(&index),
+        [
+            ("src/app.ml", "save", "function", 1, 2),
+            ("src/app.ml", "create", "function", 4, 5),
+            ("src/cli.ml", "main", "function", 1, 3),
+        ]
+    );
+    assert_eq!(index.symbols[0].language, "ocaml");
+    assert_eq!(edge_names(&index), [("create", "save", "calls"), ("main", "create", "calls")]);
+}
+
+// ---------------------------------------------------------------------------
+// 4. Imported symbols are searchable, skeletoned and keep their memories
+// ---------------------------------------------------------------------------
+#[test]
+fn test_import_and_reimport() {
+    let db = Database::open_in_memory().unwrap();
+    let dir = TempDir::new().unwrap();
+    let lsif = LSIF.replace("file:///proj", &format!("file://{}", dir.path().canonicalize().unwrap().display()));
+    let dump = dir.path().join("dump.lsif");
+    fs::write(&dump, &lsif).unwrap();
+
+    let index = load(&dump, None, dir.path()).unwrap();
+    let stats = import(&db, dir.path(), &index, &DenyList::default()).unwrap();
+    assert_eq!((stats.files, stats.symbols, stats.edges), (2, 3, 2));
+
+    let found = db.query_symbols_full("save", "", "", "", "").unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].source, "imported");
+    assert_eq!(found[0].file_path, "src/app.ml");
+    let skeleton = db.get_skeleton_by_path("src/app.ml", None, "standard").unwrap();
+    assert_eq!(skeleton.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["save", "create"]);
+    assert!(db.search_code("create", &SearchFilter::default(), 10).unwrap().iter().any(|s| s.name == "create"));
+
+    let save = db.find_symbol_by_name_any("save").unwrap().unwrap();
+    let memory = db.save_memory("save writes synchronously", "decision", &[save.id]).unwrap();
+
+    // Re-import without cli.ml: app.ml is replaced, cli.ml dropped.
+    let mut index = parse_lsif(&lsif, dir.path()).unwrap();
+    index.symbols.truncate(2);
+    index.edges.retain(|e| e.source < 2 && e.target < 2);
+    let stats = import(&db, dir.path(), &index, &DenyList::default()).unwrap();
+    assert_eq!((stats.files, stats.removed_files), (1, 1));
+    assert!(db.find_symbol_by_name_any("main").unwrap().is_none());
+
+    let linked = db.list_memories("", false, false, "save").unwrap();
+    assert_eq!(linked.iter().map(|m| m.id).collect::<Vec<_>>(), [memory], "relinked by name");
+    assert!(!linked[0].stale);
+}
+
+// ---------------------------------------------------------------------------
+// 5. Natively indexed files win; re-indexing leaves imported files alone
+// ---------------------------------------------------------------------------
+#[test]
+fn test_native_files_win() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.go"), "package main\n\nfunc run() {}\n").unwrap();
+    fs::write(dir.path().join("app.ml"), ML_SOURCE).unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let tags = "run\tmain.go\t3;\"\tf\nsave\tapp.ml\t1;\"\tf\n";
+    let stats = import(&db, dir.path(), &parse_ctags(tags, dir.path()).unwrap(), &DenyList::default()).unwrap();
+    assert_eq!((stats.files, stats.skipped_files), (1, 1));
+    let run = db.query_symbols_full("run", "", "", "", "").unwrap();
+    assert_eq!(run.len(), 1);
+    assert_eq!(run[0].source, "local");
+
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+    Indexer::new(&db, &registry).reindex_changed(dir.path()).unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let file = db.get_file_by_path(repo_id, "app.ml").unwrap().unwrap();
+    assert_eq!(file.hash, IMPORTED_HASH);
+    assert_eq!(db.query_symbols_full("save", "", "", "", "").unwrap().len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// 6. Denied paths are skipped; a new repository never takes a used name
+// ---------------------------------------------------------------------------
+#[test]
+fn test_import_deny_paths_and_repo_name() {
+    let db = Database::open_in_memory().unwrap();
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("app");
+    fs::create_dir(&root).unwrap();
+    db.upsert_repository("app", "/elsewhere/app").unwrap();
+
+    let tags = "save\tsrc/app.ml\t1;\"\tf\ntoken\tsecrets/vault.ml\t1;\"\tf\n";
+    let deny = DenyList::new(["**/secrets/**"]);
+    let stats = import(&db, &root, &parse_ctags(tags, &root).unwrap(), &deny).unwrap();
+    assert_eq!((stats.files, stats.denied_files), (1, 1));
+    assert!(db.find_symbol_by_name_any("token").unwrap().is_none());
+
+    let canonical = root.canonicalize().unwrap();
+    let repo = db.get_repository_by_path(&canonical.to_string_lossy()).unwrap().unwrap();
+    assert_eq!(repo.name, "app-2");
+    assert_eq!(db.get_repo_id_by_name("app").unwrap(), db.get_repository_by_path("/elsewhere/app").unwrap().map(|r| r.id));
+
+    // Indexing the root later keeps the suffixed name.
+    Indexer::new(&db, &GrammarRegistry::new()).index_directory(&root).unwrap();
+    assert_eq!(db.get_repository_by_path(&canonical.to_string_lossy()).unwrap().unwrap().name, "app-2");
+}
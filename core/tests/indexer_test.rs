@@ -1,7 +1,8 @@
 use std::fs;
 
 use tempfile::TempDir;
-use focal_core::db::Database;
+use focal_core::builtins::BuiltinFilter;
+use focal_core::db::{Database, SearchFilter};
 use focal_core::grammar::GrammarRegistry;
 use focal_core::indexer::Indexer;
 
@@ -14,6 +15,11 @@ fn setup() -> (Database, GrammarRegistry) {
 
 /// Write a Go file into `dir` at the given relative path.
 fn write_go_file(dir: &TempDir, rel_path: &str, content: &str) {
+    write_file(dir, rel_path, content);
+}
+
+/// Write a source file of any language into `dir` at the given relative path.
+fn write_file(dir: &TempDir, rel_path: &str, content: &str) {
     let full = dir.path().join(rel_path);
     if let Some(parent) = full.parent() {
         fs::create_dir_all(parent).unwrap();
@@ -160,3 +166,644 @@ fn test_exclude_patterns() {
     let hidden = db.find_symbol_by_name(repo.id, "Hidden").unwrap();
     assert!(hidden.is_none(), "Hidden should not be indexed (it's in node_modules)");
 }
+
+/// Run a git command in `dir`, panicking on failure.
+fn git(dir: &TempDir, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(status.status.success(), "git {args:?} failed: {status:?}");
+}
+
+// ---------------------------------------------------------------------------
+// 5. Index a git revision — local dirt excluded, revision recorded
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_revision_ignores_working_tree() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+
+    write_go_file(&dir, "main.go", TWO_FUNC_GO);
+    write_go_file(&dir, "big.go", &format!("package main\n\nfunc Big() {{}}\n{}", "// pad\n".repeat(1000)));
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "initial"]);
+    git(&dir, &["tag", "v1"]);
+
+    // Local dirt: edited tracked files and an untracked file
+    write_go_file(&dir, "main.go", "package main\n\nfunc Gamma() {}\n");
+    write_go_file(&dir, "big.go", "package main\n\nfunc Small() {}\n");
+    write_go_file(&dir, "scratch.go", "package main\n\nfunc Scratch() {}\n");
+
+    // A prior working-tree index picks them up...
+    let indexer = Indexer::new(&db, &registry).with_max_partial_file_size(4096);
+    indexer.index_directory(dir.path()).unwrap();
+
+    // ...indexing the tag replaces them with the committed content.
+    let stats = indexer.index_revision(dir.path(), "v1").unwrap();
+    assert!(stats.errors.is_empty(), "unexpected errors: {:?}", stats.errors);
+    assert_eq!(stats.files_indexed, 1);
+
+    let root = dir.path().canonicalize().unwrap();
+    let repo = db
+        .get_repository_by_path(&root.to_string_lossy())
+        .unwrap()
+        .unwrap();
+    let files: Vec<String> = db
+        .get_files_for_repo(repo.id)
+        .unwrap()
+        .into_iter()
+        .map(|f| f.path)
+        .collect();
+    assert_eq!(files, vec!["main.go"]);
+    assert!(db.find_symbol_by_name(repo.id, "Alpha").unwrap().is_some());
+    assert!(db.find_symbol_by_name(repo.id, "Gamma").unwrap().is_none());
+    assert!(db.find_symbol_by_name(repo.id, "Scratch").unwrap().is_none());
+    assert!(db.find_symbol_by_name(repo.id, "Small").unwrap().is_none(), "over-size at the revision");
+
+    let revision = repo.revision.expect("revision recorded");
+    assert_eq!(revision.len(), 40, "expected a full commit SHA, got {revision}");
+
+    // Re-indexing the working tree clears the revision.
+    indexer.index_directory(dir.path()).unwrap();
+    let repo = db
+        .get_repository_by_path(&root.to_string_lossy())
+        .unwrap()
+        .unwrap();
+    assert!(repo.revision.is_none());
+
+    // Unknown revisions are an error, not an empty index.
+    assert!(indexer.index_revision(dir.path(), "no-such-branch").is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 6. Branch namespaces — switching parks and restores only the delta
+// ---------------------------------------------------------------------------
+#[test]
+fn test_branch_switch_parks_and_restores() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+
+    write_go_file(&dir, "main.go", TWO_FUNC_GO);
+    write_go_file(&dir, "util.go", "package main\n\nfunc Helper() {}\n");
+    git(&dir, &["init", "-q", "-b", "main"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "initial"]);
+
+    git(&dir, &["checkout", "-q", "-b", "feature"]);
+    write_go_file(&dir, "main.go", "package main\n\nfunc Gamma() {\n    Helper()\n}\n");
+    write_go_file(&dir, "extra.go", "package main\n\nfunc Extra() {}\n");
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "feature work"]);
+    git(&dir, &["checkout", "-q", "main"]);
+
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let repo = db
+        .get_repository_by_path(&root.to_string_lossy())
+        .unwrap()
+        .unwrap();
+    assert_eq!(repo.branch.as_deref(), Some("main"));
+
+    let alpha = db.find_symbol_by_name(repo.id, "Alpha").unwrap().unwrap();
+    let memory_id = db.save_memory("Alpha must stay idempotent", "invariant", &[alpha.id]).unwrap();
+
+    // Same branch: nothing to do
+    assert!(indexer.sync_branch(dir.path()).unwrap().is_none());
+
+    // main -> feature: main.go parked, main.go + extra.go parsed, util.go untouched
+    git(&dir, &["checkout", "-q", "feature"]);
+    let switch = indexer.sync_branch(dir.path()).unwrap().unwrap();
+    assert_eq!((switch.from.as_str(), switch.to.as_str()), ("main", "feature"));
+    assert_eq!((switch.parked, switch.restored, switch.reindexed), (1, 0, 2));
+    assert!(db.find_symbol_by_name(repo.id, "Alpha").unwrap().is_none());
+    let gamma = db.find_symbol_by_name(repo.id, "Gamma").unwrap().unwrap();
+    let deps = db.get_dependencies(gamma.id).unwrap();
+    assert!(deps.iter().any(|(_, s)| s.name == "Helper"), "Gamma -> Helper edge");
+
+    // feature -> main: main.go restored from its snapshot, extra.go parked
+    git(&dir, &["checkout", "-q", "main"]);
+    let switch = indexer.sync_branch(dir.path()).unwrap().unwrap();
+    assert_eq!((switch.parked, switch.restored, switch.reindexed), (2, 1, 0));
+    assert!(db.find_symbol_by_name(repo.id, "Extra").unwrap().is_none());
+    let alpha = db.find_symbol_by_name(repo.id, "Alpha").unwrap().unwrap();
+    let memories = db.get_memories_for_symbol(alpha.id, false).unwrap();
+    assert!(
+        memories.iter().any(|m| m.id == memory_id),
+        "memory should be relinked to the restored Alpha"
+    );
+
+    // main -> feature again: both feature files come back without parsing
+    git(&dir, &["checkout", "-q", "feature"]);
+    let switch = indexer.sync_branch(dir.path()).unwrap().unwrap();
+    assert_eq!((switch.parked, switch.restored, switch.reindexed), (1, 2, 0));
+    assert!(db.find_symbol_by_name(repo.id, "Extra").unwrap().is_some());
+    let parked = db.count_parked_files(repo.id).unwrap();
+    assert_eq!(parked, vec![("main".to_string(), 1)], "the live branch keeps no snapshots");
+
+    // Snapshots of a branch deleted from git are pruned on the next switch
+    git(&dir, &["branch", "-q", "-D", "main"]);
+    git(&dir, &["checkout", "-q", "-b", "topic"]);
+    indexer.sync_branch(dir.path()).unwrap().unwrap();
+    assert!(db.count_parked_files(repo.id).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 7. Attributes and decorators — stored and filterable
+// ---------------------------------------------------------------------------
+#[test]
+fn test_attribute_filter() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_file(
+        &dir,
+        "model.rs",
+        "#[derive(Debug, Serialize)]\npub struct User;\n\n#[derive(Debug)]\npub struct Session;\n",
+    );
+    write_file(
+        &dir,
+        "app.py",
+        "@app.route(\"/users\")\ndef list_users():\n    pass\n\ndef helper():\n    pass\n",
+    );
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let serializable = db.query_symbols_full("", "struct", "", "", "Serialize").unwrap();
+    assert_eq!(serializable.len(), 1);
+    assert_eq!(serializable[0].name, "User");
+    assert_eq!(serializable[0].attributes, vec!["#[derive(Debug, Serialize)]"]);
+    // `%` and `_` in the filter are literal, not wildcards.
+    assert!(db.query_symbols_full("", "struct", "", "", "Serial_ze").unwrap().is_empty());
+    assert!(db.query_symbols_full("", "struct", "", "", "%").unwrap().is_empty());
+
+    let routes = db.search_code("users", &SearchFilter { attribute: "@app.route", ..SearchFilter::default() }, 10).unwrap();
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes[0].name, "list_users");
+    assert_eq!(routes[0].attributes, "@app.route(\"/users\")");
+}
+
+// ---------------------------------------------------------------------------
+// 8. HTTP routes — stored per file, handlers resolved, filterable
+// ---------------------------------------------------------------------------
+#[test]
+fn test_list_routes() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(
+        &dir,
+        "server/routes.go",
+        "package server\n\nfunc Routes(r *gin.Engine) {\n\tr.GET(\"/api/users\", ListUsers)\n\tr.Any(\"/health\", health)\n}\n",
+    );
+    write_go_file(
+        &dir,
+        "server/users.go",
+        "package server\n\nfunc ListUsers(c *gin.Context) {}\n",
+    );
+    write_file(&dir, "app.py", "@app.post(\"/api/orders\")\ndef create_order():\n    pass\n");
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+
+    let all = db.list_routes(None, "", "").unwrap();
+    let summary: Vec<_> = all.iter().map(|r| (r.method.as_str(), r.path.as_str())).collect();
+    assert_eq!(summary, vec![("POST", "/api/orders"), ("GET", "/api/users"), ("ANY", "/health")]);
+
+    let users = &all[1];
+    assert_eq!(users.handler, "ListUsers");
+    assert_eq!(users.file_path, "server/routes.go");
+    assert_eq!(users.line, 4);
+    assert_eq!(users.handler_file.as_deref(), Some("server/users.go"));
+    assert_eq!(users.handler_line, Some(3));
+    // `health` is not defined anywhere in the index.
+    assert_eq!(all[2].handler_file, None);
+
+    // ANY registrations match every method filter.
+    let gets = db.list_routes(None, "get", "").unwrap();
+    assert_eq!(gets.iter().map(|r| r.path.as_str()).collect::<Vec<_>>(), vec!["/api/users", "/health"]);
+    assert_eq!(db.list_routes(None, "", "/api").unwrap().len(), 2);
+
+    // Re-indexing a file replaces its routes.
+    write_file(&dir, "app.py", "def create_order():\n    pass\n");
+    indexer.index_file(&dir.path().join("app.py"), dir.path()).unwrap();
+    assert_eq!(db.list_routes(None, "", "/api/orders").unwrap().len(), 0);
+}
+
+// ---------------------------------------------------------------------------
+// 9. Error sites — stored with enclosing symbol, filterable by path and kind
+// ---------------------------------------------------------------------------
+#[test]
+fn test_list_error_sites() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_file(
+        &dir,
+        "src/payments/charge.rs",
+        "pub fn charge(id: &str) -> u64 {\n    id.parse().unwrap()\n}\n",
+    );
+    write_file(&dir, "src/paymentsx/other.rs", "fn f() { None::<u8>.expect(\"x\"); }\n");
+    write_file(&dir, "jobs.py", "def sync():\n    try:\n        run()\n    except:\n        pass\n");
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let all = db.list_error_sites(None, "", "").unwrap();
+    let found: Vec<_> = all
+        .iter()
+        .map(|s| (s.file_path.as_str(), s.kind.as_str(), s.symbol.as_deref()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("jobs.py", "bare_except", Some("sync")),
+            ("src/payments/charge.rs", "unwrap", Some("charge")),
+            ("src/paymentsx/other.rs", "expect", Some("f")),
+        ]
+    );
+
+    // A directory prefix does not match sibling directories sharing its name.
+    let payments = db.list_error_sites(None, "src/payments", "").unwrap();
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].line, 2);
+    assert_eq!(db.list_error_sites(None, "", "bare_except").unwrap().len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// 10. Size statistics — line counts in overview and size report
+// ---------------------------------------------------------------------------
+#[test]
+fn test_size_report() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(&dir, "main.go", TWO_FUNC_GO);
+    write_go_file(
+        &dir,
+        "big.go",
+        "package main\n\nfunc Long() {\n\ta := 1\n\tb := 2\n\tprintln(a, b)\n}\n\ntype T struct{}",
+    );
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let main_lines = TWO_FUNC_GO.lines().count() as i64;
+    let overview = db.get_repo_overview("").unwrap();
+    assert_eq!(overview[0].line_count, main_lines + 9);
+    assert_eq!(overview[0].max_symbol_lines, 5);
+
+    let repo = &db.list_repositories().unwrap()[0];
+    let report = db.get_size_report(repo, 2).unwrap();
+    assert_eq!(report.file_count, 2);
+    assert_eq!(report.symbol_count, 4);
+    assert_eq!(report.largest_symbols.len(), 2);
+    assert_eq!(report.largest_symbols[0].name, "Long");
+    assert_eq!(report.largest_symbols[0].lines, 5);
+
+    let function = report.kinds.iter().find(|k| k.kind == "function").unwrap();
+    assert_eq!(function.count, 3);
+    // Both files are 9 lines (big.go's last line has no newline); ties sort by path.
+    let largest = &report.largest_files[0];
+    assert_eq!((largest.path.as_str(), largest.line_count, largest.symbol_count), ("big.go", 9, 2));
+}
+
+// ---------------------------------------------------------------------------
+// 11. Index errors — recorded per file, cleared once the file parses cleanly
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_errors_recorded_and_cleared() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(&dir, "main.go", TWO_FUNC_GO);
+    write_go_file(&dir, "broken.go", "package main\n\nfunc Ok() {}\n\nfunc Broken( {\n");
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let errors = db.list_index_errors(None, "").unwrap();
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert_eq!((errors[0].path.as_str(), errors[0].kind.as_str()), ("broken.go", "syntax"));
+    assert_eq!(errors[0].line, Some(5));
+    assert_eq!(db.get_health().unwrap().index_error_count, 1);
+    assert!(db.list_index_errors(None, "panic").unwrap().is_empty());
+
+    write_go_file(&dir, "broken.go", "package main\n\nfunc Ok() {}\n\nfunc Broken() {}\n");
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert!(db.list_index_errors(None, "").unwrap().is_empty());
+    assert_eq!(db.get_health().unwrap().index_error_count, 0);
+}
+
+// ---------------------------------------------------------------------------
+// 12. Oversized files — names and line ranges kept, bodies dropped
+// ---------------------------------------------------------------------------
+#[test]
+fn test_oversized_file_indexed_without_bodies() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(&dir, "main.go", TWO_FUNC_GO);
+    let mut generated = String::from("package main\n\nfunc GeneratedTable() int {\n\tmarkerToken := 0\n");
+    generated.push_str(&"\tmarkerToken++\n".repeat(200));
+    generated.push_str("\treturn markerToken\n}\n");
+    write_go_file(&dir, "generated.go", &generated);
+    write_go_file(&dir, "huge.go", &format!("package main\n\nfunc Huge() {{}}\n{}", "// pad\n".repeat(2_000)));
+
+    let stats = Indexer::new(&db, &registry)
+        .with_max_file_size(1_000)
+        .with_max_partial_file_size(10_000)
+        .index_directory(dir.path())
+        .unwrap();
+    assert_eq!(stats.files_indexed, 2);
+    assert_eq!(stats.files_partial, 1);
+    assert_eq!(stats.files_skipped, 1);
+
+    let table = db.query_symbols_full("GeneratedTable", "", "", "", "").unwrap();
+    assert_eq!(table.len(), 1);
+    assert_eq!(table[0].signature, "func GeneratedTable() int");
+    assert_eq!((table[0].start_line, table[0].end_line), (3, 206));
+    assert!(table[0].body.is_empty());
+    assert!(db.symbol_file_flags(&[table[0].id]).unwrap().0.contains(&table[0].id));
+
+    // Names stay searchable; body text does not.
+    assert_eq!(db.search_code("GeneratedTable", &SearchFilter::default(), 10).unwrap().len(), 1);
+    assert!(db.search_code("markerToken", &SearchFilter::default(), 10).unwrap().is_empty());
+    assert!(db.query_symbols_full("Huge", "", "", "", "").unwrap().is_empty());
+
+    // A file that grows past the limit is dropped, not left with stale symbols.
+    let indexer = Indexer::new(&db, &registry).with_max_file_size(1_000).with_max_partial_file_size(10_000);
+    write_go_file(&dir, "main.go", &format!("{TWO_FUNC_GO}{}", "// pad\n".repeat(2_000)));
+    assert!(indexer.index_file(&dir.path().join("main.go"), dir.path()).unwrap());
+    assert!(db.query_symbols_full("Alpha", "", "", "", "").unwrap().is_empty());
+    assert!(!indexer.index_file(&dir.path().join("main.go"), dir.path()).unwrap());
+}
+
+// ---------------------------------------------------------------------------
+// 13. Builtin calls — no edge or external stub unless a repository symbol
+//     has the name
+// ---------------------------------------------------------------------------
+#[test]
+fn test_builtin_calls_do_not_become_edges() {
+    let callees = |filter: BuiltinFilter| -> Vec<String> {
+        let (db, registry) = setup();
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"app\"\n\n[dependencies]\nserde_json = \"1\"\n")
+            .unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src/lib.rs"),
+            "use serde_json::to_string;\n\npub fn len(s: &str) -> usize { 0 }\n\n\
+             pub fn size() -> usize {\n    println!(\"size\");\n    to_string(&1);\n    count();\n    len(\"x\")\n}\n\n\
+             pub fn count() {}\n",
+        )
+        .unwrap();
+        Indexer::new(&db, &registry).with_builtin_filter(filter).index_directory(dir.path()).unwrap();
+        let sym = db.find_symbol_by_name_any("size").unwrap().unwrap();
+        let mut names: Vec<String> =
+            db.get_dependencies(sym.id).unwrap().into_iter().map(|(_, s)| s.qualified_name).collect();
+        names.sort();
+        names
+    };
+
+    // A user-defined `len` keeps its callers; `to_string` gets no stub.
+    assert_eq!(callees(BuiltinFilter::default()), ["count", "len"]);
+    assert_eq!(callees(BuiltinFilter::disabled()), ["count", "len", "serde_json::to_string"]);
+    let extra = BuiltinFilter::default().with_extra("rs", ["count".to_string()]);
+    assert_eq!(callees(extra), ["count", "len"], "extra names don't hide repository symbols either");
+}
+
+// ---------------------------------------------------------------------------
+// 14. Line lookup — enclosing symbols outermost first, innermost last
+// ---------------------------------------------------------------------------
+#[test]
+fn test_symbols_at_line() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("shop.py"),
+        "import os\n\nclass Cart:\n    def add(self, item):\n        self.items.append(item)\n\n    def total(self):\n        return 0\n",
+    )
+    .unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let file = db.resolve_file_path("shop.py", None).unwrap().unwrap();
+
+    let names = |line| -> Vec<String> {
+        db.symbols_at_line(file.file_id, line).unwrap().into_iter().map(|s| s.name).collect()
+    };
+    assert_eq!(names(5), ["Cart", "add"]);
+    assert_eq!(names(8), ["Cart", "total"]);
+    assert_eq!(names(3), ["Cart"]);
+    assert!(names(1).is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 15. Overview breakdown — symbols per kind and language, edges, index age
+// ---------------------------------------------------------------------------
+#[test]
+fn test_overview_breakdown() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(&dir, "main.go", "package main\n\nfunc Alpha() {\n\tBeta()\n}\n\nfunc Beta() {}\n\ntype T struct{}\n");
+    fs::write(dir.path().join("cart.py"), "class Cart:\n    def add(self):\n        pass\n").unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let overview = &db.get_repo_overview("").unwrap()[0];
+    let go = overview.languages.iter().find(|l| l.language == "go").unwrap();
+    assert_eq!((go.count, go.symbols), (1, 3));
+    let python = overview.languages.iter().find(|l| l.language == "py").unwrap();
+    assert_eq!((python.count, python.symbols), (1, 2));
+
+    assert_eq!(overview.symbol_kinds[0].kind, "function");
+    assert_eq!(overview.symbol_kinds[0].count, 2);
+    assert_eq!(overview.symbol_kinds.iter().map(|k| k.count).sum::<i64>(), overview.symbol_count);
+    let calls = overview.edge_kinds.iter().find(|k| k.kind == "calls").unwrap();
+    assert_eq!(calls.count, 1);
+    assert!(overview.indexed_at.is_some());
+    assert!((0..60).contains(&overview.index_age_secs.unwrap()));
+}
+
+// ---------------------------------------------------------------------------
+// 16. Go: same-named methods stay apart; interfaces get implements edges
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_methods_and_implements() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(
+        &dir,
+        "store.go",
+        "package store\n\n\
+         type Getter interface {\n\tGet(key string) string\n}\n\n\
+         type Store interface {\n\tGetter\n\tPut(key, value string)\n}\n\n\
+         type Memory struct{}\n\n\
+         func (m *Memory) Get(key string) string { return \"\" }\n\n\
+         func (m *Memory) Put(key, value string) { m.Get(key) }\n\n\
+         type Cache struct{}\n\n\
+         func (c Cache) Get(key string) string { return \"\" }\n",
+    );
+    write_go_file(
+        &dir,
+        "legacy/memory.go",
+        "package legacy\n\ntype Memory struct{}\n\nfunc (m Memory) Flush() {}\n",
+    );
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+
+    let memory_get = db.find_symbol_by_name(repo_id, "Memory.Get").unwrap().unwrap();
+    let cache_get = db.find_symbol_by_name(repo_id, "Cache.Get").unwrap().unwrap();
+    assert_ne!(memory_get.id, cache_get.id);
+    let put = db.find_symbol_by_name(repo_id, "Memory.Put").unwrap().unwrap();
+    let callees: Vec<String> = db.get_dependencies(put.id).unwrap().into_iter().map(|(_, s)| s.name).collect();
+    assert_eq!(callees, ["Memory.Get"], "receiver calls resolve to the receiver's method");
+
+    let ids = db.get_all_symbol_names_for_repo(repo_id).unwrap();
+    let implements = |qualified: &str| -> Vec<String> {
+        let mut names: Vec<String> = db
+            .get_dependencies(ids[qualified])
+            .unwrap()
+            .into_iter()
+            .filter(|(edge, _)| edge.kind == "implements")
+            .map(|(_, s)| s.name)
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(implements("store.Memory"), ["Getter", "Store"]);
+    assert!(implements("legacy.Memory").is_empty(), "legacy.Memory's methods are its own");
+    assert_eq!(implements("store.Cache"), ["Getter"], "Cache lacks Put");
+
+    // A batch re-index re-infers once it is done.
+    write_go_file(&dir, "cache_put.go", "package store\n\nfunc (c Cache) Put(key, value string) {}\n");
+    indexer.reindex_changed(dir.path()).unwrap();
+    assert_eq!(implements("store.Cache"), ["Getter", "Store"]);
+}
+
+// ---------------------------------------------------------------------------
+// 17. Calls inside closures and impl methods become edges of the named symbol
+// ---------------------------------------------------------------------------
+#[test]
+fn test_closure_calls_resolve_to_enclosing_symbol() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_file(
+        &dir,
+        "lib.rs",
+        "fn parse_port(h: &str) -> u16 { 0 }\n\n\
+         struct Config { hosts: Vec<String> }\n\n\
+         impl Config {\n    fn ports(&self) -> Vec<u16> { self.hosts.iter().map(|h| parse_port(h)).collect() }\n}\n",
+    );
+    write_file(
+        &dir,
+        "api.ts",
+        "function loadUser(id: string) {}\n\n\
+         export const fetchUser = async (id: string) => { await retry(() => loadUser(id)); };\n",
+    );
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let callees = |name: &str| -> Vec<String> {
+        let sym = db.find_symbol_by_name_any(name).unwrap().unwrap();
+        db.get_dependencies(sym.id).unwrap().into_iter().map(|(_, s)| s.name).collect()
+    };
+    assert_eq!(callees("Config::ports"), ["parse_port"]);
+    assert_eq!(callees("fetchUser"), ["loadUser"]);
+}
+
+// ---------------------------------------------------------------------------
+// 18. Python package paths and TS namespaces qualify same-named symbols
+// ---------------------------------------------------------------------------
+#[test]
+fn test_module_qualified_names() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_file(
+        &dir,
+        "src/billing/utils.py",
+        "def parse(x):\n    pass\n\nclass Invoice:\n    def total(self):\n        return tax()\n\n\
+         class Refund:\n    def total(self):\n        return fee()\n\ndef tax():\n    pass\n\ndef fee():\n    pass\n",
+    );
+    write_file(&dir, "src/shipping/utils.py", "def parse(x):\n    pass\n");
+    write_file(&dir, "src/shipping/__init__.py", "def ship():\n    pass\n");
+    write_file(
+        &dir,
+        "checks.ts",
+        "namespace Orders { export function parse() { orderId(); } }\n\
+         namespace Users { export function parse() { userId(); } }\n\
+         function orderId() {}\nfunction userId() {}\n",
+    );
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+
+    let names = db.get_all_symbol_names_for_repo(repo_id).unwrap();
+    for qualified in [
+        "billing.utils.parse",
+        "billing.utils.Invoice.total",
+        "shipping.utils.parse",
+        "shipping.ship",
+        "checks.Orders.parse",
+        "checks.Users.parse",
+    ] {
+        assert!(names.contains_key(qualified), "missing {qualified}");
+    }
+    assert_ne!(names["billing.utils.parse"], names["shipping.utils.parse"]);
+    assert_ne!(names["Orders.parse"], names["Users.parse"], "namespace-qualified aliases");
+
+    let orders = db.find_symbol_by_name(repo_id, "Orders").unwrap().unwrap();
+    let children = db.get_symbols_by_file(orders.file_id).unwrap();
+    let parse = children.iter().find(|s| s.id == names["Orders.parse"]).unwrap();
+    assert_eq!(parse.parent_id, Some(orders.id));
+
+    // Calls are attributed to the same-named symbol they sit in.
+    let callees = |qualified: &str| -> Vec<String> {
+        db.get_dependencies(names[qualified]).unwrap().into_iter().map(|(_, s)| s.name).collect()
+    };
+    assert_eq!(callees("checks.Orders.parse"), ["orderId"]);
+    assert_eq!(callees("checks.Users.parse"), ["userId"]);
+    assert_eq!(callees("billing.utils.Invoice.total"), ["tax"]);
+    assert_eq!(callees("billing.utils.Refund.total"), ["fee"]);
+}
+
+// ---------------------------------------------------------------------------
+// 19. Call edges record the distinct argument lists of their call sites
+// ---------------------------------------------------------------------------
+#[test]
+fn test_call_sites_on_edges() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(
+        &dir,
+        "main.go",
+        "package main\n\nfunc connect(host string, port int) {}\n\n\
+         func main() {\n    connect(\"db\", 5432)\n    connect(\"cache\", 6379)\n    connect(host())\n}\n\n\
+         func host() string { return \"\" }\n",
+    );
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+
+    let connect = db.find_symbol_by_name_any("connect").unwrap().unwrap();
+    let callers = db.get_dependents(connect.id).unwrap();
+    assert_eq!(callers.len(), 1);
+    let (edge, caller) = &callers[0];
+    assert_eq!(caller.name, "main");
+    assert_eq!(edge.call_sites, [vec!["string", "number"], vec!["expr"]]);
+
+    let host = db.find_symbol_by_name_any("host").unwrap().unwrap();
+    let (edge, _) = &db.get_dependents(host.id).unwrap()[0];
+    assert_eq!(edge.call_sites, [Vec::<String>::new()]);
+}
+
+// ---------------------------------------------------------------------------
+// 20. Re-indexing one file keeps the edges other files have into it
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_file_keeps_incoming_edges() {
+    let (db, registry) = setup();
+    let dir = TempDir::new().unwrap();
+    write_go_file(&dir, "main.go", "package main\n\nfunc main() {\n    connect(\"db\")\n}\n");
+    write_go_file(&dir, "conn.go", "package main\n\nfunc connect(host string) {}\n\nfunc unused() {}\n");
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+
+    write_go_file(&dir, "conn.go", "package main\n\n// connect dials host.\nfunc connect(host string) {}\n");
+    indexer.index_file(&dir.path().join("conn.go"), dir.path()).unwrap();
+
+    let connect = db.find_symbol_by_name_any("connect").unwrap().unwrap();
+    let callers = db.get_dependents(connect.id).unwrap();
+    assert_eq!(callers.len(), 1, "{callers:?}");
+    let (edge, caller) = &callers[0];
+    assert_eq!(caller.name, "main");
+    assert_eq!(edge.call_sites, [vec!["string"]]);
+}
@@ -1,7 +1,7 @@
 use std::fs;
 
 use tempfile::TempDir;
-use focal_core::db::Database;
+use focal_core::db::{Database, SearchFilter};
 use focal_core::grammar::GrammarRegistry;
 use focal_core::indexer::Indexer;
 
@@ -113,7 +113,7 @@ func FormatPort(port int) string {
     assert_eq!(sym_start.name, "Start");
 
     // Rich query — search by partial name, no kind/repo filter
-    let results = db.query_symbols_full("Server", "", "").unwrap();
+    let results = db.query_symbols_full("Server", "", "", "", "").unwrap();
     assert!(
         !results.is_empty(),
         "query_symbols_full('Server') should return results"
@@ -128,7 +128,7 @@ func FormatPort(port int) string {
     // 5. Full-text search via FTS
     // ---------------------------------------------------------------
     // Search by function name
-    let fts_results = db.search_code("NewServer", "", None, 10).unwrap();
+    let fts_results = db.search_code("NewServer", &SearchFilter::default(), 10).unwrap();
     assert!(
         !fts_results.is_empty(),
         "FTS search for 'NewServer' should return results"
@@ -136,7 +136,7 @@ func FormatPort(port int) string {
     assert_eq!(fts_results[0].name, "NewServer");
 
     // Search by body content — "starting server" appears in Start's body
-    let fts_body = db.search_code("starting server", "", None, 10).unwrap();
+    let fts_body = db.search_code("starting server", &SearchFilter::default(), 10).unwrap();
     assert!(
         !fts_body.is_empty(),
         "FTS search for 'starting server' should match Start's body"
@@ -144,12 +144,12 @@ func FormatPort(port int) string {
     assert_eq!(fts_body[0].name, "Start");
 
     // Search with kind filter
-    let fts_func = db.search_code("Sanitize", "function", None, 10).unwrap();
+    let fts_func = db.search_code("Sanitize", &SearchFilter { kind: "function", ..SearchFilter::default() }, 10).unwrap();
     assert_eq!(fts_func.len(), 1);
     assert_eq!(fts_func[0].name, "Sanitize");
 
     // Search scoped to repo
-    let fts_repo = db.search_code("HandleRequest", "", Some(repo_id), 10).unwrap();
+    let fts_repo = db.search_code("HandleRequest", &SearchFilter::repo(Some(&repo_id)), 10).unwrap();
     assert!(
         !fts_repo.is_empty(),
         "FTS search scoped to repo should find HandleRequest"
@@ -178,16 +178,16 @@ func FormatPort(port int) string {
     assert!(!mems[0].stale);
 
     // Via list_memories filtered by symbol name
-    let mems_by_name = db.list_memories("", false, "HandleRequest").unwrap();
+    let mems_by_name = db.list_memories("", false, false, "HandleRequest").unwrap();
     assert_eq!(mems_by_name.len(), 1);
     assert_eq!(mems_by_name[0].id, memory_id);
 
     // Via list_memories filtered by category
-    let mems_by_cat = db.list_memories("note", false, "").unwrap();
+    let mems_by_cat = db.list_memories("note", false, false, "").unwrap();
     assert_eq!(mems_by_cat.len(), 1);
 
     // Via query_symbols_full — memories should be attached to the symbol result
-    let rich = db.query_symbols_full("HandleRequest", "", "").unwrap();
+    let rich = db.query_symbols_full("HandleRequest", "", "", "", "").unwrap();
     assert!(!rich.is_empty());
     assert!(
         !rich[0].memories.is_empty(),
@@ -245,7 +245,7 @@ func HandleRequest(s *Server) {
     // ---------------------------------------------------------------
     // HandleRequest still exists in v2, so the indexer re-links the memory
     // to the new symbol ID and clears the stale flag.
-    let mems_fresh = db.list_memories("", false, "").unwrap();
+    let mems_fresh = db.list_memories("", false, false, "").unwrap();
     assert_eq!(
         mems_fresh.len(), 1,
         "memory should be re-linked and un-staled since HandleRequest persists, got {}",
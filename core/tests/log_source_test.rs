@@ -0,0 +1,49 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::log_source::{find_log_source, literal_fragments};
+use tempfile::TempDir;
+
+// ---------------------------------------------------------------------------
+// 1. Variable parts and log levels are stripped
+// ---------------------------------------------------------------------------
+#[test]
+fn test_literal_fragments() {
+    assert_eq!(
+        literal_fragments("2026-03-01T10:00:00Z ERROR user 42 failed to connect to db-7: timeout"),
+        ["failed to connect to", "timeout", "user"]
+    );
+    assert_eq!(
+        literal_fragments("[WARN] retrying request id=9f3a attempt=3 path=/v1/items"),
+        ["retrying request id", "attempt", "path"]
+    );
+    assert_eq!(literal_fragments("open \"/etc/app.toml\": no such file"), ["no such file", "open"]);
+    assert!(literal_fragments("42 0xdeadbeef").is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 2. The logging function ranks first, ahead of its enclosing class
+// ---------------------------------------------------------------------------
+#[test]
+fn test_find_log_source() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("pool.py"),
+        "class Pool:\n    def connect(self, host):\n        log.error(\"failed to connect to %s: timeout after %d retries\", host, n)\n\n    def close(self):\n        log.info(\"closing pool\")\n\n\ndef ping():\n    print(\"connect to upstream\")\n",
+    )
+    .unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let matches = find_log_source(&db, "ERROR failed to connect to db-7:5432: timeout after 3 retries", None, 10).unwrap();
+    let names: Vec<&str> = matches.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["connect"]);
+    assert_eq!(matches[0].score, 1.0);
+    assert_eq!(matches[0].line, 3);
+    assert!(matches[0].excerpt.starts_with("log.error("), "{}", matches[0].excerpt);
+
+    assert!(find_log_source(&db, "nothing like this anywhere", None, 10).unwrap().is_empty());
+}
@@ -0,0 +1,142 @@
+use focal_core::db::Database;
+use focal_core::memory::{render_fields, render_memory, validate_fields, MemoryKind};
+use serde_json::json;
+
+// ---------------------------------------------------------------------------
+// 1. Template validation — required, unknown, and non-string fields
+// ---------------------------------------------------------------------------
+#[test]
+fn test_validate_structured_fields() {
+    let ok = validate_fields(
+        "decision",
+        &json!({"decision": " Use WAL mode ", "context": "readers block writers", "consequences": ""}),
+    )
+    .unwrap();
+    // Trimmed, empty optional dropped
+    assert_eq!(ok, json!({"decision": "Use WAL mode", "context": "readers block writers"}));
+
+    let missing = validate_fields("invariant", &json!({"scope": "db"})).unwrap_err();
+    assert!(missing.contains("missing required field 'rule'"), "got: {missing}");
+
+    let unknown = validate_fields("invariant", &json!({"scope": "db", "rule": "x", "why": "y"}))
+        .unwrap_err();
+    assert!(unknown.contains("unknown field 'why'"), "got: {unknown}");
+
+    let not_string = validate_fields("decision", &json!({"decision": 3})).unwrap_err();
+    assert!(not_string.contains("must be a string"), "got: {not_string}");
+
+    let no_template = validate_fields("pattern", &json!({})).unwrap_err();
+    assert!(no_template.contains("no structured template"), "got: {no_template}");
+
+    assert!(MemoryKind::for_category("bug_fix").is_some());
+    assert!(MemoryKind::for_category("architecture").is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Rendering follows template field order
+// ---------------------------------------------------------------------------
+#[test]
+fn test_render_structured_fields() {
+    let fields = json!({"context": "readers block writers", "decision": "Use WAL mode"});
+    assert_eq!(
+        render_fields("decision", &fields),
+        "decision: Use WAL mode; context: readers block writers"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 3. Structured memories round-trip through the database
+// ---------------------------------------------------------------------------
+#[test]
+fn test_structured_memory_roundtrip() {
+    let db = Database::open_in_memory().unwrap();
+    let fields = validate_fields("invariant", &json!({"scope": "indexer", "rule": "never hold the lock across a walk"}))
+        .unwrap();
+    let id = db
+        .save_structured_memory("Lock discipline", "invariant", Some(&fields), &[])
+        .unwrap();
+
+    let mem = db.get_memory_by_id(id).unwrap().unwrap();
+    assert_eq!(mem.structured.as_ref(), Some(&fields));
+    assert_eq!(
+        render_memory(&mem),
+        "Lock discipline (scope: indexer; rule: never hold the lock across a walk)"
+    );
+
+    // Free-text memories carry no structured payload and render as-is
+    let plain = db.save_memory("just a note", "pattern", &[]).unwrap();
+    let plain = db.get_memory_by_id(plain).unwrap().unwrap();
+    assert!(plain.structured.is_none());
+    assert_eq!(render_memory(&plain), "just a note");
+
+    // Clearing fields
+    db.set_memory_structured(id, None).unwrap();
+    assert!(db.get_memory_by_id(id).unwrap().unwrap().structured.is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 4. Symbol mentions — backticks and CamelCase, deduplicated
+// ---------------------------------------------------------------------------
+#[test]
+fn test_extract_symbol_mentions() {
+    let mentions = focal_core::memory::extract_symbol_mentions(
+        "`parse_input()` must run before HandleRequest; see `Config::new` and HandleRequest. \
+         HTTP is an acronym, `two words` is prose, Handle is a plain word.",
+    );
+    assert_eq!(
+        mentions,
+        vec!["parse_input", "Config::new", "HandleRequest"]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 5. Stale reasons — recorded by each staleness path, cleared on relink
+// ---------------------------------------------------------------------------
+#[test]
+fn test_stale_reason_recorded_and_cleared() {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("r", "/tmp/r").unwrap();
+    let file_id = db.upsert_file(repo_id, "f.rs", "rust", "h").unwrap();
+    let kept = db
+        .insert_symbol(file_id, "kept", "", "function", "", "", "", 1, 1, None)
+        .unwrap();
+    let gone = db
+        .insert_symbol(file_id, "gone", "", "function", "", "", "", 2, 2, None)
+        .unwrap();
+    let kept_mem = db.save_memory("about kept", "note", &[kept]).unwrap();
+    let gone_mem = db.save_memory("about gone", "note", &[gone]).unwrap();
+
+    // Re-index: only `kept` comes back
+    db.mark_memories_stale_for_file(file_id).unwrap();
+    db.delete_symbols_by_file(file_id).unwrap();
+    db.insert_symbol(file_id, "kept", "", "function", "", "", "", 1, 1, None)
+        .unwrap();
+    let links = vec![
+        (kept_mem, "kept".to_string(), String::new()),
+        (gone_mem, "gone".to_string(), String::new()),
+    ];
+    assert_eq!(db.relink_memories_to_symbols(file_id, &links).unwrap(), 1);
+
+    let kept_mem = db.get_memory_by_id(kept_mem).unwrap().unwrap();
+    assert!(!kept_mem.stale);
+    assert!(kept_mem.stale_reason.is_none() && kept_mem.stale_at.is_none());
+    let gone_mem = db.get_memory_by_id(gone_mem).unwrap().unwrap();
+    assert!(gone_mem.stale);
+    assert_eq!(gone_mem.stale_reason.as_deref(), Some("symbol_removed"));
+
+    // Manual staleness and back
+    db.set_memory_stale(kept_mem.id, true).unwrap();
+    let manual = db.get_memory_by_id(kept_mem.id).unwrap().unwrap();
+    assert!(manual.stale);
+    assert_eq!(manual.stale_reason.as_deref(), Some("manual"));
+    db.set_memory_stale(kept_mem.id, false).unwrap();
+    let fresh = db.get_memory_by_id(kept_mem.id).unwrap().unwrap();
+    assert!(!fresh.stale && fresh.stale_reason.is_none());
+
+    // An already stale memory keeps its first reason.
+    db.set_memory_stale(gone_mem.id, true).unwrap();
+    db.mark_memories_stale_for_file(file_id).unwrap();
+    let still = db.get_memory_by_id(gone_mem.id).unwrap().unwrap();
+    assert_eq!(still.stale_reason.as_deref(), Some("symbol_removed"));
+    assert_eq!(still.stale_at, gone_mem.stale_at);
+}
@@ -0,0 +1,102 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::module_docs::{extract, is_package_entry, is_readme, readme_paragraph};
+use tempfile::TempDir;
+
+fn doc(rel_path: &str, text: &str) -> Option<String> {
+    extract(rel_path, text).map(|d| d.doc)
+}
+
+// ---------------------------------------------------------------------------
+// 1. README paragraphs skip headings, badges and HTML
+// ---------------------------------------------------------------------------
+#[test]
+fn test_readme_paragraph() {
+    assert!(is_readme("README.md"));
+    assert!(is_readme("docs/readme.rst"));
+    assert!(is_readme("README"));
+    assert!(!is_readme("README.go"));
+    assert!(!is_readme("readme_test.py"));
+
+    let text = "# Billing\n\n[![ci](badge.svg)](ci)\n<p align=\"center\">logo</p>\n\nCharges cards and\nissues refunds.\n\nSecond paragraph.\n";
+    assert_eq!(readme_paragraph(text).as_deref(), Some("Charges cards and issues refunds."));
+    assert_eq!(doc("README.rst", "Billing\n=======\n\nCharges cards.\n").as_deref(), Some("Charges cards."));
+    assert!(readme_paragraph("# Title only\n").is_none());
+
+    let long = format!("{}\n", "word ".repeat(500));
+    assert!(readme_paragraph(&long).unwrap().ends_with('…'));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Top-level doc comments per language, entry files only
+// ---------------------------------------------------------------------------
+#[test]
+fn test_doc_comments() {
+    assert_eq!(
+        doc("src/lib.rs", "//! Card vault client.\n//! Talks to the vault.\n//!\n//! Details.\n\npub fn f() {}\n").as_deref(),
+        Some("Card vault client. Talks to the vault.")
+    );
+    assert_eq!(doc("src/lib.rs", "/*!\n * Block docs.\n */\n").as_deref(), Some("Block docs."));
+    assert!(doc("src/util.rs", "//! Not an entry file.\n").is_none());
+    assert!(is_package_entry("src/lib.rs") && is_package_entry("pkg/index.ts"));
+    assert!(!is_package_entry("src/util.rs") && !is_package_entry("pkg/pay_test.go"));
+
+    assert_eq!(
+        doc("billing/doc.go", "// Copyright 2024\n\n// Package billing charges cards.\npackage billing\n").as_deref(),
+        Some("Package billing charges cards.")
+    );
+    assert!(doc("billing/charge.go", "// Charge helpers.\npackage billing\n").is_none());
+
+    assert_eq!(
+        doc("shop/__init__.py", "# coding: utf-8\n\"\"\"Shop domain.\n\nMore.\n\"\"\"\nimport os\n").as_deref(),
+        Some("Shop domain.")
+    );
+    assert!(doc("shop/cart.py", "\"\"\"Cart.\"\"\"\n").is_none());
+
+    assert_eq!(
+        doc("src/index.ts", "/**\n * UI kit entry point.\n *\n * @packageDocumentation\n */\nexport {};\n").as_deref(),
+        Some("UI kit entry point.")
+    );
+    assert!(doc("src/index.ts", "/** Adds numbers. */\nexport function add() {}\n").is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Indexed per package: README first, doc comment otherwise
+// ---------------------------------------------------------------------------
+#[test]
+fn test_package_docs_in_overview() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("shop");
+    fs::create_dir_all(root.join("crates/vault/src")).unwrap();
+    fs::create_dir_all(root.join("crates/cart/src")).unwrap();
+    fs::write(root.join("README.md"), "# Shop\n\nAn online shop.\n").unwrap();
+    fs::write(root.join("crates/vault/Cargo.toml"), "[package]\nname = \"vault\"\n").unwrap();
+    fs::write(root.join("crates/vault/src/lib.rs"), "//! Card vault client.\n\npub fn store() {}\n").unwrap();
+    fs::write(root.join("crates/cart/Cargo.toml"), "[package]\nname = \"cart\"\n").unwrap();
+    fs::write(root.join("crates/cart/README.md"), "Shopping cart.\n").unwrap();
+    fs::write(root.join("crates/cart/src/lib.rs"), "//! Overridden by the README.\n\npub fn add() {}\n").unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(&root).unwrap();
+
+    let mut overview = db.get_repo_overview("shop").unwrap();
+    assert!(overview[0].docs.is_empty(), "docs are opt-in");
+    db.fill_package_docs(&mut overview).unwrap();
+    let docs: Vec<(&str, &str, &str)> = overview[0]
+        .docs
+        .iter()
+        .map(|d| (d.package.as_str(), d.source.as_str(), d.doc.as_str()))
+        .collect();
+    assert_eq!(
+        docs,
+        [
+            ("shop", "README.md", "An online shop."),
+            ("cart", "crates/cart/README.md", "Shopping cart."),
+            ("vault", "crates/vault/src/lib.rs", "Card vault client."),
+        ]
+    );
+}
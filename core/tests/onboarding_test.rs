@@ -0,0 +1,66 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::onboarding::onboarding_capsule;
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, Database) {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/main.rs"),
+        "fn main() {\n    let c = load();\n    run(c);\n}\n\nfn run(c: Config) {\n    load();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src/config.rs"),
+        "/// Settings read at startup.\n/// Second line.\npub struct Config {}\n\n/// Read the config file.\npub fn load() -> Config {\n    Config {}\n}\n\npub fn unused() {}\n",
+    )
+    .unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    (dir, db)
+}
+
+// ---------------------------------------------------------------------------
+// 1. Entry points, hotspots, ranked public API and pinned memories
+// ---------------------------------------------------------------------------
+#[test]
+fn test_onboarding_capsule() {
+    let (_dir, db) = setup();
+    let memory = db.save_memory("Config is immutable after startup", "decision", &[]).unwrap();
+    db.set_memory_pinned(memory, true).unwrap();
+    let repo = db.list_repositories().unwrap().remove(0);
+
+    let capsule = onboarding_capsule(&db, &repo, 3_000).unwrap();
+    assert_eq!(capsule.overview.name, repo.name);
+    assert_eq!(capsule.pinned_memories.len(), 1);
+    let entry: Vec<(&str, &str)> = capsule.entry_points.iter().map(|e| (e.kind.as_str(), e.file_path.as_str())).collect();
+    assert_eq!(entry, [("main", "src/main.rs")]);
+    assert_eq!(capsule.hotspots[0].name, "load");
+    assert_eq!(capsule.hotspots[0].dependents, 2);
+
+    let apis: Vec<&str> = capsule.key_apis.iter().map(|a| a.symbol.name.as_str()).collect();
+    assert_eq!(apis[0], "load");
+    assert_eq!(*apis.last().unwrap(), "unused");
+    let config = capsule.key_apis.iter().find(|a| a.symbol.name == "Config").unwrap();
+    assert_eq!(config.symbol.doc.as_deref(), Some("Settings read at startup."));
+    assert!(capsule.omitted.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 2. A small budget keeps the overview and reports what was cut
+// ---------------------------------------------------------------------------
+#[test]
+fn test_onboarding_capsule_budget() {
+    let (_dir, db) = setup();
+    let repo = db.list_repositories().unwrap().remove(0);
+
+    let capsule = onboarding_capsule(&db, &repo, 50).unwrap();
+    assert_eq!(capsule.overview.name, repo.name);
+    assert!(capsule.key_apis.is_empty());
+    assert!(capsule.omitted.get("key_apis").is_some_and(|&n| n > 0));
+}
@@ -0,0 +1,111 @@
+use focal_core::owners::CodeOwners;
+use tempfile::TempDir;
+
+// ---------------------------------------------------------------------------
+// 1. Default rule and unanchored extension patterns
+// ---------------------------------------------------------------------------
+#[test]
+fn test_default_and_extension_rules() {
+    let owners = CodeOwners::parse(
+        "# comment line\n\
+         *       @org/everyone\n\
+         *.rs    @rustaceans   # inline comment\n",
+    );
+    assert_eq!(owners.owners_for("README.md"), vec!["@org/everyone"]);
+    assert_eq!(owners.owners_for("src/db.rs"), vec!["@rustaceans"]);
+    assert_eq!(owners.owners_for("core/src/grammar/go.rs"), vec!["@rustaceans"]);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Anchored directories, one-level `*`, and `**`
+// ---------------------------------------------------------------------------
+#[test]
+fn test_directory_patterns() {
+    let owners = CodeOwners::parse(
+        "/docs/            @docs-team\n\
+         apps/*            @apps-team\n\
+         **/migrations     @dba\n\
+         src/**/handlers   @api-team\n",
+    );
+
+    // Anchored directory covers everything beneath it, but only at the root.
+    assert_eq!(owners.owners_for("docs/guide/intro.md"), vec!["@docs-team"]);
+    assert!(owners.owners_for("core/docs/notes.md").is_empty());
+
+    // `apps/*` matches direct children only.
+    assert_eq!(owners.owners_for("apps/main.go"), vec!["@apps-team"]);
+    assert!(owners.owners_for("apps/web/main.go").is_empty());
+
+    // `**` matches any number of leading or interior segments.
+    assert_eq!(owners.owners_for("db/migrations/001.sql"), vec!["@dba"]);
+    assert_eq!(owners.owners_for("migrations/001.sql"), vec!["@dba"]);
+    assert_eq!(owners.owners_for("src/handlers/user.go"), vec!["@api-team"]);
+    assert_eq!(owners.owners_for("src/v1/http/handlers/user.go"), vec!["@api-team"]);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Last matching rule wins; an empty owner list unassigns
+// ---------------------------------------------------------------------------
+#[test]
+fn test_last_match_wins_and_unassign() {
+    let owners = CodeOwners::parse(
+        "*                @org/everyone\n\
+         /payments/       @payments\n\
+         /payments/vendor/\n",
+    );
+    assert_eq!(owners.owners_for("payments/charge.go"), vec!["@payments"]);
+    assert!(owners.owners_for("payments/vendor/stripe.go").is_empty());
+
+    let rule = owners.rule_for("payments/vendor/stripe.go").unwrap();
+    assert_eq!(rule.pattern, "/payments/vendor/");
+    assert!(rule.owners.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 4. load — finds .github/CODEOWNERS, None when absent
+// ---------------------------------------------------------------------------
+#[test]
+fn test_load_from_repo_root() {
+    let tmp = TempDir::new().unwrap();
+    assert!(CodeOwners::load(tmp.path()).is_none());
+
+    std::fs::create_dir_all(tmp.path().join(".github")).unwrap();
+    std::fs::write(tmp.path().join(".github/CODEOWNERS"), "*.go @gophers\n").unwrap();
+
+    let owners = CodeOwners::load(tmp.path()).unwrap();
+    assert_eq!(owners.owners_for("cmd/server/main.go"), vec!["@gophers"]);
+}
+
+// ---------------------------------------------------------------------------
+// 5. load_cached — reused until the file changes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_load_cached_reloads_on_change() {
+    let tmp = TempDir::new().unwrap();
+    assert!(CodeOwners::load_cached(tmp.path()).is_none());
+
+    let path = tmp.path().join("CODEOWNERS");
+    std::fs::write(&path, "*.go @gophers\n").unwrap();
+    let first = CodeOwners::load_cached(tmp.path()).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &CodeOwners::load_cached(tmp.path()).unwrap()));
+
+    std::fs::write(&path, "*.go @gophers @reviewers\n").unwrap();
+    assert_eq!(CodeOwners::load_cached(tmp.path()).unwrap().owners_for("main.go"), ["@gophers", "@reviewers"]);
+}
+
+// ---------------------------------------------------------------------------
+// 6. A symbol's file resolves in its own repository
+// ---------------------------------------------------------------------------
+#[test]
+fn test_symbol_file_in_own_repo() {
+    let db = focal_core::db::Database::open_in_memory().unwrap();
+    let first = db.upsert_repository("first", "/repos/first").unwrap();
+    let second = db.upsert_repository("second", "/repos/second").unwrap();
+    db.upsert_file(first, "src/pay.go", "go", "h1").unwrap();
+    let file_id = db.upsert_file(second, "src/pay.go", "go", "h2").unwrap();
+    let id = db.insert_symbol(file_id, "Pay", "", "function", "func Pay()", "func Pay() {}", "", 1, 1, None).unwrap();
+
+    let file = db.resolved_file_for_symbol(id).unwrap().unwrap();
+    assert_eq!((file.repo.as_str(), file.file_path.as_str(), file.file_id), ("second", "src/pay.go", file_id));
+    assert!(db.resolved_file_for_symbol(id + 1).unwrap().is_none());
+}
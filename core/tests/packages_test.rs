@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use focal_core::context::ContextEngine;
+use focal_core::db::{Database, SearchFilter};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::packages::{package_for_path, parse_manifest, Package};
+use tempfile::TempDir;
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// Cargo workspace with two crates, an npm workspace package, a Go module
+/// and a loose script outside every package.
+fn monorepo() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    write(root, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+    write(root, "crates/billing/Cargo.toml", "[package]\nname = \"billing\"\nversion = \"0.1.0\"\n");
+    write(root, "crates/billing/src/lib.rs", "pub fn charge_invoice() {}\npub fn refund_invoice() {}\n");
+    write(root, "crates/shipping/Cargo.toml", "[package]\nname = \"shipping\"\nversion = \"0.1.0\"\n");
+    write(root, "crates/shipping/src/lib.rs", "pub fn invoice_label() {}\n");
+    write(root, "package.json", r#"{ "private": true, "workspaces": ["web"] }"#);
+    write(root, "web/package.json", r#"{ "name": "@acme/web", "version": "1.0.0" }"#);
+    write(root, "web/src/invoice.ts", "export function renderInvoice() {}\n");
+    write(root, "services/api/go.mod", "module github.com/acme/api\n\ngo 1.22\n");
+    write(root, "services/api/main.go", "package main\n\nfunc ServeInvoice() {}\n");
+    write(root, "scripts/invoice_tool.py", "def dump_invoice():\n    pass\n");
+    dir
+}
+
+fn index(dir: &TempDir) -> Database {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    db
+}
+
+// ---------------------------------------------------------------------------
+// 1. Manifest parsing
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_manifest() {
+    let cargo = parse_manifest("crates/a/Cargo.toml", "[package]\nname = \"a\"\n").unwrap();
+    assert_eq!(cargo, Package { name: "a".into(), path: "crates/a".into(), kind: "cargo".into() });
+    assert!(parse_manifest("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n").is_none());
+
+    let npm = parse_manifest("package.json", r#"{"name": "root-app"}"#).unwrap();
+    assert_eq!((npm.name.as_str(), npm.path.as_str(), npm.kind.as_str()), ("root-app", "", "npm"));
+    assert!(parse_manifest("package.json", r#"{"workspaces": ["a"]}"#).is_none());
+
+    let go = parse_manifest("svc/go.mod", "// comment\nmodule example.com/svc\n").unwrap();
+    assert_eq!(go.name, "example.com/svc");
+
+    let poetry = parse_manifest("tools/pyproject.toml", "[tool.poetry]\nname = \"tools\"\n").unwrap();
+    assert_eq!((poetry.name.as_str(), poetry.kind.as_str()), ("tools", "python"));
+    assert!(parse_manifest("Cargo.toml", "not = [valid").is_none());
+}
+
+#[test]
+fn test_package_for_path_picks_innermost() {
+    let packages = vec![
+        Package { name: "root".into(), path: "".into(), kind: "npm".into() },
+        Package { name: "app".into(), path: "apps/app".into(), kind: "npm".into() },
+    ];
+    assert_eq!(package_for_path(&packages, "apps/app/src/x.ts").unwrap().name, "app");
+    assert_eq!(package_for_path(&packages, "apps/application/x.ts").unwrap().name, "root");
+    assert!(package_for_path(&packages[1..], "lib/x.ts").is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Indexing assigns packages and reports per-package stats
+// ---------------------------------------------------------------------------
+#[test]
+fn test_list_package_stats() {
+    let dir = monorepo();
+    let db = index(&dir);
+    let stats = db.list_package_stats(None).unwrap();
+    let summary: Vec<(&str, &str, &str, i64, i64)> = stats
+        .iter()
+        .map(|p| (p.name.as_str(), p.path.as_str(), p.kind.as_str(), p.file_count, p.symbol_count))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("billing", "crates/billing", "cargo", 1, 2),
+            ("shipping", "crates/shipping", "cargo", 1, 1),
+            ("github.com/acme/api", "services/api", "go", 1, 1),
+            ("@acme/web", "web", "npm", 1, 1),
+        ]
+    );
+    assert_eq!(stats[3].languages, ["ts"]);
+
+    let repo = &db.list_repositories().unwrap()[0];
+    let script = db.get_file_by_path(repo.id, "scripts/invoice_tool.py").unwrap().unwrap();
+    let packages = db.get_packages(repo.id).unwrap();
+    assert!(package_for_path(&packages, &script.path).is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Package-scoped search and context
+// ---------------------------------------------------------------------------
+#[test]
+fn test_package_scoped_search_and_context() {
+    let dir = monorepo();
+    let db = index(&dir);
+
+    // FTS splits snake_case only; the camelCase names are not matched here.
+    assert_eq!(db.search_code("invoice", &SearchFilter::default(), 20).unwrap().len(), 4);
+    let billing: Vec<String> = db
+        .search_code("invoice", &SearchFilter { package: "billing", ..SearchFilter::default() }, 20)
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(billing.len(), 2);
+    assert!(billing.iter().all(|n| n.ends_with("_invoice")), "{billing:?}");
+
+    let ids: Vec<i64> = db
+        .search_code("ServeInvoice", &SearchFilter::default(), 5)
+        .unwrap()
+        .iter()
+        .map(|s| s.id)
+        .collect();
+    assert_eq!(db.get_packages_for_symbols(&ids).unwrap()[&ids[0]], "github.com/acme/api");
+
+    let capsule = ContextEngine::new(&db)
+        .with_package("@acme/web")
+        .get_capsule("invoice", 8_000, None, &HashSet::new())
+        .unwrap();
+    assert!(!capsule.items.is_empty());
+    assert!(capsule.items.iter().all(|i| i.file_path.starts_with("web/")), "{:?}", capsule.items);
+}
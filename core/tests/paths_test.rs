@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use focal_core::paths::{is_absolute, normalize_path, repo_relative, strip_root};
+
+// ---------------------------------------------------------------------------
+// 1. normalize_path — separators, dot segments, trailing slashes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_normalize_path() {
+    assert_eq!(normalize_path("src/db.rs"), "src/db.rs");
+    assert_eq!(normalize_path("./src/db.rs"), "src/db.rs");
+    assert_eq!(normalize_path("src\\grammar\\go.rs"), "src/grammar/go.rs");
+    assert_eq!(normalize_path("src//./db.rs"), "src/db.rs");
+    assert_eq!(normalize_path("payments/"), "payments");
+    assert_eq!(normalize_path("/home/u/repo/"), "/home/u/repo");
+    assert_eq!(normalize_path("C:\\work\\repo"), "C:/work/repo");
+    assert_eq!(normalize_path("../sibling"), "../sibling");
+}
+
+// ---------------------------------------------------------------------------
+// 2. is_absolute — Unix and Windows drive paths
+// ---------------------------------------------------------------------------
+#[test]
+fn test_is_absolute() {
+    assert!(is_absolute("/home/u"));
+    assert!(is_absolute("C:/work"));
+    assert!(!is_absolute("src/db.rs"));
+}
+
+// ---------------------------------------------------------------------------
+// 3. strip_root — segment-aligned, drive letters case-insensitive
+// ---------------------------------------------------------------------------
+#[test]
+fn test_strip_root() {
+    assert_eq!(
+        strip_root("/home/u/repo/src/db.rs", "/home/u/repo").as_deref(),
+        Some("src/db.rs")
+    );
+    assert_eq!(strip_root("/home/u/repo", "/home/u/repo").as_deref(), Some(""));
+    assert_eq!(strip_root("/home/u/repo2/x.rs", "/home/u/repo"), None);
+    assert_eq!(strip_root("/other/x.rs", "/home/u/repo"), None);
+    assert_eq!(
+        strip_root("c:\\Work\\Repo\\src\\main.rs", "C:\\Work\\Repo").as_deref(),
+        Some("src/main.rs")
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 4. repo_relative — canonical form for indexed files
+// ---------------------------------------------------------------------------
+#[test]
+fn test_repo_relative() {
+    let root = Path::new("/tmp/repo");
+    assert_eq!(repo_relative(Path::new("/tmp/repo/src/lib.rs"), root), "src/lib.rs");
+}
+
+// ---------------------------------------------------------------------------
+// 5. Windows — verbatim prefixes and drive-letter case differences
+// ---------------------------------------------------------------------------
+#[test]
+fn test_windows_path_variants() {
+    use focal_core::paths::{is_under, simplify_verbatim};
+    use std::path::PathBuf;
+
+    assert_eq!(normalize_path(r"\\?\C:\work\repo\src\main.rs"), "C:/work/repo/src/main.rs");
+
+    // canonicalize() on Windows yields a verbatim root; notify reports plain
+    // paths, sometimes with a lowercase drive letter.
+    let root = Path::new(r"\\?\C:\Work\Repo");
+    let event = Path::new(r"c:\Work\Repo\src\main.rs");
+    assert!(is_under(event, root));
+    assert_eq!(repo_relative(event, root), "src/main.rs");
+    assert!(!is_under(Path::new(r"C:\Work\Repo2\x.rs"), root));
+
+    assert_eq!(
+        simplify_verbatim(PathBuf::from(r"\\?\C:\Work\Repo")),
+        PathBuf::from(r"C:\Work\Repo")
+    );
+    // UNC verbatim paths are left alone.
+    assert_eq!(
+        simplify_verbatim(PathBuf::from(r"\\?\UNC\server\share")),
+        PathBuf::from(r"\\?\UNC\server\share")
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 6. to_repo_relative_path — nested repositories use the innermost root
+// ---------------------------------------------------------------------------
+#[test]
+fn test_to_repo_relative_path_nested_roots() {
+    let db = focal_core::db::Database::open_in_memory().unwrap();
+    db.upsert_repository("mono", "/work/mono").unwrap();
+    db.upsert_repository("billing", "/work/mono/services/billing").unwrap();
+    assert_eq!(db.to_repo_relative_path("/work/mono/services/billing/api.go").unwrap(), "api.go");
+    assert_eq!(db.to_repo_relative_path("/work/mono/tools/gen.go").unwrap(), "tools/gen.go");
+    assert_eq!(db.to_repo_relative_path("src/lib.rs").unwrap(), "src/lib.rs");
+}
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use focal_core::db::Database;
+use focal_core::probes::Probes;
+
+fn body(response: &(u16, String)) -> serde_json::Value {
+    serde_json::from_str(&response.1).unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Liveness always answers; readiness waits for the startup index
+// ---------------------------------------------------------------------------
+#[test]
+fn test_ready_after_initial_index() {
+    let complete = Arc::new(AtomicBool::new(false));
+    let probes = Probes::new(Arc::clone(&complete));
+    let db = Mutex::new(Database::open_in_memory().unwrap());
+
+    assert_eq!(probes.liveness().0, 200);
+    let response = probes.readiness(&db);
+    assert_eq!(response.0, 503);
+    assert_eq!(body(&response)["reasons"][0], "initial index in progress");
+
+    complete.store(true, Ordering::Relaxed);
+    let response = probes.readiness(&db);
+    assert_eq!(response.0, 200);
+    assert_eq!(body(&response)["db_writable"], true);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Reindexing only flips readiness when configured to
+// ---------------------------------------------------------------------------
+#[test]
+fn test_reindex_readiness_is_opt_in() {
+    let complete = Arc::new(AtomicBool::new(true));
+    let db = Mutex::new(Database::open_in_memory().unwrap());
+
+    let lenient = Probes::new(Arc::clone(&complete));
+    {
+        let _guard = lenient.begin_reindex();
+        let response = lenient.readiness(&db);
+        assert_eq!(response.0, 200);
+        assert_eq!(body(&response)["reindexing"], true);
+    }
+
+    let strict = Probes::new(Arc::clone(&complete)).with_unready_during_reindex(true);
+    {
+        let _guard = strict.begin_reindex();
+        assert_eq!(strict.readiness(&db).0, 503);
+    }
+    assert_eq!(strict.readiness(&db).0, 200, "guard drop clears the flag");
+}
+
+// ---------------------------------------------------------------------------
+// 3. A read-only database is not ready; a busy one is
+// ---------------------------------------------------------------------------
+#[test]
+fn test_database_writability() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.db");
+    drop(Database::open(path.to_str().unwrap()).unwrap());
+    let complete = Arc::new(AtomicBool::new(true));
+    let probes = Probes::new(complete);
+
+    let read_only = Mutex::new(Database::open_read_only(path.to_str().unwrap()).unwrap());
+    let response = probes.readiness(&read_only);
+    assert_eq!(response.0, 503);
+    assert_eq!(body(&response)["db_writable"], false);
+
+    let db = Mutex::new(Database::open(path.to_str().unwrap()).unwrap());
+    assert!(!db.lock().unwrap().check_writable().unwrap());
+
+    // Another connection holding the write lock is reported at once as
+    // contended, not waited out for the busy timeout.
+    let writer = Database::open(path.to_str().unwrap()).unwrap();
+    writer.begin_transaction().unwrap();
+    let started = std::time::Instant::now();
+    let response = probes.readiness(&db);
+    assert!(started.elapsed() < std::time::Duration::from_secs(1), "{:?}", started.elapsed());
+    assert_eq!(response.0, 200);
+    assert_eq!(body(&response)["db_contended"], true);
+    writer.rollback_transaction();
+    assert_eq!(body(&probes.readiness(&db))["db_contended"], false);
+
+    let _held = db.lock().unwrap();
+    assert_eq!(probes.readiness(&db).0, 200);
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use focal_core::db::{Database, SearchFilter};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+/// `backend/` calls into `proto/`; `tools/` declares the same name but is
+/// not a member.
+fn write_roots(dir: &TempDir) -> (PathBuf, PathBuf, PathBuf) {
+    let backend = dir.path().join("backend");
+    let proto = dir.path().join("proto");
+    let tools = dir.path().join("tools");
+    for root in [&backend, &proto, &tools] {
+        fs::create_dir_all(root).unwrap();
+    }
+    fs::write(
+        backend.join("server.go"),
+        "package server\n\nfunc HandleOrder() {\n\tDecodeOrder()\n}\n",
+    )
+    .unwrap();
+    fs::write(proto.join("order.go"), "package proto\n\nfunc DecodeOrder() {}\n").unwrap();
+    fs::write(tools.join("lint.go"), "package tools\n\nfunc DecodeOrder() {}\n").unwrap();
+    (backend, proto, tools)
+}
+
+fn shop() -> BTreeMap<String, Vec<String>> {
+    BTreeMap::from([(
+        "shop".to_string(),
+        vec!["backend".to_string(), "proto".to_string(), "frontend".to_string()],
+    )])
+}
+
+fn index(db: &Database, roots: &[&Path]) {
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(db, &registry);
+    for root in roots {
+        indexer.index_directory(root).unwrap();
+    }
+}
+
+fn calls_decode_order(db: &Database) -> bool {
+    let handler = db.find_symbol_by_name_any("HandleOrder").unwrap().unwrap();
+    db.get_dependencies(handler.id)
+        .unwrap()
+        .iter()
+        .any(|(_, target)| target.name == "DecodeOrder")
+}
+
+// ---------------------------------------------------------------------------
+// 1. Configured groupings resolve to indexed member repositories
+// ---------------------------------------------------------------------------
+#[test]
+fn test_project_membership() {
+    let dir = TempDir::new().unwrap();
+    let (backend, proto, tools) = write_roots(&dir);
+    let db = Database::open_in_memory().unwrap();
+    db.sync_projects(&shop()).unwrap();
+    index(&db, &[&proto, &backend, &tools]);
+
+    let members = db.project_repositories("shop").unwrap().unwrap();
+    let names: Vec<&str> = members.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["backend", "proto"], "unindexed members are left out");
+    assert!(db.project_repositories("unknown").unwrap().is_none());
+
+    let backend_id = db.get_repo_id_by_name("backend").unwrap().unwrap();
+    let proto_id = db.get_repo_id_by_name("proto").unwrap().unwrap();
+    let tools_id = db.get_repo_id_by_name("tools").unwrap().unwrap();
+    assert_eq!(db.project_sibling_repo_ids(backend_id).unwrap(), vec![proto_id]);
+    assert!(db.project_sibling_repo_ids(tools_id).unwrap().is_empty());
+
+    // Syncing replaces the previous groupings.
+    db.sync_projects(&BTreeMap::new()).unwrap();
+    assert!(db.project_repositories("shop").unwrap().is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 2. References resolve across members of a project, and only there
+// ---------------------------------------------------------------------------
+#[test]
+fn test_cross_repo_edges_within_project() {
+    let dir = TempDir::new().unwrap();
+    let (backend, proto, _) = write_roots(&dir);
+
+    let ungrouped = Database::open_in_memory().unwrap();
+    index(&ungrouped, &[&proto, &backend]);
+    assert!(!calls_decode_order(&ungrouped));
+
+    let grouped = Database::open_in_memory().unwrap();
+    grouped.sync_projects(&shop()).unwrap();
+    index(&grouped, &[&proto, &backend]);
+    assert!(calls_decode_order(&grouped));
+}
+
+// ---------------------------------------------------------------------------
+// 3. Project overview totals and project-scoped search
+// ---------------------------------------------------------------------------
+#[test]
+fn test_project_overview_and_search() {
+    let dir = TempDir::new().unwrap();
+    let (backend, proto, tools) = write_roots(&dir);
+    let db = Database::open_in_memory().unwrap();
+    db.sync_projects(&shop()).unwrap();
+    index(&db, &[&proto, &backend, &tools]);
+
+    let overview = db.get_project_overview("shop").unwrap().unwrap();
+    assert_eq!(overview.repos.len(), 2);
+    assert_eq!(overview.file_count, 2);
+    assert_eq!(overview.symbol_count, overview.repos.iter().map(|r| r.symbol_count).sum::<i64>());
+    assert_eq!(overview.cross_repo_edges, 1);
+    let kinds: Vec<(&str, i64)> = overview.symbol_kinds.iter().map(|k| (k.kind.as_str(), k.count)).collect();
+    assert_eq!(kinds, [("function", 2)], "summed across members");
+    assert_eq!(overview.missing_repos, vec!["frontend".to_string()]);
+    assert_eq!(overview.repos[0].projects, vec!["shop".to_string()]);
+    assert!(db.get_project_overview("unknown").unwrap().is_none());
+
+    let ids: Vec<i64> = db.project_repositories("shop").unwrap().unwrap().iter().map(|r| r.id).collect();
+    let names = |repo_ids: Option<&[i64]>| -> Vec<String> {
+        let mut names: Vec<String> = db
+            .search_code("DecodeOrder", &SearchFilter { kind: "function", repo_ids, ..SearchFilter::default() }, 10)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(None).len(), 3);
+    assert_eq!(names(Some(&ids)), ["DecodeOrder", "HandleOrder"], "tools/ is not a member");
+    assert!(names(Some(&[])).is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 4. A member indexed later still resolves, and per-file re-indexes keep
+//    cross-repo edges
+// ---------------------------------------------------------------------------
+#[test]
+fn test_cross_repo_edges_any_order() {
+    let dir = TempDir::new().unwrap();
+    let (backend, proto, _) = write_roots(&dir);
+    let db = Database::open_in_memory().unwrap();
+    db.sync_projects(&shop()).unwrap();
+    index(&db, &[&backend, &proto]);
+    assert!(calls_decode_order(&db), "backend is re-resolved when proto is first indexed");
+
+    fs::write(
+        backend.join("server.go"),
+        "package server\n\n// HandleOrder decodes.\nfunc HandleOrder() {\n\tDecodeOrder()\n}\n",
+    )
+    .unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    assert!(indexer.index_file(&backend.join("server.go"), &backend).unwrap());
+    assert!(calls_decode_order(&db), "the watcher path resolves into siblings");
+}
@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+use std::fs;
+
+use focal_core::context::ContextEngine;
+use focal_core::db::{Database, SearchFilter};
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::proximity::Focus;
+use focal_core::working_set::{WorkingSet, MAX_TRACKED};
+use tempfile::TempDir;
+
+/// Two `parse_*` functions in different directories; only the billing one is
+/// near `charge`.
+fn setup() -> (TempDir, Database) {
+    let dir = TempDir::new().unwrap();
+    for sub in ["auth", "billing"] {
+        fs::create_dir_all(dir.path().join(sub)).unwrap();
+    }
+    fs::write(dir.path().join("auth/token.rs"), "pub fn parse_token(raw: &str) -> u32 {\n    raw.len() as u32\n}\n").unwrap();
+    fs::write(dir.path().join("billing/invoice.rs"), "pub fn parse_invoice(raw: &str) -> u32 {\n    raw.len() as u32\n}\n").unwrap();
+    fs::write(dir.path().join("billing/charge.rs"), "pub fn charge() -> u32 {\n    7\n}\n").unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    (dir, db)
+}
+
+// ---------------------------------------------------------------------------
+// 1. Same-directory results move ahead of equally relevant distant ones
+// ---------------------------------------------------------------------------
+#[test]
+fn test_focus_reranks_by_directory() {
+    let (_dir, db) = setup();
+    let results = db.search_code("parse", &SearchFilter::default(), 10).unwrap();
+    assert_eq!(results.len(), 2);
+
+    let focus = Focus::resolve(&db, "billing/charge.rs", None).unwrap().unwrap();
+    let ranked = focus.rerank(&db, results.clone()).unwrap();
+    assert_eq!(ranked[0].name, "parse_invoice");
+
+    let focus = Focus::resolve(&db, "auth/token.rs", None).unwrap().unwrap();
+    let ranked = focus.rerank(&db, results).unwrap();
+    assert_eq!(ranked[0].name, "parse_token");
+
+    assert!(Focus::resolve(&db, "no_such_thing", None).unwrap().is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 2. get_context pivots follow a symbol focus
+// ---------------------------------------------------------------------------
+#[test]
+fn test_capsule_pivots_near_focus() {
+    let (_dir, db) = setup();
+    for (near, expected) in [("charge", "parse_invoice"), ("parse_token", "parse_token")] {
+        let focus = Focus::resolve(&db, near, None).unwrap().unwrap();
+        let capsule = ContextEngine::new(&db)
+            .with_focus(focus)
+            .get_capsule("parse", 10_000, None, &HashSet::new())
+            .unwrap();
+        assert_eq!(capsule.items[0].name, expected, "near {near}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 3. The working set steers pivots, is ordered most recent first and is capped
+// ---------------------------------------------------------------------------
+#[test]
+fn test_capsule_pivots_near_working_set() {
+    let (_dir, db) = setup();
+    let charge = db.find_symbol_by_name_any("charge").unwrap().unwrap();
+
+    let mut working_set = WorkingSet::new();
+    working_set.touch_symbols([charge.id]);
+    working_set.touch_symbols([charge.id + 1000]);
+    working_set.touch_symbols([charge.id]);
+    assert_eq!(working_set.recent_symbols(10), [charge.id, charge.id + 1000]);
+
+    let focus = working_set.focus(&db).unwrap().unwrap();
+    let capsule = ContextEngine::new(&db)
+        .with_working_set(focus)
+        .get_capsule("parse", 10_000, None, &HashSet::new())
+        .unwrap();
+    assert_eq!(capsule.items[0].name, "parse_invoice");
+
+    working_set.clear();
+    assert!(working_set.is_empty());
+    assert!(working_set.focus(&db).unwrap().is_none());
+    assert!(Focus::from_ids(&db, &[], &[]).unwrap().is_none());
+
+    // The least recently touched entries are forgotten past the cap.
+    working_set.touch_symbols(0..MAX_TRACKED as i64 + 10);
+    assert_eq!(working_set.symbol_count(), MAX_TRACKED);
+    assert_eq!(working_set.recent_symbols(1), [MAX_TRACKED as i64 + 9]);
+    assert!(!working_set.recent_symbols(MAX_TRACKED).contains(&9));
+    for file_id in 0..MAX_TRACKED as i64 + 1 {
+        working_set.touch_file(file_id);
+    }
+    assert_eq!(working_set.file_count(), MAX_TRACKED);
+}
@@ -0,0 +1,95 @@
+use focal_core::db::Database;
+use focal_core::query_cache::QueryCache;
+
+fn setup() -> (Database, i64) {
+    let db = Database::open_in_memory().unwrap();
+    let repo_id = db.upsert_repository("repo", "/work/repo").unwrap();
+    let file_id = db.upsert_file(repo_id, "src/lib.rs", "rust", "h1").unwrap();
+    (db, file_id)
+}
+
+// ---------------------------------------------------------------------------
+// 1. The data version changes on index writes but not on auto-observations
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_data_version_tracks_index_writes() {
+    let (db, file_id) = setup();
+    let before = db.data_version().unwrap();
+    assert_eq!(db.data_version().unwrap(), before, "reads leave the version alone");
+
+    db.save_auto_observation("Explored 'parse'", "auto:query_symbol", "session-1", &[]).unwrap();
+    assert_eq!(db.data_version().unwrap(), before);
+
+    db.insert_symbol(file_id, "parse", "", "function", "fn parse()", "fn parse() {}", "b", 1, 1, None).unwrap();
+    assert_ne!(db.data_version().unwrap(), before);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Hits need the same tool, key and version; a new version drops everything
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_cache_invalidated_by_new_version() {
+    let (db, file_id) = setup();
+    let version = db.data_version().unwrap();
+    let mut cache = QueryCache::new(8);
+
+    assert_eq!(cache.get::<Vec<i64>>("query_symbol", "parse", version), None);
+    cache.insert("query_symbol", "parse".to_string(), version, vec![1_i64, 2]);
+    assert_eq!(cache.get::<Vec<i64>>("query_symbol", "parse", version), Some(vec![1, 2]));
+    assert_eq!(cache.get::<Vec<i64>>("search_code", "parse", version), None);
+
+    db.insert_symbol(file_id, "parse", "", "function", "", "", "", 1, 1, None).unwrap();
+    let changed = db.data_version().unwrap();
+    assert_eq!(cache.get::<Vec<i64>>("query_symbol", "parse", changed), None);
+    assert_eq!(cache.stats().entries, 0);
+    assert_eq!((cache.stats().hits, cache.stats().misses), (1, 3));
+}
+
+// ---------------------------------------------------------------------------
+// 3. At capacity, the least recently used entry is evicted
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_cache_evicts_least_recently_used() {
+    let (db, _) = setup();
+    let version = db.data_version().unwrap();
+    let mut cache = QueryCache::new(2);
+
+    cache.insert("query_symbol", "a".to_string(), version, 1_u32);
+    cache.insert("query_symbol", "b".to_string(), version, 2_u32);
+    assert_eq!(cache.get::<u32>("query_symbol", "a", version), Some(1));
+    cache.insert("query_symbol", "c".to_string(), version, 3_u32);
+
+    assert_eq!(cache.get::<u32>("query_symbol", "b", version), None, "b was least recently used");
+    assert_eq!(cache.get::<u32>("query_symbol", "a", version), Some(1));
+    assert_eq!(cache.get::<u32>("query_symbol", "c", version), Some(3));
+}
+
+// ---------------------------------------------------------------------------
+// 4. The byte budget evicts by size; oversized results aren't kept
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_cache_byte_budget() {
+    let (db, _) = setup();
+    let version = db.data_version().unwrap();
+    // Each entry: 12-byte tool + 1-byte key + 102-byte JSON string.
+    let value = "x".repeat(100);
+    let mut cache = QueryCache::new(16).with_budget(250);
+
+    cache.insert("query_symbol", "a".to_string(), version, value.clone());
+    cache.insert("query_symbol", "b".to_string(), version, value.clone());
+    assert_eq!((cache.stats().entries, cache.stats().bytes), (2, 230));
+    cache.insert("query_symbol", "c".to_string(), version, value.clone());
+
+    assert_eq!(cache.get::<String>("query_symbol", "a", version), None, "evicted to fit c");
+    assert!(cache.get::<String>("query_symbol", "c", version).is_some());
+    let stats = cache.stats();
+    assert_eq!((stats.entries, stats.bytes, stats.budget_bytes, stats.evictions), (2, 230, 250, 1));
+
+    cache.insert("query_symbol", "big".to_string(), version, "x".repeat(300));
+    assert_eq!(cache.get::<String>("query_symbol", "big", version), None);
+    assert_eq!(cache.stats().entries, 2, "nothing evicted for a value that can't fit");
+}
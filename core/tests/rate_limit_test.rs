@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use focal_core::rate_limit::RateLimiter;
+
+// ---------------------------------------------------------------------------
+// 1. A session may burst, then is held to the sustained rate
+// ---------------------------------------------------------------------------
+#[test]
+fn test_burst_then_rate() {
+    let limiter = RateLimiter::new(60, 3);
+    let t0 = Instant::now();
+    for _ in 0..3 {
+        assert!(limiter.check("a", t0).is_ok());
+    }
+    let wait = limiter.check("a", t0).unwrap_err();
+    assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1), "{wait:?}");
+
+    // One request per second refills.
+    assert!(limiter.check("a", t0 + Duration::from_millis(1000)).is_ok());
+    assert!(limiter.check("a", t0 + Duration::from_millis(1000)).is_err());
+
+    // A long pause refills only up to the burst.
+    let later = t0 + Duration::from_secs(120);
+    for _ in 0..3 {
+        assert!(limiter.check("a", later).is_ok());
+    }
+    assert!(limiter.check("a", later).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 2. Sessions have separate buckets; idle ones are evicted
+// ---------------------------------------------------------------------------
+#[test]
+fn test_sessions_are_independent() {
+    let limiter = RateLimiter::new(60, 1);
+    let t0 = Instant::now();
+    assert!(limiter.check("runaway", t0).is_ok());
+    assert!(limiter.check("runaway", t0).is_err());
+    assert!(limiter.check("other", t0).is_ok());
+    assert_eq!(limiter.tracked_sessions(), 2);
+
+    assert!(limiter.check("third", t0 + Duration::from_secs(3600)).is_ok());
+    assert_eq!(limiter.tracked_sessions(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// 3. A zero rate disables limiting
+// ---------------------------------------------------------------------------
+#[test]
+fn test_disabled_limiter() {
+    let limiter = RateLimiter::new(0, 1);
+    assert!(!limiter.is_enabled());
+    let t0 = Instant::now();
+    for _ in 0..100 {
+        assert!(limiter.check("a", t0).is_ok());
+    }
+    assert_eq!(limiter.tracked_sessions(), 0);
+}
+
+// ---------------------------------------------------------------------------
+// 4. Tracked sessions are capped; the least recently used is evicted
+// ---------------------------------------------------------------------------
+#[test]
+fn test_tracked_sessions_capped() {
+    let limiter = RateLimiter::new(60, 1).with_max_sessions(3);
+    let t0 = Instant::now();
+    for i in 0..100u64 {
+        assert!(limiter.check(&format!("s{i}"), t0 + Duration::from_millis(i)).is_ok());
+        assert!(limiter.tracked_sessions() <= 3);
+    }
+    assert_eq!(limiter.tracked_sessions(), 3);
+
+    // "a" stays recently used, so fresh sessions evict "b" rather than it.
+    let t1 = t0 + Duration::from_secs(1);
+    assert!(limiter.check("a", t1).is_ok());
+    assert!(limiter.check("b", t1 + Duration::from_millis(1)).is_ok());
+    assert!(limiter.check("a", t1 + Duration::from_millis(2)).is_err());
+    assert!(limiter.check("c", t1 + Duration::from_millis(3)).is_ok());
+    assert!(limiter.check("d", t1 + Duration::from_millis(4)).is_ok());
+    assert!(limiter.check("a", t1 + Duration::from_millis(5)).is_err());
+    assert_eq!(limiter.tracked_sessions(), 3);
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::Path;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+fn write(root: &Path, rel_path: &str, func: &str) {
+    let path = root.join(rel_path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("package p\n\nfunc {func}() {{}}\n")).unwrap();
+}
+
+fn has(db: &Database, name: &str) -> bool {
+    db.query_symbols_full(name, "", "", "", "").unwrap().iter().any(|s| s.name == name)
+}
+
+fn globs(globs: &[&str]) -> Vec<String> {
+    globs.iter().map(|g| g.to_string()).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Only matching files are re-parsed; new matches are indexed and deleted
+//    ones removed
+// ---------------------------------------------------------------------------
+#[test]
+fn test_reindex_paths() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "api/orders.go", "CreateOrder");
+    write(dir.path(), "api/v2/refunds.go", "CreateRefund");
+    write(dir.path(), "api/stale.go", "Stale");
+    write(dir.path(), "web/handler.go", "Handle");
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+
+    write(dir.path(), "api/orders.go", "PlaceOrder");
+    write(dir.path(), "api/v2/new.go", "Fresh");
+    fs::remove_file(dir.path().join("api/stale.go")).unwrap();
+    write(dir.path(), "web/handler.go", "Serve");
+
+    let stats = indexer.reindex_paths(dir.path(), &globs(&["/api/"])).unwrap();
+    assert_eq!((stats.files_indexed, stats.files_skipped, stats.files_removed), (2, 1, 1));
+    assert!(has(&db, "PlaceOrder") && !has(&db, "CreateOrder"));
+    assert!(has(&db, "Fresh") && has(&db, "CreateRefund"));
+    assert!(!has(&db, "Stale"));
+    assert!(has(&db, "Handle") && !has(&db, "Serve"), "files outside the globs are left alone");
+
+    let stats = indexer.reindex_paths(dir.path(), &globs(&["handler.go"])).unwrap();
+    assert_eq!(stats.files_indexed, 1, "an unanchored glob matches at any depth");
+    assert!(has(&db, "Serve"));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Wildcards stay within their segment, and an unindexed root is refused
+// ---------------------------------------------------------------------------
+#[test]
+fn test_reindex_paths_wildcards() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "api/orders.go", "CreateOrder");
+    write(dir.path(), "api/v2/refunds.go", "CreateRefund");
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    assert!(indexer.reindex_paths(dir.path(), &globs(&["api/"])).is_err());
+    indexer.index_directory(dir.path()).unwrap();
+
+    write(dir.path(), "api/orders.go", "PlaceOrder");
+    write(dir.path(), "api/v2/refunds.go", "IssueRefund");
+    let stats = indexer.reindex_paths(dir.path(), &globs(&["api/*.go"])).unwrap();
+    assert_eq!(stats.files_indexed, 1);
+    assert!(has(&db, "PlaceOrder") && has(&db, "CreateRefund"));
+
+    indexer.reindex_paths(dir.path(), &globs(&["api/**/*.go"])).unwrap();
+    assert!(has(&db, "IssueRefund"));
+    assert!(indexer.reindex_paths(dir.path(), &globs(&[" ", "/"])).is_err(), "empty globs are refused");
+}
+
+// ---------------------------------------------------------------------------
+// 3. Globs can't leave the root, by `..` or through a symlinked directory
+// ---------------------------------------------------------------------------
+#[test]
+fn test_reindex_paths_stays_in_root() {
+    let dir = TempDir::new().unwrap();
+    let outside = TempDir::new().unwrap();
+    write(dir.path(), "api/orders.go", "CreateOrder");
+    write(outside.path(), "other/leak.go", "Leak");
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    indexer.index_directory(dir.path()).unwrap();
+
+    let escape = format!("../{}/other/", outside.path().file_name().unwrap().to_string_lossy());
+    assert!(indexer.reindex_paths(dir.path(), &globs(&[&escape])).is_err());
+    assert!(indexer.reindex_paths(dir.path(), &globs(&["api/../../"])).is_err());
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(outside.path().join("other"), dir.path().join("linked")).unwrap();
+        assert!(indexer.reindex_paths(dir.path(), &globs(&["/linked/"])).is_err());
+    }
+    assert!(!has(&db, "Leak"));
+}
@@ -0,0 +1,284 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+use focal_core::builtins::BuiltinFilter;
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::reindex_scheduler::{
+    describe_batch, BatchReport, HotFiles, IndexerTarget, ReindexScheduler, ReindexTarget, BATCH_SIZE,
+};
+use tempfile::TempDir;
+
+/// In-memory filesystem that records every operation in order.
+#[derive(Default)]
+struct MockFs {
+    files: HashSet<PathBuf>,
+    failing: HashSet<PathBuf>,
+    log: Vec<String>,
+}
+
+impl MockFs {
+    fn with_files(files: &[&str]) -> Self {
+        Self { files: files.iter().map(PathBuf::from).collect(), ..Default::default() }
+    }
+}
+
+impl ReindexTarget for MockFs {
+    fn sync_root(&mut self, root: &Path) -> Result<()> {
+        self.log.push(format!("sync {}", root.display()));
+        Ok(())
+    }
+
+    fn rescan_root(&mut self, root: &Path) -> Result<Vec<String>> {
+        self.log.push(format!("rescan {}", root.display()));
+        Ok(Vec::new())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains(path)
+    }
+
+    fn reindex_file(&mut self, path: &Path, _root: &Path) -> Result<bool> {
+        if self.failing.contains(path) {
+            bail!("parse failed");
+        }
+        self.log.push(format!("index {}", path.display()));
+        Ok(true)
+    }
+
+    fn remove_file(&mut self, path: &Path, _root: &Path) -> Result<bool> {
+        self.log.push(format!("remove {}", path.display()));
+        Ok(true)
+    }
+
+    fn begin_batch(&mut self) -> Result<()> {
+        self.log.push("begin".to_string());
+        Ok(())
+    }
+
+    fn end_batch(&mut self, batch: &BatchReport) -> Result<()> {
+        self.log.push(format!("end {}+{}", batch.reindexed.len(), batch.removed.len()));
+        Ok(())
+    }
+}
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Duplicates coalesce and paths outside the roots are ignored
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_enqueue_coalesces_duplicates() {
+    let mut scheduler = ReindexScheduler::new(paths(&["/repo"]), 100);
+    assert_eq!(scheduler.enqueue(paths(&["/repo/a.rs", "/repo/b.rs", "/repo/a.rs", "/elsewhere/c.rs"])), 2);
+    assert_eq!(scheduler.enqueue(paths(&["/repo/b.rs"])), 0);
+    assert_eq!(scheduler.len(), 2);
+
+    assert_eq!(scheduler.next_batch(10), paths(&["/repo/a.rs", "/repo/b.rs"]));
+    assert!(scheduler.is_empty());
+    // Once taken, a path can be queued again.
+    assert_eq!(scheduler.enqueue(paths(&["/repo/a.rs"])), 1);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Recently read files are processed first, most recent first
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_hot_files_jump_the_queue() {
+    let hot = HotFiles::new();
+    let mut scheduler = ReindexScheduler::new(paths(&["/repo"]), 100).with_hot_files(hot.clone());
+    scheduler.enqueue(paths(&["/repo/a.rs", "/repo/b.rs", "/repo/c.rs", "/repo/d.rs"]));
+    hot.touch(paths(&["/repo/c.rs", "/repo/b.rs"]));
+
+    assert_eq!(scheduler.next_batch(3), paths(&["/repo/b.rs", "/repo/c.rs", "/repo/a.rs"]));
+    assert_eq!(scheduler.next_batch(3), paths(&["/repo/d.rs"]));
+}
+
+// ---------------------------------------------------------------------------
+// 3. Overflowing the queue turns a root's changes into one rescan
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_overflow_falls_back_to_rescan() {
+    let mut scheduler = ReindexScheduler::new(paths(&["/big", "/small"]), 3);
+    scheduler.enqueue(paths(&["/small/x.rs", "/big/1.rs", "/big/2.rs", "/big/3.rs", "/big/4.rs"]));
+
+    assert_eq!(scheduler.pending_rescans(), paths(&["/big"]));
+    assert_eq!(scheduler.dropped(), 1);
+    // Later changes under the root are covered by the pending rescan.
+    assert_eq!(scheduler.enqueue(paths(&["/big/5.rs"])), 0);
+
+    let mut fs = MockFs::with_files(&["/small/x.rs"]);
+    let stats = scheduler.run(&mut fs);
+    assert_eq!(fs.log, vec!["sync /big", "sync /small", "rescan /big", "begin", "index /small/x.rs", "end 1+0"]);
+    assert_eq!((stats.rescanned, stats.reindexed), (1, 1));
+    assert!(scheduler.is_empty() && scheduler.pending_rescans().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 4. A run syncs roots first, removes deleted files and collects errors
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_run_reindexes_and_removes() {
+    let mut scheduler = ReindexScheduler::new(paths(&["/repo"]), 10 * BATCH_SIZE);
+    let many: Vec<PathBuf> = (0..BATCH_SIZE + 5).map(|i| PathBuf::from(format!("/repo/f{i}.rs"))).collect();
+    scheduler.enqueue(many.clone());
+    scheduler.enqueue(paths(&["/repo/gone.rs", "/repo/broken.rs"]));
+
+    let mut fs = MockFs::default();
+    fs.files.extend(many);
+    fs.files.insert(PathBuf::from("/repo/broken.rs"));
+    fs.failing.insert(PathBuf::from("/repo/broken.rs"));
+
+    let stats = scheduler.run(&mut fs);
+    assert_eq!(fs.log[0], "sync /repo");
+    assert_eq!(fs.log.iter().filter(|l| l.starts_with("sync")).count(), 1);
+    assert!(fs.log.contains(&"remove /repo/gone.rs".to_string()));
+    assert_eq!((stats.reindexed, stats.removed), (BATCH_SIZE + 5, 1));
+    assert_eq!(stats.errors.len(), 1);
+    assert!(stats.errors[0].contains("broken.rs"), "{:?}", stats.errors);
+    assert!(scheduler.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 5. Files are applied in bounded batches, each bracketed by begin/end
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_run_brackets_bounded_batches() {
+    let mut scheduler = ReindexScheduler::new(paths(&["/repo"]), 100).with_batch_size(2);
+    scheduler.enqueue(paths(&["/repo/a.rs", "/repo/b.rs", "/repo/c.rs", "/repo/gone.rs"]));
+    let mut fs = MockFs::with_files(&["/repo/a.rs", "/repo/b.rs", "/repo/c.rs"]);
+
+    let stats = scheduler.run(&mut fs);
+    assert_eq!(
+        fs.log,
+        vec![
+            "sync /repo", "begin", "index /repo/a.rs", "index /repo/b.rs", "end 2+0",
+            "begin", "index /repo/c.rs", "remove /repo/gone.rs", "end 1+1",
+        ]
+    );
+    assert_eq!((stats.batches, stats.reindexed, stats.removed), (2, 3, 1));
+}
+
+// ---------------------------------------------------------------------------
+// 6. A watcher batch writes changed files, skips unchanged ones and records
+//    one observation
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_indexer_target_batch_observation() {
+    let dir = TempDir::new().unwrap();
+    let registry = GrammarRegistry::new();
+    for name in ["a", "b", "c"] {
+        fs::write(dir.path().join(format!("{name}.go")), format!("package main\n\nfunc Fn{name}() {{}}\n")).unwrap();
+    }
+    let db = Database::open_in_memory().unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let db = Mutex::new(db);
+
+    fs::write(root.join("a.go"), "package main\n\nfunc Fna() {}\n\nfunc Fnd() {}\n").unwrap();
+    fs::remove_file(root.join("b.go")).unwrap();
+    let mut scheduler = ReindexScheduler::new(vec![root.clone()], 100);
+    scheduler.enqueue(vec![root.join("a.go"), root.join("b.go"), root.join("c.go")]);
+    let mut target = IndexerTarget::new(&db, &registry, BuiltinFilter::default()).with_session_id("watcher-1");
+    let stats = scheduler.run(&mut target);
+    drop(target);
+    assert_eq!((stats.batches, stats.reindexed, stats.removed), (1, 1, 1), "{:?}", stats.errors);
+
+    let db = db.into_inner().unwrap();
+    assert!(db.find_symbol_by_name_any("Fnd").unwrap().is_some());
+    assert!(db.find_symbol_by_name_any("Fnb").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("Fnc").unwrap().is_some(), "unchanged files are left as they were");
+    let observations = db.list_memories("observation", false, false, "").unwrap();
+    assert_eq!(observations.len(), 1);
+    assert_eq!(observations[0].source, "auto:watcher");
+    assert_eq!(observations[0].session_id, "watcher-1");
+    assert_eq!(observations[0].content, "Re-indexed 1 file (a.go); Removed 1 file (b.go)");
+}
+
+#[test]
+fn test_describe_batch_truncates_paths() {
+    let batch = BatchReport {
+        reindexed: (0..7).map(|i| PathBuf::from(format!("/repo/f{i}.rs"))).collect(),
+        removed: Vec::new(),
+        errors: 2,
+    };
+    assert_eq!(
+        describe_batch(&batch),
+        "Re-indexed 7 files (f0.rs, f1.rs, f2.rs, f3.rs, f4.rs, +2 more); 2 failed"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 7. Roots are branch-synced again only after a checkout rewrites HEAD
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_sync_only_when_head_changes() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().to_path_buf();
+    fs::create_dir(root.join(".git")).unwrap();
+    let head = root.join(".git").join("HEAD");
+    fs::write(&head, "ref: refs/heads/main\n").unwrap();
+    let mut scheduler = ReindexScheduler::new(vec![root.clone()], 100);
+    let mut fs_mock = MockFs::default();
+    let syncs = |fs_mock: &MockFs| fs_mock.log.iter().filter(|l| l.starts_with("sync")).count();
+
+    scheduler.enqueue(vec![root.join("a.rs")]);
+    scheduler.run(&mut fs_mock);
+    scheduler.enqueue(vec![root.join("b.rs")]);
+    scheduler.run(&mut fs_mock);
+    assert_eq!(syncs(&fs_mock), 1, "unchanged HEAD: {:?}", fs_mock.log);
+
+    fs::write(&head, "ref: refs/heads/feature\n").unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+    fs::File::options().write(true).open(&head).unwrap().set_modified(later).unwrap();
+    scheduler.enqueue(vec![root.join("a.rs")]);
+    scheduler.run(&mut fs_mock);
+    assert_eq!(syncs(&fs_mock), 2, "checkout: {:?}", fs_mock.log);
+}
+
+// ---------------------------------------------------------------------------
+// 8. An overflow rescan re-indexes changed files and removes deleted ones
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_indexer_target_rescan() {
+    let dir = TempDir::new().unwrap();
+    let registry = GrammarRegistry::new();
+    for name in ["a", "b", "c"] {
+        fs::write(dir.path().join(format!("{name}.go")), format!("package main\n\nfunc Fn{name}() {{}}\n")).unwrap();
+    }
+    let db = Database::open_in_memory().unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let db = Mutex::new(db);
+
+    fs::write(root.join("a.go"), "package main\n\nfunc Fnd() {}\n").unwrap();
+    fs::remove_file(root.join("b.go")).unwrap();
+    let mut scheduler = ReindexScheduler::new(vec![root.clone()], 1);
+    scheduler.enqueue(vec![root.join("a.go"), root.join("b.go")]);
+    assert_eq!(scheduler.pending_rescans(), vec![root.clone()]);
+    let mut target = IndexerTarget::new(&db, &registry, BuiltinFilter::default());
+    let stats = scheduler.run(&mut target);
+    drop(target);
+    assert_eq!((stats.rescanned, stats.batches), (1, 0));
+    assert!(stats.errors.is_empty(), "{:?}", stats.errors);
+
+    let db = db.into_inner().unwrap();
+    assert!(db.find_symbol_by_name_any("Fnd").unwrap().is_some());
+    assert!(db.find_symbol_by_name_any("Fna").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("Fnb").unwrap().is_none());
+    assert!(db.find_symbol_by_name_any("Fnc").unwrap().is_some());
+}
@@ -0,0 +1,129 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::rename::plan_rename;
+use tempfile::TempDir;
+
+fn index_repo() -> (TempDir, Database) {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("orders.go"),
+        "package orders\n\n\
+         // CreateOrder places an order.\n\
+         func CreateOrder(id int) int {\n\treturn id\n}\n\n\
+         func PlaceOrder() int {\n\treturn CreateOrder(1) + CreateOrder(2)\n}\n\n\
+         func Describe() string {\n\treturn \"CreateOrder is idempotent\"\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("handler.go"),
+        "package orders\n\nfunc Handle() int {\n\treturn CreateOrder(4)\n}\n\nfunc SubmitOrder() {}\n",
+    )
+    .unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    (dir, db)
+}
+
+// ---------------------------------------------------------------------------
+// 1. References, affected files, unconfirmed mentions
+// ---------------------------------------------------------------------------
+#[test]
+fn test_plan_rename_references() {
+    let (_dir, db) = index_repo();
+    let sym = db.symbols_named(None, "CreateOrder").unwrap().remove(0);
+    let plan = plan_rename(&db, &sym, "PlaceNewOrder", 500).unwrap();
+
+    let sites: Vec<(&str, i64, usize, &str)> = plan
+        .references
+        .iter()
+        .map(|l| (l.file_path.as_str(), l.line, l.column, l.kind.as_str()))
+        .collect();
+    assert_eq!(
+        sites,
+        [
+            ("orders.go", 4, 6, "definition"),
+            ("handler.go", 4, 9, "calls"),
+            ("orders.go", 9, 9, "calls"),
+            ("orders.go", 9, 26, "calls"),
+        ]
+    );
+    assert_eq!(plan.references[1].text, "return CreateOrder(4)");
+    assert_eq!(plan.references[2].in_symbol, "PlaceOrder");
+
+    let files: Vec<(&str, usize)> = plan.files.iter().map(|f| (f.file_path.as_str(), f.references)).collect();
+    assert_eq!(files, [("handler.go", 1), ("orders.go", 3)]);
+
+    let mentions: Vec<(&str, i64)> = plan.unconfirmed.iter().map(|l| (l.in_symbol.as_str(), l.line)).collect();
+    assert_eq!(mentions, [("Describe", 13)], "a string mention has no edge");
+
+    let capped = plan_rename(&db, &sym, "PlaceNewOrder", 2).unwrap();
+    assert_eq!((capped.references.len(), capped.references_omitted, capped.files.len()), (2, 2, 2));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Collisions, memories and invalid names
+// ---------------------------------------------------------------------------
+#[test]
+fn test_plan_rename_collisions_and_memories() {
+    let (_dir, db) = index_repo();
+    let sym = db.symbols_named(None, "CreateOrder").unwrap().remove(0);
+    let linked = db.save_memory("orders are soft-deleted", "decision", &[sym.id]).unwrap();
+    let mentioned = db.save_memory("never call CreateOrder twice per cart", "gotcha", &[]).unwrap();
+    db.save_memory("CreateOrderRequest is generated", "note", &[]).unwrap();
+
+    let plan = plan_rename(&db, &sym, "SubmitOrder", 500).unwrap();
+    let collisions: Vec<(&str, &str)> = plan.collisions.iter().map(|c| (c.file_path.as_str(), c.scope)).collect();
+    assert_eq!(collisions, [("handler.go", "referencing_file")]);
+    assert_eq!(plan_rename(&db, &sym, "Describe", 500).unwrap().collisions[0].scope, "same_file");
+
+    let memories: Vec<(i64, bool, bool)> = plan.memories.iter().map(|m| (m.id, m.linked, m.mentions)).collect();
+    assert_eq!(memories, [(linked, true, false), (mentioned, false, true)], "only whole-word mentions count");
+
+    assert!(plan_rename(&db, &sym, "Create-Order", 500).is_err());
+    assert!(plan_rename(&db, &sym, "CreateOrder", 500).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Methods: the last segment is scanned, renamed and checked for clashes
+// ---------------------------------------------------------------------------
+#[test]
+fn test_plan_rename_method() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("server.go"),
+        "package srv\n\ntype Server struct{}\n\n\
+         func (s *Server) Start() {}\n\n\
+         func (s *Server) Run() {}\n\n\
+         type Client struct{}\n\n\
+         func (c *Client) Start() {}\n\n\
+         func Boot(s *Server) {\n\ts.Start()\n}\n",
+    )
+    .unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    let named: Vec<String> = db.symbols_named(None, "Start").unwrap().into_iter().map(|s| s.name).collect();
+    assert_eq!(named, ["Server.Start", "Client.Start"]);
+    let sym = db.symbols_named(None, "Server.Start").unwrap().remove(0);
+
+    let plan = plan_rename(&db, &sym, "Stop", 500).unwrap();
+    assert_eq!(plan.new_name, "Server.Stop");
+    let sites: Vec<(i64, usize)> =
+        plan.references.iter().chain(&plan.unconfirmed).map(|l| (l.line, l.column)).collect();
+    assert!(sites.contains(&(5, 18)), "definition: {sites:?}");
+    assert!(sites.contains(&(14, 4)), "call site: {sites:?}");
+    assert!(plan.collisions.is_empty(), "Client.Start has another qualifier");
+    assert_eq!(plan_rename(&db, &sym, "Server.Stop", 500).unwrap().new_name, "Server.Stop");
+
+    let clash = plan_rename(&db, &sym, "Run", 500).unwrap();
+    let collisions: Vec<&str> = clash.collisions.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(collisions, ["Server.Run"]);
+
+    assert!(plan_rename(&db, &sym, "Client.Stop", 500).is_err(), "the qualifier can't change");
+    assert!(plan_rename(&db, &sym, "Start", 500).is_err());
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+/// Two checkouts whose directories are both called `api`.
+fn write_roots(dir: &TempDir) -> (PathBuf, PathBuf) {
+    let work = dir.path().join("work/api");
+    let oss = dir.path().join("oss/api");
+    for root in [&work, &oss] {
+        fs::create_dir_all(root).unwrap();
+    }
+    fs::write(work.join("billing.go"), "package api\n\nfunc ChargeCard() {}\n").unwrap();
+    fs::write(oss.join("client.go"), "package api\n\nfunc NewClient() {}\n").unwrap();
+    (work, oss)
+}
+
+fn canonical(root: &Path) -> String {
+    focal_core::paths::canonicalize(root).unwrap().to_string_lossy().to_string()
+}
+
+fn index(db: &Database, roots: &[&Path]) {
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(db, &registry);
+    for root in roots {
+        indexer.index_directory(root).unwrap();
+    }
+}
+
+fn names(db: &Database) -> Vec<String> {
+    db.list_repositories().unwrap().into_iter().map(|r| r.name).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Configured names replace colliding directory basenames
+// ---------------------------------------------------------------------------
+#[test]
+fn test_configured_names() {
+    let dir = TempDir::new().unwrap();
+    let (work, oss) = write_roots(&dir);
+
+    let basenames = Database::open_in_memory().unwrap();
+    index(&basenames, &[&work, &oss]);
+    assert_eq!(names(&basenames), ["api", "api"]);
+
+    let db = Database::open_in_memory().unwrap();
+    db.sync_repo_names(&BTreeMap::from([(canonical(&oss), "oss-api".to_string())])).unwrap();
+    index(&db, &[&work, &oss]);
+    assert_eq!(names(&db), ["api", "oss-api"]);
+
+    // Reindexing keeps the configured name.
+    index(&db, &[&oss]);
+    assert_eq!(names(&db), ["api", "oss-api"]);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Renaming keeps ids and memory links; the old name stays an alias
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rename_keeps_links() {
+    let dir = TempDir::new().unwrap();
+    let (work, _) = write_roots(&dir);
+    let db = Database::open_in_memory().unwrap();
+    index(&db, &[&work]);
+    let repo_id = db.get_repo_id_by_name("api").unwrap().unwrap();
+    let charge = db.find_symbol_by_name_any("ChargeCard").unwrap().unwrap();
+    db.save_memory("Charges go through the card vault", "decision", &[charge.id]).unwrap();
+
+    db.rename_repository("api", "work-api").unwrap();
+    assert_eq!(names(&db), ["work-api"]);
+    assert_eq!(db.get_repo_id_by_name("work-api").unwrap(), Some(repo_id));
+    assert_eq!(db.get_repo_id_by_name("api").unwrap(), Some(repo_id), "old name is an alias");
+    assert_eq!(db.current_repo_name("api").unwrap(), "work-api");
+    assert_eq!(db.query_symbols_full("ChargeCard", "", "api", "", "").unwrap().len(), 1);
+
+    index(&db, &[&work]);
+    assert_eq!(names(&db), ["work-api"], "reindexing keeps the new name");
+    let charge_after = db.find_symbol_by_name_any("ChargeCard").unwrap().unwrap();
+    assert_eq!(charge_after.id, charge.id);
+    assert_eq!(db.get_memories_for_symbol(charge.id, false).unwrap().len(), 1);
+
+    assert!(db.rename_repository("missing", "x").is_err());
+    assert!(db.rename_repository("work-api", " ").is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Config renames indexed repositories; names must stay unique
+// ---------------------------------------------------------------------------
+#[test]
+fn test_sync_renames_and_projects_follow() {
+    let dir = TempDir::new().unwrap();
+    let (work, oss) = write_roots(&dir);
+    let db = Database::open_in_memory().unwrap();
+    db.sync_repo_names(&BTreeMap::from([(canonical(&oss), "oss-api".to_string())])).unwrap();
+    index(&db, &[&work, &oss]);
+    db.sync_projects(&BTreeMap::from([("billing".to_string(), vec!["api".to_string()])])).unwrap();
+
+    db.sync_repo_names(&BTreeMap::from([
+        (canonical(&work), "work-api".to_string()),
+        (canonical(&oss), "oss-api".to_string()),
+    ]))
+    .unwrap();
+    assert_eq!(names(&db), ["oss-api", "work-api"]);
+    let members = db.project_repositories("billing").unwrap().unwrap();
+    assert_eq!(members[0].name, "work-api");
+
+    // A project listing the old name still finds the repository.
+    db.sync_projects(&BTreeMap::from([("billing".to_string(), vec!["api".to_string()])])).unwrap();
+    assert_eq!(db.project_repositories("billing").unwrap().unwrap().len(), 1);
+
+    assert!(db.rename_repository("work-api", "oss-api").is_err());
+
+    // Swapping two names in the config applies in one pass; memberships
+    // stay with their repository.
+    db.sync_repo_names(&BTreeMap::from([
+        (canonical(&work), "oss-api".to_string()),
+        (canonical(&oss), "work-api".to_string()),
+    ]))
+    .unwrap();
+    let root_of = |name: &str| db.list_repositories().unwrap().into_iter().find(|r| r.name == name).unwrap().root_path;
+    assert_eq!(root_of("oss-api"), canonical(&work));
+    assert_eq!(root_of("work-api"), canonical(&oss));
+    let members = db.project_repositories("billing").unwrap().unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].name, "oss-api");
+}
@@ -0,0 +1,50 @@
+use std::fs;
+use std::time::Duration;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::report::{read_report, write_report, IndexReport};
+use tempfile::TempDir;
+
+// ---------------------------------------------------------------------------
+// 1. Report totals the run and round-trips through last_index.json
+// ---------------------------------------------------------------------------
+#[test]
+fn test_report_round_trip() {
+    let repo = TempDir::new().unwrap();
+    fs::write(repo.path().join("main.go"), "package main\n\nfunc A() { B() }\n\nfunc B() {}\n").unwrap();
+    fs::write(repo.path().join("broken.py"), "def ok():\n    pass\n\ndef broken(:\n").unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let stats = Indexer::new(&db, &registry).index_directory(repo.path()).unwrap();
+
+    let mut report = IndexReport::default();
+    report.add(repo.path(), &stats);
+    report.add_failure(&repo.path().join("gone"), &anyhow::anyhow!("no such directory"));
+    report.finish(Duration::from_millis(42));
+    assert_eq!(report.roots.len(), 2);
+    assert_eq!((report.files_indexed, report.symbols, report.edges), (2, stats.symbols_extracted, 1));
+    assert_eq!(report.duration_ms, 42);
+    assert!(report.finished_at > 0);
+    assert!(report.errors.iter().any(|e| e.ends_with("gone: no such directory")), "{:?}", report.errors);
+
+    let out = TempDir::new().unwrap();
+    let path = out.path().join("last_index.json");
+    assert_eq!(read_report(&path).unwrap(), None);
+    write_report(&path, &report).unwrap();
+    assert_eq!(read_report(&path).unwrap(), Some(report));
+
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["files_indexed"], 2);
+    assert_eq!(json["edges"], 1);
+
+    // Rewrites replace the file whole and leave no temporary behind.
+    let mut rerun = read_report(&path).unwrap().unwrap();
+    rerun.files_indexed = 7;
+    write_report(&path, &rerun).unwrap();
+    assert_eq!(read_report(&path).unwrap().unwrap().files_indexed, 7);
+    let names: Vec<_> = fs::read_dir(out.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+    assert_eq!(names, vec![std::ffi::OsString::from("last_index.json")]);
+}
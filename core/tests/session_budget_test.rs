@@ -0,0 +1,56 @@
+use focal_core::session_budget::{SessionBudget, MIN_SHRUNK_BUDGET};
+
+// ---------------------------------------------------------------------------
+// 1. Usage is totalled per tool and reported heaviest first
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_report_totals_per_tool() {
+    let mut budget = SessionBudget::new(None);
+    budget.record("get_context", 3_000);
+    budget.record("query_symbol", 200);
+    budget.record("get_context", 2_000);
+
+    let report = budget.report("session-1");
+    assert_eq!(report.total_tokens, 5_200);
+    assert_eq!(report.calls, 3);
+    assert_eq!(report.by_tool[0].0, "get_context");
+    assert_eq!(report.by_tool[0].1.calls, 2);
+    assert_eq!(report.by_tool[0].1.tokens, 5_000);
+    assert_eq!(report.by_tool[1].0, "query_symbol");
+    assert!(report.soft_cap.is_none() && report.remaining.is_none());
+    assert_eq!(report.context_budget_factor, 1.0);
+}
+
+// ---------------------------------------------------------------------------
+// 2. Without a cap, budgets are never shrunk
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_no_cap_keeps_budget() {
+    let mut budget = SessionBudget::new(None);
+    budget.record("get_context", 1_000_000);
+    assert_eq!(budget.shrink(12_000), 12_000);
+    // A zero cap is treated as no cap.
+    assert_eq!(SessionBudget::new(Some(0)).shrink(12_000), 12_000);
+}
+
+// ---------------------------------------------------------------------------
+// 3. With a cap, budgets shrink as the session grows, down to a floor
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_soft_cap_shrinks_budget() {
+    let mut budget = SessionBudget::new(Some(100_000));
+    assert_eq!(budget.shrink(12_000), 12_000);
+
+    budget.record("get_context", 50_000);
+    assert_eq!(budget.shrink(12_000), 6_000);
+    assert_eq!(budget.report("s").remaining, Some(50_000));
+
+    budget.record("search_code", 60_000);
+    assert_eq!(budget.shrink(12_000), MIN_SHRUNK_BUDGET);
+    assert_eq!(budget.report("s").remaining, Some(0));
+    // Small requests are never raised to the floor.
+    assert_eq!(budget.shrink(500), 500);
+}
@@ -164,3 +164,42 @@ fn test_get_skeleton_detail_levels_are_equivalent() {
         assert_eq!(minimal[i].signature, standard[i].signature);
     }
 }
+
+// ---------------------------------------------------------------------------
+// 6. Path resolution — absolute, ./-prefixed, and Windows-separated inputs
+// ---------------------------------------------------------------------------
+#[test]
+fn test_resolve_file_path_variants() {
+    let (db, repo_id, file_id) = setup_db_with_symbols();
+    // A second file whose path also ends in "handler.rs"
+    db.upsert_file(repo_id, "src/api/handler.rs", "rust", "bbb222")
+        .unwrap();
+
+    for input in [
+        "src/handler.rs",
+        "./src/handler.rs",
+        "src\\handler.rs",
+        "/tmp/test-repo/src/handler.rs",
+        "/tmp/test-repo/./src//handler.rs",
+    ] {
+        let resolved = db
+            .resolve_file_path(input, None)
+            .unwrap()
+            .unwrap_or_else(|| panic!("{input} should resolve"));
+        assert_eq!(resolved.file_id, file_id, "input: {input}");
+        assert_eq!(resolved.file_path, "src/handler.rs");
+        assert_eq!(resolved.repo, "test-repo");
+    }
+
+    // Suffix match must align on a path segment: "api/handler.rs" only
+    // matches src/api/handler.rs, "andler.rs" matches nothing.
+    let api = db.resolve_file_path("api/handler.rs", None).unwrap().unwrap();
+    assert_eq!(api.file_path, "src/api/handler.rs");
+    assert!(db.resolve_file_path("andler.rs", None).unwrap().is_none());
+
+    // Absolute paths outside every repo root never fall back to suffix matching.
+    assert!(db
+        .resolve_file_path("/elsewhere/src/handler.rs", None)
+        .unwrap()
+        .is_none());
+}
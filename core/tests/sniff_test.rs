@@ -0,0 +1,75 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::sniff::{sniff, Content};
+use tempfile::TempDir;
+
+/// A bundle-style source: `count` small functions on one line.
+fn minified(count: usize) -> String {
+    let mut out = String::from("\"use strict\";");
+    for i in 0..count {
+        out.push_str(&format!("function f{i}(a,b){{return a*{i}+b}}"));
+    }
+    out.push_str("\n//# debugId=1\n");
+    out
+}
+
+// ---------------------------------------------------------------------------
+// 1. Classification
+// ---------------------------------------------------------------------------
+#[test]
+fn test_sniff() {
+    let source = "export function total(items) {\n  return items.length;\n}\n".repeat(100);
+    assert_eq!(sniff(source.as_bytes()), Content::Text);
+    assert_eq!(sniff(minified(100).as_bytes()), Content::Minified);
+    assert_eq!(sniff(b"const x = 1;"), Content::Text, "short files are never minified");
+
+    let sql = format!("const QUERY = \"{}\";\n", "SELECT id, name FROM users WHERE id = ? AND ".repeat(80));
+    assert_eq!(sniff(sql.as_bytes()), Content::Text, "long but spaced-out lines are not minified");
+
+    let mut ts_stream = vec![0x47u8, 0x40, 0x00, 0x10];
+    ts_stream.extend(std::iter::repeat_n(0xffu8, 184));
+    assert_eq!(sniff(&ts_stream), Content::Binary);
+    assert_eq!(sniff(&[0x01, 0x02, 0x03, b'a', 0x04, 0x05, 0x06, 0x07, b'b', 0x08]), Content::Binary);
+
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend("let x = 1;\n".encode_utf16().flat_map(u16::to_le_bytes));
+    assert_eq!(sniff(&utf16), Content::Text, "UTF-16 with a BOM holds NULs but is text");
+}
+
+// ---------------------------------------------------------------------------
+// 2. Binary files are skipped, minified ones indexed without bodies
+// ---------------------------------------------------------------------------
+#[test]
+fn test_index_binary_and_minified() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("app.js"), "export function start() {\n  return 1;\n}\n").unwrap();
+    fs::write(dir.path().join("bundle.js"), minified(100)).unwrap();
+    fs::write(dir.path().join("clip.ts"), [0x47, 0x40, 0x00, 0x10, 0x00, 0x00, 0xb0, 0x0d]).unwrap();
+
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let stats = Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert_eq!((stats.files_indexed, stats.files_partial, stats.files_minified), (2, 1, 1));
+    assert_eq!((stats.files_skipped, stats.files_binary), (1, 1));
+
+    let f7 = db.query_symbols_full("f7", "", "", "", "").unwrap();
+    let f7 = f7.iter().find(|s| s.name == "f7").unwrap();
+    assert!(f7.body.is_empty(), "minified bodies are not stored");
+    assert!(!db.query_symbols_full("start", "", "", "", "").unwrap()[0].body.is_empty());
+
+    let root = dir.path().canonicalize().unwrap();
+    let repo_id = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id;
+    assert!(db.get_file_by_path(repo_id, "clip.ts").unwrap().is_none());
+
+    // A source file overwritten with binary content leaves the index as if
+    // deleted: the call reports a change and the file is tombstoned.
+    fs::write(dir.path().join("app.js"), [0u8; 64]).unwrap();
+    assert!(Indexer::new(&db, &registry).index_file(&dir.path().join("app.js"), dir.path()).unwrap());
+    assert!(db.get_file_by_path(repo_id, "app.js").unwrap().is_none());
+    assert!(db.query_symbols_full("start", "", "", "", "").unwrap().is_empty());
+    assert!(db.take_tombstone(repo_id, "app.js").unwrap().is_some());
+    assert!(!Indexer::new(&db, &registry).index_file(&dir.path().join("app.js"), dir.path()).unwrap());
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::stacktrace::{parse_stacktrace, resolve_stacktrace, short_name, Frame};
+use tempfile::TempDir;
+
+fn locations(frames: &[Frame]) -> Vec<(Option<&str>, &str, i64)> {
+    frames.iter().map(|f| (f.function.as_deref(), f.file.as_str(), f.line)).collect()
+}
+
+// ---------------------------------------------------------------------------
+// 1. Frame parsing per language, innermost first
+// ---------------------------------------------------------------------------
+#[test]
+fn test_parse_stacktrace_formats() {
+    let rust = "\
+thread 'main' panicked at src/server.rs:42:9:
+index out of bounds
+stack backtrace:
+   0: rust_begin_unwind
+             at /rustc/abc/library/std/src/panicking.rs:665:5
+   1: app::server::Server::handle::h0123456789abcdef
+             at ./src/server.rs:42:9
+   2: app::main
+             at ./src/main.rs:7:5
+";
+    assert_eq!(
+        locations(&parse_stacktrace(rust)),
+        [
+            (None, "src/server.rs", 42),
+            (Some("rust_begin_unwind"), "/rustc/abc/library/std/src/panicking.rs", 665),
+            (Some("app::server::Server::handle::h0123456789abcdef"), "./src/server.rs", 42),
+            (Some("app::main"), "./src/main.rs", 7),
+        ]
+    );
+
+    let go = "\
+panic: runtime error: invalid memory address
+
+goroutine 1 [running]:
+main.(*Server).handle(0xc000010000, 0x1)
+\t/app/server.go:42 +0x1d
+main.main()
+\t/app/main.go:9 +0x25
+";
+    assert_eq!(
+        locations(&parse_stacktrace(go)),
+        [(Some("main.(*Server).handle"), "/app/server.go", 42), (Some("main.main"), "/app/main.go", 9)]
+    );
+
+    let python = "\
+Traceback (most recent call last):
+  File \"/app/main.py\", line 9, in <module>
+    serve()
+  File \"/app/server.py\", line 42, in handle
+    raise ValueError(\"boom\")
+ValueError: boom
+";
+    assert_eq!(
+        locations(&parse_stacktrace(python)),
+        [(Some("handle"), "/app/server.py", 42), (Some("<module>"), "/app/main.py", 9)]
+    );
+
+    let js = "\
+TypeError: Cannot read properties of undefined
+    at Server.handle (/app/src/server.ts:42:9)
+    at async main (file:///app/src/main.ts:7:3)
+    at /app/src/index.js:3:1
+    at node:internal/main/run_main_module:28:49
+handle@/app/src/server.js:42:9
+";
+    assert_eq!(
+        locations(&parse_stacktrace(js)),
+        [
+            (Some("Server.handle"), "/app/src/server.ts", 42),
+            (Some("main"), "/app/src/main.ts", 7),
+            (None, "/app/src/index.js", 3),
+            (Some("handle"), "/app/src/server.js", 42),
+        ]
+    );
+
+    assert_eq!(short_name("app::server::Server::handle::h0123456789abcdef"), "handle");
+    assert_eq!(short_name("main.(*Server).handle"), "handle");
+    assert_eq!(short_name("Server.handle"), "handle");
+    assert_eq!(short_name("app::run::{{closure}}"), "run");
+}
+
+// ---------------------------------------------------------------------------
+// 2. Frames map to indexed symbols by path suffix and line, within budget
+// ---------------------------------------------------------------------------
+#[test]
+fn test_resolve_stacktrace() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("app")).unwrap();
+    fs::write(
+        dir.path().join("app/server.py"),
+        "def handle(req):\n    return parse(req)\n\n\ndef parse(req):\n    raise ValueError(req)\n",
+    )
+    .unwrap();
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    let root = fs::canonicalize(dir.path()).unwrap();
+
+    // Absolute paths resolve under their root, relative ones by their
+    // indexed suffix.
+    let trace = format!(
+        "\
+Traceback (most recent call last):
+  File \"build/app/server.py\", line 2, in handle
+  File \"{}/app/server.py\", line 6, in parse
+  File \"/usr/lib/python3.12/json/decoder.py\", line 337, in decode
+",
+        root.display()
+    );
+    let trace = trace.as_str();
+    let res = resolve_stacktrace(&db, trace, 10_000, &HashSet::new()).unwrap();
+    assert_eq!(res.frames.len(), 3);
+    assert_eq!(res.resolved, 2);
+    assert!(res.frames[0].symbol.is_none());
+    let parse = res.frames[1].symbol.as_ref().unwrap();
+    assert_eq!((parse.name.as_str(), parse.file_path.as_str()), ("parse", "app/server.py"));
+    assert!(parse.body.as_deref().unwrap().contains("raise ValueError"));
+    let handle = res.frames[2].symbol.as_ref().unwrap();
+    assert_eq!(handle.name, "handle");
+    assert!(handle.body.is_some());
+
+    // A tiny budget keeps signatures but drops bodies.
+    let res = resolve_stacktrace(&db, trace, 1, &HashSet::new()).unwrap();
+    let parse = res.frames[1].symbol.as_ref().unwrap();
+    assert!(parse.body.is_none());
+    assert_eq!(parse.note.as_deref(), Some("body omitted: token budget exhausted"));
+
+    // Absolute paths outside every indexed root never match by suffix, even
+    // when the function name agrees.
+    let trace = "\
+Traceback (most recent call last):
+  File \"/home/me/.venv/lib/python3.12/site-packages/app/server.py\", line 6, in parse
+";
+    let res = resolve_stacktrace(&db, trace, 10_000, &HashSet::new()).unwrap();
+    assert_eq!(res.resolved, 0);
+}
@@ -0,0 +1,167 @@
+use std::fs;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use focal_core::test_map::{is_test_file, name_candidates};
+use tempfile::TempDir;
+
+fn links(entries: &[focal_core::db::TestLinkEntry]) -> Vec<(String, String, String)> {
+    let mut out: Vec<_> = entries
+        .iter()
+        .map(|e| (e.test.clone(), e.symbol.clone(), e.via.clone()))
+        .collect();
+    out.sort();
+    out
+}
+
+// ---------------------------------------------------------------------------
+// 1. Naming heuristics and test-file detection
+// ---------------------------------------------------------------------------
+#[test]
+fn test_name_candidates() {
+    assert_eq!(
+        name_candidates("test_parse_header_rejects_empty"),
+        vec!["parseheaderrejectsempty", "parseheaderrejects", "parseheader", "parse"]
+    );
+    assert_eq!(name_candidates("TestParseHeader"), vec!["parseheader", "parse"]);
+    assert_eq!(name_candidates("BenchmarkServeHTTP"), vec!["servehttp", "serve"]);
+    assert_eq!(name_candidates("parser_test"), vec!["parser"]);
+
+    assert!(is_test_file("pkg/server/handler_test.go"));
+    assert!(is_test_file("tests/test_parser.py"));
+    assert!(is_test_file("src/parser.test.ts"));
+    assert!(is_test_file("src/__tests__/parser.ts"));
+    assert!(is_test_file("tests/common/mod.rs"));
+    assert!(!is_test_file("src/testing.py"));
+    assert!(!is_test_file("pkg/server/handler.go"));
+}
+
+// ---------------------------------------------------------------------------
+// 2. Rust integration tests link by call and by name
+// ---------------------------------------------------------------------------
+#[test]
+fn test_rust_test_links() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::create_dir_all(dir.path().join("tests")).unwrap();
+    fs::write(
+        dir.path().join("src/lib.rs"),
+        "pub fn parse_header(s: &str) -> usize {\n    normalize(s).len()\n}\n\npub fn normalize(s: &str) -> String {\n    s.trim().to_string()\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("tests/header_test.rs"),
+        r#"fn fixture() -> &'static str {
+    " x "
+}
+
+#[test]
+fn test_parse_header_trims() {
+    let n = parse_header(fixture());
+    assert_eq!(n, 1);
+}
+
+#[test]
+fn trimmed_input_is_stable() {
+    let once = normalize(" a ");
+    assert_eq!(once, "a");
+}
+"#,
+    )
+    .unwrap();
+    let stats = Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+    assert_eq!(stats.test_links, 2);
+
+    assert_eq!(
+        links(&db.tests_for_symbol("parse_header", None).unwrap()),
+        vec![("test_parse_header_trims".into(), "parse_header".into(), "call+name".into())]
+    );
+    assert_eq!(
+        links(&db.symbols_for_test("trimmed_input_is_stable", None).unwrap()),
+        vec![("trimmed_input_is_stable".into(), "normalize".into(), "call".into())]
+    );
+    // Helpers next to the tests are not production symbols.
+    assert!(db.tests_for_symbol("fixture", None).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 3. Go and Python test files link to neighbouring sources by name
+// ---------------------------------------------------------------------------
+#[test]
+fn test_go_and_python_test_links() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("server")).unwrap();
+    fs::create_dir_all(dir.path().join("app")).unwrap();
+    fs::create_dir_all(dir.path().join("tests")).unwrap();
+    fs::write(
+        dir.path().join("server/router.go"),
+        "package server\n\ntype Router struct{}\n\nfunc NewRouter() *Router {\n\treturn &Router{}\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("server/router_test.go"),
+        "package server\n\nimport \"testing\"\n\nfunc TestRouter(t *testing.T) {\n\t_ = 1\n}\n\nfunc helper() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("app/billing.py"),
+        "def compute_invoice(items):\n    return sum(items)\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("tests/test_billing.py"),
+        "class TestComputeInvoice:\n    def test_empty(self):\n        assert True\n\n\ndef test_compute_invoice_sums():\n    assert True\n",
+    )
+    .unwrap();
+    Indexer::new(&db, &registry).index_directory(dir.path()).unwrap();
+
+    assert_eq!(
+        links(&db.tests_for_symbol("Router", None).unwrap()),
+        vec![("TestRouter".into(), "Router".into(), "name".into())]
+    );
+    assert_eq!(
+        links(&db.tests_for_symbol("compute_invoice", None).unwrap()),
+        vec![
+            ("test_compute_invoice_sums".into(), "compute_invoice".into(), "name".into()),
+            ("test_empty".into(), "compute_invoice".into(), "name".into()),
+        ]
+    );
+    assert!(db.symbols_for_test("helper", None).unwrap().is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 4. Single-file re-indexes re-link tests, and `repo` scopes the lookup
+// ---------------------------------------------------------------------------
+#[test]
+fn test_links_follow_file_updates() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let indexer = Indexer::new(&db, &registry);
+    let dirs: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+    for dir in &dirs {
+        fs::write(dir.path().join("router.go"), "package server\n\nfunc NewRouter() {}\n").unwrap();
+        indexer.index_directory(dir.path()).unwrap();
+    }
+    assert!(db.tests_for_symbol("NewRouter", None).unwrap().is_empty());
+
+    let test_file = dirs[0].path().join("router_test.go");
+    fs::write(&test_file, "package server\n\nimport \"testing\"\n\nfunc TestNewRouter(t *testing.T) {\n\tNewRouter()\n}\n")
+        .unwrap();
+    assert!(indexer.index_file(&test_file, dirs[0].path()).unwrap());
+    assert_eq!(
+        links(&db.tests_for_symbol("NewRouter", None).unwrap()),
+        vec![("TestNewRouter".into(), "NewRouter".into(), "call+name".into())]
+    );
+
+    let repo = |dir: &TempDir| {
+        let root = dir.path().canonicalize().unwrap();
+        db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap().id
+    };
+    assert_eq!(db.tests_for_symbol("NewRouter", Some(repo(&dirs[0]))).unwrap().len(), 1);
+    assert!(db.tests_for_symbol("NewRouter", Some(repo(&dirs[1]))).unwrap().is_empty());
+}
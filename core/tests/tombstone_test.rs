@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::PathBuf;
+
+use focal_core::db::Database;
+use focal_core::grammar::GrammarRegistry;
+use focal_core::indexer::Indexer;
+use tempfile::TempDir;
+
+const CALLER: &str = "package main\n\nfunc Caller() {\n    Helper()\n}\n";
+const HELPER: &str = "package main\n\nfunc Helper() {\n    println(\"help\")\n}\n";
+
+/// Index `caller.go` (calling `Helper`) and `helper.go`; returns the root
+/// and the repo id.
+fn indexed_repo(db: &Database, registry: &GrammarRegistry, dir: &TempDir) -> (PathBuf, i64) {
+    fs::write(dir.path().join("caller.go"), CALLER).unwrap();
+    fs::write(dir.path().join("helper.go"), HELPER).unwrap();
+    Indexer::new(db, registry).index_directory(dir.path()).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let repo = db.get_repository_by_path(&root.to_string_lossy()).unwrap().unwrap();
+    (root, repo.id)
+}
+
+fn helper_id(db: &Database) -> Option<i64> {
+    db.find_symbol_by_name_any("Helper").unwrap().map(|s| s.id)
+}
+
+// ---------------------------------------------------------------------------
+// 1. A deleted file that comes back unchanged keeps its identity
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_unchanged_file_is_resurrected() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (root, repo_id) = indexed_repo(&db, &registry, &dir);
+    let helper = helper_id(&db).unwrap();
+    let memory = db.save_memory("Helper must stay idempotent", "invariant", &[helper]).unwrap();
+    assert_eq!(db.get_dependents(helper).unwrap().len(), 1);
+
+    let indexer = Indexer::new(&db, &registry);
+    fs::remove_file(root.join("helper.go")).unwrap();
+    assert!(indexer.remove_deleted_file(&root.join("helper.go"), &root).unwrap());
+    assert!(helper_id(&db).is_none(), "tombstoned symbols leave the live index");
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 1);
+
+    fs::write(root.join("helper.go"), HELPER).unwrap();
+    assert!(indexer.index_file(&root.join("helper.go"), &root).unwrap());
+    assert_eq!(helper_id(&db), Some(helper), "same symbol id");
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 0);
+
+    let memories = db.get_memories_for_symbol(helper, false).unwrap();
+    assert_eq!(memories.iter().map(|m| m.id).collect::<Vec<_>>(), vec![memory]);
+    assert!(!memories[0].needs_review);
+    let dependents = db.get_dependents(helper).unwrap();
+    assert_eq!(dependents.len(), 1, "incoming edge from Caller restored");
+    assert_eq!(dependents[0].1.name, "Caller");
+}
+
+// ---------------------------------------------------------------------------
+// 2. A file that comes back changed is re-parsed; memories carry over
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_changed_file_relinks_memories() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (root, repo_id) = indexed_repo(&db, &registry, &dir);
+    let memory = db.save_memory("Helper prints", "decision", &[helper_id(&db).unwrap()]).unwrap();
+
+    let indexer = Indexer::new(&db, &registry);
+    fs::remove_file(root.join("helper.go")).unwrap();
+    indexer.remove_deleted_file(&root.join("helper.go"), &root).unwrap();
+    fs::write(root.join("helper.go"), "package main\n\nfunc Helper() {\n    println(\"changed\")\n}\n").unwrap();
+    assert!(indexer.index_file(&root.join("helper.go"), &root).unwrap());
+
+    let memories = db.get_memories_for_symbol(helper_id(&db).unwrap(), true).unwrap();
+    assert_eq!(memories.iter().map(|m| m.id).collect::<Vec<_>>(), vec![memory]);
+    assert!(memories[0].needs_review, "body changed while deleted");
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 0);
+}
+
+// ---------------------------------------------------------------------------
+// 3. Tombstones past the grace period are purged and their memories go stale
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_purge_marks_memories_stale() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (root, repo_id) = indexed_repo(&db, &registry, &dir);
+    let symbol_memory = db.save_memory("Helper prints", "decision", &[helper_id(&db).unwrap()]).unwrap();
+    let path_memory = db.save_memory("helper.go is generated", "decision", &[]).unwrap();
+    db.link_memory_files(path_memory, &["helper.go".to_string()], None).unwrap();
+
+    fs::remove_file(root.join("helper.go")).unwrap();
+    Indexer::new(&db, &registry).remove_deleted_file(&root.join("helper.go"), &root).unwrap();
+    assert_eq!(db.purge_tombstones(24).unwrap(), 0, "still within the grace period");
+    assert!(!db.get_memory_by_id(path_memory).unwrap().unwrap().stale);
+
+    assert_eq!(db.purge_tombstones(0).unwrap(), 1);
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 0);
+    assert!(db.get_memory_by_id(symbol_memory).unwrap().unwrap().stale);
+    assert!(db.get_memory_by_id(path_memory).unwrap().unwrap().stale);
+}
+
+// ---------------------------------------------------------------------------
+// 4. Incoming edges survive the referring file being re-indexed meanwhile
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_incoming_edges_follow_reindexed_source() {
+    let db = Database::open_in_memory().unwrap();
+    let registry = GrammarRegistry::new();
+    let dir = TempDir::new().unwrap();
+    let (root, repo_id) = indexed_repo(&db, &registry, &dir);
+    let old_caller = db.find_symbol_by_name_any("Caller").unwrap().unwrap().id;
+
+    let indexer = Indexer::new(&db, &registry);
+    fs::remove_file(root.join("helper.go")).unwrap();
+    indexer.remove_deleted_file(&root.join("helper.go"), &root).unwrap();
+    fs::write(root.join("caller.go"), format!("{CALLER}\nfunc Other() {{}}\n")).unwrap();
+    assert!(indexer.index_file(&root.join("caller.go"), &root).unwrap());
+    let caller = db.find_symbol_by_name_any("Caller").unwrap().unwrap().id;
+    assert_ne!(caller, old_caller, "re-indexing renumbers the referring symbols");
+
+    fs::write(root.join("helper.go"), HELPER).unwrap();
+    assert!(indexer.index_file(&root.join("helper.go"), &root).unwrap());
+    assert_eq!(db.count_tombstones(repo_id).unwrap(), 0);
+    let dependents = db.get_dependents(helper_id(&db).unwrap()).unwrap();
+    let sources: Vec<i64> = dependents.iter().map(|(_, s)| s.id).collect();
+    assert_eq!(sources, [caller], "the edge is re-resolved by file and name");
+}
@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use pyo3::exceptions::{PyFileNotFoundError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use serde::Serialize;
+
+use focal_core::db::SearchFilter;
+use focal_core::format::{render_skeleton, OutputFormat};
+use focal_core::Focal;
+
+// ---------------------------------------------------------------------------
+// Python bindings
+// ---------------------------------------------------------------------------
+//
+// `import focal` for notebooks and scripts that want the index without an
+// MCP client:
+//
+//     index = focal.Index()                   # the index `focal serve` uses
+//     index.search_code("refresh token", kind="function")   # filters are keyword-only
+//     index.query_symbols_full("RefreshToken")
+//     print(index.get_skeleton("auth/session.go"))
+//     index.save_memory("Tokens refresh lazily", "decision", ["RefreshToken"])
+//
+// Results are the MCP tools' JSON shapes as plain dicts and lists. Database
+// work runs with the GIL released, so other Python threads keep going.
+// Opening an index applies the config's `deny_paths`, as `focal serve`
+// does at startup.
+
+fn runtime_error(e: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{e:#}"))
+}
+
+/// `value` as the Python object `json.loads` makes of its JSON.
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<Py<PyAny>> {
+    let json = serde_json::to_string(value).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// A focal index. Without `db_path`, the one `focal serve` uses
+/// (`$FOCAL_DB_PATH`, else `~/.focal/index.db`). The file must exist: the
+/// bindings read an index built by `focal index` or `focal serve`.
+#[pyclass(module = "focal", frozen)]
+struct Index {
+    focal: Focal,
+}
+
+#[pymethods]
+impl Index {
+    #[new]
+    #[pyo3(signature = (db_path=None))]
+    fn new(db_path: Option<PathBuf>) -> PyResult<Self> {
+        let path = db_path.unwrap_or_else(focal_core::config::db_path);
+        if !path.is_file() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "no focal index at {}; build one with `focal index`",
+                path.display()
+            )));
+        }
+        Ok(Self { focal: Focal::builder().db_path(path).build().map_err(runtime_error)? })
+    }
+
+    /// Full-text search over symbol names, signatures and docs. Generated
+    /// files are left out unless `include_generated`.
+    #[pyo3(signature = (
+        query, *, kind="", repo=None, visibility="", attribute="", package="", max_results=20, include_generated=false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn search_code(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        kind: &str,
+        repo: Option<&str>,
+        visibility: &str,
+        attribute: &str,
+        package: &str,
+        max_results: i64,
+        include_generated: bool,
+    ) -> PyResult<Py<PyAny>> {
+        let results = py
+            .detach(|| -> anyhow::Result<_> {
+                let repo_id = match repo {
+                    Some(name) => {
+                        Some(self.focal.repo_id(name)?.ok_or_else(|| anyhow!("repository '{name}' not found"))?)
+                    }
+                    None => None,
+                };
+                let filter = SearchFilter {
+                    kind,
+                    visibility,
+                    attribute,
+                    package,
+                    include_generated,
+                    ..SearchFilter::repo(repo_id.as_ref())
+                };
+                self.focal.search_filtered(query, &filter, max_results)
+            })
+            .map_err(runtime_error)?;
+        to_python(py, &results)
+    }
+
+    /// Symbols named `name` with their file, repository and memories. Empty
+    /// filters match anything.
+    #[pyo3(signature = (name, kind="", repo="", visibility="", attribute=""))]
+    fn query_symbols_full(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        kind: &str,
+        repo: &str,
+        visibility: &str,
+        attribute: &str,
+    ) -> PyResult<Py<PyAny>> {
+        let results = py
+            .detach(|| self.focal.query_symbols(name, kind, repo, visibility, attribute))
+            .map_err(runtime_error)?;
+        to_python(py, &results)
+    }
+
+    /// Signatures of a file's symbols without their bodies, as text, `json`
+    /// or `compact-json`.
+    #[pyo3(signature = (file_path, repo=None, format="text"))]
+    fn get_skeleton(&self, py: Python<'_>, file_path: &str, repo: Option<&str>, format: &str) -> PyResult<String> {
+        let format = OutputFormat::parse(Some(format)).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        py.detach(|| -> anyhow::Result<String> {
+            let (file, symbols) = self
+                .focal
+                .skeleton(file_path, repo)?
+                .ok_or_else(|| anyhow!("file '{file_path}' not found in the index"))?;
+            render_skeleton(&file.repo, &file.file_path, &symbols, format)
+        })
+        .map_err(runtime_error)
+    }
+
+    /// Store a memory linked to the named symbols (names that aren't indexed
+    /// are skipped). Returns the memory id.
+    #[pyo3(signature = (content, category, symbol_names=Vec::new()))]
+    fn save_memory(&self, py: Python<'_>, content: &str, category: &str, symbol_names: Vec<String>) -> PyResult<i64> {
+        let names: Vec<&str> = symbol_names.iter().map(String::as_str).collect();
+        py.detach(|| self.focal.save_memory(content, category, &names)).map_err(runtime_error)
+    }
+}
+
+#[pymodule]
+fn focal(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Index>()?;
+    Ok(())
+}
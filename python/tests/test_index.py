@@ -0,0 +1,75 @@
+"""Tests for the `focal` module against an index built by the `focal` binary.
+
+Set FOCAL_BIN to the binary (default: `focal` on PATH); the tests skip
+without one. HOME points at a scratch directory so the user's config and
+index are never touched.
+"""
+
+import os
+import shutil
+import subprocess
+import tempfile
+import unittest
+from unittest import mock
+
+import focal
+
+FOCAL_BIN = os.environ.get("FOCAL_BIN") or shutil.which("focal")
+
+
+@unittest.skipUnless(FOCAL_BIN, "focal binary not found; set FOCAL_BIN")
+class IndexTest(unittest.TestCase):
+    def setUp(self):
+        self.scratch = tempfile.TemporaryDirectory()
+        self.addCleanup(self.scratch.cleanup)
+        home = os.path.join(self.scratch.name, "home")
+        os.makedirs(os.path.join(home, ".focal"))
+        self.config = os.path.join(home, ".focal", "config.toml")
+        self.db_path = os.path.join(self.scratch.name, "index.db")
+        env = mock.patch.dict(os.environ, {"HOME": home, "FOCAL_DB_PATH": self.db_path})
+        env.start()
+        self.addCleanup(env.stop)
+
+        self.repo = os.path.join(self.scratch.name, "repo")
+        os.makedirs(os.path.join(self.repo, "secrets"))
+        self.write("store.go", "package main\n\n// Save persists a session.\nfunc Save() {}\n")
+        self.write("api.go", "package main\n\nfunc Create() {\n    Save()\n}\n")
+        self.write("secrets/vault.go", "package secrets\n\nfunc VaultToken() string { return \"\" }\n")
+        subprocess.run([FOCAL_BIN, "index", self.repo], check=True, capture_output=True)
+
+    def write(self, rel_path, content):
+        with open(os.path.join(self.repo, rel_path), "w") as f:
+            f.write(content)
+
+    def test_missing_index_is_an_error(self):
+        missing = os.path.join(self.scratch.name, "missing.db")
+        with self.assertRaises(FileNotFoundError):
+            focal.Index(missing)
+        self.assertFalse(os.path.exists(missing))
+
+    def test_search_code_filters_are_keyword_only(self):
+        index = focal.Index()
+        names = [r["name"] for r in index.search_code("Save", kind="function")]
+        self.assertIn("Save", names)
+        with self.assertRaises(TypeError):
+            index.search_code("Save", "function")
+
+    def test_memory_and_skeleton(self):
+        index = focal.Index(self.db_path)
+        memory_id = index.save_memory("Sessions persist on save", "decision", ["Save"])
+        self.assertGreater(memory_id, 0)
+        (symbol,) = index.query_symbols_full("Save")
+        self.assertIn("Sessions persist on save", [m["content"] for m in symbol["memories"]])
+        self.assertIn("func Save()", index.get_skeleton("store.go"))
+        with self.assertRaises(ValueError):
+            index.get_skeleton("store.go", format="yaml")
+
+    def test_opening_applies_deny_paths(self):
+        self.assertTrue(focal.Index().query_symbols_full("VaultToken"))
+        with open(self.config, "w") as f:
+            f.write('[index]\ndeny_paths = ["secrets/"]\n')
+        self.assertEqual(focal.Index().query_symbols_full("VaultToken"), [])
+
+
+if __name__ == "__main__":
+    unittest.main()